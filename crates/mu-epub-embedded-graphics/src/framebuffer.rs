@@ -0,0 +1,477 @@
+//! In-memory 8-bit framebuffer backend with PNG dumps for desktop development.
+
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use embedded_graphics::{pixelcolor::Gray8, prelude::*};
+use mu_epub_render::RenderPage;
+
+use crate::{EgRenderConfig, EgRenderer, FontBackend, MonoFontBackend};
+
+const PNG_SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+/// Largest payload of a single stored deflate block.
+const STORED_BLOCK_MAX: usize = 0xffff;
+
+/// 8-bit grayscale pixel buffer (0 = black, 255 = white).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Framebuffer {
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+}
+
+impl Framebuffer {
+    /// Create a white framebuffer.
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            pixels: vec![u8::MAX; width as usize * height as usize],
+        }
+    }
+
+    /// Width in pixels.
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// Height in pixels.
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Row-major luma bytes.
+    pub fn pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    /// Luma at `(x, y)`, when inside the buffer.
+    pub fn pixel(&self, x: u32, y: u32) -> Option<u8> {
+        (x < self.width && y < self.height)
+            .then(|| self.pixels[y as usize * self.width as usize + x as usize])
+    }
+
+    /// Number of pixels whose luma differs by more than `threshold`.
+    ///
+    /// Buffers of different sizes differ everywhere.
+    pub fn count_differences(&self, other: &Framebuffer, threshold: u8) -> usize {
+        if self.width != other.width || self.height != other.height {
+            return self.pixels.len().max(other.pixels.len());
+        }
+        self.pixels
+            .iter()
+            .zip(&other.pixels)
+            .filter(|(a, b)| a.abs_diff(**b) > threshold)
+            .count()
+    }
+
+    /// Encode as an 8-bit grayscale PNG.
+    ///
+    /// Image data is stored uncompressed; dumps favor simplicity over size.
+    pub fn encode_png(&self) -> Vec<u8> {
+        let row = self.width as usize;
+        let mut raw = Vec::with_capacity((row + 1) * self.height as usize);
+        for line in self.pixels.chunks(row.max(1)) {
+            raw.push(0);
+            raw.extend_from_slice(line);
+        }
+
+        let mut ihdr = Vec::with_capacity(13);
+        ihdr.extend_from_slice(&self.width.to_be_bytes());
+        ihdr.extend_from_slice(&self.height.to_be_bytes());
+        ihdr.extend_from_slice(&[8, 0, 0, 0, 0]);
+
+        let mut out = Vec::with_capacity(raw.len() + raw.len() / STORED_BLOCK_MAX * 5 + 64);
+        out.extend_from_slice(&PNG_SIGNATURE);
+        write_chunk(&mut out, b"IHDR", &ihdr);
+        write_chunk(&mut out, b"IDAT", &zlib_stored(&raw));
+        write_chunk(&mut out, b"IEND", &[]);
+        out
+    }
+
+    /// Decode a PNG produced by [`Framebuffer::encode_png`].
+    ///
+    /// Only 8-bit grayscale images with stored deflate blocks are accepted.
+    pub fn decode_png(bytes: &[u8]) -> io::Result<Self> {
+        let mut rest = bytes
+            .strip_prefix(&PNG_SIGNATURE)
+            .ok_or_else(|| invalid("missing PNG signature"))?;
+        let mut header = None;
+        let mut idat = Vec::with_capacity(bytes.len());
+        while !rest.is_empty() {
+            let (kind, data, tail) = read_chunk(rest)?;
+            rest = tail;
+            match &kind {
+                b"IHDR" => header = Some(data),
+                b"IDAT" => idat.extend_from_slice(data),
+                b"IEND" => break,
+                _ => {}
+            }
+        }
+        let header = header.ok_or_else(|| invalid("missing IHDR"))?;
+        if header.len() != 13 || header[8..] != [8, 0, 0, 0, 0] {
+            return Err(invalid("unsupported PNG format"));
+        }
+        let width = u32::from_be_bytes([header[0], header[1], header[2], header[3]]);
+        let height = u32::from_be_bytes([header[4], header[5], header[6], header[7]]);
+
+        let raw = zlib_unstored(&idat)?;
+        let row = width as usize;
+        if raw.len() != (row + 1) * height as usize {
+            return Err(invalid("image data size mismatch"));
+        }
+        let mut pixels = Vec::with_capacity(row * height as usize);
+        for line in raw.chunks(row + 1) {
+            if line[0] != 0 {
+                return Err(invalid("unsupported PNG filter"));
+            }
+            pixels.extend_from_slice(&line[1..]);
+        }
+        Ok(Self {
+            width,
+            height,
+            pixels,
+        })
+    }
+
+    /// Write the buffer as a PNG file.
+    pub fn write_png(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        fs::write(path, self.encode_png())
+    }
+
+    /// Read a PNG file written by [`Framebuffer::write_png`].
+    pub fn read_png(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::decode_png(&fs::read(path)?)
+    }
+}
+
+impl OriginDimensions for Framebuffer {
+    fn size(&self) -> Size {
+        Size::new(self.width, self.height)
+    }
+}
+
+impl DrawTarget for Framebuffer {
+    type Color = Gray8;
+    type Error = core::convert::Infallible;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        for Pixel(point, color) in pixels {
+            if let (Ok(x), Ok(y)) = (u32::try_from(point.x), u32::try_from(point.y)) {
+                if x < self.width && y < self.height {
+                    self.pixels[y as usize * self.width as usize + x as usize] = color.luma();
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn clear(&mut self, color: Self::Color) -> Result<(), Self::Error> {
+        self.pixels.fill(color.luma());
+        Ok(())
+    }
+}
+
+/// Outcome of comparing a frame against a golden image.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GoldenOutcome {
+    /// Frame matched within tolerance.
+    Matched,
+    /// Updates were requested; the frame was written as the golden.
+    Written,
+    /// Frame differs from the golden in `differing_pixels` pixels.
+    Mismatch { differing_pixels: usize },
+}
+
+/// Set to regenerate golden images instead of comparing against them.
+pub const UPDATE_GOLDEN_ENV: &str = "MU_EPUB_UPDATE_GOLDEN";
+
+/// Compare `frame` against the golden PNG at `path` for visual regression tests.
+///
+/// Pixels whose luma differs by more than `threshold` count as different.
+/// With [`UPDATE_GOLDEN_ENV`] set in the environment, `frame` is written as
+/// the new golden instead; otherwise a missing golden is a
+/// [`io::ErrorKind::NotFound`] error. On mismatch the frame is saved next to
+/// the golden with an `.actual.png` suffix for inspection.
+pub fn compare_golden(
+    frame: &Framebuffer,
+    path: impl AsRef<Path>,
+    threshold: u8,
+) -> io::Result<GoldenOutcome> {
+    let update = std::env::var_os(UPDATE_GOLDEN_ENV).is_some();
+    compare_or_update_golden(frame, path.as_ref(), threshold, update)
+}
+
+fn compare_or_update_golden(
+    frame: &Framebuffer,
+    path: &Path,
+    threshold: u8,
+    update: bool,
+) -> io::Result<GoldenOutcome> {
+    if !update && !path.exists() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!(
+                "missing golden {}; set {} to create it",
+                path.display(),
+                UPDATE_GOLDEN_ENV
+            ),
+        ));
+    }
+    if update {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        frame.write_png(path)?;
+        return Ok(GoldenOutcome::Written);
+    }
+    let golden = Framebuffer::read_png(path)?;
+    let differing_pixels = frame.count_differences(&golden, threshold);
+    if differing_pixels == 0 {
+        return Ok(GoldenOutcome::Matched);
+    }
+    frame.write_png(path.with_extension("actual.png"))?;
+    Ok(GoldenOutcome::Mismatch { differing_pixels })
+}
+
+/// Renders pages into fresh [`Framebuffer`]s.
+#[derive(Clone, Copy, Debug)]
+pub struct FramebufferRenderer<B = MonoFontBackend> {
+    renderer: EgRenderer<B>,
+    width: u32,
+    height: u32,
+}
+
+impl FramebufferRenderer<MonoFontBackend> {
+    /// Create a renderer for `width`x`height` frames with the mono backend.
+    pub fn new(width: u32, height: u32) -> Self {
+        Self::with_renderer(EgRenderer::new(EgRenderConfig::default()), width, height)
+    }
+}
+
+impl<B> FramebufferRenderer<B>
+where
+    B: FontBackend,
+{
+    /// Wrap an existing renderer.
+    pub fn with_renderer(renderer: EgRenderer<B>, width: u32, height: u32) -> Self {
+        Self {
+            renderer,
+            width,
+            height,
+        }
+    }
+
+    /// Render `page` into a new frame.
+    pub fn render(&self, page: &RenderPage) -> Framebuffer {
+        let mut frame = Framebuffer::new(self.width, self.height);
+        match self.renderer.render_page(page, &mut frame) {
+            Ok(()) => frame,
+            Err(never) => match never {},
+        }
+    }
+
+    /// Render `page` and write it as a PNG file.
+    pub fn dump_png(&self, page: &RenderPage, path: impl AsRef<Path>) -> io::Result<()> {
+        self.render(page).write_png(path)
+    }
+}
+
+fn invalid(message: &'static str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+fn write_chunk(out: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    out.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    out.extend_from_slice(kind);
+    out.extend_from_slice(data);
+    let crc = crc32(crc32(0, kind), data);
+    out.extend_from_slice(&crc.to_be_bytes());
+}
+
+fn read_chunk(bytes: &[u8]) -> io::Result<([u8; 4], &[u8], &[u8])> {
+    if bytes.len() < 12 {
+        return Err(invalid("truncated PNG chunk"));
+    }
+    let len = u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as usize;
+    let kind = [bytes[4], bytes[5], bytes[6], bytes[7]];
+    let end = len
+        .checked_add(12)
+        .filter(|end| *end <= bytes.len())
+        .ok_or_else(|| invalid("truncated PNG chunk"))?;
+    let data = &bytes[8..8 + len];
+    let crc = u32::from_be_bytes([
+        bytes[end - 4],
+        bytes[end - 3],
+        bytes[end - 2],
+        bytes[end - 1],
+    ]);
+    if crc != crc32(crc32(0, &kind), data) {
+        return Err(invalid("PNG chunk CRC mismatch"));
+    }
+    Ok((kind, data, &bytes[end..]))
+}
+
+/// Wrap `data` in a zlib stream of uncompressed deflate blocks.
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(data.len() + data.len() / STORED_BLOCK_MAX * 5 + 11);
+    out.extend_from_slice(&[0x78, 0x01]);
+    let mut blocks = data.chunks(STORED_BLOCK_MAX).peekable();
+    if blocks.peek().is_none() {
+        out.extend_from_slice(&[1, 0, 0, 0xff, 0xff]);
+    }
+    while let Some(block) = blocks.next() {
+        let last = blocks.peek().is_none();
+        let len = block.len() as u16;
+        out.push(last as u8);
+        out.extend_from_slice(&len.to_le_bytes());
+        out.extend_from_slice(&(!len).to_le_bytes());
+        out.extend_from_slice(block);
+    }
+    out.extend_from_slice(&adler32(data).to_be_bytes());
+    out
+}
+
+/// Inverse of [`zlib_stored`]; compressed blocks are rejected.
+fn zlib_unstored(stream: &[u8]) -> io::Result<Vec<u8>> {
+    if stream.len() < 6 || stream[0] & 0x0f != 8 {
+        return Err(invalid("unsupported zlib stream"));
+    }
+    let mut rest = &stream[2..];
+    let mut out = Vec::with_capacity(stream.len());
+    loop {
+        if rest.len() < 5 {
+            return Err(invalid("truncated deflate block"));
+        }
+        let header = rest[0];
+        if header & 0x06 != 0 {
+            return Err(invalid("compressed deflate blocks are not supported"));
+        }
+        let len = u16::from_le_bytes([rest[1], rest[2]]);
+        let nlen = u16::from_le_bytes([rest[3], rest[4]]);
+        if len != !nlen || rest.len() < 5 + len as usize {
+            return Err(invalid("corrupt deflate block"));
+        }
+        out.extend_from_slice(&rest[5..5 + len as usize]);
+        rest = &rest[5 + len as usize..];
+        if header & 1 == 1 {
+            break;
+        }
+    }
+    if rest.len() < 4 || u32::from_be_bytes([rest[0], rest[1], rest[2], rest[3]]) != adler32(&out) {
+        return Err(invalid("zlib checksum mismatch"));
+    }
+    Ok(out)
+}
+
+fn crc32(crc: u32, data: &[u8]) -> u32 {
+    let mut crc = !crc;
+    for byte in data {
+        crc ^= u32::from(*byte);
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xedb8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for chunk in data.chunks(5552) {
+        for byte in chunk {
+            a += u32::from(*byte);
+            b += a;
+        }
+        a %= 65521;
+        b %= 65521;
+    }
+    (b << 16) | a
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mu_epub_render::{DrawCommand, RectCommand};
+
+    fn square_page() -> RenderPage {
+        let mut page = RenderPage::new(1);
+        page.push_content_command(DrawCommand::Rect(RectCommand {
+            x: 2,
+            y: 3,
+            width: 4,
+            height: 4,
+            fill: true,
+        }));
+        page.sync_commands();
+        page
+    }
+
+    fn temp_path(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("mu-epub-fb-{}-{}", std::process::id(), name))
+    }
+
+    #[test]
+    fn renders_page_into_gray_pixels() {
+        let frame = FramebufferRenderer::new(10, 10).render(&square_page());
+        assert_eq!(frame.pixel(0, 0), Some(255));
+        assert_eq!(frame.pixel(2, 3), Some(0));
+        assert_eq!(frame.pixel(5, 6), Some(0));
+        assert_eq!(frame.pixel(6, 7), Some(255));
+        assert_eq!(frame.pixel(10, 0), None);
+    }
+
+    #[test]
+    fn png_round_trips() {
+        let frame = FramebufferRenderer::new(300, 260).render(&square_page());
+        let png = frame.encode_png();
+        assert!(png.starts_with(&PNG_SIGNATURE));
+        assert_eq!(Framebuffer::decode_png(&png).unwrap(), frame);
+
+        let mut corrupt = png.clone();
+        let last = corrupt.len() - 20;
+        corrupt[last] ^= 0xff;
+        assert!(Framebuffer::decode_png(&corrupt).is_err());
+        assert_eq!(adler32(b"Wikipedia"), 0x11e6_0398);
+        assert_eq!(crc32(0, b"123456789"), 0xcbf4_3926);
+    }
+
+    #[test]
+    fn golden_is_written_then_compared() {
+        let path = temp_path("golden.png");
+        let _ = fs::remove_file(&path);
+        let renderer = FramebufferRenderer::new(10, 10);
+        let frame = renderer.render(&square_page());
+
+        let missing = compare_or_update_golden(&frame, &path, 0, false).unwrap_err();
+        assert_eq!(missing.kind(), io::ErrorKind::NotFound);
+        assert!(!path.exists());
+        assert_eq!(
+            compare_or_update_golden(&frame, &path, 0, true).unwrap(),
+            GoldenOutcome::Written
+        );
+        assert_eq!(
+            compare_golden(&frame, &path, 0).unwrap(),
+            GoldenOutcome::Matched
+        );
+
+        let changed = renderer.render(&RenderPage::new(1));
+        assert_eq!(
+            compare_golden(&changed, &path, 0).unwrap(),
+            GoldenOutcome::Mismatch {
+                differing_pixels: 16
+            }
+        );
+        let actual = path.with_extension("actual.png");
+        assert_eq!(Framebuffer::read_png(&actual).unwrap(), changed);
+        let _ = fs::remove_file(&path);
+        let _ = fs::remove_file(&actual);
+    }
+}
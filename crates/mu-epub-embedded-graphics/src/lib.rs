@@ -18,15 +18,25 @@ use embedded_graphics::{
         ascii::{FONT_6X13_ITALIC, FONT_7X13_BOLD, FONT_8X13, FONT_9X15_BOLD},
         MonoTextStyle,
     },
-    pixelcolor::BinaryColor,
+    pixelcolor::{BinaryColor, Gray2, Gray4, Gray8, GrayColor},
     prelude::*,
     primitives::{Line, PrimitiveStyle, Rectangle},
     text::Text,
 };
+mod framebuffer;
+mod terminal;
+
+pub use framebuffer::{
+    compare_golden, Framebuffer, FramebufferRenderer, GoldenOutcome, UPDATE_GOLDEN_ENV,
+};
+pub use terminal::{TerminalConfig, TerminalRenderer};
+
 use mu_epub_render::{
-    DrawCommand, JustifyMode, PageChromeCommand, PageChromeConfig, PageChromeKind,
-    PageChromeTextStyle, RenderPage, ResolvedTextStyle, TextCommand,
+    ChromeData, DrawCommand, FontSynthesis, OverlayRect, PageChromeCommand, PageChromeConfig,
+    PageChromeKind, PageChromeTextStyle, RenderPage, ResolvedTextStyle, TextCommand,
 };
+#[cfg(feature = "ttf-backend")]
+use mu_epub_render::{DitherMode, RenderIntent};
 
 /// Backend-local font identifier used for metrics and rasterization dispatch.
 pub type FontId = u8;
@@ -85,7 +95,8 @@ pub trait FontBackend {
         origin: Point,
     ) -> Result<i32, D::Error>
     where
-        D: DrawTarget<Color = BinaryColor>;
+        D: DrawTarget,
+        D::Color: RenderColor;
 
     fn capabilities(&self) -> BackendCapabilities {
         BackendCapabilities {
@@ -97,6 +108,49 @@ pub trait FontBackend {
     }
 }
 
+/// Target pixel colors the renderer can draw with.
+///
+/// `BinaryColor` keeps the historical 1-bit output; grayscale targets map
+/// glyph coverage onto their available levels for anti-aliased text.
+pub trait RenderColor: PixelColor {
+    /// Background color used when clearing.
+    const PAPER: Self;
+    /// Foreground color for text and rules.
+    const INK: Self;
+
+    /// Color for a pixel covered `coverage`/255 by ink.
+    fn from_coverage(coverage: u8) -> Self;
+}
+
+impl RenderColor for BinaryColor {
+    const PAPER: Self = BinaryColor::Off;
+    const INK: Self = BinaryColor::On;
+
+    fn from_coverage(coverage: u8) -> Self {
+        if coverage >= 128 {
+            Self::INK
+        } else {
+            Self::PAPER
+        }
+    }
+}
+
+macro_rules! gray_render_color {
+    ($($ty:ty => $max:expr),* $(,)?) => {$(
+        impl RenderColor for $ty {
+            const PAPER: Self = <$ty as GrayColor>::WHITE;
+            const INK: Self = <$ty as GrayColor>::BLACK;
+
+            fn from_coverage(coverage: u8) -> Self {
+                let ink = (u16::from(coverage) * $max + 127) / 255;
+                <$ty>::new(($max - ink) as u8)
+            }
+        }
+    )*};
+}
+
+gray_render_color!(Gray2 => 3, Gray4 => 15, Gray8 => 255);
+
 /// Mono-font backend used by default and matching previous behavior.
 #[derive(Clone, Copy, Debug, Default)]
 pub struct MonoFontBackend;
@@ -107,12 +161,12 @@ impl MonoFontBackend {
     const BOLD: FontId = 2;
     const BOLD_ITALIC: FontId = 3;
 
-    fn style_for(font_id: FontId) -> MonoTextStyle<'static, BinaryColor> {
+    fn style_for<C: RenderColor>(font_id: FontId) -> MonoTextStyle<'static, C> {
         match font_id {
-            Self::BOLD_ITALIC => MonoTextStyle::new(&FONT_7X13_BOLD, BinaryColor::On),
-            Self::BOLD => MonoTextStyle::new(&FONT_9X15_BOLD, BinaryColor::On),
-            Self::ITALIC => MonoTextStyle::new(&FONT_6X13_ITALIC, BinaryColor::On),
-            _ => MonoTextStyle::new(&FONT_8X13, BinaryColor::On),
+            Self::BOLD_ITALIC => MonoTextStyle::new(&FONT_7X13_BOLD, C::INK),
+            Self::BOLD => MonoTextStyle::new(&FONT_9X15_BOLD, C::INK),
+            Self::ITALIC => MonoTextStyle::new(&FONT_6X13_ITALIC, C::INK),
+            _ => MonoTextStyle::new(&FONT_8X13, C::INK),
         }
     }
 
@@ -166,7 +220,7 @@ impl FontBackend for MonoFontBackend {
     }
 
     fn metrics(&self, font_id: FontId) -> FontMetrics {
-        let style = Self::style_for(font_id);
+        let style = Self::style_for::<BinaryColor>(font_id);
         let width = style.font.character_size.width as i32;
         FontMetrics {
             char_width: width,
@@ -182,9 +236,10 @@ impl FontBackend for MonoFontBackend {
         origin: Point,
     ) -> Result<i32, D::Error>
     where
-        D: DrawTarget<Color = BinaryColor>,
+        D: DrawTarget,
+        D::Color: RenderColor,
     {
-        let style = Self::style_for(font_id);
+        let style = Self::style_for::<D::Color>(font_id);
         Text::new(text, origin, style).draw(display)?;
         Ok((text.chars().count() as i32) * (style.font.character_size.width as i32))
     }
@@ -210,8 +265,10 @@ pub enum TtfFallbackPolicy {
 
 /// Options for the experimental `ttf-backend` path.
 ///
-/// Note: the current backend remains fallback-oriented and routes drawing
-/// through mono rendering until full TTF rasterization support is implemented.
+/// Note: the current backend remains fallback-oriented: until full TTF
+/// rasterization support is implemented, it rasterizes mono fallback glyphs
+/// into a coverage bitmap and blits that through
+/// [`TtfFontBackend::draw_coverage`].
 #[cfg(feature = "ttf-backend")]
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct TtfBackendOptions {
@@ -223,6 +280,26 @@ pub struct TtfBackendOptions {
     pub max_total_face_bytes: usize,
     /// Policy for unresolved/unsupported faces.
     pub fallback_policy: TtfFallbackPolicy,
+    /// Map partial glyph coverage to gray levels; thresholds when false.
+    pub antialias: bool,
+    /// Rasterized glyphs the backend keeps cached.
+    pub glyph_cache_entries: usize,
+    /// Dithering of partial glyph coverage; replaces gray levels and
+    /// thresholding when not [`DitherMode::None`].
+    pub dither: DitherMode,
+}
+
+#[cfg(feature = "ttf-backend")]
+impl TtfBackendOptions {
+    /// Defaults with the glyph cache size and dithering chosen by a layout's
+    /// [`RenderIntent`].
+    pub fn for_intent(intent: &RenderIntent) -> Self {
+        Self {
+            glyph_cache_entries: intent.glyph_cache_entries(),
+            dither: intent.dither_mode(),
+            ..Self::default()
+        }
+    }
 }
 
 #[cfg(feature = "ttf-backend")]
@@ -233,6 +310,9 @@ impl Default for TtfBackendOptions {
             max_face_bytes: 8 * 1024 * 1024,
             max_total_face_bytes: 64 * 1024 * 1024,
             fallback_policy: TtfFallbackPolicy::MonoOnly,
+            antialias: true,
+            glyph_cache_entries: 256,
+            dither: DitherMode::None,
         }
     }
 }
@@ -275,6 +355,143 @@ impl TtfFontBackend {
     pub fn status(&self) -> &'static str {
         "fallback_only"
     }
+
+    /// Target color for a glyph pixel with `coverage`/255 ink.
+    pub fn coverage_color<C: RenderColor>(&self, coverage: u8) -> C {
+        if self.options.antialias {
+            C::from_coverage(coverage)
+        } else if coverage >= 128 {
+            C::INK
+        } else {
+            C::PAPER
+        }
+    }
+
+    /// Blit a row-major 8-bit coverage bitmap `width` pixels wide.
+    ///
+    /// Uncovered pixels are skipped so glyph boxes never erase neighbors.
+    pub fn draw_coverage<D>(
+        &self,
+        display: &mut D,
+        top_left: Point,
+        width: u32,
+        coverage: &[u8],
+    ) -> Result<(), D::Error>
+    where
+        D: DrawTarget,
+        D::Color: RenderColor,
+    {
+        let width = width.max(1) as usize;
+        let at = |i: usize| top_left + Point::new((i % width) as i32, (i / width) as i32);
+        match self.options.dither {
+            DitherMode::None => display.draw_iter(
+                coverage
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, &c)| c > 0)
+                    .map(|(i, &c)| Pixel(at(i), self.coverage_color(c))),
+            ),
+            DitherMode::Ordered => display.draw_iter(
+                coverage
+                    .iter()
+                    .enumerate()
+                    .filter(|&(i, &c)| ordered_ink(c, at(i)))
+                    .map(|(i, _)| Pixel(at(i), D::Color::INK)),
+            ),
+            DitherMode::ErrorDiffusion => display.draw_iter(
+                diffuse_coverage(coverage, width)
+                    .into_iter()
+                    .enumerate()
+                    .filter(|&(_, ink)| ink)
+                    .map(|(i, _)| Pixel(at(i), D::Color::INK)),
+            ),
+        }
+    }
+}
+
+/// 4x4 Bayer threshold matrix, in sixteenths of full coverage.
+#[cfg(feature = "ttf-backend")]
+const BAYER_4X4: [[u8; 4]; 4] = [[0, 8, 2, 10], [12, 4, 14, 6], [3, 11, 1, 9], [15, 7, 13, 5]];
+
+/// Whether ordered dithering inks a pixel at `at` with `coverage`/255 ink.
+///
+/// The matrix is anchored to page coordinates so adjacent glyphs share one
+/// pattern.
+#[cfg(feature = "ttf-backend")]
+fn ordered_ink(coverage: u8, at: Point) -> bool {
+    let threshold = BAYER_4X4[at.y.rem_euclid(4) as usize][at.x.rem_euclid(4) as usize];
+    u16::from(coverage) > u16::from(threshold) * 16 + 8
+}
+
+/// Floyd-Steinberg dithering of a row-major coverage bitmap to ink/paper.
+#[cfg(feature = "ttf-backend")]
+fn diffuse_coverage(coverage: &[u8], width: usize) -> Vec<bool> {
+    let mut ink = Vec::with_capacity(coverage.len());
+    // Error terms in sixteenths, padded by one column on each side.
+    let mut current = vec![0i32; width + 2];
+    let mut next = vec![0i32; width + 2];
+    for row in coverage.chunks(width) {
+        for (x, &c) in row.iter().enumerate() {
+            let value = i32::from(c) + current[x + 1] / 16;
+            let on = value >= 128;
+            let error = value - if on { 255 } else { 0 };
+            current[x + 2] += error * 7;
+            next[x] += error * 3;
+            next[x + 1] += error * 5;
+            next[x + 2] += error;
+            ink.push(on);
+        }
+        core::mem::swap(&mut current, &mut next);
+        next.fill(0);
+    }
+    ink
+}
+
+/// Row-major 8-bit coverage bitmap that glyphs are rasterized into.
+#[cfg(feature = "ttf-backend")]
+struct CoverageCanvas {
+    area: Rectangle,
+    coverage: Vec<u8>,
+}
+
+#[cfg(feature = "ttf-backend")]
+impl CoverageCanvas {
+    fn new(area: Rectangle) -> Self {
+        let len = area.size.width as usize * area.size.height as usize;
+        Self {
+            area,
+            coverage: vec![0; len],
+        }
+    }
+}
+
+#[cfg(feature = "ttf-backend")]
+impl Dimensions for CoverageCanvas {
+    fn bounding_box(&self) -> Rectangle {
+        self.area
+    }
+}
+
+#[cfg(feature = "ttf-backend")]
+impl DrawTarget for CoverageCanvas {
+    type Color = BinaryColor;
+    type Error = core::convert::Infallible;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        let width = self.area.size.width as usize;
+        for Pixel(point, color) in pixels {
+            if !self.area.contains(point) {
+                continue;
+            }
+            let local = point - self.area.top_left;
+            let index = local.y as usize * width + local.x as usize;
+            self.coverage[index] = if color.is_on() { 255 } else { 0 };
+        }
+        Ok(())
+    }
 }
 
 #[cfg(feature = "ttf-backend")]
@@ -317,10 +534,21 @@ impl FontBackend for TtfFontBackend {
         origin: Point,
     ) -> Result<i32, D::Error>
     where
-        D: DrawTarget<Color = BinaryColor>,
+        D: DrawTarget,
+        D::Color: RenderColor,
     {
-        self.mono_fallback
-            .draw_text_run(display, font_id, text, origin)
+        let style = MonoFontBackend::style_for::<BinaryColor>(font_id);
+        let area = Text::new(text, origin, style).bounding_box();
+        let mut canvas = CoverageCanvas::new(area);
+        let advance = match self
+            .mono_fallback
+            .draw_text_run(&mut canvas, font_id, text, origin)
+        {
+            Ok(advance) => advance,
+            Err(never) => match never {},
+        };
+        self.draw_coverage(display, area.top_left, area.size.width, &canvas.coverage)?;
+        Ok(advance)
     }
 
     fn capabilities(&self) -> BackendCapabilities {
@@ -333,6 +561,54 @@ impl FontBackend for TtfFontBackend {
     }
 }
 
+/// Clockwise panel rotation relative to the logical page.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Rotation {
+    /// Panel axes match the logical page.
+    #[default]
+    Deg0,
+    /// Logical page turned 90° clockwise; its top edge lands on the panel's right.
+    Deg90,
+    /// Logical page upside down.
+    Deg180,
+    /// Logical page turned 270° clockwise; its top edge lands on the panel's left.
+    Deg270,
+}
+
+/// Mapping from logical page coordinates to panel pixels.
+///
+/// Mirroring flips the logical page horizontally before rotation, for
+/// panels scanned right-to-left.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Orientation {
+    /// Clockwise rotation applied after mirroring.
+    pub rotation: Rotation,
+    /// Flip logical x (left ↔ right) before rotating.
+    pub mirrored: bool,
+}
+
+impl Orientation {
+    /// Orientation for a panel mounted at `rotation`.
+    pub const fn rotated(rotation: Rotation) -> Self {
+        Self {
+            rotation,
+            mirrored: false,
+        }
+    }
+
+    /// Logical page size for a panel of `physical` size.
+    pub fn logical_size(self, physical: Size) -> Size {
+        match self.rotation {
+            Rotation::Deg0 | Rotation::Deg180 => physical,
+            Rotation::Deg90 | Rotation::Deg270 => Size::new(physical.height, physical.width),
+        }
+    }
+
+    fn is_identity(self) -> bool {
+        self == Self::default()
+    }
+}
+
 /// embedded-graphics backend configuration.
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct EgRenderConfig {
@@ -340,6 +616,11 @@ pub struct EgRenderConfig {
     pub clear_first: bool,
     /// Page chrome rendering policy and geometry.
     pub page_chrome: PageChromeConfig,
+    /// Panel orientation applied to every drawn pixel.
+    ///
+    /// Commands, clip regions, and chrome geometry stay in logical page
+    /// coordinates; glyphs are rotated with them.
+    pub orientation: Orientation,
 }
 
 impl Default for EgRenderConfig {
@@ -347,7 +628,165 @@ impl Default for EgRenderConfig {
         Self {
             clear_first: true,
             page_chrome: PageChromeConfig::geometry_defaults(),
+            orientation: Orientation::default(),
+        }
+    }
+}
+
+/// Draw target adapter presenting a panel in logical page coordinates.
+struct Oriented<'a, D> {
+    target: &'a mut D,
+    orientation: Orientation,
+    physical: Rectangle,
+}
+
+impl<'a, D> Oriented<'a, D>
+where
+    D: DrawTarget,
+{
+    fn new(target: &'a mut D, orientation: Orientation) -> Self {
+        let physical = target.bounding_box();
+        Self {
+            target,
+            orientation,
+            physical,
+        }
+    }
+}
+
+fn to_physical(orientation: Orientation, physical: Rectangle, point: Point) -> Point {
+    let logical = orientation.logical_size(physical.size);
+    let (w, h) = (logical.width as i32, logical.height as i32);
+    let x = if orientation.mirrored {
+        w - 1 - point.x
+    } else {
+        point.x
+    };
+    let y = point.y;
+    let mapped = match orientation.rotation {
+        Rotation::Deg0 => Point::new(x, y),
+        Rotation::Deg90 => Point::new(h - 1 - y, x),
+        Rotation::Deg180 => Point::new(w - 1 - x, h - 1 - y),
+        Rotation::Deg270 => Point::new(y, w - 1 - x),
+    };
+    mapped + physical.top_left
+}
+
+impl<D> Dimensions for Oriented<'_, D>
+where
+    D: DrawTarget,
+{
+    fn bounding_box(&self) -> Rectangle {
+        if self.orientation.is_identity() {
+            return self.physical;
         }
+        Rectangle::new(
+            Point::zero(),
+            self.orientation.logical_size(self.physical.size),
+        )
+    }
+}
+
+impl<D> DrawTarget for Oriented<'_, D>
+where
+    D: DrawTarget,
+{
+    type Color = D::Color;
+    type Error = D::Error;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        if self.orientation.is_identity() {
+            return self.target.draw_iter(pixels);
+        }
+        let (orientation, physical) = (self.orientation, self.physical);
+        self.target
+            .draw_iter(pixels.into_iter().map(move |Pixel(point, color)| {
+                Pixel(to_physical(orientation, physical, point), color)
+            }))
+    }
+
+    fn fill_contiguous<I>(&mut self, area: &Rectangle, colors: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Self::Color>,
+    {
+        if self.orientation.is_identity() {
+            return self.target.fill_contiguous(area, colors);
+        }
+        // Logical rows are not panel rows once rotated or mirrored.
+        self.draw_iter(
+            area.points()
+                .zip(colors)
+                .map(|(point, color)| Pixel(point, color)),
+        )
+    }
+
+    fn fill_solid(&mut self, area: &Rectangle, color: Self::Color) -> Result<(), Self::Error> {
+        if self.orientation.is_identity() {
+            return self.target.fill_solid(area, color);
+        }
+        let Some(bottom_right) = area.bottom_right() else {
+            return Ok(());
+        };
+        let (orientation, physical) = (self.orientation, self.physical);
+        let area = Rectangle::with_corners(
+            to_physical(orientation, physical, area.top_left),
+            to_physical(orientation, physical, bottom_right),
+        );
+        self.target.fill_solid(&area, color)
+    }
+
+    fn clear(&mut self, color: Self::Color) -> Result<(), Self::Error> {
+        self.target.clear(color)
+    }
+}
+
+/// Draw target that fakes missing bold and italic faces.
+///
+/// Italic shears glyph pixels right in proportion to their height above
+/// `baseline_y`; bold strikes every pixel a second time one pixel to the
+/// right. Works with any [`FontBackend`] since it only moves pixels.
+struct Synthesized<'a, D> {
+    target: &'a mut D,
+    synthesis: FontSynthesis,
+    baseline_y: i32,
+}
+
+impl<D> Dimensions for Synthesized<'_, D>
+where
+    D: DrawTarget,
+{
+    fn bounding_box(&self) -> Rectangle {
+        self.target.bounding_box()
+    }
+}
+
+impl<D> DrawTarget for Synthesized<'_, D>
+where
+    D: DrawTarget,
+{
+    type Color = D::Color;
+    type Error = D::Error;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        let (synthesis, baseline_y) = (self.synthesis, self.baseline_y);
+        self.target
+            .draw_iter(pixels.into_iter().flat_map(move |Pixel(point, color)| {
+                let mut point = point;
+                if synthesis.italic {
+                    // About 14 degrees, the usual oblique slant.
+                    point.x += (baseline_y - point.y).div_euclid(4);
+                }
+                let doubled = synthesis
+                    .bold
+                    .then(|| Pixel(point + Point::new(1, 0), color));
+                core::iter::once(Pixel(point, color)).chain(doubled)
+            }))
     }
 }
 
@@ -392,60 +831,88 @@ where
     }
 
     /// Render a page to a draw target.
+    ///
+    /// Draw-time chrome fields render empty; use [`Self::render_overlay`] to
+    /// supply them.
     pub fn render_page<D>(&self, page: &RenderPage, display: &mut D) -> Result<(), D::Error>
     where
-        D: DrawTarget<Color = BinaryColor>,
+        D: DrawTarget,
+        D::Color: RenderColor,
     {
         self.render_content(page, display)?;
-        self.render_overlay(page, display)?;
+        self.render_overlay(page, display, &ChromeData::default())?;
         Ok(())
     }
 
     /// Render content commands from the current single-stream page output.
     pub fn render_content<D>(&self, page: &RenderPage, display: &mut D) -> Result<(), D::Error>
     where
-        D: DrawTarget<Color = BinaryColor>,
+        D: DrawTarget,
+        D::Color: RenderColor,
     {
+        let display = &mut Oriented::new(display, self.cfg.orientation);
         if self.cfg.clear_first {
-            display.clear(BinaryColor::Off)?;
+            display.clear(D::Color::PAPER)?;
         }
-        let content_iter: Box<dyn Iterator<Item = &DrawCommand> + '_> =
-            if !page.content_commands.is_empty() {
-                Box::new(page.content_commands.iter())
-            } else {
-                Box::new(
-                    page.commands
-                        .iter()
-                        .filter(|cmd| !matches!(cmd, DrawCommand::PageChrome(_))),
-                )
-            };
-        for cmd in content_iter {
-            self.draw_command(display, cmd)?;
+        let viewport = display.bounding_box().size;
+        for cmd in content_layer(page) {
+            self.draw_command(display, cmd, viewport, &ChromeData::default())?;
         }
         Ok(())
     }
 
     /// Render overlay/chrome commands from the current single-stream page output.
-    pub fn render_overlay<D>(&self, page: &RenderPage, display: &mut D) -> Result<(), D::Error>
+    ///
+    /// Chrome fields such as `{clock}` and `{battery}` are filled from `data`,
+    /// so a clock tick only needs this call, not a new layout.
+    pub fn render_overlay<D>(
+        &self,
+        page: &RenderPage,
+        display: &mut D,
+        data: &ChromeData<'_>,
+    ) -> Result<(), D::Error>
     where
-        D: DrawTarget<Color = BinaryColor>,
+        D: DrawTarget,
+        D::Color: RenderColor,
     {
-        if !page.chrome_commands.is_empty() || !page.overlay_commands.is_empty() {
-            for cmd in page
-                .chrome_commands
-                .iter()
-                .chain(page.overlay_commands.iter())
-            {
-                self.draw_command(display, cmd)?;
-            }
-            return Ok(());
+        let display = &mut Oriented::new(display, self.cfg.orientation);
+        let viewport = display.bounding_box().size;
+        for cmd in overlay_layer(page) {
+            self.draw_command(display, cmd, viewport, data)?;
         }
-        for cmd in page
-            .commands
-            .iter()
-            .filter(|cmd| matches!(cmd, DrawCommand::PageChrome(_)))
-        {
-            self.draw_command(display, cmd)?;
+        Ok(())
+    }
+
+    /// Redraw only `regions` of a page, e.g. the damage from [`RenderPage::diff`].
+    ///
+    /// Each region is cleared (when `clear_first` is set) and repainted with
+    /// every command clipped to it; pixels outside the regions are untouched.
+    /// Chrome fields are filled from `data`.
+    pub fn render_page_regions<D>(
+        &self,
+        page: &RenderPage,
+        display: &mut D,
+        regions: &[OverlayRect],
+        data: &ChromeData<'_>,
+    ) -> Result<(), D::Error>
+    where
+        D: DrawTarget,
+        D::Color: RenderColor,
+    {
+        let display = &mut Oriented::new(display, self.cfg.orientation);
+        let viewport = display.bounding_box().size;
+        for region in regions {
+            let area = Rectangle::new(
+                Point::new(region.x, region.y),
+                Size::new(region.width, region.height),
+            );
+            let mut clipped = display.clipped(&area);
+            if self.cfg.clear_first {
+                clipped.clear(D::Color::PAPER)?;
+            }
+            for cmd in content_layer(page).chain(overlay_layer(page)) {
+                self.draw_command(&mut clipped, cmd, viewport, data)?;
+            }
         }
         Ok(())
     }
@@ -457,13 +924,16 @@ where
         display: &mut D,
     ) -> Result<(), D::Error>
     where
-        D: DrawTarget<Color = BinaryColor>,
+        D: DrawTarget,
+        D::Color: RenderColor,
     {
+        let display = &mut Oriented::new(display, self.cfg.orientation);
         if self.cfg.clear_first {
-            display.clear(BinaryColor::Off)?;
+            display.clear(D::Color::PAPER)?;
         }
+        let viewport = display.bounding_box().size;
         for cmd in commands {
-            self.draw_command(display, cmd)?;
+            self.draw_command(display, cmd, viewport, &ChromeData::default())?;
         }
         Ok(())
     }
@@ -475,22 +945,32 @@ where
         display: &mut D,
     ) -> Result<(), D::Error>
     where
-        D: DrawTarget<Color = BinaryColor>,
+        D: DrawTarget,
+        D::Color: RenderColor,
     {
+        let display = &mut Oriented::new(display, self.cfg.orientation);
+        let viewport = display.bounding_box().size;
         for cmd in commands {
-            self.draw_command(display, cmd)?;
+            self.draw_command(display, cmd, viewport, &ChromeData::default())?;
         }
         Ok(())
     }
 
-    fn draw_command<D>(&self, display: &mut D, cmd: &DrawCommand) -> Result<(), D::Error>
+    fn draw_command<D>(
+        &self,
+        display: &mut D,
+        cmd: &DrawCommand,
+        viewport: Size,
+        data: &ChromeData<'_>,
+    ) -> Result<(), D::Error>
     where
-        D: DrawTarget<Color = BinaryColor>,
+        D: DrawTarget,
+        D::Color: RenderColor,
     {
         match cmd {
             DrawCommand::Text(text) => self.draw_text(display, text),
             DrawCommand::Rule(rule) => {
-                let style = PrimitiveStyle::with_stroke(BinaryColor::On, rule.thickness);
+                let style = PrimitiveStyle::with_stroke(D::Color::INK, rule.thickness);
                 let end = if rule.horizontal {
                     Point::new(rule.x + rule.length as i32, rule.y)
                 } else {
@@ -508,99 +988,130 @@ where
                 );
                 if rect.fill {
                     shape
-                        .into_styled(PrimitiveStyle::with_fill(BinaryColor::On))
+                        .into_styled(PrimitiveStyle::with_fill(D::Color::INK))
                         .draw(display)?;
                 } else {
                     shape
-                        .into_styled(PrimitiveStyle::with_stroke(BinaryColor::On, 1))
+                        .into_styled(PrimitiveStyle::with_stroke(D::Color::INK, 1))
                         .draw(display)?;
                 }
                 Ok(())
             }
-            DrawCommand::PageChrome(chrome) => self.draw_page_chrome(display, chrome),
+            DrawCommand::PageChrome(chrome) => {
+                self.draw_page_chrome(display, chrome, viewport, data)
+            }
         }
     }
 
     fn draw_text<D>(&self, display: &mut D, cmd: &TextCommand) -> Result<(), D::Error>
     where
-        D: DrawTarget<Color = BinaryColor>,
+        D: DrawTarget,
+        D::Color: RenderColor,
+    {
+        let synthesis = cmd.style.synthesis;
+        if synthesis.is_none() {
+            return self.draw_text_runs(display, cmd);
+        }
+        let display = &mut Synthesized {
+            target: display,
+            synthesis,
+            baseline_y: cmd.baseline_y,
+        };
+        self.draw_text_runs(display, cmd)
+    }
+
+    fn draw_text_runs<D>(&self, display: &mut D, cmd: &TextCommand) -> Result<(), D::Error>
+    where
+        D: DrawTarget,
+        D::Color: RenderColor,
     {
         let requested_font_id = cmd.font_id.or(cmd.style.font_id);
         let selection = self.backend.resolve_font(&cmd.style, requested_font_id);
         let metrics = self.backend.metrics(selection.font_id);
         let origin = Point::new(cmd.x, cmd.baseline_y);
 
-        match cmd.style.justify_mode {
-            JustifyMode::None => self
-                .backend
-                .draw_text_run(display, selection.font_id, &cmd.text, origin)
-                .map(|_| ()),
-            JustifyMode::InterWord { extra_px_total } => {
-                let spaces = cmd.text.chars().filter(|c| *c == ' ').count() as i32;
-                if spaces <= 0 || extra_px_total <= 0 {
-                    self.backend
-                        .draw_text_run(display, selection.font_id, &cmd.text, origin)?;
-                    return Ok(());
-                }
+        let mode = cmd.style.justify_mode;
+        let spaces = cmd.text.chars().filter(|c| *c == ' ').count();
+        let gaps = cmd.text.chars().count().saturating_sub(1);
+        let mut words = Spread::new(mode.word_extra_px(), spaces);
+        let mut letters = Spread::new(mode.char_extra_px(), gaps);
+        if words.is_empty() && letters.is_empty() {
+            self.backend
+                .draw_text_run(display, selection.font_id, &cmd.text, origin)?;
+            return Ok(());
+        }
 
-                let per_space = extra_px_total / spaces;
-                let mut remainder = extra_px_total % spaces;
-                let mut x = cmd.x;
-                let mut run_start = 0usize;
-
-                for (idx, ch) in cmd.text.char_indices() {
-                    if ch == ' ' {
-                        if run_start < idx {
-                            let run = &cmd.text[run_start..idx];
-                            x += self.backend.draw_text_run(
-                                display,
-                                selection.font_id,
-                                run,
-                                Point::new(x, cmd.baseline_y),
-                            )?;
-                        }
-
-                        x += metrics.space_width + per_space;
-                        if remainder > 0 {
-                            x += 1;
-                            remainder -= 1;
-                        }
-                        run_start = idx + ch.len_utf8();
+        let mut x = cmd.x;
+        if letters.is_empty() {
+            let mut run_start = 0usize;
+            for (idx, ch) in cmd.text.char_indices() {
+                if ch == ' ' {
+                    if run_start < idx {
+                        let run = &cmd.text[run_start..idx];
+                        x += self.backend.draw_text_run(
+                            display,
+                            selection.font_id,
+                            run,
+                            Point::new(x, cmd.baseline_y),
+                        )?;
                     }
+                    x += metrics.space_width + words.next_px();
+                    run_start = idx + ch.len_utf8();
                 }
+            }
+            if run_start < cmd.text.len() {
+                let run = &cmd.text[run_start..];
+                self.backend.draw_text_run(
+                    display,
+                    selection.font_id,
+                    run,
+                    Point::new(x, cmd.baseline_y),
+                )?;
+            }
+            return Ok(());
+        }
 
-                if run_start < cmd.text.len() {
-                    let run = &cmd.text[run_start..];
-                    self.backend.draw_text_run(
-                        display,
-                        selection.font_id,
-                        run,
-                        Point::new(x, cmd.baseline_y),
-                    )?;
-                }
-                Ok(())
+        // Letter spacing places every glyph individually.
+        for (i, (idx, ch)) in cmd.text.char_indices().enumerate() {
+            if i > 0 {
+                x += letters.next_px();
+            }
+            if ch == ' ' {
+                x += metrics.space_width + words.next_px();
+                continue;
             }
+            let glyph = &cmd.text[idx..idx + ch.len_utf8()];
+            x += self.backend.draw_text_run(
+                display,
+                selection.font_id,
+                glyph,
+                Point::new(x, cmd.baseline_y),
+            )?;
         }
+        Ok(())
     }
 
     fn draw_page_chrome<D>(
         &self,
         display: &mut D,
         chrome: &PageChromeCommand,
+        viewport: Size,
+        data: &ChromeData<'_>,
     ) -> Result<(), D::Error>
     where
-        D: DrawTarget<Color = BinaryColor>,
+        D: DrawTarget,
+        D::Color: RenderColor,
     {
-        let bounds = display.bounding_box();
-        let width = bounds.size.width as i32;
-        let height = bounds.size.height as i32;
+        let width = viewport.width as i32;
+        let height = viewport.height as i32;
         let chrome_cfg = self.cfg.page_chrome;
+        let text = chrome.resolve_text(data);
         match chrome.kind {
             PageChromeKind::Header => {
                 if !chrome_cfg.header_enabled {
                     return Ok(());
                 }
-                if let Some(text) = &chrome.text {
+                if let Some(text) = &text {
                     let style = mono_text_style(chrome_cfg.header_style);
                     Text::new(
                         text,
@@ -614,7 +1125,7 @@ where
                 if !chrome_cfg.footer_enabled {
                     return Ok(());
                 }
-                if let Some(text) = &chrome.text {
+                if let Some(text) = &text {
                     let style = mono_text_style(chrome_cfg.footer_style);
                     Text::new(
                         text,
@@ -640,12 +1151,12 @@ where
                 let filled = ((bar_w as usize * current.min(total)) / total) as u32;
                 Rectangle::new(Point::new(bar_x, bar_y), Size::new(bar_w, bar_h))
                     .into_styled(PrimitiveStyle::with_stroke(
-                        BinaryColor::On,
+                        D::Color::INK,
                         chrome_cfg.progress_stroke_width.max(1),
                     ))
                     .draw(display)?;
                 Rectangle::new(Point::new(bar_x, bar_y), Size::new(filled, bar_h))
-                    .into_styled(PrimitiveStyle::with_fill(BinaryColor::On))
+                    .into_styled(PrimitiveStyle::with_fill(D::Color::INK))
                     .draw(display)?;
             }
         }
@@ -653,12 +1164,77 @@ where
     }
 }
 
-fn mono_text_style(style: PageChromeTextStyle) -> MonoTextStyle<'static, BinaryColor> {
+/// Even distribution of justification px over slots, remainder first.
+struct Spread {
+    per_slot: i32,
+    remainder: i32,
+}
+
+impl Spread {
+    fn new(total_px: i32, slots: usize) -> Self {
+        let slots = i32::try_from(slots).unwrap_or(i32::MAX);
+        if slots == 0 || total_px <= 0 {
+            return Self {
+                per_slot: 0,
+                remainder: 0,
+            };
+        }
+        Self {
+            per_slot: total_px / slots,
+            remainder: total_px % slots,
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.per_slot == 0 && self.remainder == 0
+    }
+
+    fn next_px(&mut self) -> i32 {
+        if self.remainder > 0 {
+            self.remainder -= 1;
+            self.per_slot + 1
+        } else {
+            self.per_slot
+        }
+    }
+}
+
+/// Content-layer commands, falling back to the legacy merged stream.
+fn content_layer(page: &RenderPage) -> Box<dyn Iterator<Item = &DrawCommand> + '_> {
+    if !page.content_commands.is_empty() {
+        Box::new(page.content_commands.iter())
+    } else {
+        Box::new(
+            page.commands
+                .iter()
+                .filter(|cmd| !matches!(cmd, DrawCommand::PageChrome(_))),
+        )
+    }
+}
+
+/// Chrome and overlay commands, falling back to the legacy merged stream.
+fn overlay_layer(page: &RenderPage) -> Box<dyn Iterator<Item = &DrawCommand> + '_> {
+    if !page.chrome_commands.is_empty() || !page.overlay_commands.is_empty() {
+        Box::new(
+            page.chrome_commands
+                .iter()
+                .chain(page.overlay_commands.iter()),
+        )
+    } else {
+        Box::new(
+            page.commands
+                .iter()
+                .filter(|cmd| matches!(cmd, DrawCommand::PageChrome(_))),
+        )
+    }
+}
+
+fn mono_text_style<C: RenderColor>(style: PageChromeTextStyle) -> MonoTextStyle<'static, C> {
     match style {
-        PageChromeTextStyle::Regular => MonoTextStyle::new(&FONT_8X13, BinaryColor::On),
-        PageChromeTextStyle::Bold => MonoTextStyle::new(&FONT_7X13_BOLD, BinaryColor::On),
-        PageChromeTextStyle::Italic => MonoTextStyle::new(&FONT_6X13_ITALIC, BinaryColor::On),
-        PageChromeTextStyle::BoldItalic => MonoTextStyle::new(&FONT_9X15_BOLD, BinaryColor::On),
+        PageChromeTextStyle::Regular => MonoTextStyle::new(&FONT_8X13, C::INK),
+        PageChromeTextStyle::Bold => MonoTextStyle::new(&FONT_7X13_BOLD, C::INK),
+        PageChromeTextStyle::Italic => MonoTextStyle::new(&FONT_6X13_ITALIC, C::INK),
+        PageChromeTextStyle::BoldItalic => MonoTextStyle::new(&FONT_9X15_BOLD, C::INK),
     }
 }
 
@@ -688,6 +1264,7 @@ mod tests {
     struct PixelCaptureDisplay {
         size: Size,
         on_pixels: Vec<Point>,
+        solid_fills: Vec<Rectangle>,
     }
 
     impl PixelCaptureDisplay {
@@ -695,6 +1272,7 @@ mod tests {
             Self {
                 size: Size::new(width, height),
                 on_pixels: Vec::new(),
+                solid_fills: Vec::new(),
             }
         }
     }
@@ -720,6 +1298,11 @@ mod tests {
             }
             Ok(())
         }
+
+        fn fill_solid(&mut self, area: &Rectangle, color: Self::Color) -> Result<(), Self::Error> {
+            self.solid_fills.push(*area);
+            self.fill_contiguous(area, core::iter::repeat(color))
+        }
     }
 
     #[derive(Clone, Debug, Default)]
@@ -782,7 +1365,8 @@ mod tests {
             _origin: Point,
         ) -> Result<i32, D::Error>
         where
-            D: DrawTarget<Color = BinaryColor>,
+            D: DrawTarget,
+            D::Color: RenderColor,
         {
             self.state.borrow_mut().draw_runs.push(text.to_string());
             Ok(text.chars().count() as i32)
@@ -791,7 +1375,7 @@ mod tests {
 
     #[test]
     fn renders_text_command_without_error() {
-        let mut display = MockDisplay::new();
+        let mut display = MockDisplay::<BinaryColor>::new();
         display.set_allow_overdraw(true);
         let renderer = EgRenderer::default();
         let style = ResolvedTextStyle {
@@ -804,6 +1388,7 @@ mod tests {
             letter_spacing: 0.0,
             role: BlockRole::Body,
             justify_mode: JustifyMode::None,
+            synthesis: FontSynthesis::default(),
         };
         let page = page_with_commands(
             1,
@@ -822,7 +1407,7 @@ mod tests {
 
     #[test]
     fn text_command_execution_uses_backend_draw() {
-        let mut display = MockDisplay::new();
+        let mut display = MockDisplay::<BinaryColor>::new();
         display.set_allow_overdraw(true);
         let backend = BackendSpy::default();
         let state = backend.state();
@@ -837,6 +1422,7 @@ mod tests {
             letter_spacing: 0.0,
             role: BlockRole::Body,
             justify_mode: JustifyMode::None,
+            synthesis: FontSynthesis::default(),
         };
         let page = page_with_commands(
             1,
@@ -915,7 +1501,7 @@ mod tests {
 
     #[test]
     fn justification_and_non_justification_use_backend_paths() {
-        let mut display = MockDisplay::new();
+        let mut display = MockDisplay::<BinaryColor>::new();
         display.set_allow_overdraw(true);
         let backend = BackendSpy::default();
         let state = backend.state();
@@ -930,6 +1516,7 @@ mod tests {
             letter_spacing: 0.0,
             role: BlockRole::Body,
             justify_mode: JustifyMode::None,
+            synthesis: FontSynthesis::default(),
         };
 
         let plain = TextCommand {
@@ -975,6 +1562,7 @@ mod tests {
             letter_spacing: 0.0,
             role: BlockRole::Body,
             justify_mode: JustifyMode::None,
+            synthesis: FontSynthesis::default(),
         };
 
         let selection = backend.resolve_font(&style, None);
@@ -997,6 +1585,7 @@ mod tests {
             letter_spacing: 0.0,
             role: BlockRole::Body,
             justify_mode: JustifyMode::None,
+            synthesis: FontSynthesis::default(),
         };
 
         let selection = backend.resolve_font(&style, Some(999));
@@ -1008,7 +1597,7 @@ mod tests {
 
     #[test]
     fn page_chrome_commands_are_rendered_not_dropped() {
-        let mut display = MockDisplay::new();
+        let mut display = MockDisplay::<BinaryColor>::new();
         display.set_allow_overdraw(true);
         let renderer = EgRenderer::default();
         let page = page_with_commands(
@@ -1040,9 +1629,9 @@ mod tests {
 
     #[test]
     fn split_and_single_stream_render_paths_are_compatible() {
-        let mut display_single = MockDisplay::new();
+        let mut display_single = MockDisplay::<BinaryColor>::new();
         display_single.set_allow_overdraw(true);
-        let mut display_split = MockDisplay::new();
+        let mut display_split = MockDisplay::<BinaryColor>::new();
         display_split.set_allow_overdraw(true);
         let backend_single = BackendSpy::default();
         let backend_split = BackendSpy::default();
@@ -1060,6 +1649,7 @@ mod tests {
             letter_spacing: 0.0,
             role: BlockRole::Body,
             justify_mode: JustifyMode::None,
+            synthesis: FontSynthesis::default(),
         };
         let content_commands = vec![
             DrawCommand::Text(TextCommand {
@@ -1186,6 +1776,9 @@ mod tests {
             max_face_bytes: 8,
             max_total_face_bytes: 12,
             fallback_policy: TtfFallbackPolicy::MonoOnly,
+            antialias: true,
+            glyph_cache_entries: 16,
+            dither: DitherMode::None,
         };
         let backend = TtfFontBackend::new(opts);
         assert_eq!(backend.options(), opts);
@@ -1200,6 +1793,9 @@ mod tests {
             max_face_bytes: 4,
             max_total_face_bytes: 6,
             fallback_policy: TtfFallbackPolicy::MonoOnly,
+            antialias: true,
+            glyph_cache_entries: 16,
+            dither: DitherMode::None,
         };
         let mut backend = TtfFontBackend::new(opts);
         let face_a = FontFaceRegistration {
@@ -1239,4 +1835,403 @@ mod tests {
             }
         );
     }
+
+    #[test]
+    fn render_page_regions_repaints_only_clipped_area() {
+        let mut page = RenderPage::new(1);
+        page.push_content_command(DrawCommand::Rect(mu_epub_render::RectCommand {
+            x: 0,
+            y: 0,
+            width: 10,
+            height: 10,
+            fill: true,
+        }));
+        page.push_chrome_command(DrawCommand::PageChrome(PageChromeCommand {
+            kind: PageChromeKind::Footer,
+            text: Some("12:01".to_string()),
+            current: None,
+            total: None,
+        }));
+        page.sync_commands();
+        let region = OverlayRect {
+            x: 0,
+            y: 40,
+            width: 100,
+            height: 20,
+        };
+        let inside = |p: &Point| p.y >= 40 && p.y < 60;
+        let renderer = EgRenderer::default();
+
+        let mut full = PixelCaptureDisplay::with_size(100, 60);
+        renderer.render_page(&page, &mut full).unwrap();
+        let mut partial = PixelCaptureDisplay::with_size(100, 60);
+        renderer
+            .render_page_regions(&page, &mut partial, &[region], &ChromeData::default())
+            .unwrap();
+
+        let expected: Vec<Point> = full.on_pixels.iter().copied().filter(inside).collect();
+        assert!(!expected.is_empty());
+        assert_eq!(partial.on_pixels, expected);
+    }
+
+    #[test]
+    fn render_overlay_fills_chrome_fields_at_draw_time() {
+        let footer = |text: &str| {
+            let mut page = RenderPage::new(1);
+            page.push_chrome_command(DrawCommand::PageChrome(PageChromeCommand {
+                kind: PageChromeKind::Footer,
+                text: Some(text.to_string()),
+                current: None,
+                total: None,
+            }));
+            page.sync_commands();
+            page
+        };
+        let draw = |page: &RenderPage, data: &ChromeData<'_>| {
+            let mut display = PixelCaptureDisplay::with_size(200, 60);
+            EgRenderer::default()
+                .render_overlay(page, &mut display, data)
+                .unwrap();
+            display.on_pixels
+        };
+        let dynamic = footer("Page 3 {clock} {battery}");
+        let data = ChromeData {
+            clock: Some("14:05"),
+            battery: Some(85),
+            custom: None,
+        };
+        assert_eq!(
+            draw(&dynamic, &data),
+            draw(&footer("Page 3 14:05 85%"), &ChromeData::default())
+        );
+        assert_eq!(
+            draw(&dynamic, &ChromeData::default()),
+            draw(&footer("Page 3  "), &ChromeData::default())
+        );
+    }
+
+    fn rect_page(x: i32, y: i32, width: u32, height: u32) -> RenderPage {
+        let mut page = RenderPage::new(1);
+        page.push_content_command(DrawCommand::Rect(mu_epub_render::RectCommand {
+            x,
+            y,
+            width,
+            height,
+            fill: true,
+        }));
+        page.sync_commands();
+        page
+    }
+
+    fn oriented_renderer(rotation: Rotation, mirrored: bool) -> EgRenderer {
+        EgRenderer::new(EgRenderConfig {
+            orientation: Orientation { rotation, mirrored },
+            ..EgRenderConfig::default()
+        })
+    }
+
+    #[test]
+    fn rotation_maps_logical_origin_to_panel_corner() {
+        // Logical page is 6x4 on a 4x6 panel for quarter turns.
+        let page = rect_page(0, 0, 2, 1);
+        let cases = [
+            (
+                Rotation::Deg90,
+                4,
+                6,
+                vec![Point::new(3, 0), Point::new(3, 1)],
+            ),
+            (
+                Rotation::Deg180,
+                6,
+                4,
+                vec![Point::new(4, 3), Point::new(5, 3)],
+            ),
+            (
+                Rotation::Deg270,
+                4,
+                6,
+                vec![Point::new(0, 4), Point::new(0, 5)],
+            ),
+        ];
+        for (rotation, w, h, expected) in cases {
+            let mut display = PixelCaptureDisplay::with_size(w, h);
+            oriented_renderer(rotation, false)
+                .render_page(&page, &mut display)
+                .unwrap();
+            assert_eq!(display.on_pixels, expected, "{:?}", rotation);
+        }
+    }
+
+    #[test]
+    fn rotated_fill_solid_stays_a_single_panel_fill() {
+        let mut display = PixelCaptureDisplay::with_size(4, 6);
+        let mut oriented = Oriented::new(
+            &mut display,
+            Orientation {
+                rotation: Rotation::Deg90,
+                mirrored: false,
+            },
+        );
+        oriented
+            .fill_solid(
+                &Rectangle::new(Point::new(1, 0), Size::new(3, 2)),
+                BinaryColor::On,
+            )
+            .unwrap();
+        assert_eq!(
+            display.solid_fills,
+            vec![Rectangle::new(Point::new(2, 1), Size::new(2, 3))]
+        );
+        assert_eq!(display.on_pixels.len(), 6);
+    }
+
+    #[test]
+    fn mirroring_flips_before_rotation() {
+        let page = rect_page(0, 0, 1, 1);
+        let mut display = PixelCaptureDisplay::with_size(6, 4);
+        oriented_renderer(Rotation::Deg0, true)
+            .render_page(&page, &mut display)
+            .unwrap();
+        assert_eq!(display.on_pixels, vec![Point::new(5, 0)]);
+    }
+
+    #[test]
+    fn rotated_chrome_follows_logical_bottom() {
+        let mut page = RenderPage::new(1);
+        page.push_chrome_command(DrawCommand::PageChrome(PageChromeCommand {
+            kind: PageChromeKind::Progress,
+            text: None,
+            current: Some(1),
+            total: Some(1),
+        }));
+        page.sync_commands();
+        // Panel 60x100 rotated 90: logical page 100 wide, 60 tall.
+        let mut display = PixelCaptureDisplay::with_size(60, 100);
+        oriented_renderer(Rotation::Deg90, false)
+            .render_page(&page, &mut display)
+            .unwrap();
+        assert!(!display.on_pixels.is_empty());
+        // The logical bottom edge lands on the panel's left edge.
+        assert!(display.on_pixels.iter().all(|p| p.x < 30));
+        let logical_y = 60 - PageChromeConfig::geometry_defaults().progress_y_from_bottom;
+        assert!(display.on_pixels.iter().all(|p| p.x <= 59 - logical_y));
+    }
+
+    #[test]
+    fn gray_coverage_maps_onto_panel_levels() {
+        assert_eq!(Gray4::from_coverage(0), Gray4::WHITE);
+        assert_eq!(Gray4::from_coverage(255), Gray4::BLACK);
+        assert_eq!(Gray4::from_coverage(128), Gray4::new(7));
+        assert_eq!(Gray2::from_coverage(85), Gray2::new(2));
+        assert_eq!(Gray8::from_coverage(1), Gray8::new(254));
+        assert_eq!(BinaryColor::from_coverage(127), BinaryColor::Off);
+        assert_eq!(BinaryColor::from_coverage(128), BinaryColor::On);
+    }
+
+    #[test]
+    fn renders_to_grayscale_targets() {
+        let mut page = RenderPage::new(1);
+        page.push_content_command(DrawCommand::Rect(mu_epub_render::RectCommand {
+            x: 1,
+            y: 1,
+            width: 2,
+            height: 2,
+            fill: true,
+        }));
+        page.sync_commands();
+        let mut display: MockDisplay<Gray4> = MockDisplay::new();
+        display.set_allow_overdraw(true);
+        EgRenderer::default()
+            .render_page(&page, &mut display)
+            .unwrap();
+        assert_eq!(display.get_pixel(Point::new(0, 0)), Some(Gray4::WHITE));
+        assert_eq!(display.get_pixel(Point::new(1, 1)), Some(Gray4::BLACK));
+        assert_eq!(display.get_pixel(Point::new(2, 2)), Some(Gray4::BLACK));
+    }
+
+    #[cfg(feature = "ttf-backend")]
+    #[test]
+    fn ttf_coverage_blit_respects_antialias_option() {
+        let coverage = [0u8, 64, 200, 255];
+        let mut display: MockDisplay<Gray4> = MockDisplay::new();
+        TtfFontBackend::default()
+            .draw_coverage(&mut display, Point::new(0, 0), 2, &coverage)
+            .unwrap();
+        assert_eq!(display.get_pixel(Point::new(0, 0)), None);
+        assert_eq!(display.get_pixel(Point::new(1, 0)), Some(Gray4::new(11)));
+        assert_eq!(display.get_pixel(Point::new(0, 1)), Some(Gray4::new(3)));
+        assert_eq!(display.get_pixel(Point::new(1, 1)), Some(Gray4::BLACK));
+
+        let binary = TtfFontBackend::new(TtfBackendOptions {
+            antialias: false,
+            ..TtfBackendOptions::default()
+        });
+        assert_eq!(binary.coverage_color::<Gray4>(64), Gray4::WHITE);
+        assert_eq!(binary.coverage_color::<Gray4>(200), Gray4::BLACK);
+    }
+
+    #[cfg(feature = "ttf-backend")]
+    #[test]
+    fn ttf_text_runs_draw_through_coverage_blit() {
+        let origin = Point::new(2, 12);
+        let mut mono: MockDisplay<Gray4> = MockDisplay::new();
+        let mono_advance = MonoFontBackend
+            .draw_text_run(&mut mono, 0, "Hi", origin)
+            .unwrap();
+        let mut ttf: MockDisplay<Gray4> = MockDisplay::new();
+        let ttf_advance = TtfFontBackend::default()
+            .draw_text_run(&mut ttf, 0, "Hi", origin)
+            .unwrap();
+        assert_eq!(ttf_advance, mono_advance);
+        assert!(ttf.affected_area().size.width > 0);
+        ttf.assert_eq(&mono);
+    }
+
+    #[cfg(feature = "ttf-backend")]
+    #[test]
+    fn ttf_options_follow_render_intent() {
+        let intent = RenderIntent {
+            dither: DitherMode::ErrorDiffusion,
+            priority: mu_epub_render::RenderPriority::MinMemory,
+            ..RenderIntent::default()
+        };
+        let opts = TtfBackendOptions::for_intent(&intent);
+        assert_eq!(opts.dither, DitherMode::Ordered);
+        assert_eq!(opts.glyph_cache_entries, 32);
+
+        // A flat 50% gray dithers to half ink with either method.
+        let coverage = [128u8; 64];
+        for dither in [DitherMode::Ordered, DitherMode::ErrorDiffusion] {
+            let backend = TtfFontBackend::new(TtfBackendOptions {
+                dither,
+                ..TtfBackendOptions::default()
+            });
+            let mut display: MockDisplay<BinaryColor> = MockDisplay::new();
+            backend
+                .draw_coverage(&mut display, Point::new(0, 0), 8, &coverage)
+                .unwrap();
+            let inked = (0..8)
+                .flat_map(|y| (0..8).map(move |x| Point::new(x, y)))
+                .filter(|&p| display.get_pixel(p) == Some(BinaryColor::On))
+                .count();
+            assert_eq!(inked, 32, "{:?}", dither);
+        }
+    }
+
+    #[test]
+    fn letter_spaced_text_places_each_glyph() {
+        let style = ResolvedTextStyle {
+            font_id: None,
+            family: "monospace".to_string(),
+            weight: 400,
+            italic: false,
+            size_px: 13.0,
+            line_height: 1.2,
+            letter_spacing: 0.0,
+            role: BlockRole::Body,
+            justify_mode: JustifyMode::None,
+            synthesis: FontSynthesis::default(),
+        };
+        let text = |x: i32, text: &str, justify_mode| {
+            DrawCommand::Text(TextCommand {
+                x,
+                baseline_y: 12,
+                text: text.to_string(),
+                font_id: None,
+                style: ResolvedTextStyle {
+                    justify_mode,
+                    ..style.clone()
+                },
+            })
+        };
+        let render = |commands| {
+            let mut display = PixelCaptureDisplay::with_size(64, 16);
+            EgRenderer::default()
+                .render_page(&page_with_commands(1, commands), &mut display)
+                .unwrap();
+            let mut pixels = display.on_pixels;
+            pixels.sort_by_key(|p| (p.x, p.y));
+            pixels
+        };
+
+        let spaced = render(vec![text(
+            0,
+            "ab",
+            JustifyMode::InterCharacter { extra_px_total: 3 },
+        )]);
+        let expected = render(vec![
+            text(0, "a", JustifyMode::None),
+            text(11, "b", JustifyMode::None),
+        ]);
+        assert_eq!(spaced, expected);
+
+        let mixed = render(vec![text(
+            0,
+            "a b",
+            JustifyMode::InterWordAndCharacter {
+                word_px_total: 2,
+                char_px_total: 2,
+            },
+        )]);
+        // 'a' (8) + gap (1) + space (8 + 2) + gap (1) puts 'b' at 20.
+        let expected = render(vec![
+            text(0, "a", JustifyMode::None),
+            text(20, "b", JustifyMode::None),
+        ]);
+        assert_eq!(mixed, expected);
+    }
+
+    #[test]
+    fn synthesized_bold_and_italic_offset_glyph_pixels() {
+        let render = |synthesis: FontSynthesis| {
+            let cmd = DrawCommand::Text(TextCommand {
+                x: 0,
+                baseline_y: 12,
+                text: "l".to_string(),
+                font_id: None,
+                style: ResolvedTextStyle {
+                    font_id: None,
+                    family: "monospace".to_string(),
+                    weight: 400,
+                    italic: false,
+                    size_px: 13.0,
+                    line_height: 1.2,
+                    letter_spacing: 0.0,
+                    role: BlockRole::Body,
+                    justify_mode: JustifyMode::None,
+                    synthesis,
+                },
+            });
+            let mut display = PixelCaptureDisplay::with_size(32, 16);
+            EgRenderer::default()
+                .render_page(&page_with_commands(1, vec![cmd]), &mut display)
+                .unwrap();
+            let mut pixels = display.on_pixels;
+            pixels.sort_by_key(|p| (p.x, p.y));
+            pixels.dedup();
+            pixels
+        };
+
+        let regular = render(FontSynthesis::default());
+        let bold = render(FontSynthesis {
+            bold: true,
+            italic: false,
+        });
+        for point in &regular {
+            assert!(bold.contains(point));
+            assert!(bold.contains(&(*point + Point::new(1, 0))));
+        }
+        assert!(bold.len() > regular.len());
+
+        let italic = render(FontSynthesis {
+            bold: false,
+            italic: true,
+        });
+        assert_eq!(italic.len(), regular.len());
+        let top = |pixels: &[Point]| *pixels.iter().min_by_key(|p| p.y).unwrap();
+        let bottom = |pixels: &[Point]| *pixels.iter().max_by_key(|p| p.y).unwrap();
+        assert!(top(&italic).x > top(&regular).x);
+        assert_eq!(bottom(&italic).x, bottom(&regular).x);
+    }
 }
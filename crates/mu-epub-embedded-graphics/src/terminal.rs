@@ -0,0 +1,372 @@
+//! Character-grid renderer for inspecting pages in a terminal.
+
+use mu_epub_render::{
+    ChromeData, DrawCommand, OverlaySize, PageChromeCommand, PageChromeConfig, PageChromeKind,
+    RectCommand, RenderPage, RuleCommand, TextCommand,
+};
+
+use crate::{content_layer, overlay_layer, FontBackend, MonoFontBackend, Spread};
+
+const ANSI_RESET: &str = "\x1b[0m";
+const ANSI_BOLD: &str = "\x1b[1m";
+const ANSI_ITALIC: &str = "\x1b[3m";
+
+/// Terminal grid geometry and output options.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TerminalConfig {
+    /// Grid width in cells.
+    pub columns: u16,
+    /// Grid height in cells.
+    pub rows: u16,
+    /// Page pixels covered by one cell horizontally.
+    pub cell_width_px: u16,
+    /// Page pixels covered by one cell vertically.
+    pub cell_height_px: u16,
+    /// Emit ANSI bold/italic escapes.
+    pub ansi: bool,
+    /// Chrome geometry used to place header/footer/progress markers.
+    pub page_chrome: PageChromeConfig,
+}
+
+impl Default for TerminalConfig {
+    fn default() -> Self {
+        Self {
+            columns: 80,
+            rows: 30,
+            cell_width_px: 8,
+            cell_height_px: 16,
+            ansi: true,
+            page_chrome: PageChromeConfig::geometry_defaults(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Cell {
+    ch: char,
+    bold: bool,
+    italic: bool,
+}
+
+impl Cell {
+    const BLANK: Self = Self {
+        ch: ' ',
+        bold: false,
+        italic: false,
+    };
+}
+
+/// Rasterizes draw commands into a character grid.
+///
+/// Text is placed glyph by glyph using backend metrics, so justified lines
+/// keep their spacing. Rules and rectangles are drawn with box-drawing
+/// characters.
+#[derive(Clone, Copy, Debug)]
+pub struct TerminalRenderer<B = MonoFontBackend> {
+    cfg: TerminalConfig,
+    backend: B,
+}
+
+impl TerminalRenderer<MonoFontBackend> {
+    /// Create a terminal renderer with the mono backend's metrics.
+    pub fn new(cfg: TerminalConfig) -> Self {
+        Self::with_backend(cfg, MonoFontBackend)
+    }
+}
+
+impl<B> TerminalRenderer<B>
+where
+    B: FontBackend,
+{
+    /// Create a terminal renderer with explicit backend metrics.
+    pub fn with_backend(cfg: TerminalConfig, backend: B) -> Self {
+        Self { cfg, backend }
+    }
+
+    /// Render a page into newline-separated rows with trailing blanks trimmed.
+    pub fn render_page(&self, page: &RenderPage) -> String {
+        let mut grid = vec![Cell::BLANK; self.cfg.columns as usize * self.cfg.rows as usize];
+        for cmd in content_layer(page).chain(overlay_layer(page)) {
+            match cmd {
+                DrawCommand::Text(text) => self.put_text(&mut grid, text),
+                DrawCommand::Rule(rule) => self.put_rule(&mut grid, rule),
+                DrawCommand::Rect(rect) => self.put_rect(&mut grid, rect),
+                DrawCommand::PageChrome(chrome) => self.put_chrome(&mut grid, chrome),
+            }
+        }
+        self.format(&grid)
+    }
+
+    fn cell_index(&self, x: i32, y: i32) -> Option<usize> {
+        let col = x.div_euclid(i32::from(self.cfg.cell_width_px.max(1)));
+        let row = y.div_euclid(i32::from(self.cfg.cell_height_px.max(1)));
+        let col = usize::try_from(col).ok()?;
+        let row = usize::try_from(row).ok()?;
+        (col < self.cfg.columns as usize && row < self.cfg.rows as usize)
+            .then(|| row * self.cfg.columns as usize + col)
+    }
+
+    fn put(&self, grid: &mut [Cell], x: i32, y: i32, cell: Cell) {
+        if let Some(index) = self.cell_index(x, y) {
+            grid[index] = cell;
+        }
+    }
+
+    fn put_text(&self, grid: &mut [Cell], cmd: &TextCommand) {
+        let selection = self
+            .backend
+            .resolve_font(&cmd.style, cmd.font_id.or(cmd.style.font_id));
+        let metrics = self.backend.metrics(selection.font_id);
+        let spaces = cmd.text.chars().filter(|c| *c == ' ').count();
+        let gaps = cmd.text.chars().count().saturating_sub(1);
+        let mut words = Spread::new(cmd.style.justify_mode.word_extra_px(), spaces);
+        let mut letters = Spread::new(cmd.style.justify_mode.char_extra_px(), gaps);
+        // Cells cover the glyph's body, which sits above the baseline.
+        let y = cmd.baseline_y - 1;
+        let mut x = cmd.x;
+        for (i, ch) in cmd.text.chars().enumerate() {
+            if i > 0 {
+                x += letters.next_px();
+            }
+            if ch == ' ' {
+                x += metrics.space_width + words.next_px();
+                continue;
+            }
+            let cell = Cell {
+                ch,
+                bold: cmd.style.weight >= 700,
+                italic: cmd.style.italic,
+            };
+            self.put(grid, x, y, cell);
+            x += metrics.char_width;
+        }
+    }
+
+    fn put_line(&self, grid: &mut [Cell], x: i32, y: i32, length: u32, horizontal: bool) {
+        let (step, ch) = if horizontal {
+            (i32::from(self.cfg.cell_width_px.max(1)), '─')
+        } else {
+            (i32::from(self.cfg.cell_height_px.max(1)), '│')
+        };
+        let line = Cell { ch, ..Cell::BLANK };
+        let mut offset = 0;
+        while offset <= length as i32 {
+            if horizontal {
+                self.put(grid, x + offset, y, line);
+            } else {
+                self.put(grid, x, y + offset, line);
+            }
+            offset += step;
+        }
+    }
+
+    fn put_rule(&self, grid: &mut [Cell], rule: &RuleCommand) {
+        self.put_line(grid, rule.x, rule.y, rule.length, rule.horizontal);
+    }
+
+    fn put_rect(&self, grid: &mut [Cell], rect: &RectCommand) {
+        let right = rect.x + rect.width.saturating_sub(1) as i32;
+        let bottom = rect.y + rect.height.saturating_sub(1) as i32;
+        if rect.fill {
+            let (cw, ch) = (
+                i32::from(self.cfg.cell_width_px.max(1)),
+                i32::from(self.cfg.cell_height_px.max(1)),
+            );
+            let block = Cell {
+                ch: '█',
+                ..Cell::BLANK
+            };
+            let mut y = rect.y;
+            while y <= bottom {
+                let mut x = rect.x;
+                while x <= right {
+                    self.put(grid, x, y, block);
+                    x += cw;
+                }
+                y += ch;
+            }
+            return;
+        }
+        let (width, height) = (rect.width.saturating_sub(1), rect.height.saturating_sub(1));
+        self.put_line(grid, rect.x, rect.y, width, true);
+        self.put_line(grid, rect.x, bottom, width, true);
+        self.put_line(grid, rect.x, rect.y, height, false);
+        self.put_line(grid, right, rect.y, height, false);
+        for (x, y, ch) in [
+            (rect.x, rect.y, '┌'),
+            (right, rect.y, '┐'),
+            (rect.x, bottom, '└'),
+            (right, bottom, '┘'),
+        ] {
+            self.put(grid, x, y, Cell { ch, ..Cell::BLANK });
+        }
+    }
+
+    fn put_chrome(&self, grid: &mut [Cell], chrome: &PageChromeCommand) {
+        let viewport = OverlaySize {
+            width: u32::from(self.cfg.columns) * u32::from(self.cfg.cell_width_px),
+            height: u32::from(self.cfg.rows) * u32::from(self.cfg.cell_height_px),
+        };
+        let chrome_cfg = &self.cfg.page_chrome;
+        let Some(bounds) = chrome_cfg.marker_bounds(chrome, viewport) else {
+            return;
+        };
+        let row_y = bounds.y + bounds.height as i32 / 2;
+        match chrome.kind {
+            PageChromeKind::Header | PageChromeKind::Footer => {
+                let x = if chrome.kind == PageChromeKind::Header {
+                    chrome_cfg.header_x
+                } else {
+                    chrome_cfg.footer_x
+                };
+                let step = i32::from(self.cfg.cell_width_px.max(1));
+                let text = chrome.resolve_text(&ChromeData::default());
+                for (i, ch) in text.as_deref().unwrap_or("").chars().enumerate() {
+                    self.put(grid, x + i as i32 * step, row_y, Cell { ch, ..Cell::BLANK });
+                }
+            }
+            PageChromeKind::Progress => {
+                let current = chrome.current.unwrap_or(0);
+                let total = chrome.total.unwrap_or(1).max(1);
+                let filled = (bounds.width as usize * current.min(total) / total) as i32;
+                let step = i32::from(self.cfg.cell_width_px.max(1));
+                let mut offset = 0;
+                while offset < bounds.width as i32 {
+                    let ch = if offset < filled { '█' } else { '░' };
+                    self.put(grid, bounds.x + offset, row_y, Cell { ch, ..Cell::BLANK });
+                    offset += step;
+                }
+            }
+        }
+    }
+
+    fn format(&self, grid: &[Cell]) -> String {
+        let columns = self.cfg.columns.max(1) as usize;
+        let mut out = String::with_capacity(grid.len() + self.cfg.rows as usize);
+        let mut rows: Vec<&[Cell]> = grid.chunks(columns).collect();
+        while rows
+            .last()
+            .is_some_and(|row| row.iter().all(|c| *c == Cell::BLANK))
+        {
+            rows.pop();
+        }
+        for (i, row) in rows.iter().enumerate() {
+            if i > 0 {
+                out.push('\n');
+            }
+            let used = row
+                .iter()
+                .rposition(|c| *c != Cell::BLANK)
+                .map_or(0, |p| p + 1);
+            let mut styled = (false, false);
+            for cell in &row[..used] {
+                if self.cfg.ansi && (cell.bold, cell.italic) != styled {
+                    out.push_str(ANSI_RESET);
+                    if cell.bold {
+                        out.push_str(ANSI_BOLD);
+                    }
+                    if cell.italic {
+                        out.push_str(ANSI_ITALIC);
+                    }
+                    styled = (cell.bold, cell.italic);
+                }
+                out.push(cell.ch);
+            }
+            if styled != (false, false) {
+                out.push_str(ANSI_RESET);
+            }
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mu_epub_render::{BlockRole, FontSynthesis, JustifyMode, ResolvedTextStyle};
+
+    fn text(x: i32, baseline_y: i32, text: &str, weight: u16, italic: bool) -> DrawCommand {
+        DrawCommand::Text(TextCommand {
+            x,
+            baseline_y,
+            text: text.to_string(),
+            font_id: None,
+            style: ResolvedTextStyle {
+                font_id: None,
+                family: "monospace".to_string(),
+                weight,
+                italic,
+                size_px: 13.0,
+                line_height: 1.2,
+                letter_spacing: 0.0,
+                role: BlockRole::Body,
+                justify_mode: JustifyMode::None,
+                synthesis: FontSynthesis::default(),
+            },
+        })
+    }
+
+    fn page(commands: Vec<DrawCommand>) -> RenderPage {
+        let mut page = RenderPage::new(1);
+        for cmd in commands {
+            page.push_content_command(cmd);
+        }
+        page.sync_commands();
+        page
+    }
+
+    fn plain() -> TerminalConfig {
+        TerminalConfig {
+            columns: 12,
+            rows: 4,
+            ansi: false,
+            ..TerminalConfig::default()
+        }
+    }
+
+    #[test]
+    fn maps_text_pixels_to_cells() {
+        let page = page(vec![
+            text(0, 14, "Hello", 400, false),
+            text(16, 46, "world", 400, false),
+        ]);
+        let out = TerminalRenderer::new(plain()).render_page(&page);
+        assert_eq!(out, "Hello\n\n  world");
+    }
+
+    #[test]
+    fn draws_rules_and_boxes() {
+        let page = page(vec![
+            DrawCommand::Rule(RuleCommand {
+                x: 0,
+                y: 0,
+                length: 24,
+                thickness: 1,
+                horizontal: true,
+            }),
+            DrawCommand::Rect(RectCommand {
+                x: 0,
+                y: 16,
+                width: 24,
+                height: 48,
+                fill: false,
+            }),
+        ]);
+        let out = TerminalRenderer::new(plain()).render_page(&page);
+        assert_eq!(out, "────\n┌─┐\n│ │\n└─┘");
+    }
+
+    #[test]
+    fn wraps_styled_runs_in_ansi_escapes() {
+        let page = page(vec![
+            text(0, 14, "ab", 700, false),
+            text(16, 14, "c", 400, true),
+        ]);
+        let out = TerminalRenderer::new(TerminalConfig {
+            ansi: true,
+            ..plain()
+        })
+        .render_page(&page);
+        assert_eq!(out, "\x1b[0m\x1b[1mab\x1b[0m\x1b[3mc\x1b[0m");
+    }
+}
@@ -0,0 +1,414 @@
+//! Wire primitives shared by [`page_codec`](crate::page_codec) and
+//! [`recording`](crate::recording).
+//!
+//! Integers are LEB128 varints with signed values zigzag-encoded, floats are
+//! little-endian, and strings are length-prefixed UTF-8. Optional `u32`s shift
+//! values up by one so that 0 encodes `None`; optional `usize`s, which can
+//! span the full `u64` range, carry a presence flag instead. Repeated strings and
+//! styles go through per-message tables and are referenced by index.
+
+use mu_epub::ComputedTextStyle;
+
+use crate::render_ir::ResolvedTextStyle;
+
+/// Byte sink for [`Writer`].
+pub(crate) trait Sink {
+    fn put(&mut self, bytes: &[u8]);
+}
+
+impl Sink for Vec<u8> {
+    fn put(&mut self, bytes: &[u8]) {
+        self.extend_from_slice(bytes);
+    }
+}
+
+impl<S: Sink + ?Sized> Sink for &mut S {
+    fn put(&mut self, bytes: &[u8]) {
+        (**self).put(bytes);
+    }
+}
+
+/// Sink that only counts bytes, for sizing output without allocating it.
+pub(crate) struct Counter(pub(crate) usize);
+
+impl Sink for Counter {
+    fn put(&mut self, bytes: &[u8]) {
+        self.0 += bytes.len();
+    }
+}
+
+/// Decoding error common to both formats.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum CodecError {
+    /// Input ended early.
+    Truncated,
+    /// Input is structurally invalid.
+    Invalid(&'static str),
+}
+
+/// Primitive encoder over a [`Sink`].
+pub(crate) struct Writer<S> {
+    out: S,
+}
+
+impl<S: Sink> Writer<S> {
+    pub(crate) fn new(out: S) -> Self {
+        Self { out }
+    }
+
+    pub(crate) fn into_inner(self) -> S {
+        self.out
+    }
+
+    pub(crate) fn bytes(&mut self, bytes: &[u8]) {
+        self.out.put(bytes);
+    }
+
+    pub(crate) fn u8(&mut self, v: u8) {
+        self.out.put(&[v]);
+    }
+
+    pub(crate) fn f32(&mut self, v: f32) {
+        self.out.put(&v.to_le_bytes());
+    }
+
+    pub(crate) fn uint(&mut self, mut v: u64) {
+        let mut buf = [0u8; 10];
+        let mut len = 0;
+        loop {
+            let byte = (v & 0x7f) as u8;
+            v >>= 7;
+            if v == 0 {
+                buf[len] = byte;
+                len += 1;
+                break;
+            }
+            buf[len] = byte | 0x80;
+            len += 1;
+        }
+        self.out.put(&buf[..len]);
+    }
+
+    pub(crate) fn int(&mut self, v: i64) {
+        self.uint(((v << 1) ^ (v >> 63)) as u64);
+    }
+
+    pub(crate) fn opt_usize(&mut self, v: Option<usize>) {
+        match v {
+            Some(v) => {
+                self.u8(1);
+                self.uint(v as u64);
+            }
+            None => self.u8(0),
+        }
+    }
+
+    pub(crate) fn opt_u32(&mut self, v: Option<u32>) {
+        self.uint(v.map_or(0, |v| u64::from(v) + 1));
+    }
+
+    pub(crate) fn str(&mut self, s: &str) {
+        self.uint(s.len() as u64);
+        self.out.put(s.as_bytes());
+    }
+
+    pub(crate) fn opt_str(&mut self, s: Option<&str>) {
+        match s {
+            Some(s) => {
+                self.u8(1);
+                self.str(s);
+            }
+            None => self.u8(0),
+        }
+    }
+
+    /// Write `strings` as a count followed by each string.
+    pub(crate) fn string_table(&mut self, strings: &StringTable<'_>) {
+        self.uint(strings.entries.len() as u64);
+        for s in &strings.entries {
+            self.str(s);
+        }
+    }
+}
+
+/// Encoder-side string table, in first-seen order.
+pub(crate) struct StringTable<'a> {
+    entries: Vec<&'a str>,
+}
+
+impl<'a> StringTable<'a> {
+    pub(crate) fn new() -> Self {
+        Self {
+            entries: Vec::with_capacity(4),
+        }
+    }
+
+    pub(crate) fn intern(&mut self, s: &'a str) {
+        if !self.entries.contains(&s) {
+            self.entries.push(s);
+        }
+    }
+
+    pub(crate) fn index(&self, s: &str) -> usize {
+        self.entries.iter().position(|t| *t == s).unwrap_or(0)
+    }
+}
+
+/// Style types that can be deduplicated into a style table.
+pub(crate) trait TableStyle {
+    /// Table identity, comparing floats bit for bit.
+    fn same_style(&self, other: &Self) -> bool;
+}
+
+impl TableStyle for ResolvedTextStyle {
+    /// Justification varies per line and stays on commands.
+    fn same_style(&self, other: &Self) -> bool {
+        self.font_id == other.font_id
+            && self.family == other.family
+            && self.weight == other.weight
+            && self.italic == other.italic
+            && self.size_px.to_bits() == other.size_px.to_bits()
+            && self.line_height.to_bits() == other.line_height.to_bits()
+            && self.letter_spacing.to_bits() == other.letter_spacing.to_bits()
+            && self.role == other.role
+    }
+}
+
+impl TableStyle for ComputedTextStyle {
+    fn same_style(&self, other: &Self) -> bool {
+        self.family_stack == other.family_stack
+            && self.weight == other.weight
+            && self.italic == other.italic
+            && self.size_px.to_bits() == other.size_px.to_bits()
+            && self.line_height.to_bits() == other.line_height.to_bits()
+            && self.letter_spacing.to_bits() == other.letter_spacing.to_bits()
+            && self.block_role == other.block_role
+            && self.language == other.language
+    }
+}
+
+/// Encoder-side style table, in first-seen order.
+pub(crate) struct StyleTable<'a, T> {
+    entries: Vec<&'a T>,
+}
+
+impl<'a, T: TableStyle> StyleTable<'a, T> {
+    pub(crate) fn new() -> Self {
+        Self {
+            entries: Vec::with_capacity(4),
+        }
+    }
+
+    /// Add `style`, returning whether it was not already present.
+    pub(crate) fn insert(&mut self, style: &'a T) -> bool {
+        if self.entries.iter().any(|s| s.same_style(style)) {
+            return false;
+        }
+        self.entries.push(style);
+        true
+    }
+
+    pub(crate) fn index(&self, style: &T) -> usize {
+        self.entries
+            .iter()
+            .position(|s| s.same_style(style))
+            .unwrap_or(0)
+    }
+
+    pub(crate) fn entries(&self) -> &[&'a T] {
+        &self.entries
+    }
+}
+
+/// Primitive decoder over a byte slice, holding the decoded string table.
+pub(crate) struct Reader<'a> {
+    input: &'a [u8],
+    strings: Vec<String>,
+}
+
+impl<'a> Reader<'a> {
+    pub(crate) fn new(input: &'a [u8]) -> Self {
+        Self {
+            input,
+            strings: Vec::with_capacity(0),
+        }
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.input.is_empty()
+    }
+
+    pub(crate) fn take(&mut self, n: usize) -> Result<&'a [u8], CodecError> {
+        if self.input.len() < n {
+            return Err(CodecError::Truncated);
+        }
+        let (head, tail) = self.input.split_at(n);
+        self.input = tail;
+        Ok(head)
+    }
+
+    pub(crate) fn u8(&mut self) -> Result<u8, CodecError> {
+        Ok(self.take(1)?[0])
+    }
+
+    pub(crate) fn flag(&mut self) -> Result<bool, CodecError> {
+        match self.u8()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(CodecError::Invalid("flag")),
+        }
+    }
+
+    pub(crate) fn f32(&mut self) -> Result<f32, CodecError> {
+        let mut bytes = [0u8; 4];
+        bytes.copy_from_slice(self.take(4)?);
+        Ok(f32::from_le_bytes(bytes))
+    }
+
+    pub(crate) fn uint(&mut self) -> Result<u64, CodecError> {
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = self.u8()?;
+            value |= u64::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(CodecError::Invalid("varint overflow"))
+    }
+
+    pub(crate) fn int(&mut self) -> Result<i64, CodecError> {
+        let v = self.uint()?;
+        Ok((v >> 1) as i64 ^ -((v & 1) as i64))
+    }
+
+    pub(crate) fn usize(&mut self) -> Result<usize, CodecError> {
+        usize::try_from(self.uint()?).map_err(|_| CodecError::Invalid("length"))
+    }
+
+    pub(crate) fn u16(&mut self) -> Result<u16, CodecError> {
+        u16::try_from(self.uint()?).map_err(|_| CodecError::Invalid("u16"))
+    }
+
+    pub(crate) fn u32(&mut self) -> Result<u32, CodecError> {
+        u32::try_from(self.uint()?).map_err(|_| CodecError::Invalid("u32"))
+    }
+
+    pub(crate) fn i32(&mut self) -> Result<i32, CodecError> {
+        i32::try_from(self.int()?).map_err(|_| CodecError::Invalid("i32"))
+    }
+
+    pub(crate) fn opt_usize(&mut self) -> Result<Option<usize>, CodecError> {
+        Ok(if self.flag()? {
+            Some(self.usize()?)
+        } else {
+            None
+        })
+    }
+
+    pub(crate) fn opt_u32(&mut self) -> Result<Option<u32>, CodecError> {
+        Ok(match self.uint()? {
+            0 => None,
+            v => Some(u32::try_from(v - 1).map_err(|_| CodecError::Invalid("u32"))?),
+        })
+    }
+
+    /// Element count, bounded by remaining input so corrupt counts cannot
+    /// trigger huge allocations.
+    pub(crate) fn count(&mut self) -> Result<usize, CodecError> {
+        let count = self.usize()?;
+        if count > self.input.len() {
+            return Err(CodecError::Truncated);
+        }
+        Ok(count)
+    }
+
+    pub(crate) fn string(&mut self) -> Result<String, CodecError> {
+        let len = self.usize()?;
+        let bytes = self.take(len)?;
+        core::str::from_utf8(bytes)
+            .map(str::to_string)
+            .map_err(|_| CodecError::Invalid("utf-8"))
+    }
+
+    pub(crate) fn opt_string(&mut self) -> Result<Option<String>, CodecError> {
+        Ok(if self.flag()? {
+            Some(self.string()?)
+        } else {
+            None
+        })
+    }
+
+    /// Read a table written by [`Writer::string_table`].
+    pub(crate) fn string_table(&mut self) -> Result<(), CodecError> {
+        let count = self.count()?;
+        self.strings = Vec::with_capacity(count);
+        for _ in 0..count {
+            let s = self.string()?;
+            self.strings.push(s);
+        }
+        Ok(())
+    }
+
+    pub(crate) fn string_at(&self, index: usize) -> Result<String, CodecError> {
+        self.strings
+            .get(index)
+            .cloned()
+            .ok_or(CodecError::Invalid("string index"))
+    }
+
+    pub(crate) fn table_string(&mut self) -> Result<String, CodecError> {
+        let index = self.usize()?;
+        self.string_at(index)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn primitives_round_trip_at_extremes() {
+        let mut writer = Writer::new(Vec::with_capacity(64));
+        for v in [0, 127, 128, u64::MAX] {
+            writer.uint(v);
+        }
+        for v in [0, -1, 1, i64::MIN, i64::MAX] {
+            writer.int(v);
+        }
+        writer.opt_usize(None);
+        writer.opt_usize(Some(usize::MAX));
+        writer.opt_u32(Some(u32::MAX));
+        writer.opt_str(Some("ünïcode"));
+        let bytes = writer.into_inner();
+
+        let mut counter = Writer::new(Counter(0));
+        counter.bytes(&bytes);
+        assert_eq!(counter.into_inner().0, bytes.len());
+
+        let mut reader = Reader::new(&bytes);
+        for v in [0, 127, 128, u64::MAX] {
+            assert_eq!(reader.uint(), Ok(v));
+        }
+        for v in [0, -1, 1, i64::MIN, i64::MAX] {
+            assert_eq!(reader.int(), Ok(v));
+        }
+        assert_eq!(reader.opt_usize(), Ok(None));
+        assert_eq!(reader.opt_usize(), Ok(Some(usize::MAX)));
+        assert_eq!(reader.opt_u32(), Ok(Some(u32::MAX)));
+        assert_eq!(reader.opt_string(), Ok(Some("ünïcode".to_string())));
+        assert!(reader.is_empty());
+        assert_eq!(reader.u8(), Err(CodecError::Truncated));
+    }
+
+    #[test]
+    fn corrupt_counts_and_indices_are_rejected() {
+        let mut reader = Reader::new(&[0xff, 0x01]);
+        assert_eq!(reader.count(), Err(CodecError::Truncated));
+        let mut reader = Reader::new(&[3]);
+        assert_eq!(
+            reader.table_string(),
+            Err(CodecError::Invalid("string index"))
+        );
+    }
+}
@@ -0,0 +1,240 @@
+use mu_epub::{BlockRole, FontSynthesis};
+
+use crate::render_ir::{JustifyMode, ResolvedTextStyle};
+use crate::render_layout::{measure_text, TextMeasurer};
+use crate::render_profile::PaginationProfile;
+
+/// Reference resolution of a CSS pixel.
+pub const CSS_PX_PER_INCH: f32 = 96.0;
+/// Shortest comfortable line, in average characters.
+pub const MIN_MEASURE_CHARS: f32 = 45.0;
+/// Longest comfortable line, in average characters.
+pub const MAX_MEASURE_CHARS: f32 = 75.0;
+
+const POINTS_PER_INCH: f32 = 72.0;
+const MM_PER_INCH: f32 = 25.4;
+const DEFAULT_SIDE_MARGIN_MM: f32 = 4.0;
+const DEFAULT_TOP_MARGIN_MM: f32 = 5.0;
+const DEFAULT_BOTTOM_MARGIN_MM: f32 = 5.0;
+/// Lower-case sample with roughly English letter frequencies, used to
+/// estimate the average advance of body text.
+const MEASURE_SAMPLE: &str = "the quick brown fox jumps over the lazy dog";
+
+/// Physical display geometry used to convert print and CSS units to device px.
+///
+/// Stylesheets size text in CSS px (1/96 in) and points (1/72 in); a
+/// 300 ppi panel needs about three device pixels per CSS pixel for text to
+/// come out at its intended physical size.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DisplayProfile {
+    /// Panel width in device px.
+    pub width_px: i32,
+    /// Panel height in device px.
+    pub height_px: i32,
+    /// Pixel density in dots per inch.
+    pub dpi: f32,
+}
+
+/// Page margins in device px.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DisplayMargins {
+    /// Left margin.
+    pub left: i32,
+    /// Top margin.
+    pub top: i32,
+    /// Right margin.
+    pub right: i32,
+    /// Bottom margin.
+    pub bottom: i32,
+}
+
+impl DisplayProfile {
+    /// Profile for a `width_px` x `height_px` panel at `dpi`.
+    ///
+    /// Non-positive or non-finite `dpi` falls back to [`CSS_PX_PER_INCH`].
+    pub fn new(width_px: i32, height_px: i32, dpi: f32) -> Self {
+        let dpi = if dpi.is_finite() && dpi > 0.0 {
+            dpi
+        } else {
+            CSS_PX_PER_INCH
+        };
+        Self {
+            width_px,
+            height_px,
+            dpi,
+        }
+    }
+
+    /// Device px per CSS px.
+    pub fn css_px_scale(&self) -> f32 {
+        self.dpi / CSS_PX_PER_INCH
+    }
+
+    /// Convert CSS px to device px.
+    pub fn css_px_to_px(&self, css_px: f32) -> f32 {
+        css_px * self.css_px_scale()
+    }
+
+    /// Convert points to device px.
+    pub fn pt_to_px(&self, pt: f32) -> f32 {
+        pt * self.dpi / POINTS_PER_INCH
+    }
+
+    /// Convert millimetres to device px.
+    pub fn mm_to_px(&self, mm: f32) -> f32 {
+        mm * self.dpi / MM_PER_INCH
+    }
+
+    /// Physically sized default margins: 4 mm at the sides, 5 mm top and
+    /// bottom.
+    pub fn default_margins(&self) -> DisplayMargins {
+        let side = self.mm_to_px(DEFAULT_SIDE_MARGIN_MM).round() as i32;
+        DisplayMargins {
+            left: side,
+            top: self.mm_to_px(DEFAULT_TOP_MARGIN_MM).round() as i32,
+            right: side,
+            bottom: self.mm_to_px(DEFAULT_BOTTOM_MARGIN_MM).round() as i32,
+        }
+    }
+
+    /// Margins whose content width holds 45–75 characters of
+    /// `avg_char_width_px`.
+    ///
+    /// Starts from [`Self::default_margins`]; on wide panels the side margins
+    /// grow until lines fit [`MAX_MEASURE_CHARS`], and on narrow panels they
+    /// shrink (down to zero) toward [`MIN_MEASURE_CHARS`]. Top and bottom
+    /// margins are left alone.
+    pub fn fit_margins(&self, avg_char_width_px: f32) -> DisplayMargins {
+        let mut margins = self.default_margins();
+        if !(avg_char_width_px.is_finite() && avg_char_width_px > 0.0) {
+            return margins;
+        }
+        let content = (self.width_px - margins.left - margins.right) as f32;
+        let target = content.clamp(
+            MIN_MEASURE_CHARS * avg_char_width_px,
+            MAX_MEASURE_CHARS * avg_char_width_px,
+        );
+        if target != content {
+            let side = ((self.width_px as f32 - target) / 2.0).round().max(0.0) as i32;
+            margins.left = side;
+            margins.right = side;
+        }
+        margins
+    }
+
+    /// Average character advance of body text at `font_size_px`.
+    ///
+    /// Uses `measurer` when given, else the layout engine's built-in
+    /// estimate.
+    pub fn average_char_width_px(
+        &self,
+        font_size_px: f32,
+        measurer: Option<&dyn TextMeasurer>,
+    ) -> f32 {
+        let style = ResolvedTextStyle {
+            font_id: None,
+            family: "serif".to_string(),
+            weight: 400,
+            italic: false,
+            size_px: font_size_px,
+            line_height: 1.0,
+            letter_spacing: 0.0,
+            role: BlockRole::Body,
+            justify_mode: JustifyMode::None,
+            synthesis: FontSynthesis::default(),
+        };
+        let width = match measurer {
+            Some(measurer) => measurer.measure_text_px(MEASURE_SAMPLE, &style),
+            None => measure_text(MEASURE_SAMPLE, &style),
+        };
+        width / MEASURE_SAMPLE.chars().count() as f32
+    }
+
+    /// Pagination profile with a `base_font_pt` body size, stylesheet units
+    /// scaled to this display, and auto-fitted margins.
+    pub fn pagination_profile(
+        &self,
+        name: &str,
+        base_font_pt: f32,
+        measurer: Option<&dyn TextMeasurer>,
+    ) -> PaginationProfile {
+        let scale = self.css_px_scale();
+        let base_font_px = self.pt_to_px(base_font_pt);
+        let margins = self.fit_margins(self.average_char_width_px(base_font_px, measurer));
+        let mut profile = PaginationProfile::for_display(name, self.width_px, self.height_px)
+            .with_margins(margins.left, margins.top, margins.right, margins.bottom)
+            .with_base_font_size(base_font_px);
+        for hints in [
+            &mut profile.prep.style.hints,
+            &mut profile.prep.layout_hints,
+        ] {
+            hints.css_px_scale = scale;
+            hints.min_font_size_px *= scale;
+            hints.max_font_size_px *= scale;
+        }
+        let layout = &mut profile.layout;
+        layout.min_line_height_px = (layout.min_line_height_px as f32 * scale).round() as i32;
+        layout.max_line_height_px = (layout.max_line_height_px as f32 * scale).round() as i32;
+        profile
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Monospace(f32);
+
+    impl TextMeasurer for Monospace {
+        fn measure_text_px(&self, text: &str, _style: &ResolvedTextStyle) -> f32 {
+            text.chars().count() as f32 * self.0
+        }
+    }
+
+    #[test]
+    fn converts_units_at_display_density() {
+        let display = DisplayProfile::new(1404, 1872, 300.0);
+        assert!((display.pt_to_px(12.0) - 50.0).abs() < 0.01);
+        assert!((display.css_px_to_px(96.0) - 300.0).abs() < 0.01);
+        assert!((display.mm_to_px(25.4) - 300.0).abs() < 0.01);
+        assert_eq!(DisplayProfile::new(10, 10, 0.0).dpi, CSS_PX_PER_INCH);
+    }
+
+    #[test]
+    fn fit_margins_clamps_measure() {
+        let display = DisplayProfile::new(1404, 1872, 300.0);
+        let wide = display.fit_margins(10.0);
+        assert_eq!(display.width_px - wide.left - wide.right, 750);
+        assert_eq!(wide.top, display.default_margins().top);
+
+        let narrow = DisplayProfile::new(400, 300, 96.0);
+        let margins = narrow.fit_margins(8.4);
+        assert_eq!(
+            margins,
+            DisplayMargins {
+                left: 11,
+                top: 19,
+                right: 11,
+                bottom: 19
+            }
+        );
+
+        assert_eq!(narrow.fit_margins(10.0).left, 0);
+
+        let comfortable = narrow.fit_margins(6.0);
+        assert_eq!(comfortable, narrow.default_margins());
+    }
+
+    #[test]
+    fn pagination_profile_uses_measurer_and_scales_hints() {
+        let display = DisplayProfile::new(1404, 1872, 300.0);
+        let profile = display.pagination_profile("custom", 12.0, Some(&Monospace(12.0)));
+        assert_eq!(
+            profile.layout.display_width - profile.layout.margin_left - profile.layout.margin_right,
+            900
+        );
+        assert!((profile.prep.layout_hints.base_font_size_px - 50.0).abs() < 0.01);
+        assert!((profile.prep.style.hints.css_px_scale - 3.125).abs() < 0.001);
+        assert!(profile.prep.layout_hints.max_font_size_px > 50.0);
+    }
+}
@@ -13,22 +13,57 @@
     )
 )]
 
+mod codec;
+mod display_profile;
+mod page_codec;
+mod page_map;
+mod page_validation;
+mod quick_start;
+mod recording;
+mod render_cache;
 mod render_engine;
 mod render_ir;
 mod render_layout;
+mod render_profile;
 
-pub use mu_epub::BlockRole;
+pub use display_profile::{
+    DisplayMargins, DisplayProfile, CSS_PX_PER_INCH, MAX_MEASURE_CHARS, MIN_MEASURE_CHARS,
+};
+pub use mu_epub::{BlockRole, FontSynthesis};
+pub use page_codec::{
+    decode_page, encode_page, encode_page_into, encoded_page_size, PageCodecError,
+    PAGE_CODEC_VERSION,
+};
+pub use page_map::PageMap;
+pub use page_validation::{
+    clamp_page, validate_page, CommandLayer, OutputValidation, PageIssue, PageIssueKind,
+};
+pub use quick_start::{
+    open_first_page, open_first_page_file, FirstPageBudget, FirstPageError, FirstPageErrorKind,
+    FirstPagePhase, PhaseTiming, ResumeState,
+};
+pub use recording::{
+    decode_recording, encode_recording, ChapterRecording, RecordingError, RECORDING_VERSION,
+};
+pub use render_cache::{RenderCache, RenderCacheKey};
 pub use render_engine::{
     CancelToken, LayoutSession, NeverCancel, PageRange, RenderCacheStore, RenderConfig,
     RenderDiagnostic, RenderEngine, RenderEngineError, RenderEngineOptions, RenderPageIter,
     RenderPageStreamIter,
 };
 pub use render_ir::{
-    DitherMode, DrawCommand, FloatSupport, GrayscaleMode, HangingPunctuationConfig,
-    HyphenationConfig, HyphenationMode, JustificationConfig, JustifyMode, ObjectLayoutConfig,
-    OverlayComposer, OverlayContent, OverlayItem, OverlayRect, OverlaySize, OverlaySlot,
-    PageAnnotation, PageChromeCommand, PageChromeConfig, PageChromeKind, PageChromeTextStyle,
-    PageMeta, PageMetrics, PaginationProfileId, RectCommand, RenderIntent, RenderPage,
-    ResolvedTextStyle, RuleCommand, SvgMode, TextCommand, TypographyConfig, WidowOrphanControl,
-};
-pub use render_layout::{LayoutConfig, LayoutEngine, SoftHyphenPolicy};
+    BlockquoteConfig, ChromeData, ChromeField, ChromeSegment, ChromeSegments, DitherMode,
+    DrawCommand, DropCapConfig, FloatSupport, GrayscaleMode, HangingPunctuationConfig,
+    HeadingSpacing, HyphenationConfig, HyphenationMode, ImageDecodeHint, JustificationConfig,
+    JustificationStrategy, JustifyMode, KeepWithNext, LayoutQuality, NoBreakConfig,
+    ObjectLayoutConfig, OverlayComposer, OverlayContent, OverlayItem, OverlayRect, OverlaySize,
+    OverlaySlot, PageAnnotation, PageChromeCommand, PageChromeConfig, PageChromeKind,
+    PageChromeTextStyle, PageMeta, PageMetrics, PaginationProfileId, RectCommand, RenderIntent,
+    RenderPage, RenderPriority, ResolvedTextStyle, RuleCommand, SvgMode, TextCommand,
+    TypographyConfig, WidowOrphanControl, WordBox, IMAGE_ANNOTATION, PRINT_PAGE_ANNOTATION,
+};
+pub use render_layout::{
+    LayoutConfig, LayoutEngine, LayoutMode, ScrollSurface, SnippetBox, SnippetLayout,
+    SoftHyphenPolicy, TextMeasurer, VerticalMetrics,
+};
+pub use render_profile::{PaginationProfile, PaginationProfileRegistry};
@@ -0,0 +1,597 @@
+//! Compact binary encoding for [`RenderPage`] command streams.
+//!
+//! Intended for shipping laid-out pages to a separate display processor over
+//! a narrow link (SPI/UART). Layout:
+//!
+//! ```text
+//! version u8
+//! page_number, metrics
+//! string table    (count, then len-prefixed UTF-8)
+//! style table     (count, then styles referencing the string table)
+//! 3 layers        (content, chrome, overlay: count, then commands)
+//! annotations     (count, then kind string index + optional value)
+//! ```
+//!
+//! Integers are LEB128 varints with signed values zigzag-encoded (see the
+//! shared `codec` module), and text baselines are stored as deltas from the
+//! previous text command, so a typical text page encodes to well under 2 KB.
+//! Overlay items are composed per view by the app and are not encoded;
+//! neither are word boxes, which only the host that highlights needs.
+
+use mu_epub::{BlockRole, FontSynthesis};
+
+use crate::codec::{CodecError, Counter, Reader, Sink, StringTable, StyleTable, Writer};
+use crate::render_ir::{
+    DrawCommand, JustifyMode, LayoutQuality, PageAnnotation, PageChromeCommand, PageChromeKind,
+    PageMetrics, RectCommand, RenderPage, ResolvedTextStyle, RuleCommand, TextCommand,
+};
+
+/// Current page encoding version; bumped on incompatible layout changes.
+pub const PAGE_CODEC_VERSION: u8 = 3;
+
+const TAG_TEXT: u8 = 0;
+const TAG_RULE: u8 = 1;
+const TAG_RECT: u8 = 2;
+const TAG_CHROME: u8 = 3;
+
+const TEXT_HAS_FONT_ID: u8 = 1 << 0;
+const TEXT_JUSTIFIED: u8 = 1 << 1;
+const TEXT_LETTER_SPACED: u8 = 1 << 2;
+
+const STYLE_HAS_FONT_ID: u8 = 1 << 0;
+const STYLE_ITALIC: u8 = 1 << 1;
+const STYLE_SYNTHETIC_BOLD: u8 = 1 << 2;
+const STYLE_SYNTHETIC_ITALIC: u8 = 1 << 3;
+
+/// Page decoding error.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum PageCodecError {
+    /// Input ended before the page was complete.
+    Truncated,
+    /// Encoded with a version this build cannot read.
+    UnsupportedVersion(u8),
+    /// Input is structurally invalid.
+    Invalid(&'static str),
+}
+
+impl core::fmt::Display for PageCodecError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::Truncated => write!(f, "encoded page truncated"),
+            Self::UnsupportedVersion(v) => write!(f, "unsupported page codec version: {}", v),
+            Self::Invalid(reason) => write!(f, "invalid encoded page: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for PageCodecError {}
+
+impl From<CodecError> for PageCodecError {
+    fn from(err: CodecError) -> Self {
+        match err {
+            CodecError::Truncated => Self::Truncated,
+            CodecError::Invalid(reason) => Self::Invalid(reason),
+        }
+    }
+}
+
+/// Encode `page` into a new buffer.
+pub fn encode_page(page: &RenderPage) -> Vec<u8> {
+    let mut out = Vec::with_capacity(encoded_page_size(page));
+    encode_page_into(page, &mut out);
+    out
+}
+
+/// Append the encoding of `page` to `out`.
+pub fn encode_page_into(page: &RenderPage, out: &mut Vec<u8>) {
+    Encoder::new(page, out).page(page);
+}
+
+/// Exact encoded size of `page` in bytes, computed without allocating output.
+pub fn encoded_page_size(page: &RenderPage) -> usize {
+    let mut counter = Counter(0);
+    Encoder::new(page, &mut counter).page(page);
+    counter.0
+}
+
+/// Decode a page produced by [`encode_page`].
+///
+/// The whole input must be consumed; trailing bytes are an error.
+pub fn decode_page(bytes: &[u8]) -> Result<RenderPage, PageCodecError> {
+    let mut decoder = Decoder {
+        input: Reader::new(bytes),
+        styles: Vec::with_capacity(0),
+    };
+    let page = decoder.page()?;
+    if !decoder.input.is_empty() {
+        return Err(PageCodecError::Invalid("trailing bytes"));
+    }
+    Ok(page)
+}
+
+fn layers(page: &RenderPage) -> [&[DrawCommand]; 3] {
+    [
+        &page.content_commands,
+        &page.chrome_commands,
+        &page.overlay_commands,
+    ]
+}
+
+struct Encoder<'a, S: Sink + ?Sized> {
+    out: Writer<&'a mut S>,
+    strings: StringTable<'a>,
+    styles: StyleTable<'a, ResolvedTextStyle>,
+    last_baseline: i32,
+}
+
+impl<'a, S: Sink + ?Sized> Encoder<'a, S> {
+    fn new(page: &'a RenderPage, out: &'a mut S) -> Self {
+        let mut encoder = Self {
+            out: Writer::new(out),
+            strings: StringTable::new(),
+            styles: StyleTable::new(),
+            last_baseline: 0,
+        };
+        for cmd in layers(page).into_iter().flatten() {
+            if let DrawCommand::Text(text) = cmd {
+                if encoder.styles.insert(&text.style) {
+                    encoder.strings.intern(&text.style.family);
+                }
+            }
+        }
+        for annotation in &page.annotations {
+            encoder.strings.intern(&annotation.kind);
+        }
+        encoder
+    }
+
+    fn page(&mut self, page: &RenderPage) {
+        self.out.u8(PAGE_CODEC_VERSION);
+        self.out.uint(page.page_number as u64);
+        self.metrics(&page.metrics);
+
+        self.out.string_table(&self.strings);
+        self.out.uint(self.styles.entries().len() as u64);
+        for i in 0..self.styles.entries().len() {
+            let style = self.styles.entries()[i];
+            self.style(style);
+        }
+
+        for layer in layers(page) {
+            self.out.uint(layer.len() as u64);
+            for cmd in layer {
+                self.command(cmd);
+            }
+        }
+
+        self.out.uint(page.annotations.len() as u64);
+        for annotation in &page.annotations {
+            self.out.uint(self.strings.index(&annotation.kind) as u64);
+            self.out.opt_str(annotation.value.as_deref());
+        }
+    }
+
+    fn metrics(&mut self, m: &PageMetrics) {
+        self.out.uint(m.chapter_index as u64);
+        self.out.uint(m.chapter_page_index as u64);
+        self.out.opt_usize(m.chapter_page_count);
+        self.out.opt_usize(m.global_page_index);
+        self.out.opt_usize(m.global_page_count_estimate);
+        self.out.f32(m.progress_chapter);
+        match m.progress_book {
+            Some(v) => {
+                self.out.u8(1);
+                self.out.f32(v);
+            }
+            None => self.out.u8(0),
+        }
+        let q = &m.quality;
+        self.out.int(i64::from(q.max_word_gap_px));
+        for count in [q.loose_lines, q.hyphenated_lines, q.widows, q.orphans] {
+            self.out.uint(u64::from(count));
+        }
+    }
+
+    fn style(&mut self, style: &ResolvedTextStyle) {
+        let mut flags = 0;
+        if style.font_id.is_some() {
+            flags |= STYLE_HAS_FONT_ID;
+        }
+        if style.italic {
+            flags |= STYLE_ITALIC;
+        }
+        if style.synthesis.bold {
+            flags |= STYLE_SYNTHETIC_BOLD;
+        }
+        if style.synthesis.italic {
+            flags |= STYLE_SYNTHETIC_ITALIC;
+        }
+        self.out.u8(flags);
+        if let Some(id) = style.font_id {
+            self.out.uint(u64::from(id));
+        }
+        self.out.uint(self.strings.index(&style.family) as u64);
+        self.out.uint(u64::from(style.weight));
+        self.out.f32(style.size_px);
+        self.out.f32(style.line_height);
+        self.out.f32(style.letter_spacing);
+        let (role, level) = style.role.code();
+        self.out.u8(role);
+        self.out.u8(level);
+    }
+
+    fn command(&mut self, cmd: &DrawCommand) {
+        match cmd {
+            DrawCommand::Text(text) => {
+                self.out.u8(TAG_TEXT);
+                self.out.uint(self.styles.index(&text.style) as u64);
+                let mut flags = 0;
+                if text.font_id.is_some() {
+                    flags |= TEXT_HAS_FONT_ID;
+                }
+                let (word_px, char_px) = match text.style.justify_mode {
+                    JustifyMode::None => (None, None),
+                    JustifyMode::InterWord { extra_px_total } => (Some(extra_px_total), None),
+                    JustifyMode::InterCharacter { extra_px_total } => (None, Some(extra_px_total)),
+                    JustifyMode::InterWordAndCharacter {
+                        word_px_total,
+                        char_px_total,
+                    } => (Some(word_px_total), Some(char_px_total)),
+                };
+                if word_px.is_some() {
+                    flags |= TEXT_JUSTIFIED;
+                }
+                if char_px.is_some() {
+                    flags |= TEXT_LETTER_SPACED;
+                }
+                self.out.u8(flags);
+                if let Some(id) = text.font_id {
+                    self.out.uint(u64::from(id));
+                }
+                for extra in word_px.into_iter().chain(char_px) {
+                    self.out.int(i64::from(extra));
+                }
+                self.out.int(i64::from(text.x));
+                self.out
+                    .int(i64::from(text.baseline_y) - i64::from(self.last_baseline));
+                self.last_baseline = text.baseline_y;
+                self.out.str(&text.text);
+            }
+            DrawCommand::Rule(rule) => {
+                self.out.u8(TAG_RULE);
+                self.out.int(i64::from(rule.x));
+                self.out.int(i64::from(rule.y));
+                self.out.uint(u64::from(rule.length));
+                self.out.uint(u64::from(rule.thickness));
+                self.out.u8(rule.horizontal as u8);
+            }
+            DrawCommand::Rect(rect) => {
+                self.out.u8(TAG_RECT);
+                self.out.int(i64::from(rect.x));
+                self.out.int(i64::from(rect.y));
+                self.out.uint(u64::from(rect.width));
+                self.out.uint(u64::from(rect.height));
+                self.out.u8(rect.fill as u8);
+            }
+            DrawCommand::PageChrome(chrome) => {
+                self.out.u8(TAG_CHROME);
+                self.out.u8(match chrome.kind {
+                    PageChromeKind::Header => 0,
+                    PageChromeKind::Footer => 1,
+                    PageChromeKind::Progress => 2,
+                });
+                self.out.opt_str(chrome.text.as_deref());
+                self.out.opt_usize(chrome.current);
+                self.out.opt_usize(chrome.total);
+            }
+        }
+    }
+}
+
+struct Decoder<'a> {
+    input: Reader<'a>,
+    styles: Vec<ResolvedTextStyle>,
+}
+
+impl<'a> Decoder<'a> {
+    fn page(&mut self) -> Result<RenderPage, PageCodecError> {
+        let version = self.input.u8()?;
+        if version != PAGE_CODEC_VERSION {
+            return Err(PageCodecError::UnsupportedVersion(version));
+        }
+        let mut page = RenderPage::new(self.input.usize()?);
+        page.metrics = self.metrics()?;
+
+        self.input.string_table()?;
+        let count = self.input.count()?;
+        self.styles = Vec::with_capacity(count);
+        for _ in 0..count {
+            let style = self.style()?;
+            self.styles.push(style);
+        }
+
+        let mut last_baseline = 0;
+        for layer in [
+            &mut page.content_commands,
+            &mut page.chrome_commands,
+            &mut page.overlay_commands,
+        ] {
+            let count = self.input.count()?;
+            layer.reserve(count);
+            for _ in 0..count {
+                layer.push(self.command(&mut last_baseline)?);
+            }
+        }
+
+        let count = self.input.count()?;
+        for _ in 0..count {
+            let kind = self.input.table_string()?;
+            let value = self.input.opt_string()?;
+            page.annotations.push(PageAnnotation { kind, value });
+        }
+        page.sync_commands();
+        Ok(page)
+    }
+
+    fn metrics(&mut self) -> Result<PageMetrics, PageCodecError> {
+        Ok(PageMetrics {
+            chapter_index: self.input.usize()?,
+            chapter_page_index: self.input.usize()?,
+            chapter_page_count: self.input.opt_usize()?,
+            global_page_index: self.input.opt_usize()?,
+            global_page_count_estimate: self.input.opt_usize()?,
+            progress_chapter: self.input.f32()?,
+            progress_book: if self.input.flag()? {
+                Some(self.input.f32()?)
+            } else {
+                None
+            },
+            quality: LayoutQuality {
+                max_word_gap_px: self.input.i32()?,
+                loose_lines: self.input.u16()?,
+                hyphenated_lines: self.input.u16()?,
+                widows: self.input.u16()?,
+                orphans: self.input.u16()?,
+            },
+        })
+    }
+
+    fn style(&mut self) -> Result<ResolvedTextStyle, PageCodecError> {
+        let flags = self.input.u8()?;
+        let font_id = if flags & STYLE_HAS_FONT_ID != 0 {
+            Some(self.input.u32()?)
+        } else {
+            None
+        };
+        let family = self.input.table_string()?;
+        let weight =
+            u16::try_from(self.input.uint()?).map_err(|_| PageCodecError::Invalid("weight"))?;
+        let size_px = self.input.f32()?;
+        let line_height = self.input.f32()?;
+        let letter_spacing = self.input.f32()?;
+        let (role, level) = (self.input.u8()?, self.input.u8()?);
+        let role =
+            BlockRole::from_code(role, level).ok_or(PageCodecError::Invalid("block role"))?;
+        Ok(ResolvedTextStyle {
+            font_id,
+            family,
+            weight,
+            italic: flags & STYLE_ITALIC != 0,
+            size_px,
+            line_height,
+            letter_spacing,
+            role,
+            justify_mode: JustifyMode::None,
+            synthesis: FontSynthesis {
+                bold: flags & STYLE_SYNTHETIC_BOLD != 0,
+                italic: flags & STYLE_SYNTHETIC_ITALIC != 0,
+            },
+        })
+    }
+
+    fn command(&mut self, last_baseline: &mut i32) -> Result<DrawCommand, PageCodecError> {
+        Ok(match self.input.u8()? {
+            TAG_TEXT => {
+                let index = self.input.usize()?;
+                let mut style = self
+                    .styles
+                    .get(index)
+                    .cloned()
+                    .ok_or(PageCodecError::Invalid("style index"))?;
+                let flags = self.input.u8()?;
+                let font_id = if flags & TEXT_HAS_FONT_ID != 0 {
+                    Some(self.input.u32()?)
+                } else {
+                    None
+                };
+                let word_px = if flags & TEXT_JUSTIFIED != 0 {
+                    Some(self.input.i32()?)
+                } else {
+                    None
+                };
+                let char_px = if flags & TEXT_LETTER_SPACED != 0 {
+                    Some(self.input.i32()?)
+                } else {
+                    None
+                };
+                style.justify_mode = match (word_px, char_px) {
+                    (None, None) => JustifyMode::None,
+                    (Some(extra_px_total), None) => JustifyMode::InterWord { extra_px_total },
+                    (None, Some(extra_px_total)) => JustifyMode::InterCharacter { extra_px_total },
+                    (Some(word_px_total), Some(char_px_total)) => {
+                        JustifyMode::InterWordAndCharacter {
+                            word_px_total,
+                            char_px_total,
+                        }
+                    }
+                };
+                let x = self.input.i32()?;
+                let baseline_y = last_baseline
+                    .checked_add(self.input.i32()?)
+                    .ok_or(PageCodecError::Invalid("baseline"))?;
+                *last_baseline = baseline_y;
+                DrawCommand::Text(TextCommand {
+                    x,
+                    baseline_y,
+                    text: self.input.string()?,
+                    font_id,
+                    style,
+                })
+            }
+            TAG_RULE => DrawCommand::Rule(RuleCommand {
+                x: self.input.i32()?,
+                y: self.input.i32()?,
+                length: self.input.u32()?,
+                thickness: self.input.u32()?,
+                horizontal: self.input.flag()?,
+            }),
+            TAG_RECT => DrawCommand::Rect(RectCommand {
+                x: self.input.i32()?,
+                y: self.input.i32()?,
+                width: self.input.u32()?,
+                height: self.input.u32()?,
+                fill: self.input.flag()?,
+            }),
+            TAG_CHROME => DrawCommand::PageChrome(PageChromeCommand {
+                kind: match self.input.u8()? {
+                    0 => PageChromeKind::Header,
+                    1 => PageChromeKind::Footer,
+                    2 => PageChromeKind::Progress,
+                    _ => return Err(PageCodecError::Invalid("chrome kind")),
+                },
+                text: self.input.opt_string()?,
+                current: self.input.opt_usize()?,
+                total: self.input.opt_usize()?,
+            }),
+            _ => return Err(PageCodecError::Invalid("command tag")),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn body_style(justify_mode: JustifyMode) -> ResolvedTextStyle {
+        ResolvedTextStyle {
+            font_id: Some(1),
+            family: "serif".to_string(),
+            weight: 400,
+            italic: false,
+            size_px: 16.0,
+            line_height: 1.4,
+            letter_spacing: 0.0,
+            role: BlockRole::Paragraph,
+            justify_mode,
+            synthesis: FontSynthesis::default(),
+        }
+    }
+
+    /// 24 justified lines of ~44 characters plus footer chrome.
+    fn text_page() -> RenderPage {
+        let mut page = RenderPage::new(7);
+        for line in 0..24 {
+            page.push_content_command(DrawCommand::Text(TextCommand {
+                x: 12,
+                baseline_y: 30 + line * 22,
+                text: "the quick brown fox jumps over the lazy dog.".to_string(),
+                font_id: Some(1),
+                style: body_style(match line % 3 {
+                    0 => JustifyMode::InterWord {
+                        extra_px_total: line % 9,
+                    },
+                    1 => JustifyMode::InterCharacter {
+                        extra_px_total: line % 7,
+                    },
+                    _ => JustifyMode::InterWordAndCharacter {
+                        word_px_total: 8,
+                        char_px_total: line % 5,
+                    },
+                }),
+            }));
+        }
+        page.push_chrome_command(DrawCommand::PageChrome(PageChromeCommand {
+            kind: PageChromeKind::Footer,
+            text: Some("Page 7".to_string()),
+            current: Some(7),
+            total: None,
+        }));
+        page.metrics.chapter_page_count = Some(12);
+        page.metrics.progress_chapter = 0.5;
+        page.metrics.quality.max_word_gap_px = 9;
+        page.metrics.quality.orphans = 1;
+        page.sync_commands();
+        page
+    }
+
+    #[test]
+    fn mixed_page_round_trips() {
+        let mut page = text_page();
+        let mut heading = body_style(JustifyMode::None);
+        heading.role = BlockRole::Heading(2);
+        heading.italic = true;
+        heading.font_id = None;
+        page.push_content_command(DrawCommand::Text(TextCommand {
+            x: -3,
+            baseline_y: 4,
+            text: "Über".to_string(),
+            font_id: None,
+            style: heading,
+        }));
+        page.push_content_command(DrawCommand::Rule(RuleCommand {
+            x: 10,
+            y: 600,
+            length: 200,
+            thickness: 1,
+            horizontal: true,
+        }));
+        page.push_overlay_command(DrawCommand::Rect(RectCommand {
+            x: 0,
+            y: 0,
+            width: 40,
+            height: 8,
+            fill: true,
+        }));
+        page.annotations.push(PageAnnotation {
+            kind: "anchor".to_string(),
+            value: Some("ch2".to_string()),
+        });
+        page.annotations.push(PageAnnotation {
+            kind: "anchor".to_string(),
+            value: None,
+        });
+        page.sync_commands();
+
+        let bytes = encode_page(&page);
+        assert_eq!(bytes.len(), encoded_page_size(&page));
+        assert_eq!(decode_page(&bytes), Ok(page));
+    }
+
+    #[test]
+    fn typical_text_page_fits_in_two_kilobytes() {
+        let size = encoded_page_size(&text_page());
+        assert!(size < 2048, "encoded size {}", size);
+    }
+
+    #[test]
+    fn rejects_corrupt_input() {
+        let bytes = encode_page(&text_page());
+        assert_eq!(
+            decode_page(&bytes[..bytes.len() - 1]),
+            Err(PageCodecError::Truncated)
+        );
+        let mut versioned = bytes.clone();
+        versioned[0] = PAGE_CODEC_VERSION + 1;
+        assert_eq!(
+            decode_page(&versioned),
+            Err(PageCodecError::UnsupportedVersion(PAGE_CODEC_VERSION + 1))
+        );
+        let mut trailing = bytes;
+        trailing.push(0);
+        assert!(matches!(
+            decode_page(&trailing),
+            Err(PageCodecError::Invalid(_))
+        ));
+    }
+}
@@ -0,0 +1,174 @@
+use mu_epub::ListStyleType;
+
+use crate::render_ir::{RenderPage, PRINT_PAGE_ANNOTATION};
+
+/// Display labels for rendered pages, for page indicators and go-to-page UI.
+///
+/// Pages are addressed by 0-based index in book order. When the book carries
+/// a print page-list, pages show the print label they were annotated with
+/// (see [`PRINT_PAGE_ANNOTATION`]); unannotated pages continue the preceding
+/// print page, and pages before the first print page are numbered in lower
+/// roman numerals like printed front matter. Books without a page-list get
+/// synthesized 1-based numbers.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PageMap {
+    print_labels: Vec<Option<String>>,
+    has_print_labels: bool,
+}
+
+impl PageMap {
+    /// Create an empty page map.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Build a page map from pages in book order.
+    pub fn from_pages<'a>(pages: impl IntoIterator<Item = &'a RenderPage>) -> Self {
+        let mut map = Self::new();
+        for page in pages {
+            map.push_page(page);
+        }
+        map
+    }
+
+    /// Append the next page in book order.
+    pub fn push_page(&mut self, page: &RenderPage) {
+        let label = page
+            .annotations
+            .iter()
+            .find(|annotation| annotation.kind == PRINT_PAGE_ANNOTATION)
+            .and_then(|annotation| annotation.value.as_deref())
+            .map(str::trim)
+            .filter(|label| !label.is_empty())
+            .map(str::to_string);
+        self.has_print_labels |= label.is_some();
+        self.print_labels.push(label);
+    }
+
+    /// Number of pages in the map.
+    pub fn len(&self) -> usize {
+        self.print_labels.len()
+    }
+
+    /// Whether the map has no pages.
+    pub fn is_empty(&self) -> bool {
+        self.print_labels.is_empty()
+    }
+
+    /// Whether labels come from the book's print page-list.
+    pub fn has_print_labels(&self) -> bool {
+        self.has_print_labels
+    }
+
+    /// Display label for the 0-based `page`, or `None` when out of range.
+    pub fn label_for(&self, page: usize) -> Option<String> {
+        if page >= self.len() {
+            return None;
+        }
+        if !self.has_print_labels {
+            return Some((page + 1).to_string());
+        }
+        let label = self.print_labels[..=page]
+            .iter()
+            .rev()
+            .find_map(|label| label.clone())
+            .unwrap_or_else(|| lower_roman(page + 1));
+        Some(label)
+    }
+
+    /// First 0-based page whose label matches `label`.
+    ///
+    /// Matching ignores surrounding whitespace and ASCII case, so `"XII"`
+    /// finds front-matter page `xii`.
+    pub fn page_for_label(&self, label: &str) -> Option<usize> {
+        let label = label.trim();
+        if label.is_empty() {
+            return None;
+        }
+        if !self.has_print_labels {
+            let page = label.parse::<usize>().ok()?.checked_sub(1)?;
+            return (page < self.len()).then_some(page);
+        }
+        let mut current = None;
+        for (page, print_label) in self.print_labels.iter().enumerate() {
+            if print_label.is_some() {
+                current = print_label.as_deref();
+            }
+            let matches = match current {
+                Some(current) => current.eq_ignore_ascii_case(label),
+                None => lower_roman(page + 1).eq_ignore_ascii_case(label),
+            };
+            if matches {
+                return Some(page);
+            }
+        }
+        None
+    }
+}
+
+/// Lower-case roman numeral for `n`, falling back to arabic digits outside
+/// `1..=3999`.
+fn lower_roman(n: usize) -> String {
+    match i32::try_from(n) {
+        Ok(n) => ListStyleType::LowerRoman.counter(n),
+        Err(_) => n.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render_ir::PageAnnotation;
+
+    fn page(label: Option<&str>) -> RenderPage {
+        let mut page = RenderPage::new(1);
+        if let Some(label) = label {
+            page.annotations.push(PageAnnotation {
+                kind: PRINT_PAGE_ANNOTATION.to_string(),
+                value: Some(label.to_string()),
+            });
+        }
+        page
+    }
+
+    #[test]
+    fn synthesizes_numbers_without_print_labels() {
+        let pages = [page(None), page(None), page(None)];
+        let map = PageMap::from_pages(&pages);
+        assert!(!map.has_print_labels());
+        assert_eq!(map.label_for(0).as_deref(), Some("1"));
+        assert_eq!(map.label_for(2).as_deref(), Some("3"));
+        assert_eq!(map.label_for(3), None);
+        assert_eq!(map.page_for_label(" 2 "), Some(1));
+        assert_eq!(map.page_for_label("4"), None);
+    }
+
+    #[test]
+    fn prefers_print_labels_and_romanizes_front_matter() {
+        let pages = [
+            page(None),
+            page(None),
+            page(Some("xi")),
+            page(Some("1")),
+            page(None),
+            page(Some("2")),
+        ];
+        let map = PageMap::from_pages(&pages);
+        assert!(map.has_print_labels());
+        let labels: Vec<String> = (0..map.len()).filter_map(|p| map.label_for(p)).collect();
+        assert_eq!(labels, ["i", "ii", "xi", "1", "1", "2"]);
+        assert_eq!(map.page_for_label("XI"), Some(2));
+        assert_eq!(map.page_for_label("1"), Some(3));
+        assert_eq!(map.page_for_label("ii"), Some(1));
+        assert_eq!(map.page_for_label(""), None);
+        assert_eq!(map.page_for_label("6"), None);
+    }
+
+    #[test]
+    fn roman_numerals() {
+        assert_eq!(lower_roman(4), "iv");
+        assert_eq!(lower_roman(12), "xii");
+        assert_eq!(lower_roman(1994), "mcmxciv");
+        assert_eq!(lower_roman(4000), "4000");
+    }
+}
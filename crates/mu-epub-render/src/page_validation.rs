@@ -0,0 +1,379 @@
+//! Sanity checks for laid-out pages before they reach a display driver.
+//!
+//! Some panel drivers fault on negative or far off-screen coordinates, so a
+//! style or layout bug upstream can take down the whole device. Enable
+//! [`RenderEngineOptions::validate_output`](crate::RenderEngineOptions::validate_output)
+//! to check every emitted page: problems are reported through
+//! [`RenderDiagnostic::PageIssues`](crate::RenderDiagnostic::PageIssues) and,
+//! with [`OutputValidation::Clamp`], repaired in place.
+
+use crate::render_ir::{
+    DrawCommand, OverlayRect, OverlaySize, PageChromeConfig, RectCommand, RenderPage, RuleCommand,
+};
+
+/// What the render engine does with emitted pages.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum OutputValidation {
+    /// Pages are emitted as laid out.
+    #[default]
+    Off,
+    /// Issues are reported; pages are left untouched.
+    Report,
+    /// Issues are reported and offending commands clamped or dropped.
+    Clamp,
+}
+
+/// Command layer of a [`RenderPage`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum CommandLayer {
+    /// `content_commands`.
+    Content,
+    /// `chrome_commands`.
+    Chrome,
+    /// `overlay_commands`.
+    Overlay,
+}
+
+/// What is wrong with a draw command.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum PageIssueKind {
+    /// The command's anchor or extent lies outside the page.
+    OutOfBounds,
+    /// A text command with no text.
+    EmptyText,
+    /// A rect or rule with zero width, height, length or thickness.
+    Degenerate,
+    /// A rect or rule larger than the page itself.
+    Oversized,
+    /// A chrome marker overlaps the content command at `content_index`.
+    ChromeOverlap { content_index: usize },
+}
+
+/// One problem found on a page.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PageIssue {
+    /// Layer holding the offending command.
+    pub layer: CommandLayer,
+    /// Index of the command within its layer, before any clamping.
+    pub index: usize,
+    /// What is wrong.
+    pub kind: PageIssueKind,
+}
+
+/// Check the command layers of `page` against a `viewport`-sized display.
+///
+/// Chrome markers are placed with `chrome` geometry. Text extents are
+/// estimated, so text is only checked by its anchor: left x on the page and
+/// baseline within its height.
+pub fn validate_page(
+    page: &RenderPage,
+    chrome: &PageChromeConfig,
+    viewport: OverlaySize,
+) -> Vec<PageIssue> {
+    let mut issues = Vec::with_capacity(0);
+    let layers = [
+        (CommandLayer::Content, &page.content_commands),
+        (CommandLayer::Chrome, &page.chrome_commands),
+        (CommandLayer::Overlay, &page.overlay_commands),
+    ];
+    for (layer, commands) in layers {
+        for (index, cmd) in commands.iter().enumerate() {
+            if let Some(kind) = command_issue(cmd, viewport) {
+                issues.push(PageIssue { layer, index, kind });
+            }
+        }
+    }
+    for (index, marker) in page.chrome_commands.iter().enumerate() {
+        let DrawCommand::PageChrome(marker) = marker else {
+            continue;
+        };
+        let Some(band) = chrome.marker_bounds(marker, viewport) else {
+            continue;
+        };
+        let overlap = page
+            .content_commands
+            .iter()
+            .position(|cmd| cmd.bounds().is_some_and(|rect| overlaps(&band, &rect)));
+        if let Some(content_index) = overlap {
+            issues.push(PageIssue {
+                layer: CommandLayer::Chrome,
+                index,
+                kind: PageIssueKind::ChromeOverlap { content_index },
+            });
+        }
+    }
+    issues
+}
+
+/// Validate `page`, then clamp off-page commands onto it and drop the ones
+/// that cannot be drawn. Returns the issues found before repair.
+///
+/// Chrome overlaps are reported but left alone; moving either side would
+/// change the page's reading order.
+pub fn clamp_page(
+    page: &mut RenderPage,
+    chrome: &PageChromeConfig,
+    viewport: OverlaySize,
+) -> Vec<PageIssue> {
+    let issues = validate_page(page, chrome, viewport);
+    if issues
+        .iter()
+        .all(|issue| matches!(issue.kind, PageIssueKind::ChromeOverlap { .. }))
+    {
+        return issues;
+    }
+    for commands in [
+        &mut page.content_commands,
+        &mut page.chrome_commands,
+        &mut page.overlay_commands,
+    ] {
+        commands.retain_mut(|cmd| clamp_command(cmd, viewport));
+    }
+    page.sync_commands();
+    issues
+}
+
+fn command_issue(cmd: &DrawCommand, viewport: OverlaySize) -> Option<PageIssueKind> {
+    let (width, height) = (viewport.width as i32, viewport.height as i32);
+    match cmd {
+        DrawCommand::Text(text) => {
+            if text.text.is_empty() {
+                Some(PageIssueKind::EmptyText)
+            } else if !(0..width).contains(&text.x) || !(0..=height).contains(&text.baseline_y) {
+                Some(PageIssueKind::OutOfBounds)
+            } else {
+                None
+            }
+        }
+        DrawCommand::Rect(rect) => shape_issue(rect_extent(rect), viewport),
+        DrawCommand::Rule(rule) => shape_issue(rule_extent(rule), viewport),
+        DrawCommand::PageChrome(_) => None,
+    }
+}
+
+fn shape_issue(extent: OverlayRect, viewport: OverlaySize) -> Option<PageIssueKind> {
+    if extent.width == 0 || extent.height == 0 {
+        Some(PageIssueKind::Degenerate)
+    } else if extent.width > viewport.width || extent.height > viewport.height {
+        Some(PageIssueKind::Oversized)
+    } else if extent.x < 0
+        || extent.y < 0
+        || extent.x.saturating_add(extent.width as i32) > viewport.width as i32
+        || extent.bottom() > viewport.height as i32
+    {
+        Some(PageIssueKind::OutOfBounds)
+    } else {
+        None
+    }
+}
+
+fn rect_extent(rect: &RectCommand) -> OverlayRect {
+    OverlayRect {
+        x: rect.x,
+        y: rect.y,
+        width: rect.width,
+        height: rect.height,
+    }
+}
+
+/// Rule extent by its start point, ignoring the stroke centring
+/// [`DrawCommand::bounds`] applies.
+fn rule_extent(rule: &RuleCommand) -> OverlayRect {
+    let (width, height) = if rule.horizontal {
+        (rule.length, rule.thickness)
+    } else {
+        (rule.thickness, rule.length)
+    };
+    OverlayRect {
+        x: rule.x,
+        y: rule.y,
+        width,
+        height,
+    }
+}
+
+/// Whether two rects share a non-empty area.
+fn overlaps(a: &OverlayRect, b: &OverlayRect) -> bool {
+    a.x < b.x.saturating_add(b.width as i32)
+        && b.x < a.x.saturating_add(a.width as i32)
+        && a.y < b.bottom()
+        && b.y < a.bottom()
+}
+
+/// Intersect `extent` with the page as `(x, y, width, height)`, or `None`
+/// when nothing of it remains.
+fn clip(extent: OverlayRect, viewport: OverlaySize) -> Option<(i32, i32, u32, u32)> {
+    let left = extent.x.max(0);
+    let top = extent.y.max(0);
+    let right = extent
+        .x
+        .saturating_add(extent.width as i32)
+        .min(viewport.width as i32);
+    let bottom = extent.bottom().min(viewport.height as i32);
+    (right > left && bottom > top)
+        .then(|| (left, top, (right - left) as u32, (bottom - top) as u32))
+}
+
+/// Clamp `cmd` onto the page; `false` when it should be dropped.
+fn clamp_command(cmd: &mut DrawCommand, viewport: OverlaySize) -> bool {
+    match cmd {
+        DrawCommand::Text(text) => {
+            if text.text.is_empty() || viewport.width == 0 {
+                return false;
+            }
+            text.x = text.x.clamp(0, viewport.width as i32 - 1);
+            text.baseline_y = text.baseline_y.clamp(0, viewport.height as i32);
+            true
+        }
+        DrawCommand::Rect(rect) => match clip(rect_extent(rect), viewport) {
+            Some((x, y, width, height)) => {
+                *rect = RectCommand {
+                    x,
+                    y,
+                    width,
+                    height,
+                    ..*rect
+                };
+                true
+            }
+            None => false,
+        },
+        DrawCommand::Rule(rule) => match clip(rule_extent(rule), viewport) {
+            Some((x, y, width, height)) => {
+                let (length, thickness) = if rule.horizontal {
+                    (width, height)
+                } else {
+                    (height, width)
+                };
+                *rule = RuleCommand {
+                    x,
+                    y,
+                    length,
+                    thickness,
+                    ..*rule
+                };
+                true
+            }
+            None => false,
+        },
+        DrawCommand::PageChrome(_) => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render_ir::{
+        JustifyMode, PageChromeCommand, PageChromeKind, ResolvedTextStyle, TextCommand,
+    };
+    use mu_epub::{BlockRole, FontSynthesis};
+
+    const VIEWPORT: OverlaySize = OverlaySize {
+        width: 200,
+        height: 300,
+    };
+
+    fn text(x: i32, baseline_y: i32, text: &str) -> DrawCommand {
+        DrawCommand::Text(TextCommand {
+            x,
+            baseline_y,
+            text: text.to_string(),
+            font_id: None,
+            style: ResolvedTextStyle {
+                font_id: None,
+                family: "serif".to_string(),
+                weight: 400,
+                italic: false,
+                size_px: 16.0,
+                line_height: 1.4,
+                letter_spacing: 0.0,
+                role: BlockRole::Body,
+                justify_mode: JustifyMode::None,
+                synthesis: FontSynthesis::default(),
+            },
+        })
+    }
+
+    fn rect(x: i32, y: i32, width: u32, height: u32) -> DrawCommand {
+        DrawCommand::Rect(RectCommand {
+            x,
+            y,
+            width,
+            height,
+            fill: true,
+        })
+    }
+
+    fn page(content: Vec<DrawCommand>) -> RenderPage {
+        let mut page = RenderPage::new(1);
+        page.content_commands = content;
+        page.sync_commands();
+        page
+    }
+
+    #[test]
+    fn reports_off_page_empty_and_oversized_commands() {
+        let page = page(vec![
+            text(10, 40, "fine"),
+            text(-30, 40, "left of page"),
+            text(10, 60, ""),
+            rect(0, 0, 0, 10),
+            rect(0, 0, 5000, 10),
+            rect(190, 290, 20, 20),
+        ]);
+        let kinds: Vec<_> = validate_page(&page, &PageChromeConfig::default(), VIEWPORT)
+            .into_iter()
+            .map(|issue| (issue.index, issue.kind))
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                (1, PageIssueKind::OutOfBounds),
+                (2, PageIssueKind::EmptyText),
+                (3, PageIssueKind::Degenerate),
+                (4, PageIssueKind::Oversized),
+                (5, PageIssueKind::OutOfBounds),
+            ]
+        );
+    }
+
+    #[test]
+    fn detects_content_under_chrome_markers() {
+        let mut page = page(vec![text(10, 40, "body"), text(10, 292, "too low")]);
+        page.push_chrome_command(DrawCommand::PageChrome(PageChromeCommand {
+            kind: PageChromeKind::Footer,
+            text: Some("12".to_string()),
+            current: None,
+            total: None,
+        }));
+        let chrome = PageChromeConfig::geometry_defaults();
+        let issues = validate_page(&page, &chrome, VIEWPORT);
+        assert_eq!(
+            issues,
+            vec![PageIssue {
+                layer: CommandLayer::Chrome,
+                index: 0,
+                kind: PageIssueKind::ChromeOverlap { content_index: 1 },
+            }]
+        );
+    }
+
+    #[test]
+    fn clamping_pulls_commands_onto_the_page() {
+        let mut page = page(vec![
+            text(-30, 400, "stray"),
+            text(10, 60, ""),
+            rect(190, -10, 50, 20),
+            rect(500, 500, 5, 5),
+        ]);
+        let issues = clamp_page(&mut page, &PageChromeConfig::default(), VIEWPORT);
+        assert_eq!(issues.len(), 4);
+        assert_eq!(
+            page.content_commands,
+            vec![text(0, 300, "stray"), rect(190, 0, 10, 10)]
+        );
+        assert_eq!(page.commands, page.content_commands);
+        assert!(validate_page(&page, &PageChromeConfig::default(), VIEWPORT).is_empty());
+    }
+}
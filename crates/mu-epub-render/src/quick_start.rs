@@ -0,0 +1,443 @@
+//! One-call "open to first page" pipeline.
+//!
+//! [`open_first_page`] runs the steps most integrations start with: open the
+//! book with navigation deferred, pick where reading starts, and lay out the
+//! start chapter until its first page is complete. All three phases share a
+//! single [`FirstPageBudget`], and failures name the phase they happened in.
+
+use std::cell::Cell;
+use std::fs::File;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use mu_epub::book::OpenConfig;
+use mu_epub::{
+    EpubBook, EpubBookOptions, EpubError, MemoryBudget, RandomAccess, ReadingPosition, ZipLimits,
+};
+
+use crate::render_engine::{CancelToken, RenderEngine, RenderEngineError};
+use crate::render_ir::{PaginationProfileId, RenderPage};
+use crate::render_profile::PaginationProfile;
+
+/// Max bytes accepted for the `mimetype` entry during open.
+const MAX_MIMETYPE_BYTES: usize = 1024;
+
+/// Combined limits for [`open_first_page`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FirstPageBudget {
+    /// Wall-clock allowance for the whole pipeline; `None` disables it.
+    pub max_duration: Option<Duration>,
+    /// Byte caps for open and render prep.
+    ///
+    /// Entry and navigation caps apply while opening; render prep uses the
+    /// field-wise minimum of this and the profile's own budget.
+    pub memory: MemoryBudget,
+}
+
+impl Default for FirstPageBudget {
+    fn default() -> Self {
+        Self {
+            max_duration: Some(Duration::from_secs(2)),
+            memory: MemoryBudget::default(),
+        }
+    }
+}
+
+/// Step of [`open_first_page`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum FirstPagePhase {
+    /// Archive, container, and package parse; navigation is deferred.
+    Open,
+    /// Choosing the start chapter.
+    Locate,
+    /// Styling and laying out the start chapter up to its first page.
+    Render,
+}
+
+impl core::fmt::Display for FirstPagePhase {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::Open => write!(f, "open"),
+            Self::Locate => write!(f, "locate"),
+            Self::Render => write!(f, "render"),
+        }
+    }
+}
+
+/// Wall-clock time spent in one phase.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PhaseTiming {
+    /// Phase measured.
+    pub phase: FirstPagePhase,
+    /// Time spent in it.
+    pub elapsed: Duration,
+}
+
+/// Where reading continues after the first page.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ResumeState {
+    /// Chapter the page came from, with the start fragment from the
+    /// package guide when there is one.
+    pub position: ReadingPosition,
+    /// 0-based page index of the returned page within its chapter.
+    pub chapter_page_index: usize,
+    /// Pagination profile the page was laid out with.
+    pub profile: PaginationProfileId,
+    /// Per-phase timings, in pipeline order.
+    pub phases: Vec<PhaseTiming>,
+}
+
+/// Cause of an [`open_first_page`] failure.
+#[derive(Debug)]
+pub enum FirstPageErrorKind {
+    /// Opening or reading the book failed.
+    Epub(EpubError),
+    /// Render prep or layout failed.
+    Render(RenderEngineError),
+    /// The pipeline ran past [`FirstPageBudget::max_duration`].
+    DeadlineExceeded {
+        /// Configured allowance.
+        limit: Duration,
+    },
+    /// No chapter from the start position onward produced a page.
+    NoContent,
+}
+
+/// [`open_first_page`] failure, tagged with the phase it happened in.
+#[derive(Debug)]
+pub struct FirstPageError {
+    /// Phase that failed.
+    pub phase: FirstPagePhase,
+    /// Time since the pipeline started.
+    pub elapsed: Duration,
+    /// What went wrong.
+    pub kind: FirstPageErrorKind,
+}
+
+impl core::fmt::Display for FirstPageError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "{} phase failed after {} ms: ",
+            self.phase,
+            self.elapsed.as_millis()
+        )?;
+        match &self.kind {
+            FirstPageErrorKind::Epub(err) => write!(f, "{}", err),
+            FirstPageErrorKind::Render(err) => write!(f, "{}", err),
+            FirstPageErrorKind::DeadlineExceeded { limit } => {
+                write!(f, "deadline of {} ms exceeded", limit.as_millis())
+            }
+            FirstPageErrorKind::NoContent => write!(f, "no chapter produced a page"),
+        }
+    }
+}
+
+impl std::error::Error for FirstPageError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match &self.kind {
+            FirstPageErrorKind::Epub(err) => Some(err),
+            FirstPageErrorKind::Render(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+/// Cancels layout once the first page is in hand or the deadline passes.
+struct FirstPageCancel {
+    deadline: Option<Instant>,
+    done: Cell<bool>,
+}
+
+impl CancelToken for FirstPageCancel {
+    fn is_cancelled(&self) -> bool {
+        self.done.get() || self.deadline.is_some_and(|at| Instant::now() >= at)
+    }
+}
+
+struct Pipeline {
+    started: Instant,
+    phase_started: Instant,
+    budget: FirstPageBudget,
+    phases: Vec<PhaseTiming>,
+}
+
+impl Pipeline {
+    fn start(budget: FirstPageBudget) -> Self {
+        let now = Instant::now();
+        Self {
+            started: now,
+            phase_started: now,
+            budget,
+            phases: Vec::with_capacity(3),
+        }
+    }
+
+    fn deadline(&self) -> Option<Instant> {
+        self.budget.max_duration.map(|limit| self.started + limit)
+    }
+
+    fn fail(&self, phase: FirstPagePhase, kind: FirstPageErrorKind) -> FirstPageError {
+        FirstPageError {
+            phase,
+            elapsed: self.started.elapsed(),
+            kind,
+        }
+    }
+
+    fn deadline_exceeded(&self, phase: FirstPagePhase) -> Option<FirstPageError> {
+        let limit = self.budget.max_duration?;
+        (self.started.elapsed() > limit)
+            .then(|| self.fail(phase, FirstPageErrorKind::DeadlineExceeded { limit }))
+    }
+
+    /// Record the phase that just ended and check the shared deadline.
+    fn finish(&mut self, phase: FirstPagePhase) -> Result<(), FirstPageError> {
+        let now = Instant::now();
+        self.phases.push(PhaseTiming {
+            phase,
+            elapsed: now - self.phase_started,
+        });
+        self.phase_started = now;
+        match self.deadline_exceeded(phase) {
+            Some(err) => Err(err),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Open a book and lay out the page reading should start on.
+///
+/// Runs three phases under one `budget`:
+///
+/// 1. **Open** -- parse the archive and package with navigation deferred,
+///    capping entry and navigation reads at the budget's byte limits.
+/// 2. **Locate** -- pick the start chapter: the package guide's `text`,
+///    `start`, or `bodymatter` reference, else the first linear XHTML spine
+///    item that is not the guide's cover.
+/// 3. **Render** -- prepare that chapter with `profile` and stop layout as
+///    soon as its first page is complete. Chapters that produce no page are
+///    skipped.
+///
+/// The returned [`ResumeState`] carries the position and profile id needed
+/// to continue paginating or to persist the session.
+pub fn open_first_page<R: RandomAccess>(
+    reader: R,
+    profile: &PaginationProfile,
+    budget: FirstPageBudget,
+) -> Result<(EpubBook<R>, RenderPage, ResumeState), FirstPageError> {
+    let mut pipeline = Pipeline::start(budget);
+
+    let config = OpenConfig {
+        options: EpubBookOptions {
+            zip_limits: Some(ZipLimits::new(
+                budget.memory.max_entry_bytes,
+                MAX_MIMETYPE_BYTES,
+            )),
+            max_nav_bytes: Some(budget.memory.max_nav_bytes),
+            ..EpubBookOptions::default()
+        },
+        lazy_navigation: true,
+        profile: false,
+    };
+    let mut book = EpubBook::from_storage_with_config(reader, config)
+        .map_err(|err| pipeline.fail(FirstPagePhase::Open, FirstPageErrorKind::Epub(err)))?;
+    pipeline.finish(FirstPagePhase::Open)?;
+
+    let mut position = start_position(&book);
+    pipeline.finish(FirstPagePhase::Locate)?;
+
+    let mut profile = profile.clone();
+    profile.prep.memory = clamp_memory(profile.prep.memory, budget.memory);
+    let engine = RenderEngine::from_profile(&profile);
+    let cancel = FirstPageCancel {
+        deadline: pipeline.deadline(),
+        done: Cell::new(false),
+    };
+    for index in position.chapter_index..book.chapter_count() {
+        let mut first = None;
+        let result = engine.prepare_chapter_with_cancel(&mut book, index, &cancel, |page| {
+            if first.is_none() {
+                first = Some(page);
+                cancel.done.set(true);
+            }
+        });
+        match (first, result) {
+            (Some(page), _) => {
+                pipeline.finish(FirstPagePhase::Render)?;
+                if index != position.chapter_index {
+                    position = chapter_start(&book, index);
+                }
+                let resume = ResumeState {
+                    position,
+                    chapter_page_index: 0,
+                    profile: engine.pagination_profile_id(),
+                    phases: pipeline.phases,
+                };
+                return Ok((book, page, resume));
+            }
+            (None, Err(RenderEngineError::Cancelled)) => {
+                let limit = budget.max_duration.unwrap_or_default();
+                return Err(pipeline.fail(
+                    FirstPagePhase::Render,
+                    FirstPageErrorKind::DeadlineExceeded { limit },
+                ));
+            }
+            (None, Err(err)) => {
+                return Err(pipeline.fail(FirstPagePhase::Render, FirstPageErrorKind::Render(err)));
+            }
+            (None, Ok(())) => {
+                if let Some(err) = pipeline.deadline_exceeded(FirstPagePhase::Render) {
+                    return Err(err);
+                }
+            }
+        }
+    }
+    Err(pipeline.fail(FirstPagePhase::Render, FirstPageErrorKind::NoContent))
+}
+
+/// [`open_first_page`] for an EPUB file on disk.
+pub fn open_first_page_file<P: AsRef<Path>>(
+    path: P,
+    profile: &PaginationProfile,
+    budget: FirstPageBudget,
+) -> Result<(EpubBook<File>, RenderPage, ResumeState), FirstPageError> {
+    let started = Instant::now();
+    let file = File::open(path).map_err(|err| FirstPageError {
+        phase: FirstPagePhase::Open,
+        elapsed: started.elapsed(),
+        kind: FirstPageErrorKind::Epub(EpubError::Io(err.to_string())),
+    })?;
+    open_first_page(file, profile, budget)
+}
+
+fn clamp_memory(profile: MemoryBudget, budget: MemoryBudget) -> MemoryBudget {
+    MemoryBudget {
+        max_entry_bytes: profile.max_entry_bytes.min(budget.max_entry_bytes),
+        max_css_bytes: profile.max_css_bytes.min(budget.max_css_bytes),
+        max_nav_bytes: profile.max_nav_bytes.min(budget.max_nav_bytes),
+        max_inline_style_bytes: profile
+            .max_inline_style_bytes
+            .min(budget.max_inline_style_bytes),
+        max_pages_in_memory: profile.max_pages_in_memory.min(budget.max_pages_in_memory),
+    }
+}
+
+fn chapter_start<R: RandomAccess>(book: &EpubBook<R>, index: usize) -> ReadingPosition {
+    ReadingPosition {
+        chapter_index: index,
+        chapter_href: book.chapter(index).ok().map(|chapter| chapter.href),
+        segment: None,
+        anchor: None,
+        fallback_offset: 0,
+    }
+}
+
+/// Start of reading: the guide's start reference, else the first linear
+/// XHTML spine item that is not the cover.
+fn start_position<R: RandomAccess>(book: &EpubBook<R>) -> ReadingPosition {
+    let guide = &book.metadata().guide;
+    let guide_href = |types: &[&str]| {
+        guide
+            .iter()
+            .find(|entry| {
+                types
+                    .iter()
+                    .any(|ty| entry.guide_type.eq_ignore_ascii_case(ty))
+            })
+            .map(|entry| match entry.href.split_once('#') {
+                Some((path, fragment)) => (path.to_string(), Some(fragment.to_string())),
+                None => (entry.href.clone(), None),
+            })
+    };
+
+    if let Some((path, anchor)) = guide_href(&["text", "start", "bodymatter"]) {
+        if let Some(chapter) = book.chapters().find(|chapter| chapter.href == path) {
+            return ReadingPosition {
+                anchor: anchor.filter(|fragment| !fragment.is_empty()),
+                ..chapter_start(book, chapter.index)
+            };
+        }
+    }
+
+    let cover = guide_href(&["cover"]).map(|(path, _)| path);
+    let spine = book.spine();
+    let start = book
+        .chapters()
+        .find(|chapter| {
+            spine
+                .get_item(chapter.index)
+                .is_some_and(|item| item.linear)
+                && matches!(
+                    chapter.media_type.as_str(),
+                    "application/xhtml+xml" | "text/html"
+                )
+                && cover.as_deref() != Some(chapter.href.as_str())
+        })
+        .map_or(0, |chapter| chapter.index);
+    chapter_start(book, start)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn fixture_path() -> PathBuf {
+        let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        path.push(
+            "../../tests/fixtures/Fundamental-Accessibility-Tests-Basic-Functionality-v2.0.0.epub",
+        );
+        path
+    }
+
+    #[test]
+    fn open_first_page_renders_start_chapter_with_phase_timings() {
+        let profile = PaginationProfile::for_display("test", 420, 300);
+        let (book, page, resume) =
+            open_first_page_file(fixture_path(), &profile, FirstPageBudget::default())
+                .expect("fixture should reach its first page");
+
+        assert!(!page.content_commands.is_empty());
+        assert_eq!(resume.chapter_page_index, 0);
+        assert_eq!(resume.profile, profile.id());
+        let chapter = book
+            .chapter(resume.position.chapter_index)
+            .expect("start chapter should exist");
+        assert_eq!(
+            resume.position.chapter_href.as_deref(),
+            Some(chapter.href.as_str())
+        );
+        let phases: Vec<_> = resume.phases.iter().map(|timing| timing.phase).collect();
+        assert_eq!(
+            phases,
+            [
+                FirstPagePhase::Open,
+                FirstPagePhase::Locate,
+                FirstPagePhase::Render
+            ]
+        );
+    }
+
+    #[test]
+    fn open_first_page_reports_the_failing_phase() {
+        let profile = PaginationProfile::for_display("test", 420, 300);
+        let budget = FirstPageBudget {
+            memory: MemoryBudget {
+                max_entry_bytes: 16,
+                ..MemoryBudget::default()
+            },
+            ..FirstPageBudget::default()
+        };
+        let err = open_first_page_file(fixture_path(), &profile, budget)
+            .err()
+            .expect("tiny entry cap should fail");
+        assert_eq!(err.phase, FirstPagePhase::Open);
+        assert!(matches!(err.kind, FirstPageErrorKind::Epub(_)));
+
+        let err = open_first_page_file("missing.epub", &profile, FirstPageBudget::default())
+            .err()
+            .expect("missing file should fail");
+        assert_eq!(err.phase, FirstPagePhase::Open);
+    }
+}
@@ -0,0 +1,669 @@
+//! Recording and replay of styled chapter streams.
+//!
+//! A [`ChapterRecording`] holds exactly what render-prep fed the layout
+//! engine for one chapter: every [`StyledEventOrRun`], the chapter title and
+//! page-list anchors, plus the [`PaginationProfileId`] of the engine that
+//! produced it. Capture one on the device with
+//! [`RenderEngine::record_chapter`](crate::RenderEngine::record_chapter),
+//! attach [`encode_recording`]'s bytes to a bug report, and reproduce the
+//! layout elsewhere with
+//! [`RenderEngine::replay_with`](crate::RenderEngine::replay_with) — no EPUB
+//! needed. Layout:
+//!
+//! ```text
+//! magic "MURS", version u8
+//! profile id      (32 bytes)
+//! chapter_index, optional title, page-list anchors
+//! string table    (count, then len-prefixed UTF-8)
+//! style table     (count, then computed styles referencing strings)
+//! items           (count, then tagged events and runs)
+//! ```
+//!
+//! Primitives are the shared `codec` ones also used by `page_codec`: integers
+//! are LEB128 varints with signed values zigzag-encoded.
+
+use mu_epub::tokenizer::Span;
+use mu_epub::{
+    BlockRole, BreakBefore, ComputedTextStyle, Dimension, Float, FontSynthesis, ListStyleType,
+    StyledEvent, StyledEventOrRun, StyledRun,
+};
+
+use crate::codec::{CodecError, Reader, StringTable, StyleTable, Writer};
+use crate::render_ir::PaginationProfileId;
+
+/// Current recording format version; bumped on incompatible changes.
+pub const RECORDING_VERSION: u8 = 2;
+
+const MAGIC: &[u8; 4] = b"MURS";
+
+const ITEM_RUN: u8 = 0;
+const ITEM_EVENT: u8 = 1;
+
+const RUN_SYNTHETIC_BOLD: u8 = 1 << 0;
+const RUN_SYNTHETIC_ITALIC: u8 = 1 << 1;
+const RUN_HAS_SPAN: u8 = 1 << 2;
+
+/// The styled stream of one chapter as it reached layout.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ChapterRecording {
+    /// Spine index of the recorded chapter.
+    pub chapter_index: usize,
+    /// Layout settings of the recording engine.
+    pub profile: PaginationProfileId,
+    /// Chapter title handed to the page chrome.
+    pub title: Option<String>,
+    /// Page-list anchors handed to layout, as `(fragment, label)` pairs.
+    pub page_list_anchors: Vec<(String, String)>,
+    /// Styled items in emission order.
+    pub items: Vec<StyledEventOrRun>,
+}
+
+/// Recording decoding error.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum RecordingError {
+    /// Input does not start with the recording magic.
+    NotARecording,
+    /// Input ended before the recording was complete.
+    Truncated,
+    /// Encoded with a version this build cannot read.
+    UnsupportedVersion(u8),
+    /// Input is structurally invalid.
+    Invalid(&'static str),
+}
+
+impl core::fmt::Display for RecordingError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::NotARecording => write!(f, "not a chapter recording"),
+            Self::Truncated => write!(f, "chapter recording truncated"),
+            Self::UnsupportedVersion(v) => write!(f, "unsupported recording version: {}", v),
+            Self::Invalid(reason) => write!(f, "invalid chapter recording: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for RecordingError {}
+
+impl From<CodecError> for RecordingError {
+    fn from(err: CodecError) -> Self {
+        match err {
+            CodecError::Truncated => Self::Truncated,
+            CodecError::Invalid(reason) => Self::Invalid(reason),
+        }
+    }
+}
+
+/// Encode `recording` into a new buffer.
+pub fn encode_recording(recording: &ChapterRecording) -> Vec<u8> {
+    let mut encoder = Encoder::new(recording);
+    encoder.recording(recording);
+    encoder.out.into_inner()
+}
+
+/// Decode a recording produced by [`encode_recording`].
+///
+/// The whole input must be consumed; trailing bytes are an error.
+pub fn decode_recording(bytes: &[u8]) -> Result<ChapterRecording, RecordingError> {
+    let mut decoder = Decoder {
+        input: Reader::new(bytes),
+        styles: Vec::with_capacity(0),
+    };
+    let recording = decoder.recording()?;
+    if !decoder.input.is_empty() {
+        return Err(RecordingError::Invalid("trailing bytes"));
+    }
+    Ok(recording)
+}
+
+fn runs(recording: &ChapterRecording) -> impl Iterator<Item = &StyledRun> {
+    recording.items.iter().filter_map(|item| match item {
+        StyledEventOrRun::Run(run) => Some(run),
+        StyledEventOrRun::Event(_) => None,
+    })
+}
+
+struct Encoder<'a> {
+    out: Writer<Vec<u8>>,
+    strings: StringTable<'a>,
+    styles: StyleTable<'a, ComputedTextStyle>,
+}
+
+impl<'a> Encoder<'a> {
+    fn new(recording: &'a ChapterRecording) -> Self {
+        let mut encoder = Self {
+            out: Writer::new(Vec::with_capacity(64 + recording.items.len() * 16)),
+            strings: StringTable::new(),
+            styles: StyleTable::new(),
+        };
+        for run in runs(recording) {
+            encoder.strings.intern(&run.resolved_family);
+            if encoder.styles.insert(&run.style) {
+                for family in &run.style.family_stack {
+                    encoder.strings.intern(family);
+                }
+                if let Some(language) = &run.style.language {
+                    encoder.strings.intern(language);
+                }
+            }
+        }
+        encoder
+    }
+
+    fn recording(&mut self, recording: &ChapterRecording) {
+        self.out.bytes(MAGIC);
+        self.out.u8(RECORDING_VERSION);
+        self.out.bytes(&recording.profile.0);
+        self.out.uint(recording.chapter_index as u64);
+        self.out.opt_str(recording.title.as_deref());
+        self.out.uint(recording.page_list_anchors.len() as u64);
+        for (fragment, label) in &recording.page_list_anchors {
+            self.out.str(fragment);
+            self.out.str(label);
+        }
+
+        self.out.string_table(&self.strings);
+        self.out.uint(self.styles.entries().len() as u64);
+        for i in 0..self.styles.entries().len() {
+            let style = self.styles.entries()[i];
+            self.style(style);
+        }
+
+        self.out.uint(recording.items.len() as u64);
+        for item in &recording.items {
+            match item {
+                StyledEventOrRun::Run(run) => {
+                    self.out.u8(ITEM_RUN);
+                    self.run(run);
+                }
+                StyledEventOrRun::Event(event) => {
+                    self.out.u8(ITEM_EVENT);
+                    self.event(event);
+                }
+            }
+        }
+    }
+
+    fn style(&mut self, style: &ComputedTextStyle) {
+        self.out.uint(style.family_stack.len() as u64);
+        for family in &style.family_stack {
+            self.out.uint(self.strings.index(family) as u64);
+        }
+        self.out.uint(u64::from(style.weight));
+        self.out.u8(u8::from(style.italic));
+        self.out.f32(style.size_px);
+        self.out.f32(style.line_height);
+        self.out.f32(style.letter_spacing);
+        let (role, level) = style.block_role.code();
+        self.out.u8(role);
+        self.out.u8(level);
+        let language = style.language.as_deref();
+        self.out.opt_usize(language.map(|l| self.strings.index(l)));
+    }
+
+    fn run(&mut self, run: &StyledRun) {
+        let mut flags = 0;
+        if run.synthesis.bold {
+            flags |= RUN_SYNTHETIC_BOLD;
+        }
+        if run.synthesis.italic {
+            flags |= RUN_SYNTHETIC_ITALIC;
+        }
+        if run.span.is_some() {
+            flags |= RUN_HAS_SPAN;
+        }
+        self.out.u8(flags);
+        self.out.str(&run.text);
+        self.out.uint(self.styles.index(&run.style) as u64);
+        self.out.uint(u64::from(run.font_id));
+        self.out
+            .uint(self.strings.index(&run.resolved_family) as u64);
+        if let Some(span) = run.span {
+            self.out.uint(span.start as u64);
+            self.out.uint(span.len() as u64);
+        }
+    }
+
+    fn event(&mut self, event: &StyledEvent) {
+        match event {
+            StyledEvent::ParagraphStart => self.out.u8(0),
+            StyledEvent::ParagraphEnd => self.out.u8(1),
+            StyledEvent::HeadingStart(level) => {
+                self.out.u8(2);
+                self.out.u8(*level);
+            }
+            StyledEvent::HeadingEnd(level) => {
+                self.out.u8(3);
+                self.out.u8(*level);
+            }
+            StyledEvent::ListStart { style, start } => {
+                self.out.u8(4);
+                self.list_style(style);
+                self.out.int(i64::from(*start));
+            }
+            StyledEvent::ListEnd => self.out.u8(5),
+            StyledEvent::ListItemValue(value) => {
+                self.out.u8(6);
+                self.out.int(i64::from(*value));
+            }
+            StyledEvent::ListItemStart => self.out.u8(7),
+            StyledEvent::ListItemEnd => self.out.u8(8),
+            StyledEvent::BlockquoteStart(depth) => {
+                self.out.u8(9);
+                self.out.u8(*depth);
+            }
+            StyledEvent::BlockquoteEnd(depth) => {
+                self.out.u8(10);
+                self.out.u8(*depth);
+            }
+            StyledEvent::Image {
+                src,
+                alt,
+                width,
+                height,
+                float,
+            } => {
+                self.out.u8(11);
+                self.out.str(src);
+                self.out.str(alt);
+                self.dimension(*width);
+                self.dimension(*height);
+                self.out.u8(match float {
+                    Float::None => 0,
+                    Float::Left => 1,
+                    Float::Right => 2,
+                    _ => 0,
+                });
+            }
+            StyledEvent::BlockMargins { top, bottom } => {
+                self.out.u8(12);
+                self.out.opt_u32(*top);
+                self.out.opt_u32(*bottom);
+            }
+            StyledEvent::LineBreak => self.out.u8(13),
+            StyledEvent::Anchor(id) => {
+                self.out.u8(14);
+                self.out.str(id);
+            }
+            StyledEvent::NoteRef { number, target } => {
+                self.out.u8(15);
+                self.out.uint(u64::from(*number));
+                self.out.str(target);
+            }
+            StyledEvent::ScriptedContent => self.out.u8(16),
+            StyledEvent::Hr => self.out.u8(17),
+            StyledEvent::LinkStart(href) => {
+                self.out.u8(18);
+                self.out.str(href);
+            }
+            StyledEvent::LinkEnd => self.out.u8(19),
+            StyledEvent::FigureStart => self.out.u8(20),
+            StyledEvent::FigureEnd => self.out.u8(21),
+            StyledEvent::CaptionStart => self.out.u8(22),
+            StyledEvent::CaptionEnd => self.out.u8(23),
+            StyledEvent::BlockBreak(kind) => {
+                self.out.u8(24);
+                self.out.u8(match kind {
+                    BreakBefore::Auto => 0,
+                    BreakBefore::Page => 1,
+                    BreakBefore::Column => 2,
+                    BreakBefore::Region => 3,
+                    _ => 1,
+                });
+            }
+        }
+    }
+
+    fn list_style(&mut self, style: &ListStyleType) {
+        match style {
+            ListStyleType::Decimal => self.out.u8(0),
+            ListStyleType::LowerAlpha => self.out.u8(1),
+            ListStyleType::UpperAlpha => self.out.u8(2),
+            ListStyleType::LowerRoman => self.out.u8(3),
+            ListStyleType::UpperRoman => self.out.u8(4),
+            ListStyleType::Disc => self.out.u8(5),
+            ListStyleType::Circle => self.out.u8(6),
+            ListStyleType::Square => self.out.u8(7),
+            ListStyleType::None => self.out.u8(8),
+            ListStyleType::Custom(marker) => {
+                self.out.u8(9);
+                self.out.str(marker);
+            }
+            _ => self.out.u8(0),
+        }
+    }
+
+    fn dimension(&mut self, dimension: Option<Dimension>) {
+        match dimension {
+            None => self.out.u8(0),
+            Some(Dimension::Px(px)) => {
+                self.out.u8(1);
+                self.out.uint(u64::from(px));
+            }
+            Some(Dimension::Percent(pct)) => {
+                self.out.u8(2);
+                self.out.uint(u64::from(pct));
+            }
+            Some(_) => self.out.u8(0),
+        }
+    }
+}
+
+struct Decoder<'a> {
+    input: Reader<'a>,
+    styles: Vec<ComputedTextStyle>,
+}
+
+impl<'a> Decoder<'a> {
+    fn recording(&mut self) -> Result<ChapterRecording, RecordingError> {
+        if self.input.take(MAGIC.len()).ok() != Some(MAGIC.as_slice()) {
+            return Err(RecordingError::NotARecording);
+        }
+        let version = self.input.u8()?;
+        if version != RECORDING_VERSION {
+            return Err(RecordingError::UnsupportedVersion(version));
+        }
+        let mut profile = [0u8; 32];
+        profile.copy_from_slice(self.input.take(32)?);
+        let chapter_index = self.input.usize()?;
+        let title = self.input.opt_string()?;
+        let count = self.input.count()?;
+        let mut page_list_anchors = Vec::with_capacity(count);
+        for _ in 0..count {
+            page_list_anchors.push((self.input.string()?, self.input.string()?));
+        }
+
+        self.input.string_table()?;
+        let count = self.input.count()?;
+        self.styles = Vec::with_capacity(count);
+        for _ in 0..count {
+            let style = self.style()?;
+            self.styles.push(style);
+        }
+
+        let count = self.input.count()?;
+        let mut items = Vec::with_capacity(count);
+        for _ in 0..count {
+            items.push(match self.input.u8()? {
+                ITEM_RUN => StyledEventOrRun::Run(self.run()?),
+                ITEM_EVENT => StyledEventOrRun::Event(self.event()?),
+                _ => return Err(RecordingError::Invalid("item tag")),
+            });
+        }
+        Ok(ChapterRecording {
+            chapter_index,
+            profile: PaginationProfileId(profile),
+            title,
+            page_list_anchors,
+            items,
+        })
+    }
+
+    fn style(&mut self) -> Result<ComputedTextStyle, RecordingError> {
+        let count = self.input.count()?;
+        let mut family_stack = Vec::with_capacity(count);
+        for _ in 0..count {
+            family_stack.push(self.input.table_string()?);
+        }
+        let weight =
+            u16::try_from(self.input.uint()?).map_err(|_| RecordingError::Invalid("weight"))?;
+        let italic = self.input.flag()?;
+        let size_px = self.input.f32()?;
+        let line_height = self.input.f32()?;
+        let letter_spacing = self.input.f32()?;
+        let (role, level) = (self.input.u8()?, self.input.u8()?);
+        let block_role =
+            BlockRole::from_code(role, level).ok_or(RecordingError::Invalid("block role"))?;
+        let language = match self.input.opt_usize()? {
+            Some(index) => Some(self.input.string_at(index)?),
+            None => None,
+        };
+        Ok(ComputedTextStyle {
+            family_stack,
+            weight,
+            italic,
+            size_px,
+            line_height,
+            letter_spacing,
+            block_role,
+            language,
+        })
+    }
+
+    fn run(&mut self) -> Result<StyledRun, RecordingError> {
+        let flags = self.input.u8()?;
+        let text = self.input.string()?;
+        let index = self.input.usize()?;
+        let style = self
+            .styles
+            .get(index)
+            .cloned()
+            .ok_or(RecordingError::Invalid("style index"))?;
+        let font_id = self.input.u32()?;
+        let resolved_family = self.input.table_string()?;
+        let span = if flags & RUN_HAS_SPAN != 0 {
+            let start = self.input.usize()?;
+            let len = self.input.usize()?;
+            let end = start
+                .checked_add(len)
+                .ok_or(RecordingError::Invalid("span"))?;
+            Some(Span::new(start, end))
+        } else {
+            None
+        };
+        Ok(StyledRun {
+            text,
+            style,
+            font_id,
+            resolved_family,
+            synthesis: FontSynthesis {
+                bold: flags & RUN_SYNTHETIC_BOLD != 0,
+                italic: flags & RUN_SYNTHETIC_ITALIC != 0,
+            },
+            span,
+        })
+    }
+
+    fn event(&mut self) -> Result<StyledEvent, RecordingError> {
+        Ok(match self.input.u8()? {
+            0 => StyledEvent::ParagraphStart,
+            1 => StyledEvent::ParagraphEnd,
+            2 => StyledEvent::HeadingStart(self.input.u8()?),
+            3 => StyledEvent::HeadingEnd(self.input.u8()?),
+            4 => StyledEvent::ListStart {
+                style: self.list_style()?,
+                start: self.input.i32()?,
+            },
+            5 => StyledEvent::ListEnd,
+            6 => StyledEvent::ListItemValue(self.input.i32()?),
+            7 => StyledEvent::ListItemStart,
+            8 => StyledEvent::ListItemEnd,
+            9 => StyledEvent::BlockquoteStart(self.input.u8()?),
+            10 => StyledEvent::BlockquoteEnd(self.input.u8()?),
+            11 => StyledEvent::Image {
+                src: self.input.string()?,
+                alt: self.input.string()?,
+                width: self.dimension()?,
+                height: self.dimension()?,
+                float: match self.input.u8()? {
+                    0 => Float::None,
+                    1 => Float::Left,
+                    2 => Float::Right,
+                    _ => return Err(RecordingError::Invalid("float")),
+                },
+            },
+            12 => StyledEvent::BlockMargins {
+                top: self.input.opt_u32()?,
+                bottom: self.input.opt_u32()?,
+            },
+            13 => StyledEvent::LineBreak,
+            14 => StyledEvent::Anchor(self.input.string()?),
+            15 => StyledEvent::NoteRef {
+                number: self.input.u32()?,
+                target: self.input.string()?,
+            },
+            16 => StyledEvent::ScriptedContent,
+            17 => StyledEvent::Hr,
+            18 => StyledEvent::LinkStart(self.input.string()?),
+            19 => StyledEvent::LinkEnd,
+            20 => StyledEvent::FigureStart,
+            21 => StyledEvent::FigureEnd,
+            22 => StyledEvent::CaptionStart,
+            23 => StyledEvent::CaptionEnd,
+            24 => StyledEvent::BlockBreak(match self.input.u8()? {
+                0 => BreakBefore::Auto,
+                1 => BreakBefore::Page,
+                2 => BreakBefore::Column,
+                3 => BreakBefore::Region,
+                _ => return Err(RecordingError::Invalid("break kind")),
+            }),
+            _ => return Err(RecordingError::Invalid("event tag")),
+        })
+    }
+
+    fn list_style(&mut self) -> Result<ListStyleType, RecordingError> {
+        Ok(match self.input.u8()? {
+            0 => ListStyleType::Decimal,
+            1 => ListStyleType::LowerAlpha,
+            2 => ListStyleType::UpperAlpha,
+            3 => ListStyleType::LowerRoman,
+            4 => ListStyleType::UpperRoman,
+            5 => ListStyleType::Disc,
+            6 => ListStyleType::Circle,
+            7 => ListStyleType::Square,
+            8 => ListStyleType::None,
+            9 => ListStyleType::Custom(self.input.string()?),
+            _ => return Err(RecordingError::Invalid("list style")),
+        })
+    }
+
+    fn dimension(&mut self) -> Result<Option<Dimension>, RecordingError> {
+        Ok(match self.input.u8()? {
+            0 => None,
+            1 => Some(Dimension::Px(self.input.u32()?)),
+            2 => Some(Dimension::Percent(self.input.u32()?)),
+            _ => return Err(RecordingError::Invalid("dimension")),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn style(family: &str, language: Option<&str>) -> ComputedTextStyle {
+        ComputedTextStyle {
+            family_stack: vec![family.to_string(), "serif".to_string()],
+            weight: 400,
+            italic: false,
+            size_px: 16.5,
+            line_height: 1.4,
+            letter_spacing: 0.0,
+            block_role: BlockRole::Paragraph,
+            language: language.map(str::to_string),
+        }
+    }
+
+    fn run(text: &str, style: ComputedTextStyle) -> StyledEventOrRun {
+        StyledEventOrRun::Run(StyledRun {
+            text: text.to_string(),
+            style,
+            font_id: 2,
+            resolved_family: "Literata".to_string(),
+            synthesis: FontSynthesis {
+                bold: true,
+                italic: false,
+            },
+            span: Some(Span::new(40, 52)),
+        })
+    }
+
+    fn recording() -> ChapterRecording {
+        let events = [
+            StyledEvent::ParagraphStart,
+            StyledEvent::ParagraphEnd,
+            StyledEvent::HeadingStart(2),
+            StyledEvent::HeadingEnd(2),
+            StyledEvent::ListStart {
+                style: ListStyleType::Custom("→".to_string()),
+                start: -3,
+            },
+            StyledEvent::ListEnd,
+            StyledEvent::ListItemValue(7),
+            StyledEvent::ListItemStart,
+            StyledEvent::ListItemEnd,
+            StyledEvent::BlockquoteStart(1),
+            StyledEvent::BlockquoteEnd(1),
+            StyledEvent::Image {
+                src: "img/a.png".to_string(),
+                alt: "A".to_string(),
+                width: Some(Dimension::Px(120)),
+                height: Some(Dimension::Percent(50)),
+                float: Float::Right,
+            },
+            StyledEvent::BlockMargins {
+                top: Some(0),
+                bottom: None,
+            },
+            StyledEvent::LineBreak,
+            StyledEvent::Anchor("p1".to_string()),
+            StyledEvent::NoteRef {
+                number: 4,
+                target: "notes.xhtml#n4".to_string(),
+            },
+            StyledEvent::ScriptedContent,
+            StyledEvent::Hr,
+            StyledEvent::LinkStart("#p1".to_string()),
+            StyledEvent::LinkEnd,
+            StyledEvent::FigureStart,
+            StyledEvent::FigureEnd,
+            StyledEvent::CaptionStart,
+            StyledEvent::CaptionEnd,
+            StyledEvent::BlockBreak(BreakBefore::Column),
+        ];
+        let mut items: Vec<StyledEventOrRun> =
+            events.into_iter().map(StyledEventOrRun::Event).collect();
+        items.push(run("Hello", style("Literata", None)));
+        items.push(run("καλημέρα", style("Literata", Some("el"))));
+        items.push(run("again", style("Literata", None)));
+        ChapterRecording {
+            chapter_index: 3,
+            profile: PaginationProfileId::from_bytes(b"profile"),
+            title: Some("Chapter Three".to_string()),
+            page_list_anchors: vec![("pg12".to_string(), "12".to_string())],
+            items,
+        }
+    }
+
+    #[test]
+    fn recording_round_trips_every_item_kind() {
+        let recording = recording();
+        let bytes = encode_recording(&recording);
+        assert_eq!(decode_recording(&bytes), Ok(recording));
+    }
+
+    #[test]
+    fn rejects_corrupt_recordings() {
+        let bytes = encode_recording(&recording());
+        assert_eq!(
+            decode_recording(b"MURC\x01"),
+            Err(RecordingError::NotARecording)
+        );
+        assert_eq!(
+            decode_recording(&bytes[..bytes.len() - 1]),
+            Err(RecordingError::Truncated)
+        );
+        let mut versioned = bytes.clone();
+        versioned[4] = RECORDING_VERSION + 1;
+        assert_eq!(
+            decode_recording(&versioned),
+            Err(RecordingError::UnsupportedVersion(RECORDING_VERSION + 1))
+        );
+        let mut trailing = bytes;
+        trailing.push(0);
+        assert_eq!(
+            decode_recording(&trailing),
+            Err(RecordingError::Invalid("trailing bytes"))
+        );
+    }
+}
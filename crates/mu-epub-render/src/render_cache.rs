@@ -0,0 +1,430 @@
+use mu_epub::{EmbeddedFontFace, EpubBook};
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::sync::Mutex;
+
+use crate::page_codec::{decode_page, encode_page_into, encoded_page_size};
+use crate::render_engine::RenderCacheStore;
+use crate::render_ir::{PaginationProfileId, RenderPage};
+
+const MAGIC: &[u8; 4] = b"MURC";
+const FORMAT_VERSION: u8 = 2;
+
+/// Identity of one chapter's cached page stream.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct RenderCacheKey {
+    /// Hash of the chapter's identity within its book.
+    pub chapter_hash: u64,
+    /// Hash of every layout-affecting setting (the pagination profile).
+    pub layout_hash: u64,
+    /// Hash of the font faces available to layout.
+    pub font_set_hash: u64,
+}
+
+type LoadCallback = Box<dyn Fn(&RenderCacheKey) -> Option<Vec<u8>> + Send + Sync>;
+type StoreCallback = Box<dyn Fn(&RenderCacheKey, &[u8]) + Send + Sync>;
+
+/// Byte-budgeted page cache implementing [`RenderCacheStore`].
+///
+/// Pages are kept serialized, so the budget bounds real memory use. The
+/// least recently used chapters are evicted first. Optional storage
+/// callbacks receive every stored stream and serve misses, e.g. to persist
+/// layouts across restarts.
+pub struct RenderCache {
+    budget_bytes: usize,
+    chapter_hashes: Vec<u64>,
+    font_set_hash: u64,
+    entries: Mutex<CacheEntries>,
+    load: Option<LoadCallback>,
+    store: Option<StoreCallback>,
+}
+
+#[derive(Default)]
+struct CacheEntries {
+    map: HashMap<RenderCacheKey, Vec<u8>>,
+    /// Recency order, least recently used first.
+    order: VecDeque<RenderCacheKey>,
+    bytes: usize,
+}
+
+impl fmt::Debug for RenderCache {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RenderCache")
+            .field("budget_bytes", &self.budget_bytes)
+            .field("chapters", &self.chapter_hashes.len())
+            .field("font_set_hash", &self.font_set_hash)
+            .field("bytes_used", &self.bytes_used())
+            .finish_non_exhaustive()
+    }
+}
+
+impl RenderCache {
+    /// Create an empty cache holding at most `budget_bytes` of serialized pages.
+    pub fn new(budget_bytes: usize) -> Self {
+        Self {
+            budget_bytes,
+            chapter_hashes: Vec::with_capacity(0),
+            font_set_hash: 0,
+            entries: Mutex::new(CacheEntries::default()),
+            load: None,
+            store: None,
+        }
+    }
+
+    /// Key chapters by the book's identifier and each spine item's href.
+    ///
+    /// Without a book, chapters are keyed by spine index alone.
+    pub fn with_book<R: mu_epub::RandomAccess>(mut self, book: &EpubBook<R>) -> Self {
+        let book_id = book
+            .metadata()
+            .identifier
+            .as_deref()
+            .unwrap_or(book.title());
+        self.chapter_hashes = book
+            .chapters()
+            .map(|chapter| {
+                let mut hasher = Fnv64::new();
+                book_id.hash(&mut hasher);
+                chapter.idref.hash(&mut hasher);
+                chapter.href.hash(&mut hasher);
+                hasher.finish()
+            })
+            .collect();
+        self
+    }
+
+    /// Key entries by the font faces registered for layout.
+    pub fn with_font_set(mut self, faces: &[EmbeddedFontFace]) -> Self {
+        let mut hasher = Fnv64::new();
+        faces.hash(&mut hasher);
+        self.font_set_hash = hasher.finish();
+        self
+    }
+
+    /// Mirror entries to external storage.
+    ///
+    /// `load` is consulted on in-memory misses; `store` receives each newly
+    /// rendered chapter's serialized pages.
+    pub fn with_storage<L, S>(mut self, load: L, store: S) -> Self
+    where
+        L: Fn(&RenderCacheKey) -> Option<Vec<u8>> + Send + Sync + 'static,
+        S: Fn(&RenderCacheKey, &[u8]) + Send + Sync + 'static,
+    {
+        self.load = Some(Box::new(load));
+        self.store = Some(Box::new(store));
+        self
+    }
+
+    /// Key for `chapter_index` rendered under `profile`.
+    pub fn key(&self, profile: PaginationProfileId, chapter_index: usize) -> RenderCacheKey {
+        let chapter_hash = self
+            .chapter_hashes
+            .get(chapter_index)
+            .copied()
+            .unwrap_or(chapter_index as u64);
+        let mut layout = [0u8; 8];
+        layout.copy_from_slice(&profile.0[..8]);
+        RenderCacheKey {
+            chapter_hash,
+            layout_hash: u64::from_le_bytes(layout),
+            font_set_hash: self.font_set_hash,
+        }
+    }
+
+    /// Number of chapters held in memory.
+    pub fn len(&self) -> usize {
+        self.entries.lock().map(|e| e.map.len()).unwrap_or(0)
+    }
+
+    /// Whether no chapters are held in memory.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Serialized bytes held in memory.
+    pub fn bytes_used(&self) -> usize {
+        self.entries.lock().map(|e| e.bytes).unwrap_or(0)
+    }
+
+    /// Drop every in-memory entry; external storage is untouched.
+    pub fn clear(&self) {
+        if let Ok(mut entries) = self.entries.lock() {
+            entries.map.clear();
+            entries.order.clear();
+            entries.bytes = 0;
+        }
+    }
+
+    fn get(&self, key: &RenderCacheKey) -> Option<Vec<RenderPage>> {
+        let mut entries = self.entries.lock().ok()?;
+        let pages = decode_pages(entries.map.get(key)?);
+        if pages.is_some() {
+            entries.touch(key);
+        }
+        pages
+    }
+
+    fn insert(&self, key: RenderCacheKey, bytes: Vec<u8>) {
+        if bytes.len() > self.budget_bytes {
+            return;
+        }
+        let Ok(mut entries) = self.entries.lock() else {
+            return;
+        };
+        entries.remove(&key);
+        entries.bytes += bytes.len();
+        entries.map.insert(key, bytes);
+        entries.order.push_back(key);
+        while entries.bytes > self.budget_bytes {
+            let Some(oldest) = entries.order.front().copied() else {
+                break;
+            };
+            entries.remove(&oldest);
+        }
+    }
+}
+
+impl CacheEntries {
+    fn touch(&mut self, key: &RenderCacheKey) {
+        if let Some(pos) = self.order.iter().position(|k| k == key) {
+            self.order.remove(pos);
+            self.order.push_back(*key);
+        }
+    }
+
+    fn remove(&mut self, key: &RenderCacheKey) {
+        if let Some(bytes) = self.map.remove(key) {
+            self.bytes -= bytes.len();
+            self.order.retain(|k| k != key);
+        }
+    }
+}
+
+impl RenderCacheStore for RenderCache {
+    fn load_chapter_pages(
+        &self,
+        profile: PaginationProfileId,
+        chapter_index: usize,
+    ) -> Option<Vec<RenderPage>> {
+        let key = self.key(profile, chapter_index);
+        if let Some(pages) = self.get(&key) {
+            return Some(pages);
+        }
+        let bytes = self.load.as_ref()?(&key)?;
+        let pages = decode_pages(&bytes)?;
+        self.insert(key, bytes);
+        Some(pages)
+    }
+
+    fn store_chapter_pages(
+        &self,
+        profile: PaginationProfileId,
+        chapter_index: usize,
+        pages: &[RenderPage],
+    ) {
+        let key = self.key(profile, chapter_index);
+        let bytes = encode_pages(pages);
+        if let Some(store) = &self.store {
+            store(&key, &bytes);
+        }
+        self.insert(key, bytes);
+    }
+}
+
+/// FNV-1a hasher; stable across builds so keys survive external storage.
+struct Fnv64(u64);
+
+impl Fnv64 {
+    fn new() -> Self {
+        Self(0xcbf29ce484222325)
+    }
+}
+
+impl Hasher for Fnv64 {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for b in bytes {
+            self.0 ^= u64::from(*b);
+            self.0 = self.0.wrapping_mul(0x100000001b3);
+        }
+    }
+}
+
+/// Concatenate length-prefixed [`page_codec`](crate::page_codec) frames.
+fn encode_pages(pages: &[RenderPage]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(MAGIC.len() + 1 + pages.len() * 512);
+    out.extend_from_slice(MAGIC);
+    out.push(FORMAT_VERSION);
+    out.extend_from_slice(&(pages.len() as u32).to_le_bytes());
+    for page in pages {
+        out.extend_from_slice(&(encoded_page_size(page) as u32).to_le_bytes());
+        encode_page_into(page, &mut out);
+    }
+    out
+}
+
+/// Decode bytes from [`encode_pages`]; `None` on any corruption.
+fn decode_pages(bytes: &[u8]) -> Option<Vec<RenderPage>> {
+    let rest = bytes.strip_prefix(MAGIC)?;
+    let (&version, rest) = rest.split_first()?;
+    if version != FORMAT_VERSION {
+        return None;
+    }
+    let (count, mut rest) = split_u32(rest)?;
+    let mut pages = Vec::with_capacity(count.min(64));
+    for _ in 0..count {
+        let (len, tail) = split_u32(rest)?;
+        if tail.len() < len {
+            return None;
+        }
+        let (frame, tail) = tail.split_at(len);
+        pages.push(decode_page(frame).ok()?);
+        rest = tail;
+    }
+    rest.is_empty().then_some(pages)
+}
+
+fn split_u32(bytes: &[u8]) -> Option<(usize, &[u8])> {
+    if bytes.len() < 4 {
+        return None;
+    }
+    let (head, tail) = bytes.split_at(4);
+    let value = u32::from_le_bytes([head[0], head[1], head[2], head[3]]);
+    Some((usize::try_from(value).ok()?, tail))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render_ir::{
+        DrawCommand, JustifyMode, PageAnnotation, PageChromeCommand, PageChromeKind,
+        ResolvedTextStyle, RuleCommand, TextCommand,
+    };
+    use mu_epub::{BlockRole, FontSynthesis};
+    use std::sync::Arc;
+
+    fn sample_page(number: usize, text: &str) -> RenderPage {
+        let mut page = RenderPage::new(number);
+        page.push_content_command(DrawCommand::Text(TextCommand {
+            x: 4,
+            baseline_y: 20,
+            text: text.to_string(),
+            font_id: Some(2),
+            style: ResolvedTextStyle {
+                font_id: Some(2),
+                family: "serif".to_string(),
+                weight: 700,
+                italic: true,
+                size_px: 17.5,
+                line_height: 1.3,
+                letter_spacing: 0.0,
+                role: BlockRole::Heading(2),
+                justify_mode: JustifyMode::InterWord { extra_px_total: 6 },
+                synthesis: FontSynthesis {
+                    bold: true,
+                    italic: false,
+                },
+            },
+        }));
+        page.push_content_command(DrawCommand::Rule(RuleCommand {
+            x: 1,
+            y: 2,
+            length: 30,
+            thickness: 1,
+            horizontal: true,
+        }));
+        page.push_chrome_command(DrawCommand::PageChrome(PageChromeCommand {
+            kind: PageChromeKind::Progress,
+            text: None,
+            current: Some(number),
+            total: Some(9),
+        }));
+        page.annotations.push(PageAnnotation {
+            kind: "anchor".to_string(),
+            value: Some("ch1".to_string()),
+        });
+        page.metrics.chapter_page_count = Some(3);
+        page.metrics.progress_book = Some(0.25);
+        page.sync_commands();
+        page
+    }
+
+    fn profile(seed: u8) -> PaginationProfileId {
+        PaginationProfileId::from_bytes(&[seed])
+    }
+
+    #[test]
+    fn pages_round_trip_through_encoding() {
+        let pages = vec![sample_page(1, "alpha"), sample_page(2, "beta")];
+        let bytes = encode_pages(&pages);
+        assert_eq!(decode_pages(&bytes), Some(pages));
+        assert_eq!(decode_pages(&bytes[..bytes.len() - 1]), None);
+        assert_eq!(decode_pages(b"nope"), None);
+    }
+
+    #[test]
+    fn keys_separate_chapters_layouts_and_fonts() {
+        let cache = RenderCache::new(1 << 20);
+        cache.store_chapter_pages(profile(1), 0, &[sample_page(1, "a")]);
+        assert!(cache.load_chapter_pages(profile(1), 0).is_some());
+        assert!(cache.load_chapter_pages(profile(2), 0).is_none());
+        assert!(cache.load_chapter_pages(profile(1), 1).is_none());
+
+        let font = EmbeddedFontFace {
+            family: "Literata".to_string(),
+            weight: 400,
+            style: mu_epub::EmbeddedFontStyle::Normal,
+            stretch: None,
+            href: "fonts/l.woff2".to_string(),
+            format: None,
+        };
+        let with_fonts = RenderCache::new(1 << 20).with_font_set(&[font]);
+        assert_ne!(with_fonts.key(profile(1), 0), cache.key(profile(1), 0));
+    }
+
+    #[test]
+    fn budget_evicts_least_recently_used() {
+        let one = encode_pages(&[sample_page(1, "x")]).len();
+        let cache = RenderCache::new(one * 2);
+        cache.store_chapter_pages(profile(1), 0, &[sample_page(1, "x")]);
+        cache.store_chapter_pages(profile(1), 1, &[sample_page(1, "y")]);
+        // Touch chapter 0 so chapter 1 becomes the eviction candidate.
+        assert!(cache.load_chapter_pages(profile(1), 0).is_some());
+        cache.store_chapter_pages(profile(1), 2, &[sample_page(1, "z")]);
+        assert_eq!(cache.len(), 2);
+        assert!(cache.bytes_used() <= one * 2);
+        assert!(cache.load_chapter_pages(profile(1), 1).is_none());
+        assert!(cache.load_chapter_pages(profile(1), 0).is_some());
+
+        let tiny = RenderCache::new(one - 1);
+        tiny.store_chapter_pages(profile(1), 0, &[sample_page(1, "x")]);
+        assert!(tiny.is_empty());
+    }
+
+    #[test]
+    fn external_storage_serves_misses() {
+        let shelf: Arc<Mutex<HashMap<RenderCacheKey, Vec<u8>>>> = Arc::default();
+        let (load_shelf, store_shelf) = (shelf.clone(), shelf.clone());
+        let make = || {
+            let (load_shelf, store_shelf) = (load_shelf.clone(), store_shelf.clone());
+            RenderCache::new(1 << 20).with_storage(
+                move |key| load_shelf.lock().unwrap().get(key).cloned(),
+                move |key, bytes| {
+                    store_shelf.lock().unwrap().insert(*key, bytes.to_vec());
+                },
+            )
+        };
+        let pages = vec![sample_page(1, "kept")];
+        make().store_chapter_pages(profile(3), 4, &pages);
+        assert_eq!(shelf.lock().unwrap().len(), 1);
+
+        let fresh = make();
+        assert!(fresh.is_empty());
+        assert_eq!(fresh.load_chapter_pages(profile(3), 4), Some(pages));
+        assert_eq!(fresh.len(), 1);
+    }
+}
@@ -1,12 +1,23 @@
-use mu_epub::{EpubBook, RenderPrep, RenderPrepError, RenderPrepOptions, StyledEventOrRun};
+use mu_epub::{
+    ChapterRef, ChapterTitleResolver, EpubBook, RenderPrep, RenderPrepError, RenderPrepOptions,
+    StyledEventOrRun, StyledRun,
+};
 use std::collections::VecDeque;
 use std::fmt;
 use std::sync::mpsc::{sync_channel, Receiver};
 use std::sync::{Arc, Mutex};
 use std::time::Instant;
 
-use crate::render_ir::{OverlayContent, OverlaySize, PaginationProfileId, RenderPage};
-use crate::render_layout::{LayoutConfig, LayoutEngine, LayoutSession as CoreLayoutSession};
+use crate::page_validation::{clamp_page, validate_page, OutputValidation, PageIssue};
+use crate::recording::ChapterRecording;
+use crate::render_ir::{
+    LayoutQuality, OverlayContent, OverlaySize, PaginationProfileId, RenderPage,
+};
+use crate::render_layout::{
+    LayoutConfig, LayoutEngine, LayoutMode, LayoutSession as CoreLayoutSession, ScrollSurface,
+    SnippetBox, SnippetLayout, TextMeasurer,
+};
+use crate::render_profile::PaginationProfile;
 
 /// Cancellation hook for long-running layout operations.
 pub trait CancelToken {
@@ -28,6 +39,18 @@ impl CancelToken for NeverCancel {
 pub enum RenderDiagnostic {
     ReflowTimeMs(u32),
     Cancelled,
+    /// Line-breaking quality of one freshly laid-out page.
+    PageQuality {
+        chapter_index: usize,
+        page_number: usize,
+        quality: LayoutQuality,
+    },
+    /// Problems found by [`RenderEngineOptions::validate_output`].
+    PageIssues {
+        chapter_index: usize,
+        page_number: usize,
+        issues: Vec<PageIssue>,
+    },
 }
 
 type DiagnosticCallback = Arc<Mutex<Box<dyn FnMut(RenderDiagnostic) + Send + 'static>>>;
@@ -40,18 +63,34 @@ pub struct RenderEngineOptions {
     pub prep: RenderPrepOptions,
     /// Layout options used to produce pages.
     pub layout: LayoutConfig,
+    /// Check emitted pages for off-page or malformed draw commands.
+    ///
+    /// Not part of the pagination profile: cached pages are validated as
+    /// they are emitted, like fresh ones.
+    pub validate_output: OutputValidation,
 }
 
 impl RenderEngineOptions {
     /// Build options for a target display size.
     pub fn for_display(width: i32, height: i32) -> Self {
         Self {
-            prep: RenderPrepOptions::default(),
+            prep: prep_for_viewport(width, height),
             layout: LayoutConfig::for_display(width, height),
+            validate_output: OutputValidation::Off,
         }
     }
 }
 
+/// Default prep options with `vw`/`vh` sizes resolved against the display.
+pub(crate) fn prep_for_viewport(width: i32, height: i32) -> RenderPrepOptions {
+    let mut prep = RenderPrepOptions::default();
+    for hints in [&mut prep.style.hints, &mut prep.layout_hints] {
+        hints.viewport_width_px = width.max(0) as f32;
+        hints.viewport_height_px = height.max(0) as f32;
+    }
+    prep
+}
+
 /// Alias used for chapter page slicing.
 pub type PageRange = core::ops::Range<usize>;
 
@@ -130,6 +169,8 @@ impl<'a> RenderConfig<'a> {
 pub struct RenderEngine {
     opts: RenderEngineOptions,
     layout: LayoutEngine,
+    /// Id of the profile the engine was built from, if any.
+    profile_id: Option<PaginationProfileId>,
     diagnostic_sink: DiagnosticSink,
 }
 
@@ -148,10 +189,37 @@ impl RenderEngine {
         Self {
             layout: LayoutEngine::new(opts.layout),
             opts,
+            profile_id: None,
             diagnostic_sink: None,
         }
     }
 
+    /// Create a render engine from a pagination profile.
+    ///
+    /// Caches are keyed by [`PaginationProfile::id`], which also covers the
+    /// profile's font set.
+    pub fn from_profile(profile: &PaginationProfile) -> Self {
+        let mut engine = Self::new(profile.render_options());
+        engine.profile_id = Some(profile.id());
+        engine
+    }
+
+    /// Measure text with a backend measurer instead of the built-in estimate.
+    ///
+    /// The measurer is not part of [`Self::pagination_profile_id`]; use a
+    /// distinct cache when switching measurers.
+    pub fn set_text_measurer(&mut self, measurer: Arc<dyn TextMeasurer>) {
+        self.layout = LayoutEngine::new(self.opts.layout).with_text_measurer(measurer);
+    }
+
+    /// Wrap short rich text into a box, e.g. for a dictionary popup.
+    ///
+    /// Uses the engine's typography and measurer but none of its page
+    /// geometry; see [`LayoutEngine::layout_snippet`].
+    pub fn layout_snippet(&self, runs: &[StyledRun], area: SnippetBox) -> SnippetLayout {
+        self.layout.layout_snippet(runs, area)
+    }
+
     /// Register or replace the diagnostics sink.
     pub fn set_diagnostic_sink<F>(&mut self, sink: F)
     where
@@ -171,6 +239,9 @@ impl RenderEngine {
 
     /// Stable fingerprint for all layout-affecting settings.
     pub fn pagination_profile_id(&self) -> PaginationProfileId {
+        if let Some(id) = self.profile_id {
+            return id;
+        }
         let payload = format!("{:?}|{:?}", self.opts.prep, self.opts.layout);
         PaginationProfileId::from_bytes(payload.as_bytes())
     }
@@ -213,13 +284,54 @@ impl RenderEngine {
         }
     }
 
+    fn chapter_title_resolver<R: mu_epub::RandomAccess>(
+        book: &EpubBook<R>,
+        chapter_index: usize,
+    ) -> ChapterTitleResolver {
+        let nav_label = book
+            .chapter(chapter_index)
+            .ok()
+            .and_then(|chapter| chapter.title);
+        ChapterTitleResolver::new(nav_label, book.title())
+    }
+
+    fn page_list_anchors<R: mu_epub::RandomAccess>(
+        book: &EpubBook<R>,
+        chapter_index: usize,
+    ) -> Vec<(String, String)> {
+        match (book.navigation(), book.chapter(chapter_index)) {
+            (Some(nav), Ok(chapter)) => nav.page_list_for_chapter(&chapter.href),
+            _ => Vec::with_capacity(0),
+        }
+    }
+
+    fn validate_output(&self, page: &mut RenderPage) {
+        let layout = &self.opts.layout;
+        let viewport = OverlaySize {
+            width: layout.display_width.max(0) as u32,
+            height: layout.display_height.max(0) as u32,
+        };
+        let issues = match self.opts.validate_output {
+            OutputValidation::Off => return,
+            OutputValidation::Report => validate_page(page, &layout.page_chrome, viewport),
+            OutputValidation::Clamp => clamp_page(page, &layout.page_chrome, viewport),
+        };
+        if !issues.is_empty() {
+            self.emit_diagnostic(RenderDiagnostic::PageIssues {
+                chapter_index: page.metrics.chapter_index,
+                page_number: page.page_number,
+                issues,
+            });
+        }
+    }
+
     fn annotate_page_for_chapter(page: &mut RenderPage, chapter_index: usize) {
         page.metrics.chapter_index = chapter_index;
         page.metrics.chapter_page_index = page.page_number.saturating_sub(1);
     }
 
     /// Prepare and layout a chapter into render pages.
-    pub fn prepare_chapter<R: std::io::Read + std::io::Seek>(
+    pub fn prepare_chapter<R: mu_epub::RandomAccess>(
         &self,
         book: &mut EpubBook<R>,
         chapter_index: usize,
@@ -227,8 +339,25 @@ impl RenderEngine {
         self.prepare_chapter_with_config_collect(book, chapter_index, RenderConfig::default())
     }
 
+    /// Prepare and layout a chapter onto one tall surface for continuous
+    /// scrolling, whatever the configured [`LayoutConfig::mode`].
+    ///
+    /// Stream the result to the display with [`ScrollSurface::render_band`].
+    pub fn prepare_chapter_scroll<R: mu_epub::RandomAccess>(
+        &self,
+        book: &mut EpubBook<R>,
+        chapter_index: usize,
+    ) -> Result<ScrollSurface, RenderEngineError> {
+        let mut engine = self.clone();
+        engine.opts.layout.mode = LayoutMode::Scroll;
+        engine.layout = self.layout.with_mode(LayoutMode::Scroll);
+        let mut pages = Vec::with_capacity(1);
+        engine.prepare_chapter_with(book, chapter_index, |page| pages.push(page))?;
+        Ok(ScrollSurface::from_pages(pages, &engine.opts.layout))
+    }
+
     /// Prepare and layout a chapter into render pages with explicit run config.
-    pub fn prepare_chapter_with_config_collect<R: std::io::Read + std::io::Seek>(
+    pub fn prepare_chapter_with_config_collect<R: mu_epub::RandomAccess>(
         &self,
         book: &mut EpubBook<R>,
         chapter_index: usize,
@@ -262,7 +391,7 @@ impl RenderEngine {
         on_page: F,
     ) -> Result<(), RenderEngineError>
     where
-        R: std::io::Read + std::io::Seek,
+        R: mu_epub::RandomAccess,
         F: FnMut(RenderPage),
     {
         self.prepare_chapter_with_config(book, chapter_index, RenderConfig::default(), on_page)
@@ -274,16 +403,66 @@ impl RenderEngine {
         book: &mut EpubBook<R>,
         chapter_index: usize,
         config: RenderConfig<'_>,
-        mut on_page: F,
+        on_page: F,
     ) -> Result<(), RenderEngineError>
     where
-        R: std::io::Read + std::io::Seek,
+        R: mu_epub::RandomAccess,
         F: FnMut(RenderPage),
     {
         let cancel = config.cancel.unwrap_or(&NeverCancel);
-        self.prepare_chapter_with_cancel_and_config(book, chapter_index, cancel, config, |page| {
-            on_page(page)
-        })
+        self.prepare_chapter_with_cancel_and_config(
+            book,
+            chapter_index,
+            None,
+            cancel,
+            config,
+            on_page,
+        )
+    }
+
+    /// Prepare and layout a chapter from [`EpubBook::split_chapters`] and
+    /// stream each page; a segment lays out only its own content.
+    pub fn prepare_chapter_ref_with<R, F>(
+        &self,
+        book: &mut EpubBook<R>,
+        chapter: &ChapterRef,
+        on_page: F,
+    ) -> Result<(), RenderEngineError>
+    where
+        R: mu_epub::RandomAccess,
+        F: FnMut(RenderPage),
+    {
+        self.prepare_chapter_ref_with_config(book, chapter, RenderConfig::default(), on_page)
+    }
+
+    /// Like [`Self::prepare_chapter_ref_with`], with explicit config.
+    ///
+    /// Segments of one spine item share its chapter index, so they bypass
+    /// the render cache.
+    pub fn prepare_chapter_ref_with_config<R, F>(
+        &self,
+        book: &mut EpubBook<R>,
+        chapter: &ChapterRef,
+        mut config: RenderConfig<'_>,
+        on_page: F,
+    ) -> Result<(), RenderEngineError>
+    where
+        R: mu_epub::RandomAccess,
+        F: FnMut(RenderPage),
+    {
+        if chapter.segment().is_none() {
+            return self.prepare_chapter_with_config(book, chapter.index, config, on_page);
+        }
+        config.cache = None;
+        let cancel = config.cancel.unwrap_or(&NeverCancel);
+        self.prepare_chapter_with_cancel_and_config(
+            book,
+            chapter.index,
+            Some(chapter),
+            cancel,
+            config,
+            on_page,
+        )
     }
 
     /// Prepare and layout caller-provided chapter bytes and stream each page.
@@ -298,7 +477,7 @@ impl RenderEngine {
         on_page: F,
     ) -> Result<(), RenderEngineError>
     where
-        R: std::io::Read + std::io::Seek,
+        R: mu_epub::RandomAccess,
         F: FnMut(RenderPage),
     {
         self.prepare_chapter_bytes_with_config(
@@ -320,7 +499,7 @@ impl RenderEngine {
         on_page: F,
     ) -> Result<(), RenderEngineError>
     where
-        R: std::io::Read + std::io::Seek,
+        R: mu_epub::RandomAccess,
         F: FnMut(RenderPage),
     {
         let cancel = config.cancel.unwrap_or(&NeverCancel);
@@ -343,24 +522,32 @@ impl RenderEngine {
         on_page: F,
     ) -> Result<(), RenderEngineError>
     where
-        R: std::io::Read + std::io::Seek,
+        R: mu_epub::RandomAccess,
         C: CancelToken,
         F: FnMut(RenderPage),
     {
         let config = RenderConfig::default().with_cancel(cancel);
-        self.prepare_chapter_with_cancel_and_config(book, chapter_index, cancel, config, on_page)
+        self.prepare_chapter_with_cancel_and_config(
+            book,
+            chapter_index,
+            None,
+            cancel,
+            config,
+            on_page,
+        )
     }
 
     fn prepare_chapter_with_cancel_and_config<R, C, F>(
         &self,
         book: &mut EpubBook<R>,
         chapter_index: usize,
+        segment: Option<&ChapterRef>,
         cancel: &C,
         config: RenderConfig<'_>,
         mut on_page: F,
     ) -> Result<(), RenderEngineError>
     where
-        R: std::io::Read + std::io::Seek,
+        R: mu_epub::RandomAccess,
         C: CancelToken + ?Sized,
         F: FnMut(RenderPage),
     {
@@ -380,21 +567,32 @@ impl RenderEngine {
             prep = prep.with_embedded_fonts_from_book(book)?;
         }
         let mut saw_cancelled = false;
-        prep.prepare_chapter_with(book, chapter_index, |item| {
+        let mut title = match segment {
+            Some(chapter) => ChapterTitleResolver::new(chapter.title.clone(), book.title()),
+            None => Self::chapter_title_resolver(book, chapter_index),
+        };
+        session.set_page_list_anchors(Self::page_list_anchors(book, chapter_index));
+        let on_item = |item| {
             if saw_cancelled || cancel.is_cancelled() {
                 saw_cancelled = true;
                 return;
             }
+            title.observe(&item);
             if session.push(item).is_err() {
                 saw_cancelled = true;
                 return;
             }
             session.drain_pages(&mut on_page);
-        })?;
+        };
+        match segment {
+            Some(chapter) => prep.prepare_chapter_ref_with(book, chapter, on_item)?,
+            None => prep.prepare_chapter_with(book, chapter_index, on_item)?,
+        }
         if saw_cancelled || cancel.is_cancelled() {
             self.emit_diagnostic(RenderDiagnostic::Cancelled);
             return Err(RenderEngineError::Cancelled);
         }
+        session.set_chapter_title(title.title());
         session.finish()?;
         session.drain_pages(&mut on_page);
         let elapsed = started.elapsed().as_millis().min(u32::MAX as u128) as u32;
@@ -412,7 +610,7 @@ impl RenderEngine {
         mut on_page: F,
     ) -> Result<(), RenderEngineError>
     where
-        R: std::io::Read + std::io::Seek,
+        R: mu_epub::RandomAccess,
         C: CancelToken + ?Sized,
         F: FnMut(RenderPage),
     {
@@ -432,11 +630,14 @@ impl RenderEngine {
             prep = prep.with_embedded_fonts_from_book(book)?;
         }
         let mut saw_cancelled = false;
+        let mut title = Self::chapter_title_resolver(book, chapter_index);
+        session.set_page_list_anchors(Self::page_list_anchors(book, chapter_index));
         prep.prepare_chapter_bytes_with(book, chapter_index, html, |item| {
             if saw_cancelled || cancel.is_cancelled() {
                 saw_cancelled = true;
                 return;
             }
+            title.observe(&item);
             if session.push(item).is_err() {
                 saw_cancelled = true;
                 return;
@@ -447,6 +648,7 @@ impl RenderEngine {
             self.emit_diagnostic(RenderDiagnostic::Cancelled);
             return Err(RenderEngineError::Cancelled);
         }
+        session.set_chapter_title(title.title());
         session.finish()?;
         session.drain_pages(&mut on_page);
         let elapsed = started.elapsed().as_millis().min(u32::MAX as u128) as u32;
@@ -458,7 +660,7 @@ impl RenderEngine {
     ///
     /// Range indices are zero-based over the emitted chapter page sequence.
     /// Returned `RenderPage::page_number` values remain 1-based chapter page numbers.
-    pub fn prepare_chapter_page_range<R: std::io::Read + std::io::Seek>(
+    pub fn prepare_chapter_page_range<R: mu_epub::RandomAccess>(
         &self,
         book: &mut EpubBook<R>,
         chapter_index: usize,
@@ -469,7 +671,7 @@ impl RenderEngine {
     }
 
     /// Alias for chapter page range rendering.
-    pub fn page_range<R: std::io::Read + std::io::Seek>(
+    pub fn page_range<R: mu_epub::RandomAccess>(
         &self,
         book: &mut EpubBook<R>,
         chapter_index: usize,
@@ -488,7 +690,7 @@ impl RenderEngine {
     /// Prepare and layout a chapter and return pages as an iterator.
     ///
     /// This iterator is eager: pages are prepared first, then iterated.
-    pub fn prepare_chapter_iter<R: std::io::Read + std::io::Seek>(
+    pub fn prepare_chapter_iter<R: mu_epub::RandomAccess>(
         &self,
         book: &mut EpubBook<R>,
         chapter_index: usize,
@@ -510,7 +712,7 @@ impl RenderEngine {
         chapter_index: usize,
     ) -> RenderPageStreamIter
     where
-        R: std::io::Read + std::io::Seek + Send + 'static,
+        R: mu_epub::RandomAccess + Send + 'static,
     {
         let (tx, rx) = sync_channel(1);
         let engine = self.clone();
@@ -555,7 +757,7 @@ impl RenderEngine {
         mut on_page: F,
     ) -> Result<(), RenderEngineError>
     where
-        R: std::io::Read + std::io::Seek,
+        R: mu_epub::RandomAccess,
         O: crate::render_ir::OverlayComposer,
         F: FnMut(RenderPage),
     {
@@ -571,6 +773,61 @@ impl RenderEngine {
             on_page(page);
         })
     }
+
+    /// Capture the styled stream of a chapter for offline replay.
+    ///
+    /// Runs render prep exactly as [`RenderEngine::prepare_chapter`] does,
+    /// embedded fonts included, but records the items instead of laying
+    /// them out.
+    pub fn record_chapter<R: mu_epub::RandomAccess>(
+        &self,
+        book: &mut EpubBook<R>,
+        chapter_index: usize,
+    ) -> Result<ChapterRecording, RenderEngineError> {
+        let mut prep = RenderPrep::new(self.opts.prep)
+            .with_serif_default()
+            .with_embedded_fonts_from_book(book)?;
+        let mut title = Self::chapter_title_resolver(book, chapter_index);
+        let page_list_anchors = Self::page_list_anchors(book, chapter_index);
+        let mut items = Vec::with_capacity(0);
+        prep.prepare_chapter_with(book, chapter_index, |item| {
+            title.observe(&item);
+            items.push(item);
+        })?;
+        Ok(ChapterRecording {
+            chapter_index,
+            profile: self.pagination_profile_id(),
+            title: title.title(),
+            page_list_anchors,
+            items,
+        })
+    }
+
+    /// Lay out a recorded chapter without the source book.
+    ///
+    /// Pages come from this engine's settings; compare
+    /// [`ChapterRecording::profile`] with
+    /// [`RenderEngine::pagination_profile_id`] to confirm the device's
+    /// settings are reproduced.
+    pub fn replay_with<F>(
+        &self,
+        recording: &ChapterRecording,
+        mut on_page: F,
+    ) -> Result<(), RenderEngineError>
+    where
+        F: FnMut(RenderPage),
+    {
+        let mut session = self.begin(recording.chapter_index, RenderConfig::default());
+        session.set_page_list_anchors(recording.page_list_anchors.clone());
+        for item in &recording.items {
+            session.push(item.clone())?;
+            session.drain_pages(&mut on_page);
+        }
+        session.set_chapter_title(recording.title.clone());
+        session.finish()?;
+        session.drain_pages(&mut on_page);
+        Ok(())
+    }
 }
 
 /// Incremental wrapper session returned by `RenderEngine::begin`.
@@ -603,8 +860,14 @@ impl LayoutSession<'_> {
             let pending = &mut self.pending_pages;
             let page_index = &mut self.page_index;
             let capture_for_cache = self.cfg.cache.is_some();
+            let engine = self.engine;
             inner.push_item_with_pages(item, &mut |mut page| {
                 RenderEngine::annotate_page_for_chapter(&mut page, chapter);
+                engine.emit_diagnostic(RenderDiagnostic::PageQuality {
+                    chapter_index: chapter,
+                    page_number: page.page_number,
+                    quality: page.metrics.quality,
+                });
                 if capture_for_cache {
                     rendered.push(page.clone());
                 }
@@ -617,12 +880,28 @@ impl LayoutSession<'_> {
         Ok(())
     }
 
+    /// Set the chapter title used by the page chrome templates.
+    pub fn set_chapter_title(&mut self, title: Option<String>) {
+        if let Some(inner) = self.inner.as_mut() {
+            inner.set_chapter_title(title);
+        }
+    }
+
+    /// Set the chapter's page-list anchors used by
+    /// [`LayoutConfig::page_list_breaks`].
+    pub fn set_page_list_anchors(&mut self, anchors: Vec<(String, String)>) {
+        if let Some(inner) = self.inner.as_mut() {
+            inner.set_page_list_anchors(anchors);
+        }
+    }
+
     /// Drain currently available pages in FIFO order.
     pub fn drain_pages<F>(&mut self, mut on_page: F)
     where
         F: FnMut(RenderPage),
     {
-        while let Some(page) = self.pending_pages.pop_front() {
+        while let Some(mut page) = self.pending_pages.pop_front() {
+            self.engine.validate_output(&mut page);
             on_page(page);
         }
     }
@@ -643,8 +922,14 @@ impl LayoutSession<'_> {
             let pending = &mut self.pending_pages;
             let page_index = &mut self.page_index;
             let capture_for_cache = self.cfg.cache.is_some();
+            let engine = self.engine;
             inner.finish(&mut |mut page| {
                 RenderEngine::annotate_page_for_chapter(&mut page, chapter);
+                engine.emit_diagnostic(RenderDiagnostic::PageQuality {
+                    chapter_index: chapter,
+                    page_number: page.page_number,
+                    quality: page.metrics.quality,
+                });
                 if capture_for_cache {
                     rendered.push(page.clone());
                 }
@@ -784,7 +1069,7 @@ impl From<RenderPrepError> for RenderEngineError {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use mu_epub::{BlockRole, ComputedTextStyle, StyledEvent, StyledRun};
+    use mu_epub::{BlockRole, ComputedTextStyle, FontSynthesis, StyledEvent, StyledRun};
 
     fn body_run(text: &str) -> StyledEventOrRun {
         StyledEventOrRun::Run(StyledRun {
@@ -797,12 +1082,34 @@ mod tests {
                 line_height: 1.4,
                 letter_spacing: 0.0,
                 block_role: BlockRole::Body,
+                language: None,
             },
             font_id: 0,
             resolved_family: "serif".to_string(),
+            synthesis: FontSynthesis::default(),
+            span: None,
         })
     }
 
+    #[test]
+    fn engine_from_profile_keys_by_profile_id() {
+        let profile = PaginationProfile::eink_6in();
+        let engine = RenderEngine::from_profile(&profile);
+        assert_eq!(engine.pagination_profile_id(), profile.id());
+
+        let mut fonts = profile.clone();
+        fonts.fonts.default_family = "sans-serif".to_string();
+        let other = RenderEngine::from_profile(&fonts);
+        assert_ne!(
+            engine.pagination_profile_id(),
+            other.pagination_profile_id()
+        );
+        assert_ne!(
+            RenderEngine::new(profile.render_options()).pagination_profile_id(),
+            engine.pagination_profile_id()
+        );
+    }
+
     #[test]
     fn begin_push_and_drain_pages_streams_incrementally() {
         let mut opts = RenderEngineOptions::for_display(300, 120);
@@ -833,4 +1140,39 @@ mod tests {
         assert_eq!(streamed, expected);
         assert!(streamed.iter().all(|page| page.metrics.chapter_index == 3));
     }
+
+    #[test]
+    fn replaying_a_recording_matches_direct_layout() {
+        let engine = RenderEngine::new(RenderEngineOptions::for_display(300, 120));
+        let mut items = Vec::with_capacity(0);
+        for _ in 0..12 {
+            items.push(StyledEventOrRun::Event(StyledEvent::ParagraphStart));
+            items.push(body_run("one two three four five six seven eight nine ten"));
+            items.push(StyledEventOrRun::Event(StyledEvent::ParagraphEnd));
+        }
+        let recording = crate::ChapterRecording {
+            chapter_index: 2,
+            profile: engine.pagination_profile_id(),
+            title: None,
+            page_list_anchors: Vec::with_capacity(0),
+            items: items.clone(),
+        };
+        let decoded = crate::decode_recording(&crate::encode_recording(&recording))
+            .expect("recording should decode");
+
+        let mut replayed = Vec::with_capacity(0);
+        engine
+            .replay_with(&decoded, |page| replayed.push(page))
+            .expect("replay should pass");
+
+        let mut direct = Vec::with_capacity(0);
+        let mut session = engine.begin(2, RenderConfig::default());
+        for item in items {
+            session.push(item).expect("push should pass");
+        }
+        session.finish().expect("finish should pass");
+        session.drain_pages(|page| direct.push(page));
+        assert!(!replayed.is_empty());
+        assert_eq!(replayed, direct);
+    }
 }
@@ -1,4 +1,4 @@
-use mu_epub::BlockRole;
+use mu_epub::{BlockRole, FontSynthesis};
 
 /// Page represented as backend-agnostic draw commands.
 #[derive(Clone, Debug, Default, PartialEq)]
@@ -20,6 +20,9 @@ pub struct RenderPage {
     pub overlay_items: Vec<OverlayItem>,
     /// Structured non-draw annotations associated with this page.
     pub annotations: Vec<PageAnnotation>,
+    /// Word geometry for content text, filled when
+    /// [`crate::LayoutConfig::word_boxes`] is set.
+    pub word_boxes: Vec<WordBox>,
     /// Per-page metrics for navigation/progress consumers.
     pub metrics: PageMetrics,
 }
@@ -35,6 +38,7 @@ impl RenderPage {
             overlay_commands: Vec::with_capacity(0),
             overlay_items: Vec::with_capacity(0),
             annotations: Vec::with_capacity(0),
+            word_boxes: Vec::with_capacity(0),
             metrics: PageMetrics {
                 chapter_page_index: page_number.saturating_sub(1),
                 ..PageMetrics::default()
@@ -42,6 +46,13 @@ impl RenderPage {
         }
     }
 
+    /// Word under the point `(x, y)`, if any.
+    pub fn word_at(&self, x: i32, y: i32) -> Option<&WordBox> {
+        self.word_boxes.iter().find(|word| {
+            x >= word.rect.x && x < word.rect.right() && y >= word.rect.y && y < word.rect.bottom()
+        })
+    }
+
     /// Push a content-layer command.
     pub fn push_content_command(&mut self, cmd: DrawCommand) {
         self.content_commands.push(cmd);
@@ -69,8 +80,164 @@ impl RenderPage {
     pub fn page_meta(&self) -> &PageMeta {
         &self.metrics
     }
+
+    /// Damaged regions when replacing `other` with `self`.
+    ///
+    /// Covers every positioned command present in one page but not the
+    /// other; text extents are conservative estimates. Chrome markers carry
+    /// no geometry of their own, so a changed marker damages the whole
+    /// `viewport`, or an unbounded rectangle to clip against the display
+    /// when no viewport is given; use [`RenderPage::diff_with_chrome`] to
+    /// narrow it to the header/footer/progress band.
+    pub fn diff(&self, other: &RenderPage, viewport: Option<OverlaySize>) -> Vec<OverlayRect> {
+        let whole = viewport.map_or(
+            OverlayRect {
+                x: 0,
+                y: 0,
+                width: i32::MAX as u32,
+                height: i32::MAX as u32,
+            },
+            |viewport| OverlayRect {
+                x: 0,
+                y: 0,
+                width: viewport.width,
+                height: viewport.height,
+            },
+        );
+        self.damage(other, |cmd| match cmd {
+            DrawCommand::PageChrome(_) => Some(whole),
+            cmd => cmd.bounds(),
+        })
+    }
+
+    /// Like [`RenderPage::diff`], resolving chrome markers with `chrome`
+    /// geometry on a `viewport`-sized display.
+    pub fn diff_with_chrome(
+        &self,
+        other: &RenderPage,
+        chrome: &PageChromeConfig,
+        viewport: OverlaySize,
+    ) -> Vec<OverlayRect> {
+        self.damage(other, |cmd| match cmd {
+            DrawCommand::PageChrome(marker) => chrome.marker_bounds(marker, viewport),
+            cmd => cmd.bounds(),
+        })
+    }
+
+    fn damage(
+        &self,
+        other: &RenderPage,
+        bounds: impl Fn(&DrawCommand) -> Option<OverlayRect>,
+    ) -> Vec<OverlayRect> {
+        let ours = self.all_commands();
+        let theirs = other.all_commands();
+        // Only commands with the same kind and anchor can be equal, so
+        // matching searches one sorted run of `theirs` per command.
+        let mut keyed: Vec<(MatchKey, usize)> = theirs
+            .iter()
+            .enumerate()
+            .map(|(i, cmd)| (match_key(cmd), i))
+            .collect();
+        keyed.sort_unstable();
+        let mut matched = vec![false; theirs.len()];
+        let mut rects = Vec::with_capacity(4);
+        for cmd in &ours {
+            let key = match_key(cmd);
+            let start = keyed.partition_point(|(k, _)| *k < key);
+            let twin = keyed[start..]
+                .iter()
+                .take_while(|(k, _)| *k == key)
+                .map(|(_, i)| *i)
+                .find(|&i| !matched[i] && *theirs[i] == **cmd);
+            match twin {
+                Some(i) => matched[i] = true,
+                None => rects.extend(bounds(cmd)),
+            }
+        }
+        for (cmd, matched) in theirs.iter().zip(matched) {
+            if !matched {
+                rects.extend(bounds(cmd));
+            }
+        }
+        merge_rects(rects)
+    }
+
+    fn all_commands(&self) -> Vec<&DrawCommand> {
+        let split = self
+            .content_commands
+            .iter()
+            .chain(&self.chrome_commands)
+            .chain(&self.overlay_commands);
+        if self.content_commands.is_empty()
+            && self.chrome_commands.is_empty()
+            && self.overlay_commands.is_empty()
+        {
+            self.commands.iter().collect()
+        } else {
+            split.collect()
+        }
+    }
+}
+
+/// Command kind and anchor point; equal commands have equal keys.
+type MatchKey = (u8, i32, i32);
+
+fn match_key(cmd: &DrawCommand) -> MatchKey {
+    match cmd {
+        DrawCommand::Text(text) => (0, text.x, text.baseline_y),
+        DrawCommand::Rule(rule) => (1, rule.x, rule.y),
+        DrawCommand::Rect(rect) => (2, rect.x, rect.y),
+        DrawCommand::PageChrome(marker) => {
+            let kind = match marker.kind {
+                PageChromeKind::Header => 0,
+                PageChromeKind::Footer => 1,
+                PageChromeKind::Progress => 2,
+            };
+            (3, kind, 0)
+        }
+    }
+}
+
+/// Union overlapping or touching rectangles until none remain.
+fn merge_rects(mut rects: Vec<OverlayRect>) -> Vec<OverlayRect> {
+    let mut i = 0;
+    while i < rects.len() {
+        let mut merged = false;
+        let mut j = i + 1;
+        while j < rects.len() {
+            if rects[i].touches(&rects[j]) {
+                let other = rects.swap_remove(j);
+                rects[i] = rects[i].union(&other);
+                merged = true;
+            } else {
+                j += 1;
+            }
+        }
+        if !merged {
+            i += 1;
+        }
+    }
+    rects
+}
+
+/// Bounding box of one word of a content [`TextCommand`], for read-along
+/// highlighting.
+///
+/// Horizontal positions follow the renderer's justification spacing; the
+/// box spans the full line height.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WordBox {
+    /// Index of the text command in [`RenderPage::content_commands`].
+    pub command: usize,
+    /// UTF-8 byte range of the word within the command's text.
+    pub char_range: core::ops::Range<usize>,
+    /// Word bounds in page coordinates.
+    pub rect: OverlayRect,
 }
 
+/// [`PageAnnotation::kind`] whose value is the publisher's print page label.
+pub const PRINT_PAGE_ANNOTATION: &str = "print_page";
+
 /// Structured page annotation.
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct PageAnnotation {
@@ -97,6 +264,24 @@ pub struct PageMetrics {
     pub progress_chapter: f32,
     /// Book progress in range `[0.0, 1.0]`, when known.
     pub progress_book: Option<f32>,
+    /// Typographic quality of the page's line breaking.
+    pub quality: LayoutQuality,
+}
+
+/// Per-page line-breaking quality metrics collected during layout.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct LayoutQuality {
+    /// Widest inter-word gap on the page in px, including justification.
+    pub max_word_gap_px: i32,
+    /// Justified lines whose per-space stretch exceeds
+    /// [`JustificationConfig::loose_space_ratio`].
+    pub loose_lines: u16,
+    /// Lines ending with an inserted hyphen.
+    pub hyphenated_lines: u16,
+    /// Paragraphs whose final line is alone at the top of the page.
+    pub widows: u16,
+    /// Paragraphs whose first line is alone at the bottom of the page.
+    pub orphans: u16,
 }
 
 /// Backward-compatible alias for page-level metadata.
@@ -158,6 +343,41 @@ pub struct OverlayRect {
     pub height: u32,
 }
 
+impl OverlayRect {
+    fn from_edges(left: i32, top: i32, right: i32, bottom: i32) -> Self {
+        Self {
+            x: left,
+            y: top,
+            width: right.saturating_sub(left).max(0) as u32,
+            height: bottom.saturating_sub(top).max(0) as u32,
+        }
+    }
+
+    fn right(&self) -> i32 {
+        self.x.saturating_add(self.width as i32)
+    }
+
+    pub(crate) fn bottom(&self) -> i32 {
+        self.y.saturating_add(self.height as i32)
+    }
+
+    fn touches(&self, other: &OverlayRect) -> bool {
+        self.x <= other.right()
+            && other.x <= self.right()
+            && self.y <= other.bottom()
+            && other.y <= self.bottom()
+    }
+
+    fn union(&self, other: &OverlayRect) -> OverlayRect {
+        Self::from_edges(
+            self.x.min(other.x),
+            self.y.min(other.y),
+            self.right().max(other.right()),
+            self.bottom().max(other.bottom()),
+        )
+    }
+}
+
 /// Overlay content payload.
 #[derive(Clone, Debug, PartialEq)]
 pub enum OverlayContent {
@@ -196,6 +416,60 @@ pub enum DrawCommand {
     PageChrome(PageChromeCommand),
 }
 
+impl DrawCommand {
+    /// Conservative screen extent, when the command is self-positioned.
+    ///
+    /// Text assumes glyphs at most one em wide with descenders of 0.3 em.
+    /// Page chrome markers return `None`; their geometry lives in
+    /// [`PageChromeConfig`].
+    pub fn bounds(&self) -> Option<OverlayRect> {
+        match self {
+            Self::Text(text) => {
+                let em = text.style.size_px.max(1.0);
+                let chars = text.text.chars().count() as f32;
+                let mode = text.style.justify_mode;
+                let extra = mode.word_extra_px().max(0) + mode.char_extra_px().max(0);
+                let width =
+                    (chars * (em + text.style.letter_spacing.max(0.0))).ceil() as i32 + extra;
+                Some(OverlayRect::from_edges(
+                    text.x,
+                    text.baseline_y.saturating_sub(em.ceil() as i32),
+                    text.x.saturating_add(width),
+                    text.baseline_y
+                        .saturating_add((em * 0.3).ceil() as i32)
+                        .saturating_add(1),
+                ))
+            }
+            Self::Rule(rule) => {
+                let half = (rule.thickness / 2) as i32;
+                let (w, h) = if rule.horizontal {
+                    (rule.length, rule.thickness)
+                } else {
+                    (rule.thickness, rule.length)
+                };
+                let (x, y) = if rule.horizontal {
+                    (rule.x, rule.y - half)
+                } else {
+                    (rule.x - half, rule.y)
+                };
+                Some(OverlayRect {
+                    x,
+                    y,
+                    width: w + 1,
+                    height: h + 1,
+                })
+            }
+            Self::Rect(rect) => Some(OverlayRect {
+                x: rect.x,
+                y: rect.y,
+                width: rect.width,
+                height: rect.height,
+            }),
+            Self::PageChrome(_) => None,
+        }
+    }
+}
+
 /// Theme-aware render intent.
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct RenderIntent {
@@ -205,6 +479,8 @@ pub struct RenderIntent {
     pub dither: DitherMode,
     /// Contrast multiplier in percent (100 = neutral).
     pub contrast_boost: u8,
+    /// What to favor where output quality can be traded for time or memory.
+    pub priority: RenderPriority,
 }
 
 impl Default for RenderIntent {
@@ -213,10 +489,97 @@ impl Default for RenderIntent {
             grayscale_mode: GrayscaleMode::Off,
             dither: DitherMode::None,
             contrast_boost: 100,
+            priority: RenderPriority::Quality,
         }
     }
 }
 
+impl RenderIntent {
+    /// Whether and at what size to decode an image laid out in a
+    /// `width` x `height` px box.
+    pub fn image_hint(&self, width: u32, height: u32) -> ImageDecodeHint {
+        match self.priority {
+            RenderPriority::Quality => ImageDecodeHint::Decode { width, height },
+            // Half-size decodes map onto the cheap 1/2 DCT scaling of JPEG
+            // decoders and a quarter of the pixel work elsewhere.
+            RenderPriority::Speed => ImageDecodeHint::Decode {
+                width: width.div_ceil(2),
+                height: height.div_ceil(2),
+            },
+            RenderPriority::MinMemory => ImageDecodeHint::Placeholder,
+        }
+    }
+
+    /// Dithering to apply: error diffusion needs a row of error terms, so
+    /// only [`RenderPriority::Quality`] keeps it, others fall back to
+    /// ordered dithering.
+    pub fn dither_mode(&self) -> DitherMode {
+        match (self.dither, self.priority) {
+            (DitherMode::ErrorDiffusion, RenderPriority::Speed | RenderPriority::MinMemory) => {
+                DitherMode::Ordered
+            }
+            (dither, _) => dither,
+        }
+    }
+
+    /// Rasterized glyphs a backend should keep cached.
+    pub fn glyph_cache_entries(&self) -> usize {
+        match self.priority {
+            RenderPriority::Quality => 256,
+            RenderPriority::Speed => 1024,
+            RenderPriority::MinMemory => 32,
+        }
+    }
+
+    /// `base` adjusted to the justification effort this intent allows.
+    ///
+    /// Letter spacing multiplies the glyph runs a renderer positions, so
+    /// outside [`RenderPriority::Quality`] slack goes to spaces only.
+    pub fn justification(&self, base: JustificationConfig) -> JustificationConfig {
+        match self.priority {
+            RenderPriority::Quality => base,
+            RenderPriority::Speed | RenderPriority::MinMemory => JustificationConfig {
+                strategy: JustificationStrategy::InterWord,
+                ..base
+            },
+        }
+    }
+}
+
+/// What rendering favors where work can be traded against output.
+///
+/// Layout and backends read the concrete behaviors from [`RenderIntent`]
+/// helpers: [`RenderIntent::image_hint`], [`RenderIntent::dither_mode`],
+/// [`RenderIntent::glyph_cache_entries`] and
+/// [`RenderIntent::justification`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum RenderPriority {
+    /// Full-size image decodes, configured dithering and justification.
+    #[default]
+    Quality,
+    /// Half-size image decodes, a larger glyph cache, and cheaper
+    /// dithering and justification.
+    Speed,
+    /// Image placeholders only, a small glyph cache, and cheaper dithering
+    /// and justification.
+    MinMemory,
+}
+
+/// Image decode decision from [`RenderIntent::image_hint`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ImageDecodeHint {
+    /// Decode the image scaled to fit this size in px, then draw it over
+    /// its laid-out box.
+    Decode { width: u32, height: u32 },
+    /// Leave the laid-out box as an outline placeholder.
+    Placeholder,
+}
+
+/// [`PageAnnotation::kind`] for an image to decode onto the page; the value
+/// is `"{x},{y} {width}x{height} {src}"`, with the box origin and the decode
+/// size from [`RenderIntent::image_hint`].
+pub const IMAGE_ANNOTATION: &str = "image";
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum GrayscaleMode {
     Off,
@@ -251,6 +614,8 @@ pub struct ResolvedTextStyle {
     pub role: BlockRole,
     /// Justification mode from layout.
     pub justify_mode: JustifyMode,
+    /// Bold or italic the backend must synthesize for the chosen face.
+    pub synthesis: FontSynthesis,
 }
 
 /// Justification mode determined during layout.
@@ -260,6 +625,33 @@ pub enum JustifyMode {
     None,
     /// Inter-word with total extra px to distribute.
     InterWord { extra_px_total: i32 },
+    /// Letter spacing with total extra px to distribute between characters.
+    InterCharacter { extra_px_total: i32 },
+    /// Capped inter-word spacing with the remainder as letter spacing.
+    InterWordAndCharacter {
+        word_px_total: i32,
+        char_px_total: i32,
+    },
+}
+
+impl JustifyMode {
+    /// Extra px to distribute across spaces.
+    pub fn word_extra_px(self) -> i32 {
+        match self {
+            Self::InterWord { extra_px_total } => extra_px_total,
+            Self::InterWordAndCharacter { word_px_total, .. } => word_px_total,
+            Self::None | Self::InterCharacter { .. } => 0,
+        }
+    }
+
+    /// Extra px to distribute across gaps between characters.
+    pub fn char_extra_px(self) -> i32 {
+        match self {
+            Self::InterCharacter { extra_px_total } => extra_px_total,
+            Self::InterWordAndCharacter { char_px_total, .. } => char_px_total,
+            Self::None | Self::InterWord { .. } => 0,
+        }
+    }
 }
 
 /// Text draw command.
@@ -320,6 +712,126 @@ pub struct PageChromeCommand {
     pub total: Option<usize>,
 }
 
+impl PageChromeCommand {
+    /// Split `text` into literal segments and draw-time fields.
+    pub fn segments(&self) -> ChromeSegments<'_> {
+        ChromeSegments {
+            rest: self.text.as_deref().unwrap_or_default(),
+        }
+    }
+
+    /// Whether `text` contains fields filled at draw time.
+    pub fn is_dynamic(&self) -> bool {
+        self.segments()
+            .any(|segment| matches!(segment, ChromeSegment::Field(_)))
+    }
+
+    /// Text with draw-time fields substituted from `data`.
+    pub fn resolve_text(&self, data: &ChromeData<'_>) -> Option<String> {
+        let text = self.text.as_deref()?;
+        let mut out = String::with_capacity(text.len());
+        for segment in self.segments() {
+            match segment {
+                ChromeSegment::Literal(literal) => out.push_str(literal),
+                ChromeSegment::Field(field) => data.write_field(field, &mut out),
+            }
+        }
+        Some(out)
+    }
+}
+
+/// Chrome text value that changes without re-layout.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ChromeField {
+    /// `{clock}`: wall-clock time.
+    Clock,
+    /// `{battery}`: battery charge.
+    Battery,
+    /// `{custom}`: host-defined status such as sync state.
+    Custom,
+}
+
+impl ChromeField {
+    const ALL: [ChromeField; 3] = [Self::Clock, Self::Battery, Self::Custom];
+
+    /// Template placeholder for this field.
+    pub const fn placeholder(self) -> &'static str {
+        match self {
+            Self::Clock => "{clock}",
+            Self::Battery => "{battery}",
+            Self::Custom => "{custom}",
+        }
+    }
+}
+
+/// Piece of chrome text: fixed at layout time or filled at draw time.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ChromeSegment<'a> {
+    /// Text fixed at layout time.
+    Literal(&'a str),
+    /// Field substituted from [`ChromeData`] when drawing.
+    Field(ChromeField),
+}
+
+/// Iterator over [`ChromeSegment`]s of a chrome command's text.
+#[derive(Clone, Debug)]
+pub struct ChromeSegments<'a> {
+    rest: &'a str,
+}
+
+impl<'a> Iterator for ChromeSegments<'a> {
+    type Item = ChromeSegment<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.rest.is_empty() {
+            return None;
+        }
+        let next_field = ChromeField::ALL
+            .iter()
+            .filter_map(|field| Some((self.rest.find(field.placeholder())?, *field)))
+            .min_by_key(|(pos, _)| *pos);
+        match next_field {
+            Some((0, field)) => {
+                self.rest = &self.rest[field.placeholder().len()..];
+                Some(ChromeSegment::Field(field))
+            }
+            Some((pos, _)) => {
+                let (literal, rest) = self.rest.split_at(pos);
+                self.rest = rest;
+                Some(ChromeSegment::Literal(literal))
+            }
+            None => Some(ChromeSegment::Literal(core::mem::take(&mut self.rest))),
+        }
+    }
+}
+
+/// Draw-time values for [`ChromeField`]s; missing values render as nothing.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ChromeData<'a> {
+    /// Preformatted clock text, e.g. `"14:05"`.
+    pub clock: Option<&'a str>,
+    /// Battery charge in percent, rendered as `"85%"`.
+    pub battery: Option<u8>,
+    /// Host-defined text.
+    pub custom: Option<&'a str>,
+}
+
+impl ChromeData<'_> {
+    /// Append the value of `field` to `out`.
+    pub fn write_field(&self, field: ChromeField, out: &mut String) {
+        match field {
+            ChromeField::Clock => out.push_str(self.clock.unwrap_or_default()),
+            ChromeField::Battery => {
+                if let Some(percent) = self.battery {
+                    out.push_str(&format!("{}%", percent.min(100)));
+                }
+            }
+            ChromeField::Custom => out.push_str(self.custom.unwrap_or_default()),
+        }
+    }
+}
+
 /// Kind of page-level metadata/chrome.
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum PageChromeKind {
@@ -340,6 +852,11 @@ pub enum PageChromeTextStyle {
     BoldItalic,
 }
 
+/// Tallest chrome font ascent above the baseline.
+const CHROME_TEXT_ASCENT_PX: i32 = 15;
+/// Deepest chrome font descent below the baseline.
+const CHROME_TEXT_DESCENT_PX: i32 = 4;
+
 /// Shared page-chrome policy and geometry configuration.
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct PageChromeConfig {
@@ -361,6 +878,10 @@ pub struct PageChromeConfig {
     pub footer_baseline_from_bottom: i32,
     /// Footer text style.
     pub footer_style: PageChromeTextStyle,
+    /// Header text template; see [`Self::expand_template`].
+    pub header_template: &'static str,
+    /// Footer text template; see [`Self::expand_template`].
+    pub footer_template: &'static str,
     /// Progress bar left/right inset.
     pub progress_x_inset: i32,
     /// Progress bar top y offset from bottom edge.
@@ -384,6 +905,8 @@ impl PageChromeConfig {
             footer_x: 8,
             footer_baseline_from_bottom: 8,
             footer_style: PageChromeTextStyle::Regular,
+            header_template: "{chapter}",
+            footer_template: "Page {page}",
             progress_x_inset: 8,
             progress_y_from_bottom: 20,
             progress_height: 4,
@@ -391,6 +914,65 @@ impl PageChromeConfig {
         }
     }
 
+    /// Expand a header/footer template.
+    ///
+    /// `{page}` and `{total}` are the chapter page number and count, and
+    /// `{chapter}` the chapter title (empty when unknown). Text that expands
+    /// to nothing falls back to `Page {page}`. [`ChromeField`] placeholders
+    /// such as `{battery}` are left in place for the renderer to fill.
+    pub fn expand_template(
+        template: &str,
+        page: usize,
+        total: usize,
+        chapter_title: Option<&str>,
+    ) -> String {
+        let text = template
+            .replace("{page}", &page.to_string())
+            .replace("{total}", &total.to_string())
+            .replace("{chapter}", chapter_title.unwrap_or_default());
+        if text.trim().is_empty() {
+            format!("Page {}", page)
+        } else {
+            text
+        }
+    }
+
+    /// Screen extent of a chrome marker on a `viewport`-sized display.
+    ///
+    /// Header and footer text span the full width since their text width is
+    /// backend-dependent; disabled or empty markers draw nothing.
+    pub fn marker_bounds(
+        &self,
+        marker: &PageChromeCommand,
+        viewport: OverlaySize,
+    ) -> Option<OverlayRect> {
+        let width = viewport.width as i32;
+        let height = viewport.height as i32;
+        let text_band = |baseline: i32| {
+            OverlayRect::from_edges(
+                0,
+                baseline - CHROME_TEXT_ASCENT_PX,
+                width,
+                baseline + CHROME_TEXT_DESCENT_PX,
+            )
+        };
+        match marker.kind {
+            PageChromeKind::Header => (self.header_enabled && marker.text.is_some())
+                .then(|| text_band(self.header_baseline_y)),
+            PageChromeKind::Footer => (self.footer_enabled && marker.text.is_some())
+                .then(|| text_band(height.saturating_sub(self.footer_baseline_from_bottom))),
+            PageChromeKind::Progress => self.progress_enabled.then(|| {
+                let top = height.saturating_sub(self.progress_y_from_bottom);
+                OverlayRect::from_edges(
+                    self.progress_x_inset,
+                    top,
+                    (width - self.progress_x_inset).max(self.progress_x_inset + 1),
+                    top + self.progress_height.max(1) as i32,
+                )
+            }),
+        }
+    }
+
     /// Defaults used by layout so chrome markers are opt-in.
     pub const fn layout_defaults() -> Self {
         let mut cfg = Self::geometry_defaults();
@@ -414,10 +996,20 @@ pub struct TypographyConfig {
     pub hyphenation: HyphenationConfig,
     /// Widow/orphan control policy.
     pub widow_orphan_control: WidowOrphanControl,
+    /// Keep-with-next policy for headings.
+    pub keep_with_next: KeepWithNext,
     /// Justification policy.
     pub justification: JustificationConfig,
     /// Hanging punctuation policy.
     pub hanging_punctuation: HangingPunctuationConfig,
+    /// Drop cap policy.
+    pub drop_caps: DropCapConfig,
+    /// No-break rules between adjacent words.
+    pub no_break: NoBreakConfig,
+    /// Per-level space around headings.
+    pub heading_spacing: HeadingSpacing,
+    /// Nested blockquote indentation and quote bars.
+    pub blockquote: BlockquoteConfig,
 }
 
 /// Hyphenation behavior.
@@ -459,15 +1051,117 @@ impl Default for WidowOrphanControl {
     }
 }
 
+/// Keep-with-next policy for headings.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct KeepWithNext {
+    /// Body lines that must follow a heading on the same page.
+    pub min_lines: u8,
+    /// Enable keep-with-next for headings (off by default).
+    pub enabled: bool,
+}
+
+impl Default for KeepWithNext {
+    fn default() -> Self {
+        Self {
+            min_lines: 1,
+            enabled: false,
+        }
+    }
+}
+
+/// Per-level space above and below headings, replacing the uniform
+/// `heading_gap_px` when enabled.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct HeadingSpacing {
+    /// Space above `h1` through `h6` in px.
+    pub above_px: [i32; 6],
+    /// Space below `h1` through `h6` in px.
+    pub below_px: [i32; 6],
+    /// Enable per-level spacing.
+    pub enabled: bool,
+}
+
+impl Default for HeadingSpacing {
+    fn default() -> Self {
+        Self {
+            above_px: [28, 22, 18, 14, 12, 12],
+            below_px: [14, 12, 10, 8, 6, 6],
+            enabled: false,
+        }
+    }
+}
+
+impl HeadingSpacing {
+    /// Space above heading `level`, or `fallback_px` when disabled.
+    pub fn above(&self, level: u8, fallback_px: i32) -> i32 {
+        self.pick(&self.above_px, level, fallback_px)
+    }
+
+    /// Space below heading `level`, or `fallback_px` when disabled.
+    pub fn below(&self, level: u8, fallback_px: i32) -> i32 {
+        self.pick(&self.below_px, level, fallback_px)
+    }
+
+    fn pick(&self, table: &[i32; 6], level: u8, fallback_px: i32) -> i32 {
+        if self.enabled {
+            table[usize::from(level.clamp(1, 6) - 1)]
+        } else {
+            fallback_px
+        }
+    }
+}
+
+/// Blockquote indentation policy.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BlockquoteConfig {
+    /// Left indent added per nesting level in px.
+    pub indent_px: i32,
+    /// Draw a vertical bar at the left edge of each nesting level.
+    pub quote_bars: bool,
+    /// Quote bar thickness in px.
+    pub bar_width_px: u32,
+}
+
+impl Default for BlockquoteConfig {
+    fn default() -> Self {
+        Self {
+            indent_px: 16,
+            quote_bars: false,
+            bar_width_px: 2,
+        }
+    }
+}
+
+/// How justified lines distribute their slack.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum JustificationStrategy {
+    /// Widen spaces only; lines without spaces stay ragged.
+    #[default]
+    InterWord,
+    /// Widen gaps between all characters.
+    InterCharacter,
+    /// Widen spaces up to `max_word_extra_px` each, then letter-space the
+    /// rest. Lines without spaces (single words, CJK) are letter-spaced.
+    Adaptive,
+}
+
 /// Justification policy.
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub struct JustificationConfig {
-    /// Enable inter-word justification.
+    /// Enable justification.
     pub enabled: bool,
-    /// Minimum words required for justification.
+    /// Minimum words required for inter-word justification.
     pub min_words: usize,
     /// Minimum fill ratio required for justification.
     pub min_fill_ratio: f32,
+    /// Slack distribution strategy.
+    pub strategy: JustificationStrategy,
+    /// Extra px per space before [`JustificationStrategy::Adaptive`] falls
+    /// back to letter spacing.
+    pub max_word_extra_px: f32,
+    /// Per-space stretch, as a multiple of the space width, above which a
+    /// justified line counts as loose in [`LayoutQuality`].
+    pub loose_space_ratio: f32,
 }
 
 impl Default for JustificationConfig {
@@ -476,15 +1170,198 @@ impl Default for JustificationConfig {
             enabled: true,
             min_words: 7,
             min_fill_ratio: 0.75,
+            strategy: JustificationStrategy::InterWord,
+            max_word_extra_px: 4.0,
+            loose_space_ratio: 1.0,
         }
     }
 }
 
-/// Hanging punctuation policy.
+impl JustificationConfig {
+    /// Distribute `extra_px` of slack over a line with `words` words,
+    /// `spaces` spaces, and `chars` characters.
+    pub fn justify_mode(
+        &self,
+        extra_px: i32,
+        words: usize,
+        spaces: usize,
+        chars: usize,
+    ) -> JustifyMode {
+        let char_gaps = chars.saturating_sub(1);
+        let word_spaced = spaces > 0 && words >= self.min_words;
+        match self.strategy {
+            JustificationStrategy::InterWord if word_spaced => JustifyMode::InterWord {
+                extra_px_total: extra_px,
+            },
+            JustificationStrategy::InterCharacter if char_gaps > 0 => JustifyMode::InterCharacter {
+                extra_px_total: extra_px,
+            },
+            JustificationStrategy::Adaptive if word_spaced => {
+                let cap = (self.max_word_extra_px.max(0.0) * spaces as f32) as i32;
+                let word_px_total = extra_px.min(cap);
+                let char_px_total = extra_px - word_px_total;
+                if char_px_total > 0 && char_gaps > 0 {
+                    JustifyMode::InterWordAndCharacter {
+                        word_px_total,
+                        char_px_total,
+                    }
+                } else {
+                    JustifyMode::InterWord {
+                        extra_px_total: extra_px,
+                    }
+                }
+            }
+            JustificationStrategy::Adaptive if spaces == 0 && char_gaps > 0 => {
+                JustifyMode::InterCharacter {
+                    extra_px_total: extra_px,
+                }
+            }
+            _ => JustifyMode::None,
+        }
+    }
+}
+
+/// Drop cap policy.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DropCapConfig {
+    /// Drop the initial letter of a chapter's first body paragraph.
+    pub enabled: bool,
+    /// Lines the dropped initial spans; values below 2 disable drop caps.
+    pub lines: u8,
+    /// Treat an enlarged single-letter run opening a paragraph as a drop cap.
+    pub detect_styled_initials: bool,
+    /// Gap between the initial and the text wrapped beside it.
+    pub gap_px: i32,
+}
+
+impl Default for DropCapConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            lines: 3,
+            detect_styled_initials: true,
+            gap_px: 4,
+        }
+    }
+}
+
+/// Rules that keep adjacent words on the same line.
 #[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct NoBreakConfig {
+    /// Keep titles such as "Mr." and initials such as "J." with the next word.
+    pub abbreviations: bool,
+    /// Keep a number with the unit that follows it ("10 km", "5 %").
+    pub number_units: bool,
+    /// Keep one-letter words off line ends, as Czech and Polish typesetting requires.
+    pub single_letter_words: bool,
+}
+
+impl NoBreakConfig {
+    /// Titles that never end a line when [`Self::abbreviations`] is set.
+    const ABBREVIATIONS: &'static [&'static str] = &[
+        "mr.", "mrs.", "ms.", "mx.", "dr.", "prof.", "st.", "mt.", "fr.", "rev.", "sr.", "gen.",
+        "capt.", "lt.", "col.", "sgt.", "no.", "vol.", "ch.", "p.", "pp.", "fig.",
+    ];
+
+    /// Units kept with a preceding number when [`Self::number_units`] is set.
+    const UNITS: &'static [&'static str] = &[
+        "%", "‰", "°", "°c", "°f", "k", "mm", "cm", "m", "km", "mi", "ft", "yd", "mg", "g", "kg",
+        "lb", "lbs", "oz", "ml", "cl", "l", "s", "ms", "min", "h", "hr", "hrs", "kb", "mb", "gb",
+        "tb", "px", "pt", "kph", "mph", "km/h", "hz", "khz", "mhz", "ghz", "w", "kw", "v", "€",
+        "$", "£", "¥",
+    ];
+
+    /// Whether a line must not break between `word` and `next`.
+    pub fn keeps_together(&self, word: &str, next: &str) -> bool {
+        let word = word.trim_start_matches(is_opening_punctuation);
+        (self.abbreviations && is_abbreviation(word))
+            || (self.number_units && is_number(word) && is_unit(next))
+            || (self.single_letter_words && is_single_letter(word))
+    }
+}
+
+fn is_opening_punctuation(ch: char) -> bool {
+    matches!(
+        ch,
+        '(' | '[' | '"' | '\'' | '\u{201C}' | '\u{2018}' | '\u{00AB}' | '\u{201E}'
+    )
+}
+
+fn is_abbreviation(word: &str) -> bool {
+    let mut chars = word.chars();
+    // A lone capital with a period is an initial: "J. R. R. Tolkien".
+    if let (Some(first), Some('.'), None) = (chars.next(), chars.next(), chars.next()) {
+        if first.is_uppercase() {
+            return true;
+        }
+    }
+    NoBreakConfig::ABBREVIATIONS
+        .iter()
+        .any(|abbr| word.eq_ignore_ascii_case(abbr))
+}
+
+fn is_number(word: &str) -> bool {
+    let digits = word.trim_start_matches(['+', '-', '\u{2212}', '~']);
+    digits.starts_with(|ch: char| ch.is_ascii_digit())
+        && digits
+            .chars()
+            .all(|ch| ch.is_ascii_digit() || matches!(ch, '.' | ',' | '\u{2009}'))
+}
+
+fn is_unit(word: &str) -> bool {
+    let unit = word
+        .trim_end_matches(['.', ',', ';', ':', ')', '!', '?'])
+        .to_lowercase();
+    NoBreakConfig::UNITS.contains(&unit.as_str())
+}
+
+fn is_single_letter(word: &str) -> bool {
+    let mut chars = word.chars();
+    matches!((chars.next(), chars.next()), (Some(ch), None) if ch.is_alphabetic())
+}
+
+/// Hanging punctuation policy.
+///
+/// Ratios are the fraction of a mark's advance width that hangs past the
+/// line edge, so `1.0` hangs the whole glyph and `0.0` keeps it inside.
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub struct HangingPunctuationConfig {
-    /// Enable hanging punctuation (currently informational).
+    /// Enable hanging punctuation.
     pub enabled: bool,
+    /// Hang ratio for quotation marks and guillemets.
+    pub quote_ratio: f32,
+    /// Hang ratio for hyphens and dashes.
+    pub hyphen_ratio: f32,
+    /// Hang ratio for commas and periods.
+    pub stop_ratio: f32,
+}
+
+impl Default for HangingPunctuationConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            quote_ratio: 1.0,
+            hyphen_ratio: 0.75,
+            stop_ratio: 0.5,
+        }
+    }
+}
+
+impl HangingPunctuationConfig {
+    /// Fraction of `ch`'s width that hangs into the margin, or `0.0`.
+    pub fn hang_ratio(&self, ch: char) -> f32 {
+        if !self.enabled {
+            return 0.0;
+        }
+        let ratio = match ch {
+            '"' | '\'' | '\u{201C}' | '\u{201D}' | '\u{2018}' | '\u{2019}' | '\u{201E}'
+            | '\u{00AB}' | '\u{00BB}' | '\u{2039}' | '\u{203A}' => self.quote_ratio,
+            '-' | '\u{2010}' | '\u{2011}' | '\u{2013}' | '\u{2014}' => self.hyphen_ratio,
+            ',' | '.' | '\u{3001}' | '\u{3002}' => self.stop_ratio,
+            _ => 0.0,
+        };
+        ratio.clamp(0.0, 1.0)
+    }
 }
 
 /// Non-text object layout policy knobs.
@@ -494,10 +1371,19 @@ pub struct ObjectLayoutConfig {
     pub max_inline_image_height_ratio: f32,
     /// Enable/disable float placement.
     pub float_support: FloatSupport,
+    /// Widest float, as a fraction of the content width; wider floated
+    /// images are laid out as blocks.
+    pub max_float_width_ratio: f32,
+    /// Gap between a float and the text wrapping around it.
+    pub float_gap_px: i32,
     /// SVG placement mode.
     pub svg_mode: SvgMode,
     /// Emit alt-text fallback when object drawing is unavailable.
     pub alt_text_fallback: bool,
+    /// `<hr>` rule length as a fraction of the content width.
+    pub hr_width_ratio: f32,
+    /// Font size multiplier applied to figure captions.
+    pub caption_font_scale: f32,
 }
 
 impl Default for ObjectLayoutConfig {
@@ -505,15 +1391,22 @@ impl Default for ObjectLayoutConfig {
         Self {
             max_inline_image_height_ratio: 0.5,
             float_support: FloatSupport::None,
+            max_float_width_ratio: 0.5,
+            float_gap_px: 8,
             svg_mode: SvgMode::RasterizeFallback,
             alt_text_fallback: true,
+            hr_width_ratio: 0.5,
+            caption_font_scale: 0.85,
         }
     }
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum FloatSupport {
+    /// Floated images are laid out as blocks (no wrap state kept).
     None,
+    /// Small floated images sit at the left/right edge with text wrapping
+    /// beside them.
     Basic,
 }
 
@@ -523,3 +1416,204 @@ pub enum SvgMode {
     RasterizeFallback,
     Native,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(x: i32, baseline_y: i32, text: &str) -> DrawCommand {
+        DrawCommand::Text(TextCommand {
+            x,
+            baseline_y,
+            text: text.to_string(),
+            font_id: None,
+            style: ResolvedTextStyle {
+                font_id: None,
+                family: "serif".to_string(),
+                weight: 400,
+                italic: false,
+                size_px: 10.0,
+                line_height: 1.2,
+                letter_spacing: 0.0,
+                role: BlockRole::Body,
+                justify_mode: JustifyMode::None,
+                synthesis: FontSynthesis::default(),
+            },
+        })
+    }
+
+    const VIEWPORT: OverlaySize = OverlaySize {
+        width: 200,
+        height: 300,
+    };
+
+    fn page(commands: &[DrawCommand], footer: &str) -> RenderPage {
+        let mut page = RenderPage::new(1);
+        for cmd in commands {
+            page.push_content_command(cmd.clone());
+        }
+        page.push_chrome_command(DrawCommand::PageChrome(PageChromeCommand {
+            kind: PageChromeKind::Footer,
+            text: Some(footer.to_string()),
+            current: None,
+            total: None,
+        }));
+        page.sync_commands();
+        page
+    }
+
+    #[test]
+    fn chrome_text_splits_into_literal_and_draw_time_segments() {
+        let chrome = PageChromeCommand {
+            kind: PageChromeKind::Footer,
+            text: Some(PageChromeConfig::expand_template(
+                "{page}/{total} {battery}{custom}",
+                2,
+                9,
+                None,
+            )),
+            current: None,
+            total: None,
+        };
+        assert_eq!(
+            chrome.segments().collect::<Vec<_>>(),
+            vec![
+                ChromeSegment::Literal("2/9 "),
+                ChromeSegment::Field(ChromeField::Battery),
+                ChromeSegment::Field(ChromeField::Custom),
+            ]
+        );
+        assert!(chrome.is_dynamic());
+        let data = ChromeData {
+            battery: Some(130),
+            custom: Some(" synced"),
+            ..ChromeData::default()
+        };
+        assert_eq!(
+            chrome.resolve_text(&data).as_deref(),
+            Some("2/9 100% synced")
+        );
+        assert_eq!(
+            chrome.resolve_text(&ChromeData::default()).as_deref(),
+            Some("2/9 ")
+        );
+
+        let plain = PageChromeCommand {
+            text: Some("{clock".to_string()),
+            ..chrome
+        };
+        assert!(!plain.is_dynamic());
+        assert_eq!(
+            plain.segments().collect::<Vec<_>>(),
+            vec![ChromeSegment::Literal("{clock")]
+        );
+    }
+
+    #[test]
+    fn diff_reports_only_changed_commands() {
+        let same = text(0, 20, "unchanged");
+        let before = page(&[same.clone(), text(0, 40, "old")], "12:00");
+        let after = page(&[same, text(0, 40, "new!")], "12:00");
+        assert_eq!(
+            after.diff(&before, Some(VIEWPORT)),
+            vec![OverlayRect {
+                x: 0,
+                y: 30,
+                width: 40,
+                height: 14,
+            }]
+        );
+        assert!(before.diff(&before, None).is_empty());
+    }
+
+    #[test]
+    fn diff_with_chrome_covers_footer_band() {
+        let body = [text(0, 20, "body")];
+        let before = page(&body, "12:00");
+        let after = page(&body, "12:01");
+        // Without chrome geometry, a changed footer repaints the page.
+        assert_eq!(
+            after.diff(&before, Some(VIEWPORT)),
+            vec![OverlayRect {
+                x: 0,
+                y: 0,
+                width: 200,
+                height: 300,
+            }]
+        );
+        assert_eq!(
+            after.diff(&before, None),
+            vec![OverlayRect {
+                x: 0,
+                y: 0,
+                width: i32::MAX as u32,
+                height: i32::MAX as u32,
+            }]
+        );
+
+        let chrome = PageChromeConfig::geometry_defaults();
+        assert_eq!(
+            after.diff_with_chrome(&before, &chrome, VIEWPORT),
+            vec![OverlayRect {
+                x: 0,
+                y: 277,
+                width: 200,
+                height: 19,
+            }]
+        );
+    }
+
+    #[test]
+    fn touching_damage_merges() {
+        let a = text(0, 20, "aa");
+        let b = text(20, 20, "bb");
+        let before = page(&[], "f");
+        let after = page(&[a, b], "f");
+        assert_eq!(after.diff(&before, Some(VIEWPORT)).len(), 1);
+    }
+
+    #[test]
+    fn justification_strategies_distribute_slack() {
+        let words = JustificationConfig {
+            min_words: 2,
+            ..JustificationConfig::default()
+        };
+        assert_eq!(
+            words.justify_mode(10, 3, 2, 20),
+            JustifyMode::InterWord { extra_px_total: 10 }
+        );
+        assert_eq!(words.justify_mode(10, 1, 0, 9), JustifyMode::None);
+
+        let letters = JustificationConfig {
+            strategy: JustificationStrategy::InterCharacter,
+            ..words
+        };
+        assert_eq!(
+            letters.justify_mode(10, 1, 0, 9),
+            JustifyMode::InterCharacter { extra_px_total: 10 }
+        );
+        assert_eq!(letters.justify_mode(10, 1, 0, 1), JustifyMode::None);
+
+        let adaptive = JustificationConfig {
+            strategy: JustificationStrategy::Adaptive,
+            max_word_extra_px: 3.0,
+            ..words
+        };
+        assert_eq!(
+            adaptive.justify_mode(5, 3, 2, 20),
+            JustifyMode::InterWord { extra_px_total: 5 }
+        );
+        assert_eq!(
+            adaptive.justify_mode(10, 3, 2, 20),
+            JustifyMode::InterWordAndCharacter {
+                word_px_total: 6,
+                char_px_total: 4,
+            }
+        );
+        // Spaceless lines (single words, CJK runs) fall back to letter spacing.
+        assert_eq!(
+            adaptive.justify_mode(10, 1, 0, 12),
+            JustifyMode::InterCharacter { extra_px_total: 10 }
+        );
+    }
+}
@@ -1,11 +1,22 @@
-use mu_epub::{BlockRole, ComputedTextStyle, StyledEvent, StyledEventOrRun, StyledRun};
+use std::fmt;
+use std::sync::Arc;
+
+use mu_epub::{
+    BlockRole, ComputedTextStyle, Dimension, Float, FontSynthesis, ListStyleType, StyledEvent,
+    StyledEventOrRun, StyledRun,
+};
 
 use crate::render_ir::{
-    DrawCommand, JustifyMode, ObjectLayoutConfig, PageChromeCommand, PageChromeConfig,
-    PageChromeKind, RenderIntent, RenderPage, ResolvedTextStyle, TextCommand, TypographyConfig,
+    DrawCommand, FloatSupport, ImageDecodeHint, JustificationConfig, JustifyMode,
+    ObjectLayoutConfig, OverlayRect, PageAnnotation, PageChromeCommand, PageChromeConfig,
+    PageChromeKind, RectCommand, RenderIntent, RenderPage, ResolvedTextStyle, RuleCommand,
+    TextCommand, TypographyConfig, WordBox, IMAGE_ANNOTATION, PRINT_PAGE_ANNOTATION,
 };
 
 const SOFT_HYPHEN: char = '\u{00AD}';
+/// Minimum size ratio between a paragraph's opening letter and its text for
+/// the letter to count as a styled drop cap.
+const STYLED_INITIAL_SIZE_RATIO: f32 = 1.5;
 
 /// Policy for discretionary soft-hyphen handling.
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -16,6 +27,19 @@ pub enum SoftHyphenPolicy {
     Discretionary,
 }
 
+/// How laid-out content is divided for display.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LayoutMode {
+    /// Break content into display-sized pages.
+    #[default]
+    Paginated,
+    /// Lay content out on one tall surface for continuous scrolling.
+    ///
+    /// Page breaks, page-list breaks, and page chrome are skipped; see
+    /// [`LayoutEngine::layout_scroll`].
+    Scroll,
+}
+
 /// Layout configuration for page construction.
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub struct LayoutConfig {
@@ -51,6 +75,8 @@ pub struct LayoutConfig {
     pub min_line_height_px: i32,
     /// Maximum final line height in px.
     pub max_line_height_px: i32,
+    /// Snap text baselines to multiples of this many px (`0` disables).
+    pub baseline_grid_px: i32,
     /// Soft-hyphen handling policy.
     pub soft_hyphen_policy: SoftHyphenPolicy,
     /// Page chrome emission policy.
@@ -61,6 +87,15 @@ pub struct LayoutConfig {
     pub object_layout: ObjectLayoutConfig,
     /// Theme/render intent surface.
     pub render_intent: RenderIntent,
+    /// Break pages at the publisher page-list anchors given to
+    /// [`crate::LayoutSession::set_page_list_anchors`], so page numbers
+    /// match the print edition. Long print pages still break where they
+    /// overflow.
+    pub page_list_breaks: bool,
+    /// Record [`RenderPage::word_boxes`] for each laid-out word.
+    pub word_boxes: bool,
+    /// Paginated or continuous-scroll output.
+    pub mode: LayoutMode,
 }
 
 impl LayoutConfig {
@@ -78,7 +113,15 @@ impl LayoutConfig {
     }
 
     fn content_bottom(self) -> i32 {
-        self.display_height - self.margin_bottom
+        match self.mode {
+            LayoutMode::Paginated => self.display_height - self.margin_bottom,
+            LayoutMode::Scroll => i32::MAX / 2,
+        }
+    }
+
+    /// Height of the content area on one screen, in either mode.
+    fn viewport_content_height(self) -> i32 {
+        self.display_height - self.margin_top - self.margin_bottom
     }
 }
 
@@ -101,19 +144,229 @@ impl Default for LayoutConfig {
             justify_min_fill_ratio: 0.75,
             min_line_height_px: 14,
             max_line_height_px: 48,
+            baseline_grid_px: 0,
             soft_hyphen_policy: SoftHyphenPolicy::Discretionary,
             page_chrome: PageChromeConfig::default(),
             typography: TypographyConfig::default(),
             object_layout: ObjectLayoutConfig::default(),
             render_intent: RenderIntent::default(),
+            page_list_breaks: false,
+            word_boxes: false,
+            mode: LayoutMode::Paginated,
+        }
+    }
+}
+
+/// Vertical font metrics in px for one resolved style, measured from the
+/// baseline.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct VerticalMetrics {
+    /// Distance from the baseline to the top of the tallest glyphs.
+    pub ascent_px: f32,
+    /// Distance from the baseline to the bottom of the deepest glyphs.
+    pub descent_px: f32,
+}
+
+/// Backend text measurement used by layout in place of its built-in
+/// width estimate.
+pub trait TextMeasurer: Send + Sync {
+    /// Advance width of `text` in px.
+    fn measure_text_px(&self, text: &str, style: &ResolvedTextStyle) -> f32;
+
+    /// Ascent and descent for `style`, when the backend knows them.
+    ///
+    /// Lines are then sized from their tallest glyphs plus the style's
+    /// half-leading instead of from the line-height multiplier alone.
+    fn vertical_metrics(&self, _style: &ResolvedTextStyle) -> Option<VerticalMetrics> {
+        None
+    }
+}
+
+/// Optional shared measurer, falling back to the built-in estimate.
+#[derive(Clone, Default)]
+struct Measurer(Option<Arc<dyn TextMeasurer>>);
+
+impl fmt::Debug for Measurer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Measurer").field(&self.0.is_some()).finish()
+    }
+}
+
+impl Measurer {
+    fn text_px(&self, text: &str, style: &ResolvedTextStyle) -> f32 {
+        match &self.0 {
+            Some(measurer) => measurer.measure_text_px(text, style),
+            None => measure_text(text, style),
         }
     }
+
+    /// Ascent and total height of the line box for `style`.
+    fn line_box(&self, style: &ResolvedTextStyle, cfg: &LayoutConfig) -> (i32, i32) {
+        let height = line_height_px(style, cfg);
+        let Some(metrics) = self.0.as_ref().and_then(|m| m.vertical_metrics(style)) else {
+            return (0, height);
+        };
+        let content = metrics.ascent_px + metrics.descent_px;
+        let half_leading = (height as f32 - content) / 2.0;
+        let ascent = (metrics.ascent_px + half_leading).ceil() as i32;
+        let descent = (metrics.descent_px + half_leading).ceil() as i32;
+        (ascent.max(0), (ascent + descent).max(1))
+    }
 }
 
 /// Deterministic layout engine that emits render pages.
 #[derive(Clone, Debug)]
 pub struct LayoutEngine {
     cfg: LayoutConfig,
+    measurer: Measurer,
+}
+
+/// Bounded box for [`LayoutEngine::layout_snippet`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SnippetBox {
+    /// Box position and size on the display.
+    pub rect: OverlayRect,
+    /// Inner padding on every side in px.
+    pub padding_px: i32,
+}
+
+impl SnippetBox {
+    fn inner_height(self) -> i32 {
+        (self.rect.height as i32 - 2 * self.padding_px.max(0)).max(0)
+    }
+}
+
+/// Short rich text wrapped to a [`SnippetBox`], independent of any page.
+///
+/// Lines are kept in content coordinates so scrolling only re-selects and
+/// shifts commands; [`Self::commands`] never reflows.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SnippetLayout {
+    area: SnippetBox,
+    /// Line top, line bottom, and text command, relative to the content top.
+    lines: Vec<(i32, i32, DrawCommand)>,
+}
+
+impl SnippetLayout {
+    /// Box the snippet was laid out into.
+    pub fn area(&self) -> SnippetBox {
+        self.area
+    }
+
+    /// Number of wrapped lines.
+    pub fn line_count(&self) -> usize {
+        self.lines.len()
+    }
+
+    /// Height of all wrapped lines in px.
+    pub fn content_height(&self) -> i32 {
+        self.lines.last().map_or(0, |(_, bottom, _)| *bottom)
+    }
+
+    /// Largest useful scroll offset in px.
+    pub fn max_scroll_y(&self) -> i32 {
+        (self.content_height() - self.area.inner_height()).max(0)
+    }
+
+    /// Scroll offsets that bring each line to the top of the box.
+    pub fn line_offsets(&self) -> impl Iterator<Item = i32> + '_ {
+        let max = self.max_scroll_y();
+        self.lines.iter().map(move |(top, _, _)| (*top).min(max))
+    }
+
+    /// Draw commands for the lines fully visible at `scroll_y`, in display
+    /// coordinates. `scroll_y` is clamped to `0..=max_scroll_y()`.
+    pub fn commands(&self, scroll_y: i32) -> Vec<DrawCommand> {
+        let scroll_y = scroll_y.clamp(0, self.max_scroll_y());
+        let visible = scroll_y..=scroll_y + self.area.inner_height();
+        let dy = self.area.rect.y + self.area.padding_px.max(0) - scroll_y;
+        self.lines
+            .iter()
+            .filter(|(top, bottom, _)| visible.contains(top) && visible.contains(bottom))
+            .map(|(_, _, cmd)| {
+                let mut cmd = cmd.clone();
+                shift_command_y(&mut cmd, dy);
+                if let DrawCommand::Text(text) = &mut cmd {
+                    text.x += self.area.rect.x;
+                }
+                cmd
+            })
+            .collect()
+    }
+}
+
+/// Chapter content laid out on one tall virtual surface for
+/// [`LayoutMode::Scroll`].
+///
+/// Commands keep surface coordinates; [`Self::render_band`] selects and
+/// shifts the ones a viewport-sized band shows, so scrolling never reflows.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ScrollSurface {
+    width: i32,
+    height: i32,
+    /// Command top, command bottom, and command, in surface coordinates.
+    commands: Vec<(i32, i32, DrawCommand)>,
+}
+
+impl ScrollSurface {
+    /// Collect the content of scroll-mode pages into one surface.
+    ///
+    /// Scroll-mode layout emits a single page per chapter; later pages, if
+    /// any, are stacked below it.
+    pub fn from_pages(pages: impl IntoIterator<Item = RenderPage>, cfg: &LayoutConfig) -> Self {
+        let mut surface = Self {
+            width: cfg.display_width,
+            height: 0,
+            commands: Vec::with_capacity(0),
+        };
+        for page in pages {
+            let dy = surface.height;
+            let mut bottom = dy;
+            for mut cmd in page.content_commands {
+                shift_command_y(&mut cmd, dy);
+                let Some(bounds) = cmd.bounds() else {
+                    continue;
+                };
+                bottom = bottom.max(bounds.bottom());
+                surface.commands.push((bounds.y, bounds.bottom(), cmd));
+            }
+            surface.height = bottom + cfg.margin_bottom;
+        }
+        surface
+    }
+
+    /// Surface width in px.
+    pub fn width(&self) -> i32 {
+        self.width
+    }
+
+    /// Total surface height in px, including the bottom margin.
+    pub fn height(&self) -> i32 {
+        self.height
+    }
+
+    /// Largest useful band offset for a viewport `viewport_height` px tall.
+    pub fn max_scroll_y(&self, viewport_height: i32) -> i32 {
+        (self.height - viewport_height).max(0)
+    }
+
+    /// Draw commands overlapping the band `y_offset..y_offset + height`,
+    /// shifted so the band's top is at `y = 0`.
+    ///
+    /// Commands straddling an edge are included in both bands and clipped
+    /// by the display.
+    pub fn render_band(&self, y_offset: i32, height: i32) -> Vec<DrawCommand> {
+        let band_bottom = y_offset.saturating_add(height.max(0));
+        self.commands
+            .iter()
+            .filter(|(top, bottom, _)| *bottom > y_offset && *top < band_bottom)
+            .map(|(_, _, cmd)| {
+                let mut cmd = cmd.clone();
+                shift_command_y(&mut cmd, -y_offset);
+                cmd
+            })
+            .collect()
+    }
 }
 
 /// Incremental layout session for streaming styled items into pages.
@@ -121,12 +374,24 @@ pub struct LayoutSession {
     engine: LayoutEngine,
     st: LayoutState,
     ctx: BlockCtx,
+    chapter_title: Option<String>,
+    /// Page-list anchors as `(anchor id, print page label)`.
+    page_list: Vec<(String, String)>,
 }
 
 impl LayoutEngine {
     /// Create a layout engine.
     pub fn new(cfg: LayoutConfig) -> Self {
-        Self { cfg }
+        Self {
+            cfg,
+            measurer: Measurer::default(),
+        }
+    }
+
+    /// Measure text with `measurer` instead of the built-in estimate.
+    pub fn with_text_measurer(mut self, measurer: Arc<dyn TextMeasurer>) -> Self {
+        self.measurer = Measurer(Some(measurer));
+        self
     }
 
     /// Layout styled items into pages.
@@ -139,12 +404,83 @@ impl LayoutEngine {
         pages
     }
 
+    /// Layout styled items onto one tall [`ScrollSurface`], regardless of
+    /// the configured [`LayoutConfig::mode`].
+    pub fn layout_scroll<I>(&self, items: I) -> ScrollSurface
+    where
+        I: IntoIterator<Item = StyledEventOrRun>,
+    {
+        let engine = self.with_mode(LayoutMode::Scroll);
+        ScrollSurface::from_pages(engine.layout_items(items), &engine.cfg)
+    }
+
+    /// Copy of this engine laying out in `mode`.
+    pub(crate) fn with_mode(&self, mode: LayoutMode) -> Self {
+        Self {
+            cfg: LayoutConfig { mode, ..self.cfg },
+            measurer: self.measurer.clone(),
+        }
+    }
+
     /// Start an incremental layout session.
     pub fn start_session(&self) -> LayoutSession {
         LayoutSession {
             engine: self.clone(),
-            st: LayoutState::new(self.cfg),
+            st: LayoutState::new(self.cfg, self.measurer.clone()),
             ctx: BlockCtx::default(),
+            chapter_title: None,
+            page_list: Vec::with_capacity(0),
+        }
+    }
+
+    /// Wrap `runs` into `area` with this engine's typography.
+    ///
+    /// Margins, first-line indent, the baseline grid, and page chrome are
+    /// replaced by the box geometry; the text never breaks across pages.
+    pub fn layout_snippet(&self, runs: &[StyledRun], area: SnippetBox) -> SnippetLayout {
+        let padding = area.padding_px.max(0);
+        let cfg = LayoutConfig {
+            display_width: area.rect.width as i32,
+            display_height: i32::MAX / 2,
+            margin_left: padding,
+            margin_right: padding,
+            margin_top: 0,
+            margin_bottom: 0,
+            first_line_indent_px: 0,
+            baseline_grid_px: 0,
+            page_list_breaks: false,
+            ..self.cfg
+        };
+        let engine = Self {
+            cfg,
+            measurer: self.measurer.clone(),
+        };
+        let mut st = LayoutState::new(cfg, self.measurer.clone());
+        st.line_boxes = Some(Vec::with_capacity(4));
+        let mut ctx = BlockCtx::default();
+        for run in runs {
+            engine.handle_run(&mut st, &mut ctx, run.clone());
+        }
+        st.flush_line(true);
+
+        let boxes = st.line_boxes.take().unwrap_or_default();
+        // Estimated metrics can put the first line's top above zero.
+        let origin = boxes.first().map_or(0, |(top, _)| *top);
+        let texts = st
+            .page
+            .content_commands
+            .into_iter()
+            .filter(|cmd| matches!(cmd, DrawCommand::Text(_)));
+        SnippetLayout {
+            area,
+            lines: boxes
+                .into_iter()
+                .zip(texts)
+                .map(|((top, bottom), mut cmd)| {
+                    shift_command_y(&mut cmd, -origin);
+                    (top - origin, bottom - origin, cmd)
+                })
+                .collect(),
         }
     }
 
@@ -162,19 +498,92 @@ impl LayoutEngine {
     }
 
     fn handle_run(&self, st: &mut LayoutState, ctx: &mut BlockCtx, run: StyledRun) {
+        ctx.trailing_gap = 0;
         let mut style = to_resolved_style(&run.style);
         style.font_id = Some(run.font_id);
         if !run.resolved_family.is_empty() {
             style.family = run.resolved_family.clone();
         }
+        style.synthesis = run.synthesis;
         if let Some(level) = ctx.heading_level {
             style.role = BlockRole::Heading(level);
         }
         if ctx.in_list {
             style.role = BlockRole::ListItem;
         }
+        if ctx.in_caption {
+            style.role = BlockRole::Caption;
+            style.italic = true;
+            style.size_px *= self.cfg.object_layout.caption_font_scale;
+        }
+
+        let drop_caps = self.cfg.typography.drop_caps;
+        let is_body = matches!(style.role, BlockRole::Body | BlockRole::Paragraph)
+            && !ctx.in_list
+            && ctx.heading_level.is_none();
+        if let Some(marker) = ctx.pending_marker.take() {
+            st.push_word(&marker, style.clone(), 0);
+        }
+        let mut text = run.text.as_str();
+        if let Some((initial, initial_style)) = ctx.styled_initial.take() {
+            if is_body
+                && initial_style.size_px >= style.size_px * STYLED_INITIAL_SIZE_RATIO
+                && st.start_drop_cap(initial.trim(), initial_style.clone(), &style)
+            {
+                ctx.pending_indent = false;
+            } else {
+                self.push_words(st, ctx, &initial, initial_style);
+            }
+        } else if ctx.at_block_start && is_body {
+            if drop_caps.detect_styled_initials && is_initial_letter(text) {
+                ctx.at_block_start = false;
+                ctx.drop_cap_opener = false;
+                ctx.styled_initial = Some((run.text, style));
+                return;
+            }
+            if ctx.drop_cap_opener {
+                if let Some((initial, rest)) = split_initial_letter(text) {
+                    if st.start_drop_cap(initial, style.clone(), &style) {
+                        ctx.pending_indent = false;
+                        text = rest;
+                    }
+                }
+            }
+        }
+        ctx.at_block_start = false;
+        ctx.drop_cap_opener = false;
+        if is_body {
+            ctx.seen_body = true;
+        }
+        self.push_words(st, ctx, text, style);
+    }
 
-        for word in run.text.split_whitespace() {
+    fn push_words(
+        &self,
+        st: &mut LayoutState,
+        ctx: &mut BlockCtx,
+        text: &str,
+        style: ResolvedTextStyle,
+    ) {
+        let no_break = self.cfg.typography.no_break;
+        let mut words = text.split_whitespace().peekable();
+        let mut joined = String::with_capacity(0);
+        while let Some(word) = words.next() {
+            // Words that must share a line are laid out as one unit.
+            if words
+                .peek()
+                .is_some_and(|next| no_break.keeps_together(word, next))
+            {
+                joined.push_str(word);
+                joined.push(' ');
+                continue;
+            }
+            let word = if joined.is_empty() {
+                word
+            } else {
+                joined.push_str(word);
+                joined.as_str()
+            };
             let mut extra_indent_px = 0;
             if ctx.pending_indent
                 && matches!(style.role, BlockRole::Body | BlockRole::Paragraph)
@@ -185,56 +594,216 @@ impl LayoutEngine {
                 ctx.pending_indent = false;
             }
             st.push_word(word, style.clone(), extra_indent_px);
+            joined.clear();
         }
     }
 
     fn handle_event(&self, st: &mut LayoutState, ctx: &mut BlockCtx, ev: StyledEvent) {
+        if !matches!(
+            ev,
+            StyledEvent::Anchor(_)
+                | StyledEvent::NoteRef { .. }
+                | StyledEvent::ScriptedContent
+                | StyledEvent::LinkStart(_)
+                | StyledEvent::LinkEnd
+                | StyledEvent::BlockMargins { .. }
+        ) {
+            if let Some((initial, style)) = ctx.styled_initial.take() {
+                self.push_words(st, ctx, &initial, style);
+            }
+        }
+        if matches!(
+            ev,
+            StyledEvent::ParagraphEnd
+                | StyledEvent::HeadingStart(_)
+                | StyledEvent::ListStart { .. }
+                | StyledEvent::BlockquoteStart(_)
+                | StyledEvent::BlockquoteEnd(_)
+                | StyledEvent::ListEnd
+                | StyledEvent::ListItemStart
+                | StyledEvent::Hr
+                | StyledEvent::Image { .. }
+                | StyledEvent::FigureStart
+                | StyledEvent::CaptionStart
+        ) {
+            st.flush_line(true);
+            st.end_drop_cap();
+        }
         match ev {
+            StyledEvent::BlockBreak(_) => {
+                // Layout is single-column, so column and region breaks fall
+                // back to page breaks; scrolling has no pages to break.
+                st.flush_line(true);
+                if self.cfg.mode == LayoutMode::Paginated && !st.page.content_commands.is_empty() {
+                    st.start_next_page();
+                    ctx.trailing_gap = 0;
+                }
+            }
+            StyledEvent::BlockMargins { top, bottom } => {
+                let to_px = |margin: u32| i32::try_from(margin).unwrap_or(i32::MAX);
+                ctx.pending_margins = Some((top.map(to_px), bottom.map(to_px)));
+            }
             StyledEvent::ParagraphStart => {
+                let (top, bottom) = ctx.pending_margins.take().unwrap_or_default();
+                if let Some(top) = top {
+                    // Adjoining vertical margins collapse to the larger one.
+                    st.flush_line(true);
+                    st.add_vertical_gap(top - ctx.trailing_gap);
+                }
+                ctx.margin_bottoms.push(bottom);
                 if !ctx.suppress_next_indent {
                     ctx.pending_indent = true;
                 }
                 ctx.suppress_next_indent = false;
+                ctx.at_block_start = true;
+                ctx.drop_cap_opener = self.cfg.typography.drop_caps.enabled && !ctx.seen_body;
             }
             StyledEvent::ParagraphEnd => {
                 st.flush_line(true);
-                st.add_vertical_gap(self.cfg.paragraph_gap_px);
+                let gap = ctx
+                    .margin_bottoms
+                    .pop()
+                    .flatten()
+                    .unwrap_or(self.cfg.paragraph_gap_px);
+                st.add_vertical_gap(gap);
+                ctx.trailing_gap = gap;
+                st.release_float();
                 ctx.pending_indent = true;
             }
             StyledEvent::HeadingStart(level) => {
                 st.flush_line(true);
-                st.add_vertical_gap(self.cfg.heading_gap_px);
+                st.clear_float();
+                let (top, bottom) = ctx.pending_margins.take().unwrap_or_default();
+                ctx.margin_bottoms.push(bottom);
+                let spacing = self.cfg.typography.heading_spacing;
+                st.add_vertical_gap(match top {
+                    Some(top) => top - ctx.trailing_gap,
+                    None => spacing.above(level, self.cfg.heading_gap_px),
+                });
+                st.open_keep_heading();
                 ctx.heading_level = Some(level.clamp(1, 6));
                 ctx.pending_indent = false;
             }
-            StyledEvent::HeadingEnd(_) => {
+            StyledEvent::HeadingEnd(level) => {
                 st.flush_line(true);
-                st.add_vertical_gap(self.cfg.heading_gap_px);
+                st.close_keep_heading();
+                let spacing = self.cfg.typography.heading_spacing;
+                let gap = ctx
+                    .margin_bottoms
+                    .pop()
+                    .flatten()
+                    .unwrap_or(spacing.below(level, self.cfg.heading_gap_px));
+                st.add_vertical_gap(gap);
+                ctx.trailing_gap = gap;
                 ctx.heading_level = None;
                 ctx.pending_indent = false;
                 ctx.suppress_next_indent = self.cfg.suppress_indent_after_heading;
             }
+            StyledEvent::ListStart { style, start } => {
+                ctx.lists.push((style, start.saturating_sub(1)));
+                ctx.pending_marker = None;
+                ctx.pending_indent = false;
+            }
+            StyledEvent::ListEnd => {
+                ctx.lists.pop();
+                ctx.pending_indent = true;
+            }
+            StyledEvent::ListItemValue(value) => {
+                if let Some((_, ordinal)) = ctx.lists.last_mut() {
+                    *ordinal = value.saturating_sub(1);
+                }
+            }
             StyledEvent::ListItemStart => {
                 st.flush_line(true);
                 ctx.in_list = true;
                 ctx.pending_indent = false;
+                ctx.pending_marker = ctx.lists.last_mut().and_then(|(style, ordinal)| {
+                    *ordinal = ordinal.saturating_add(1);
+                    let marker = style.marker(*ordinal);
+                    let marker = marker.trim();
+                    (!marker.is_empty()).then(|| marker.to_string())
+                });
             }
             StyledEvent::ListItemEnd => {
+                ctx.pending_marker = None;
                 st.flush_line(true);
                 st.add_vertical_gap(self.cfg.paragraph_gap_px.saturating_sub(2));
                 ctx.in_list = false;
                 ctx.pending_indent = true;
             }
+            StyledEvent::BlockquoteStart(_) => {
+                st.open_quote();
+                ctx.pending_indent = false;
+            }
+            StyledEvent::BlockquoteEnd(_) => {
+                st.close_quote();
+                ctx.pending_indent = true;
+            }
             StyledEvent::LineBreak => {
                 st.flush_line(false);
                 ctx.pending_indent = false;
             }
+            StyledEvent::Anchor(_)
+            | StyledEvent::NoteRef { .. }
+            | StyledEvent::ScriptedContent
+            | StyledEvent::LinkStart(_)
+            | StyledEvent::LinkEnd => {}
+            StyledEvent::Hr => {
+                ctx.trailing_gap = 0;
+                st.flush_line(true);
+                st.clear_float();
+                st.push_rule(self.cfg.object_layout.hr_width_ratio);
+                ctx.pending_indent = false;
+            }
+            StyledEvent::Image {
+                src,
+                width,
+                height,
+                float,
+                ..
+            } => {
+                ctx.trailing_gap = 0;
+                st.clear_float();
+                if !st.push_float(&src, float, width, height) {
+                    st.push_image_box(&src, width, height);
+                }
+            }
+            StyledEvent::FigureStart => {
+                st.flush_line(true);
+                st.clear_float();
+                st.open_keep_block();
+                ctx.pending_indent = false;
+            }
+            StyledEvent::FigureEnd => {
+                st.flush_line(true);
+                // The figure and its caption are placed; nothing further is kept with them.
+                st.keep_anchor = None;
+                st.add_vertical_gap(self.cfg.paragraph_gap_px);
+                ctx.pending_indent = true;
+            }
+            StyledEvent::CaptionStart => {
+                st.flush_line(true);
+                ctx.in_caption = true;
+                ctx.pending_indent = false;
+            }
+            StyledEvent::CaptionEnd => {
+                st.flush_line(true);
+                ctx.in_caption = false;
+            }
         }
     }
 }
 
 impl LayoutSession {
     fn push_item_impl(&mut self, item: StyledEventOrRun) {
+        if let StyledEventOrRun::Event(StyledEvent::Anchor(id)) = &item {
+            if self.engine.cfg.page_list_breaks && self.engine.cfg.mode == LayoutMode::Paginated {
+                if let Some((_, label)) = self.page_list.iter().find(|(anchor, _)| anchor == id) {
+                    self.st.flush_line(true);
+                    self.st.start_print_page(label.clone());
+                }
+            }
+        }
         match item {
             StyledEventOrRun::Run(run) => self.engine.handle_run(&mut self.st, &mut self.ctx, run),
             StyledEventOrRun::Event(ev) => {
@@ -243,6 +812,21 @@ impl LayoutSession {
         }
     }
 
+    /// Set the chapter title shown through the `{chapter}` chrome template.
+    pub fn set_chapter_title(&mut self, title: Option<String>) {
+        self.chapter_title = title;
+    }
+
+    /// Set the chapter's publisher page-list anchors as
+    /// `(anchor id, print page label)` pairs.
+    ///
+    /// With [`LayoutConfig::page_list_breaks`], each anchor starts a new page
+    /// annotated with its label; pages that overflow a print page keep the
+    /// label of the print page they continue.
+    pub fn set_page_list_anchors(&mut self, anchors: Vec<(String, String)>) {
+        self.page_list = anchors;
+    }
+
     /// Push one styled item into the layout state.
     pub fn push_item(&mut self, item: StyledEventOrRun) {
         self.push_item_impl(item);
@@ -264,9 +848,13 @@ impl LayoutSession {
     where
         F: FnMut(RenderPage),
     {
+        if let Some((initial, style)) = self.ctx.styled_initial.take() {
+            self.engine
+                .push_words(&mut self.st, &mut self.ctx, &initial, style);
+        }
         self.st.flush_line(true);
         let mut pages = core::mem::take(&mut self.st).into_pages();
-        annotate_page_chrome(&mut pages, self.engine.cfg);
+        annotate_page_chrome(&mut pages, self.engine.cfg, self.chapter_title.as_deref());
         for page in pages {
             on_page(page);
         }
@@ -277,8 +865,28 @@ impl LayoutSession {
 struct BlockCtx {
     heading_level: Option<u8>,
     in_list: bool,
+    in_caption: bool,
     pending_indent: bool,
     suppress_next_indent: bool,
+    /// No run has been laid out since the paragraph started.
+    at_block_start: bool,
+    /// The current paragraph opens the chapter and gets a configured drop cap.
+    drop_cap_opener: bool,
+    /// Body text has been laid out in this session.
+    seen_body: bool,
+    /// Enlarged opening letter held until the paragraph text's size is known.
+    styled_initial: Option<(String, ResolvedTextStyle)>,
+    /// Open lists with their marker style and last item ordinal.
+    lists: Vec<(ListStyleType, i32)>,
+    /// Marker for the current list item, placed before its first run.
+    pending_marker: Option<String>,
+    /// Author margins for the next paragraph or heading start.
+    pending_margins: Option<(Option<i32>, Option<i32>)>,
+    /// Bottom margins of open paragraphs and headings, innermost last.
+    margin_bottoms: Vec<Option<i32>>,
+    /// Gap added by the last block end with no content since, which a
+    /// following author top margin collapses into.
+    trailing_gap: i32,
 }
 
 #[derive(Clone, Debug)]
@@ -287,34 +895,144 @@ struct CurrentLine {
     style: ResolvedTextStyle,
     width_px: f32,
     line_height_px: i32,
+    /// Line box height above the baseline; `0` places the baseline at the
+    /// top of the box.
+    ascent_px: i32,
     left_inset_px: i32,
+    /// Width given up at the right edge to a float.
+    right_inset_px: i32,
+    /// The line ends in a hyphen inserted at a soft-hyphen break.
+    hyphenated: bool,
+}
+
+/// Heading block that must stay on the same page as the lines following it.
+#[derive(Clone, Copy, Debug)]
+struct KeepAnchor {
+    /// Index of the heading's first content command on the current page.
+    start_command: usize,
+    /// Cursor position where the heading starts.
+    start_y: i32,
+    heading_open: bool,
+    body_lines: usize,
+}
+
+/// Dropped initial that following lines wrap around.
+#[derive(Clone, Copy, Debug)]
+struct DropCapWrap {
+    /// Index of the initial's command on the current page.
+    command: usize,
+    /// Horizontal space reserved for the initial and its gap.
+    inset_px: i32,
+    /// Cursor position below the initial; lines above it are inset.
+    bottom_y: i32,
+}
+
+/// Floated image that lines beside it wrap around.
+#[derive(Clone, Copy, Debug)]
+struct FloatWrap {
+    /// Float sits at the right edge rather than the left.
+    right: bool,
+    /// Horizontal space reserved for the float and its gap.
+    inset_px: i32,
+    /// Bottom of the float; lines starting above it are inset.
+    bottom_y: i32,
 }
 
 #[derive(Clone, Debug)]
 struct LayoutState {
     cfg: LayoutConfig,
+    measurer: Measurer,
     page_no: usize,
     cursor_y: i32,
     page: RenderPage,
     line: Option<CurrentLine>,
     emitted: Vec<RenderPage>,
+    keep_anchor: Option<KeepAnchor>,
+    drop_cap: Option<DropCapWrap>,
+    float: Option<FloatWrap>,
+    /// Lines of the current paragraph placed on the current page.
+    para_lines_on_page: usize,
+    /// The current paragraph started on an earlier page.
+    para_continued: bool,
+    /// Vertical extent of lines placed on this page per open blockquote
+    /// level, outermost first.
+    quote_spans: Vec<Option<(i32, i32)>>,
+    /// Print page label in effect, carried onto overflow pages.
+    print_page: Option<String>,
+    /// Top and bottom of each placed line, recorded for snippet layout.
+    line_boxes: Option<Vec<(i32, i32)>>,
 }
 
 impl Default for LayoutState {
     fn default() -> Self {
-        Self::new(LayoutConfig::default())
+        Self::new(LayoutConfig::default(), Measurer::default())
     }
 }
 
 impl LayoutState {
-    fn new(cfg: LayoutConfig) -> Self {
+    fn new(cfg: LayoutConfig, measurer: Measurer) -> Self {
         Self {
             cfg,
+            measurer,
             page_no: 1,
             cursor_y: cfg.margin_top,
             page: RenderPage::new(1),
             line: None,
             emitted: Vec::with_capacity(2),
+            keep_anchor: None,
+            drop_cap: None,
+            float: None,
+            para_lines_on_page: 0,
+            para_continued: false,
+            quote_spans: Vec::with_capacity(0),
+            print_page: None,
+            line_boxes: None,
+        }
+    }
+
+    /// Begin print page `label`, breaking unless the current page is blank.
+    fn start_print_page(&mut self, label: String) {
+        if !self.page.content_commands.is_empty() {
+            self.start_next_page();
+        }
+        self.page
+            .annotations
+            .retain(|annotation| annotation.kind != PRINT_PAGE_ANNOTATION);
+        self.page.annotations.push(PageAnnotation {
+            kind: PRINT_PAGE_ANNOTATION.to_string(),
+            value: Some(label.clone()),
+        });
+        self.print_page = Some(label);
+    }
+
+    fn open_keep_heading(&mut self) {
+        let keep = self.cfg.typography.keep_with_next;
+        if !keep.enabled || keep.min_lines == 0 {
+            return;
+        }
+        self.open_keep_block();
+    }
+
+    /// Start a block whose lines move to the next page together.
+    fn open_keep_block(&mut self) {
+        // Consecutive headings keep together as one block.
+        if let Some(anchor) = self.keep_anchor.as_mut() {
+            if anchor.body_lines == 0 {
+                anchor.heading_open = true;
+                return;
+            }
+        }
+        self.keep_anchor = Some(KeepAnchor {
+            start_command: self.page.content_commands.len(),
+            start_y: self.cursor_y,
+            heading_open: true,
+            body_lines: 0,
+        });
+    }
+
+    fn close_keep_heading(&mut self) {
+        if let Some(anchor) = self.keep_anchor.as_mut() {
+            anchor.heading_open = false;
         }
     }
 
@@ -329,14 +1047,20 @@ impl LayoutState {
             0
         };
         left_inset_px += extra_first_line_indent_px.max(0);
+        left_inset_px += self.quote_inset();
 
         if self.line.is_none() {
+            let (ascent_px, line_height_px) = self.measurer.line_box(&style, &self.cfg);
+            let (wrap_left, wrap_right) = self.wrap_insets();
             self.line = Some(CurrentLine {
                 text: String::with_capacity(64),
                 style: style.clone(),
                 width_px: 0.0,
-                line_height_px: line_height_px(&style, &self.cfg),
-                left_inset_px,
+                line_height_px,
+                ascent_px,
+                left_inset_px: left_inset_px + wrap_left,
+                right_inset_px: wrap_right,
+                hyphenated: false,
             });
         }
 
@@ -346,20 +1070,32 @@ impl LayoutState {
 
         if line.text.is_empty() {
             line.style = style.clone();
-            line.left_inset_px = left_inset_px;
-            line.line_height_px = line_height_px(&style, &self.cfg);
+            let (wrap_left, wrap_right) = self.wrap_insets();
+            line.left_inset_px = left_inset_px + wrap_left;
+            line.right_inset_px = wrap_right;
+            (line.ascent_px, line.line_height_px) = self.measurer.line_box(&style, &self.cfg);
         }
 
         let space_w = if line.text.is_empty() {
             0.0
         } else {
-            measure_text(" ", &line.style)
+            self.measurer.text_px(" ", &line.style)
         };
         let sanitized_word = strip_soft_hyphens(word);
-        let word_w = measure_text(&sanitized_word, &style);
-        let max_width = (self.cfg.content_width() - line.left_inset_px).max(1) as f32;
+        let word_w = self.measurer.text_px(&sanitized_word, &style);
+        // Hanging marks may extend past either edge of the line.
+        let leading_hang = if line.text.is_empty() {
+            self.leading_hang(&sanitized_word, &style)
+        } else {
+            self.leading_hang(&line.text, &line.style)
+        };
+        let max_width = (self.cfg.content_width() - line.left_inset_px - line.right_inset_px).max(1)
+            as f32
+            + leading_hang;
 
-        if line.width_px + space_w + word_w > max_width {
+        if line.width_px + space_w + word_w - self.trailing_hang(&sanitized_word, &style)
+            > max_width
+        {
             if (self.cfg.soft_hyphen_policy == SoftHyphenPolicy::Discretionary
                 || matches!(
                     self.cfg.typography.hyphenation.soft_hyphen_policy,
@@ -379,12 +1115,17 @@ impl LayoutState {
             }
             self.line = Some(line);
             self.flush_line(false);
+            let (ascent_px, line_height_px) = self.measurer.line_box(&style, &self.cfg);
+            let (wrap_left, wrap_right) = self.wrap_insets();
             self.line = Some(CurrentLine {
                 text: sanitized_word,
                 style: style.clone(),
                 width_px: word_w,
-                line_height_px: line_height_px(&style, &self.cfg),
-                left_inset_px,
+                line_height_px,
+                ascent_px,
+                left_inset_px: left_inset_px + wrap_left,
+                right_inset_px: wrap_right,
+                hyphenated: false,
             });
             return;
         }
@@ -395,10 +1136,111 @@ impl LayoutState {
         }
         line.text.push_str(&sanitized_word);
         line.width_px += word_w;
+        // With font metrics, mixed sizes share a line box tall enough for all.
+        let (ascent_px, height_px) = self.measurer.line_box(&style, &self.cfg);
+        if ascent_px > 0 || line.ascent_px > 0 {
+            let descent_px = (height_px - ascent_px).max(line.line_height_px - line.ascent_px);
+            line.ascent_px = line.ascent_px.max(ascent_px);
+            line.line_height_px = line.ascent_px + descent_px;
+        }
         line.style = style;
         self.line = Some(line);
     }
 
+    /// Draw `initial` spanning the configured number of body lines and
+    /// inset the following lines beside it.
+    ///
+    /// Returns `false` when drop caps are configured to span fewer than two
+    /// lines, leaving the initial to be laid out as ordinary text.
+    fn start_drop_cap(
+        &mut self,
+        initial: &str,
+        mut cap_style: ResolvedTextStyle,
+        body_style: &ResolvedTextStyle,
+    ) -> bool {
+        let cfg = self.cfg.typography.drop_caps;
+        if cfg.lines < 2 || initial.is_empty() {
+            return false;
+        }
+        self.flush_line(false);
+        let lines = i32::from(cfg.lines);
+        let (ascent_px, line_height_px) = self.measurer.line_box(body_style, &self.cfg);
+        let step = line_height_px + self.cfg.line_gap_px;
+        if self.cursor_y + lines * step > self.cfg.content_bottom() {
+            self.start_next_page();
+        }
+        // The initial's top aligns with the first line and its baseline with the last.
+        cap_style.size_px = (lines - 1) as f32 * step as f32 + body_style.size_px;
+        cap_style.role = body_style.role;
+        cap_style.justify_mode = JustifyMode::None;
+        let cap_width = self.measurer.text_px(initial, &cap_style).ceil() as i32;
+        self.drop_cap = Some(DropCapWrap {
+            command: self.page.content_commands.len(),
+            inset_px: cap_width + cfg.gap_px.max(0),
+            bottom_y: self.cursor_y + lines * step,
+        });
+        self.page
+            .push_content_command(DrawCommand::Text(TextCommand {
+                x: self.cfg.margin_left,
+                baseline_y: self.cursor_y + ascent_px + (lines - 1) * step,
+                text: initial.to_string(),
+                font_id: cap_style.font_id,
+                style: cap_style,
+            }));
+        self.page.sync_commands();
+        true
+    }
+
+    /// Width of the opening mark of `text` that hangs into the left margin.
+    fn leading_hang(&self, text: &str, style: &ResolvedTextStyle) -> f32 {
+        text.trim_start()
+            .chars()
+            .next()
+            .map_or(0.0, |ch| self.hang_px(ch, style))
+    }
+
+    /// Width of the closing mark of `text` that hangs into the right margin.
+    fn trailing_hang(&self, text: &str, style: &ResolvedTextStyle) -> f32 {
+        text.trim_end()
+            .chars()
+            .next_back()
+            .map_or(0.0, |ch| self.hang_px(ch, style))
+    }
+
+    fn hang_px(&self, ch: char, style: &ResolvedTextStyle) -> f32 {
+        let ratio = self.cfg.typography.hanging_punctuation.hang_ratio(ch);
+        if ratio <= 0.0 {
+            return 0.0;
+        }
+        let mut buf = [0u8; 4];
+        self.measurer.text_px(ch.encode_utf8(&mut buf), style) * ratio
+    }
+
+    /// Extra left/right insets for a line starting at the cursor beside a
+    /// drop cap or float.
+    fn wrap_insets(&self) -> (i32, i32) {
+        let mut left = match self.drop_cap {
+            Some(cap) if self.cursor_y < cap.bottom_y => cap.inset_px,
+            _ => 0,
+        };
+        let mut right = 0;
+        if let Some(float) = self.float.filter(|f| self.cursor_y < f.bottom_y) {
+            if float.right {
+                right = float.inset_px;
+            } else {
+                left += float.inset_px;
+            }
+        }
+        (left, right)
+    }
+
+    /// Close the drop cap, moving below it if the paragraph ended early.
+    fn end_drop_cap(&mut self) {
+        if let Some(cap) = self.drop_cap.take() {
+            self.cursor_y = self.cursor_y.max(cap.bottom_y);
+        }
+    }
+
     fn try_break_word_at_soft_hyphen(
         &mut self,
         line: &mut CurrentLine,
@@ -420,13 +1262,13 @@ impl LayoutState {
                 continue;
             }
             let candidate = format!("{prefix}-");
-            let candidate_w = measure_text(&candidate, style);
+            let candidate_w = self.measurer.text_px(&candidate, style);
             let added = if line.text.is_empty() {
                 candidate_w
             } else {
                 space_w + candidate_w
             };
-            if line.width_px + added <= max_width {
+            if line.width_px + added - self.trailing_hang(&candidate, style) <= max_width {
                 best_prefix = Some((candidate, suffix));
             } else {
                 break;
@@ -442,7 +1284,8 @@ impl LayoutState {
             line.width_px += space_w;
         }
         line.text.push_str(&prefix_with_hyphen);
-        line.width_px += measure_text(&prefix_with_hyphen, style);
+        line.width_px += self.measurer.text_px(&prefix_with_hyphen, style);
+        line.hyphenated = true;
 
         self.line = Some(line.clone());
         self.flush_line(false);
@@ -458,90 +1301,485 @@ impl LayoutState {
             return;
         }
 
-        if self.cursor_y + line.line_height_px > self.cfg.content_bottom() {
+        let body_text = matches!(line.style.role, BlockRole::Body | BlockRole::Paragraph);
+        let descent_px = line.line_height_px - line.ascent_px;
+        let mut baseline_y = self.snap_baseline(self.cursor_y + line.ascent_px);
+        if baseline_y + descent_px > self.cfg.content_bottom() {
+            if body_text && self.para_lines_on_page == 1 && !self.para_continued {
+                let quality = &mut self.page.metrics.quality;
+                quality.orphans = quality.orphans.saturating_add(1);
+            }
+            self.para_continued |= self.para_lines_on_page > 0;
+            self.para_lines_on_page = 0;
             self.start_next_page();
+            baseline_y = self.snap_baseline(self.cursor_y + line.ascent_px);
         }
 
-        let available_width = self.cfg.content_width() - line.left_inset_px;
+        // Justified lines stretch to the optical edges, past which marks hang.
+        let leading_hang = self.leading_hang(&line.text, &line.style);
+        let trailing_hang = self.trailing_hang(&line.text, &line.style);
+        let available_width = (self.cfg.content_width() - line.left_inset_px - line.right_inset_px)
+            as f32
+            + leading_hang
+            + trailing_hang;
         let words = line.text.split_whitespace().count();
-        let spaces = line.text.chars().filter(|c| *c == ' ').count() as i32;
-        let fill_ratio = if available_width > 0 {
-            line.width_px / available_width as f32
+        let spaces = line.text.chars().filter(|c| *c == ' ').count();
+        let fill_ratio = if available_width > 0.0 {
+            line.width_px / available_width
         } else {
             0.0
         };
 
-        if self.cfg.typography.justification.enabled
-            && matches!(line.style.role, BlockRole::Body | BlockRole::Paragraph)
+        let base = self
+            .cfg
+            .render_intent
+            .justification(self.cfg.typography.justification);
+        let justification = JustificationConfig {
+            min_words: base.min_words.max(self.cfg.justify_min_words),
+            ..base
+        };
+        line.style.justify_mode = if justification.enabled
+            && body_text
             && !is_last_in_block
-            && words
-                >= self
-                    .cfg
-                    .typography
-                    .justification
-                    .min_words
-                    .max(self.cfg.justify_min_words)
-            && spaces > 0
             && fill_ratio
-                >= self
-                    .cfg
-                    .typography
-                    .justification
+                >= justification
                     .min_fill_ratio
                     .max(self.cfg.justify_min_fill_ratio)
         {
-            let extra = (available_width as f32 - line.width_px).max(0.0) as i32;
-            line.style.justify_mode = JustifyMode::InterWord {
-                extra_px_total: extra,
-            };
+            let extra = (available_width - line.width_px).max(0.0) as i32;
+            justification.justify_mode(extra, words, spaces, line.text.chars().count())
         } else {
-            line.style.justify_mode = JustifyMode::None;
+            JustifyMode::None
+        };
+        self.record_line_quality(&line, spaces, is_last_in_block);
+        let line_top = baseline_y
+            - if line.ascent_px > 0 {
+                line.ascent_px
+            } else {
+                line.style.size_px.round() as i32
+            };
+        let line_bottom = line_top + line.line_height_px;
+        if let Some(boxes) = self.line_boxes.as_mut() {
+            boxes.push((line_top, line_bottom));
         }
 
+        let x = self.cfg.margin_left + line.left_inset_px - leading_hang.round() as i32;
+        if self.cfg.word_boxes {
+            self.push_word_boxes(&line.text, &line.style, x, line_top, line.line_height_px);
+        }
         self.page
             .push_content_command(DrawCommand::Text(TextCommand {
-                x: self.cfg.margin_left + line.left_inset_px,
-                baseline_y: self.cursor_y,
+                x,
+                baseline_y,
                 text: line.text,
                 font_id: line.style.font_id,
                 style: line.style,
             }));
         self.page.sync_commands();
 
-        self.cursor_y += line.line_height_px + self.cfg.line_gap_px;
-    }
+        self.cursor_y = baseline_y + descent_px + self.cfg.line_gap_px;
 
-    fn add_vertical_gap(&mut self, gap_px: i32) {
-        if gap_px <= 0 {
-            return;
-        }
-        self.cursor_y += gap_px;
-        if self.cursor_y >= self.cfg.content_bottom() {
-            self.start_next_page();
+        for span in &mut self.quote_spans {
+            *span = Some(span.map_or((line_top, line_bottom), |(t, b)| {
+                (t.min(line_top), b.max(line_bottom))
+            }));
         }
-    }
 
-    fn start_next_page(&mut self) {
-        self.flush_page_if_non_empty();
-        self.page_no += 1;
-        self.page = RenderPage::new(self.page_no);
-        self.cursor_y = self.cfg.margin_top;
+        if let Some(anchor) = self.keep_anchor.as_mut() {
+            if !anchor.heading_open {
+                anchor.body_lines += 1;
+                if anchor.body_lines >= self.cfg.typography.keep_with_next.min_lines as usize {
+                    self.keep_anchor = None;
+                }
+            }
+        }
     }
 
-    fn flush_page_if_non_empty(&mut self) {
-        if self.page.content_commands.is_empty()
-            && self.page.chrome_commands.is_empty()
-            && self.page.overlay_commands.is_empty()
-        {
-            return;
+    /// Record word boxes for a line about to be pushed at `x`, spacing words
+    /// the way renderers apply the line's justification.
+    fn push_word_boxes(
+        &mut self,
+        text: &str,
+        style: &ResolvedTextStyle,
+        x: i32,
+        top: i32,
+        height: i32,
+    ) {
+        let command = self.page.content_commands.len();
+        let mode = style.justify_mode;
+        let spaces = text.chars().filter(|c| *c == ' ').count();
+        let gaps = text.chars().count().saturating_sub(1);
+        let space_px = self.measurer.text_px(" ", style);
+        let per_glyph = mode.char_extra_px() > 0;
+        let mut x = x as f32;
+        let mut space_no = 0;
+        let mut word: Option<(usize, f32)> = None;
+        for (i, (idx, ch)) in text.char_indices().enumerate() {
+            if i > 0 && per_glyph {
+                x += spread_px(mode.char_extra_px(), gaps, i - 1) as f32;
+            }
+            if ch == ' ' {
+                if let Some((start, start_x)) = word.take() {
+                    self.push_word_box(command, start..idx, start_x, x, top, height);
+                }
+                x += space_px + spread_px(mode.word_extra_px(), spaces, space_no) as f32;
+                space_no += 1;
+                continue;
+            }
+            if word.is_none() {
+                word = Some((idx, x));
+                if !per_glyph {
+                    let end = text[idx..].find(' ').map_or(text.len(), |n| idx + n);
+                    x += self.measurer.text_px(&text[idx..end], style);
+                }
+            }
+            if per_glyph {
+                x += self
+                    .measurer
+                    .text_px(&text[idx..idx + ch.len_utf8()], style);
+            }
+        }
+        if let Some((start, start_x)) = word {
+            self.push_word_box(command, start..text.len(), start_x, x, top, height);
         }
-        let mut page = core::mem::replace(&mut self.page, RenderPage::new(self.page_no + 1));
-        page.metrics.chapter_page_index = page.page_number.saturating_sub(1);
-        page.sync_commands();
-        self.emitted.push(page);
     }
 
-    fn into_pages(mut self) -> Vec<RenderPage> {
+    fn push_word_box(
+        &mut self,
+        command: usize,
+        char_range: core::ops::Range<usize>,
+        left: f32,
+        right: f32,
+        top: i32,
+        height: i32,
+    ) {
+        let x = left.round() as i32;
+        self.page.word_boxes.push(WordBox {
+            command,
+            char_range,
+            rect: OverlayRect {
+                x,
+                y: top,
+                width: (right.round() as i32 - x).max(0) as u32,
+                height: height.max(0) as u32,
+            },
+        });
+    }
+
+    /// Left inset of lines inside the open blockquotes.
+    fn quote_inset(&self) -> i32 {
+        self.quote_spans.len() as i32 * self.cfg.typography.blockquote.indent_px.max(0)
+    }
+
+    fn open_quote(&mut self) {
+        self.quote_spans.push(None);
+    }
+
+    fn close_quote(&mut self) {
+        if let Some(Some(span)) = self.quote_spans.pop() {
+            self.push_quote_bar(self.quote_spans.len(), span);
+        }
+    }
+
+    /// Draw bars for the quote lines placed on this page and restart them.
+    fn flush_quote_bars(&mut self) {
+        for level in 0..self.quote_spans.len() {
+            if let Some(span) = self.quote_spans[level].take() {
+                self.push_quote_bar(level, span);
+            }
+        }
+    }
+
+    /// Vertical bar at the left edge of nesting `level` (0 = outermost).
+    fn push_quote_bar(&mut self, level: usize, (top, bottom): (i32, i32)) {
+        let cfg = self.cfg.typography.blockquote;
+        if !cfg.quote_bars || bottom <= top {
+            return;
+        }
+        self.page
+            .push_content_command(DrawCommand::Rule(RuleCommand {
+                x: self.cfg.margin_left + level as i32 * cfg.indent_px.max(0),
+                y: top,
+                length: (bottom - top) as u32,
+                thickness: cfg.bar_width_px,
+                horizontal: false,
+            }));
+        self.page.sync_commands();
+    }
+
+    /// Move `baseline_y` down onto the configured baseline grid.
+    fn snap_baseline(&self, baseline_y: i32) -> i32 {
+        let grid = self.cfg.baseline_grid_px;
+        if grid <= 0 {
+            return baseline_y;
+        }
+        baseline_y + (grid - baseline_y.rem_euclid(grid)) % grid
+    }
+
+    /// Fold one placed line into the current page's [`LayoutQuality`].
+    fn record_line_quality(&mut self, line: &CurrentLine, spaces: usize, is_last_in_block: bool) {
+        let body_text = matches!(line.style.role, BlockRole::Body | BlockRole::Paragraph);
+        let quality = &mut self.page.metrics.quality;
+        if spaces > 0 {
+            let space_w = self.measurer.text_px(" ", &line.style);
+            let extra = line.style.justify_mode.word_extra_px() as f32 / spaces as f32;
+            quality.max_word_gap_px = quality.max_word_gap_px.max((space_w + extra).ceil() as i32);
+            if extra > 0.0 && extra > space_w * self.cfg.typography.justification.loose_space_ratio
+            {
+                quality.loose_lines = quality.loose_lines.saturating_add(1);
+            }
+        }
+        if line.hyphenated {
+            quality.hyphenated_lines = quality.hyphenated_lines.saturating_add(1);
+        }
+        if !body_text {
+            self.para_lines_on_page = 0;
+            self.para_continued = false;
+            return;
+        }
+        self.para_lines_on_page += 1;
+        if is_last_in_block {
+            if self.para_continued && self.para_lines_on_page == 1 {
+                quality.widows = quality.widows.saturating_add(1);
+            }
+            self.para_lines_on_page = 0;
+            self.para_continued = false;
+        }
+    }
+
+    /// Draw a centered horizontal rule, padded by half a paragraph gap on each side.
+    fn push_rule(&mut self, width_ratio: f32) {
+        let pad = (self.cfg.paragraph_gap_px / 2).max(0);
+        let thickness = 1;
+        if self.cursor_y + pad + thickness > self.cfg.content_bottom() {
+            self.start_next_page();
+        }
+        let content_width = self.cfg.content_width();
+        let length = ((content_width as f32 * width_ratio.clamp(0.0, 1.0)) as i32).max(1);
+        let y = self.cursor_y + pad;
+        self.page
+            .push_content_command(DrawCommand::Rule(RuleCommand {
+                x: self.cfg.margin_left + (content_width - length) / 2,
+                y,
+                length: length as u32,
+                thickness: thickness as u32,
+                horizontal: true,
+            }));
+        self.page.sync_commands();
+        self.cursor_y = y + thickness + pad;
+    }
+
+    /// Outline the box of an image at its declared size in normal flow.
+    fn push_image_box(&mut self, src: &str, width: Option<Dimension>, height: Option<Dimension>) {
+        let Some((w, h)) = self.image_box_size(width, height) else {
+            return;
+        };
+        let available_width = (self.cfg.content_width() - self.quote_inset()).max(1);
+        if self.cursor_y + h > self.cfg.content_bottom() && self.cursor_y > self.cfg.margin_top {
+            self.start_next_page();
+        }
+        let x = self.cfg.margin_left + self.quote_inset() + (available_width - w) / 2;
+        self.push_image_outline(src, x, w, h);
+        self.cursor_y += h + self.cfg.line_gap_px;
+    }
+
+    /// Outline an image box at the cursor and, unless the render intent
+    /// keeps images as placeholders, annotate the page with what to decode.
+    fn push_image_outline(&mut self, src: &str, x: i32, w: i32, h: i32) {
+        self.page
+            .push_content_command(DrawCommand::Rect(RectCommand {
+                x,
+                y: self.cursor_y,
+                width: w as u32,
+                height: h as u32,
+                fill: false,
+            }));
+        self.page.sync_commands();
+        if let ImageDecodeHint::Decode { width, height } =
+            self.cfg.render_intent.image_hint(w as u32, h as u32)
+        {
+            self.page.annotations.push(PageAnnotation {
+                kind: IMAGE_ANNOTATION.to_string(),
+                value: Some(format!(
+                    "{},{} {}x{} {}",
+                    x, self.cursor_y, width, height, src
+                )),
+            });
+        }
+    }
+
+    /// Outline a floated image at the left/right edge and wrap the following
+    /// lines around it. Returns `false` when floats are disabled or the image
+    /// is too wide to float, leaving it for normal flow.
+    fn push_float(
+        &mut self,
+        src: &str,
+        float: Float,
+        width: Option<Dimension>,
+        height: Option<Dimension>,
+    ) -> bool {
+        let objects = self.cfg.object_layout;
+        if objects.float_support != FloatSupport::Basic {
+            return false;
+        }
+        let right = match float {
+            Float::Left => false,
+            Float::Right => true,
+            _ => return false,
+        };
+        let Some((w, h)) = self.image_box_size(width, height) else {
+            return false;
+        };
+        let available_width = (self.cfg.content_width() - self.quote_inset()).max(1);
+        if w as f32 > available_width as f32 * objects.max_float_width_ratio.clamp(0.0, 1.0) {
+            return false;
+        }
+        if self.cursor_y + h > self.cfg.content_bottom() && self.cursor_y > self.cfg.margin_top {
+            self.start_next_page();
+        }
+        let x = if right {
+            self.cfg.margin_left + self.cfg.content_width() - w
+        } else {
+            self.cfg.margin_left + self.quote_inset()
+        };
+        self.push_image_outline(src, x, w, h);
+        self.float = Some(FloatWrap {
+            right,
+            inset_px: w + objects.float_gap_px.max(0),
+            bottom_y: self.cursor_y + h,
+        });
+        true
+    }
+
+    /// Move below the active float.
+    fn clear_float(&mut self) {
+        if let Some(float) = self.float.take() {
+            self.cursor_y = self.cursor_y.max(float.bottom_y);
+        }
+    }
+
+    /// Forget the active float once the cursor has passed it.
+    fn release_float(&mut self) {
+        if self.float.is_some_and(|f| self.cursor_y >= f.bottom_y) {
+            self.float = None;
+        }
+    }
+
+    /// Declared image size in px, scaled down to fit the content area. A
+    /// missing width spans the content width; a missing height uses the
+    /// inline-image height cap. Unsized images have no box.
+    fn image_box_size(
+        &self,
+        width: Option<Dimension>,
+        height: Option<Dimension>,
+    ) -> Option<(i32, i32)> {
+        if width.is_none() && height.is_none() {
+            return None;
+        }
+        let available_width = (self.cfg.content_width() - self.quote_inset()).max(1);
+        let content_height = self.cfg.viewport_content_height().max(1);
+        let max_height_ratio = self
+            .cfg
+            .object_layout
+            .max_inline_image_height_ratio
+            .clamp(0.0, 1.0);
+        let w = width.map_or(available_width as f32, |d| {
+            d.resolve(available_width as u32) as f32
+        });
+        let h = height.map_or(content_height as f32 * max_height_ratio, |d| {
+            d.resolve(content_height as u32) as f32
+        });
+        if w <= 0.0 || h <= 0.0 {
+            return None;
+        }
+        let scale = (available_width as f32 / w)
+            .min(content_height as f32 / h)
+            .min(1.0);
+        Some((
+            ((w * scale).round() as i32).max(1),
+            ((h * scale).round() as i32).max(1),
+        ))
+    }
+
+    fn add_vertical_gap(&mut self, gap_px: i32) {
+        if gap_px <= 0 {
+            return;
+        }
+        self.cursor_y += gap_px;
+        if self.cursor_y >= self.cfg.content_bottom() {
+            self.start_next_page();
+        }
+    }
+
+    fn start_next_page(&mut self) {
+        // A heading still waiting for its following lines moves to the new page.
+        let mut carried = Vec::with_capacity(0);
+        let mut carried_words = Vec::with_capacity(0);
+        let mut carried_from_y = self.cursor_y;
+        let mut carried_start = usize::MAX;
+        if let Some(anchor) = self.keep_anchor.take() {
+            if anchor.start_command > 0 && anchor.start_command < self.page.content_commands.len() {
+                carried = self.page.content_commands.split_off(anchor.start_command);
+                let split = self
+                    .page
+                    .word_boxes
+                    .partition_point(|word| word.command < anchor.start_command);
+                carried_words = self.page.word_boxes.split_off(split);
+                self.page.sync_commands();
+                carried_from_y = anchor.start_y;
+                carried_start = anchor.start_command;
+            }
+        }
+        // A drop cap keeps wrapping only if it moved along with the carried lines.
+        let drop_cap = self
+            .drop_cap
+            .take()
+            .filter(|cap| cap.command >= carried_start);
+        // Floats stay on the page they were placed on.
+        self.float = None;
+        self.flush_quote_bars();
+        self.flush_page_if_non_empty();
+        self.page_no += 1;
+        self.page = RenderPage::new(self.page_no);
+        if let Some(label) = &self.print_page {
+            self.page.annotations.push(PageAnnotation {
+                kind: PRINT_PAGE_ANNOTATION.to_string(),
+                value: Some(label.clone()),
+            });
+        }
+        let dy = self.cfg.margin_top - carried_from_y;
+        for mut cmd in carried {
+            shift_command_y(&mut cmd, dy);
+            self.page.push_content_command(cmd);
+        }
+        for mut word in carried_words {
+            word.command -= carried_start;
+            word.rect.y += dy;
+            self.page.word_boxes.push(word);
+        }
+        self.page.sync_commands();
+        self.cursor_y += dy;
+        self.drop_cap = drop_cap.map(|cap| DropCapWrap {
+            command: cap.command - carried_start,
+            bottom_y: cap.bottom_y + dy,
+            ..cap
+        });
+    }
+
+    fn flush_page_if_non_empty(&mut self) {
+        if self.page.content_commands.is_empty()
+            && self.page.chrome_commands.is_empty()
+            && self.page.overlay_commands.is_empty()
+        {
+            return;
+        }
+        let mut page = core::mem::replace(&mut self.page, RenderPage::new(self.page_no + 1));
+        page.metrics.chapter_page_index = page.page_number.saturating_sub(1);
+        page.sync_commands();
+        self.emitted.push(page);
+    }
+
+    fn into_pages(mut self) -> Vec<RenderPage> {
         self.flush_page_if_non_empty();
         self.emitted
     }
@@ -551,6 +1789,46 @@ impl LayoutState {
     }
 }
 
+/// Share of `total_px` given to gap `index` of `slots`, front-loading the
+/// remainder as renderers do.
+fn spread_px(total_px: i32, slots: usize, index: usize) -> i32 {
+    let slots = i32::try_from(slots).unwrap_or(i32::MAX);
+    if slots == 0 || total_px <= 0 {
+        return 0;
+    }
+    let index = i32::try_from(index).unwrap_or(i32::MAX);
+    total_px / slots + i32::from(index < total_px % slots)
+}
+
+fn shift_command_y(cmd: &mut DrawCommand, dy: i32) {
+    match cmd {
+        DrawCommand::Text(text) => text.baseline_y += dy,
+        DrawCommand::Rule(rule) => rule.y += dy,
+        DrawCommand::Rect(rect) => rect.y += dy,
+        DrawCommand::PageChrome(_) => {}
+    }
+}
+
+/// Whether `text` is a lone letter, optionally after opening punctuation.
+fn is_initial_letter(text: &str) -> bool {
+    split_initial_letter(text).is_some_and(|(_, rest)| rest.trim().is_empty())
+}
+
+/// Split the opening letter (with any leading punctuation) from `text`.
+fn split_initial_letter(text: &str) -> Option<(&str, &str)> {
+    let text = text.trim_start();
+    for (idx, ch) in text.char_indices() {
+        if ch.is_alphanumeric() {
+            let end = idx + ch.len_utf8();
+            return Some((&text[..end], &text[end..]));
+        }
+        if ch.is_whitespace() || idx > 0 {
+            return None;
+        }
+    }
+    None
+}
+
 fn to_resolved_style(style: &ComputedTextStyle) -> ResolvedTextStyle {
     let family = style
         .family_stack
@@ -567,10 +1845,11 @@ fn to_resolved_style(style: &ComputedTextStyle) -> ResolvedTextStyle {
         letter_spacing: style.letter_spacing,
         role: style.block_role,
         justify_mode: JustifyMode::None,
+        synthesis: FontSynthesis::default(),
     }
 }
 
-fn measure_text(text: &str, style: &ResolvedTextStyle) -> f32 {
+pub(crate) fn measure_text(text: &str, style: &ResolvedTextStyle) -> f32 {
     let chars = text.chars().count() as f32;
     if chars == 0.0 {
         return 0.0;
@@ -605,29 +1884,51 @@ fn strip_soft_hyphens(text: &str) -> String {
     }
 }
 
-fn annotate_page_chrome(pages: &mut [RenderPage], cfg: LayoutConfig) {
-    if pages.is_empty() {
+fn annotate_page_chrome(pages: &mut [RenderPage], cfg: LayoutConfig, chapter_title: Option<&str>) {
+    if pages.is_empty() || cfg.mode == LayoutMode::Scroll {
         return;
     }
     let total = pages.len();
+    let chrome = cfg.page_chrome;
     for page in pages.iter_mut() {
-        if cfg.page_chrome.header_enabled {
+        // Print page labels stand in for `{page}` when page-list breaks are on.
+        let print_page = page
+            .annotations
+            .iter()
+            .find(|annotation| annotation.kind == PRINT_PAGE_ANNOTATION)
+            .and_then(|annotation| annotation.value.clone())
+            .filter(|_| cfg.page_list_breaks);
+        let template = |template: &str| match &print_page {
+            Some(label) => template.replace("{page}", label),
+            None => template.to_string(),
+        };
+        if chrome.header_enabled {
             page.push_chrome_command(DrawCommand::PageChrome(PageChromeCommand {
                 kind: PageChromeKind::Header,
-                text: Some(format!("Page {}", page.page_number)),
+                text: Some(PageChromeConfig::expand_template(
+                    &template(chrome.header_template),
+                    page.page_number,
+                    total,
+                    chapter_title,
+                )),
                 current: None,
                 total: None,
             }));
         }
-        if cfg.page_chrome.footer_enabled {
+        if chrome.footer_enabled {
             page.push_chrome_command(DrawCommand::PageChrome(PageChromeCommand {
                 kind: PageChromeKind::Footer,
-                text: Some(format!("Page {}", page.page_number)),
+                text: Some(PageChromeConfig::expand_template(
+                    &template(chrome.footer_template),
+                    page.page_number,
+                    total,
+                    chapter_title,
+                )),
                 current: None,
                 total: None,
             }));
         }
-        if cfg.page_chrome.progress_enabled {
+        if chrome.progress_enabled {
             page.push_chrome_command(DrawCommand::PageChrome(PageChromeCommand {
                 kind: PageChromeKind::Progress,
                 text: None,
@@ -642,21 +1943,30 @@ fn annotate_page_chrome(pages: &mut [RenderPage], cfg: LayoutConfig) {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::render_ir::{JustificationStrategy, RenderPriority};
+    use mu_epub::BreakBefore;
 
     fn body_run(text: &str) -> StyledEventOrRun {
+        sized_run(text, 16.0)
+    }
+
+    fn sized_run(text: &str, size_px: f32) -> StyledEventOrRun {
         StyledEventOrRun::Run(StyledRun {
             text: text.to_string(),
             style: ComputedTextStyle {
                 family_stack: vec!["serif".to_string()],
                 weight: 400,
                 italic: false,
-                size_px: 16.0,
+                size_px,
                 line_height: 1.4,
                 letter_spacing: 0.0,
                 block_role: BlockRole::Body,
+                language: None,
             },
             font_id: 0,
             resolved_family: "serif".to_string(),
+            synthesis: FontSynthesis::default(),
+            span: None,
         })
     }
 
@@ -802,6 +2112,252 @@ mod tests {
         );
     }
 
+    #[test]
+    fn snippet_wraps_into_box_and_scrolls_without_reflow() {
+        let runs: Vec<StyledRun> = ["serendipity (n.): ", LONG_TEXT]
+            .into_iter()
+            .filter_map(|text| match body_run(text) {
+                StyledEventOrRun::Run(run) => Some(run),
+                StyledEventOrRun::Event(_) => None,
+            })
+            .collect();
+        let area = SnippetBox {
+            rect: OverlayRect {
+                x: 40,
+                y: 300,
+                width: 200,
+                height: 120,
+            },
+            padding_px: 8,
+        };
+        let snippet = LayoutEngine::new(LayoutConfig::default()).layout_snippet(&runs, area);
+
+        assert!(snippet.line_count() > 5);
+        assert!(snippet.content_height() > 104);
+        assert_eq!(snippet.max_scroll_y(), snippet.content_height() - 104);
+
+        let top = snippet.commands(0);
+        assert!(!top.is_empty() && top.len() < snippet.line_count());
+        for cmd in &top {
+            let DrawCommand::Text(text) = cmd else {
+                panic!("expected text");
+            };
+            assert_eq!(text.x, 48);
+            assert!(text.baseline_y > 308 && text.baseline_y <= 412);
+        }
+        let DrawCommand::Text(first) = &top[0] else {
+            panic!("expected text");
+        };
+        assert!(first.text.starts_with("serendipity"));
+
+        let offsets: Vec<i32> = snippet.line_offsets().collect();
+        assert_eq!(offsets[0], 0);
+        let end = snippet.commands(i32::MAX);
+        assert_eq!(end, snippet.commands(snippet.max_scroll_y()));
+        assert_ne!(end, top);
+        let (DrawCommand::Text(last), DrawCommand::Text(scrolled)) =
+            (end.last().unwrap(), snippet.commands(offsets[1])[0].clone())
+        else {
+            panic!("expected text");
+        };
+        assert!(last.baseline_y <= 412);
+        assert!(!scrolled.text.starts_with("serendipity"));
+    }
+
+    #[test]
+    fn word_boxes_cover_each_word_with_justified_spacing() {
+        let mut cfg = LayoutConfig::for_display(480, 240);
+        cfg.word_boxes = true;
+        let mut items = vec![StyledEventOrRun::Event(StyledEvent::HeadingStart(2))];
+        items.push(body_run("Chapter One"));
+        items.push(StyledEventOrRun::Event(StyledEvent::HeadingEnd(2)));
+        for _ in 0..4 {
+            items.extend(paragraph(LONG_TEXT));
+        }
+        let pages = LayoutEngine::new(cfg).layout_items(items);
+        assert!(pages.len() > 1);
+
+        let mut justified = 0;
+        for page in &pages {
+            let mut words_per_command = vec![0; page.content_commands.len()];
+            for word in &page.word_boxes {
+                let DrawCommand::Text(text) = &page.content_commands[word.command] else {
+                    panic!("word box must point at a text command");
+                };
+                let slice = &text.text[word.char_range.clone()];
+                assert!(!slice.is_empty() && !slice.contains(' '));
+                assert!(word.rect.x >= text.x && word.rect.width > 0);
+                assert!(word.rect.y < text.baseline_y);
+                words_per_command[word.command] += 1;
+                assert_eq!(page.word_at(word.rect.x, word.rect.y), Some(word));
+            }
+            for (idx, cmd) in page.content_commands.iter().enumerate() {
+                let DrawCommand::Text(text) = cmd else {
+                    continue;
+                };
+                assert_eq!(words_per_command[idx], text.text.split_whitespace().count());
+                if text.style.justify_mode.word_extra_px() > 0 {
+                    justified += 1;
+                    let last = page
+                        .word_boxes
+                        .iter()
+                        .rfind(|word| word.command == idx)
+                        .unwrap();
+                    let right = last.rect.x + last.rect.width as i32;
+                    assert!((right - (cfg.display_width - cfg.margin_right)).abs() <= 1);
+                }
+            }
+        }
+        assert!(justified > 0);
+        assert!(LayoutEngine::new(LayoutConfig::default())
+            .layout_items(paragraph(LONG_TEXT))
+            .iter()
+            .all(|page| page.word_boxes.is_empty()));
+    }
+
+    #[test]
+    fn page_list_anchors_force_breaks_and_label_pages() {
+        let cfg = LayoutConfig {
+            display_height: 120,
+            margin_top: 8,
+            margin_bottom: 8,
+            page_list_breaks: true,
+            page_chrome: PageChromeConfig {
+                footer_enabled: true,
+                ..PageChromeConfig::default()
+            },
+            ..LayoutConfig::default()
+        };
+        let anchor = |id: &str| StyledEventOrRun::Event(StyledEvent::Anchor(id.to_string()));
+        let mut items = vec![anchor("pg11")];
+        items.extend(paragraph("Short print page."));
+        items.push(StyledEventOrRun::Event(StyledEvent::ParagraphStart));
+        items.push(body_run("Twelve starts"));
+        items.push(anchor("pg12"));
+        items.push(body_run("mid paragraph."));
+        items.push(StyledEventOrRun::Event(StyledEvent::ParagraphEnd));
+        for _ in 0..6 {
+            items.extend(paragraph("filler words that overflow the small page"));
+        }
+        items.push(anchor("pg13"));
+        items.extend(paragraph("Thirteen."));
+
+        let layout = |cfg: LayoutConfig| {
+            let mut session = LayoutEngine::new(cfg).start_session();
+            session.set_page_list_anchors(
+                ["11", "12", "13"]
+                    .iter()
+                    .map(|label| (format!("pg{}", label), label.to_string()))
+                    .collect(),
+            );
+            for item in items.clone() {
+                session.push_item(item);
+            }
+            let mut pages = Vec::with_capacity(0);
+            session.finish(&mut |page| pages.push(page));
+            pages
+        };
+        let pages = layout(cfg);
+        let labels: Vec<_> = pages
+            .iter()
+            .map(|page| {
+                page.annotations
+                    .iter()
+                    .find(|a| a.kind == PRINT_PAGE_ANNOTATION)
+                    .and_then(|a| a.value.clone())
+            })
+            .collect();
+        // Print page 12 overflows onto computed pages that keep its label.
+        assert!(pages.len() > 4);
+        assert_eq!(labels[0].as_deref(), Some("11"));
+        assert_eq!(labels[1].as_deref(), Some("12"));
+        assert!(labels[2..pages.len() - 1]
+            .iter()
+            .all(|label| label.as_deref() == Some("12")));
+        assert_eq!(labels.last().cloned().flatten().as_deref(), Some("13"));
+
+        let first_texts: Vec<_> = pages
+            .iter()
+            .map(|page| text_commands(std::slice::from_ref(page))[0].text.clone())
+            .collect();
+        assert_eq!(first_texts[0], "Short print page.");
+        assert_eq!(first_texts[1], "mid paragraph.");
+        assert_eq!(first_texts.last().map(String::as_str), Some("Thirteen."));
+        let footer = pages[1].chrome_commands.iter().find_map(|cmd| match cmd {
+            DrawCommand::PageChrome(chrome) if chrome.kind == PageChromeKind::Footer => {
+                chrome.text.clone()
+            }
+            _ => None,
+        });
+        assert_eq!(footer.as_deref(), Some("Page 12"));
+
+        // Without the mode, anchors do not affect pagination.
+        let plain = layout(LayoutConfig {
+            page_list_breaks: false,
+            ..cfg
+        });
+        assert!(plain.len() < pages.len());
+    }
+
+    #[test]
+    fn block_breaks_start_pages_only_when_paginated() {
+        let mut items = paragraph("Before the break.");
+        items.push(StyledEventOrRun::Event(StyledEvent::BlockBreak(
+            BreakBefore::Column,
+        )));
+        items.extend(paragraph("After the break."));
+        // A break at the top of a page does not leave a blank page.
+        let mut leading = vec![StyledEventOrRun::Event(StyledEvent::BlockBreak(
+            BreakBefore::Page,
+        ))];
+        leading.extend(items.clone());
+
+        let engine = LayoutEngine::new(LayoutConfig::default());
+        let pages = engine.layout_items(leading);
+        assert_eq!(pages.len(), 2);
+        assert_eq!(
+            text_commands(std::slice::from_ref(&pages[1]))[0].text,
+            "After the break."
+        );
+        let scroll = engine.with_mode(LayoutMode::Scroll).layout_items(items);
+        assert_eq!(scroll.len(), 1);
+    }
+
+    #[test]
+    fn page_header_shows_chapter_title() {
+        let engine = LayoutEngine::new(LayoutConfig {
+            page_chrome: PageChromeConfig {
+                header_enabled: true,
+                footer_enabled: true,
+                footer_template: "{chapter} \u{b7} {page}/{total}",
+                ..PageChromeConfig::default()
+            },
+            ..LayoutConfig::default()
+        });
+        let chrome_texts = |title: Option<&str>| {
+            let mut session = engine.start_session();
+            session.set_chapter_title(title.map(String::from));
+            for item in paragraph("alpha beta gamma") {
+                session.push_item(item);
+            }
+            let mut texts = Vec::with_capacity(2);
+            session.finish(&mut |page| {
+                for cmd in &page.commands {
+                    if let DrawCommand::PageChrome(chrome) = cmd {
+                        texts.extend(chrome.text.clone());
+                    }
+                }
+            });
+            texts
+        };
+        assert_eq!(
+            chrome_texts(Some("Chapter One")),
+            vec!["Chapter One", "Chapter One \u{b7} 1/1"]
+        );
+        // Without a title the header falls back to the page number.
+        assert_eq!(chrome_texts(None), vec!["Page 1", " \u{b7} 1/1"]);
+    }
+
     #[test]
     fn page_chrome_policy_controls_emitted_markers() {
         let engine = LayoutEngine::new(LayoutConfig {
@@ -876,6 +2432,43 @@ mod tests {
         }
     }
 
+    #[test]
+    fn block_margins_replace_and_collapse_paragraph_gaps() {
+        let engine = LayoutEngine::new(LayoutConfig::default());
+        let baselines = |margins: Option<(Option<u32>, Option<u32>)>| {
+            let mut items = Vec::with_capacity(0);
+            for text in ["first", "second"] {
+                if let Some((top, bottom)) = margins {
+                    items.push(StyledEventOrRun::Event(StyledEvent::BlockMargins {
+                        top,
+                        bottom,
+                    }));
+                }
+                items.push(StyledEventOrRun::Event(StyledEvent::ParagraphStart));
+                items.push(body_run(text));
+                items.push(StyledEventOrRun::Event(StyledEvent::ParagraphEnd));
+            }
+            let pages = engine.layout_items(items);
+            let ys: Vec<i32> = pages[0]
+                .commands
+                .iter()
+                .filter_map(|cmd| match cmd {
+                    DrawCommand::Text(text) => Some(text.baseline_y),
+                    _ => None,
+                })
+                .collect();
+            ys[1] - ys[0]
+        };
+        let default_step = baselines(None);
+        let gap = LayoutConfig::default().paragraph_gap_px;
+        assert_eq!(baselines(Some((None, Some(0)))), default_step - gap);
+        // Top 30 after bottom 20 collapses to 30, not 50.
+        assert_eq!(
+            baselines(Some((Some(30), Some(20)))),
+            default_step - gap + 30
+        );
+    }
+
     #[test]
     fn incremental_session_matches_batch_layout() {
         let cfg = LayoutConfig {
@@ -948,4 +2541,782 @@ mod tests {
             .collect();
         assert_eq!(during_push_numbers, batch_prefix_numbers);
     }
+
+    fn pages_ending_with_heading(keep_with_next: crate::render_ir::KeepWithNext) -> usize {
+        let mut stranded = 0;
+        for display_height in (100..260).step_by(7) {
+            let mut cfg = LayoutConfig {
+                display_height,
+                margin_top: 8,
+                margin_bottom: 8,
+                ..LayoutConfig::default()
+            };
+            cfg.typography.keep_with_next = keep_with_next;
+            let mut items = Vec::with_capacity(0);
+            for _ in 0..6 {
+                items.push(StyledEventOrRun::Event(StyledEvent::HeadingStart(2)));
+                items.push(body_run("Section"));
+                items.push(StyledEventOrRun::Event(StyledEvent::HeadingEnd(2)));
+                items.push(StyledEventOrRun::Event(StyledEvent::ParagraphStart));
+                items.push(body_run("one two three four five six seven eight nine ten"));
+                items.push(StyledEventOrRun::Event(StyledEvent::ParagraphEnd));
+            }
+            let pages = LayoutEngine::new(cfg).layout_items(items);
+            for page in &pages[..pages.len() - 1] {
+                let last = page
+                    .content_commands
+                    .iter()
+                    .rev()
+                    .find_map(|cmd| match cmd {
+                        DrawCommand::Text(t) => Some(t),
+                        _ => None,
+                    });
+                if last.is_some_and(|t| matches!(t.style.role, BlockRole::Heading(_))) {
+                    stranded += 1;
+                }
+            }
+        }
+        stranded
+    }
+
+    #[test]
+    fn keep_with_next_avoids_headings_at_page_bottom() {
+        let enabled = crate::render_ir::KeepWithNext {
+            enabled: true,
+            ..Default::default()
+        };
+        assert!(pages_ending_with_heading(Default::default()) > 0);
+        assert_eq!(pages_ending_with_heading(enabled), 0);
+    }
+
+    #[test]
+    fn hr_emits_centered_rule() {
+        let cfg = LayoutConfig::default();
+        let items = vec![
+            StyledEventOrRun::Event(StyledEvent::ParagraphStart),
+            body_run("above"),
+            StyledEventOrRun::Event(StyledEvent::ParagraphEnd),
+            StyledEventOrRun::Event(StyledEvent::Hr),
+        ];
+        let pages = LayoutEngine::new(cfg).layout_items(items);
+        let rule = pages[0]
+            .content_commands
+            .iter()
+            .find_map(|cmd| match cmd {
+                DrawCommand::Rule(rule) => Some(*rule),
+                _ => None,
+            })
+            .expect("rule command");
+        let content_width = cfg.content_width();
+        assert!(rule.horizontal);
+        assert_eq!(rule.length as i32, content_width / 2);
+        assert_eq!(
+            rule.x - cfg.margin_left,
+            content_width - rule.length as i32 - (rule.x - cfg.margin_left)
+        );
+    }
+
+    #[test]
+    fn caption_is_small_italic_and_kept_with_figure() {
+        let cfg = LayoutConfig {
+            display_height: 115,
+            margin_top: 8,
+            margin_bottom: 8,
+            ..LayoutConfig::default()
+        };
+        let mut items = vec![
+            StyledEventOrRun::Event(StyledEvent::ParagraphStart),
+            body_run("alpha beta gamma"),
+            StyledEventOrRun::Event(StyledEvent::ParagraphEnd),
+            StyledEventOrRun::Event(StyledEvent::ParagraphStart),
+            body_run("delta epsilon"),
+            StyledEventOrRun::Event(StyledEvent::ParagraphEnd),
+            StyledEventOrRun::Event(StyledEvent::FigureStart),
+            body_run("figure"),
+            StyledEventOrRun::Event(StyledEvent::CaptionStart),
+        ];
+        for _ in 0..3 {
+            items.push(body_run("caption words here"));
+            items.push(StyledEventOrRun::Event(StyledEvent::LineBreak));
+        }
+        items.push(StyledEventOrRun::Event(StyledEvent::CaptionEnd));
+        items.push(StyledEventOrRun::Event(StyledEvent::FigureEnd));
+
+        let pages = LayoutEngine::new(cfg).layout_items(items);
+        let page_of = |needle: &str| {
+            pages
+                .iter()
+                .position(|page| {
+                    page.content_commands.iter().any(
+                        |cmd| matches!(cmd, DrawCommand::Text(t) if t.text.starts_with(needle)),
+                    )
+                })
+                .expect("text placed")
+        };
+        assert!(pages.len() > 1);
+        assert_eq!(page_of("figure"), page_of("caption"));
+        let caption = pages
+            .iter()
+            .flat_map(|page| page.content_commands.iter())
+            .find_map(|cmd| match cmd {
+                DrawCommand::Text(t) if t.text.starts_with("caption") => Some(t),
+                _ => None,
+            })
+            .expect("caption text");
+        assert!(caption.style.italic);
+        assert_eq!(caption.style.role, BlockRole::Caption);
+        assert!(caption.style.size_px < 16.0);
+    }
+
+    #[test]
+    fn keep_with_next_moves_heading_to_page_top() {
+        let layout = |enabled| {
+            let mut cfg = LayoutConfig {
+                display_height: 100,
+                margin_top: 8,
+                margin_bottom: 8,
+                ..LayoutConfig::default()
+            };
+            cfg.typography.keep_with_next.enabled = enabled;
+            let items = vec![
+                StyledEventOrRun::Event(StyledEvent::ParagraphStart),
+                body_run("alpha beta gamma"),
+                StyledEventOrRun::Event(StyledEvent::ParagraphEnd),
+                StyledEventOrRun::Event(StyledEvent::HeadingStart(2)),
+                body_run("Section"),
+                StyledEventOrRun::Event(StyledEvent::HeadingEnd(2)),
+                StyledEventOrRun::Event(StyledEvent::ParagraphStart),
+                body_run("delta epsilon"),
+                StyledEventOrRun::Event(StyledEvent::ParagraphEnd),
+            ];
+            let pages = LayoutEngine::new(cfg).layout_items(items);
+            let texts: Vec<Vec<String>> = pages
+                .iter()
+                .map(|page| text_commands(core::slice::from_ref(page)))
+                .map(|cmds| cmds.into_iter().map(|t| t.text).collect())
+                .collect();
+            (cfg, pages, texts)
+        };
+        let (_, _, texts) = layout(false);
+        assert_eq!(texts[0], vec!["alpha beta gamma", "Section"]);
+        assert_eq!(texts[1], vec!["delta epsilon"]);
+
+        let (cfg, pages, texts) = layout(true);
+        assert_eq!(texts[0], vec!["alpha beta gamma"]);
+        assert_eq!(texts[1], vec!["Section", "delta epsilon"]);
+        let heading = &text_commands(&pages[1..2])[0];
+        assert!(heading.baseline_y >= cfg.margin_top);
+        assert!(heading.baseline_y < text_commands(&pages[1..2])[1].baseline_y);
+    }
+
+    fn text_commands(pages: &[RenderPage]) -> Vec<TextCommand> {
+        pages
+            .iter()
+            .flat_map(|page| page.content_commands.iter())
+            .filter_map(|cmd| match cmd {
+                DrawCommand::Text(t) => Some(t.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    const LONG_TEXT: &str = "nce upon a time there lived a reader who loved long books \
+        and small screens, and who read every night until the battery ran out";
+
+    #[test]
+    fn drop_cap_wraps_chapter_opener() {
+        let mut cfg = LayoutConfig::default();
+        cfg.typography.drop_caps.enabled = true;
+        let items = vec![
+            StyledEventOrRun::Event(StyledEvent::ParagraphStart),
+            body_run(&format!("O{LONG_TEXT}")),
+            StyledEventOrRun::Event(StyledEvent::ParagraphEnd),
+            StyledEventOrRun::Event(StyledEvent::ParagraphStart),
+            body_run("Second paragraph"),
+            StyledEventOrRun::Event(StyledEvent::ParagraphEnd),
+        ];
+        let texts = text_commands(&LayoutEngine::new(cfg).layout_items(items));
+        let cap = &texts[0];
+        assert_eq!(cap.text, "O");
+        assert_eq!(cap.x, cfg.margin_left);
+        assert!(cap.style.size_px > 16.0 * 2.0);
+        assert!(texts[1].text.starts_with("nce"));
+        for line in &texts[1..4] {
+            assert!(line.x > cfg.margin_left);
+            assert!(line.baseline_y <= cap.baseline_y);
+        }
+        assert_eq!(texts[4].x, cfg.margin_left);
+        let second = texts
+            .iter()
+            .find(|t| t.text.starts_with("Second"))
+            .expect("second paragraph");
+        assert!(second.x > cfg.margin_left, "only the opener drops its cap");
+        assert!(second.baseline_y > cap.baseline_y);
+    }
+
+    #[test]
+    fn styled_initial_becomes_drop_cap() {
+        let items = vec![
+            StyledEventOrRun::Event(StyledEvent::ParagraphStart),
+            sized_run("O", 40.0),
+            body_run(LONG_TEXT),
+            StyledEventOrRun::Event(StyledEvent::ParagraphEnd),
+        ];
+        let texts = text_commands(&LayoutEngine::new(LayoutConfig::default()).layout_items(items));
+        assert_eq!(texts[0].text, "O");
+        assert!(texts[0].style.size_px > 40.0);
+        assert!(texts[1].text.starts_with("nce"));
+        assert!(texts[1].x > LayoutConfig::default().margin_left);
+    }
+
+    #[test]
+    fn drop_caps_need_at_least_two_lines() {
+        let mut cfg = LayoutConfig::default();
+        cfg.typography.drop_caps.lines = 1;
+        let items = vec![
+            StyledEventOrRun::Event(StyledEvent::ParagraphStart),
+            sized_run("O", 40.0),
+            body_run(LONG_TEXT),
+            StyledEventOrRun::Event(StyledEvent::ParagraphEnd),
+        ];
+        let texts = text_commands(&LayoutEngine::new(cfg).layout_items(items));
+        assert!(texts[0].text.starts_with("O nce"));
+    }
+
+    fn hanging_config(enabled: bool) -> LayoutConfig {
+        let mut cfg = LayoutConfig {
+            display_width: 164,
+            first_line_indent_px: 0,
+            justify_min_words: 1,
+            justify_min_fill_ratio: 0.0,
+            ..LayoutConfig::default()
+        };
+        cfg.typography.justification.min_words = 1;
+        cfg.typography.justification.min_fill_ratio = 0.0;
+        cfg.typography.hanging_punctuation.enabled = enabled;
+        cfg
+    }
+
+    fn paragraph(text: &str) -> Vec<StyledEventOrRun> {
+        vec![
+            StyledEventOrRun::Event(StyledEvent::ParagraphStart),
+            body_run(text),
+            StyledEventOrRun::Event(StyledEvent::ParagraphEnd),
+        ]
+    }
+
+    #[test]
+    fn leading_quote_hangs_into_margin() {
+        let items = paragraph("\u{201C}Quoted\u{201D} words");
+        let plain =
+            text_commands(&LayoutEngine::new(hanging_config(false)).layout_items(items.clone()));
+        let hung = text_commands(&LayoutEngine::new(hanging_config(true)).layout_items(items));
+        assert_eq!(plain[0].x, LayoutConfig::default().margin_left);
+        // One quote mark at 16px is 9.28px wide and hangs entirely.
+        assert_eq!(hung[0].x, plain[0].x - 9);
+    }
+
+    #[test]
+    fn trailing_comma_hangs_and_widens_justification() {
+        // Two words measure 102.08px against a 100px measure; half of the
+        // final comma (4.64px) may hang past the right edge.
+        let items = paragraph("aaaa, aaaa, aaaa, aaaa,");
+        let plain =
+            text_commands(&LayoutEngine::new(hanging_config(false)).layout_items(items.clone()));
+        assert_eq!(plain[0].text, "aaaa,");
+
+        let hung = text_commands(&LayoutEngine::new(hanging_config(true)).layout_items(items));
+        assert_eq!(hung[0].text, "aaaa, aaaa,");
+        assert_eq!(
+            hung[0].style.justify_mode,
+            JustifyMode::InterWord { extra_px_total: 2 }
+        );
+    }
+
+    #[test]
+    fn hang_ratios_follow_config() {
+        let mut cfg = crate::render_ir::HangingPunctuationConfig {
+            enabled: true,
+            ..Default::default()
+        };
+        assert_eq!(cfg.hang_ratio('\u{201C}'), 1.0);
+        assert_eq!(cfg.hang_ratio('-'), 0.75);
+        assert_eq!(cfg.hang_ratio(','), 0.5);
+        assert_eq!(cfg.hang_ratio('a'), 0.0);
+        cfg.stop_ratio = 2.0;
+        assert_eq!(cfg.hang_ratio('.'), 1.0);
+        cfg.enabled = false;
+        assert_eq!(cfg.hang_ratio('\u{201C}'), 0.0);
+    }
+
+    fn line_texts(cfg: LayoutConfig, text: &str) -> Vec<String> {
+        text_commands(&LayoutEngine::new(cfg).layout_items(paragraph(text)))
+            .into_iter()
+            .map(|t| t.text)
+            .collect()
+    }
+
+    #[test]
+    fn no_break_rules_keep_words_on_one_line() {
+        // 100px measure: ten 9.28px characters fit on a line.
+        let mut cfg = LayoutConfig {
+            display_width: 164,
+            first_line_indent_px: 0,
+            ..LayoutConfig::default()
+        };
+        cfg.typography.justification.enabled = false;
+        let text = "aaaa Mr. Smith aaaaa 10 km aaaa z domu";
+        assert_eq!(
+            line_texts(cfg, text),
+            vec!["aaaa Mr.", "Smith", "aaaaa 10", "km aaaa z", "domu"]
+        );
+
+        cfg.typography.no_break = crate::render_ir::NoBreakConfig {
+            abbreviations: true,
+            number_units: true,
+            single_letter_words: true,
+        };
+        assert_eq!(
+            line_texts(cfg, text),
+            vec!["aaaa", "Mr. Smith", "aaaaa", "10 km aaaa", "z domu"]
+        );
+    }
+
+    #[test]
+    fn no_break_rule_matching() {
+        let rules = crate::render_ir::NoBreakConfig {
+            abbreviations: true,
+            number_units: true,
+            single_letter_words: true,
+        };
+        assert!(rules.keeps_together("Dr.", "Who"));
+        assert!(rules.keeps_together("(J.", "R."));
+        assert!(rules.keeps_together("1,200", "km."));
+        assert!(rules.keeps_together("42", "%"));
+        assert!(rules.keeps_together("v", "Praze"));
+        assert!(!rules.keeps_together("end.", "Next"));
+        assert!(!rules.keeps_together("10", "apples"));
+        assert!(!crate::render_ir::NoBreakConfig::default().keeps_together("Mr.", "Smith"));
+    }
+
+    fn two_paragraph_pages(display_height: i32) -> Vec<RenderPage> {
+        let cfg = LayoutConfig {
+            display_width: 200,
+            display_height,
+            ..LayoutConfig::default()
+        };
+        let mut items = paragraph("one two three four five six seven eight nine ten eleven twelve");
+        items.extend(paragraph(
+            "alpha beta gamma delta epsilon zeta eta theta iota kappa lambda mu",
+        ));
+        LayoutEngine::new(cfg).layout_items(items)
+    }
+
+    #[test]
+    fn scroll_mode_lays_out_one_surface_streamed_in_bands() {
+        let cfg = LayoutConfig {
+            display_width: 200,
+            display_height: 120,
+            page_chrome: PageChromeConfig {
+                footer_enabled: true,
+                ..PageChromeConfig::default()
+            },
+            ..LayoutConfig::default()
+        };
+        let mut items = Vec::with_capacity(0);
+        for _ in 0..6 {
+            items.extend(paragraph(
+                "one two three four five six seven eight nine ten",
+            ));
+        }
+        let engine = LayoutEngine::new(cfg);
+        let paged = engine.layout_items(items.clone());
+        assert!(paged.len() > 1);
+
+        let surface = engine.layout_scroll(items);
+        let lines: usize = paged.iter().map(|page| page.content_commands.len()).sum();
+        assert!(surface.height() > cfg.display_height);
+        assert_eq!(surface.width(), cfg.display_width);
+        assert_eq!(surface.render_band(0, surface.height()).len(), lines);
+        assert!(surface.max_scroll_y(cfg.display_height) > 0);
+
+        let mut seen = 0;
+        let mut y = 0;
+        while y < surface.height() {
+            let band = surface.render_band(y, cfg.display_height);
+            for cmd in &band {
+                assert!(!matches!(cmd, DrawCommand::PageChrome(_)));
+                let bounds = cmd.bounds().expect("content commands have bounds");
+                assert!(bounds.y < cfg.display_height && bounds.bottom() > 0);
+            }
+            seen += band.len();
+            y += cfg.display_height;
+        }
+        assert!(seen >= lines);
+        assert!(surface.render_band(surface.height(), 50).is_empty());
+    }
+
+    #[test]
+    fn quality_counts_orphans_and_widows() {
+        // Six lines fit: the second paragraph's first line is stranded below the first.
+        let pages = two_paragraph_pages(228);
+        assert_eq!(pages[0].metrics.quality.orphans, 1);
+        assert_eq!(pages[0].metrics.quality.widows, 0);
+        assert_eq!(pages[1].metrics.quality.orphans, 0);
+
+        // Four lines fit: the first paragraph's last line opens page two.
+        let pages = two_paragraph_pages(176);
+        assert_eq!(pages[1].metrics.quality.widows, 1);
+        assert_eq!(pages[1].metrics.quality.orphans, 0);
+    }
+
+    #[test]
+    fn quality_reports_loose_and_hyphenated_lines() {
+        let mut cfg = LayoutConfig {
+            display_width: 200,
+            first_line_indent_px: 0,
+            justify_min_words: 1,
+            justify_min_fill_ratio: 0.0,
+            soft_hyphen_policy: SoftHyphenPolicy::Discretionary,
+            ..LayoutConfig::default()
+        };
+        cfg.typography.justification.min_words = 1;
+        cfg.typography.justification.min_fill_ratio = 0.0;
+        let text = "a b extra\u{00AD}ordinary words";
+        let pages = LayoutEngine::new(cfg).layout_items(paragraph(text));
+        let quality = pages[0].metrics.quality;
+        assert_eq!(quality.hyphenated_lines, 1);
+        assert_eq!(quality.loose_lines, 1);
+        let space_w = measure_text(" ", &text_commands(&pages)[0].style).ceil() as i32;
+        assert!(quality.max_word_gap_px > space_w);
+
+        cfg.typography.justification.enabled = false;
+        let ragged = LayoutEngine::new(cfg).layout_items(paragraph(text));
+        assert_eq!(ragged[0].metrics.quality.loose_lines, 0);
+        assert_eq!(ragged[0].metrics.quality.max_word_gap_px, space_w);
+    }
+
+    #[test]
+    fn baseline_grid_snaps_every_line() {
+        let cfg = LayoutConfig {
+            display_width: 200,
+            margin_top: 45,
+            baseline_grid_px: 8,
+            ..LayoutConfig::default()
+        };
+        let mut items = paragraph("one two three four five six seven");
+        items.extend([
+            StyledEventOrRun::Event(StyledEvent::ParagraphStart),
+            sized_run("eight nine ten eleven twelve", 13.0),
+            StyledEventOrRun::Event(StyledEvent::ParagraphEnd),
+        ]);
+        let lines = text_commands(&LayoutEngine::new(cfg).layout_items(items));
+        assert!(lines.len() > 3);
+        assert!(lines.iter().all(|t| t.baseline_y % 8 == 0));
+        assert!(lines.windows(2).all(|w| w[1].baseline_y > w[0].baseline_y));
+    }
+
+    struct FixedMetrics;
+
+    impl TextMeasurer for FixedMetrics {
+        fn measure_text_px(&self, text: &str, style: &ResolvedTextStyle) -> f32 {
+            text.chars().count() as f32 * style.size_px * 0.5
+        }
+
+        fn vertical_metrics(&self, style: &ResolvedTextStyle) -> Option<VerticalMetrics> {
+            Some(VerticalMetrics {
+                ascent_px: style.size_px * 0.8,
+                descent_px: style.size_px * 0.2,
+            })
+        }
+    }
+
+    #[test]
+    fn font_metrics_size_line_boxes() {
+        let cfg = LayoutConfig {
+            display_width: 400,
+            min_line_height_px: 0,
+            ..LayoutConfig::default()
+        };
+        let engine = LayoutEngine::new(cfg).with_text_measurer(Arc::new(FixedMetrics));
+        let mut items = vec![StyledEventOrRun::Event(StyledEvent::ParagraphStart)];
+        items.push(sized_run("small", 10.0));
+        items.push(sized_run("BIG", 30.0));
+        items.push(StyledEventOrRun::Event(StyledEvent::LineBreak));
+        items.push(sized_run("after", 10.0));
+        items.push(StyledEventOrRun::Event(StyledEvent::ParagraphEnd));
+        let lines = text_commands(&engine.layout_items(items));
+        assert_eq!(lines.len(), 2);
+        // 30px at 1.4: 42px box, 24px ascent plus 6px half-leading above the baseline.
+        assert_eq!(lines[0].baseline_y, cfg.margin_top + 30);
+        // 12px below the first baseline, then the 14px box's 8 + 2 above the next.
+        assert_eq!(lines[1].baseline_y, lines[0].baseline_y + 12 + 10);
+    }
+
+    #[test]
+    fn heading_spacing_varies_by_level() {
+        let items = |level: u8| {
+            let mut items = paragraph("Intro");
+            items.push(StyledEventOrRun::Event(StyledEvent::HeadingStart(level)));
+            items.push(body_run("Heading"));
+            items.push(StyledEventOrRun::Event(StyledEvent::HeadingEnd(level)));
+            items.extend(paragraph("Body"));
+            items
+        };
+        let baselines = |cfg: LayoutConfig, level: u8| -> Vec<i32> {
+            text_commands(&LayoutEngine::new(cfg).layout_items(items(level)))
+                .iter()
+                .map(|t| t.baseline_y)
+                .collect()
+        };
+
+        let uniform = LayoutConfig::default();
+        assert_eq!(baselines(uniform, 1), baselines(uniform, 4));
+
+        let mut cfg = uniform;
+        cfg.typography.heading_spacing.enabled = true;
+        let h1 = baselines(cfg, 1);
+        let h4 = baselines(cfg, 4);
+        let spacing = cfg.typography.heading_spacing;
+        assert_eq!(h1[1] - h4[1], spacing.above_px[0] - spacing.above_px[3]);
+        assert_eq!(
+            (h1[2] - h1[1]) - (h4[2] - h4[1]),
+            spacing.below_px[0] - spacing.below_px[3]
+        );
+    }
+
+    #[test]
+    fn list_items_get_formatted_markers() {
+        let item = |text: &str| {
+            [
+                StyledEventOrRun::Event(StyledEvent::ListItemStart),
+                body_run(text),
+                StyledEventOrRun::Event(StyledEvent::ListItemEnd),
+            ]
+        };
+        let mut items = vec![StyledEventOrRun::Event(StyledEvent::ListStart {
+            style: ListStyleType::LowerAlpha,
+            start: 2,
+        })];
+        items.extend(item("bee"));
+        items.push(StyledEventOrRun::Event(StyledEvent::ListItemValue(26)));
+        items.extend(item("zed"));
+        items.extend(item("after"));
+        items.push(StyledEventOrRun::Event(StyledEvent::ListEnd));
+        items.push(StyledEventOrRun::Event(StyledEvent::ListStart {
+            style: ListStyleType::Square,
+            start: 1,
+        }));
+        items.extend(item("boxed"));
+        items.push(StyledEventOrRun::Event(StyledEvent::ListEnd));
+
+        let lines: Vec<String> =
+            text_commands(&LayoutEngine::new(LayoutConfig::default()).layout_items(items))
+                .into_iter()
+                .map(|t| t.text)
+                .collect();
+        assert_eq!(
+            lines,
+            vec!["b. bee", "z. zed", "aa. after", "\u{25AA} boxed"]
+        );
+    }
+
+    #[test]
+    fn nested_blockquotes_indent_and_draw_quote_bars() {
+        let ev = |e| vec![StyledEventOrRun::Event(e)];
+        let items = vec![
+            ev(StyledEvent::BlockquoteStart(1)),
+            paragraph("outer"),
+            ev(StyledEvent::BlockquoteStart(2)),
+            paragraph("inner"),
+            ev(StyledEvent::BlockquoteEnd(2)),
+            ev(StyledEvent::BlockquoteEnd(1)),
+            paragraph("after"),
+        ]
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>();
+        let mut cfg = LayoutConfig {
+            first_line_indent_px: 0,
+            ..LayoutConfig::default()
+        };
+        cfg.typography.blockquote.quote_bars = true;
+        let indent = cfg.typography.blockquote.indent_px;
+        let pages = LayoutEngine::new(cfg).layout_items(items);
+
+        let xs: Vec<(String, i32)> = text_commands(&pages)
+            .into_iter()
+            .map(|t| (t.text, t.x - cfg.margin_left))
+            .collect();
+        assert_eq!(
+            xs,
+            vec![
+                ("outer".to_string(), indent),
+                ("inner".to_string(), indent * 2),
+                ("after".to_string(), 0),
+            ]
+        );
+
+        let bars: Vec<RuleCommand> = pages[0]
+            .commands
+            .iter()
+            .filter_map(|cmd| match cmd {
+                DrawCommand::Rule(rule) if !rule.horizontal => Some(*rule),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(bars.len(), 2);
+        assert_eq!(bars[0].x, cfg.margin_left + indent);
+        assert_eq!(bars[1].x, cfg.margin_left);
+        assert!(bars[1].y <= bars[0].y);
+        assert!(bars[1].length > bars[0].length);
+    }
+
+    #[test]
+    fn images_reserve_declared_size_and_break_pages() {
+        let image = |width, height| {
+            StyledEventOrRun::Event(StyledEvent::Image {
+                src: "map.png".to_string(),
+                alt: String::with_capacity(0),
+                width,
+                height,
+                float: Float::None,
+            })
+        };
+        let cfg = LayoutConfig::default();
+        let content_width = cfg.content_width();
+        let items = vec![
+            image(
+                Some(Dimension::Px(content_width as u32 * 2)),
+                Some(Dimension::Px(200)),
+            ),
+            image(Some(Dimension::Percent(50)), Some(Dimension::Percent(100))),
+            image(None, None),
+        ];
+        let pages = LayoutEngine::new(cfg).layout_items(items);
+
+        let boxes: Vec<(usize, RectCommand)> = pages
+            .iter()
+            .enumerate()
+            .flat_map(|(i, page)| {
+                page.commands.iter().filter_map(move |cmd| match cmd {
+                    DrawCommand::Rect(rect) => Some((i, *rect)),
+                    _ => None,
+                })
+            })
+            .collect();
+        assert_eq!(boxes.len(), 2);
+        // Oversized width scales the whole box down to the content width.
+        let (page, wide) = boxes[0];
+        assert_eq!(page, 0);
+        assert_eq!(wide.width as i32, content_width);
+        assert_eq!(wide.height, 100);
+        assert_eq!(wide.y, cfg.margin_top);
+        // A full-height image cannot follow it and starts the next page.
+        let (page, tall) = boxes[1];
+        assert_eq!(page, 1);
+        assert_eq!(tall.y, cfg.margin_top);
+        assert_eq!(tall.width as i32, content_width / 2);
+        assert_eq!(
+            tall.x,
+            cfg.margin_left + (content_width - content_width / 2) / 2
+        );
+    }
+
+    #[test]
+    fn render_priority_picks_image_decodes_or_placeholders() {
+        let image = || {
+            StyledEventOrRun::Event(StyledEvent::Image {
+                src: "map.png".to_string(),
+                alt: String::with_capacity(0),
+                width: Some(Dimension::Px(101)),
+                height: Some(Dimension::Px(60)),
+                float: Float::None,
+            })
+        };
+        let annotations = |priority| {
+            let mut cfg = LayoutConfig::default();
+            cfg.render_intent.priority = priority;
+            let pages = LayoutEngine::new(cfg).layout_items(vec![image()]);
+            pages[0]
+                .annotations
+                .iter()
+                .filter(|a| a.kind == IMAGE_ANNOTATION)
+                .filter_map(|a| a.value.clone())
+                .collect::<Vec<_>>()
+        };
+        let cfg = LayoutConfig::default();
+        let x = cfg.margin_left + (cfg.content_width() - 101) / 2;
+        assert_eq!(
+            annotations(RenderPriority::Quality),
+            vec![format!("{},{} 101x60 map.png", x, cfg.margin_top)]
+        );
+        assert_eq!(
+            annotations(RenderPriority::Speed),
+            vec![format!("{},{} 51x30 map.png", x, cfg.margin_top)]
+        );
+        assert!(annotations(RenderPriority::MinMemory).is_empty());
+
+        let mut cfg = LayoutConfig::default();
+        cfg.typography.justification.strategy = JustificationStrategy::Adaptive;
+        cfg.render_intent.priority = RenderPriority::Speed;
+        let justification = cfg
+            .render_intent
+            .justification(cfg.typography.justification);
+        assert_eq!(justification.strategy, JustificationStrategy::InterWord);
+    }
+
+    #[test]
+    fn text_wraps_around_floated_images() {
+        let float_items = |float| {
+            let mut items = vec![StyledEventOrRun::Event(StyledEvent::Image {
+                src: "icon.png".to_string(),
+                alt: String::with_capacity(0),
+                width: Some(Dimension::Px(120)),
+                height: Some(Dimension::Px(80)),
+                float,
+            })];
+            items.extend(paragraph(&"wrap these words around the float ".repeat(12)));
+            items
+        };
+        let mut cfg = LayoutConfig {
+            first_line_indent_px: 0,
+            ..LayoutConfig::default()
+        };
+        cfg.object_layout.float_support = FloatSupport::Basic;
+        let gap = cfg.object_layout.float_gap_px;
+        let float_bottom = cfg.margin_top + 80;
+
+        let pages = LayoutEngine::new(cfg).layout_items(float_items(Float::Left));
+        let lines = text_commands(&pages);
+        assert!(lines[0].baseline_y < float_bottom);
+        for line in &lines {
+            // Without font metrics a line's box starts at its baseline.
+            let beside = line.baseline_y < float_bottom;
+            let expected_x = cfg.margin_left + if beside { 120 + gap } else { 0 };
+            assert_eq!(line.x, expected_x, "{:?}", line.text);
+        }
+        assert!(lines.last().is_some_and(|l| l.x == cfg.margin_left));
+
+        let pages = LayoutEngine::new(cfg).layout_items(float_items(Float::Right));
+        let rect = pages[0]
+            .commands
+            .iter()
+            .find_map(|cmd| match cmd {
+                DrawCommand::Rect(rect) => Some(*rect),
+                _ => None,
+            })
+            .expect("float box");
+        assert_eq!(rect.x, cfg.margin_left + cfg.content_width() - 120);
+        let lines = text_commands(&pages);
+        assert!(lines.iter().all(|l| l.x == cfg.margin_left));
+        assert!(measure_text(&lines[0].text, &lines[0].style) <= (rect.x - gap) as f32);
+
+        // With floats disabled the image takes its own block above the text.
+        cfg.object_layout.float_support = FloatSupport::None;
+        let pages = LayoutEngine::new(cfg).layout_items(float_items(Float::Left));
+        let lines = text_commands(&pages);
+        assert!(lines[0].baseline_y >= float_bottom);
+        assert!(lines.iter().all(|l| l.x == cfg.margin_left));
+    }
 }
@@ -0,0 +1,218 @@
+use mu_epub::{FontPolicy, RenderPrepOptions};
+
+use crate::render_engine::{prep_for_viewport, RenderEngineOptions};
+use crate::render_ir::PaginationProfileId;
+use crate::render_layout::LayoutConfig;
+
+/// Everything that affects where page breaks fall, bundled under a name.
+///
+/// Page metrics, typography, and hyphenation live in [`Self::layout`]; style
+/// resolution and font-size hints in [`Self::prep`]; the font set in
+/// [`Self::fonts`]. [`Self::id`] hashes all three (but not the name), so two
+/// profiles with identical settings share page maps and cache entries.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PaginationProfile {
+    /// Registry key, e.g. `"eink-6"`.
+    pub name: String,
+    /// Page size, margins, and typography.
+    pub layout: LayoutConfig,
+    /// Style preparation options.
+    pub prep: RenderPrepOptions,
+    /// Font families and synthesis policy the host resolves text with.
+    pub fonts: FontPolicy,
+}
+
+impl PaginationProfile {
+    /// Profile with default settings for a display size.
+    pub fn for_display(name: &str, width: i32, height: i32) -> Self {
+        Self {
+            name: name.to_string(),
+            layout: LayoutConfig::for_display(width, height),
+            prep: prep_for_viewport(width, height),
+            fonts: FontPolicy::default(),
+        }
+    }
+
+    /// 6" 758x1024 e-ink reader (212 ppi).
+    pub fn eink_6in() -> Self {
+        Self::for_display("eink-6", 758, 1024)
+            .with_margins(40, 48, 40, 44)
+            .with_base_font_size(22.0)
+    }
+
+    /// 7.8" 1404x1872 e-ink reader (300 ppi).
+    pub fn eink_7_8in() -> Self {
+        Self::for_display("eink-7.8", 1404, 1872)
+            .with_margins(72, 88, 72, 80)
+            .with_base_font_size(32.0)
+    }
+
+    /// 4.2" 400x300 status display.
+    pub fn status_4_2in() -> Self {
+        let mut profile = Self::for_display("status-4.2", 400, 300)
+            .with_margins(12, 16, 12, 14)
+            .with_base_font_size(14.0);
+        profile.layout.first_line_indent_px = 12;
+        profile.layout.paragraph_gap_px = 4;
+        profile
+    }
+
+    /// Built-in device presets.
+    pub fn presets() -> [Self; 3] {
+        [Self::eink_6in(), Self::eink_7_8in(), Self::status_4_2in()]
+    }
+
+    /// Set left/top/right/bottom margins in px.
+    pub fn with_margins(mut self, left: i32, top: i32, right: i32, bottom: i32) -> Self {
+        self.layout.margin_left = left;
+        self.layout.margin_top = top;
+        self.layout.margin_right = right;
+        self.layout.margin_bottom = bottom;
+        self
+    }
+
+    /// Set the body font size used by style resolution.
+    pub fn with_base_font_size(mut self, px: f32) -> Self {
+        self.prep.style.hints.base_font_size_px = px;
+        self.prep.layout_hints.base_font_size_px = px;
+        self
+    }
+
+    /// Stable content hash of the layout-affecting settings.
+    pub fn id(&self) -> PaginationProfileId {
+        let payload = format!("{:?}|{:?}|{:?}", self.prep, self.layout, self.fonts);
+        PaginationProfileId::from_bytes(payload.as_bytes())
+    }
+
+    /// Render-engine options for this profile.
+    pub fn render_options(&self) -> RenderEngineOptions {
+        RenderEngineOptions {
+            prep: self.prep,
+            layout: self.layout,
+            ..RenderEngineOptions::default()
+        }
+    }
+}
+
+/// Named pagination profiles, seeded with the built-in presets.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PaginationProfileRegistry {
+    profiles: Vec<PaginationProfile>,
+}
+
+impl Default for PaginationProfileRegistry {
+    fn default() -> Self {
+        Self {
+            profiles: PaginationProfile::presets().into(),
+        }
+    }
+}
+
+impl PaginationProfileRegistry {
+    /// Registry holding the built-in presets.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registry with no profiles.
+    pub fn empty() -> Self {
+        Self {
+            profiles: Vec::with_capacity(0),
+        }
+    }
+
+    /// Add a profile, replacing and returning any profile with the same name.
+    pub fn register(&mut self, profile: PaginationProfile) -> Option<PaginationProfile> {
+        match self.profiles.iter_mut().find(|p| p.name == profile.name) {
+            Some(existing) => Some(core::mem::replace(existing, profile)),
+            None => {
+                self.profiles.push(profile);
+                None
+            }
+        }
+    }
+
+    /// Remove a profile by name.
+    pub fn unregister(&mut self, name: &str) -> Option<PaginationProfile> {
+        let idx = self.profiles.iter().position(|p| p.name == name)?;
+        Some(self.profiles.remove(idx))
+    }
+
+    /// Look up a profile by name.
+    pub fn get(&self, name: &str) -> Option<&PaginationProfile> {
+        self.profiles.iter().find(|p| p.name == name)
+    }
+
+    /// Look up the first profile whose settings hash to `id`.
+    pub fn find_by_id(&self, id: PaginationProfileId) -> Option<&PaginationProfile> {
+        self.profiles.iter().find(|p| p.id() == id)
+    }
+
+    /// Registered profiles in registration order.
+    pub fn iter(&self) -> impl Iterator<Item = &PaginationProfile> {
+        self.profiles.iter()
+    }
+
+    /// Number of registered profiles.
+    pub fn len(&self) -> usize {
+        self.profiles.len()
+    }
+
+    /// Whether no profiles are registered.
+    pub fn is_empty(&self) -> bool {
+        self.profiles.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn presets_have_distinct_stable_ids() {
+        let presets = PaginationProfile::presets();
+        assert_eq!(presets[0].layout.display_width, 758);
+        assert_eq!(presets[0].layout.display_height, 1024);
+        for (i, a) in presets.iter().enumerate() {
+            assert_eq!(a.id(), a.clone().id());
+            for b in &presets[i + 1..] {
+                assert_ne!(a.id(), b.id());
+            }
+        }
+    }
+
+    #[test]
+    fn id_tracks_settings_not_name() {
+        let base = PaginationProfile::eink_6in();
+        let mut renamed = base.clone();
+        renamed.name = "my-reader".to_string();
+        assert_eq!(base.id(), renamed.id());
+
+        let mut hyphen = base.clone();
+        hyphen.layout.typography.hyphenation.soft_hyphen_policy =
+            crate::render_ir::HyphenationMode::Ignore;
+        assert_ne!(base.id(), hyphen.id());
+
+        let mut fonts = base.clone();
+        fonts.fonts.default_family = "monospace".to_string();
+        assert_ne!(base.id(), fonts.id());
+    }
+
+    #[test]
+    fn registry_registers_replaces_and_finds_profiles() {
+        let mut registry = PaginationProfileRegistry::new();
+        assert_eq!(registry.len(), 3);
+        assert!(registry.get("eink-7.8").is_some());
+
+        let custom = PaginationProfile::for_display("kiosk", 600, 800).with_base_font_size(18.0);
+        assert_eq!(registry.register(custom.clone()), None);
+        assert_eq!(registry.find_by_id(custom.id()), Some(&custom));
+
+        let replaced = registry.register(custom.clone().with_margins(8, 8, 8, 8));
+        assert_eq!(replaced, Some(custom));
+        assert_eq!(registry.len(), 4);
+        assert!(registry.unregister("kiosk").is_some());
+        assert!(registry.get("kiosk").is_none());
+        assert!(PaginationProfileRegistry::empty().is_empty());
+    }
+}
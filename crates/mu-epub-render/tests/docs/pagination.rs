@@ -1,7 +1,7 @@
 use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
 
-use mu_epub::{EpubBook, MemoryBudget, RenderPrepOptions};
+use mu_epub::{ChapterSplitOptions, EpubBook, MemoryBudget, RenderPrepOptions};
 use mu_epub_render::{
     CancelToken, OverlayComposer, OverlayContent, OverlayItem, OverlaySize, OverlaySlot,
     PageChromeConfig, PaginationProfileId, RenderCacheStore, RenderConfig, RenderDiagnostic,
@@ -63,6 +63,48 @@ fn prepare_chapter_page_range_matches_full_slice() {
     assert_eq!(actual, expected);
 }
 
+#[test]
+fn prepare_chapter_ref_lays_out_only_the_segment() {
+    let engine = build_engine();
+    let mut book = open_fixture_book();
+    let chapters = book
+        .split_chapters(ChapterSplitOptions {
+            min_chapter_bytes: 0,
+            max_segment_bytes: 1024,
+            max_heading_level: 2,
+        })
+        .expect("split should succeed");
+    let segment = chapters
+        .iter()
+        .find(|chapter| chapter.segment().is_some_and(|s| s.index == 1))
+        .expect("some chapter should split");
+
+    let mut segment_pages = Vec::new();
+    engine
+        .prepare_chapter_ref_with(&mut book, segment, |page| segment_pages.push(page))
+        .expect("segment render should succeed");
+    let html = book.chapter_segment_html(segment).unwrap();
+    let mut from_bytes = Vec::new();
+    engine
+        .prepare_chapter_bytes_with(&mut book, segment.index, &html, |page| {
+            from_bytes.push(page)
+        })
+        .expect("byte render should succeed");
+    let whole = engine
+        .prepare_chapter(&mut book, segment.index)
+        .expect("full chapter render should succeed");
+
+    assert!(!segment_pages.is_empty());
+    assert!(segment_pages.len() <= whole.len());
+    let content = |pages: &[RenderPage]| {
+        pages
+            .iter()
+            .map(|page| page.content_commands.clone())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(content(&segment_pages), content(&from_bytes));
+}
+
 #[test]
 fn prepare_chapter_iter_matches_full_render() {
     let engine = build_engine();
@@ -233,13 +275,18 @@ fn diagnostic_sink_receives_reflow_timing() {
         }
     });
     let mut book = open_fixture_book();
-    let _ = engine
+    let pages = engine
         .prepare_chapter(&mut book, 0)
         .expect("prepare should pass");
     let diagnostics = seen.lock().expect("diag lock").clone();
     assert!(diagnostics
         .iter()
         .any(|d| matches!(d, RenderDiagnostic::ReflowTimeMs(_))));
+    let quality_reports = diagnostics
+        .iter()
+        .filter(|d| matches!(d, RenderDiagnostic::PageQuality { .. }))
+        .count();
+    assert_eq!(quality_reports, pages.len());
 }
 
 #[derive(Default)]
@@ -1,20 +1,37 @@
 //! Optional async helpers for high-level EPUB opening.
 //!
-//! This module is available with the `async` feature.
+//! This module is available with the `async` feature. [`AsyncEpubBook`] reads
+//! archives through [`AsyncStreamingZip`] without blocking the executor.
 
 extern crate alloc;
 
+use alloc::format;
+use alloc::string::{String, ToString};
 use alloc::vec::Vec;
 use core::result::Result;
-use std::io::Cursor;
+use std::collections::VecDeque;
+use std::io::{Cursor, Write};
 use std::path::Path;
+use tokio::io::{AsyncRead, AsyncSeek};
 
-use crate::book::{EpubBook, EpubBookOptions};
-use crate::error::EpubError;
+use crate::book::{
+    chapter_ref, chapter_refs, displayable_chapter_ref, emit_capped_chapter_events,
+    navigation_from_bytes, navigation_item, resolve_opf_relative_path, validate_open_invariants,
+    ChapterEventsOptions, ChapterRef, EpubBook, EpubBookOptions,
+};
+use crate::error::{EpubError, ZipError};
+use crate::metadata::{extract_metadata, EpubMetadata};
+use crate::navigation::Navigation;
+use crate::render_prep::{
+    parse_stylesheet_links_bytes, ChapterStylesheets, RenderPrep, StyleLimits, StyledEventOrRun,
+    StylesheetSource,
+};
+use crate::spine::Spine;
+use crate::zip::AsyncStreamingZip;
 
 /// Read an EPUB file asynchronously and open it as an `EpubBook`.
 ///
-/// This helper reads the file into memory and uses `EpubBook::from_reader`.
+/// This helper reads the file into memory and uses `EpubBook::from_storage`.
 pub async fn open_epub_file_async<P: AsRef<Path>>(
     path: P,
 ) -> Result<EpubBook<Cursor<Vec<u8>>>, EpubError> {
@@ -29,5 +46,573 @@ pub async fn open_epub_file_async_with_options<P: AsRef<Path>>(
     let bytes = tokio::fs::read(path)
         .await
         .map_err(|e| EpubError::Io(e.to_string()))?;
-    EpubBook::from_reader_with_options(Cursor::new(bytes), options)
+    EpubBook::from_storage_with_options(Cursor::new(bytes), options)
+}
+
+/// Read-ahead settings for [`AsyncEpubBook::prefetch_next`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PrefetchOptions {
+    /// Options used to style-prepare prefetched chapters.
+    ///
+    /// [`AsyncEpubBook::chapter_events`] only replays a prefetched chapter
+    /// when called with the same options.
+    pub events: ChapterEventsOptions,
+    /// Maximum chapters styled concurrently.
+    pub max_concurrency: usize,
+    /// Maximum prefetched chapters kept; the oldest are evicted first.
+    pub max_cached_chapters: usize,
+}
+
+impl Default for PrefetchOptions {
+    fn default() -> Self {
+        Self {
+            events: ChapterEventsOptions::default(),
+            max_concurrency: 2,
+            max_cached_chapters: 4,
+        }
+    }
+}
+
+/// A decompressed and style-prepared chapter.
+struct PrefetchedChapter {
+    index: usize,
+    html: Vec<u8>,
+    events: ChapterEventsOptions,
+    items: Vec<StyledEventOrRun>,
+}
+
+/// High-level EPUB handle backed by an [`AsyncStreamingZip`].
+///
+/// Opening and resource reads await the underlying source directly, so
+/// async files and network objects can be read without `spawn_blocking`.
+/// Parsed metadata, spine, and navigation match [`EpubBook`].
+pub struct AsyncEpubBook<R> {
+    zip: AsyncStreamingZip<R>,
+    opf_path: String,
+    metadata: EpubMetadata,
+    spine: Spine,
+    navigation: Option<Navigation>,
+    prefetch_options: PrefetchOptions,
+    prefetched: VecDeque<PrefetchedChapter>,
+    /// Last chapter read through `chapter_html`/`chapter_events`.
+    last_chapter: Option<usize>,
+}
+
+impl AsyncEpubBook<tokio::fs::File> {
+    /// Open an EPUB file without reading it fully into memory.
+    pub async fn open<P: AsRef<Path>>(path: P) -> Result<Self, EpubError> {
+        Self::open_with_options(path, EpubBookOptions::default()).await
+    }
+
+    /// Open an EPUB file with explicit options.
+    pub async fn open_with_options<P: AsRef<Path>>(
+        path: P,
+        options: EpubBookOptions,
+    ) -> Result<Self, EpubError> {
+        let file = tokio::fs::File::open(path)
+            .await
+            .map_err(|e| EpubError::Io(e.to_string()))?;
+        Self::from_reader_with_options(file, options).await
+    }
+}
+
+impl<R: AsyncRead + AsyncSeek + Unpin> AsyncEpubBook<R> {
+    /// Open an EPUB from an async source and parse core structures.
+    pub async fn from_reader(reader: R) -> Result<Self, EpubError> {
+        Self::from_reader_with_options(reader, EpubBookOptions::default()).await
+    }
+
+    /// Open an EPUB from an async source with explicit options.
+    ///
+    /// # Allocation behavior
+    /// - Bounded by `ZipLimits` in options
+    /// - Allocates the central directory once while opening
+    pub async fn from_reader_with_options(
+        reader: R,
+        options: EpubBookOptions,
+    ) -> Result<Self, EpubError> {
+        let mut zip = AsyncStreamingZip::new_with_limits(reader, options.zip_limits)
+            .await
+            .map_err(EpubError::Zip)?;
+        zip.set_tolerant_names(options.tolerant_entry_names);
+        zip.validate_mimetype().await.map_err(EpubError::Zip)?;
+
+        let container = read_entry(&mut zip, "META-INF/container.xml", usize::MAX).await?;
+        let opf_path = crate::metadata::parse_container_xml(&container)?;
+        let opf = read_entry(&mut zip, &opf_path, usize::MAX).await?;
+        let metadata = extract_metadata(&container, &opf)?;
+        let mut spine = crate::spine::parse_spine(&opf)?;
+        spine.resolve_duplicate_idrefs();
+        validate_open_invariants(&metadata, &spine, &opf, options.validation_mode)?;
+
+        let navigation = match navigation_item(&metadata, &spine) {
+            Some(nav_item) => {
+                let nav_path = resolve_opf_relative_path(&opf_path, &nav_item.href);
+                let nav_bytes = read_entry(&mut zip, &nav_path, usize::MAX).await;
+                navigation_from_bytes(
+                    nav_item,
+                    &nav_path,
+                    nav_bytes,
+                    options.validation_mode,
+                    options.max_nav_bytes,
+                )?
+            }
+            None => None,
+        };
+
+        Ok(Self {
+            zip,
+            opf_path,
+            metadata,
+            spine,
+            navigation,
+            prefetch_options: PrefetchOptions::default(),
+            prefetched: VecDeque::with_capacity(0),
+            last_chapter: None,
+        })
+    }
+
+    /// EPUB package metadata.
+    pub fn metadata(&self) -> &EpubMetadata {
+        &self.metadata
+    }
+
+    /// Convenience: metadata title.
+    pub fn title(&self) -> &str {
+        self.metadata.title.as_str()
+    }
+
+    /// Reading order from `<spine>`.
+    pub fn spine(&self) -> &Spine {
+        &self.spine
+    }
+
+    /// Parsed navigation document, when one is available.
+    pub fn navigation(&self) -> Option<&Navigation> {
+        self.navigation.as_ref()
+    }
+
+    /// Number of entries in the spine reading order.
+    pub fn chapter_count(&self) -> usize {
+        self.spine.len()
+    }
+
+    /// Enumerate chapters in spine order.
+    pub fn chapters(&self) -> impl Iterator<Item = ChapterRef> + '_ {
+        chapter_refs(&self.metadata, &self.spine, self.navigation.as_ref())
+    }
+
+    /// Get a chapter descriptor by spine index.
+    pub fn chapter(&self, index: usize) -> Result<ChapterRef, EpubError> {
+        chapter_ref(&self.metadata, &self.spine, self.navigation.as_ref(), index)
+    }
+
+    /// Get the descriptor of the content displayed for a spine index,
+    /// following manifest `fallback` chains past undisplayable items.
+    ///
+    /// See [`EpubBook::resolve_displayable_chapter`].
+    pub fn resolve_displayable_chapter(&self, index: usize) -> Result<ChapterRef, EpubError> {
+        displayable_chapter_ref(&self.metadata, &self.spine, self.navigation.as_ref(), index)
+    }
+
+    /// Read a resource by OPF-relative href into a new `Vec<u8>`.
+    ///
+    /// Fragment suffixes (e.g. `chapter.xhtml#p3`) are ignored.
+    pub async fn read_resource(&mut self, href: &str) -> Result<Vec<u8>, EpubError> {
+        let mut out = Vec::with_capacity(0);
+        self.read_resource_into(href, &mut out).await?;
+        Ok(out)
+    }
+
+    /// Stream a resource by OPF-relative href into a writer.
+    ///
+    /// Fragment suffixes (e.g. `chapter.xhtml#p3`) are ignored.
+    ///
+    /// # Allocation behavior
+    /// - Uses bounded internal buffers
+    /// - Caller buffer required: Yes (writer handles output)
+    pub async fn read_resource_into<W: Write>(
+        &mut self,
+        href: &str,
+        writer: &mut W,
+    ) -> Result<usize, EpubError> {
+        self.read_resource_into_with_hard_cap(href, writer, usize::MAX)
+            .await
+    }
+
+    /// Stream a resource by OPF-relative href with a hard cap.
+    ///
+    /// Fragment suffixes (e.g. `chapter.xhtml#p3`) are ignored.
+    pub async fn read_resource_into_with_hard_cap<W: Write>(
+        &mut self,
+        href: &str,
+        writer: &mut W,
+        hard_cap_bytes: usize,
+    ) -> Result<usize, EpubError> {
+        let zip_path = resolve_opf_relative_path(&self.opf_path, href);
+        read_entry_into(&mut self.zip, &zip_path, writer, hard_cap_bytes).await
+    }
+
+    /// Read a spine chapter as UTF-8 HTML/XHTML text by index.
+    pub async fn chapter_html(&mut self, index: usize) -> Result<String, EpubError> {
+        let chapter = self.resolve_displayable_chapter(index)?;
+        self.last_chapter = Some(index);
+        let bytes = match self.prefetched_chapter(index) {
+            Some(cached) => cached.html.clone(),
+            None => self.read_resource(&chapter.href).await?,
+        };
+        String::from_utf8(bytes).map_err(|_| EpubError::ChapterNotUtf8 { href: chapter.href })
+    }
+
+    /// Resolve chapter stylesheet sources in cascade order with explicit limits.
+    pub async fn chapter_stylesheets_with_options(
+        &mut self,
+        index: usize,
+        limits: StyleLimits,
+    ) -> Result<ChapterStylesheets, EpubError> {
+        let chapter = self.resolve_displayable_chapter(index)?;
+        let html = self.read_resource(&chapter.href).await?;
+        self.stylesheets_for(&chapter.href, &html, limits).await
+    }
+
+    async fn stylesheets_for(
+        &mut self,
+        chapter_href: &str,
+        html: &[u8],
+        limits: StyleLimits,
+    ) -> Result<ChapterStylesheets, EpubError> {
+        let mut sources = Vec::with_capacity(0);
+        for href in parse_stylesheet_links_bytes(chapter_href, html) {
+            let mut bytes = Vec::with_capacity(0);
+            self.read_resource_into_with_hard_cap(&href, &mut bytes, limits.max_css_bytes)
+                .await
+                .map_err(|err| match err {
+                    EpubError::Zip(ZipError::FileTooLarge) => EpubError::Parse(format!(
+                        "Stylesheet exceeds max_css_bytes ({}) at '{}'",
+                        limits.max_css_bytes, href
+                    )),
+                    other => other,
+                })?;
+            let css = String::from_utf8(bytes)
+                .map_err(|_| EpubError::Parse(format!("Stylesheet is not UTF-8: {}", href)))?;
+            sources.push(StylesheetSource { href, css });
+        }
+        Ok(ChapterStylesheets { sources })
+    }
+
+    /// Stream styled chapter events/runs, awaiting chapter and stylesheet reads.
+    ///
+    /// Async counterpart of [`EpubBook::chapter_events`]: resources are fetched
+    /// from the async source first, then styled without further I/O.
+    /// The chapter read is capped by `opts.render.memory.max_entry_bytes` and
+    /// stylesheet reads by the style `max_css_bytes` limit, so oversized
+    /// entries are rejected before they are buffered.
+    pub async fn chapter_events<F>(
+        &mut self,
+        index: usize,
+        opts: ChapterEventsOptions,
+        on_item: F,
+    ) -> Result<usize, EpubError>
+    where
+        F: FnMut(StyledEventOrRun) -> Result<(), EpubError>,
+    {
+        let chapter = self.resolve_displayable_chapter(index)?;
+        self.last_chapter = Some(index);
+        if let Some(cached) = self
+            .prefetched_chapter(index)
+            .filter(|cached| cached.events == opts)
+        {
+            let mut on_item = on_item;
+            for item in &cached.items {
+                on_item(item.clone())?;
+            }
+            return Ok(cached.items.len());
+        }
+        let mut html = Vec::with_capacity(0);
+        self.read_resource_into_with_hard_cap(
+            &chapter.href,
+            &mut html,
+            opts.render.memory.max_entry_bytes,
+        )
+        .await?;
+        let stylesheets = self
+            .stylesheets_for(&chapter.href, &html, opts.render.style.limits)
+            .await?;
+
+        let mut prep = RenderPrep::new(opts.render).with_serif_default();
+        emit_capped_chapter_events(opts.max_items, on_item, |emit| {
+            prep.prepare_chapter_source_with(index, &chapter.href, &html, &stylesheets, emit)
+        })
+    }
+
+    /// Set read-ahead options for [`Self::prefetch_next`].
+    pub fn set_prefetch_options(&mut self, options: PrefetchOptions) {
+        self.prefetch_options = options;
+        self.trim_prefetched();
+    }
+
+    /// Decompress and style-prepare the `n` spine items after the last chapter
+    /// read, so crossing into them does not stall on I/O or styling.
+    ///
+    /// Archive reads are sequential; styling runs on tokio's blocking pool with
+    /// at most [`PrefetchOptions::max_concurrency`] chapters in flight, or
+    /// inline on the calling task when no tokio runtime is running.
+    /// `n` is clamped to [`PrefetchOptions::max_cached_chapters`]. Returns the
+    /// number of chapters newly cached; chapters already cached with the
+    /// current options are skipped.
+    pub async fn prefetch_next(&mut self, n: usize) -> Result<usize, EpubError> {
+        let opts = self.prefetch_options;
+        let start = self.last_chapter.map_or(0, |index| index + 1);
+        let end = start
+            .saturating_add(n.min(opts.max_cached_chapters))
+            .min(self.chapter_count());
+        let limit = opts.max_concurrency.max(1);
+        let mut in_flight = VecDeque::with_capacity(limit);
+        let mut cached = 0usize;
+
+        for index in start..end {
+            if self
+                .prefetched_chapter(index)
+                .is_some_and(|chapter| chapter.events == opts.events)
+            {
+                continue;
+            }
+            if in_flight.len() >= limit {
+                if let Some(task) = in_flight.pop_front() {
+                    self.store_prefetched(join_prefetch(task).await?);
+                    cached += 1;
+                }
+            }
+            let chapter = self.resolve_displayable_chapter(index)?;
+            let mut html = Vec::with_capacity(0);
+            self.read_resource_into_with_hard_cap(
+                &chapter.href,
+                &mut html,
+                opts.events.render.memory.max_entry_bytes,
+            )
+            .await?;
+            let stylesheets = self
+                .stylesheets_for(&chapter.href, &html, opts.events.render.style.limits)
+                .await?;
+            let job = move || prepare_prefetch(index, &chapter.href, html, &stylesheets, opts);
+            match tokio::runtime::Handle::try_current() {
+                Ok(runtime) => in_flight.push_back(runtime.spawn_blocking(job)),
+                Err(_) => {
+                    self.store_prefetched(job()?);
+                    cached += 1;
+                }
+            }
+        }
+        while let Some(task) = in_flight.pop_front() {
+            self.store_prefetched(join_prefetch(task).await?);
+            cached += 1;
+        }
+        Ok(cached)
+    }
+
+    /// Indices of chapters currently held by the prefetch cache.
+    pub fn prefetched_chapters(&self) -> impl Iterator<Item = usize> + '_ {
+        self.prefetched.iter().map(|chapter| chapter.index)
+    }
+
+    fn prefetched_chapter(&self, index: usize) -> Option<&PrefetchedChapter> {
+        self.prefetched
+            .iter()
+            .find(|chapter| chapter.index == index)
+    }
+
+    fn store_prefetched(&mut self, chapter: PrefetchedChapter) {
+        self.prefetched
+            .retain(|cached| cached.index != chapter.index);
+        self.prefetched.push_back(chapter);
+        self.trim_prefetched();
+    }
+
+    fn trim_prefetched(&mut self) {
+        while self.prefetched.len() > self.prefetch_options.max_cached_chapters {
+            self.prefetched.pop_front();
+        }
+    }
+}
+
+fn prepare_prefetch(
+    index: usize,
+    href: &str,
+    html: Vec<u8>,
+    stylesheets: &ChapterStylesheets,
+    opts: PrefetchOptions,
+) -> Result<PrefetchedChapter, EpubError> {
+    let mut items = Vec::with_capacity(0);
+    let mut prep = RenderPrep::new(opts.events.render).with_serif_default();
+    emit_capped_chapter_events(
+        opts.events.max_items,
+        |item| {
+            items.push(item);
+            Ok(())
+        },
+        |emit| prep.prepare_chapter_source_with(index, href, &html, stylesheets, emit),
+    )?;
+    Ok(PrefetchedChapter {
+        index,
+        html,
+        events: opts.events,
+        items,
+    })
+}
+
+async fn join_prefetch(
+    task: tokio::task::JoinHandle<Result<PrefetchedChapter, EpubError>>,
+) -> Result<PrefetchedChapter, EpubError> {
+    task.await
+        .map_err(|err| EpubError::Io(format!("Prefetch task failed: {}", err)))?
+}
+
+async fn read_entry<R: AsyncRead + AsyncSeek + Unpin>(
+    zip: &mut AsyncStreamingZip<R>,
+    path: &str,
+    max_bytes: usize,
+) -> Result<Vec<u8>, EpubError> {
+    let mut buf = Vec::with_capacity(0);
+    read_entry_into(zip, path, &mut buf, max_bytes).await?;
+    Ok(buf)
+}
+
+async fn read_entry_into<R: AsyncRead + AsyncSeek + Unpin, W: Write>(
+    zip: &mut AsyncStreamingZip<R>,
+    path: &str,
+    writer: &mut W,
+    max_bytes: usize,
+) -> Result<usize, EpubError> {
+    let entry = zip
+        .get_entry(path)
+        .ok_or(EpubError::Zip(ZipError::FileNotFound))?
+        .clone();
+    if entry.uncompressed_size > max_bytes as u64 || entry.compressed_size > max_bytes as u64 {
+        return Err(EpubError::Zip(ZipError::FileTooLarge));
+    }
+    zip.read_file_to_writer(&entry, writer)
+        .await
+        .map_err(EpubError::Zip)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIXTURE: &str =
+        "tests/fixtures/Fundamental-Accessibility-Tests-Basic-Functionality-v2.0.0.epub";
+
+    fn block_on<F: core::future::Future>(future: F) -> F::Output {
+        tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap()
+            .block_on(future)
+    }
+
+    #[test]
+    fn async_book_matches_blocking_book() {
+        let mut blocking = EpubBook::open(FIXTURE).unwrap();
+        block_on(async {
+            let bytes = tokio::fs::read(FIXTURE).await.unwrap();
+            let mut book = AsyncEpubBook::from_reader(Cursor::new(bytes))
+                .await
+                .unwrap();
+            assert_eq!(book.metadata(), blocking.metadata());
+            assert_eq!(book.chapter_count(), blocking.chapter_count());
+            assert_eq!(book.navigation(), blocking.navigation());
+
+            for index in 0..book.chapter_count() {
+                let expected = blocking.chapter_html(index).unwrap();
+                assert_eq!(book.chapter_html(index).await.unwrap(), expected);
+            }
+        });
+    }
+
+    #[test]
+    fn prefetch_next_caches_following_chapters() {
+        let mut blocking = EpubBook::open(FIXTURE).unwrap();
+        block_on(async {
+            let mut book = AsyncEpubBook::open(FIXTURE).await.unwrap();
+            book.set_prefetch_options(PrefetchOptions {
+                max_concurrency: 1,
+                max_cached_chapters: 2,
+                ..PrefetchOptions::default()
+            });
+            book.chapter_html(0).await.unwrap();
+            assert_eq!(book.prefetch_next(5).await.unwrap(), 2);
+            assert_eq!(book.prefetched_chapters().collect::<Vec<_>>(), [1, 2]);
+            // Already-cached chapters are not prepared again.
+            assert_eq!(book.prefetch_next(2).await.unwrap(), 0);
+
+            let mut expected = Vec::with_capacity(0);
+            blocking
+                .chapter_events(1, ChapterEventsOptions::default(), |item| {
+                    expected.push(item);
+                    Ok(())
+                })
+                .unwrap();
+            let mut actual = Vec::with_capacity(0);
+            let count = book
+                .chapter_events(1, ChapterEventsOptions::default(), |item| {
+                    actual.push(item);
+                    Ok(())
+                })
+                .await
+                .unwrap();
+            assert_eq!(count, expected.len());
+            assert_eq!(actual, expected);
+            assert_eq!(
+                book.chapter_html(2).await.unwrap(),
+                blocking.chapter_html(2).unwrap()
+            );
+
+            // Reading chapter 2 moves the window forward.
+            assert_eq!(book.prefetch_next(1).await.unwrap(), 1);
+            assert_eq!(book.prefetched_chapters().collect::<Vec<_>>(), [2, 3]);
+        });
+    }
+
+    #[test]
+    fn prefetch_next_runs_inline_without_tokio_runtime() {
+        let bytes = std::fs::read(FIXTURE).unwrap();
+        // Polls with a no-op waker; in-memory reads are always ready.
+        let mut future = core::pin::pin!(async move {
+            let mut book = AsyncEpubBook::from_reader(Cursor::new(bytes)).await?;
+            book.chapter_html(0).await?;
+            let cached = book.prefetch_next(2).await?;
+            Ok::<_, EpubError>((cached, book.prefetched_chapters().collect::<Vec<_>>()))
+        });
+        let mut cx = core::task::Context::from_waker(core::task::Waker::noop());
+        let core::task::Poll::Ready(result) = core::future::Future::poll(future.as_mut(), &mut cx)
+        else {
+            panic!("in-memory book read should not pend");
+        };
+        assert_eq!(result.unwrap(), (2, vec![1, 2]));
+    }
+
+    #[test]
+    fn async_chapter_events_match_blocking_events() {
+        let mut blocking = EpubBook::open(FIXTURE).unwrap();
+        let mut expected = Vec::with_capacity(0);
+        blocking
+            .chapter_events(1, ChapterEventsOptions::default(), |item| {
+                expected.push(item);
+                Ok(())
+            })
+            .unwrap();
+
+        block_on(async {
+            let mut book = AsyncEpubBook::open(FIXTURE).await.unwrap();
+            let mut actual = Vec::with_capacity(0);
+            let count = book
+                .chapter_events(1, ChapterEventsOptions::default(), |item| {
+                    actual.push(item);
+                    Ok(())
+                })
+                .await
+                .unwrap();
+            assert_eq!(count, actual.len());
+            assert_eq!(actual, expected);
+        });
+    }
 }
@@ -3,7 +3,7 @@ use std::process::ExitCode;
 
 use mu_epub::metadata::EpubMetadata;
 use mu_epub::navigation::NavPoint;
-use mu_epub::validate::{validate_epub_file, ValidationDiagnostic, ValidationSeverity};
+use mu_epub::validate::{validate_epub_file, ValidationDiagnostic};
 use mu_epub::{ChapterRef, EpubBook, EpubError};
 
 #[derive(Clone, Debug)]
@@ -501,10 +501,7 @@ fn metadata_json(metadata: &EpubMetadata) -> Json {
 }
 
 fn diagnostic_json(diag: &ValidationDiagnostic) -> Json {
-    let severity = match diag.severity {
-        ValidationSeverity::Error => "error",
-        ValidationSeverity::Warning => "warning",
-    };
+    let severity = diag.severity.as_str();
     Json::Obj(vec![
         ("code".to_string(), Json::Str(diag.code.to_string())),
         ("severity".to_string(), Json::Str(severity.to_string())),
@@ -520,6 +517,7 @@ fn diagnostic_json(diag: &ValidationDiagnostic) -> Json {
         (
             "spec_ref".to_string(),
             diag.spec_ref
+                .as_ref()
                 .map(|v| Json::Str(v.to_string()))
                 .unwrap_or(Json::Null),
         ),
@@ -6,30 +6,48 @@
 
 extern crate alloc;
 
+use alloc::collections::VecDeque;
 use alloc::format;
 use alloc::string::{String, ToString};
 use alloc::vec::Vec;
 use core::str;
 use quick_xml::events::Event;
 use quick_xml::reader::Reader;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
 use std::fs::File;
+use std::hash::{Hash, Hasher};
 use std::io::{Read, Seek, Write};
 use std::path::Path;
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
 
+use crate::css::{cascade, parse_stylesheet, Display, Stylesheet};
 use crate::error::{
     EpubError, ErrorLimitContext, ErrorPhase, LimitKind, PhaseError, PhaseErrorContext, ZipError,
 };
-use crate::metadata::{extract_metadata, EpubMetadata};
-use crate::navigation::{parse_nav_xhtml, parse_ncx, NavPoint, Navigation};
+use crate::export::{ExportFormat, ExportOptions, ExportWriter};
+use crate::extract::{
+    sanitized_relative_path, ExtractOptions, ExtractProgress, ExtractReport, ExtractedResource,
+    ResourceFilter, SkipReason, SkippedResource,
+};
+use crate::lang::{detect_language, Language, LanguageGuess};
+use crate::metadata::{extract_metadata, EpubMetadata, ManifestItem};
+use crate::navigation::{href_targets_chapter, parse_nav_xhtml, parse_ncx, NavPoint, Navigation};
+pub use crate::persist::ReadingPosition;
 use crate::render_prep::{
-    parse_font_faces_from_css, parse_stylesheet_links, ChapterStylesheets, EmbeddedFontFace,
-    FontLimits, RenderPrep, RenderPrepOptions, StyleLimits, StyledChapter, StyledEventOrRun,
-    StylesheetSource,
+    parse_font_faces_from_css, parse_stylesheet_links, ChapterEventIter, ChapterStylesheets,
+    EmbeddedFontFace, FontLimits, FontObfuscation, FontObfuscationMap, RenderPrep, RenderPrepError,
+    RenderPrepOptions, StyleLimits, StyledChapter, StyledEventOrRun, StylesheetSource,
 };
 use crate::spine::Spine;
+use crate::split::{ChapterSegment, ChapterSplitOptions, RangeCapture, SegmentPlanner};
+use crate::storage::{RandomAccess, ReadSeekAdapter};
 
-use crate::tokenizer::{tokenize_html, Token};
-use crate::zip::{CdEntry, StreamingZip, ZipLimits};
+use crate::tokenizer::{
+    tokenize_html_with_stylesheets, PrefixCache, Token, TokenizeError, TokenizeLimits, Tokenizer,
+};
+use crate::zip::{percent_decode_path, CdEntry, StreamingZip, ZipLimits};
 
 /// Validation strictness for high-level open/parse flows.
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
@@ -53,6 +71,9 @@ pub struct EpubBookOptions {
     pub validation_mode: ValidationMode,
     /// Optional cap for navigation payload bytes.
     pub max_nav_bytes: Option<usize>,
+    /// Fall back to Unicode-tolerant ZIP entry-name matching (case folding,
+    /// NFC/NFD equivalence) when an href has no exact match.
+    pub tolerant_entry_names: bool,
 }
 
 impl Default for EpubBookOptions {
@@ -61,6 +82,7 @@ impl Default for EpubBookOptions {
             zip_limits: None,
             validation_mode: ValidationMode::Lenient,
             max_nav_bytes: None,
+            tolerant_entry_names: false,
         }
     }
 }
@@ -72,6 +94,9 @@ pub struct OpenConfig {
     pub options: EpubBookOptions,
     /// When enabled, navigation parsing is deferred until `ensure_navigation`.
     pub lazy_navigation: bool,
+    /// Time each open phase; read back with [`EpubBook::open_profile`].
+    /// When disabled, open never reads the clock.
+    pub profile: bool,
 }
 
 impl From<EpubBookOptions> for OpenConfig {
@@ -79,6 +104,60 @@ impl From<EpubBookOptions> for OpenConfig {
         Self {
             options,
             lazy_navigation: false,
+            profile: false,
+        }
+    }
+}
+
+/// Cost of one open phase.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct OpenPhaseStats {
+    /// Wall-clock time spent in the phase.
+    pub duration: Duration,
+    /// Archive bytes the phase consumed (stored sizes, excluding ZIP headers).
+    pub bytes: u64,
+}
+
+/// Per-phase breakdown of [`EpubBook`] open time.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct OpenProfile {
+    /// EOCD search and central directory parse.
+    pub central_directory: OpenPhaseStats,
+    /// `mimetype` entry check.
+    pub mimetype: OpenPhaseStats,
+    /// `container.xml` and OPF read and parse, including spine validation.
+    pub package: OpenPhaseStats,
+    /// Navigation document read and parse; `None` until navigation loads
+    /// when [`OpenConfig::lazy_navigation`] is set.
+    pub navigation: Option<OpenPhaseStats>,
+}
+
+impl OpenProfile {
+    /// Sum of all recorded phase durations.
+    pub fn total(&self) -> Duration {
+        self.central_directory.duration
+            + self.mimetype.duration
+            + self.package.duration
+            + self.navigation.map_or(Duration::ZERO, |nav| nav.duration)
+    }
+}
+
+/// Phase clock that is only started when profiling.
+#[derive(Clone, Copy)]
+struct PhaseTimer(Option<Instant>);
+
+impl PhaseTimer {
+    fn start(enabled: bool) -> Self {
+        Self(enabled.then(Instant::now))
+    }
+
+    fn stop(self, bytes: impl FnOnce() -> u64) -> OpenPhaseStats {
+        match self.0 {
+            Some(start) => OpenPhaseStats {
+                duration: start.elapsed(),
+                bytes: bytes(),
+            },
+            None => OpenPhaseStats::default(),
         }
     }
 }
@@ -155,6 +234,94 @@ impl StreamingChapterOptions {
     }
 }
 
+/// Elements whose content [`TextExtractOptions::default`] drops.
+pub const DEFAULT_TEXT_SKIP_ELEMENTS: &[&str] = &[
+    "script",
+    "style",
+    "head",
+    "nav",
+    "header",
+    "footer",
+    "aside",
+    "noscript",
+    "epub:case",
+    "epub:trigger",
+];
+
+/// Plain-text extraction options for [`EpubBook::chapter_text_with_options`].
+///
+/// The default matches [`EpubBook::chapter_text`]; [`Self::search`],
+/// [`Self::tts`] and [`Self::excerpt`] are starting points for other
+/// consumers.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TextExtractOptions {
+    /// Elements whose content is dropped, matched by qualified tag name.
+    pub skip_elements: &'static [&'static str],
+    /// Separator emitted between block elements (`p`, `div`, `li`).
+    pub block_separator: &'static str,
+    /// Separator emitted for `<br>`.
+    pub line_break_separator: &'static str,
+    /// Emit the `alt` text of images in place of the image.
+    pub include_alt_text: bool,
+    /// Drop elements carrying a `hidden` attribute, an inline
+    /// `display: none` style, or a `display: none` rule from the chapter's
+    /// stylesheets. Clear it to index hidden content such as answer keys.
+    pub drop_hidden: bool,
+    /// Hard cap on output bytes, truncated on a UTF-8 boundary.
+    pub max_bytes: usize,
+}
+
+impl Default for TextExtractOptions {
+    fn default() -> Self {
+        Self {
+            skip_elements: DEFAULT_TEXT_SKIP_ELEMENTS,
+            block_separator: "\n",
+            line_break_separator: "\n",
+            include_alt_text: false,
+            drop_hidden: false,
+            max_bytes: usize::MAX,
+        }
+    }
+}
+
+impl TextExtractOptions {
+    /// Search indexing: every visible word, including image descriptions.
+    pub fn search() -> Self {
+        Self {
+            include_alt_text: true,
+            drop_hidden: true,
+            ..Self::default()
+        }
+    }
+
+    /// Text-to-speech: visible text with paragraph pauses and image
+    /// descriptions; footnote asides stay skipped.
+    pub fn tts() -> Self {
+        Self {
+            block_separator: "\n\n",
+            include_alt_text: true,
+            drop_hidden: true,
+            ..Self::default()
+        }
+    }
+
+    /// Excerpt display: visible text run together on one line.
+    pub fn excerpt() -> Self {
+        Self {
+            block_separator: " ",
+            line_break_separator: " ",
+            drop_hidden: true,
+            ..Self::default()
+        }
+    }
+
+    /// Set the output byte cap.
+    pub fn with_max_bytes(mut self, max_bytes: usize) -> Self {
+        self.max_bytes = max_bytes;
+        self
+    }
+}
+
 /// Result from streaming chapter event processing.
 #[derive(Clone, Debug)]
 pub struct ChapterStreamResult {
@@ -202,16 +369,30 @@ impl EpubBookBuilder {
         self
     }
 
+    /// Enable tolerant ZIP entry-name matching for quirky archives.
+    pub fn with_tolerant_entry_names(mut self, tolerant: bool) -> Self {
+        self.options.tolerant_entry_names = tolerant;
+        self
+    }
+
     /// Open an EPUB from a file path.
     pub fn open<P: AsRef<Path>>(self, path: P) -> Result<EpubBook<File>, EpubError> {
         EpubBook::open_with_options(path, self.options)
     }
 
     /// Open an EPUB from an arbitrary reader.
-    pub fn from_reader<R: Read + Seek>(self, reader: R) -> Result<EpubBook<R>, EpubError> {
+    pub fn from_reader<R: Read + Seek>(
+        self,
+        reader: R,
+    ) -> Result<EpubBook<ReadSeekAdapter<R>>, EpubError> {
         EpubBook::from_reader_with_options(reader, self.options)
     }
 
+    /// Open an EPUB from any [`RandomAccess`] storage.
+    pub fn from_storage<R: RandomAccess>(self, storage: R) -> Result<EpubBook<R>, EpubError> {
+        EpubBook::from_storage_with_options(storage, self.options)
+    }
+
     /// Parse summary metadata from a file path.
     pub fn parse_file<P: AsRef<Path>>(self, path: P) -> Result<EpubSummary, EpubError> {
         parse_epub_file_with_options(path, self.options)
@@ -221,6 +402,11 @@ impl EpubBookBuilder {
     pub fn parse_reader<R: Read + Seek>(self, reader: R) -> Result<EpubSummary, EpubError> {
         parse_epub_reader_with_options(reader, self.options)
     }
+
+    /// Parse summary metadata from any [`RandomAccess`] storage.
+    pub fn parse_storage<R: RandomAccess>(self, storage: R) -> Result<EpubSummary, EpubError> {
+        parse_epub_storage_with_options(storage, self.options)
+    }
 }
 
 /// Parsed top-level EPUB data for lightweight usage.
@@ -257,9 +443,23 @@ pub fn parse_epub_reader<R: Read + Seek>(reader: R) -> Result<EpubSummary, EpubE
 pub fn parse_epub_reader_with_options<R: Read + Seek>(
     reader: R,
     options: EpubBookOptions,
+) -> Result<EpubSummary, EpubError> {
+    parse_epub_storage_with_options(ReadSeekAdapter::new(reader), options)
+}
+
+/// Parse an EPUB from any [`RandomAccess`] storage.
+pub fn parse_epub_storage<R: RandomAccess>(storage: R) -> Result<EpubSummary, EpubError> {
+    parse_epub_storage_with_options(storage, EpubBookOptions::default())
+}
+
+/// Parse an EPUB from any [`RandomAccess`] storage with explicit options.
+pub fn parse_epub_storage_with_options<R: RandomAccess>(
+    storage: R,
+    options: EpubBookOptions,
 ) -> Result<EpubSummary, EpubError> {
     let mut zip =
-        StreamingZip::new_with_limits(reader, options.zip_limits).map_err(EpubError::Zip)?;
+        StreamingZip::new_with_limits(storage, options.zip_limits).map_err(EpubError::Zip)?;
+    zip.set_tolerant_names(options.tolerant_entry_names);
     load_summary_from_zip(&mut zip, options)
 }
 
@@ -274,11 +474,11 @@ pub fn parse_epub_file_with_options<P: AsRef<Path>>(
     options: EpubBookOptions,
 ) -> Result<EpubSummary, EpubError> {
     let file = File::open(path).map_err(|e| EpubError::Io(e.to_string()))?;
-    parse_epub_reader_with_options(file, options)
+    parse_epub_storage_with_options(file, options)
 }
 
 /// High-level EPUB handle backed by an open ZIP reader.
-pub struct EpubBook<R: Read + Seek> {
+pub struct EpubBook<R: RandomAccess> {
     zip: StreamingZip<R>,
     opf_path: String,
     metadata: EpubMetadata,
@@ -288,36 +488,159 @@ pub struct EpubBook<R: Read + Seek> {
     navigation_loaded: bool,
     navigation: Option<Navigation>,
     embedded_fonts_cache: Option<Vec<EmbeddedFontFace>>,
+    open_profile: Option<OpenProfile>,
+    spine_index: OnceLock<SpineIndex>,
+}
+
+/// Parsed package state detached from an [`EpubBook`].
+pub(crate) struct BookParts<R: RandomAccess> {
+    pub(crate) zip: StreamingZip<R>,
+    pub(crate) opf_path: String,
+    pub(crate) metadata: EpubMetadata,
+    pub(crate) spine: Spine,
+    pub(crate) navigation: Option<Navigation>,
 }
 
 /// Lightweight chapter descriptor in spine order.
-#[derive(Clone, Debug, PartialEq, Eq)]
+///
+/// Ordering compares `index` first, so sorted collections follow the spine.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ChapterRef {
     /// Spine position index.
     pub index: usize,
+    /// Part of the spine item this chapter covers; see [`Self::segment`].
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub(crate) segment: Option<ChapterSegment>,
     /// Spine `idref`.
     pub idref: String,
     /// Manifest href relative to OPF.
     pub href: String,
     /// Manifest media type.
     pub media_type: String,
+    /// Label of the first table-of-contents entry pointing at this chapter.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub title: Option<String>,
+    /// Whether the manifest item declares `properties="scripted"`, i.e.
+    /// EPUB 3 interactive content whose scripts will not run here.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub is_scripted: bool,
 }
 
-/// Stable reading position with anchor + fallback offset information.
+impl ChapterRef {
+    /// Part of the spine item this chapter covers when it was split by
+    /// [`EpubBook::split_chapters`]; `None` for whole spine items.
+    pub fn segment(&self) -> Option<&ChapterSegment> {
+        self.segment.as_ref()
+    }
+}
+
+/// Scripted (interactive) content found along a book's spine.
+///
+/// Built by [`EpubBook::scripted_content_summary`] so UIs can warn up front
+/// that interactive features are unavailable.
 #[derive(Clone, Debug, Default, PartialEq, Eq)]
-pub struct ReadingPosition {
-    /// 0-based chapter index in spine order.
-    pub chapter_index: usize,
-    /// Optional chapter href hint for robust restore across index shifts.
-    pub chapter_href: Option<String>,
-    /// Optional anchor payload (fragment id or CFI-like token).
-    pub anchor: Option<String>,
-    /// Fallback character offset in the chapter when anchor cannot be resolved.
-    pub fallback_offset: usize,
+pub struct ScriptedContentSummary {
+    /// Spine indices whose content item declares `properties="scripted"`.
+    pub declared: Vec<usize>,
+    /// Spine indices whose XHTML contains a `<script>` element.
+    pub script_elements: Vec<usize>,
+}
+
+impl ScriptedContentSummary {
+    /// Whether no chapter is scripted by declaration or markup.
+    pub fn is_empty(&self) -> bool {
+        self.declared.is_empty() && self.script_elements.is_empty()
+    }
+
+    /// Whether the chapter at spine `index` is scripted by declaration or markup.
+    pub fn contains(&self, index: usize) -> bool {
+        self.declared.contains(&index) || self.script_elements.contains(&index)
+    }
+}
+
+/// Most chapter text bytes sampled for language detection.
+pub const LANGUAGE_SAMPLE_BYTES: usize = 4 * 1024;
+
+/// Book-level statistics gathered by [`EpubBook::stats`].
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub struct BookStats {
+    /// Number of entries in the spine reading order.
+    pub chapter_count: usize,
+    /// Language parsed from `dc:language`, when it names a detectable language.
+    pub declared_language: Option<Language>,
+    /// Language guessed from the opening chapters' text.
+    pub detected_language: Option<LanguageGuess>,
+}
+
+impl BookStats {
+    /// Language to use for hyphenation and line breaking.
+    ///
+    /// Prefers the declared language unless detection confidently disagrees;
+    /// falls back to any detected language when nothing usable is declared.
+    pub fn effective_language(&self) -> Option<Language> {
+        match (self.declared_language, self.detected_language) {
+            (_, Some(guess)) if guess.is_confident() => Some(guess.language),
+            (Some(declared), _) => Some(declared),
+            (None, guess) => guess.map(|guess| guess.language),
+        }
+    }
+}
+
+/// Chapter markup above this many bytes is considered slow to prepare.
+const HEAVY_CHAPTER_BYTES: u64 = 256 * 1024;
+/// Stylesheet payloads above this many bytes are considered slow to resolve.
+const HEAVY_STYLESHEET_BYTES: u64 = 128 * 1024;
+/// Font counts above this are considered slow to load.
+const HEAVY_FONT_COUNT: usize = 4;
+
+/// Cheap preparation-cost estimate from [`EpubBook::chapter_complexity`].
+///
+/// Sizes come from the ZIP central directory and resource counts from the
+/// manifest; nothing is decompressed. Stylesheet, font, and image figures
+/// cover the whole manifest, since which of them a chapter links is only
+/// known after reading its markup.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ChapterComplexity {
+    /// Spine index the estimate is for.
+    pub index: usize,
+    /// Uncompressed size of the displayed chapter document.
+    pub chapter_bytes: u64,
+    /// Compressed size of the displayed chapter document.
+    pub compressed_bytes: u64,
+    /// CSS items in the manifest.
+    pub stylesheet_count: usize,
+    /// Uncompressed bytes of all CSS items.
+    pub stylesheet_bytes: u64,
+    /// Font items in the manifest.
+    pub font_count: usize,
+    /// Uncompressed bytes of all font items.
+    pub font_bytes: u64,
+    /// Image items in the manifest.
+    pub image_count: usize,
+    /// The chapter declares `properties="mathml"`.
+    pub has_mathml: bool,
+    /// The chapter declares `properties="svg"`.
+    pub has_svg: bool,
+}
+
+impl ChapterComplexity {
+    /// Whether preparing the chapter likely takes long enough to show progress.
+    ///
+    /// True for large markup, large stylesheets, many fonts, or MathML.
+    pub fn is_heavy(&self) -> bool {
+        self.chapter_bytes > HEAVY_CHAPTER_BYTES
+            || self.stylesheet_bytes > HEAVY_STYLESHEET_BYTES
+            || self.font_count > HEAVY_FONT_COUNT
+            || self.has_mathml
+    }
 }
 
 /// Semantic navigation primitive for seeking/resolve operations.
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
 pub enum Locator {
     /// Resolve by chapter index.
     Chapter(usize),
@@ -332,7 +655,7 @@ pub enum Locator {
 }
 
 /// Fully resolved location information returned from locator APIs.
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct ResolvedLocation {
     /// Resolved chapter descriptor.
     pub chapter: ChapterRef,
@@ -343,26 +666,161 @@ pub struct ResolvedLocation {
 }
 
 /// Lightweight mutable reading session detached from ZIP/file state.
+///
+/// Content-aware operations (progress from offsets, excerpts) go through
+/// [`attach`](Self::attach), which borrows a book for their duration.
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ReadingSession {
     chapters: Vec<ChapterRef>,
     navigation: Option<Navigation>,
     current: ReadingPosition,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    href_index: Option<SpineIndex>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    history: NavigationHistory,
+}
+
+/// Positions history for [`ReadingSession::go_back`] and
+/// [`ReadingSession::go_forward`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+struct NavigationHistory {
+    back: VecDeque<ReadingPosition>,
+    forward: Vec<ReadingPosition>,
+    capacity: usize,
+}
+
+impl Default for NavigationHistory {
+    fn default() -> Self {
+        Self {
+            back: VecDeque::with_capacity(0),
+            forward: Vec::with_capacity(0),
+            capacity: DEFAULT_HISTORY_CAPACITY,
+        }
+    }
 }
 
+impl NavigationHistory {
+    /// Record a jump away from `from`; the forward stack is dropped.
+    fn push(&mut self, from: ReadingPosition) {
+        self.forward.clear();
+        if self.capacity == 0 {
+            return;
+        }
+        if self.back.len() == self.capacity {
+            self.back.pop_front();
+        }
+        self.back.push_back(from);
+    }
+}
+
+/// Positions kept by a [`ReadingSession`]'s back history by default.
+pub const DEFAULT_HISTORY_CAPACITY: usize = 32;
+
 impl ReadingSession {
     /// Create a reading session from chapter descriptors and optional navigation.
     pub fn new(chapters: Vec<ChapterRef>, navigation: Option<Navigation>) -> Self {
         let first_href = chapters.first().map(|c| c.href.clone());
+        let first_segment = chapters.first().and_then(segment_index);
         Self {
             chapters,
             navigation,
             current: ReadingPosition {
                 chapter_index: 0,
                 chapter_href: first_href,
+                segment: first_segment,
                 anchor: None,
                 fallback_offset: 0,
             },
+            href_index: None,
+            history: NavigationHistory::default(),
+        }
+    }
+
+    /// Keep at most `capacity` positions of back history (`0` disables it).
+    ///
+    /// Older entries are dropped first.
+    pub fn with_history_capacity(mut self, capacity: usize) -> Self {
+        let history = &mut self.history;
+        history.capacity = capacity;
+        while history.back.len() > capacity {
+            history.back.pop_front();
+        }
+        history.forward.truncate(capacity);
+        self
+    }
+
+    /// Whether [`Self::go_back`] has a position to return to.
+    pub fn can_go_back(&self) -> bool {
+        !self.history.back.is_empty()
+    }
+
+    /// Whether [`Self::go_forward`] has a position to return to.
+    pub fn can_go_forward(&self) -> bool {
+        !self.history.forward.is_empty()
+    }
+
+    /// Return to the position before the most recent jump.
+    ///
+    /// The current position becomes available to [`Self::go_forward`].
+    /// Returns `None` when there is no history.
+    pub fn go_back(&mut self) -> Option<ResolvedLocation> {
+        let previous = self.history.back.pop_back()?;
+        let current = core::mem::replace(&mut self.current, previous);
+        self.history.forward.push(current);
+        self.current_location()
+    }
+
+    /// Redo a jump undone by [`Self::go_back`].
+    ///
+    /// Returns `None` when nothing was undone since the last jump.
+    pub fn go_forward(&mut self) -> Option<ResolvedLocation> {
+        let next = self.history.forward.pop()?;
+        let current = core::mem::replace(&mut self.current, next);
+        self.history.back.push_back(current);
+        if self.history.back.len() > self.history.capacity {
+            self.history.back.pop_front();
+        }
+        self.current_location()
+    }
+
+    /// Forget all back and forward history.
+    pub fn clear_history(&mut self) {
+        self.history.back.clear();
+        self.history.forward.clear();
+    }
+
+    fn current_location(&self) -> Option<ResolvedLocation> {
+        let chapter = self.chapters.get(self.current.chapter_index)?.clone();
+        Some(ResolvedLocation {
+            chapter,
+            fragment: self.current.anchor.clone(),
+            position: self.current.clone(),
+        })
+    }
+
+    /// Index chapter hrefs so [`Locator::Href`] resolves without a scan.
+    fn with_href_index(mut self) -> Self {
+        self.href_index = Some(SpineIndex::build(self.chapters.iter().enumerate().map(
+            |(position, chapter)| (position, chapter.idref.as_str(), chapter.href.as_str()),
+        )));
+        self
+    }
+
+    /// Position in `chapters` of the chapter with the normalized `href`.
+    fn find_href(&self, href: &str) -> Option<usize> {
+        let key = spine_href_key(href);
+        let is_match = |position: usize| {
+            self.chapters
+                .get(position)
+                .is_some_and(|chapter| spine_href_key(&chapter.href) == key)
+        };
+        match self.href_index.as_ref().map(|index| index.href(&key)) {
+            Some(SpineLookup::Hit(position)) if is_match(position) => Some(position),
+            Some(SpineLookup::Miss) => None,
+            _ => (0..self.chapters.len()).find(|&position| is_match(position)),
         }
     }
 
@@ -408,7 +866,21 @@ impl ReadingSession {
     }
 
     /// Resolve a semantic locator to a concrete chapter/fragment location.
+    ///
+    /// Moving to a different position records the previous one for
+    /// [`Self::go_back`] and drops the forward history, except for
+    /// [`Locator::Position`], which restores rather than navigates.
     pub fn resolve_locator(&mut self, loc: Locator) -> Result<ResolvedLocation, EpubError> {
+        let records_history = !matches!(loc, Locator::Position(_));
+        let before = self.current.clone();
+        let resolved = self.resolve_locator_inner(loc)?;
+        if records_history && self.current != before {
+            self.history.push(before);
+        }
+        Ok(resolved)
+    }
+
+    fn resolve_locator_inner(&mut self, loc: Locator) -> Result<ResolvedLocation, EpubError> {
         match loc {
             Locator::Chapter(index) => {
                 let chapter =
@@ -421,6 +893,7 @@ impl ReadingSession {
                         })?;
                 self.current.chapter_index = index;
                 self.current.chapter_href = Some(chapter.href.clone());
+                self.current.segment = segment_index(&chapter);
                 self.current.anchor = None;
                 Ok(ResolvedLocation {
                     chapter,
@@ -430,17 +903,13 @@ impl ReadingSession {
             }
             Locator::Href(href) => {
                 let (base, fragment) = split_href_fragment(&href);
-                let (index, chapter) = self
-                    .chapters
-                    .iter()
-                    .enumerate()
-                    .find(|(_, chapter)| chapter.href == base)
-                    .map(|(idx, chapter)| (idx, chapter.clone()))
-                    .ok_or_else(|| {
-                        EpubError::InvalidEpub(format!("unknown chapter href: {}", href))
-                    })?;
+                let index = self.find_href(&base).ok_or_else(|| {
+                    EpubError::InvalidEpub(format!("unknown chapter href: {}", href))
+                })?;
+                let chapter = self.chapters[index].clone();
                 self.current.chapter_index = index;
                 self.current.chapter_href = Some(chapter.href.clone());
+                self.current.segment = segment_index(&chapter);
                 self.current.anchor = fragment.clone();
                 Ok(ResolvedLocation {
                     chapter,
@@ -463,6 +932,7 @@ impl ReadingSession {
                         })?;
                 self.current.chapter_index = idx;
                 self.current.chapter_href = Some(chapter.href.clone());
+                self.current.segment = segment_index(&chapter);
                 self.current.anchor = Some(fragment.clone());
                 Ok(ResolvedLocation {
                     chapter,
@@ -470,21 +940,823 @@ impl ReadingSession {
                     position: self.current.clone(),
                 })
             }
-            Locator::TocId(id) => {
-                let nav = self.navigation.as_ref().ok_or_else(|| {
-                    EpubError::Navigation("no navigation document available".to_string())
-                })?;
-                let href = find_toc_href(nav, &id).ok_or_else(|| {
-                    EpubError::Navigation(format!("toc id/label not found: {}", id))
-                })?;
-                self.resolve_locator(Locator::Href(href))
+            Locator::TocId(id) => {
+                let nav = self.navigation.as_ref().ok_or_else(|| {
+                    EpubError::Navigation("no navigation document available".to_string())
+                })?;
+                let href = find_toc_href(nav, &id).ok_or_else(|| {
+                    EpubError::Navigation(format!("toc id/label not found: {}", id))
+                })?;
+                self.resolve_locator_inner(Locator::Href(href))
+            }
+            Locator::Position(pos) => {
+                // A split chapter's (href, segment) pair outlives its index.
+                let index = self
+                    .chapters
+                    .iter()
+                    .position(|chapter| {
+                        pos.segment.is_some()
+                            && pos.chapter_href.as_deref() == Some(chapter.href.as_str())
+                            && segment_index(chapter) == pos.segment
+                    })
+                    .unwrap_or(pos.chapter_index);
+                self.seek_position(&ReadingPosition {
+                    chapter_index: index,
+                    ..pos
+                })?;
+                self.resolve_locator_inner(Locator::Chapter(index))
+            }
+        }
+    }
+
+    /// Resolve a locator like [`Self::resolve_locator`], then locate its
+    /// fragment (if any) in the chapter content.
+    ///
+    /// When the fragment's element is found, `fallback_offset` is set to its
+    /// character offset (see [`Self::resolve_fragment_offset`]); otherwise
+    /// the position falls back to the chapter start.
+    pub fn resolve_locator_in_book<R: RandomAccess>(
+        &mut self,
+        book: &mut EpubBook<R>,
+        loc: Locator,
+    ) -> Result<ResolvedLocation, EpubError> {
+        let mut resolved = self.resolve_locator(loc)?;
+        if let Some(fragment) = resolved.fragment.as_deref() {
+            let offset = self.resolve_fragment_offset(book, fragment)?;
+            self.current.fallback_offset = offset.unwrap_or(0);
+            resolved.position = self.current.clone();
+        }
+        Ok(resolved)
+    }
+
+    /// Borrow `book` for content-backed operations on this session.
+    ///
+    /// `book` should be the one the session's chapters came from.
+    pub fn attach<'a, R: RandomAccess>(
+        &'a mut self,
+        book: &'a mut EpubBook<R>,
+    ) -> SessionWithBook<'a, R> {
+        SessionWithBook {
+            session: self,
+            book,
+            chapter_chars: None,
+        }
+    }
+
+    fn current_chapter(&self) -> Result<&ChapterRef, EpubError> {
+        self.chapters
+            .get(self.current.chapter_index)
+            .ok_or(EpubError::ChapterOutOfBounds {
+                index: self.current.chapter_index,
+                chapter_count: self.chapters.len(),
+            })
+    }
+
+    /// Find the element with `id == fragment` in the current chapter.
+    ///
+    /// Returns the character offset of the element within the chapter's
+    /// tokenized text (the total length in `char`s of all `Token::Text`
+    /// content before it), or `None` when no element carries that id.
+    /// A leading `#` on `fragment` is ignored.
+    ///
+    /// # Allocation behavior
+    /// - Streams the chapter through the incremental tokenizer; the chapter
+    ///   is never materialized
+    /// - Stops reading once the anchor is found
+    pub fn resolve_fragment_offset<R: RandomAccess>(
+        &self,
+        book: &mut EpubBook<R>,
+        fragment: &str,
+    ) -> Result<Option<usize>, EpubError> {
+        let chapter = self.current_chapter()?;
+        let fragment = fragment.strip_prefix('#').unwrap_or(fragment);
+        let mut scan = FragmentScan::new(Some(fragment));
+        let read = book.read_resource_into(&chapter.href, &mut scan);
+        if let Some(offset) = scan.found {
+            return Ok(Some(offset));
+        }
+        if let Some(err) = scan.error {
+            return Err(EpubError::from(err));
+        }
+        read?;
+        scan.finish().map_err(EpubError::from)
+    }
+}
+
+/// A [`ReadingSession`] attached to its book; see [`ReadingSession::attach`].
+///
+/// Offsets are counted like [`ReadingPosition::fallback_offset`]. The
+/// current chapter's length is cached until the session moves to another
+/// chapter.
+pub struct SessionWithBook<'a, R: RandomAccess> {
+    session: &'a mut ReadingSession,
+    book: &'a mut EpubBook<R>,
+    chapter_chars: Option<(usize, usize)>,
+}
+
+impl<R: RandomAccess> SessionWithBook<'_, R> {
+    /// The attached session.
+    pub fn session(&self) -> &ReadingSession {
+        self.session
+    }
+
+    /// Resolve a locator, including its fragment's offset; see
+    /// [`ReadingSession::resolve_locator_in_book`].
+    pub fn resolve_locator(&mut self, loc: Locator) -> Result<ResolvedLocation, EpubError> {
+        self.session.resolve_locator_in_book(self.book, loc)
+    }
+
+    /// Character offset of `fragment` in the current chapter; see
+    /// [`ReadingSession::resolve_fragment_offset`].
+    pub fn resolve_fragment_offset(&mut self, fragment: &str) -> Result<Option<usize>, EpubError> {
+        self.session.resolve_fragment_offset(self.book, fragment)
+    }
+
+    /// Text characters in the current chapter.
+    ///
+    /// # Allocation behavior
+    /// - Streams the chapter through the incremental tokenizer once per
+    ///   chapter; the text is never materialized
+    pub fn chapter_chars(&mut self) -> Result<usize, EpubError> {
+        let index = self.session.current.chapter_index;
+        if let Some((cached, chars)) = self.chapter_chars {
+            if cached == index {
+                return Ok(chars);
+            }
+        }
+        let href = self.session.current_chapter()?.href.clone();
+        let mut scan = FragmentScan::new(None);
+        let read = self.book.read_resource_into(&href, &mut scan);
+        if let Some(err) = scan.error.take() {
+            return Err(EpubError::from(err));
+        }
+        read?;
+        let chars = scan.finish_count().map_err(EpubError::from)?;
+        self.chapter_chars = Some((index, chars));
+        Ok(chars)
+    }
+
+    /// Progress through the current chapter in `[0.0, 1.0]`, from the
+    /// position's offset.
+    pub fn chapter_progress(&mut self) -> Result<f32, EpubError> {
+        let chars = self.chapter_chars()?;
+        if chars == 0 {
+            return Ok(0.0);
+        }
+        Ok((self.session.current.fallback_offset as f32 / chars as f32).min(1.0))
+    }
+
+    /// Whole-book progress in `[0.0, 1.0]`, with the current chapter
+    /// weighted by [`Self::chapter_progress`].
+    pub fn book_progress(&mut self) -> Result<f32, EpubError> {
+        let count = self.session.chapters.len();
+        if count == 0 {
+            return Ok(0.0);
+        }
+        let chapter = self.chapter_progress()?;
+        Ok((self.session.current.chapter_index as f32 + chapter) / count as f32)
+    }
+
+    /// Context around the current position; see [`EpubBook::excerpt_at`].
+    pub fn excerpt(
+        &mut self,
+        before_chars: usize,
+        after_chars: usize,
+    ) -> Result<String, EpubError> {
+        let href = self.session.current_chapter()?.href.clone();
+        let offset = self.session.current.fallback_offset;
+        self.book
+            .excerpt_in(&href, offset, before_chars, after_chars)
+    }
+}
+
+/// `Write` sink that tokenizes chapter bytes until an anchor is found, or
+/// to the end when no fragment is given.
+struct FragmentScan<'a> {
+    fragment: Option<&'a str>,
+    tokenizer: Tokenizer,
+    chars: usize,
+    found: Option<usize>,
+    error: Option<TokenizeError>,
+}
+
+impl<'a> FragmentScan<'a> {
+    fn new(fragment: Option<&'a str>) -> Self {
+        // Tokens are discarded as they stream by, so only the per-token
+        // bounds matter.
+        let limits = TokenizeLimits {
+            max_tokens: usize::MAX,
+            ..TokenizeLimits::default()
+        };
+        Self {
+            fragment,
+            tokenizer: Tokenizer::new(limits),
+            chars: 0,
+            found: None,
+            error: None,
+        }
+    }
+
+    /// Scan tokens, advancing the character count until the anchor.
+    fn visit(
+        fragment: Option<&str>,
+        chars: &mut usize,
+        tokens: impl Iterator<Item = Token>,
+    ) -> Option<usize> {
+        for token in tokens {
+            match token {
+                Token::Anchor(id) if Some(id.as_str()) == fragment => return Some(*chars),
+                Token::Text(text) => *chars += text.chars().count(),
+                _ => {}
+            }
+        }
+        None
+    }
+
+    /// Flush the tokenizer at end of input.
+    fn finish(mut self) -> Result<Option<usize>, TokenizeError> {
+        let tokens = self.tokenizer.finish()?;
+        Ok(Self::visit(self.fragment, &mut self.chars, tokens))
+    }
+
+    /// Flush the tokenizer at end of input and return the total count.
+    fn finish_count(mut self) -> Result<usize, TokenizeError> {
+        let tokens = self.tokenizer.finish()?;
+        Self::visit(self.fragment, &mut self.chars, tokens);
+        Ok(self.chars)
+    }
+}
+
+impl Write for FragmentScan<'_> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let tokens = match self.tokenizer.feed(buf) {
+            Ok(tokens) => tokens,
+            Err(err) => {
+                self.error = Some(err);
+                return Err(std::io::Error::other("chapter tokenization failed"));
+            }
+        };
+        self.found = Self::visit(self.fragment, &mut self.chars, tokens);
+        if self.found.is_some() {
+            // Stop the ZIP stream early; the caller checks `found` first.
+            return Err(std::io::Error::other("fragment found"));
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// `Write` sink that feeds chapter bytes to a
+/// [`ChapterPaginator`](crate::streaming::ChapterPaginator).
+#[cfg(feature = "layout")]
+struct PaginateSink<'a, F> {
+    paginator: &'a mut crate::streaming::ChapterPaginator,
+    on_page: &'a mut F,
+    pages: usize,
+    error: Option<TokenizeError>,
+}
+
+#[cfg(feature = "layout")]
+impl<F: FnMut(crate::layout::Page)> Write for PaginateSink<'_, F> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match self.paginator.feed(buf, &mut *self.on_page) {
+            Ok(pages) => {
+                self.pages += pages;
+                Ok(buf.len())
+            }
+            Err(err) => {
+                self.error = Some(err);
+                Err(std::io::Error::other("chapter pagination failed"))
+            }
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Hard cap on the bytes returned by [`EpubBook::excerpt_at`], ellipses
+/// included.
+pub const MAX_EXCERPT_BYTES: usize = 1024;
+
+const ELLIPSIS: &str = "\u{2026}";
+
+/// `Write` sink that tokenizes chapter bytes into an [`ExcerptWindow`] until
+/// the trailing context is full.
+struct ExcerptScan {
+    tokenizer: Tokenizer,
+    window: ExcerptWindow,
+    error: Option<TokenizeError>,
+}
+
+impl ExcerptScan {
+    fn new(offset: usize, before_chars: usize, after_chars: usize) -> Self {
+        let limits = TokenizeLimits {
+            max_tokens: usize::MAX,
+            ..TokenizeLimits::default()
+        };
+        Self {
+            tokenizer: Tokenizer::new(limits),
+            window: ExcerptWindow {
+                offset,
+                chars: 0,
+                joined: false,
+                before: VecDeque::with_capacity(0),
+                before_chars: before_chars.min(MAX_EXCERPT_BYTES),
+                after: String::with_capacity(0),
+                after_len: 0,
+                after_chars: after_chars.min(MAX_EXCERPT_BYTES),
+            },
+            error: None,
+        }
+    }
+
+    fn is_full(&self) -> bool {
+        self.window.is_full()
+    }
+
+    /// Flush the tokenizer at end of input and assemble the excerpt.
+    fn finish(mut self) -> Result<String, TokenizeError> {
+        if !self.window.is_full() {
+            let tokens = self.tokenizer.finish()?;
+            self.window.visit(tokens);
+        }
+        Ok(self.window.into_excerpt())
+    }
+}
+
+/// Text collected on both sides of an excerpt offset.
+///
+/// Offsets count `Token::Text` characters, as
+/// [`ReadingSession::resolve_fragment_offset`] does; the spaces joining
+/// adjacent text runs are kept in the window but not counted. Each side
+/// holds one character beyond its budget so the excerpt knows whether it was
+/// cut, and where.
+struct ExcerptWindow {
+    offset: usize,
+    chars: usize,
+    joined: bool,
+    before: VecDeque<char>,
+    before_chars: usize,
+    after: String,
+    after_len: usize,
+    after_chars: usize,
+}
+
+impl ExcerptWindow {
+    fn is_full(&self) -> bool {
+        self.after_len > self.after_chars
+    }
+
+    fn push(&mut self, ch: char) {
+        if self.chars < self.offset {
+            self.before.push_back(ch);
+            if self.before.len() > self.before_chars + 1 {
+                self.before.pop_front();
+            }
+        } else if !self.is_full() {
+            self.after.push(ch);
+            self.after_len += 1;
+        }
+    }
+
+    fn visit(&mut self, tokens: impl Iterator<Item = Token>) {
+        for token in tokens {
+            if self.is_full() {
+                return;
+            }
+            if let Token::Text(text) = token {
+                if self.joined {
+                    self.push(' ');
+                }
+                for ch in text.chars() {
+                    self.push(ch);
+                    self.chars += 1;
+                }
+                self.joined = !text.is_empty();
+            }
+        }
+    }
+
+    /// Snap cut sides to word boundaries, apply the byte cap, and add
+    /// ellipses.
+    fn into_excerpt(self) -> String {
+        let mut before: String = self.before.iter().collect();
+        let mut cut_start = false;
+        if self.before.len() > self.before_chars {
+            let boundary = before.remove(0);
+            cut_start = true;
+            if !boundary.is_whitespace() && !before.starts_with(char::is_whitespace) {
+                let word_end = before.find(char::is_whitespace).unwrap_or(before.len());
+                before.drain(..word_end);
+            }
+        }
+        let mut after = self.after;
+        let mut cut_end = false;
+        if self.after_len > self.after_chars {
+            let boundary = after.pop().unwrap_or(' ');
+            cut_end = true;
+            if !boundary.is_whitespace() && !after.ends_with(char::is_whitespace) {
+                let word_start = after.rfind(char::is_whitespace).unwrap_or(0);
+                after.truncate(word_start);
+            }
+        }
+
+        let budget = MAX_EXCERPT_BYTES - 2 * ELLIPSIS.len();
+        if before.len() + after.len() > budget {
+            let before_budget = before
+                .len()
+                .min(budget.saturating_sub(after.len()).max(budget / 2));
+            if before.len() > before_budget {
+                let mut start = before.len() - before_budget;
+                while !before.is_char_boundary(start) {
+                    start += 1;
+                }
+                let word_end = before[start..]
+                    .find(char::is_whitespace)
+                    .map_or(before.len(), |pos| start + pos);
+                before.drain(..word_end);
+                cut_start = true;
+            }
+            let after_budget = budget - before.len();
+            if after.len() > after_budget {
+                let mut end = after_budget;
+                while !after.is_char_boundary(end) {
+                    end -= 1;
+                }
+                let word_start = after[..end].rfind(char::is_whitespace).unwrap_or(0);
+                after.truncate(word_start);
+                cut_end = true;
+            }
+        }
+
+        let body_start = before.trim_start();
+        let mut out = String::with_capacity(MAX_EXCERPT_BYTES.min(before.len() + after.len() + 6));
+        if cut_start && !body_start.is_empty() {
+            out.push_str(ELLIPSIS);
+        }
+        out.push_str(body_start);
+        out.push_str(&after);
+        let trimmed = out.trim_end().len();
+        out.truncate(trimmed);
+        if cut_end && !out.is_empty() {
+            out.push_str(ELLIPSIS);
+        }
+        out
+    }
+}
+
+impl Write for ExcerptScan {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let tokens = match self.tokenizer.feed(buf) {
+            Ok(tokens) => tokens,
+            Err(err) => {
+                self.error = Some(err);
+                return Err(std::io::Error::other("chapter tokenization failed"));
+            }
+        };
+        self.window.visit(tokens);
+        if self.window.is_full() {
+            // Stop the ZIP stream early; the caller checks `is_full` first.
+            return Err(std::io::Error::other("excerpt complete"));
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Matching options for [`EpubBook::find_in_chapter`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FindOptions {
+    /// Compare characters ignoring case.
+    pub case_insensitive: bool,
+    /// Only report matches not flanked by a letter, digit, or `_`.
+    pub whole_word: bool,
+    /// Stop streaming after this many hits.
+    pub max_hits: usize,
+}
+
+impl Default for FindOptions {
+    fn default() -> Self {
+        Self {
+            case_insensitive: true,
+            whole_word: false,
+            max_hits: 100,
+        }
+    }
+}
+
+/// Match reported by [`EpubBook::find_in_chapter`].
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct TextHit {
+    /// Character offset of the match in the chapter text, counted like
+    /// [`ReadingPosition::fallback_offset`].
+    pub offset: usize,
+    /// Matched length in characters, on the same scale as `offset`.
+    pub len: usize,
+    /// Position of the match, usable with [`Locator::Position`] and
+    /// [`EpubBook::excerpt_at`].
+    pub position: ReadingPosition,
+}
+
+/// One character of the searched text stream.
+///
+/// `offset` counts `Token::Text` characters before it; the spaces joining
+/// adjacent text runs take part in matching but are not `counted`.
+#[derive(Clone, Copy)]
+struct FindChar {
+    ch: char,
+    offset: usize,
+    counted: bool,
+}
+
+/// Per-call limits for a chapter search that may stop part-way.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct FindLimits {
+    /// Stop once this instant passes.
+    pub(crate) deadline: Option<Instant>,
+    /// Stop once this many chapter bytes were scanned.
+    pub(crate) max_bytes: usize,
+}
+
+impl FindLimits {
+    pub(crate) const UNBOUNDED: Self = Self {
+        deadline: None,
+        max_bytes: usize::MAX,
+    };
+}
+
+/// Result of [`EpubBook::find_in_chapter_from`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct FindOutcome {
+    /// Hits reported.
+    pub(crate) hits: usize,
+    /// Chapter bytes scanned past the replayed prefix.
+    pub(crate) bytes: usize,
+    /// Character offset to resume from when the scan stopped early;
+    /// `None` when it reached the end of the chapter.
+    pub(crate) resume_offset: Option<usize>,
+}
+
+/// `Write` sink that tokenizes chapter bytes into a [`FindWindow`],
+/// reporting hits as they complete.
+struct FindScan<F> {
+    tokenizer: Tokenizer,
+    window: FindWindow<F>,
+    limits: FindLimits,
+    bytes: usize,
+}
+
+impl<F> FindScan<F>
+where
+    F: FnMut(TextHit) -> Result<bool, EpubError>,
+{
+    fn new(
+        needle: &str,
+        options: FindOptions,
+        position: ReadingPosition,
+        limits: FindLimits,
+        on_hit: F,
+    ) -> Self {
+        let limits_tokenize = TokenizeLimits {
+            max_tokens: usize::MAX,
+            ..TokenizeLimits::default()
+        };
+        let needle: Vec<char> = needle.chars().collect();
+        let mut chars = VecDeque::with_capacity(needle.len() + 2);
+        chars.push_back(FindChar {
+            ch: ' ',
+            offset: 0,
+            counted: false,
+        });
+        Self {
+            tokenizer: Tokenizer::new(limits_tokenize),
+            window: FindWindow {
+                needle,
+                options,
+                from_offset: position.fallback_offset,
+                position,
+                chars,
+                offset: 0,
+                joined: false,
+                skip: 0,
+                hits: 0,
+                resume_offset: None,
+                on_hit,
+                error: None,
+            },
+            limits,
+            bytes: 0,
+        }
+    }
+
+    fn is_done(&self) -> bool {
+        self.window.is_done()
+    }
+
+    /// Stop at the next unchecked candidate if the limits are spent.
+    ///
+    /// Text before the resume offset is a replay of an earlier call and is
+    /// not charged, and the scan only stops past that offset, so every call
+    /// makes progress.
+    fn charge(&mut self, bytes: usize) {
+        if self.window.offset < self.window.from_offset {
+            return;
+        }
+        self.bytes += bytes;
+        let expired = self
+            .limits
+            .deadline
+            .is_some_and(|deadline| Instant::now() >= deadline);
+        let next = self
+            .window
+            .chars
+            .get(1)
+            .map_or(self.window.offset, |c| c.offset);
+        if (self.bytes >= self.limits.max_bytes || expired) && next > self.window.from_offset {
+            self.window.resume_offset = Some(next);
+        }
+    }
+
+    /// Flush the tokenizer at end of input and test the trailing candidate
+    /// against a virtual space for the end of the chapter.
+    fn finish(mut self) -> Result<FindOutcome, EpubError> {
+        if !self.window.is_done() {
+            let tokens = self.tokenizer.finish().map_err(EpubError::from)?;
+            self.window.visit(tokens);
+            self.window.push(' ', false);
+        }
+        match self.window.error {
+            Some(err) => Err(err),
+            None => Ok(FindOutcome {
+                hits: self.window.hits,
+                bytes: self.bytes,
+                resume_offset: self.window.resume_offset,
+            }),
+        }
+    }
+}
+
+/// Rolling match state for [`EpubBook::find_in_chapter`].
+///
+/// Holds the needle's length in characters plus one character of context on
+/// each side; the leading slot starts as a virtual space for the start of
+/// the chapter. Offsets count `Token::Text` characters, as
+/// [`ReadingSession::resolve_fragment_offset`] does.
+struct FindWindow<F> {
+    needle: Vec<char>,
+    options: FindOptions,
+    /// Matches starting before this offset were reported by an earlier call.
+    from_offset: usize,
+    position: ReadingPosition,
+    chars: VecDeque<FindChar>,
+    offset: usize,
+    joined: bool,
+    /// Candidates to skip so hits do not overlap.
+    skip: usize,
+    hits: usize,
+    /// Set when the scan stops before the end of the chapter.
+    resume_offset: Option<usize>,
+    on_hit: F,
+    error: Option<EpubError>,
+}
+
+impl<F> FindWindow<F>
+where
+    F: FnMut(TextHit) -> Result<bool, EpubError>,
+{
+    fn is_done(&self) -> bool {
+        self.resume_offset.is_some() || self.error.is_some()
+    }
+
+    fn visit(&mut self, tokens: impl Iterator<Item = Token>) {
+        for token in tokens {
+            if self.is_done() {
+                return;
+            }
+            if let Token::Text(text) = token {
+                if self.joined {
+                    self.push(' ', false);
+                }
+                for ch in text.chars() {
+                    self.push(ch, true);
+                }
+                self.joined = !text.is_empty();
             }
-            Locator::Position(pos) => {
-                self.seek_position(&pos)?;
-                self.resolve_locator(Locator::Chapter(pos.chapter_index))
+        }
+    }
+
+    fn push(&mut self, ch: char, counted: bool) {
+        self.chars.push_back(FindChar {
+            ch,
+            offset: self.offset,
+            counted,
+        });
+        if counted {
+            self.offset += 1;
+        }
+        if self.chars.len() == self.needle.len() + 2 {
+            self.check_candidate();
+            self.chars.pop_front();
+        }
+    }
+
+    /// Test whether the needle sits between the window's first and last
+    /// characters.
+    fn check_candidate(&mut self) {
+        if self.is_done() {
+            return;
+        }
+        if self.skip > 0 {
+            self.skip -= 1;
+            return;
+        }
+        let options = self.options;
+        let candidate = self.chars.range(1..=self.needle.len());
+        if !candidate
+            .zip(&self.needle)
+            .all(|(found, &wanted)| chars_match(found.ch, wanted, options.case_insensitive))
+        {
+            return;
+        }
+        if options.whole_word {
+            let before = self.chars.front().map_or(' ', |c| c.ch);
+            let after = self.chars.back().map_or(' ', |c| c.ch);
+            if is_word_char(before) || is_word_char(after) {
+                return;
+            }
+        }
+        let (Some(first), Some(last)) = (self.chars.get(1), self.chars.get(self.needle.len()))
+        else {
+            return;
+        };
+        let offset = first.offset;
+        if offset < self.from_offset {
+            return;
+        }
+        let end = last.offset + usize::from(last.counted);
+        let hit = TextHit {
+            offset,
+            len: end.saturating_sub(offset),
+            position: ReadingPosition {
+                fallback_offset: offset,
+                ..self.position.clone()
+            },
+        };
+        self.skip = self.needle.len() - 1;
+        self.hits += 1;
+        match (self.on_hit)(hit) {
+            Ok(true) if self.hits < self.options.max_hits => {}
+            Ok(_) => self.resume_offset = Some(end.max(offset + 1)),
+            Err(err) => self.error = Some(err),
+        }
+    }
+}
+
+impl<F> Write for FindScan<F>
+where
+    F: FnMut(TextHit) -> Result<bool, EpubError>,
+{
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let tokens = match self.tokenizer.feed(buf) {
+            Ok(tokens) => tokens,
+            Err(err) => {
+                self.window.error = Some(EpubError::from(err));
+                return Err(std::io::Error::other("chapter tokenization failed"));
             }
+        };
+        self.window.visit(tokens);
+        if !self.window.is_done() {
+            self.charge(buf.len());
+        }
+        if self.window.is_done() {
+            // Stop the ZIP stream early; the caller checks `is_done` first.
+            return Err(std::io::Error::other("search complete"));
         }
+        Ok(buf.len())
     }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+fn chars_match(found: char, wanted: char, case_insensitive: bool) -> bool {
+    if found == wanted || (found.is_whitespace() && wanted.is_whitespace()) {
+        return true;
+    }
+    case_insensitive && found.to_lowercase().eq(wanted.to_lowercase())
+}
+
+fn is_word_char(ch: char) -> bool {
+    ch.is_alphanumeric() || ch == '_'
 }
 
 /// Resumable pagination session that tracks parse/layout state across page turns.
@@ -606,6 +1878,7 @@ impl PaginationSession {
         ReadingPosition {
             chapter_index: self.chapter_index,
             chapter_href: None,
+            segment: None,
             anchor: None,
             fallback_offset: self.byte_offset,
         }
@@ -618,6 +1891,10 @@ impl Default for PaginationSession {
     }
 }
 
+fn segment_index(chapter: &ChapterRef) -> Option<usize> {
+    chapter.segment.as_ref().map(|segment| segment.index)
+}
+
 fn split_href_fragment(href: &str) -> (String, Option<String>) {
     if let Some((base, fragment)) = href.split_once('#') {
         return (base.to_string(), Some(fragment.to_string()));
@@ -625,6 +1902,65 @@ fn split_href_fragment(href: &str) -> (String, Option<String>) {
     (href.to_string(), None)
 }
 
+/// Hashed idref and href lookups over spine positions.
+///
+/// Only 64-bit key hashes are stored, so memory stays at two map entries
+/// per spine item. A hit is a candidate the caller must confirm against the
+/// spine; if it does not match, two keys collided and the caller falls back
+/// to a scan. A miss is definitive.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+struct SpineIndex {
+    idrefs: HashMap<u64, usize>,
+    hrefs: HashMap<u64, usize>,
+}
+
+/// Result of a [`SpineIndex`] lookup.
+enum SpineLookup {
+    Hit(usize),
+    Miss,
+}
+
+impl SpineIndex {
+    /// Index `(position, idref, href)` entries; earlier positions win.
+    fn build<'a>(entries: impl Iterator<Item = (usize, &'a str, &'a str)>) -> Self {
+        let mut index = Self::default();
+        for (position, idref, href) in entries {
+            index.idrefs.entry(lookup_hash(idref)).or_insert(position);
+            index
+                .hrefs
+                .entry(lookup_hash(&spine_href_key(href)))
+                .or_insert(position);
+        }
+        index
+    }
+
+    fn idref(&self, idref: &str) -> SpineLookup {
+        Self::lookup(&self.idrefs, idref)
+    }
+
+    /// Look up an href already normalized with [`spine_href_key`].
+    fn href(&self, key: &str) -> SpineLookup {
+        Self::lookup(&self.hrefs, key)
+    }
+
+    fn lookup(map: &HashMap<u64, usize>, key: &str) -> SpineLookup {
+        map.get(&lookup_hash(key))
+            .map_or(SpineLookup::Miss, |&position| SpineLookup::Hit(position))
+    }
+}
+
+fn lookup_hash(key: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    key.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Comparable form of an OPF-relative href: no fragment, percent-decoded,
+/// dot segments resolved.
+fn spine_href_key(href: &str) -> String {
+    resolve_opf_relative_path("", href)
+}
+
 fn find_toc_href(nav: &Navigation, id: &str) -> Option<String> {
     fn visit(points: &[NavPoint], id: &str) -> Option<String> {
         for point in points {
@@ -653,7 +1989,7 @@ impl EpubBook<File> {
         options: EpubBookOptions,
     ) -> Result<Self, EpubError> {
         let file = File::open(path).map_err(|e| EpubError::Io(e.to_string()))?;
-        Self::from_reader_with_options(file, options)
+        Self::from_storage_with_options(file, options)
     }
 
     /// Open an EPUB from disk with compatibility open configuration.
@@ -662,13 +1998,16 @@ impl EpubBook<File> {
         config: OpenConfig,
     ) -> Result<Self, EpubError> {
         let file = File::open(path).map_err(|e| EpubError::Io(e.to_string()))?;
-        Self::from_reader_with_config(file, config)
+        Self::from_storage_with_config(file, config)
     }
 }
 
-impl<R: Read + Seek> EpubBook<R> {
+impl<R: Read + Seek> EpubBook<ReadSeekAdapter<R>> {
     /// Open an EPUB from any `Read + Seek` source and parse core structures.
     ///
+    /// The reader is wrapped in a [`ReadSeekAdapter`]; use
+    /// [`EpubBook::from_storage`] for sources that implement [`RandomAccess`].
+    ///
     /// # Allocation behavior
     /// - Bounded by `ZipLimits` in options
     /// - Allocates central directory cache (~4KB fixed)
@@ -691,23 +2030,55 @@ impl<R: Read + Seek> EpubBook<R> {
     }
 
     /// Open an EPUB from any `Read + Seek` source with compatibility open configuration.
+    pub fn from_reader_with_config(reader: R, config: OpenConfig) -> Result<Self, EpubError> {
+        Self::from_storage_with_config(ReadSeekAdapter::new(reader), config)
+    }
+}
+
+impl<R: RandomAccess> EpubBook<R> {
+    /// Open an EPUB from any [`RandomAccess`] storage and parse core structures.
+    pub fn from_storage(storage: R) -> Result<Self, EpubError> {
+        Self::from_storage_with_options(storage, EpubBookOptions::default())
+    }
+
+    /// Open an EPUB from any [`RandomAccess`] storage with explicit options.
+    pub fn from_storage_with_options(
+        storage: R,
+        options: EpubBookOptions,
+    ) -> Result<Self, EpubError> {
+        Self::from_storage_with_config(storage, OpenConfig::from(options))
+    }
+
+    /// Open an EPUB from any [`RandomAccess`] storage with compatibility open configuration.
     ///
     /// # Allocation behavior
     /// - Bounded by `ZipLimits` in config.options
     /// - Supports lazy navigation loading to defer allocation
     /// - Caller buffer required: No
-    pub fn from_reader_with_config(reader: R, config: OpenConfig) -> Result<Self, EpubError> {
+    pub fn from_storage_with_config(storage: R, config: OpenConfig) -> Result<Self, EpubError> {
         let options = config.options;
+        let timer = PhaseTimer::start(config.profile);
         let mut zip =
-            StreamingZip::new_with_limits(reader, options.zip_limits).map_err(EpubError::Zip)?;
+            StreamingZip::new_with_limits(storage, options.zip_limits).map_err(EpubError::Zip)?;
+        zip.set_tolerant_names(options.tolerant_entry_names);
+        let central_directory = timer.stop(|| zip.central_directory_size());
+
+        let timer = PhaseTimer::start(config.profile);
         zip.validate_mimetype().map_err(EpubError::Zip)?;
+        let mimetype = timer.stop(|| stored_size(&zip, "mimetype"));
 
+        let timer = PhaseTimer::start(config.profile);
         let container = read_entry(&mut zip, "META-INF/container.xml")?;
         let opf_path = crate::metadata::parse_container_xml(&container)?;
         let opf = read_entry(&mut zip, &opf_path)?;
         let metadata = extract_metadata(&container, &opf)?;
-        let spine = crate::spine::parse_spine(&opf)?;
-        validate_open_invariants(&metadata, &spine, options.validation_mode)?;
+        let mut spine = crate::spine::parse_spine(&opf)?;
+        spine.resolve_duplicate_idrefs();
+        validate_open_invariants(&metadata, &spine, &opf, options.validation_mode)?;
+        let package = timer
+            .stop(|| stored_size(&zip, "META-INF/container.xml") + stored_size(&zip, &opf_path));
+
+        let timer = PhaseTimer::start(config.profile);
         let (navigation, navigation_loaded) = if config.lazy_navigation {
             (None, false)
         } else {
@@ -723,6 +2094,13 @@ impl<R: Read + Seek> EpubBook<R> {
                 true,
             )
         };
+        let open_profile = config.profile.then(|| OpenProfile {
+            central_directory,
+            mimetype,
+            package,
+            navigation: navigation_loaded
+                .then(|| timer.stop(|| navigation_size(&zip, &metadata, &spine, &opf_path))),
+        });
 
         Ok(Self {
             zip,
@@ -734,9 +2112,27 @@ impl<R: Read + Seek> EpubBook<R> {
             navigation_loaded,
             navigation,
             embedded_fonts_cache: None,
+            open_profile,
+            spine_index: OnceLock::new(),
         })
     }
 
+    /// Per-phase open timings, when opened with [`OpenConfig::profile`].
+    pub fn open_profile(&self) -> Option<&OpenProfile> {
+        self.open_profile.as_ref()
+    }
+
+    /// Split into the ZIP reader and parsed package state.
+    pub(crate) fn into_parts(self) -> BookParts<R> {
+        BookParts {
+            zip: self.zip,
+            opf_path: self.opf_path,
+            metadata: self.metadata,
+            spine: self.spine,
+            navigation: self.navigation,
+        }
+    }
+
     /// EPUB package metadata.
     pub fn metadata(&self) -> &EpubMetadata {
         &self.metadata
@@ -770,6 +2166,7 @@ impl<R: Read + Seek> EpubBook<R> {
     /// Lazily parse and cache navigation data when not loaded yet.
     pub fn ensure_navigation(&mut self) -> Result<Option<&Navigation>, EpubError> {
         if !self.navigation_loaded {
+            let timer = PhaseTimer::start(self.open_profile.is_some());
             self.navigation = parse_navigation(
                 &mut self.zip,
                 &self.metadata,
@@ -779,6 +2176,11 @@ impl<R: Read + Seek> EpubBook<R> {
                 self.max_nav_bytes,
             )?;
             self.navigation_loaded = true;
+            if let Some(profile) = self.open_profile.as_mut() {
+                profile.navigation = Some(timer.stop(|| {
+                    navigation_size(&self.zip, &self.metadata, &self.spine, &self.opf_path)
+                }));
+            }
         }
         Ok(self.navigation.as_ref())
     }
@@ -794,65 +2196,224 @@ impl<R: Read + Seek> EpubBook<R> {
     }
 
     /// Create a detached reading session for locator/progress operations.
+    ///
+    /// The session indexes chapter hrefs, so [`Locator::Href`] lookups do
+    /// not scan the spine.
     pub fn reading_session(&self) -> ReadingSession {
-        ReadingSession::new(self.chapters().collect(), self.navigation.clone())
+        ReadingSession::new(self.chapters().collect(), self.navigation.clone()).with_href_index()
     }
 
     /// Enumerate chapters in spine order.
     pub fn chapters(&self) -> impl Iterator<Item = ChapterRef> + '_ {
-        self.spine
-            .items()
-            .iter()
-            .enumerate()
-            .filter_map(|(index, spine_item)| {
-                self.metadata
-                    .get_item(&spine_item.idref)
-                    .map(|manifest_item| ChapterRef {
-                        index,
-                        idref: spine_item.idref.clone(),
-                        href: manifest_item.href.clone(),
-                        media_type: manifest_item.media_type.clone(),
-                    })
-            })
+        chapter_refs(&self.metadata, &self.spine, self.navigation.as_ref())
     }
 
     /// Get a chapter descriptor by spine index.
     pub fn chapter(&self, index: usize) -> Result<ChapterRef, EpubError> {
-        let spine_item = self
-            .spine
-            .get_item(index)
-            .ok_or(EpubError::ChapterOutOfBounds {
-                index,
-                chapter_count: self.spine.len(),
-            })?;
+        chapter_ref(&self.metadata, &self.spine, self.navigation.as_ref(), index)
+    }
 
-        let manifest_item = self.metadata.get_item(&spine_item.idref).ok_or_else(|| {
-            EpubError::ManifestItemMissing {
-                idref: spine_item.idref.clone(),
+    /// Get the descriptor of the content displayed for a spine index.
+    ///
+    /// Spine items the reader cannot display (e.g. SVG or foreign media
+    /// types) are replaced by the first XHTML or image item along their
+    /// manifest `fallback` chain; `index`, `idref` and `title` still describe
+    /// the spine entry. Items without a usable fallback resolve to
+    /// themselves. All chapter content APIs read through this resolution.
+    pub fn resolve_displayable_chapter(&self, index: usize) -> Result<ChapterRef, EpubError> {
+        displayable_chapter_ref(&self.metadata, &self.spine, self.navigation.as_ref(), index)
+    }
+
+    /// Gather book-level statistics, including a detected text language.
+    ///
+    /// Detection samples chapter text in spine order until
+    /// [`LANGUAGE_SAMPLE_BYTES`] are collected, so covers and short title
+    /// pages do not decide the guess on their own.
+    pub fn stats(&mut self) -> Result<BookStats, EpubError> {
+        let mut sample = String::with_capacity(0);
+        let mut text = String::with_capacity(0);
+        for index in 0..self.chapter_count() {
+            if sample.len() >= LANGUAGE_SAMPLE_BYTES {
+                break;
             }
-        })?;
+            let Ok(chapter) = self.resolve_displayable_chapter(index) else {
+                continue;
+            };
+            if !is_markup_media_type(&chapter.media_type) {
+                continue;
+            }
+            let remaining = LANGUAGE_SAMPLE_BYTES - sample.len();
+            self.chapter_text_into_with_limit(index, remaining, &mut text)?;
+            sample.push_str(&text);
+            sample.push(' ');
+        }
+        Ok(BookStats {
+            chapter_count: self.chapter_count(),
+            declared_language: Language::from_tag(&self.metadata.language),
+            detected_language: detect_language(&sample),
+        })
+    }
 
-        Ok(ChapterRef {
+    /// Estimate how expensive chapter `index` is to prepare.
+    ///
+    /// Reads only the manifest and ZIP central directory, so hosts can call
+    /// it for upcoming chapters to schedule preparation or decide whether to
+    /// show a spinner. Entries missing from the archive count as zero bytes.
+    pub fn chapter_complexity(&self, index: usize) -> Result<ChapterComplexity, EpubError> {
+        let chapter = self.resolve_displayable_chapter(index)?;
+        let entry = |href: &str| {
+            self.zip
+                .get_entry(&resolve_opf_relative_path(&self.opf_path, href))
+        };
+        let mut complexity = ChapterComplexity {
             index,
-            idref: spine_item.idref.clone(),
-            href: manifest_item.href.clone(),
-            media_type: manifest_item.media_type.clone(),
-        })
+            ..ChapterComplexity::default()
+        };
+        if let Some(entry) = entry(&chapter.href) {
+            complexity.chapter_bytes = entry.uncompressed_size;
+            complexity.compressed_bytes = entry.compressed_size;
+        }
+        if let Some(item) = self
+            .metadata
+            .manifest
+            .iter()
+            .find(|item| item.href == chapter.href)
+        {
+            complexity.has_mathml = item.has_property("mathml");
+            complexity.has_svg = item.has_property("svg");
+        }
+        for item in &self.metadata.manifest {
+            let size = || entry(&item.href).map_or(0, |entry| entry.uncompressed_size);
+            if ResourceFilter::Stylesheets.matches(item) {
+                complexity.stylesheet_count += 1;
+                complexity.stylesheet_bytes += size();
+            } else if ResourceFilter::Fonts.matches(item) {
+                complexity.font_count += 1;
+                complexity.font_bytes += size();
+            } else if ResourceFilter::Images.matches(item) {
+                complexity.image_count += 1;
+            }
+        }
+        Ok(complexity)
+    }
+
+    /// Export the whole book as plain text or Markdown into `writer`.
+    ///
+    /// Every displayable XHTML spine item is streamed through the style
+    /// pipeline in reading order; only the block being converted is held in
+    /// memory. With [`ExportOptions::page_markers`], the book's print
+    /// page-list is marked with form feeds (plain text) or HTML comments
+    /// (Markdown).
+    pub fn export<W: Write>(
+        &mut self,
+        format: ExportFormat,
+        writer: W,
+        options: ExportOptions,
+    ) -> Result<(), EpubError> {
+        if options.page_markers {
+            self.ensure_navigation()?;
+        }
+        let mut prep = RenderPrep::new(options.render)
+            .with_serif_default()
+            .with_link_events(format == ExportFormat::Markdown);
+        let mut export = ExportWriter::new(format, writer);
+        for index in 0..self.chapter_count() {
+            let Ok(chapter) = self.resolve_displayable_chapter(index) else {
+                continue;
+            };
+            if !is_markup_media_type(&chapter.media_type) {
+                continue;
+            }
+            let page_anchors = match self.navigation() {
+                Some(nav) if options.page_markers => nav.page_list_for_chapter(&chapter.href),
+                _ => Vec::with_capacity(0),
+            };
+            export.begin_chapter(page_anchors);
+            prep.prepare_chapter_with(self, index, |item| export.push(item))
+                .map_err(EpubError::from)?;
+            export
+                .end_chapter()
+                .map_err(|e| EpubError::Io(e.to_string()))?;
+        }
+        export.finish().map_err(|e| EpubError::Io(e.to_string()))
+    }
+
+    /// Summarize scripted content across the spine.
+    ///
+    /// Checks each chapter's `scripted` manifest property and scans the
+    /// displayed XHTML for `<script>` elements, so this reads every chapter;
+    /// call it once per book rather than per page.
+    pub fn scripted_content_summary(&mut self) -> Result<ScriptedContentSummary, EpubError> {
+        let mut summary = ScriptedContentSummary::default();
+        for index in 0..self.chapter_count() {
+            let Ok(chapter) = self.resolve_displayable_chapter(index) else {
+                continue;
+            };
+            if chapter.is_scripted {
+                summary.declared.push(index);
+            }
+            if is_markup_media_type(&chapter.media_type)
+                && contains_script_element(&self.read_resource(&chapter.href)?)
+            {
+                summary.script_elements.push(index);
+            }
+        }
+        Ok(summary)
     }
 
     /// Get a chapter descriptor by spine `idref`.
     pub fn chapter_by_id(&self, idref: &str) -> Result<ChapterRef, EpubError> {
-        let index = self
-            .spine
-            .items()
-            .iter()
-            .position(|item| item.idref == idref)
-            .ok_or_else(|| EpubError::ManifestItemMissing {
-                idref: idref.to_string(),
-            })?;
+        let items = self.spine.items();
+        let is_match = |index: usize| items.get(index).is_some_and(|item| item.idref == idref);
+        let index = match self.spine_index().idref(idref) {
+            SpineLookup::Hit(index) if is_match(index) => Some(index),
+            SpineLookup::Miss => None,
+            _ => (0..items.len()).find(|&index| is_match(index)),
+        }
+        .ok_or_else(|| EpubError::ManifestItemMissing {
+            idref: idref.to_string(),
+        })?;
         self.chapter(index)
     }
 
+    /// Spine index of the chapter at an OPF-relative `href`.
+    ///
+    /// The href is normalized first: any `#fragment` is dropped,
+    /// percent-escapes are decoded and `.`/`..` segments resolved, so links
+    /// already resolved against the OPF directory can be passed as-is. The
+    /// first spine entry wins when several share an href.
+    ///
+    /// # Allocation behavior
+    /// - The first lookup (by href or idref) builds a hashed index of the
+    ///   spine: two small map entries per spine item, no strings copied
+    /// - Later lookups are O(1) apart from normalizing `href`
+    pub fn chapter_index_for_href(&self, href: &str) -> Option<usize> {
+        let key = spine_href_key(href);
+        let is_match = |index: usize| {
+            self.spine
+                .get_item(index)
+                .and_then(|item| self.metadata.get_item(&item.idref))
+                .is_some_and(|manifest_item| spine_href_key(&manifest_item.href) == key)
+        };
+        match self.spine_index().href(&key) {
+            SpineLookup::Hit(index) if is_match(index) => Some(index),
+            SpineLookup::Miss => None,
+            _ => (0..self.spine.len()).find(|&index| is_match(index)),
+        }
+    }
+
+    fn spine_index(&self) -> &SpineIndex {
+        self.spine_index.get_or_init(|| {
+            SpineIndex::build(self.spine.items().iter().enumerate().map(|(index, item)| {
+                let href = self
+                    .metadata
+                    .get_item(&item.idref)
+                    .map_or("", |manifest_item| manifest_item.href.as_str());
+                (index, item.idref.as_str(), href)
+            }))
+        })
+    }
+
     /// Read a resource by OPF-relative href into a new `Vec<u8>`.
     ///
     /// Fragment suffixes (e.g. `chapter.xhtml#p3`) are ignored.
@@ -911,13 +2472,145 @@ impl<R: Read + Seek> EpubBook<R> {
         read_entry_into_with_limit(&mut self.zip, &zip_path, writer, hard_cap_bytes)
     }
 
+    /// Stream `len` bytes of a resource's decompressed content, starting at
+    /// byte `start`, into a writer. Returns the bytes written, fewer than
+    /// `len` only when the resource ends first.
+    ///
+    /// Fragment suffixes (e.g. `chapter.xhtml#p3`) are ignored.
+    ///
+    /// # Allocation behavior
+    /// - Stored entries are read in place at the range
+    /// - DEFLATE entries inflate from the nearest checkpoint cached by earlier
+    ///   ranged reads, so walking a multi-megabyte chapter window by window
+    ///   stays roughly linear; see
+    ///   [`StreamingZip::read_file_range_to_writer`]
+    /// - Each checkpoint holds a ~43 KB inflate state; the book keeps up to
+    ///   [`ZipLimits::max_sync_points`] of them (8 by default, ~350 KB) until
+    ///   it is dropped
+    pub fn read_resource_range<W: Write>(
+        &mut self,
+        href: &str,
+        start: u64,
+        len: usize,
+        writer: &mut W,
+    ) -> Result<usize, EpubError> {
+        let zip_path = resolve_opf_relative_path(&self.opf_path, href);
+        let entry = self
+            .zip
+            .get_entry(&zip_path)
+            .cloned()
+            .ok_or(EpubError::Zip(ZipError::FileNotFound))?;
+        self.zip
+            .read_file_range_to_writer(&entry, start, len, writer)
+            .map_err(EpubError::Zip)
+    }
+
     /// Read spine item content bytes by index.
     pub fn read_spine_item_bytes(&mut self, index: usize) -> Result<Vec<u8>, EpubError> {
-        let href = self.chapter(index)?.href;
+        let href = self.resolve_displayable_chapter(index)?.href;
 
         self.read_resource(&href)
     }
 
+    /// Chapters in spine order, with oversized XHTML items split into
+    /// virtual chapters at heading boundaries or size thresholds.
+    ///
+    /// Segments keep the `index`, `idref` and `href` of their spine item and
+    /// carry a [`ChapterSegment`]; later segments take their title from the
+    /// heading that starts them. Items are planned while they stream out of
+    /// the archive, and items of at most `min_chapter_bytes` are not read.
+    /// Read a segment with [`Self::chapter_segment_html`], or lay it out with
+    /// [`RenderPrep::prepare_chapter_ref_with`](crate::RenderPrep::prepare_chapter_ref_with).
+    pub fn split_chapters(
+        &mut self,
+        options: ChapterSplitOptions,
+    ) -> Result<Vec<ChapterRef>, EpubError> {
+        let mut chapters = Vec::with_capacity(self.chapter_count());
+        for index in 0..self.chapter_count() {
+            // Spine items without a manifest entry are skipped, as in `chapters`.
+            let Ok(chapter) = self.chapter(index) else {
+                continue;
+            };
+            let shown = self.resolve_displayable_chapter(chapter.index)?;
+            let zip_path = resolve_opf_relative_path(&self.opf_path, &shown.href);
+            let size = self
+                .zip
+                .get_entry(&zip_path)
+                .map_or(0, |entry| entry.uncompressed_size);
+            if !is_markup_media_type(&shown.media_type)
+                || usize::try_from(size).unwrap_or(usize::MAX) <= options.min_chapter_bytes
+            {
+                chapters.push(chapter);
+                continue;
+            }
+            let mut planner = SegmentPlanner::new(options);
+            if let Err(err) = self.read_resource_into(&shown.href, &mut planner) {
+                return Err(planner.take_error().unwrap_or(err));
+            }
+            let plan = planner.finish()?;
+            if plan.is_empty() {
+                chapters.push(chapter);
+                continue;
+            }
+            for (segment, heading) in plan {
+                chapters.push(ChapterRef {
+                    title: heading.or_else(|| chapter.title.clone()),
+                    segment: Some(segment),
+                    ..chapter.clone()
+                });
+            }
+        }
+        Ok(chapters)
+    }
+
+    /// Read the content document of a chapter from [`Self::split_chapters`].
+    ///
+    /// Whole spine items read like [`Self::read_spine_item_bytes`]. For a
+    /// segment, only its own bytes, the document head and the reopened
+    /// ancestor tags are kept while the item streams past, so memory
+    /// follows the segment rather than the item.
+    pub fn chapter_segment_html(&mut self, chapter: &ChapterRef) -> Result<Vec<u8>, EpubError> {
+        let Some(segment) = &chapter.segment else {
+            return self.read_spine_item_bytes(chapter.index);
+        };
+        let href = self.resolve_displayable_chapter(chapter.index)?.href;
+        let mut capture = RangeCapture::new(segment.source_ranges());
+        self.read_resource_into(&href, &mut capture)?;
+        segment.extract_captured(&capture)
+    }
+
+    /// Lay out a chapter from [`Self::split_chapters`] with `paginator`,
+    /// handing each page to `on_page`.
+    ///
+    /// Whole spine items stream from the archive straight into the
+    /// paginator; a segment is paginated as its own document, read with
+    /// [`Self::chapter_segment_html`]. Returns the number of pages emitted.
+    #[cfg(feature = "layout")]
+    pub fn paginate_chapter<F: FnMut(crate::layout::Page)>(
+        &mut self,
+        chapter: &ChapterRef,
+        paginator: &mut crate::streaming::ChapterPaginator,
+        mut on_page: F,
+    ) -> Result<usize, EpubError> {
+        let pages = if chapter.segment.is_some() {
+            let html = self.chapter_segment_html(chapter)?;
+            paginator.feed(&html, &mut on_page)?
+        } else {
+            let href = self.resolve_displayable_chapter(chapter.index)?.href;
+            let mut sink = PaginateSink {
+                paginator: &mut *paginator,
+                on_page: &mut on_page,
+                pages: 0,
+                error: None,
+            };
+            if let Err(err) = self.read_resource_into(&href, &mut sink) {
+                return Err(sink.error.take().map_or(err, EpubError::from));
+            }
+            sink.pages
+        };
+        Ok(pages + paginator.finish(&mut on_page)?)
+    }
+
     /// Read a spine chapter as UTF-8 HTML/XHTML text by index.
     ///
     /// # Allocation behavior
@@ -951,7 +2644,7 @@ impl<R: Read + Seek> EpubBook<R> {
         out: &mut String,
     ) -> Result<(), EpubError> {
         out.clear();
-        let chapter = self.chapter(index)?;
+        let chapter = self.resolve_displayable_chapter(index)?;
         let mut bytes = Vec::with_capacity(0);
         self.read_resource_into_with_hard_cap(&chapter.href, &mut bytes, max_bytes)?;
         let mut html = String::from_utf8(bytes)
@@ -971,7 +2664,7 @@ impl<R: Read + Seek> EpubBook<R> {
         index: usize,
         limits: StyleLimits,
     ) -> Result<ChapterStylesheets, EpubError> {
-        let chapter = self.chapter(index)?;
+        let chapter = self.resolve_displayable_chapter(index)?;
         let html = self.chapter_html(index)?;
         let links = parse_stylesheet_links(&chapter.href, &html);
         let mut sources = Vec::with_capacity(0);
@@ -1018,7 +2711,7 @@ impl<R: Read + Seek> EpubBook<R> {
         limits: StyleLimits,
         scratch_buf: &mut Vec<u8>,
     ) -> Result<ChapterStylesheets, EpubError> {
-        let chapter = self.chapter(index)?;
+        let chapter = self.resolve_displayable_chapter(index)?;
         let html = self.chapter_html(index)?;
         let links = parse_stylesheet_links(&chapter.href, &html);
         let mut sources = Vec::with_capacity(links.len());
@@ -1046,6 +2739,164 @@ impl<R: Read + Seek> EpubBook<R> {
         Ok(ChapterStylesheets { sources })
     }
 
+    /// Obfuscated fonts listed in `META-INF/encryption.xml`.
+    ///
+    /// The map is keyed by the package unique identifier and is empty when
+    /// the book has no `encryption.xml` or obfuscates nothing. Other
+    /// encryption methods (DRM) are ignored.
+    pub fn font_obfuscation(&mut self) -> Result<FontObfuscationMap, EpubError> {
+        let unique_identifier = self.metadata.identifier.clone().unwrap_or_default();
+        let mut map = FontObfuscationMap::new(unique_identifier);
+        if self.zip.get_entry(ENCRYPTION_XML_PATH).is_none() {
+            return Ok(map);
+        }
+        let mut xml = Vec::with_capacity(0);
+        read_entry_into(&mut self.zip, ENCRYPTION_XML_PATH, &mut xml)?;
+        for (path, algorithm) in parse_obfuscated_resources(&xml)? {
+            map.insert(
+                &container_path_to_opf_href(&self.opf_path, &path),
+                algorithm,
+            );
+        }
+        Ok(map)
+    }
+
+    /// Read a font resource by OPF-relative href, reversing font
+    /// obfuscation declared in `META-INF/encryption.xml`.
+    pub fn read_font_resource(&mut self, href: &str) -> Result<Vec<u8>, EpubError> {
+        let obfuscation = self.font_obfuscation()?;
+        let mut bytes = self.read_resource(href)?;
+        obfuscation.deobfuscate(href, &mut bytes);
+        Ok(bytes)
+    }
+
+    /// Write the manifest resources selected by `filter` under `dest_dir`.
+    ///
+    /// Each resource lands at its container path below `dest_dir` (for
+    /// example `dest_dir/OEBPS/images/cover.jpg`) and is streamed straight to
+    /// disk. Resources with unsafe paths, missing archive entries, sizes over
+    /// the caps in `options`, existing destination files, or read/write
+    /// failures are skipped and listed in the report rather than failing the
+    /// whole extraction.
+    /// Obfuscated fonts are buffered so they can be de-obfuscated first.
+    pub fn extract_resources<P: AsRef<Path>>(
+        &mut self,
+        filter: ResourceFilter,
+        dest_dir: P,
+        mut options: ExtractOptions<'_>,
+    ) -> Result<ExtractReport, EpubError> {
+        let dest_dir = dest_dir.as_ref();
+        let obfuscation = if options.deobfuscate_fonts {
+            self.font_obfuscation()?
+        } else {
+            FontObfuscationMap::default()
+        };
+        let selected: Vec<ManifestItem> = self
+            .metadata
+            .manifest
+            .iter()
+            .filter(|item| filter.matches(item))
+            .cloned()
+            .collect();
+        let mut report = ExtractReport::default();
+        let mut written_total = 0usize;
+        for (index, item) in selected.iter().enumerate() {
+            let zip_path = resolve_opf_relative_path(&self.opf_path, &item.href);
+            let skip = |reason| SkippedResource {
+                href: item.href.clone(),
+                reason,
+            };
+            let Some(relative) = sanitized_relative_path(&zip_path) else {
+                report.skipped.push(skip(SkipReason::UnsafePath));
+                continue;
+            };
+            let Some(size) = self.zip.get_entry(&zip_path).map(|e| e.uncompressed_size) else {
+                report.skipped.push(skip(SkipReason::Missing));
+                continue;
+            };
+            let size = usize::try_from(size).unwrap_or(usize::MAX);
+            if size > options.max_entry_bytes {
+                report.skipped.push(skip(SkipReason::TooLarge));
+                continue;
+            }
+            if size > options.max_total_bytes.saturating_sub(written_total) {
+                report.skipped.push(skip(SkipReason::BudgetExhausted));
+                continue;
+            }
+            let path = dest_dir.join(relative);
+            if !options.overwrite && path.exists() {
+                report.skipped.push(skip(SkipReason::Exists));
+                continue;
+            }
+            let bytes = match self.extract_entry(
+                &zip_path,
+                &item.href,
+                &path,
+                size,
+                &options,
+                &obfuscation,
+            ) {
+                Ok(bytes) => bytes,
+                Err(err) => {
+                    report
+                        .skipped
+                        .push(skip(SkipReason::Failed(err.to_string())));
+                    continue;
+                }
+            };
+            written_total += bytes;
+            options.report_progress(ExtractProgress {
+                index,
+                total: selected.len(),
+                href: &item.href,
+                path: &path,
+                bytes,
+            });
+            report.extracted.push(ExtractedResource {
+                href: item.href.clone(),
+                path,
+                bytes,
+            });
+        }
+        Ok(report)
+    }
+
+    /// Write one archive entry to `path`, removing the partial file on error.
+    fn extract_entry(
+        &mut self,
+        zip_path: &str,
+        href: &str,
+        path: &Path,
+        size: usize,
+        options: &ExtractOptions<'_>,
+        obfuscation: &FontObfuscationMap,
+    ) -> Result<usize, EpubError> {
+        let max_bytes = options.max_entry_bytes;
+        let io_err = |e: std::io::Error| EpubError::Io(format!("{}: {}", path.display(), e));
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).map_err(io_err)?;
+        }
+        let result = if obfuscation.get(href).is_some() {
+            let mut bytes = Vec::with_capacity(size);
+            read_entry_into_with_limit(&mut self.zip, zip_path, &mut bytes, max_bytes).and_then(
+                |_| {
+                    obfuscation.deobfuscate(href, &mut bytes);
+                    std::fs::write(path, &bytes).map_err(io_err)?;
+                    Ok(bytes.len())
+                },
+            )
+        } else {
+            let file = File::create(path).map_err(io_err)?;
+            let mut writer = std::io::BufWriter::new(file);
+            read_entry_into_with_limit(&mut self.zip, zip_path, &mut writer, max_bytes)
+                .and_then(|bytes| writer.flush().map(|_| bytes).map_err(io_err))
+        };
+        if result.is_err() {
+            let _ = std::fs::remove_file(path);
+        }
+        result
+    }
+
     /// Enumerate embedded font-face metadata from EPUB CSS resources.
     pub fn embedded_fonts(&mut self) -> Result<Vec<EmbeddedFontFace>, EpubError> {
         self.embedded_fonts_with_limits(FontLimits::default())
@@ -1177,44 +3028,34 @@ impl<R: Read + Seek> EpubBook<R> {
         &mut self,
         index: usize,
         opts: ChapterEventsOptions,
-        mut on_item: F,
+        on_item: F,
     ) -> Result<usize, EpubError>
     where
         F: FnMut(StyledEventOrRun) -> Result<(), EpubError>,
     {
         let mut prep = RenderPrep::new(opts.render).with_serif_default();
-        let mut emitted = 0usize;
-        let mut callback_error: Option<EpubError> = None;
-        let mut hit_cap = false;
-
-        prep.prepare_chapter_with(self, index, |item| {
-            if callback_error.is_some() || hit_cap {
-                return;
-            }
-            if emitted >= opts.max_items {
-                hit_cap = true;
-                return;
-            }
-            if let Err(err) = on_item(item) {
-                callback_error = Some(err);
-                return;
-            }
-            emitted += 1;
+        emit_capped_chapter_events(opts.max_items, on_item, |emit| {
+            prep.prepare_chapter_with(self, index, emit)
         })
-        .map_err(EpubError::from)?;
+    }
 
-        if let Some(err) = callback_error {
-            return Err(err);
-        }
-        if hit_cap {
-            // TODO: RenderPrep callbacks cannot currently short-circuit parsing.
-            // This cap bounds emitted output, but upstream tokenization keeps scanning.
-            return Err(EpubError::Parse(format!(
-                "Chapter event count exceeded max_items ({})",
-                opts.max_items
-            )));
-        }
-        Ok(emitted)
+    /// Pull chapter style events/runs from an iterator instead of a callback.
+    ///
+    /// Yields the same items as [`Self::chapter_events`], failing once more
+    /// than `opts.max_items` would be produced.
+    ///
+    /// # Allocation behavior
+    /// - Reads the chapter into memory bounded by `opts.render.memory`
+    /// - Styles incrementally as items are pulled; see [`ChapterEventIter`]
+    pub fn chapter_event_iter(
+        &mut self,
+        index: usize,
+        opts: ChapterEventsOptions,
+    ) -> Result<ChapterEventIter, EpubError> {
+        let iter = RenderPrep::new(opts.render)
+            .with_serif_default()
+            .into_chapter_events(self, index)?;
+        Ok(iter.with_max_items(opts.max_items))
     }
 
     /// Stream chapter events with caller-provided scratch buffers.
@@ -1249,7 +3090,7 @@ impl<R: Read + Seek> EpubBook<R> {
         chapter_buf.clear();
         scratch.clear();
 
-        let chapter = self.chapter(index)?;
+        let chapter = self.resolve_displayable_chapter(index)?;
         let href = chapter.href;
         let zip_path = resolve_opf_relative_path(&self.opf_path, &href);
 
@@ -1362,6 +3203,125 @@ impl<R: Read + Seek> EpubBook<R> {
         })
     }
 
+    /// Build a context string around a locator, e.g. for bookmarks.
+    ///
+    /// Up to `before_chars` and `after_chars` characters of chapter text
+    /// surround the resolved position (see
+    /// [`ReadingSession::resolve_locator_in_book`]). Sides cut short drop
+    /// their partial word and gain an ellipsis; the result never exceeds
+    /// [`MAX_EXCERPT_BYTES`].
+    ///
+    /// # Allocation behavior
+    /// - Streams the chapter through the incremental tokenizer and stops once
+    ///   the trailing context is collected
+    /// - Working memory is bounded by [`MAX_EXCERPT_BYTES`] characters per side
+    pub fn excerpt_at(
+        &mut self,
+        locator: Locator,
+        before_chars: usize,
+        after_chars: usize,
+    ) -> Result<String, EpubError> {
+        if matches!(locator, Locator::TocId(_)) {
+            self.ensure_navigation()?;
+        }
+        let mut session = self.reading_session();
+        let resolved = session.resolve_locator_in_book(self, locator)?;
+        let chapter = self.resolve_displayable_chapter(resolved.chapter.index)?;
+        self.excerpt_in(
+            &chapter.href,
+            resolved.position.fallback_offset,
+            before_chars,
+            after_chars,
+        )
+    }
+
+    /// Excerpt around character `offset` of the resource at `href`.
+    fn excerpt_in(
+        &mut self,
+        href: &str,
+        offset: usize,
+        before_chars: usize,
+        after_chars: usize,
+    ) -> Result<String, EpubError> {
+        let mut scan = ExcerptScan::new(offset, before_chars, after_chars);
+        let read = self.read_resource_into(href, &mut scan);
+        if !scan.is_full() {
+            if let Some(err) = scan.error {
+                return Err(EpubError::from(err));
+            }
+            read?;
+        }
+        scan.finish().map_err(EpubError::from)
+    }
+
+    /// Find `needle` in the text of chapter `index`, calling `on_hit` for
+    /// each match in reading order.
+    ///
+    /// Matches may span adjacent text runs (joined by a space), and any
+    /// whitespace in `needle` matches any whitespace in the text. Hits do
+    /// not overlap. Returns the number of hits, at most
+    /// [`FindOptions::max_hits`]; an empty needle finds nothing.
+    ///
+    /// # Allocation behavior
+    /// - Streams the chapter through the incremental tokenizer and stops once
+    ///   `max_hits` is reached
+    /// - Working memory is a window of `needle` length; the chapter text is
+    ///   never materialized
+    pub fn find_in_chapter<F>(
+        &mut self,
+        index: usize,
+        needle: &str,
+        options: FindOptions,
+        on_hit: F,
+    ) -> Result<usize, EpubError>
+    where
+        F: FnMut(TextHit) -> Result<(), EpubError>,
+    {
+        let mut on_hit = on_hit;
+        let outcome =
+            self.find_in_chapter_from(index, needle, options, 0, FindLimits::UNBOUNDED, |hit| {
+                on_hit(hit).map(|()| true)
+            })?;
+        Ok(outcome.hits)
+    }
+
+    /// [`Self::find_in_chapter`] that skips matches before character
+    /// `from_offset` and stops early when `limits` are spent or `on_hit`
+    /// returns `false`.
+    pub(crate) fn find_in_chapter_from<F>(
+        &mut self,
+        index: usize,
+        needle: &str,
+        options: FindOptions,
+        from_offset: usize,
+        limits: FindLimits,
+        on_hit: F,
+    ) -> Result<FindOutcome, EpubError>
+    where
+        F: FnMut(TextHit) -> Result<bool, EpubError>,
+    {
+        let chapter = self.resolve_displayable_chapter(index)?;
+        if needle.is_empty() || options.max_hits == 0 {
+            return Ok(FindOutcome {
+                hits: 0,
+                bytes: 0,
+                resume_offset: None,
+            });
+        }
+        let position = ReadingPosition {
+            chapter_index: index,
+            chapter_href: Some(self.chapter(index)?.href),
+            fallback_offset: from_offset,
+            ..ReadingPosition::default()
+        };
+        let mut scan = FindScan::new(needle, options, position, limits, on_hit);
+        let read = self.read_resource_into(&chapter.href, &mut scan);
+        if !scan.is_done() {
+            read?;
+        }
+        scan.finish()
+    }
+
     /// Read a chapter and return plain text extracted from token stream.
     ///
     /// # Allocation behavior
@@ -1417,9 +3377,64 @@ impl<R: Read + Seek> EpubBook<R> {
             return Ok(());
         }
 
-        let chapter = self.chapter(index)?;
+        self.chapter_text_into_with_options(
+            index,
+            &TextExtractOptions::default().with_max_bytes(max_bytes),
+            out,
+        )
+    }
+
+    /// Extract plain text for a chapter with explicit extraction options.
+    pub fn chapter_text_with_options(
+        &mut self,
+        index: usize,
+        options: &TextExtractOptions,
+    ) -> Result<String, EpubError> {
+        let mut out = String::with_capacity(0);
+        self.chapter_text_into_with_options(index, options, &mut out)?;
+        Ok(out)
+    }
+
+    /// Extract plain text with explicit options into caller-provided storage.
+    ///
+    /// Existing content of `out` is cleared before writing.
+    pub fn chapter_text_into_with_options(
+        &mut self,
+        index: usize,
+        options: &TextExtractOptions,
+        out: &mut String,
+    ) -> Result<(), EpubError> {
+        out.clear();
+        if options.max_bytes == 0 {
+            return Ok(());
+        }
+
+        let chapter = self.resolve_displayable_chapter(index)?;
         let bytes = self.read_resource(&chapter.href)?;
-        extract_plain_text_limited(&bytes, max_bytes, out)
+        let sheets = if options.drop_hidden {
+            self.chapter_display_rules(index)
+        } else {
+            Vec::with_capacity(0)
+        };
+        extract_plain_text(&bytes, options, &sheets, out)
+    }
+
+    /// Parsed chapter stylesheets for hidden-content checks. Sheets that
+    /// fail to load or parse are skipped; text extraction never fails on CSS.
+    fn chapter_display_rules(&mut self, index: usize) -> Vec<Stylesheet> {
+        let Ok(stylesheets) = self.chapter_stylesheets(index) else {
+            return Vec::with_capacity(0);
+        };
+        stylesheets
+            .sources
+            .iter()
+            .filter_map(|source| parse_stylesheet(&source.css).ok())
+            .filter(|sheet| {
+                sheet.rules.iter().any(|rule| {
+                    rule.style.display.or(rule.important.display) == Some(Display::None)
+                })
+            })
+            .collect()
     }
 
     /// Tokenize spine item content by index.
@@ -1433,11 +3448,48 @@ impl<R: Read + Seek> EpubBook<R> {
     /// Prefer `chapter_text_into` for low-memory extraction paths.
     /// For bounded tokenization, use `tokenize_html_limited` from the tokenizer module.
     pub fn tokenize_spine_item(&mut self, index: usize) -> Result<Vec<Token>, EpubError> {
-        let chapter = self.chapter(index)?;
+        let chapter = self.resolve_displayable_chapter(index)?;
         let bytes = self.read_resource(&chapter.href)?;
         let html =
             str::from_utf8(&bytes).map_err(|_| EpubError::ChapterNotUtf8 { href: chapter.href })?;
-        tokenize_html(html).map_err(EpubError::from)
+        let sheets = self.chapter_list_rules(index);
+        tokenize_html_with_stylesheets(html, &sheets).map_err(EpubError::from)
+    }
+
+    /// Parsed chapter stylesheets that declare `list-style-type`; sheets
+    /// that fail to load or parse are skipped.
+    fn chapter_list_rules(&mut self, index: usize) -> Vec<Stylesheet> {
+        let Ok(stylesheets) = self.chapter_stylesheets(index) else {
+            return Vec::with_capacity(0);
+        };
+        stylesheets
+            .sources
+            .iter()
+            .filter_map(|source| parse_stylesheet(&source.css).ok())
+            .filter(|sheet| {
+                sheet.rules.iter().any(|rule| {
+                    rule.style.list_style_type.is_some() || rule.important.list_style_type.is_some()
+                })
+            })
+            .collect()
+    }
+
+    /// Tokenize spine item content by index, reusing the tokenizer state
+    /// for the prefix it shares with previously tokenized chapters.
+    ///
+    /// Keep one `cache` per book and tokenize chapters in order for the best
+    /// reuse; see [`PrefixCache`].
+    pub fn tokenize_spine_item_cached(
+        &mut self,
+        index: usize,
+        cache: &mut PrefixCache,
+    ) -> Result<Vec<Token>, EpubError> {
+        let chapter = self.resolve_displayable_chapter(index)?;
+        let bytes = self.read_resource(&chapter.href)?;
+        if str::from_utf8(&bytes).is_err() {
+            return Err(EpubError::ChapterNotUtf8 { href: chapter.href });
+        }
+        cache.tokenize(&bytes).map_err(EpubError::from)
     }
 
     /// Backward-compatible alias for `read_spine_item_bytes`.
@@ -1481,7 +3533,165 @@ impl EpubBook<File> {
     }
 }
 
-fn load_summary_from_zip<R: Read + Seek>(
+/// Resolve a spine index to the descriptor of the content actually displayed,
+/// following the manifest `fallback` chain past undisplayable items.
+pub(crate) fn displayable_chapter_ref(
+    metadata: &EpubMetadata,
+    spine: &Spine,
+    navigation: Option<&Navigation>,
+    index: usize,
+) -> Result<ChapterRef, EpubError> {
+    let mut chapter = chapter_ref(metadata, spine, navigation, index)?;
+    if let Some(item) = metadata.displayable_item(&chapter.idref) {
+        if item.href != chapter.href {
+            if chapter.title.is_none() {
+                chapter.title = navigation.and_then(|nav| nav_label_for_href(nav, &item.href));
+            }
+            chapter.href = item.href.clone();
+            chapter.media_type = item.media_type.clone();
+            chapter.is_scripted = item.has_property("scripted");
+        }
+    }
+    Ok(chapter)
+}
+
+/// Enumerate chapters in spine order, skipping spine items without a manifest entry.
+pub(crate) fn chapter_refs<'a>(
+    metadata: &'a EpubMetadata,
+    spine: &'a Spine,
+    navigation: Option<&'a Navigation>,
+) -> impl Iterator<Item = ChapterRef> + 'a {
+    spine
+        .items()
+        .iter()
+        .enumerate()
+        .filter_map(move |(index, spine_item)| {
+            metadata
+                .get_item(&spine_item.idref)
+                .map(|manifest_item| ChapterRef {
+                    index,
+                    segment: None,
+                    idref: spine_item.idref.clone(),
+                    href: manifest_item.href.clone(),
+                    media_type: manifest_item.media_type.clone(),
+                    title: navigation.and_then(|nav| nav_label_for_href(nav, &manifest_item.href)),
+                    is_scripted: manifest_item.has_property("scripted"),
+                })
+        })
+}
+
+/// Resolve a chapter descriptor by spine index.
+pub(crate) fn chapter_ref(
+    metadata: &EpubMetadata,
+    spine: &Spine,
+    navigation: Option<&Navigation>,
+    index: usize,
+) -> Result<ChapterRef, EpubError> {
+    let spine_item = spine.get_item(index).ok_or(EpubError::ChapterOutOfBounds {
+        index,
+        chapter_count: spine.len(),
+    })?;
+
+    let manifest_item =
+        metadata
+            .get_item(&spine_item.idref)
+            .ok_or_else(|| EpubError::ManifestItemMissing {
+                idref: spine_item.idref.clone(),
+            })?;
+
+    Ok(ChapterRef {
+        index,
+        segment: None,
+        idref: spine_item.idref.clone(),
+        href: manifest_item.href.clone(),
+        media_type: manifest_item.media_type.clone(),
+        title: navigation.and_then(|nav| nav_label_for_href(nav, &manifest_item.href)),
+        is_scripted: manifest_item.has_property("scripted"),
+    })
+}
+
+/// Whether `media_type` is an (X)HTML content document.
+pub(crate) fn is_markup_media_type(media_type: &str) -> bool {
+    matches!(media_type, "application/xhtml+xml" | "text/html")
+}
+
+/// Whether chapter markup contains a `<script>` element (ASCII case-insensitive).
+pub(crate) fn contains_script_element(html: &[u8]) -> bool {
+    const TAG: &[u8] = b"<script";
+    html.windows(TAG.len() + 1).any(|window| {
+        window[..TAG.len()].eq_ignore_ascii_case(TAG)
+            && matches!(
+                window[TAG.len()],
+                b'>' | b'/' | b' ' | b'\t' | b'\n' | b'\r'
+            )
+    })
+}
+
+/// Label of the first TOC entry (depth-first) whose target is `chapter_href`.
+pub(crate) fn nav_label_for_href(nav: &Navigation, chapter_href: &str) -> Option<String> {
+    fn visit(points: &[NavPoint], chapter_href: &str) -> Option<String> {
+        for point in points {
+            let label = point.label.trim();
+            if href_targets_chapter(&point.href, chapter_href) && !label.is_empty() {
+                return Some(label.to_string());
+            }
+            if let Some(hit) = visit(&point.children, chapter_href) {
+                return Some(hit);
+            }
+        }
+        None
+    }
+    visit(&nav.toc, chapter_href)
+}
+
+/// Drive `prepare` and forward its items to `on_item`, enforcing `max_items`.
+///
+/// Returns the number of items delivered, the first callback error, or a
+/// parse error once the cap is exceeded.
+pub(crate) fn emit_capped_chapter_events<F, P>(
+    max_items: usize,
+    mut on_item: F,
+    prepare: P,
+) -> Result<usize, EpubError>
+where
+    F: FnMut(StyledEventOrRun) -> Result<(), EpubError>,
+    P: FnOnce(&mut dyn FnMut(StyledEventOrRun)) -> Result<(), RenderPrepError>,
+{
+    let mut emitted = 0usize;
+    let mut callback_error: Option<EpubError> = None;
+    let mut hit_cap = false;
+
+    prepare(&mut |item| {
+        if callback_error.is_some() || hit_cap {
+            return;
+        }
+        if emitted >= max_items {
+            hit_cap = true;
+            return;
+        }
+        if let Err(err) = on_item(item) {
+            callback_error = Some(err);
+            return;
+        }
+        emitted += 1;
+    })
+    .map_err(EpubError::from)?;
+
+    if let Some(err) = callback_error {
+        return Err(err);
+    }
+    if hit_cap {
+        // TODO: RenderPrep callbacks cannot currently short-circuit parsing.
+        // This cap bounds emitted output, but upstream tokenization keeps scanning.
+        return Err(EpubError::Parse(format!(
+            "Chapter event count exceeded max_items ({})",
+            max_items
+        )));
+    }
+    Ok(emitted)
+}
+
+fn load_summary_from_zip<R: RandomAccess>(
     zip: &mut StreamingZip<R>,
     options: EpubBookOptions,
 ) -> Result<EpubSummary, EpubError> {
@@ -1490,8 +3700,9 @@ fn load_summary_from_zip<R: Read + Seek>(
     let opf_path = crate::metadata::parse_container_xml(&container)?;
     let opf = read_entry(zip, &opf_path)?;
     let metadata = extract_metadata(&container, &opf)?;
-    let spine = crate::spine::parse_spine(&opf)?;
-    validate_open_invariants(&metadata, &spine, options.validation_mode)?;
+    let mut spine = crate::spine::parse_spine(&opf)?;
+    spine.resolve_duplicate_idrefs();
+    validate_open_invariants(&metadata, &spine, &opf, options.validation_mode)?;
     let navigation = parse_navigation(
         zip,
         &metadata,
@@ -1508,7 +3719,7 @@ fn load_summary_from_zip<R: Read + Seek>(
     })
 }
 
-fn parse_navigation<R: Read + Seek>(
+fn parse_navigation<R: RandomAccess>(
     zip: &mut StreamingZip<R>,
     metadata: &EpubMetadata,
     spine: &Spine,
@@ -1516,7 +3727,43 @@ fn parse_navigation<R: Read + Seek>(
     validation_mode: ValidationMode,
     max_nav_bytes: Option<usize>,
 ) -> Result<Option<Navigation>, EpubError> {
-    let nav_item = spine
+    let Some(nav_item) = navigation_item(metadata, spine) else {
+        return Ok(None);
+    };
+    let nav_path = resolve_opf_relative_path(opf_path, &nav_item.href);
+    let nav_bytes = read_entry(zip, &nav_path);
+    navigation_from_bytes(
+        nav_item,
+        &nav_path,
+        nav_bytes,
+        validation_mode,
+        max_nav_bytes,
+    )
+}
+
+/// Stored size of the navigation document, or `0` when there is none.
+fn navigation_size<R: RandomAccess>(
+    zip: &StreamingZip<R>,
+    metadata: &EpubMetadata,
+    spine: &Spine,
+    opf_path: &str,
+) -> u64 {
+    navigation_item(metadata, spine).map_or(0, |item| {
+        stored_size(zip, &resolve_opf_relative_path(opf_path, &item.href))
+    })
+}
+
+/// Stored (compressed) size of an archive entry, or `0` when it is missing.
+fn stored_size<R: RandomAccess>(zip: &StreamingZip<R>, path: &str) -> u64 {
+    zip.get_entry(path).map_or(0, |entry| entry.compressed_size)
+}
+
+/// Pick the manifest item holding navigation: spine `toc`, EPUB 3 nav, then NCX.
+pub(crate) fn navigation_item<'a>(
+    metadata: &'a EpubMetadata,
+    spine: &Spine,
+) -> Option<&'a ManifestItem> {
+    spine
         .toc_id()
         .and_then(|toc_id| metadata.get_item(toc_id))
         .or_else(|| {
@@ -1531,14 +3778,18 @@ fn parse_navigation<R: Read + Seek>(
                 item.media_type == "application/x-dtbncx+xml"
                     || item.href.to_ascii_lowercase().ends_with(".ncx")
             })
-        });
-
-    let Some(nav_item) = nav_item else {
-        return Ok(None);
-    };
+        })
+}
 
-    let nav_path = resolve_opf_relative_path(opf_path, &nav_item.href);
-    let nav_bytes = match read_entry(zip, &nav_path) {
+/// Parse navigation from the result of reading `nav_path`, honoring validation mode.
+pub(crate) fn navigation_from_bytes(
+    nav_item: &ManifestItem,
+    nav_path: &str,
+    nav_bytes: Result<Vec<u8>, EpubError>,
+    validation_mode: ValidationMode,
+    max_nav_bytes: Option<usize>,
+) -> Result<Option<Navigation>, EpubError> {
+    let nav_bytes = match nav_bytes {
         Ok(bytes) => bytes,
         Err(err) => {
             if matches!(validation_mode, ValidationMode::Strict) {
@@ -1562,7 +3813,7 @@ fn parse_navigation<R: Read + Seek>(
                 .into_boxed_str(),
                 context: Some(Box::new(PhaseErrorContext {
                     source: None,
-                    path: Some(nav_path.clone().into_boxed_str()),
+                    path: Some(nav_path.to_string().into_boxed_str()),
                     href: Some(nav_item.href.clone().into_boxed_str()),
                     chapter_index: None,
                     selector: None,
@@ -1605,14 +3856,20 @@ fn parse_navigation<R: Read + Seek>(
     }
 }
 
-fn validate_open_invariants(
+pub(crate) fn validate_open_invariants(
     metadata: &EpubMetadata,
     spine: &Spine,
+    opf: &[u8],
     validation_mode: ValidationMode,
 ) -> Result<(), EpubError> {
     if matches!(validation_mode, ValidationMode::Lenient) {
         return Ok(());
     }
+    // Lenient opens resolve conflicts last-wins without rescanning the OPF.
+    let conflicts = crate::metadata::find_opf_conflicts(opf)?;
+    if !conflicts.is_empty() {
+        return Err(EpubError::OpfConflicts(conflicts));
+    }
 
     for item in spine.items() {
         if metadata.get_item(&item.idref).is_none() {
@@ -1625,13 +3882,16 @@ fn validate_open_invariants(
     Ok(())
 }
 
-fn read_entry<R: Read + Seek>(zip: &mut StreamingZip<R>, path: &str) -> Result<Vec<u8>, EpubError> {
+fn read_entry<R: RandomAccess>(
+    zip: &mut StreamingZip<R>,
+    path: &str,
+) -> Result<Vec<u8>, EpubError> {
     let mut buf = Vec::with_capacity(0);
     read_entry_into(zip, path, &mut buf)?;
     Ok(buf)
 }
 
-fn read_entry_into<R: Read + Seek, W: Write>(
+fn read_entry_into<R: RandomAccess, W: Write>(
     zip: &mut StreamingZip<R>,
     path: &str,
     writer: &mut W,
@@ -1639,7 +3899,7 @@ fn read_entry_into<R: Read + Seek, W: Write>(
     read_entry_into_with_limit(zip, path, writer, usize::MAX)
 }
 
-fn read_entry_into_with_limit<R: Read + Seek, W: Write>(
+pub(crate) fn read_entry_into_with_limit<R: RandomAccess, W: Write>(
     zip: &mut StreamingZip<R>,
     path: &str,
     writer: &mut W,
@@ -1673,17 +3933,96 @@ fn read_entry_into_with_limit<R: Read + Seek, W: Write>(
         .map_err(EpubError::Zip)
 }
 
-fn resolve_opf_relative_path(opf_path: &str, href: &str) -> String {
+const ENCRYPTION_XML_PATH: &str = "META-INF/encryption.xml";
+
+/// `(container path, algorithm)` for each font-obfuscated `EncryptedData`
+/// entry in `encryption.xml`.
+fn parse_obfuscated_resources(xml: &[u8]) -> Result<Vec<(String, FontObfuscation)>, EpubError> {
+    let mut reader = Reader::from_reader(xml);
+    reader.config_mut().trim_text(true);
+    let mut buf = Vec::with_capacity(0);
+    let mut out = Vec::with_capacity(0);
+    let mut algorithm = None;
+    let mut uri = None;
+    loop {
+        match reader.read_event_into(&mut buf) {
+            Ok(Event::Start(e)) | Ok(Event::Empty(e)) => {
+                let attr_name: &[u8] = match e.local_name().as_ref() {
+                    b"EncryptedData" => {
+                        algorithm = None;
+                        uri = None;
+                        &[]
+                    }
+                    b"EncryptionMethod" => b"Algorithm",
+                    b"CipherReference" => b"URI",
+                    _ => &[],
+                };
+                if attr_name.is_empty() {
+                    buf.clear();
+                    continue;
+                }
+                let value = e
+                    .attributes()
+                    .flatten()
+                    .find(|attr| attr.key.local_name().as_ref() == attr_name)
+                    .map(|attr| {
+                        reader
+                            .decoder()
+                            .decode(&attr.value)
+                            .map(|value| value.trim().to_string())
+                    })
+                    .transpose()
+                    .map_err(|e| EpubError::Parse(format!("Decode error: {:?}", e)))?;
+                if attr_name == b"Algorithm" {
+                    algorithm = value.as_deref().and_then(FontObfuscation::from_algorithm);
+                } else {
+                    uri = value;
+                }
+            }
+            Ok(Event::End(e)) if e.local_name().as_ref() == b"EncryptedData" => {
+                if let (Some(algorithm), Some(uri)) = (algorithm.take(), uri.take()) {
+                    let path = percent_decode_path(uri.trim_start_matches("../"));
+                    out.push((path.trim_start_matches('/').to_string(), algorithm));
+                }
+            }
+            Ok(Event::Eof) => break,
+            Err(e) => return Err(EpubError::Parse(format!("XML parse error: {:?}", e))),
+            _ => {}
+        }
+        buf.clear();
+    }
+    Ok(out)
+}
+
+/// OPF-relative href for a container path; paths outside the package
+/// directory become root-absolute (`/path`), which
+/// [`resolve_opf_relative_path`] maps back.
+fn container_path_to_opf_href(opf_path: &str, path: &str) -> String {
+    match opf_path.rsplit_once('/') {
+        None => path.to_string(),
+        Some((dir, _)) => match path
+            .strip_prefix(dir)
+            .and_then(|rest| rest.strip_prefix('/'))
+        {
+            Some(rest) => rest.to_string(),
+            None => format!("/{}", path),
+        },
+    }
+}
+
+pub(crate) fn resolve_opf_relative_path(opf_path: &str, href: &str) -> String {
     let href = href.split('#').next().unwrap_or(href);
     if href.is_empty() {
         return normalize_path(opf_path);
     }
-    if href.starts_with('/') {
-        return normalize_path(href.trim_start_matches('/'));
-    }
     if href.contains("://") {
         return href.to_string();
     }
+    let href = percent_decode_path(href);
+    let href = href.as_str();
+    if href.starts_with('/') {
+        return normalize_path(href.trim_start_matches('/'));
+    }
 
     let base_dir = opf_path.rsplit_once('/').map(|(dir, _)| dir).unwrap_or("");
     if base_dir.is_empty() {
@@ -1707,13 +4046,6 @@ fn normalize_path(path: &str) -> String {
     parts.join("/")
 }
 
-fn should_skip_text_tag(name: &str) -> bool {
-    matches!(
-        name,
-        "script" | "style" | "head" | "nav" | "header" | "footer" | "aside" | "noscript"
-    )
-}
-
 fn normalize_plain_text_whitespace(text: &str) -> String {
     let mut result = String::with_capacity(text.len());
     let mut prev_was_space = true;
@@ -1753,28 +4085,73 @@ fn push_limited(out: &mut String, value: &str, max_bytes: usize) -> bool {
     true
 }
 
-fn push_newline_limited(out: &mut String, max_bytes: usize) -> bool {
-    if out.is_empty() || out.ends_with('\n') {
+fn push_separator_limited(out: &mut String, separator: &str, max_bytes: usize) -> bool {
+    if out.is_empty() || out.ends_with(separator) {
         return false;
     }
-    push_limited(out, "\n", max_bytes)
+    push_limited(out, separator, max_bytes)
 }
 
 fn push_text_limited(out: &mut String, text: &str, max_bytes: usize) -> bool {
     if text.is_empty() {
         return false;
     }
-    if !out.is_empty() && !out.ends_with('\n') && push_limited(out, " ", max_bytes) {
+    if !out.is_empty() && !out.ends_with(char::is_whitespace) && push_limited(out, " ", max_bytes) {
         return true;
     }
     push_limited(out, text, max_bytes)
 }
 
-fn extract_plain_text_limited(
+/// Whether an element is hidden by a `hidden` attribute, inline
+/// `display: none`, or a `display: none` rule in `sheets`.
+fn is_hidden_element(e: &quick_xml::events::BytesStart<'_>, sheets: &[Stylesheet]) -> bool {
+    let hidden = e
+        .attributes()
+        .flatten()
+        .any(|attr| match attr.key.as_ref() {
+            b"hidden" => true,
+            b"style" => {
+                let style: Vec<u8> = attr
+                    .value
+                    .iter()
+                    .filter(|b| !b.is_ascii_whitespace())
+                    .map(u8::to_ascii_lowercase)
+                    .collect();
+                style
+                    .windows(b"display:none".len())
+                    .any(|window| window == b"display:none")
+            }
+            _ => false,
+        });
+    if hidden || sheets.is_empty() {
+        return hidden;
+    }
+    let tag = String::from_utf8_lossy(e.name().as_ref()).into_owned();
+    let class_attr = e
+        .try_get_attribute("class")
+        .ok()
+        .flatten()
+        .map(|attr| String::from_utf8_lossy(&attr.value).into_owned())
+        .unwrap_or_default();
+    let classes: Vec<&str> = class_attr.split_whitespace().collect();
+    cascade(sheets, &tag, &classes, None, None).display == Some(Display::None)
+}
+
+fn image_alt_text(reader: &Reader<&[u8]>, e: &quick_xml::events::BytesStart<'_>) -> Option<String> {
+    let attr = e.try_get_attribute("alt").ok().flatten()?;
+    let value = attr.decode_and_unescape_value(reader.decoder()).ok()?;
+    Some(normalize_plain_text_whitespace(&value))
+}
+
+/// Extract plain text from chapter markup, dropping elements hidden by
+/// `sheets` when [`TextExtractOptions::drop_hidden`] is set.
+fn extract_plain_text(
     html: &[u8],
-    max_bytes: usize,
+    options: &TextExtractOptions,
+    sheets: &[Stylesheet],
     out: &mut String,
 ) -> Result<(), EpubError> {
+    let max_bytes = options.max_bytes;
     let mut reader = Reader::from_reader(html);
     reader.config_mut().trim_text(false);
     reader.config_mut().expand_empty_elements = false;
@@ -1786,22 +4163,30 @@ fn extract_plain_text_limited(
     while !done {
         match reader.read_event_into(&mut buf) {
             Ok(Event::Start(e)) => {
+                if skip_depth > 0 {
+                    skip_depth += 1;
+                    buf.clear();
+                    continue;
+                }
                 let name = reader
                     .decoder()
                     .decode(e.name().as_ref())
                     .map_err(|err| EpubError::Parse(format!("Decode error: {:?}", err)))?
                     .to_string();
-                if should_skip_text_tag(&name) {
-                    skip_depth += 1;
-                } else if skip_depth == 0
-                    && matches!(name.as_str(), "p" | "div" | "li")
-                    && push_newline_limited(out, max_bytes)
+                if options.skip_elements.contains(&name.as_str())
+                    || (options.drop_hidden && is_hidden_element(&e, sheets))
                 {
-                    done = true;
+                    skip_depth = 1;
+                } else if matches!(name.as_str(), "p" | "div" | "li") {
+                    done = push_separator_limited(out, options.block_separator, max_bytes);
+                } else if name == "img" && options.include_alt_text {
+                    if let Some(alt) = image_alt_text(&reader, &e) {
+                        done = push_text_limited(out, &alt, max_bytes);
+                    }
                 }
             }
             Ok(Event::Empty(e)) => {
-                if skip_depth > 0 {
+                if skip_depth > 0 || (options.drop_hidden && is_hidden_element(&e, sheets)) {
                     buf.clear();
                     continue;
                 }
@@ -1810,25 +4195,33 @@ fn extract_plain_text_limited(
                     .decode(e.name().as_ref())
                     .map_err(|err| EpubError::Parse(format!("Decode error: {:?}", err)))?
                     .to_string();
-                if matches!(name.as_str(), "br" | "p" | "div" | "li")
-                    && push_newline_limited(out, max_bytes)
-                {
-                    done = true;
+                if options.skip_elements.contains(&name.as_str()) {
+                    buf.clear();
+                    continue;
                 }
+                done = match name.as_str() {
+                    "br" => push_separator_limited(out, options.line_break_separator, max_bytes),
+                    "p" | "div" | "li" => {
+                        push_separator_limited(out, options.block_separator, max_bytes)
+                    }
+                    "img" if options.include_alt_text => image_alt_text(&reader, &e)
+                        .is_some_and(|alt| push_text_limited(out, &alt, max_bytes)),
+                    _ => false,
+                };
             }
             Ok(Event::End(e)) => {
+                if skip_depth > 0 {
+                    skip_depth -= 1;
+                    buf.clear();
+                    continue;
+                }
                 let name = reader
                     .decoder()
                     .decode(e.name().as_ref())
                     .map_err(|err| EpubError::Parse(format!("Decode error: {:?}", err)))?
                     .to_string();
-                if should_skip_text_tag(&name) {
-                    skip_depth = skip_depth.saturating_sub(1);
-                } else if skip_depth == 0
-                    && matches!(name.as_str(), "p" | "div" | "li")
-                    && push_newline_limited(out, max_bytes)
-                {
-                    done = true;
+                if matches!(name.as_str(), "p" | "div" | "li") {
+                    done = push_separator_limited(out, options.block_separator, max_bytes);
                 }
             }
             Ok(Event::Text(e)) => {
@@ -1884,8 +4277,10 @@ fn extract_plain_text_limited(
         buf.clear();
     }
 
-    if out.ends_with('\n') {
-        out.pop();
+    for separator in [options.block_separator, options.line_break_separator] {
+        if let Some(stripped) = out.strip_suffix(separator) {
+            out.truncate(stripped.len());
+        }
     }
     Ok(())
 }
@@ -1895,6 +4290,56 @@ mod tests {
     use super::*;
     use crate::render_prep::{RenderPrep, RenderPrepOptions, RenderPrepTrace, StyledEventOrRun};
 
+    #[test]
+    fn test_chapter_refs_order_by_spine_index_in_sets() {
+        let book = EpubBook::open(
+            "tests/fixtures/Fundamental-Accessibility-Tests-Basic-Functionality-v2.0.0.epub",
+        )
+        .unwrap();
+        let chapters: Vec<ChapterRef> = book.chapters().collect();
+        let set: alloc::collections::BTreeSet<ChapterRef> =
+            chapters.iter().rev().cloned().collect();
+        assert!(set.iter().cloned().eq(chapters.iter().cloned()));
+
+        let first = book.chapter(0).unwrap();
+        assert!(set.contains(&first));
+    }
+
+    #[test]
+    fn test_parse_obfuscated_resources() {
+        let xml = br#"<encryption xmlns="urn:oasis:names:tc:opendocument:xmlns:container"
+    xmlns:enc="http://www.w3.org/2001/04/xmlenc#">
+  <enc:EncryptedData>
+    <enc:EncryptionMethod Algorithm="http://www.idpf.org/2008/embedding"/>
+    <enc:CipherData><enc:CipherReference URI="EPUB/fonts/Serif%20Bold.otf"/></enc:CipherData>
+  </enc:EncryptedData>
+  <enc:EncryptedData>
+    <enc:EncryptionMethod Algorithm="http://www.w3.org/2001/04/xmlenc#aes128-cbc"/>
+    <enc:CipherData><enc:CipherReference URI="EPUB/secret.xhtml"/></enc:CipherData>
+  </enc:EncryptedData>
+</encryption>"#;
+        let resources = parse_obfuscated_resources(xml).unwrap();
+        assert_eq!(
+            resources,
+            vec![(
+                "EPUB/fonts/Serif Bold.otf".to_string(),
+                FontObfuscation::Idpf
+            )]
+        );
+        assert_eq!(
+            container_path_to_opf_href("EPUB/package.opf", "EPUB/fonts/a.otf"),
+            "fonts/a.otf"
+        );
+        assert_eq!(
+            container_path_to_opf_href("EPUB/package.opf", "fonts/a.otf"),
+            "/fonts/a.otf"
+        );
+        assert_eq!(
+            container_path_to_opf_href("package.opf", "fonts/a.otf"),
+            "fonts/a.otf"
+        );
+    }
+
     #[test]
     fn test_resolve_opf_relative_path() {
         assert_eq!(
@@ -1913,22 +4358,106 @@ mod tests {
             resolve_opf_relative_path("EPUB/package.opf", "/META-INF/container.xml"),
             "META-INF/container.xml"
         );
+        assert_eq!(
+            resolve_opf_relative_path("EPUB/package.opf", "text/My%20Chapter.xhtml#p%201"),
+            "EPUB/text/My Chapter.xhtml"
+        );
+    }
+
+    #[test]
+    fn test_from_reader_accepts_plain_read_seek_sources() {
+        struct Plain(std::io::Cursor<Vec<u8>>);
+        impl Read for Plain {
+            fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+                self.0.read(buf)
+            }
+        }
+        impl Seek for Plain {
+            fn seek(&mut self, pos: std::io::SeekFrom) -> std::io::Result<u64> {
+                self.0.seek(pos)
+            }
+        }
+
+        let bytes = std::fs::read(
+            "tests/fixtures/Fundamental-Accessibility-Tests-Basic-Functionality-v2.0.0.epub",
+        )
+        .unwrap();
+        let summary = parse_epub_reader(Plain(std::io::Cursor::new(bytes.clone()))).unwrap();
+        let book: EpubBook<ReadSeekAdapter<Plain>> =
+            EpubBook::from_reader(Plain(std::io::Cursor::new(bytes.clone()))).unwrap();
+        let stored = EpubBook::from_storage(bytes).unwrap();
+        assert_eq!(book.spine().len(), summary.spine().len());
+        assert_eq!(stored.spine().len(), summary.spine().len());
+    }
+
+    #[test]
+    fn test_read_resource_into_streams_to_writer() {
+        let file = std::fs::File::open(
+            "tests/fixtures/Fundamental-Accessibility-Tests-Basic-Functionality-v2.0.0.epub",
+        )
+        .expect("fixture should open");
+        let mut book = EpubBook::from_reader(file).expect("book should open");
+
+        let mut out = Vec::with_capacity(0);
+        let n = book
+            .read_resource_into("xhtml/nav.xhtml", &mut out)
+            .expect("resource should stream");
+        assert_eq!(n, out.len());
+        assert!(!out.is_empty());
     }
 
     #[test]
-    fn test_read_resource_into_streams_to_writer() {
+    fn test_read_resource_range_matches_full_read() {
         let file = std::fs::File::open(
             "tests/fixtures/Fundamental-Accessibility-Tests-Basic-Functionality-v2.0.0.epub",
         )
         .expect("fixture should open");
         let mut book = EpubBook::from_reader(file).expect("book should open");
+        let full = book.read_resource("xhtml/nav.xhtml").unwrap();
+        let mut window = Vec::with_capacity(0);
+        let n = book
+            .read_resource_range("xhtml/nav.xhtml", 100, 64, &mut window)
+            .expect("range should stream");
+        assert_eq!(n, 64);
+        assert_eq!(window, &full[100..164]);
 
-        let mut out = Vec::with_capacity(0);
+        window.clear();
+        let tail = full.len() as u64 - 10;
         let n = book
-            .read_resource_into("xhtml/nav.xhtml", &mut out)
-            .expect("resource should stream");
-        assert_eq!(n, out.len());
-        assert!(!out.is_empty());
+            .read_resource_range("xhtml/nav.xhtml", tail, 64, &mut window)
+            .unwrap();
+        assert_eq!((n, window.as_slice()), (10, &full[full.len() - 10..]));
+    }
+
+    #[test]
+    fn test_chapter_complexity_uses_manifest_and_entry_sizes() {
+        let file = std::fs::File::open(
+            "tests/fixtures/Fundamental-Accessibility-Tests-Basic-Functionality-v2.0.0.epub",
+        )
+        .expect("fixture should open");
+        let mut book = EpubBook::from_reader(file).expect("book should open");
+        let chapter = book.chapter(0).unwrap();
+        let html = book.read_resource(&chapter.href).unwrap();
+        let stylesheets = book
+            .metadata()
+            .manifest
+            .iter()
+            .filter(|item| item.media_type == "text/css")
+            .count();
+
+        let complexity = book.chapter_complexity(0).expect("estimate should succeed");
+        assert_eq!(complexity.index, 0);
+        assert_eq!(complexity.chapter_bytes, html.len() as u64);
+        assert!(complexity.compressed_bytes > 0);
+        assert_eq!(complexity.stylesheet_count, stylesheets);
+        assert!(!complexity.is_heavy());
+        assert!(book.chapter_complexity(book.chapter_count()).is_err());
+
+        let heavy = ChapterComplexity {
+            chapter_bytes: HEAVY_CHAPTER_BYTES + 1,
+            ..complexity
+        };
+        assert!(heavy.is_heavy());
     }
 
     #[test]
@@ -1962,6 +4491,31 @@ mod tests {
         assert!(!out.is_empty());
     }
 
+    #[test]
+    fn test_strict_open_rejects_opf_conflicts() {
+        let opf = br#"<package xmlns="http://www.idpf.org/2007/opf" version="3.0">
+  <manifest>
+    <item id="a" href="a.xhtml" media-type="application/xhtml+xml"/>
+    <item id="a" href="b.xhtml" media-type="application/xhtml+xml"/>
+  </manifest>
+  <spine><itemref idref="a"/></spine>
+</package>"#;
+        let metadata = crate::metadata::parse_opf(opf).unwrap();
+        let spine = crate::spine::parse_spine(opf).unwrap();
+        validate_open_invariants(&metadata, &spine, opf, ValidationMode::Lenient)
+            .expect("lenient open resolves conflicts last-wins");
+        match validate_open_invariants(&metadata, &spine, opf, ValidationMode::Strict) {
+            Err(EpubError::OpfConflicts(conflicts)) => {
+                assert_eq!(conflicts.len(), 1);
+                assert!(matches!(
+                    &conflicts[0],
+                    crate::metadata::OpfConflict::DuplicateManifestId { id, .. } if id == "a"
+                ));
+            }
+            other => panic!("expected OPF conflict error, got {:?}", other),
+        }
+    }
+
     #[test]
     fn test_open_enforces_max_nav_bytes_limit() {
         let file = std::fs::File::open(
@@ -2001,6 +4555,7 @@ mod tests {
             OpenConfig {
                 options: EpubBookOptions::default(),
                 lazy_navigation: true,
+                profile: false,
             },
         )
         .expect("book should open");
@@ -2011,6 +4566,61 @@ mod tests {
         assert!(nav.is_some());
     }
 
+    #[test]
+    fn test_tokenize_spine_item_cached_matches_uncached() {
+        let file = std::fs::File::open(
+            "tests/fixtures/Fundamental-Accessibility-Tests-Basic-Functionality-v2.0.0.epub",
+        )
+        .expect("fixture should open");
+        let mut book = EpubBook::from_reader(file).expect("book should open");
+        let mut cache = PrefixCache::new(TokenizeLimits::default(), 4096);
+        for index in 0..book.chapter_count() {
+            let cached = book
+                .tokenize_spine_item_cached(index, &mut cache)
+                .expect("cached tokenize should succeed");
+            let plain = book
+                .tokenize_spine_item(index)
+                .expect("tokenize should succeed");
+            assert_eq!(cached, plain);
+        }
+        assert!(cache.stats().hits > 0);
+        assert!(cache.stats().bytes_skipped > 0);
+    }
+
+    #[test]
+    fn test_open_profile_breaks_down_phases() {
+        let path = "tests/fixtures/Fundamental-Accessibility-Tests-Basic-Functionality-v2.0.0.epub";
+        let open = |lazy_navigation, profile| {
+            let file = std::fs::File::open(path).expect("fixture should open");
+            EpubBook::from_reader_with_config(
+                file,
+                OpenConfig {
+                    options: EpubBookOptions::default(),
+                    lazy_navigation,
+                    profile,
+                },
+            )
+            .expect("book should open")
+        };
+
+        assert!(open(false, false).open_profile().is_none());
+
+        let book = open(false, true);
+        let profile = *book.open_profile().expect("profile should be recorded");
+        assert!(profile.central_directory.bytes > 0);
+        assert!(profile.mimetype.bytes > 0);
+        assert!(profile.package.bytes > profile.mimetype.bytes);
+        let nav = profile.navigation.expect("navigation should be timed");
+        assert!(nav.bytes > 0);
+        assert!(profile.total() >= profile.package.duration + nav.duration);
+
+        let mut lazy = open(true, true);
+        assert_eq!(lazy.open_profile().and_then(|p| p.navigation), None);
+        lazy.ensure_navigation().expect("navigation should parse");
+        let lazy_nav = lazy.open_profile().and_then(|p| p.navigation);
+        assert_eq!(lazy_nav.map(|nav| nav.bytes), Some(nav.bytes));
+    }
+
     #[test]
     fn test_chapter_text_into_matches_chapter_text() {
         let file = std::fs::File::open(
@@ -2098,14 +4708,92 @@ mod tests {
     }
 
     #[test]
-    fn test_extract_plain_text_limited_preserves_utf8_boundaries() {
+    fn test_extract_plain_text_with_limit_preserves_utf8_boundaries() {
         let html = "<p>hello 😀 world</p>";
         let mut out = String::with_capacity(0);
-        extract_plain_text_limited(html.as_bytes(), 8, &mut out).expect("extract should succeed");
+        let options = TextExtractOptions::default().with_max_bytes(8);
+        extract_plain_text(html.as_bytes(), &options, &[], &mut out)
+            .expect("extract should succeed");
         assert!(out.len() <= 8);
         assert!(core::str::from_utf8(out.as_bytes()).is_ok());
     }
 
+    #[test]
+    fn test_extract_plain_text_options_control_hidden_alt_and_separators() {
+        let html = r#"<div><p>One<br/>two</p><p hidden="">secret</p><p style="Display : None">gone</p><img src="a.png" alt="A  map"/><nav><p>menu</p></nav><p>End</p></div>"#;
+        let mut out = String::with_capacity(0);
+
+        extract_plain_text(
+            html.as_bytes(),
+            &TextExtractOptions::default(),
+            &[],
+            &mut out,
+        )
+        .unwrap();
+        assert_eq!(out, "One\ntwo\nsecret\ngone\nEnd");
+
+        out.clear();
+        extract_plain_text(html.as_bytes(), &TextExtractOptions::tts(), &[], &mut out).unwrap();
+        assert_eq!(out, "One\ntwo\n\nA map\n\nEnd");
+
+        out.clear();
+        extract_plain_text(
+            html.as_bytes(),
+            &TextExtractOptions::excerpt(),
+            &[],
+            &mut out,
+        )
+        .unwrap();
+        assert_eq!(out, "One two End");
+
+        out.clear();
+        let options = TextExtractOptions {
+            skip_elements: &["header"],
+            ..TextExtractOptions::search()
+        };
+        extract_plain_text(html.as_bytes(), &options, &[], &mut out).unwrap();
+        assert_eq!(out, "One\ntwo\nA map\nmenu\nEnd");
+    }
+
+    #[test]
+    fn test_extract_plain_text_drops_stylesheet_hidden_elements() {
+        let sheets = [parse_stylesheet(".key { display: none; }").unwrap()];
+        let html = r#"<p>Question</p><div class="answer key"><p>Answer</p></div><p>End</p>"#;
+        let mut out = String::with_capacity(0);
+
+        extract_plain_text(
+            html.as_bytes(),
+            &TextExtractOptions::excerpt(),
+            &sheets,
+            &mut out,
+        )
+        .unwrap();
+        assert_eq!(out, "Question End");
+
+        out.clear();
+        let options = TextExtractOptions {
+            drop_hidden: false,
+            ..TextExtractOptions::excerpt()
+        };
+        extract_plain_text(html.as_bytes(), &options, &sheets, &mut out).unwrap();
+        assert_eq!(out, "Question Answer End");
+    }
+
+    #[test]
+    fn test_extract_plain_text_keeps_epub_switch_default() {
+        let html = r#"<p>Before</p><epub:switch id="eq"><epub:case required-namespace="http://www.w3.org/1998/Math/MathML"><math><mi>x</mi></math></epub:case><epub:default><p>x squared</p></epub:default></epub:switch><epub:trigger action="show" ref="eq"/><p>After</p>"#;
+        let mut out = String::with_capacity(0);
+        extract_plain_text(
+            html.as_bytes(),
+            &TextExtractOptions::default(),
+            &[],
+            &mut out,
+        )
+        .expect("extract should succeed");
+        assert!(out.contains("x squared"));
+        assert_eq!(out.matches('x').count(), 1);
+    }
+
     #[test]
     fn test_chapter_stylesheets_api_works() {
         let file = std::fs::File::open(
@@ -2264,6 +4952,43 @@ mod tests {
         assert!(matches!(err, EpubError::Parse(_)));
     }
 
+    #[test]
+    fn test_chapter_event_iter_matches_callback_stream() {
+        let file = std::fs::File::open(
+            "tests/fixtures/Fundamental-Accessibility-Tests-Basic-Functionality-v2.0.0.epub",
+        )
+        .expect("fixture should open");
+        let mut book = EpubBook::from_reader(file).expect("book should open");
+        for index in 0..book.chapter_count() {
+            let mut expected = Vec::with_capacity(0);
+            book.chapter_events(index, ChapterEventsOptions::default(), |item| {
+                expected.push(item);
+                Ok(())
+            })
+            .expect("chapter_events should succeed");
+            let pulled: Vec<StyledEventOrRun> = book
+                .chapter_event_iter(index, ChapterEventsOptions::default())
+                .expect("iterator should open")
+                .collect::<Result<_, _>>()
+                .expect("iteration should succeed");
+            assert_eq!(pulled, expected, "chapter {index}");
+        }
+
+        let mut capped = book
+            .chapter_event_iter(
+                0,
+                ChapterEventsOptions {
+                    max_items: 1,
+                    ..ChapterEventsOptions::default()
+                },
+            )
+            .expect("iterator should open");
+        assert!(matches!(capped.next(), Some(Ok(_))));
+        assert!(matches!(capped.next(), Some(Err(EpubError::Parse(_)))));
+        assert!(capped.next().is_none());
+        assert_eq!(capped.items_emitted(), 1);
+    }
+
     #[test]
     fn test_render_prep_prepare_chapter_into_streams_items() {
         let file = std::fs::File::open(
@@ -2360,20 +5085,429 @@ mod tests {
         assert!(saw_run);
     }
 
+    #[test]
+    fn test_nav_label_for_href_matches_nested_and_relative_targets() {
+        let point = |label: &str, href: &str, children| NavPoint {
+            label: label.to_string(),
+            href: href.to_string(),
+            children,
+        };
+        let nav = Navigation {
+            toc: vec![point(
+                "Part One",
+                "text/part1.xhtml",
+                vec![
+                    point("Chapter 1", "ch1.xhtml#start", Vec::with_capacity(0)),
+                    point("Chapter 1, later", "ch1.xhtml#end", Vec::with_capacity(0)),
+                ],
+            )],
+            page_list: Vec::with_capacity(0),
+            landmarks: Vec::with_capacity(0),
+        };
+        assert_eq!(
+            nav_label_for_href(&nav, "text/part1.xhtml").as_deref(),
+            Some("Part One")
+        );
+        assert_eq!(
+            nav_label_for_href(&nav, "text/ch1.xhtml").as_deref(),
+            Some("Chapter 1")
+        );
+        assert_eq!(nav_label_for_href(&nav, "text/xch1.xhtml"), None);
+    }
+
+    #[test]
+    fn test_displayable_chapter_ref_resolves_fallbacks() {
+        let item = |id: &str, href: &str, media_type: &str, fallback: Option<&str>| ManifestItem {
+            id: id.to_string(),
+            href: href.to_string(),
+            media_type: media_type.to_string(),
+            properties: None,
+            fallback: fallback.map(str::to_string),
+        };
+        let mut metadata = EpubMetadata::new();
+        metadata.manifest = vec![
+            item("plate", "plate.svg", "image/svg+xml", Some("plate-text")),
+            item("plate-text", "plate.xhtml", "application/xhtml+xml", None),
+            item("loop", "loop.svg", "image/svg+xml", Some("loop")),
+            item("quiz", "quiz.xhtml", "application/xhtml+xml", None),
+            item("ch1", "ch1.xhtml", "application/xhtml+xml", None),
+        ];
+        metadata.manifest[3].properties = Some("scripted svg".to_string());
+        let spine = Spine::from_idrefs(vec![
+            "plate".into(),
+            "loop".into(),
+            "ch1".into(),
+            "quiz".into(),
+        ]);
+        let nav = Navigation {
+            toc: vec![NavPoint {
+                label: "Plate".to_string(),
+                href: "plate.xhtml".to_string(),
+                children: Vec::with_capacity(0),
+            }],
+            page_list: Vec::with_capacity(0),
+            landmarks: Vec::with_capacity(0),
+        };
+
+        let plate = displayable_chapter_ref(&metadata, &spine, Some(&nav), 0).unwrap();
+        assert_eq!(plate.index, 0);
+        assert_eq!(plate.idref, "plate");
+        assert_eq!(plate.href, "plate.xhtml");
+        assert_eq!(plate.media_type, "application/xhtml+xml");
+        assert_eq!(plate.title.as_deref(), Some("Plate"));
+        assert!(!plate.is_scripted);
+
+        // A cyclic chain leaves the spine item as declared.
+        let looped = displayable_chapter_ref(&metadata, &spine, None, 1).unwrap();
+        assert_eq!(looped, chapter_ref(&metadata, &spine, None, 1).unwrap());
+        let ch1 = displayable_chapter_ref(&metadata, &spine, None, 2).unwrap();
+        assert_eq!(ch1, chapter_ref(&metadata, &spine, None, 2).unwrap());
+        assert!(!ch1.is_scripted);
+        let quiz = displayable_chapter_ref(&metadata, &spine, None, 3).unwrap();
+        assert!(quiz.is_scripted);
+    }
+
+    #[test]
+    fn test_contains_script_element() {
+        assert!(contains_script_element(
+            b"<head><SCRIPT src=\"a.js\"></SCRIPT></head>"
+        ));
+        assert!(contains_script_element(b"<p>x</p><script>run()</script>"));
+        assert!(contains_script_element(b"<script/>"));
+        assert!(!contains_script_element(b"<scripted>no</scripted>"));
+        assert!(!contains_script_element(b"<p>a &lt;script&gt; in text</p>"));
+        assert!(!contains_script_element(b"<noscript>fallback</noscript>"));
+    }
+
+    #[test]
+    fn test_scripted_content_summary_is_empty_for_plain_book() {
+        let mut book = EpubBook::open(
+            "tests/fixtures/Fundamental-Accessibility-Tests-Basic-Functionality-v2.0.0.epub",
+        )
+        .unwrap();
+        let summary = book.scripted_content_summary().unwrap();
+        assert!(summary.is_empty());
+        assert!(!summary.contains(0));
+        assert!(book.chapters().all(|chapter| !chapter.is_scripted));
+    }
+
+    #[test]
+    fn test_book_stats_reports_declared_language() {
+        let mut book = EpubBook::open(
+            "tests/fixtures/Fundamental-Accessibility-Tests-Basic-Functionality-v2.0.0.epub",
+        )
+        .unwrap();
+        let stats = book.stats().unwrap();
+        assert_eq!(stats.chapter_count, book.chapter_count());
+        assert_eq!(stats.declared_language, Language::from_tag(book.language()));
+        #[cfg(feature = "lang-detect")]
+        assert_eq!(
+            stats.detected_language.map(|guess| guess.language),
+            Some(Language::English)
+        );
+        #[cfg(not(feature = "lang-detect"))]
+        assert_eq!(stats.detected_language, None);
+    }
+
+    #[test]
+    fn test_book_stats_effective_language_prefers_confident_detection() {
+        let guess = |confidence| LanguageGuess {
+            language: Language::German,
+            confidence,
+        };
+        let stats = |declared, detected| BookStats {
+            chapter_count: 1,
+            declared_language: declared,
+            detected_language: detected,
+        };
+        assert_eq!(
+            stats(Some(Language::English), Some(guess(0.5))).effective_language(),
+            Some(Language::German)
+        );
+        assert_eq!(
+            stats(Some(Language::English), Some(guess(0.1))).effective_language(),
+            Some(Language::English)
+        );
+        assert_eq!(
+            stats(None, Some(guess(0.1))).effective_language(),
+            Some(Language::German)
+        );
+        assert_eq!(stats(None, None).effective_language(), None);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_position_and_stats_serde_round_trip() {
+        let position = ReadingPosition {
+            chapter_index: 3,
+            chapter_href: Some("text/ch3.xhtml".to_string()),
+            segment: None,
+            anchor: Some("sec2".to_string()),
+            fallback_offset: 120,
+        };
+        let json = serde_json::to_string(&position).unwrap();
+        assert!(json.contains("\"fallback_offset\":120"));
+        assert_eq!(
+            serde_json::from_str::<ReadingPosition>(&json).unwrap(),
+            position
+        );
+
+        let stats = BookStats {
+            chapter_count: 12,
+            declared_language: Some(Language::English),
+            detected_language: Some(LanguageGuess {
+                language: Language::German,
+                confidence: 0.5,
+            }),
+        };
+        let json = serde_json::to_string(&stats).unwrap();
+        assert!(json.contains("\"declared_language\""));
+        assert_eq!(serde_json::from_str::<BookStats>(&json).unwrap(), stats);
+    }
+
+    fn excerpt(html: &str, offset: usize, before: usize, after: usize) -> String {
+        let mut scan = ExcerptScan::new(offset, before, after);
+        let _ = scan.write_all(html.as_bytes());
+        scan.finish().expect("excerpt should build")
+    }
+
+    #[test]
+    fn test_excerpt_snaps_to_words_and_marks_cuts() {
+        let html = "<p>The quick brown fox jumps over the lazy dog.</p>";
+        // Offset 16 is the start of "fox".
+        assert_eq!(excerpt(html, 16, 8, 9), "\u{2026}brown fox jumps\u{2026}");
+        assert_eq!(excerpt(html, 16, 7, 7), "\u{2026}brown fox\u{2026}");
+        assert_eq!(
+            excerpt(html, 16, 100, 100),
+            "The quick brown fox jumps over the lazy dog."
+        );
+        assert_eq!(excerpt(html, 0, 10, 3), "The\u{2026}");
+        assert_eq!(excerpt(html, 1000, 9, 10), "\u{2026}lazy dog.");
+    }
+
+    #[test]
+    fn test_excerpt_joins_text_runs_and_respects_byte_cap() {
+        let html = "<p>Chapter <em>one</em></p><p>Begins here.</p>";
+        assert_eq!(excerpt(html, 10, 20, 20), "Chapter one Begins here.");
+
+        let long = format!("<p>{}</p>", "word ".repeat(2000));
+        let out = excerpt(&long, 5000, usize::MAX, usize::MAX);
+        assert!(out.len() <= MAX_EXCERPT_BYTES);
+        assert!(out.starts_with('\u{2026}') && out.ends_with('\u{2026}'));
+        assert!(!out.contains("wor\u{2026}") && !out.contains("\u{2026}ord"));
+    }
+
+    #[test]
+    fn test_excerpt_at_resolves_locator() {
+        let mut book = EpubBook::open(
+            "tests/fixtures/Fundamental-Accessibility-Tests-Basic-Functionality-v2.0.0.epub",
+        )
+        .unwrap();
+        let index = (0..book.chapter_count())
+            .find(|&index| book.chapter_text(index).is_ok_and(|text| text.len() > 200))
+            .expect("fixture should have a text chapter");
+        let start = book.excerpt_at(Locator::Chapter(index), 40, 40).unwrap();
+        assert!(!start.is_empty());
+        assert!(!start.starts_with('\u{2026}'));
+        assert!(start.ends_with('\u{2026}'));
+
+        let position = ReadingPosition {
+            chapter_index: index,
+            fallback_offset: 100,
+            ..ReadingPosition::default()
+        };
+        let middle = book
+            .excerpt_at(Locator::Position(position), 20, 20)
+            .unwrap();
+        assert!(middle.starts_with('\u{2026}') && middle.ends_with('\u{2026}'));
+        assert!(middle.chars().count() <= 20 + 20 + 2);
+    }
+
+    fn find_hits(html: &str, needle: &str, options: FindOptions) -> Vec<(usize, usize)> {
+        let mut hits = Vec::with_capacity(0);
+        let mut scan = FindScan::new(
+            needle,
+            options,
+            ReadingPosition::default(),
+            FindLimits::UNBOUNDED,
+            |hit| {
+                hits.push((hit.offset, hit.len));
+                Ok(true)
+            },
+        );
+        for chunk in html.as_bytes().chunks(7) {
+            if scan.write(chunk).is_err() {
+                break;
+            }
+        }
+        scan.finish().unwrap();
+        hits
+    }
+
+    #[test]
+    fn test_find_scan_matches_across_chunks_and_runs() {
+        let html = "<p>The cat sat.</p><p>Catalog <b>of</b> cats</p>";
+        let all = FindOptions::default();
+        assert_eq!(find_hits(html, "cat", all), [(4, 3), (12, 3), (21, 3)]);
+        let words = FindOptions {
+            whole_word: true,
+            ..all
+        };
+        assert_eq!(find_hits(html, "cat", words), [(4, 3)]);
+        let exact = FindOptions {
+            case_insensitive: false,
+            ..all
+        };
+        assert_eq!(find_hits(html, "Cat", exact), [(12, 3)]);
+        assert_eq!(find_hits(html, "sat. cat", all), [(8, 7)]);
+        let capped = FindOptions { max_hits: 2, ..all };
+        assert_eq!(find_hits(html, "cat", capped).len(), 2);
+        assert_eq!(find_hits("<p>aaaa</p>", "aa", all), [(0, 2), (2, 2)]);
+    }
+
+    #[test]
+    fn test_find_in_chapter_hits_resolve_to_excerpts() {
+        let mut book = EpubBook::open(
+            "tests/fixtures/Fundamental-Accessibility-Tests-Basic-Functionality-v2.0.0.epub",
+        )
+        .unwrap();
+        let index = (0..book.chapter_count())
+            .find(|&index| book.chapter_text(index).is_ok_and(|text| text.len() > 200))
+            .expect("fixture should have a text chapter");
+        let text = book.chapter_text(index).unwrap();
+        let needle = text
+            .split_whitespace()
+            .find(|word| word.len() >= 6 && word.chars().all(char::is_alphabetic))
+            .expect("chapter should have a long word")
+            .to_uppercase();
+
+        let mut hits = Vec::with_capacity(0);
+        let count = book
+            .find_in_chapter(index, &needle, FindOptions::default(), |hit| {
+                hits.push(hit);
+                Ok(())
+            })
+            .unwrap();
+        assert_eq!(count, hits.len());
+        assert!(count >= 1);
+        let hit = hits[0].clone();
+        assert_eq!(hit.position.chapter_index, index);
+        assert_eq!(hit.len, needle.chars().count());
+        let excerpt = book
+            .excerpt_at(Locator::Position(hit.position), 0, 40)
+            .unwrap();
+        assert!(excerpt.to_uppercase().starts_with(&needle));
+
+        let exact = FindOptions {
+            case_insensitive: false,
+            ..FindOptions::default()
+        };
+        let found = book
+            .find_in_chapter(index, "\u{2603}no such text", exact, |_| Ok(()))
+            .unwrap();
+        assert_eq!(found, 0);
+    }
+
+    #[test]
+    fn test_export_streams_whole_book() {
+        let mut book = EpubBook::open(
+            "tests/fixtures/Fundamental-Accessibility-Tests-Basic-Functionality-v2.0.0.epub",
+        )
+        .unwrap();
+        let mut markdown = Vec::with_capacity(0);
+        book.export(
+            ExportFormat::Markdown,
+            &mut markdown,
+            ExportOptions::default(),
+        )
+        .unwrap();
+        let markdown = String::from_utf8(markdown).unwrap();
+        assert!(markdown.lines().any(|line| line.starts_with("# ")));
+
+        let mut text = Vec::with_capacity(0);
+        book.export(ExportFormat::PlainText, &mut text, ExportOptions::default())
+            .unwrap();
+        let text = String::from_utf8(text).unwrap();
+        assert!(!text.contains("# "));
+        assert!(text.len() > 1000);
+    }
+
+    #[test]
+    fn test_extract_resources_writes_selected_entries() {
+        let dest = std::env::temp_dir().join(format!("mu-epub-extract-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dest);
+        let mut book = EpubBook::open(
+            "tests/fixtures/Fundamental-Accessibility-Tests-Basic-Functionality-v2.0.0.epub",
+        )
+        .unwrap();
+
+        let mut seen = Vec::with_capacity(0);
+        let mut on_progress = |progress: ExtractProgress<'_>| {
+            seen.push((progress.index, progress.total, progress.bytes));
+        };
+        let options = ExtractOptions::default().with_progress(&mut on_progress);
+        let report = book
+            .extract_resources(ResourceFilter::Images, &dest, options)
+            .unwrap();
+        assert_eq!(report.extracted.len(), 1);
+        assert!(report.skipped.is_empty());
+        let cover = &report.extracted[0];
+        assert_eq!(
+            cover.path,
+            dest.join("EPUB").join("images").join("cover.jpg")
+        );
+        assert_eq!(std::fs::metadata(&cover.path).unwrap().len(), 81146);
+        assert_eq!(seen, vec![(0, 1, 81146)]);
+
+        let report = book
+            .extract_resources(ResourceFilter::Images, &dest, ExtractOptions::default())
+            .unwrap();
+        assert_eq!(report.skipped[0].reason, SkipReason::Exists);
+
+        let options = ExtractOptions::default()
+            .with_overwrite(true)
+            .with_max_total_bytes(4096);
+        let report = book
+            .extract_resources(ResourceFilter::All, &dest, options)
+            .unwrap();
+        assert!(report.total_bytes() <= 4096);
+        assert!(report
+            .skipped
+            .iter()
+            .any(|skipped| skipped.reason == SkipReason::BudgetExhausted));
+        let _ = std::fs::remove_dir_all(&dest);
+
+        // A file where the image directory should be makes the write fail.
+        std::fs::create_dir_all(&dest).unwrap();
+        std::fs::write(dest.join("EPUB"), b"").unwrap();
+        let report = book
+            .extract_resources(ResourceFilter::Images, &dest, ExtractOptions::default())
+            .unwrap();
+        assert!(report.extracted.is_empty());
+        assert!(matches!(report.skipped[0].reason, SkipReason::Failed(_)));
+        let _ = std::fs::remove_dir_all(&dest);
+    }
+
     #[test]
     fn test_reading_session_resolve_locator_and_progress() {
         let chapters = vec![
             ChapterRef {
                 index: 0,
+                segment: None,
                 idref: "c1".to_string(),
                 href: "text/ch1.xhtml".to_string(),
                 media_type: "application/xhtml+xml".to_string(),
+                title: None,
+                is_scripted: false,
             },
             ChapterRef {
                 index: 1,
+                segment: None,
                 idref: "c2".to_string(),
                 href: "text/ch2.xhtml".to_string(),
                 media_type: "application/xhtml+xml".to_string(),
+                title: None,
+                is_scripted: false,
             },
         ];
         let nav = Navigation {
@@ -2394,19 +5528,222 @@ mod tests {
         assert!(session.book_progress() > 0.0);
     }
 
+    #[test]
+    fn test_split_chapters_reads_segments_through_the_zip() {
+        let file = std::fs::File::open(
+            "tests/fixtures/Fundamental-Accessibility-Tests-Basic-Functionality-v2.0.0.epub",
+        )
+        .expect("fixture should open");
+        let mut book = EpubBook::from_reader(file).expect("book should open");
+        let chapters = book
+            .split_chapters(ChapterSplitOptions {
+                min_chapter_bytes: 0,
+                max_segment_bytes: 1024,
+                max_heading_level: 2,
+            })
+            .expect("split should succeed");
+        assert!(chapters.len() > book.chapter_count());
+        let chapter = chapters
+            .iter()
+            .find(|chapter| chapter.segment().is_some_and(|s| s.index == 1))
+            .cloned()
+            .expect("some chapter should split");
+        let whole = book.read_spine_item_bytes(chapter.index).unwrap();
+        let segment = chapter.segment().unwrap();
+        assert_eq!(
+            book.chapter_segment_html(&chapter).unwrap(),
+            segment.extract(&whole).unwrap()
+        );
+
+        let mut prep = RenderPrep::new(RenderPrepOptions::default());
+        let mut segment_items = 0usize;
+        prep.prepare_chapter_ref_with(&mut book, &chapter, |_| segment_items += 1)
+            .unwrap();
+        let mut chapter_items = 0usize;
+        prep.prepare_chapter_with(&mut book, chapter.index, |_| chapter_items += 1)
+            .unwrap();
+        assert!(segment_items > 0 && segment_items < chapter_items);
+
+        #[cfg(feature = "layout")]
+        {
+            use crate::layout::LayoutEngine;
+            use crate::streaming::{ChapterPaginator, ChunkLimits};
+            let mut paginator =
+                ChapterPaginator::new(LayoutEngine::with_defaults(), ChunkLimits::default());
+            let segment_pages = book
+                .paginate_chapter(&chapter, &mut paginator, |_| {})
+                .unwrap();
+            let item = book.chapter(chapter.index).unwrap();
+            let item_pages = book
+                .paginate_chapter(&item, &mut paginator, |_| {})
+                .unwrap();
+            assert!(segment_pages >= 1 && segment_pages <= item_pages);
+        }
+
+        let mut session = ReadingSession::new(chapters.clone(), None);
+        let position = session
+            .resolve_locator(Locator::Chapter(
+                chapters.iter().position(|c| *c == chapter).unwrap(),
+            ))
+            .unwrap()
+            .position;
+        assert_eq!(position.segment, Some(1));
+        let mut restored = ReadingSession::new(chapters, None);
+        let resolved = restored
+            .resolve_locator(Locator::Position(ReadingPosition {
+                chapter_index: 0,
+                ..position
+            }))
+            .unwrap();
+        assert_eq!(resolved.chapter, chapter);
+    }
+
+    #[test]
+    fn test_chapter_index_for_href_normalizes_and_matches_scan() {
+        let file = std::fs::File::open(
+            "tests/fixtures/Fundamental-Accessibility-Tests-Basic-Functionality-v2.0.0.epub",
+        )
+        .expect("fixture should open");
+        let book = EpubBook::from_reader(file).expect("book should open");
+        let chapters: Vec<ChapterRef> = book.chapters().collect();
+        assert!(chapters.len() > 2);
+        for chapter in &chapters {
+            let first = chapters.iter().find(|c| c.href == chapter.href).unwrap();
+            assert_eq!(
+                book.chapter_index_for_href(&chapter.href),
+                Some(first.index)
+            );
+            assert_eq!(
+                book.chapter_index_for_href(&format!("./{}#frag", chapter.href)),
+                Some(first.index)
+            );
+            assert_eq!(book.chapter_by_id(&chapter.idref).unwrap(), *chapter);
+        }
+        assert_eq!(book.chapter_index_for_href("missing.xhtml"), None);
+        assert!(book.chapter_by_id("missing").is_err());
+
+        let last = chapters.last().unwrap();
+        let mut session = book.reading_session();
+        let resolved = session
+            .resolve_locator(Locator::Href(format!("x/../{}#end", last.href)))
+            .unwrap();
+        assert_eq!(resolved.chapter.index, last.index);
+        assert_eq!(resolved.fragment.as_deref(), Some("end"));
+        assert!(session
+            .resolve_locator(Locator::Href("missing.xhtml".to_string()))
+            .is_err());
+    }
+
+    #[test]
+    fn test_attached_session_reports_content_progress_and_excerpts() {
+        let file = std::fs::File::open(
+            "tests/fixtures/Fundamental-Accessibility-Tests-Basic-Functionality-v2.0.0.epub",
+        )
+        .expect("fixture should open");
+        let mut book = EpubBook::from_reader(file).expect("book should open");
+        let mut session = book.reading_session();
+        let index = (0..book.chapter_count())
+            .find(|&index| book.chapter_text(index).is_ok_and(|text| text.len() > 200))
+            .expect("fixture should have a chapter with text");
+        let mut attached = session.attach(&mut book);
+        attached.resolve_locator(Locator::Chapter(index)).unwrap();
+        let chars = attached.chapter_chars().unwrap();
+        assert!(chars > 0);
+        assert_eq!(attached.chapter_progress().unwrap(), 0.0);
+
+        let position = ReadingPosition {
+            fallback_offset: chars / 2,
+            ..attached.session().current_position()
+        };
+        attached
+            .resolve_locator(Locator::Position(position.clone()))
+            .unwrap();
+        let progress = attached.chapter_progress().unwrap();
+        assert!((progress - 0.5).abs() < 0.01, "{progress}");
+        let book_progress = attached.book_progress().unwrap();
+        assert!(book_progress > index as f32 / attached.session().chapters.len() as f32);
+        let excerpt = attached.excerpt(20, 20).unwrap();
+        assert!(!excerpt.is_empty());
+        assert_eq!(
+            excerpt,
+            book.excerpt_at(Locator::Position(position), 20, 20)
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_reading_session_history_back_and_forward() {
+        let chapters: Vec<ChapterRef> = (0..3)
+            .map(|index| ChapterRef {
+                index,
+                segment: None,
+                idref: format!("c{index}"),
+                href: format!("text/ch{index}.xhtml"),
+                media_type: "application/xhtml+xml".to_string(),
+                title: None,
+                is_scripted: false,
+            })
+            .collect();
+        let mut session = ReadingSession::new(chapters, None).with_history_capacity(2);
+        assert!(!session.can_go_back());
+        assert!(session.go_back().is_none());
+
+        session
+            .seek_position(&ReadingPosition {
+                fallback_offset: 40,
+                ..session.current_position()
+            })
+            .unwrap();
+        let start = session.current_position();
+        session
+            .resolve_locator(Locator::Href("text/ch2.xhtml#note1".to_string()))
+            .unwrap();
+        assert!(session.can_go_back());
+        let back = session.go_back().unwrap();
+        assert_eq!(back.position, start);
+        assert_eq!(back.chapter.index, 0);
+        assert!(session.can_go_forward());
+        let forward = session.go_forward().unwrap();
+        assert_eq!(forward.fragment.as_deref(), Some("note1"));
+        assert_eq!(forward.chapter.index, 2);
+
+        // A new jump drops the forward stack; restoring a position and
+        // re-resolving the current location record nothing.
+        session.go_back().unwrap();
+        session.resolve_locator(Locator::Chapter(0)).unwrap();
+        session.resolve_locator(Locator::Chapter(1)).unwrap();
+        assert!(!session.can_go_forward());
+        session
+            .resolve_locator(Locator::Position(ReadingPosition::default()))
+            .unwrap();
+        session.resolve_locator(Locator::Chapter(2)).unwrap();
+        session
+            .resolve_locator(Locator::Fragment("x".to_string()))
+            .unwrap();
+
+        // Capacity 2 keeps the two most recent departures.
+        assert_eq!(session.go_back().unwrap().chapter.index, 2);
+        assert_eq!(session.go_back().unwrap().chapter.index, 0);
+        assert!(session.go_back().is_none());
+    }
+
     #[test]
     fn test_reading_session_seek_position_out_of_bounds() {
         let chapters = vec![ChapterRef {
             index: 0,
+            segment: None,
             idref: "c1".to_string(),
             href: "text/ch1.xhtml".to_string(),
             media_type: "application/xhtml+xml".to_string(),
+            title: None,
+            is_scripted: false,
         }];
         let mut session = ReadingSession::new(chapters, None);
         let err = session
             .seek_position(&ReadingPosition {
                 chapter_index: 2,
                 chapter_href: None,
+                segment: None,
                 anchor: None,
                 fallback_offset: 0,
             })
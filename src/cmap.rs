@@ -0,0 +1,250 @@
+//! Character coverage from an OpenType/TrueType `cmap` table.
+//!
+//! Only what the font resolver needs to spot glyphs missing from subsetted
+//! embedded fonts: which Unicode scalars map to a non-zero glyph. Formats 4
+//! (BMP segments) and 12 (full-range groups) cover virtually every font in
+//! the wild. Compressed WOFF/WOFF2 payloads are not unpacked, so their
+//! coverage is reported as unknown.
+
+/// Unicode scalars a font maps to real glyphs, as sorted inclusive ranges.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub(crate) struct GlyphCoverage {
+    ranges: Vec<(u32, u32)>,
+}
+
+impl GlyphCoverage {
+    /// Coverage of `font` (an sfnt or the first face of a collection), or
+    /// `None` when it has no usable Unicode `cmap` subtable.
+    pub(crate) fn parse(font: &[u8]) -> Option<Self> {
+        let font = match read_tag(font, 0)? {
+            b"ttcf" => font.get(read_u32(font, 12)? as usize..)?,
+            _ => font,
+        };
+        let cmap = table(font, b"cmap")?;
+        let subtable = best_unicode_subtable(cmap)?;
+        let mut ranges = match read_u16(subtable, 0)? {
+            4 => parse_format4(subtable)?,
+            12 => parse_format12(subtable)?,
+            _ => return None,
+        };
+        ranges.sort_unstable();
+        let mut merged: Vec<(u32, u32)> = Vec::with_capacity(ranges.len());
+        for (start, end) in ranges {
+            match merged.last_mut() {
+                Some(last) if start <= last.1.saturating_add(1) => last.1 = last.1.max(end),
+                _ => merged.push((start, end)),
+            }
+        }
+        Some(Self { ranges: merged })
+    }
+
+    /// Whether `ch` maps to a glyph.
+    pub(crate) fn contains(&self, ch: char) -> bool {
+        let cp = ch as u32;
+        let idx = self.ranges.partition_point(|&(_, end)| end < cp);
+        self.ranges.get(idx).is_some_and(|&(start, _)| start <= cp)
+    }
+}
+
+fn read_u16(data: &[u8], offset: usize) -> Option<u16> {
+    let bytes = data.get(offset..offset.checked_add(2)?)?;
+    Some(u16::from_be_bytes([bytes[0], bytes[1]]))
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Option<u32> {
+    let bytes = data.get(offset..offset.checked_add(4)?)?;
+    Some(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+fn read_tag(data: &[u8], offset: usize) -> Option<&[u8; 4]> {
+    data.get(offset..offset.checked_add(4)?)?.try_into().ok()
+}
+
+/// Bytes of the table tagged `tag` in the sfnt `font`.
+fn table<'a>(font: &'a [u8], tag: &[u8; 4]) -> Option<&'a [u8]> {
+    let num_tables = read_u16(font, 4)? as usize;
+    (0..num_tables).find_map(|i| {
+        let record = 12 + i * 16;
+        if read_tag(font, record)? != tag {
+            return None;
+        }
+        let offset = read_u32(font, record + 8)? as usize;
+        let length = read_u32(font, record + 12)? as usize;
+        font.get(offset..offset.checked_add(length)?)
+    })
+}
+
+/// Preferred Unicode subtable: full-repertoire format 12 over BMP format 4.
+fn best_unicode_subtable(cmap: &[u8]) -> Option<&[u8]> {
+    let num_tables = read_u16(cmap, 2)? as usize;
+    let mut best: Option<(u8, &[u8])> = None;
+    for i in 0..num_tables {
+        let record = 4 + i * 8;
+        let (Some(platform), Some(encoding), Some(offset)) = (
+            read_u16(cmap, record),
+            read_u16(cmap, record + 2),
+            read_u32(cmap, record + 4),
+        ) else {
+            break;
+        };
+        let unicode = matches!((platform, encoding), (0, _) | (3, 1) | (3, 10));
+        let Some(subtable) = cmap.get(offset as usize..) else {
+            continue;
+        };
+        let rank = match read_u16(subtable, 0) {
+            Some(12) if unicode => 2,
+            Some(4) if unicode => 1,
+            _ => continue,
+        };
+        if best.is_none_or(|(best_rank, _)| rank > best_rank) {
+            best = Some((rank, subtable));
+        }
+    }
+    best.map(|(_, subtable)| subtable)
+}
+
+fn parse_format4(subtable: &[u8]) -> Option<Vec<(u32, u32)>> {
+    let seg_count = read_u16(subtable, 6)? as usize / 2;
+    let end_codes = 14;
+    let start_codes = end_codes + seg_count * 2 + 2;
+    let id_deltas = start_codes + seg_count * 2;
+    let id_range_offsets = id_deltas + seg_count * 2;
+    let mut ranges = Vec::with_capacity(seg_count);
+    for seg in 0..seg_count {
+        let end = read_u16(subtable, end_codes + seg * 2)?;
+        let start = read_u16(subtable, start_codes + seg * 2)?;
+        let delta = read_u16(subtable, id_deltas + seg * 2)?;
+        let range_offset_pos = id_range_offsets + seg * 2;
+        let range_offset = read_u16(subtable, range_offset_pos)? as usize;
+        if start > end || start == 0xFFFF {
+            continue;
+        }
+        if range_offset == 0 {
+            // Glyph = code + delta; only the code hitting glyph 0 is missing.
+            let hole = 0u16.wrapping_sub(delta);
+            if (start..=end).contains(&hole) {
+                if hole > start {
+                    ranges.push((u32::from(start), u32::from(hole) - 1));
+                }
+                if hole < end {
+                    ranges.push((u32::from(hole) + 1, u32::from(end)));
+                }
+            } else {
+                ranges.push((u32::from(start), u32::from(end)));
+            }
+            continue;
+        }
+        let mut run_start = None;
+        for code in start..=end {
+            let glyph_pos = range_offset_pos + range_offset + (code - start) as usize * 2;
+            let mapped = read_u16(subtable, glyph_pos).is_some_and(|glyph| glyph != 0);
+            match (mapped, run_start) {
+                (true, None) => run_start = Some(code),
+                (false, Some(first)) => {
+                    ranges.push((u32::from(first), u32::from(code) - 1));
+                    run_start = None;
+                }
+                _ => {}
+            }
+        }
+        if let Some(first) = run_start {
+            ranges.push((u32::from(first), u32::from(end)));
+        }
+    }
+    Some(ranges)
+}
+
+fn parse_format12(subtable: &[u8]) -> Option<Vec<(u32, u32)>> {
+    let num_groups = read_u32(subtable, 12)? as usize;
+    let mut ranges = Vec::with_capacity(num_groups.min(subtable.len() / 12));
+    for group in 0..num_groups {
+        let record = 16 + group * 12;
+        let start = read_u32(subtable, record)?;
+        let end = read_u32(subtable, record + 4)?.min(0x10FFFF);
+        let start_glyph = read_u32(subtable, record + 8)?;
+        // A group starting at glyph 0 maps its first code to .notdef.
+        let start = if start_glyph == 0 { start + 1 } else { start };
+        if start <= end {
+            ranges.push((start, end));
+        }
+    }
+    Some(ranges)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// Minimal sfnt with one `cmap` table holding `subtable` for (3, 1).
+    pub(crate) fn font_with_cmap(subtable: &[u8]) -> Vec<u8> {
+        let mut cmap = Vec::with_capacity(0);
+        cmap.extend_from_slice(&[0, 0, 0, 1, 0, 3, 0, 1, 0, 0, 0, 12]);
+        cmap.extend_from_slice(subtable);
+        let mut font = Vec::with_capacity(0);
+        font.extend_from_slice(&0x0001_0000u32.to_be_bytes());
+        font.extend_from_slice(&[0, 1, 0, 16, 0, 0, 0, 0]);
+        font.extend_from_slice(b"cmap");
+        font.extend_from_slice(&0u32.to_be_bytes());
+        font.extend_from_slice(&28u32.to_be_bytes());
+        font.extend_from_slice(&(cmap.len() as u32).to_be_bytes());
+        font.extend_from_slice(&cmap);
+        font
+    }
+
+    /// Format 4 subtable mapping each inclusive `(start, end)` by delta.
+    pub(crate) fn format4(segments: &[(u16, u16)]) -> Vec<u8> {
+        let mut segments = segments.to_vec();
+        segments.push((0xFFFF, 0xFFFF));
+        let seg_count = segments.len() as u16;
+        let words = |out: &mut Vec<u8>, values: &mut dyn Iterator<Item = u16>| {
+            for value in values {
+                out.extend_from_slice(&value.to_be_bytes());
+            }
+        };
+        let mut out = Vec::with_capacity(0);
+        words(&mut out, &mut [4, 0, 0, seg_count * 2, 0, 0, 0].into_iter());
+        words(&mut out, &mut segments.iter().map(|s| s.1));
+        words(&mut out, &mut [0].into_iter());
+        words(&mut out, &mut segments.iter().map(|s| s.0));
+        // Delta 1 maps every code to a non-zero glyph except U+FFFF.
+        words(&mut out, &mut segments.iter().map(|_| 1));
+        words(&mut out, &mut segments.iter().map(|_| 0));
+        let len = out.len() as u16;
+        out[2..4].copy_from_slice(&len.to_be_bytes());
+        out
+    }
+
+    #[test]
+    fn reads_format4_segments() {
+        let font = font_with_cmap(&format4(&[(0x20, 0x7E), (0x391, 0x3A9)]));
+        let coverage = GlyphCoverage::parse(&font).expect("cmap should parse");
+        assert!(coverage.contains('A'));
+        assert!(coverage.contains('Ω'));
+        assert!(!coverage.contains('é'));
+        assert!(!coverage.contains('\u{FFFF}'));
+    }
+
+    #[test]
+    fn reads_format12_groups() {
+        let mut subtable = Vec::with_capacity(0);
+        subtable.extend_from_slice(&[0, 12, 0, 0]);
+        subtable.extend_from_slice(&40u32.to_be_bytes());
+        subtable.extend_from_slice(&0u32.to_be_bytes());
+        subtable.extend_from_slice(&2u32.to_be_bytes());
+        for (start, end, glyph) in [(0x41u32, 0x5Au32, 1u32), (0x1F600, 0x1F64F, 30)] {
+            subtable.extend_from_slice(&start.to_be_bytes());
+            subtable.extend_from_slice(&end.to_be_bytes());
+            subtable.extend_from_slice(&glyph.to_be_bytes());
+        }
+        let coverage = GlyphCoverage::parse(&font_with_cmap(&subtable)).expect("cmap");
+        assert!(coverage.contains('Z'));
+        assert!(coverage.contains('😀'));
+        assert!(!coverage.contains('a'));
+    }
+
+    #[test]
+    fn rejects_non_sfnt_data() {
+        assert_eq!(GlyphCoverage::parse(b"wOF2\0\0\0\0"), None);
+        assert_eq!(GlyphCoverage::parse(&[]), None);
+    }
+}
@@ -4,13 +4,20 @@
 //! - Font properties: `font-size`, `font-family`, `font-weight`, `font-style`
 //! - Text: `text-align`, `line-height`
 //! - Spacing: `margin-top`, `margin-bottom`
+//! - Lists: `list-style-type`
+//! - Sizing: `width`, `height` (px or percentage)
+//! - Floats: `float` (`left`/`right`)
 //! - Selectors: tag, class, and inline `style` attributes
+//! - Custom properties: `--name` declared on `:root`/`html`/`body`, read
+//!   with `var()`
 //!
 //! Complex selectors, floats, positioning, and grid are out of scope.
 
 extern crate alloc;
 
-use alloc::string::String;
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::string::{String, ToString};
 use alloc::vec::Vec;
 
 use crate::error::EpubError;
@@ -33,6 +40,16 @@ pub enum FontSize {
     Px(f32),
     /// Relative size in em units
     Em(f32),
+    /// Absolute size in points (1/72 in, i.e. 4/3 CSS px)
+    Pt(f32),
+    /// Relative to the root (configured base) font size
+    Rem(f32),
+    /// Percentage of the parent font size
+    Percent(f32),
+    /// Percentage of the viewport width (`vw`)
+    Vw(f32),
+    /// Percentage of the viewport height (`vh`)
+    Vh(f32),
 }
 
 /// Font weight
@@ -72,6 +89,322 @@ pub enum TextAlign {
     Justify,
 }
 
+/// Float placement
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub enum Float {
+    /// In normal flow
+    #[default]
+    None,
+    /// Pinned to the left edge; text wraps on its right
+    Left,
+    /// Pinned to the right edge; text wraps on its left
+    Right,
+}
+
+impl Float {
+    /// Parse a CSS `float` value or an HTML `align` attribute on `<img>`.
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "none" => Some(Self::None),
+            "left" => Some(Self::Left),
+            "right" => Some(Self::Right),
+            _ => None,
+        }
+    }
+}
+
+/// Box generation from CSS `display`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub enum Display {
+    /// Flows within a line
+    #[default]
+    Inline,
+    /// Starts a new block; also used for flex, grid, and table containers
+    Block,
+    /// Atomic box within a line
+    InlineBlock,
+    /// Block with a list marker
+    ListItem,
+    /// Not rendered, together with all descendants
+    None,
+}
+
+impl Display {
+    /// Parse a CSS `display` value; `contents` and unknown values are unset.
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "none" => Some(Self::None),
+            "inline" => Some(Self::Inline),
+            "block" | "flex" | "grid" | "table" | "flow-root" => Some(Self::Block),
+            "inline-block" | "inline-flex" | "inline-grid" | "inline-table" => {
+                Some(Self::InlineBlock)
+            }
+            "list-item" => Some(Self::ListItem),
+            _ => None,
+        }
+    }
+}
+
+/// Forced break before a box, from `break-before` and its legacy and
+/// prefixed spellings
+///
+/// Layouts without columns or regions treat [`BreakBefore::Column`] and
+/// [`BreakBefore::Region`] as page breaks.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub enum BreakBefore {
+    /// No forced break; also used for `avoid`
+    #[default]
+    Auto,
+    /// Start a new page (`page`, `always`, `left`, `right`, `recto`, `verso`)
+    Page,
+    /// Start a new column
+    Column,
+    /// Start a new region, from CSS Regions as used by fixed and hybrid
+    /// layouts
+    Region,
+}
+
+impl BreakBefore {
+    /// Parse a `break-before` value; `property` picks how `always` is read
+    /// for `page-break-before`, `column-break-before`, and
+    /// `region-break-before` (with or without a `-webkit-`/`-epub-` prefix).
+    pub fn parse(property: &str, value: &str) -> Option<Self> {
+        let property = property
+            .strip_prefix("-webkit-")
+            .or_else(|| property.strip_prefix("-epub-"))
+            .unwrap_or(property);
+        let value = value.trim().to_lowercase();
+        let always = match property {
+            "break-before" => None,
+            "page-break-before" => Some(Self::Page),
+            "column-break-before" => Some(Self::Column),
+            "region-break-before" => Some(Self::Region),
+            _ => return None,
+        };
+        match value.as_str() {
+            "auto" | "avoid" | "avoid-page" | "avoid-column" | "avoid-region" => Some(Self::Auto),
+            "always" => always,
+            "page" | "left" | "right" | "recto" | "verso" if always != Some(Self::Column) => {
+                Some(Self::Page)
+            }
+            "column" if always.is_none() => Some(Self::Column),
+            "region" if always.is_none() => Some(Self::Region),
+            _ => None,
+        }
+    }
+}
+
+/// List item marker style
+#[derive(Clone, Debug, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub enum ListStyleType {
+    /// `1.`, `2.`, `3.`
+    #[default]
+    Decimal,
+    /// `a.`, `b.`, ... `z.`, `aa.`
+    LowerAlpha,
+    /// `A.`, `B.`, ... `Z.`, `AA.`
+    UpperAlpha,
+    /// `i.`, `ii.`, `iii.`
+    LowerRoman,
+    /// `I.`, `II.`, `III.`
+    UpperRoman,
+    /// Filled bullet `•`
+    Disc,
+    /// Hollow bullet `◦`
+    Circle,
+    /// Square bullet `▪`
+    Square,
+    /// No marker
+    None,
+    /// Literal marker string, e.g. `list-style-type: "→"`
+    Custom(String),
+}
+
+impl ListStyleType {
+    /// Parse a CSS `list-style-type` value.
+    pub fn from_css(value: &str) -> Option<Self> {
+        let value = value.trim();
+        if let Some(quoted) = value
+            .strip_prefix('"')
+            .and_then(|v| v.strip_suffix('"'))
+            .or_else(|| value.strip_prefix('\'').and_then(|v| v.strip_suffix('\'')))
+        {
+            return Some(Self::Custom(quoted.to_string()));
+        }
+        match value.to_lowercase().as_str() {
+            "decimal" => Some(Self::Decimal),
+            "lower-alpha" | "lower-latin" => Some(Self::LowerAlpha),
+            "upper-alpha" | "upper-latin" => Some(Self::UpperAlpha),
+            "lower-roman" => Some(Self::LowerRoman),
+            "upper-roman" => Some(Self::UpperRoman),
+            "disc" => Some(Self::Disc),
+            "circle" => Some(Self::Circle),
+            "square" => Some(Self::Square),
+            "none" => Some(Self::None),
+            _ => None,
+        }
+    }
+
+    /// Parse an HTML `type` attribute on `<ol>`/`<ul>`/`<li>`.
+    ///
+    /// Single-letter values are case-sensitive (`a` vs `A`).
+    pub fn from_type_attr(value: &str) -> Option<Self> {
+        match value.trim() {
+            "1" => Some(Self::Decimal),
+            "a" => Some(Self::LowerAlpha),
+            "A" => Some(Self::UpperAlpha),
+            "i" => Some(Self::LowerRoman),
+            "I" => Some(Self::UpperRoman),
+            other => match other.to_lowercase().as_str() {
+                "disc" => Some(Self::Disc),
+                "circle" => Some(Self::Circle),
+                "square" => Some(Self::Square),
+                "none" => Some(Self::None),
+                _ => None,
+            },
+        }
+    }
+
+    /// Whether markers count items rather than repeat a symbol.
+    pub fn is_ordered(&self) -> bool {
+        matches!(
+            self,
+            Self::Decimal
+                | Self::LowerAlpha
+                | Self::UpperAlpha
+                | Self::LowerRoman
+                | Self::UpperRoman
+        )
+    }
+
+    /// Marker text for the item numbered `ordinal`.
+    ///
+    /// Ordered styles append `.` to [`Self::counter`]; unordered styles
+    /// return their symbol.
+    pub fn marker(&self, ordinal: i32) -> String {
+        let counter = self.counter(ordinal);
+        if self.is_ordered() {
+            format!("{}.", counter)
+        } else {
+            counter
+        }
+    }
+
+    /// Counter text for `ordinal`, without the marker's trailing `.`.
+    ///
+    /// Alphabetic and roman styles fall back to decimal outside their
+    /// range (below 1, or above 3999 for roman).
+    pub fn counter(&self, ordinal: i32) -> String {
+        match self {
+            Self::Decimal => ordinal.to_string(),
+            Self::LowerAlpha | Self::UpperAlpha if ordinal >= 1 => {
+                let mut letters = alpha_ordinal(ordinal as u32);
+                if matches!(self, Self::UpperAlpha) {
+                    letters.make_ascii_uppercase();
+                }
+                letters
+            }
+            Self::LowerRoman | Self::UpperRoman if (1..=3999).contains(&ordinal) => {
+                let mut numeral = roman_ordinal(ordinal as u32);
+                if matches!(self, Self::LowerRoman) {
+                    numeral.make_ascii_lowercase();
+                }
+                numeral
+            }
+            Self::LowerAlpha | Self::UpperAlpha | Self::LowerRoman | Self::UpperRoman => {
+                ordinal.to_string()
+            }
+            Self::Disc => "\u{2022}".to_string(),
+            Self::Circle => "\u{25E6}".to_string(),
+            Self::Square => "\u{25AA}".to_string(),
+            Self::None => String::with_capacity(0),
+            Self::Custom(marker) => marker.clone(),
+        }
+    }
+}
+
+/// Bijective base-26 letters: 1 → `a`, 26 → `z`, 27 → `aa`.
+fn alpha_ordinal(mut n: u32) -> String {
+    let mut letters = Vec::with_capacity(4);
+    while n > 0 {
+        n -= 1;
+        letters.push(b'a' + (n % 26) as u8);
+        n /= 26;
+    }
+    letters.iter().rev().map(|&b| b as char).collect()
+}
+
+/// Uppercase roman numeral for `1..=3999`.
+fn roman_ordinal(mut n: u32) -> String {
+    const NUMERALS: [(u32, &str); 13] = [
+        (1000, "M"),
+        (900, "CM"),
+        (500, "D"),
+        (400, "CD"),
+        (100, "C"),
+        (90, "XC"),
+        (50, "L"),
+        (40, "XL"),
+        (10, "X"),
+        (9, "IX"),
+        (5, "V"),
+        (4, "IV"),
+        (1, "I"),
+    ];
+    let mut out = String::with_capacity(8);
+    for (value, numeral) in NUMERALS {
+        while n >= value {
+            out.push_str(numeral);
+            n -= value;
+        }
+    }
+    out
+}
+
+/// Box dimension for `width`/`height`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Dimension {
+    /// Absolute size in whole pixels
+    Px(u32),
+    /// Percentage of the containing block
+    Percent(u32),
+}
+
+impl Dimension {
+    /// Parse a CSS length (`300px`, `50%`) or an HTML dimension attribute
+    /// (bare `300`). `auto` and unsupported units yield `None`.
+    pub fn parse(value: &str) -> Option<Self> {
+        let value = value.trim().to_lowercase();
+        let number = |s: &str| {
+            s.trim()
+                .parse::<f32>()
+                .ok()
+                .filter(|v| v.is_finite() && *v >= 0.0)
+                .map(|v| (v + 0.5) as u32)
+        };
+        if let Some(pct) = value.strip_suffix('%') {
+            number(pct).map(Self::Percent)
+        } else if let Some(px) = value.strip_suffix("px") {
+            number(px).map(Self::Px)
+        } else {
+            number(&value).map(Self::Px)
+        }
+    }
+
+    /// Resolve to pixels, taking percentages of `reference_px`.
+    pub fn resolve(self, reference_px: u32) -> u32 {
+        match self {
+            Self::Px(px) => px,
+            Self::Percent(pct) => (reference_px as u64 * pct as u64 / 100) as u32,
+        }
+    }
+}
+
 /// A set of CSS property values
 ///
 /// All fields are optional — `None` means "not specified" (inherit from parent
@@ -94,6 +427,18 @@ pub struct CssStyle {
     pub margin_top: Option<f32>,
     /// Bottom margin in pixels
     pub margin_bottom: Option<f32>,
+    /// List item marker style
+    pub list_style_type: Option<ListStyleType>,
+    /// Box width
+    pub width: Option<Dimension>,
+    /// Box height
+    pub height: Option<Dimension>,
+    /// Float placement
+    pub float: Option<Float>,
+    /// Box generation; `Display::None` hides the element and its subtree
+    pub display: Option<Display>,
+    /// Forced break before the box
+    pub break_before: Option<BreakBefore>,
 }
 
 impl CssStyle {
@@ -112,6 +457,41 @@ impl CssStyle {
             && self.line_height.is_none()
             && self.margin_top.is_none()
             && self.margin_bottom.is_none()
+            && self.list_style_type.is_none()
+            && self.width.is_none()
+            && self.height.is_none()
+            && self.float.is_none()
+            && self.display.is_none()
+            && self.break_before.is_none()
+    }
+
+    /// Only the inherited (text) properties, as passed down to children
+    pub fn inherited(&self) -> CssStyle {
+        CssStyle {
+            font_size: self.font_size,
+            font_family: self.font_family.clone(),
+            font_weight: self.font_weight,
+            font_style: self.font_style,
+            text_align: self.text_align,
+            line_height: self.line_height.clone(),
+            list_style_type: self.list_style_type.clone(),
+            ..CssStyle::default()
+        }
+    }
+
+    /// Only the non-inherited (box) properties, which apply to the element
+    /// that declares them
+    pub fn box_properties(&self) -> CssStyle {
+        CssStyle {
+            margin_top: self.margin_top,
+            margin_bottom: self.margin_bottom,
+            width: self.width,
+            height: self.height,
+            float: self.float,
+            display: self.display,
+            break_before: self.break_before,
+            ..CssStyle::default()
+        }
     }
 
     /// Merge another style into this one (other's values take precedence)
@@ -140,9 +520,50 @@ impl CssStyle {
         if other.margin_bottom.is_some() {
             self.margin_bottom = other.margin_bottom;
         }
+        if other.list_style_type.is_some() {
+            self.list_style_type = other.list_style_type.clone();
+        }
+        if other.width.is_some() {
+            self.width = other.width;
+        }
+        if other.height.is_some() {
+            self.height = other.height;
+        }
+        if other.float.is_some() {
+            self.float = other.float;
+        }
+        if other.display.is_some() {
+            self.display = other.display;
+        }
+        if other.break_before.is_some() {
+            self.break_before = other.break_before;
+        }
     }
 }
 
+/// Supported properties that children inherit from their parent
+///
+/// Every other supported property (margins, `width`, `height`, `float`,
+/// `display`, `break-before`) is a box property that applies only to the element declaring
+/// it; see [`CssStyle::inherited`] and [`CssStyle::box_properties`].
+pub const INHERITED_PROPERTIES: [&str; 7] = [
+    "font-size",
+    "font-family",
+    "font-weight",
+    "font-style",
+    "text-align",
+    "line-height",
+    "list-style-type",
+];
+
+/// Check if a property is inherited by child elements
+pub fn is_inherited_property(property: &str) -> bool {
+    let property = property.trim();
+    INHERITED_PROPERTIES
+        .iter()
+        .any(|inherited| inherited.eq_ignore_ascii_case(property))
+}
+
 /// A CSS selector (subset)
 #[derive(Clone, Debug, PartialEq)]
 #[non_exhaustive]
@@ -153,17 +574,99 @@ pub enum CssSelector {
     Class(String),
     /// Tag + class selector (e.g., `p.intro`)
     TagClass(String, String),
+    /// Selector narrowed by a structural pseudo-class (e.g.,
+    /// `p:first-child`); a `None` base matches any element
+    Structural(Option<Box<CssSelector>>, PseudoClass),
+}
+
+/// Structural pseudo-class (subset)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum PseudoClass {
+    /// `:first-child`
+    FirstChild,
+    /// `:last-child`
+    LastChild,
+    /// `:first-of-type`
+    FirstOfType,
+}
+
+impl PseudoClass {
+    fn parse(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "first-child" => Some(Self::FirstChild),
+            "last-child" => Some(Self::LastChild),
+            "first-of-type" => Some(Self::FirstOfType),
+            _ => None,
+        }
+    }
+}
+
+/// Where an element sits among its element siblings
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ElementPosition {
+    /// No element sibling precedes it
+    pub first_child: bool,
+    /// No element sibling follows it
+    pub last_child: bool,
+    /// No preceding sibling has the same tag
+    pub first_of_type: bool,
 }
 
 impl CssSelector {
+    /// Selector specificity as `(classes, tags)`; higher sorts later in
+    /// the cascade
+    pub fn specificity(&self) -> (u8, u8) {
+        match self {
+            CssSelector::Tag(_) => (0, 1),
+            CssSelector::Class(_) => (1, 0),
+            CssSelector::TagClass(_, _) => (1, 1),
+            CssSelector::Structural(base, _) => {
+                let (classes, tags) = base.as_ref().map_or((0, 0), |base| base.specificity());
+                (classes + 1, tags)
+            }
+        }
+    }
+
     /// Check if this selector matches a given tag name and class list
+    ///
+    /// Structural selectors never match here; use [`Self::matches_at`].
     pub fn matches(&self, tag: &str, classes: &[&str]) -> bool {
+        self.matches_at(tag, classes, None)
+    }
+
+    /// Check if this selector matches an element at a known sibling position
+    pub fn matches_at(
+        &self,
+        tag: &str,
+        classes: &[&str],
+        position: Option<&ElementPosition>,
+    ) -> bool {
         match self {
             CssSelector::Tag(t) => t == tag,
             CssSelector::Class(c) => classes.contains(&c.as_str()),
             CssSelector::TagClass(t, c) => t == tag && classes.contains(&c.as_str()),
+            CssSelector::Structural(base, pseudo) => {
+                let Some(position) = position else {
+                    return false;
+                };
+                let at_position = match pseudo {
+                    PseudoClass::FirstChild => position.first_child,
+                    PseudoClass::LastChild => position.last_child,
+                    PseudoClass::FirstOfType => position.first_of_type,
+                };
+                at_position
+                    && base
+                        .as_ref()
+                        .is_none_or(|base| base.matches_at(tag, classes, Some(position)))
+            }
         }
     }
+
+    /// Check if this selector needs `last_child` position info
+    pub fn uses_last_child(&self) -> bool {
+        matches!(self, CssSelector::Structural(_, PseudoClass::LastChild))
+    }
 }
 
 /// A single CSS rule (selector + declarations)
@@ -171,8 +674,204 @@ impl CssSelector {
 pub struct CssRule {
     /// The selector for this rule
     pub selector: CssSelector,
-    /// The style declarations
+    /// The normal style declarations
     pub style: CssStyle,
+    /// Declarations marked `!important`
+    pub important: CssStyle,
+}
+
+/// Declarations of one block split by importance
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct StyleDeclarations {
+    /// Normal declarations
+    pub normal: CssStyle,
+    /// Declarations marked `!important`
+    pub important: CssStyle,
+}
+
+impl StyleDeclarations {
+    /// Check if no declaration is set
+    pub fn is_empty(&self) -> bool {
+        self.normal.is_empty() && self.important.is_empty()
+    }
+
+    /// Normal declarations overridden by important ones
+    pub fn cascaded(&self) -> CssStyle {
+        let mut style = self.normal.clone();
+        style.merge(&self.important);
+        style
+    }
+}
+
+/// Cascade the rules of `sheets` that match an element, plus its inline style
+///
+/// Precedence, lowest first: normal stylesheet declarations (by selector
+/// specificity, then stylesheet and rule order), normal inline declarations,
+/// important stylesheet declarations (same ordering), important inline
+/// declarations.
+///
+/// Structural selectors match only when `position` is known.
+pub fn cascade(
+    sheets: &[Stylesheet],
+    tag: &str,
+    classes: &[&str],
+    position: Option<&ElementPosition>,
+    inline: Option<&StyleDeclarations>,
+) -> CssStyle {
+    let mut matched: Vec<&CssRule> = sheets
+        .iter()
+        .flat_map(|sheet| sheet.rules.iter())
+        .filter(|rule| rule.selector.matches_at(tag, classes, position))
+        .collect();
+    matched.sort_by_key(|rule| rule.selector.specificity());
+
+    let mut style = CssStyle::new();
+    for rule in &matched {
+        style.merge(&rule.style);
+    }
+    if let Some(inline) = inline {
+        style.merge(&inline.normal);
+    }
+    for rule in &matched {
+        style.merge(&rule.important);
+    }
+    if let Some(inline) = inline {
+        style.merge(&inline.important);
+    }
+    style
+}
+
+/// Maximum nesting of `var()` references followed while resolving a value
+const MAX_VAR_DEPTH: usize = 16;
+
+/// Custom properties (`--name: value`) in scope for a chapter
+///
+/// Only declarations on the root scope (`:root`, `html`, `body`) are
+/// collected; per-element overrides are not tracked. Values are stored
+/// unresolved and substituted when a declaration reads them with `var()`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CustomProperties {
+    entries: Vec<(String, String)>,
+}
+
+impl CustomProperties {
+    /// Create an empty property set
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Raw value of the property `name` (including the leading `--`)
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.entries
+            .iter()
+            .find(|(known, _)| known == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Declare `name`, replacing any earlier value
+    pub fn set(&mut self, name: &str, value: &str) {
+        match self.entries.iter_mut().find(|(known, _)| known == name) {
+            Some(entry) => entry.1 = value.to_string(),
+            None => self.entries.push((name.to_string(), value.to_string())),
+        }
+    }
+
+    /// Number of declared properties
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Check if no properties are declared
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Replace every `var()` in `value` with its resolved value
+    ///
+    /// Returns `None` when a reference is undefined without a fallback,
+    /// cyclic, or nested deeper than the resolution limit, which makes the
+    /// declaration invalid.
+    pub fn substitute(&self, value: &str) -> Option<String> {
+        let mut stack = Vec::with_capacity(0);
+        self.substitute_inner(value, &mut stack)
+    }
+
+    fn substitute_inner<'a>(&'a self, value: &str, stack: &mut Vec<&'a str>) -> Option<String> {
+        let mut out = String::with_capacity(value.len());
+        let mut rest = value;
+        while let Some(start) = find_var_call(rest) {
+            out.push_str(&rest[..start]);
+            let args_start = start + "var(".len();
+            let args_len = matching_paren(&rest[args_start..])?;
+            let args = &rest[args_start..args_start + args_len];
+            let (name, fallback) = match top_level_comma(args) {
+                Some(comma) => (args[..comma].trim(), Some(&args[comma + 1..])),
+                None => (args.trim(), None),
+            };
+            if !name.starts_with("--") || stack.len() >= MAX_VAR_DEPTH {
+                return None;
+            }
+            let defined = self
+                .entries
+                .iter()
+                .find(|(known, _)| known == name)
+                .filter(|(known, _)| !stack.contains(&known.as_str()));
+            let resolved = match defined {
+                Some((known, raw)) => {
+                    stack.push(known.as_str());
+                    let resolved = self.substitute_inner(raw, stack);
+                    stack.pop();
+                    resolved
+                }
+                None => None,
+            };
+            let resolved = match (resolved, fallback) {
+                (Some(resolved), _) => resolved,
+                (None, Some(fallback)) => self.substitute_inner(fallback.trim(), stack)?,
+                (None, None) => return None,
+            };
+            out.push_str(resolved.trim());
+            rest = &rest[args_start + args_len + 1..];
+        }
+        out.push_str(rest);
+        Some(out)
+    }
+}
+
+/// Byte offset of the next `var(` call (ASCII case-insensitive)
+fn find_var_call(value: &str) -> Option<usize> {
+    value
+        .as_bytes()
+        .windows(4)
+        .position(|window| window.eq_ignore_ascii_case(b"var("))
+}
+
+/// Length of `s` up to the `)` closing an already-open parenthesis
+fn matching_paren(s: &str) -> Option<usize> {
+    let mut depth = 0usize;
+    for (i, ch) in s.char_indices() {
+        match ch {
+            '(' => depth += 1,
+            ')' if depth == 0 => return Some(i),
+            ')' => depth -= 1,
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Byte offset of the first comma outside nested parentheses
+fn top_level_comma(s: &str) -> Option<usize> {
+    let mut depth = 0usize;
+    for (i, ch) in s.char_indices() {
+        match ch {
+            '(' => depth += 1,
+            ')' => depth = depth.saturating_sub(1),
+            ',' if depth == 0 => return Some(i),
+            _ => {}
+        }
+    }
+    None
 }
 
 /// A parsed CSS stylesheet
@@ -180,6 +879,9 @@ pub struct CssRule {
 pub struct Stylesheet {
     /// All rules in document order
     pub rules: Vec<CssRule>,
+    /// Custom properties in scope after this stylesheet: those it was parsed
+    /// with plus its own root-scope declarations
+    pub custom_properties: CustomProperties,
 }
 
 impl Stylesheet {
@@ -190,15 +892,10 @@ impl Stylesheet {
 
     /// Resolve the computed style for an element given its tag and classes
     ///
-    /// Applies matching rules in document order (later rules override).
+    /// Applies matching rules by specificity, then document order (later
+    /// rules override), with `!important` declarations applied last.
     pub fn resolve(&self, tag: &str, classes: &[&str]) -> CssStyle {
-        let mut style = CssStyle::new();
-        for rule in &self.rules {
-            if rule.selector.matches(tag, classes) {
-                style.merge(&rule.style);
-            }
-        }
-        style
+        cascade(core::slice::from_ref(self), tag, classes, None, None)
     }
 
     /// Get the number of rules
@@ -217,7 +914,50 @@ impl Stylesheet {
 /// Handles the v1 subset: tag selectors, class selectors, tag.class selectors,
 /// and the supported property set.
 pub fn parse_stylesheet(css: &str) -> Result<Stylesheet, EpubError> {
+    parse_stylesheet_with_properties(css, &CustomProperties::new())
+}
+
+/// Parse a CSS stylesheet with custom properties already in scope
+///
+/// Root-scope custom properties declared anywhere in `css` are added to
+/// `inherited` before any `var()` is substituted, so declaration order
+/// within the sheet does not matter. Pass the previous stylesheet's
+/// [`Stylesheet::custom_properties`] to chain a chapter's stylesheets.
+pub fn parse_stylesheet_with_properties(
+    css: &str,
+    inherited: &CustomProperties,
+) -> Result<Stylesheet, EpubError> {
+    let mut properties = inherited.clone();
+    for_each_rule(css, |selector, declarations| {
+        if is_root_scope(selector) {
+            collect_custom_properties(declarations, &mut properties);
+        }
+        Ok(())
+    })?;
+
     let mut stylesheet = Stylesheet::new();
+    for_each_rule(css, |selector_str, declarations| {
+        let selector = parse_selector(selector_str)?;
+        let StyleDeclarations { normal, important } =
+            parse_declarations(declarations, &properties)?;
+        if !normal.is_empty() || !important.is_empty() {
+            stylesheet.rules.push(CssRule {
+                selector,
+                style: normal,
+                important,
+            });
+        }
+        Ok(())
+    })?;
+    stylesheet.custom_properties = properties;
+    Ok(stylesheet)
+}
+
+/// Call `f` with the selector and declaration block of each rule
+fn for_each_rule<'a, F>(css: &'a str, mut f: F) -> Result<(), EpubError>
+where
+    F: FnMut(&'a str, &'a str) -> Result<(), EpubError>,
+{
     let mut pos = 0;
     let bytes = css.as_bytes();
 
@@ -239,34 +979,64 @@ pub fn parse_stylesheet(css: &str) -> Result<Stylesheet, EpubError> {
             continue;
         }
 
-        // Parse selector
-        let selector = parse_selector(selector_str)?;
-
         // Find closing brace
         let brace_end = match css[brace_start + 1..].find('}') {
             Some(i) => brace_start + 1 + i,
             None => return Err(EpubError::Css("Unclosed CSS rule block".into())),
         };
 
-        // Parse declarations
-        let declarations = &css[brace_start + 1..brace_end];
-        let style = parse_declarations(declarations)?;
-
-        if !style.is_empty() {
-            stylesheet.rules.push(CssRule { selector, style });
-        }
+        f(selector_str, &css[brace_start + 1..brace_end])?;
 
         pos = brace_end + 1;
     }
 
-    Ok(stylesheet)
+    Ok(())
+}
+
+/// Check if any selector in a comma-separated list targets the root scope
+fn is_root_scope(selector: &str) -> bool {
+    selector.split(',').any(|part| {
+        let part = part.trim();
+        part == ":root" || part.eq_ignore_ascii_case("html") || part.eq_ignore_ascii_case("body")
+    })
+}
+
+/// Record the `--name: value` declarations of a block
+fn collect_custom_properties(declarations: &str, properties: &mut CustomProperties) {
+    for decl in declarations.split(';') {
+        if let Some((name, value)) = decl.split_once(':') {
+            let name = name.trim();
+            if name.starts_with("--") {
+                properties.set(name, strip_important(value).0);
+            }
+        }
+    }
 }
 
 /// Parse an inline `style` attribute value into a `CssStyle`
 ///
-/// Example: `"font-weight: bold; margin-top: 10px"`
+/// Example: `"font-weight: bold; margin-top: 10px"`. `!important`
+/// declarations override normal ones; use [`parse_inline_declarations`] to
+/// keep them apart for [`cascade`].
 pub fn parse_inline_style(style_attr: &str) -> Result<CssStyle, EpubError> {
-    parse_declarations(style_attr)
+    Ok(parse_declarations(style_attr, &CustomProperties::new())?.cascaded())
+}
+
+/// Parse an inline `style` attribute, substituting `var()` from `properties`
+pub fn parse_inline_style_with_properties(
+    style_attr: &str,
+    properties: &CustomProperties,
+) -> Result<CssStyle, EpubError> {
+    Ok(parse_declarations(style_attr, properties)?.cascaded())
+}
+
+/// Parse an inline `style` attribute, keeping `!important` declarations
+/// separate
+pub fn parse_inline_declarations(
+    style_attr: &str,
+    properties: &CustomProperties,
+) -> Result<StyleDeclarations, EpubError> {
+    parse_declarations(style_attr, properties)
 }
 
 // -- Internal parsing helpers -------------------------------------------------
@@ -294,6 +1064,16 @@ fn skip_whitespace_and_comments(css: &str, mut pos: usize) -> usize {
 fn parse_selector(s: &str) -> Result<CssSelector, EpubError> {
     let s = s.trim();
 
+    if let Some((base, pseudo)) = s.rsplit_once(':') {
+        if let Some(pseudo) = PseudoClass::parse(pseudo.trim()) {
+            let base = match base.trim() {
+                "" | "*" => None,
+                base => Some(Box::new(parse_selector(base)?)),
+            };
+            return Ok(CssSelector::Structural(base, pseudo));
+        }
+    }
+
     if let Some(class) = s.strip_prefix('.') {
         // Class selector
         if class.is_empty() {
@@ -318,8 +1098,15 @@ fn parse_selector(s: &str) -> Result<CssSelector, EpubError> {
 }
 
 /// Parse CSS declarations (the part inside `{ ... }`)
-fn parse_declarations(declarations: &str) -> Result<CssStyle, EpubError> {
-    let mut style = CssStyle::new();
+///
+/// `var()` references are substituted from `properties`; declarations whose
+/// references cannot be resolved are dropped. Declarations ending in
+/// `!important` go to [`StyleDeclarations::important`].
+fn parse_declarations(
+    declarations: &str,
+    properties: &CustomProperties,
+) -> Result<StyleDeclarations, EpubError> {
+    let mut declared = StyleDeclarations::default();
 
     for decl in declarations.split(';') {
         let decl = decl.trim();
@@ -333,7 +1120,23 @@ fn parse_declarations(declarations: &str) -> Result<CssStyle, EpubError> {
         };
 
         let property = decl[..colon_pos].trim().to_lowercase();
-        let value = decl[colon_pos + 1..].trim();
+        if property.starts_with("--") {
+            continue; // Custom property declaration, collected separately
+        }
+        let substituted;
+        let (mut value, important) = strip_important(&decl[colon_pos + 1..]);
+        let style = if important {
+            &mut declared.important
+        } else {
+            &mut declared.normal
+        };
+        if find_var_call(value).is_some() {
+            substituted = match properties.substitute(value) {
+                Some(resolved) => resolved,
+                None => continue, // Invalid at computed-value time, skip
+            };
+            value = substituted.trim();
+        }
 
         match property.as_str() {
             "font-size" => {
@@ -378,6 +1181,30 @@ fn parse_declarations(declarations: &str) -> Result<CssStyle, EpubError> {
             "margin-bottom" => {
                 style.margin_bottom = parse_px_value(value);
             }
+            "list-style-type" => {
+                style.list_style_type = ListStyleType::from_css(value);
+            }
+            "width" => {
+                style.width = Dimension::parse(value);
+            }
+            "height" => {
+                style.height = Dimension::parse(value);
+            }
+            "float" => {
+                style.float = Float::parse(value);
+            }
+            "display" => {
+                style.display = Display::parse(value);
+            }
+            "break-before"
+            | "page-break-before"
+            | "column-break-before"
+            | "-webkit-column-break-before"
+            | "region-break-before"
+            | "-webkit-region-break-before"
+            | "-epub-region-break-before" => {
+                style.break_before = BreakBefore::parse(&property, value);
+            }
             "margin" => {
                 // Shorthand: only handle single-value case for now
                 if let Some(val) = parse_px_value(value) {
@@ -391,26 +1218,52 @@ fn parse_declarations(declarations: &str) -> Result<CssStyle, EpubError> {
         }
     }
 
-    Ok(style)
+    Ok(declared)
+}
+
+/// Split a trailing `!important` off a declaration value
+fn strip_important(value: &str) -> (&str, bool) {
+    let value = value.trim();
+    if let Some(bang) = value.rfind('!') {
+        if value[bang + 1..].trim().eq_ignore_ascii_case("important") {
+            return (value[..bang].trim_end(), true);
+        }
+    }
+    (value, false)
 }
 
-/// Parse a font-size value (px or em)
+/// Parse a font-size value (px, pt, em, rem, %, vw, or vh)
 fn parse_font_size(value: &str) -> Option<FontSize> {
     let value = value.trim().to_lowercase();
-    if let Some(px_str) = value.strip_suffix("px") {
-        px_str.trim().parse::<f32>().ok().map(FontSize::Px)
-    } else if let Some(em_str) = value.strip_suffix("em") {
-        em_str.trim().parse::<f32>().ok().map(FontSize::Em)
-    } else {
-        None
-    }
+    let (number, unit) = ["px", "pt", "rem", "em", "%", "vw", "vh"]
+        .into_iter()
+        .find_map(|unit| Some((value.strip_suffix(unit)?, unit)))?;
+    let number = number.trim().parse::<f32>().ok().filter(|n| *n >= 0.0)?;
+    Some(match unit {
+        "px" => FontSize::Px(number),
+        "pt" => FontSize::Pt(number),
+        "rem" => FontSize::Rem(number),
+        "em" => FontSize::Em(number),
+        "%" => FontSize::Percent(number),
+        "vw" => FontSize::Vw(number),
+        _ => FontSize::Vh(number),
+    })
 }
 
-/// Parse a line-height value (px or unitless multiplier)
+/// Parse a line-height value (px, unitless multiplier, em, or %)
 fn parse_line_height(value: &str) -> Option<LineHeight> {
     let value = value.trim().to_lowercase();
     if let Some(px_str) = value.strip_suffix("px") {
         px_str.trim().parse::<f32>().ok().map(LineHeight::Px)
+    } else if let Some(percent_str) = value.strip_suffix('%') {
+        let percent = percent_str.trim().parse::<f32>().ok()?;
+        Some(LineHeight::Multiplier(percent / 100.0))
+    } else if let Some(em_str) = value.strip_suffix("em") {
+        em_str
+            .trim()
+            .parse::<f32>()
+            .ok()
+            .map(LineHeight::Multiplier)
     } else if value == "normal" {
         None // Use default
     } else {
@@ -541,6 +1394,164 @@ mod tests {
         assert_eq!(ss.len(), 3);
     }
 
+    #[test]
+    fn test_parse_custom_properties() {
+        let css = r#"
+            p { font-size: var(--body-size); margin-top: var(--gap, 4px); }
+            :root { --body-size: var(--base); --base: 18px; }
+            h1 { font-family: var(--missing); font-weight: bold; }
+        "#;
+        let ss = parse_stylesheet(css).unwrap();
+        assert_eq!(ss.custom_properties.get("--base"), Some("18px"));
+        assert_eq!(ss.rules[0].style.font_size, Some(FontSize::Px(18.0)));
+        assert_eq!(ss.rules[0].style.margin_top, Some(4.0));
+        // Unresolvable references drop only their declaration.
+        assert_eq!(ss.rules[1].style.font_family, None);
+        assert_eq!(ss.rules[1].style.font_weight, Some(FontWeight::Bold));
+    }
+
+    #[test]
+    fn test_custom_properties_reject_cycles_and_chain_sheets() {
+        let ss = parse_stylesheet(":root { --a: var(--b); --b: var(--a); --c: 2em; }").unwrap();
+        assert_eq!(ss.custom_properties.substitute("var(--a)"), None);
+        assert_eq!(
+            ss.custom_properties
+                .substitute("var(--a, var(--c))")
+                .as_deref(),
+            Some("2em")
+        );
+
+        let next = parse_stylesheet_with_properties(
+            "body { --c: 3em; } p { font-size: var(--c); }",
+            &ss.custom_properties,
+        )
+        .unwrap();
+        assert_eq!(next.rules[0].style.font_size, Some(FontSize::Em(3.0)));
+
+        let inline =
+            parse_inline_style_with_properties("font-size: VAR( --c )", &next.custom_properties)
+                .unwrap();
+        assert_eq!(inline.font_size, Some(FontSize::Em(3.0)));
+    }
+
+    #[test]
+    fn test_parse_important_declarations() {
+        let ss = parse_stylesheet(
+            "p { font-family: Serif !important; font-size: 12px ! IMPORTANT; text-align: left; }",
+        )
+        .unwrap();
+        let rule = &ss.rules[0];
+        assert_eq!(rule.style.text_align, Some(TextAlign::Left));
+        assert_eq!(rule.style.font_family, None);
+        assert_eq!(rule.important.font_family.as_deref(), Some("Serif"));
+        assert_eq!(rule.important.font_size, Some(FontSize::Px(12.0)));
+    }
+
+    #[test]
+    fn test_cascade_precedence() {
+        let ss = parse_stylesheet(
+            ".note { text-align: right; } p { text-align: center; font-family: A !important; } \
+             p { font-weight: normal !important; }",
+        )
+        .unwrap();
+        // The class selector beats the later, less specific tag selector.
+        let style = ss.resolve("p", &["note"]);
+        assert_eq!(style.text_align, Some(TextAlign::Right));
+
+        let inline = parse_inline_declarations(
+            "font-family: B; text-align: justify; font-weight: bold !important",
+            &CustomProperties::new(),
+        )
+        .unwrap();
+        let style = cascade(
+            core::slice::from_ref(&ss),
+            "p",
+            &["note"],
+            None,
+            Some(&inline),
+        );
+        assert_eq!(style.text_align, Some(TextAlign::Justify));
+        assert_eq!(style.font_family.as_deref(), Some("A"));
+        assert_eq!(style.font_weight, Some(FontWeight::Bold));
+        assert_eq!(
+            parse_inline_style("font-style: italic !important; font-style: normal")
+                .unwrap()
+                .font_style,
+            Some(FontStyle::Italic)
+        );
+    }
+
+    #[test]
+    fn test_inheritance_table_matches_style_split() {
+        for (property, value) in [
+            ("font-size", "12px"),
+            ("font-family", "Serif"),
+            ("font-weight", "bold"),
+            ("font-style", "italic"),
+            ("text-align", "center"),
+            ("line-height", "1.5"),
+            ("list-style-type", "disc"),
+            ("margin-top", "4px"),
+            ("margin-bottom", "4px"),
+            ("width", "50%"),
+            ("height", "10px"),
+            ("float", "left"),
+            ("display", "none"),
+            ("break-before", "page"),
+        ] {
+            let style = parse_inline_style(&format!("{}: {}", property, value)).unwrap();
+            assert!(!style.is_empty(), "{} should parse", property);
+            assert_eq!(
+                is_inherited_property(property),
+                !style.inherited().is_empty(),
+                "{}",
+                property
+            );
+            assert_eq!(
+                is_inherited_property(property),
+                style.box_properties().is_empty(),
+                "{}",
+                property
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_structural_pseudo_classes() {
+        let ss = parse_stylesheet(
+            "p:first-child { font-weight: bold; } .note:LAST-CHILD { font-style: italic; } \
+             :first-of-type { text-align: center; } a:hover { text-align: right; }",
+        )
+        .unwrap();
+        assert_eq!(
+            ss.rules[0].selector,
+            CssSelector::Structural(
+                Some(Box::new(CssSelector::Tag("p".into()))),
+                PseudoClass::FirstChild
+            )
+        );
+        assert_eq!(ss.rules[0].selector.specificity(), (1, 1));
+        assert!(ss.rules[1].selector.uses_last_child());
+        assert_eq!(
+            ss.rules[2].selector,
+            CssSelector::Structural(None, PseudoClass::FirstOfType)
+        );
+
+        let first = ElementPosition {
+            first_child: true,
+            first_of_type: true,
+            last_child: false,
+        };
+        assert!(ss.rules[0].selector.matches_at("p", &[], Some(&first)));
+        assert!(!ss.rules[0].selector.matches_at("div", &[], Some(&first)));
+        assert!(!ss.rules[0].selector.matches("p", &[]));
+        assert!(!ss.rules[1]
+            .selector
+            .matches_at("p", &["note"], Some(&first)));
+        // Unsupported pseudo-classes keep matching nothing.
+        assert!(!ss.rules[3].selector.matches_at("a", &[], Some(&first)));
+    }
+
     #[test]
     fn test_parse_font_size_px() {
         let css = "p { font-size: 16px; }";
@@ -548,6 +1559,12 @@ mod tests {
         assert_eq!(ss.rules[0].style.font_size, Some(FontSize::Px(16.0)));
     }
 
+    #[test]
+    fn test_parse_font_size_pt() {
+        let ss = parse_stylesheet("p { font-size: 12pt; }").unwrap();
+        assert_eq!(ss.rules[0].style.font_size, Some(FontSize::Pt(12.0)));
+    }
+
     #[test]
     fn test_parse_font_size_em() {
         let css = "p { font-size: 1.5em; }";
@@ -555,6 +1572,25 @@ mod tests {
         assert_eq!(ss.rules[0].style.font_size, Some(FontSize::Em(1.5)));
     }
 
+    #[test]
+    fn test_parse_font_size_relative_units() {
+        let ss = parse_stylesheet(
+            "p { font-size: 1.25rem; } h1 { font-size: 150%; } h2 { font-size: 4vw; } \
+             h3 { font-size: 2.5VH; line-height: 120%; } h4 { font-size: -1em; }",
+        )
+        .unwrap();
+        assert_eq!(ss.rules[0].style.font_size, Some(FontSize::Rem(1.25)));
+        assert_eq!(ss.rules[1].style.font_size, Some(FontSize::Percent(150.0)));
+        assert_eq!(ss.rules[2].style.font_size, Some(FontSize::Vw(4.0)));
+        assert_eq!(ss.rules[3].style.font_size, Some(FontSize::Vh(2.5)));
+        assert_eq!(
+            ss.rules[3].style.line_height,
+            Some(LineHeight::Multiplier(1.2))
+        );
+        // Negative sizes are invalid, leaving the last rule empty.
+        assert_eq!(ss.len(), 4);
+    }
+
     #[test]
     fn test_parse_font_family() {
         let css = "p { font-family: 'Georgia'; }";
@@ -601,10 +1637,37 @@ mod tests {
 
     #[test]
     fn test_unknown_properties_ignored() {
-        let css = "p { color: red; font-weight: bold; display: flex; }";
+        let css = "p { color: red; font-weight: bold; display: contents; }";
         let ss = parse_stylesheet(css).unwrap();
         assert_eq!(ss.rules[0].style.font_weight, Some(FontWeight::Bold));
-        // color and display are silently ignored
+        // color and unsupported display values are silently ignored
+        assert_eq!(ss.rules[0].style.display, None);
+    }
+
+    #[test]
+    fn test_parse_break_before_spellings() {
+        for (declaration, expected) in [
+            ("break-before: page", Some(BreakBefore::Page)),
+            ("break-before: column", Some(BreakBefore::Column)),
+            ("break-before: region", Some(BreakBefore::Region)),
+            ("break-before: avoid", Some(BreakBefore::Auto)),
+            ("page-break-before: always", Some(BreakBefore::Page)),
+            ("page-break-before: right", Some(BreakBefore::Page)),
+            ("column-break-before: always", Some(BreakBefore::Column)),
+            (
+                "-webkit-column-break-before: always",
+                Some(BreakBefore::Column),
+            ),
+            (
+                "-webkit-region-break-before: always",
+                Some(BreakBefore::Region),
+            ),
+            ("page-break-before: column", None),
+            ("column-break-before: page", None),
+        ] {
+            let style = parse_inline_style(declaration).unwrap();
+            assert_eq!(style.break_before, expected, "{}", declaration);
+        }
     }
 
     #[test]
@@ -785,6 +1848,12 @@ mod tests {
             font_family: Some("Arial".into()),
             line_height: Some(LineHeight::Px(20.0)),
             margin_bottom: Some(5.0),
+            list_style_type: Some(ListStyleType::Disc),
+            width: Some(Dimension::Px(100)),
+            height: None,
+            float: Some(Float::Left),
+            display: Some(Display::Block),
+            break_before: Some(BreakBefore::Page),
         };
         let overlay = CssStyle {
             font_weight: Some(FontWeight::Normal),
@@ -795,6 +1864,12 @@ mod tests {
             font_family: Some("Georgia".into()),
             line_height: Some(LineHeight::Multiplier(1.5)),
             margin_bottom: Some(15.0),
+            list_style_type: Some(ListStyleType::LowerRoman),
+            width: Some(Dimension::Percent(50)),
+            height: Some(Dimension::Px(40)),
+            float: Some(Float::Right),
+            display: Some(Display::None),
+            break_before: Some(BreakBefore::Column),
         };
         base.merge(&overlay);
 
@@ -807,6 +1882,12 @@ mod tests {
         assert_eq!(base.font_family, Some("Georgia".into()));
         assert_eq!(base.line_height, Some(LineHeight::Multiplier(1.5)));
         assert_eq!(base.margin_bottom, Some(15.0));
+        assert_eq!(base.list_style_type, Some(ListStyleType::LowerRoman));
+        assert_eq!(base.width, Some(Dimension::Percent(50)));
+        assert_eq!(base.height, Some(Dimension::Px(40)));
+        assert_eq!(base.float, Some(Float::Right));
+        assert_eq!(base.display, Some(Display::None));
+        assert_eq!(base.break_before, Some(BreakBefore::Column));
     }
 
     #[test]
@@ -956,4 +2037,65 @@ mod tests {
         assert_eq!(ss.rules[0].style.font_weight, Some(FontWeight::Bold));
         assert_eq!(ss.rules[1].style.font_style, Some(FontStyle::Italic));
     }
+
+    #[test]
+    fn test_parse_list_style_type() {
+        let ss = parse_stylesheet(
+            "ol.toc { list-style-type: upper-roman; } ul { list-style-type: \"-\"; }",
+        )
+        .unwrap();
+        assert_eq!(
+            ss.resolve("ol", &["toc"]).list_style_type,
+            Some(ListStyleType::UpperRoman)
+        );
+        assert_eq!(
+            ss.resolve("ul", &[]).list_style_type,
+            Some(ListStyleType::Custom("-".into()))
+        );
+        assert_eq!(ListStyleType::from_css("inherit"), None);
+        assert_eq!(
+            ListStyleType::from_type_attr("A"),
+            Some(ListStyleType::UpperAlpha)
+        );
+        assert_eq!(
+            ListStyleType::from_type_attr("a"),
+            Some(ListStyleType::LowerAlpha)
+        );
+    }
+
+    #[test]
+    fn test_list_markers() {
+        assert_eq!(ListStyleType::Decimal.marker(5), "5.");
+        assert_eq!(ListStyleType::LowerAlpha.marker(1), "a.");
+        assert_eq!(ListStyleType::UpperAlpha.marker(28), "AB.");
+        assert_eq!(ListStyleType::LowerRoman.marker(1994), "mcmxciv.");
+        assert_eq!(ListStyleType::UpperRoman.marker(4), "IV.");
+        assert_eq!(ListStyleType::LowerRoman.marker(0), "0.");
+        assert_eq!(ListStyleType::Circle.marker(3), "\u{25E6}");
+        assert_eq!(ListStyleType::None.marker(3), "");
+        assert!(!ListStyleType::Square.is_ordered());
+        assert_eq!(ListStyleType::LowerRoman.counter(12), "xii");
+        assert_eq!(ListStyleType::LowerRoman.counter(4000), "4000");
+    }
+
+    #[test]
+    fn test_parse_dimensions() {
+        let style = parse_inline_style("width: 50%; height: 120px").unwrap();
+        assert_eq!(style.width, Some(Dimension::Percent(50)));
+        assert_eq!(style.height, Some(Dimension::Px(120)));
+        assert_eq!(Dimension::parse("300"), Some(Dimension::Px(300)));
+        assert_eq!(Dimension::parse("auto"), None);
+        assert_eq!(Dimension::parse("-4px"), None);
+        assert_eq!(Dimension::Percent(50).resolve(460), 230);
+        assert_eq!(Dimension::Px(80).resolve(460), 80);
+    }
+
+    #[test]
+    fn test_parse_float() {
+        let ss =
+            parse_stylesheet("img.pull { float: Right; } img.inline { float: inherit; }").unwrap();
+        assert_eq!(ss.resolve("img", &["pull"]).float, Some(Float::Right));
+        assert_eq!(ss.resolve("img", &["inline"]).float, None);
+        assert_eq!(Float::parse("left"), Some(Float::Left));
+    }
 }
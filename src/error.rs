@@ -8,10 +8,12 @@ extern crate alloc;
 use alloc::boxed::Box;
 use alloc::format;
 use alloc::string::{String, ToString};
+use alloc::vec::Vec;
 use core::fmt;
 
 /// Stable processing phases for typed EPUB failures.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[non_exhaustive]
 pub enum ErrorPhase {
     /// EPUB open/bootstrap work (container + OPF discovery).
@@ -39,7 +41,7 @@ impl fmt::Display for ErrorPhase {
 }
 
 /// Typed actual-vs-limit payload for hard-cap failures.
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct ErrorLimitContext {
     /// Stable limit field name (e.g. `max_css_bytes`).
     pub kind: Box<str>,
@@ -61,7 +63,7 @@ impl ErrorLimitContext {
 }
 
 /// Rich optional context for typed phase errors.
-#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
 pub struct PhaseErrorContext {
     /// Optional archive path context.
     pub path: Option<Box<str>>,
@@ -86,7 +88,7 @@ pub struct PhaseErrorContext {
 }
 
 /// Typed error with explicit processing phase and context.
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct PhaseError {
     /// Stable processing phase.
     pub phase: ErrorPhase,
@@ -111,7 +113,7 @@ impl PhaseError {
 }
 
 /// Top-level error type for mu-epub operations
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 #[non_exhaustive]
 pub enum EpubError {
     /// Typed phase-aware error with structured context.
@@ -140,6 +142,9 @@ pub enum EpubError {
         /// Missing manifest `id` referenced by spine `idref`.
         idref: String,
     },
+    /// Package document has duplicate or contradictory declarations
+    /// (strict mode only; lenient opens resolve them last-wins).
+    OpfConflicts(Vec<crate::metadata::OpfConflict>),
     /// Chapter content could not be decoded as UTF-8
     ChapterNotUtf8 {
         /// Chapter href/path in the EPUB archive.
@@ -168,7 +173,8 @@ pub enum EpubError {
 }
 
 /// Kinds of limits that can be exceeded.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[non_exhaustive]
 pub enum LimitKind {
     /// File size limit.
@@ -183,6 +189,54 @@ pub enum LimitKind {
     CssSize,
     /// Font count/size limit.
     FontLimit,
+    /// Attributes-per-element limit.
+    AttributeCount,
+    /// Entity expansion count limit.
+    EntityExpansion,
+}
+
+impl ErrorPhase {
+    /// Compact numeric code for this phase (stable across releases).
+    pub const fn code(&self) -> u16 {
+        match self {
+            Self::Open => 1,
+            Self::Parse => 2,
+            Self::Style => 3,
+            Self::Layout => 4,
+            Self::Render => 5,
+        }
+    }
+}
+
+impl EpubError {
+    /// Compact numeric error code (stable across releases).
+    ///
+    /// Intended for constrained targets that log diagnostics over RTT/serial
+    /// without string formatting. The hundreds digit groups the error family:
+    ///
+    /// - `1xx`: typed phase errors (`100 + ErrorPhase::code()`)
+    /// - `2xx`: ZIP errors (`200 + ZipErrorKind::code()`)
+    /// - `3xx`: parse/structure errors
+    /// - `4xx`: lookup errors (chapter/manifest/encoding)
+    /// - `5xx`: hard limits (`500 + LimitKind::code()`)
+    /// - `6xx`: caller-provided buffer errors
+    pub const fn code(&self) -> u16 {
+        match self {
+            EpubError::Phase(err) => 100 + err.phase.code(),
+            EpubError::Zip(kind) => 200 + kind.code(),
+            EpubError::Parse(_) => 300,
+            EpubError::InvalidEpub(_) => 301,
+            EpubError::Navigation(_) => 302,
+            EpubError::Css(_) => 303,
+            EpubError::Io(_) => 304,
+            EpubError::OpfConflicts(_) => 305,
+            EpubError::ChapterOutOfBounds { .. } => 400,
+            EpubError::ManifestItemMissing { .. } => 401,
+            EpubError::ChapterNotUtf8 { .. } => 402,
+            EpubError::LimitExceeded { kind, .. } => 500 + kind.code(),
+            EpubError::BufferTooSmall { .. } => 600,
+        }
+    }
 }
 
 impl fmt::Display for EpubError {
@@ -201,6 +255,13 @@ impl fmt::Display for EpubError {
             EpubError::Navigation(msg) => write!(f, "Navigation error: {}", msg),
             EpubError::Css(msg) => write!(f, "CSS error: {}", msg),
             EpubError::Io(msg) => write!(f, "I/O error: {}", msg),
+            EpubError::OpfConflicts(conflicts) => {
+                write!(f, "Package document has {} conflict(s)", conflicts.len())?;
+                for conflict in conflicts {
+                    write!(f, "; {}", conflict)?;
+                }
+                Ok(())
+            }
             EpubError::ChapterOutOfBounds {
                 index,
                 chapter_count,
@@ -247,6 +308,22 @@ impl fmt::Display for EpubError {
     }
 }
 
+impl LimitKind {
+    /// Compact numeric code for this limit kind (stable across releases).
+    pub const fn code(&self) -> u16 {
+        match self {
+            LimitKind::FileSize => 1,
+            LimitKind::MemoryBudget => 2,
+            LimitKind::EventCount => 3,
+            LimitKind::NestingDepth => 4,
+            LimitKind::CssSize => 5,
+            LimitKind::FontLimit => 6,
+            LimitKind::AttributeCount => 7,
+            LimitKind::EntityExpansion => 8,
+        }
+    }
+}
+
 impl fmt::Display for LimitKind {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -256,12 +333,14 @@ impl fmt::Display for LimitKind {
             LimitKind::NestingDepth => write!(f, "Nesting depth"),
             LimitKind::CssSize => write!(f, "CSS size"),
             LimitKind::FontLimit => write!(f, "Font limit"),
+            LimitKind::AttributeCount => write!(f, "Attribute count"),
+            LimitKind::EntityExpansion => write!(f, "Entity expansions"),
         }
     }
 }
 
 /// ZIP-specific error variants
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 #[non_exhaustive]
 pub enum ZipErrorKind {
     /// File not found in archive
@@ -286,6 +365,31 @@ pub enum ZipErrorKind {
     InvalidMimetype(String),
     /// ZIP64 structures are present but unsupported
     UnsupportedZip64,
+    /// Entry inflates beyond [`ZipLimits::max_compression_ratio`](crate::ZipLimits::max_compression_ratio)
+    SuspiciousCompressionRatio,
+    /// Reads exceeded [`ZipLimits::max_total_decompressed`](crate::ZipLimits::max_total_decompressed)
+    DecompressionBudgetExceeded,
+}
+
+impl ZipErrorKind {
+    /// Compact numeric code for this ZIP error (stable across releases).
+    pub const fn code(&self) -> u16 {
+        match self {
+            ZipErrorKind::FileNotFound => 1,
+            ZipErrorKind::InvalidFormat => 2,
+            ZipErrorKind::UnsupportedCompression => 3,
+            ZipErrorKind::DecompressError => 4,
+            ZipErrorKind::CrcMismatch => 5,
+            ZipErrorKind::IoError => 6,
+            ZipErrorKind::CentralDirFull => 7,
+            ZipErrorKind::BufferTooSmall => 8,
+            ZipErrorKind::FileTooLarge => 9,
+            ZipErrorKind::InvalidMimetype(_) => 10,
+            ZipErrorKind::UnsupportedZip64 => 11,
+            ZipErrorKind::SuspiciousCompressionRatio => 12,
+            ZipErrorKind::DecompressionBudgetExceeded => 13,
+        }
+    }
 }
 
 /// Public ZIP error type alias used across the crate API.
@@ -305,6 +409,12 @@ impl fmt::Display for ZipErrorKind {
             ZipErrorKind::FileTooLarge => write!(f, "file too large"),
             ZipErrorKind::InvalidMimetype(msg) => write!(f, "invalid mimetype: {}", msg),
             ZipErrorKind::UnsupportedZip64 => write!(f, "ZIP64 is not supported"),
+            ZipErrorKind::SuspiciousCompressionRatio => {
+                write!(f, "suspicious compression ratio")
+            }
+            ZipErrorKind::DecompressionBudgetExceeded => {
+                write!(f, "decompression budget exceeded")
+            }
         }
     }
 }
@@ -315,9 +425,101 @@ impl std::error::Error for EpubError {}
 #[cfg(feature = "std")]
 impl std::error::Error for ZipErrorKind {}
 
+#[cfg(feature = "defmt")]
+impl defmt::Format for ZipErrorKind {
+    fn format(&self, f: defmt::Formatter<'_>) {
+        match self {
+            ZipErrorKind::InvalidMimetype(msg) => {
+                defmt::write!(
+                    f,
+                    "Z{=u16} invalid mimetype: {=str}",
+                    self.code(),
+                    msg.as_str()
+                )
+            }
+            _ => defmt::write!(f, "Z{=u16}", self.code()),
+        }
+    }
+}
+
+#[cfg(feature = "defmt")]
+impl defmt::Format for EpubError {
+    fn format(&self, f: defmt::Formatter<'_>) {
+        let code = self.code();
+        match self {
+            EpubError::Phase(err) => {
+                defmt::write!(f, "E{=u16} [{=str}]: {=str}", code, err.code, &*err.message)
+            }
+            EpubError::Zip(kind) => defmt::write!(f, "E{=u16} {}", code, kind),
+            EpubError::Parse(msg)
+            | EpubError::InvalidEpub(msg)
+            | EpubError::Navigation(msg)
+            | EpubError::Css(msg)
+            | EpubError::Io(msg) => defmt::write!(f, "E{=u16}: {=str}", code, msg.as_str()),
+            EpubError::ChapterOutOfBounds {
+                index,
+                chapter_count,
+            } => defmt::write!(f, "E{=u16} {=usize}/{=usize}", code, index, chapter_count),
+            EpubError::ManifestItemMissing { idref } => {
+                defmt::write!(f, "E{=u16} idref={=str}", code, idref.as_str())
+            }
+            EpubError::OpfConflicts(conflicts) => {
+                defmt::write!(f, "E{=u16} conflicts={=usize}", code, conflicts.len())
+            }
+            EpubError::ChapterNotUtf8 { href } => {
+                defmt::write!(f, "E{=u16} href={=str}", code, href.as_str())
+            }
+            EpubError::LimitExceeded { actual, limit, .. } => {
+                defmt::write!(f, "E{=u16} {=usize} > {=usize}", code, actual, limit)
+            }
+            EpubError::BufferTooSmall {
+                required, provided, ..
+            } => defmt::write!(
+                f,
+                "E{=u16} need {=usize} have {=usize}",
+                code,
+                required,
+                provided
+            ),
+        }
+    }
+}
+
 impl From<crate::tokenizer::TokenizeError> for EpubError {
     fn from(err: crate::tokenizer::TokenizeError) -> Self {
-        EpubError::Parse(err.to_string())
+        use crate::tokenizer::TokenizeError;
+        match err {
+            TokenizeError::LimitExceeded {
+                kind,
+                actual,
+                limit,
+                ..
+            } => {
+                let kind = match kind {
+                    "max_attributes" => LimitKind::AttributeCount,
+                    "max_entity_expansions" => LimitKind::EntityExpansion,
+                    "max_nesting" => LimitKind::NestingDepth,
+                    _ => LimitKind::EventCount,
+                };
+                EpubError::LimitExceeded {
+                    kind,
+                    actual,
+                    limit,
+                    path: None,
+                }
+            }
+            err => EpubError::Parse(err.to_string()),
+        }
+    }
+}
+
+impl From<crate::streaming::ScratchSizeError> for EpubError {
+    fn from(err: crate::streaming::ScratchSizeError) -> Self {
+        EpubError::BufferTooSmall {
+            required: err.required,
+            provided: err.provided,
+            context: format!("scratch.{}", err.buffer.name()),
+        }
     }
 }
 
@@ -356,6 +558,55 @@ mod tests {
         assert_eq!(format!("{:?}", kind), "FileNotFound");
     }
 
+    #[test]
+    fn test_numeric_error_codes_group_by_family() {
+        assert_eq!(EpubError::Parse("x".into()).code(), 300);
+        assert_eq!(EpubError::Zip(ZipErrorKind::CrcMismatch).code(), 205);
+        assert_eq!(
+            EpubError::Phase(PhaseError::new(ErrorPhase::Style, "STYLE_LIMIT", "x")).code(),
+            103
+        );
+        assert_eq!(
+            EpubError::LimitExceeded {
+                kind: LimitKind::NestingDepth,
+                actual: 2,
+                limit: 1,
+                path: None,
+            }
+            .code(),
+            504
+        );
+        assert_eq!(
+            EpubError::ChapterOutOfBounds {
+                index: 3,
+                chapter_count: 1,
+            }
+            .code(),
+            400
+        );
+    }
+
+    #[test]
+    fn test_tokenize_limit_maps_to_typed_limit() {
+        let err: EpubError = crate::tokenizer::TokenizeError::LimitExceeded {
+            kind: "max_attributes",
+            actual: 9,
+            limit: 8,
+            offset: 120,
+        }
+        .into();
+        assert_eq!(err.code(), 507);
+        assert!(matches!(
+            err,
+            EpubError::LimitExceeded {
+                kind: LimitKind::AttributeCount,
+                actual: 9,
+                limit: 8,
+                ..
+            }
+        ));
+    }
+
     #[test]
     fn test_invalid_mimetype_error() {
         let err = EpubError::Zip(ZipErrorKind::InvalidMimetype("wrong content type".into()));
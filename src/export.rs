@@ -0,0 +1,593 @@
+//! Whole-book export to plain text or CommonMark.
+//!
+//! [`EpubBook::export`](crate::EpubBook::export) streams every displayable
+//! spine item through the style pipeline and feeds the styled events to an
+//! `ExportWriter`, which turns blocks into lines as they close. Only the
+//! current block is buffered, so memory stays flat regardless of book size.
+
+use std::io::{self, Write};
+
+use crate::css::ListStyleType;
+use crate::render_prep::{RenderPrepOptions, StyledEvent, StyledEventOrRun, StyledRun};
+
+/// Output format for [`EpubBook::export`](crate::EpubBook::export).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// UTF-8 text with blank lines between blocks. Print pages start with a
+    /// form feed.
+    #[default]
+    PlainText,
+    /// CommonMark with headings, emphasis, lists, block quotes, images, and
+    /// links preserved.
+    Markdown,
+}
+
+/// Options for [`EpubBook::export`](crate::EpubBook::export).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ExportOptions {
+    /// Style preparation options used for every chapter.
+    pub render: RenderPrepOptions,
+    /// Mark where the book's print page-list starts each page.
+    pub page_markers: bool,
+}
+
+impl Default for ExportOptions {
+    fn default() -> Self {
+        Self {
+            render: RenderPrepOptions::default(),
+            page_markers: true,
+        }
+    }
+}
+
+impl ExportOptions {
+    /// Enable or disable print page markers.
+    pub fn with_page_markers(mut self, page_markers: bool) -> Self {
+        self.page_markers = page_markers;
+        self
+    }
+}
+
+/// Markdown emphasis currently open in the block buffer.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+enum Emphasis {
+    #[default]
+    None,
+    Italic,
+    Bold,
+    BoldItalic,
+}
+
+impl Emphasis {
+    fn of(run: &StyledRun, in_heading: bool) -> Self {
+        let bold = run.style.weight >= 700 && !in_heading;
+        match (bold, run.style.italic) {
+            (true, true) => Self::BoldItalic,
+            (true, false) => Self::Bold,
+            (false, true) => Self::Italic,
+            (false, false) => Self::None,
+        }
+    }
+
+    fn delimiter(self) -> &'static str {
+        match self {
+            Self::None => "",
+            Self::Italic => "*",
+            Self::Bold => "**",
+            Self::BoldItalic => "***",
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+struct ListLevel {
+    style: ListStyleType,
+    next: i32,
+    /// Marker width plus its trailing space, for continuation lines.
+    width: usize,
+}
+
+#[derive(Clone, Debug, Default)]
+struct OpenLink {
+    href: String,
+    /// `[` has been written for the current block.
+    started: bool,
+    /// Close after the next run (renumbered footnote markers).
+    single_run: bool,
+}
+
+/// Streaming converter from styled chapter events to text output.
+pub(crate) struct ExportWriter<W: Write> {
+    out: W,
+    format: ExportFormat,
+    error: Option<io::Error>,
+    /// `(anchor id, label)` print page starts in the current chapter.
+    page_anchors: Vec<(String, String)>,
+    pending_pages: Vec<String>,
+    line: String,
+    /// Last character of inline text in the current block.
+    last_char: Option<char>,
+    pending_break: bool,
+    emphasis: Emphasis,
+    link: Option<OpenLink>,
+    heading: Option<u8>,
+    quote_depth: u8,
+    /// Quote depth of the last written block.
+    written_quote_depth: u8,
+    lists: Vec<ListLevel>,
+    item_marker: Option<String>,
+    /// Separate the next block from the previous one with a blank line.
+    blank_before: bool,
+    wrote_any: bool,
+}
+
+impl<W: Write> ExportWriter<W> {
+    pub(crate) fn new(format: ExportFormat, out: W) -> Self {
+        Self {
+            out,
+            format,
+            error: None,
+            page_anchors: Vec::with_capacity(0),
+            pending_pages: Vec::with_capacity(0),
+            line: String::with_capacity(0),
+            last_char: None,
+            pending_break: false,
+            emphasis: Emphasis::None,
+            link: None,
+            heading: None,
+            quote_depth: 0,
+            written_quote_depth: 0,
+            lists: Vec::with_capacity(0),
+            item_marker: None,
+            blank_before: false,
+            wrote_any: false,
+        }
+    }
+
+    /// Start a chapter whose print pages begin at `page_anchors`.
+    pub(crate) fn begin_chapter(&mut self, page_anchors: Vec<(String, String)>) {
+        self.page_anchors = page_anchors;
+    }
+
+    /// Close any open block and reset nesting left unbalanced by the chapter.
+    pub(crate) fn end_chapter(&mut self) -> io::Result<()> {
+        self.end_block();
+        self.emit_pending_pages();
+        self.heading = None;
+        self.quote_depth = 0;
+        self.lists.clear();
+        self.item_marker = None;
+        self.link = None;
+        self.blank_before = self.wrote_any;
+        match self.error.take() {
+            Some(err) => Err(err),
+            None => Ok(()),
+        }
+    }
+
+    /// Flush the underlying writer.
+    pub(crate) fn finish(mut self) -> io::Result<()> {
+        if let Some(err) = self.error.take() {
+            return Err(err);
+        }
+        self.out.flush()
+    }
+
+    /// Consume one styled item.
+    pub(crate) fn push(&mut self, item: StyledEventOrRun) {
+        if self.error.is_some() {
+            return;
+        }
+        match item {
+            StyledEventOrRun::Run(run) => self.push_run(&run),
+            StyledEventOrRun::Event(event) => self.push_event(event),
+        }
+    }
+
+    fn markdown(&self) -> bool {
+        self.format == ExportFormat::Markdown
+    }
+
+    fn push_run(&mut self, run: &StyledRun) {
+        let emphasis = if self.markdown() {
+            Emphasis::of(run, self.heading.is_some())
+        } else {
+            Emphasis::None
+        };
+        let text = if self.markdown() {
+            escape_markdown(&run.text)
+        } else {
+            run.text.clone()
+        };
+        self.push_text(&text, emphasis);
+        if self.link.as_ref().is_some_and(|link| link.single_run) {
+            self.close_link();
+        }
+    }
+
+    fn push_event(&mut self, event: StyledEvent) {
+        match event {
+            StyledEvent::ParagraphStart
+            | StyledEvent::ParagraphEnd
+            | StyledEvent::FigureStart
+            | StyledEvent::FigureEnd
+            | StyledEvent::CaptionStart
+            | StyledEvent::CaptionEnd => self.end_block(),
+            StyledEvent::HeadingStart(level) => {
+                self.end_block();
+                self.heading = Some(level.clamp(1, 6));
+            }
+            StyledEvent::HeadingEnd(_) => {
+                self.end_block();
+                self.heading = None;
+            }
+            StyledEvent::ListStart { style, start } => {
+                self.end_block();
+                self.lists.push(ListLevel {
+                    style,
+                    next: start,
+                    width: 0,
+                });
+            }
+            StyledEvent::ListEnd => {
+                self.end_block();
+                self.lists.pop();
+                self.blank_before |= self.lists.is_empty() && self.wrote_any;
+            }
+            StyledEvent::ListItemValue(value) => {
+                if let Some(level) = self.lists.last_mut() {
+                    level.next = value;
+                }
+            }
+            StyledEvent::ListItemStart => {
+                self.end_block();
+                let markdown = self.markdown();
+                if let Some(level) = self.lists.last_mut() {
+                    let marker = match (markdown, level.style.is_ordered()) {
+                        (true, true) => format!("{}.", level.next),
+                        (true, false) => "-".to_string(),
+                        (false, _) => level.style.marker(level.next),
+                    };
+                    level.next = level.next.saturating_add(1);
+                    level.width = if marker.is_empty() {
+                        0
+                    } else {
+                        marker.chars().count() + 1
+                    };
+                    self.item_marker = Some(marker);
+                }
+            }
+            StyledEvent::ListItemEnd => {
+                self.end_block();
+                self.item_marker = None;
+            }
+            StyledEvent::BlockquoteStart(depth) => {
+                self.end_block();
+                self.quote_depth = depth;
+            }
+            StyledEvent::BlockquoteEnd(depth) => {
+                self.end_block();
+                self.quote_depth = depth.saturating_sub(1);
+            }
+            StyledEvent::Hr => {
+                self.end_block();
+                let rule = if self.markdown() { "---" } else { "* * *" };
+                self.line.push_str(rule);
+                self.end_block();
+            }
+            StyledEvent::Image { src, alt, .. } => {
+                let alt = alt.trim();
+                if self.markdown() {
+                    let image = format!("![{}]({})", escape_markdown(alt), link_destination(&src));
+                    self.push_text(&image, Emphasis::None);
+                } else if !alt.is_empty() {
+                    self.push_text(&format!("[{}]", alt), Emphasis::None);
+                }
+            }
+            StyledEvent::LineBreak => self.pending_break = !self.line.is_empty(),
+            StyledEvent::Anchor(id) => {
+                if let Some((_, label)) = self.page_anchors.iter().find(|(anchor, _)| *anchor == id)
+                {
+                    self.pending_pages.push(label.clone());
+                    if self.line.is_empty() {
+                        self.emit_pending_pages();
+                    }
+                }
+            }
+            StyledEvent::LinkStart(href) => {
+                if self.markdown() {
+                    self.close_link();
+                    self.link = Some(OpenLink {
+                        href,
+                        ..OpenLink::default()
+                    });
+                }
+            }
+            StyledEvent::LinkEnd => self.close_link(),
+            StyledEvent::NoteRef { target, .. } => {
+                if self.markdown() {
+                    self.close_link();
+                    self.link = Some(OpenLink {
+                        href: target,
+                        started: false,
+                        single_run: true,
+                    });
+                }
+            }
+            StyledEvent::ScriptedContent
+            | StyledEvent::BlockMargins { .. }
+            | StyledEvent::BlockBreak(_) => {}
+        }
+    }
+
+    /// Append inline text to the current block.
+    ///
+    /// Styled runs arrive with surrounding whitespace trimmed, so runs are
+    /// joined with a space unless punctuation binds them.
+    fn push_text(&mut self, text: &str, emphasis: Emphasis) {
+        let mut words = text.split_whitespace().peekable();
+        let Some(first_char) = words
+            .peek()
+            .and_then(|word| word.trim_start_matches('\\').chars().next())
+        else {
+            return;
+        };
+        if emphasis != self.emphasis {
+            self.close_emphasis();
+        }
+        if !self.line.is_empty() {
+            if self.pending_break {
+                // Headings cannot span lines in Markdown.
+                self.line.push_str(match (self.heading, self.format) {
+                    (Some(_), _) => " ",
+                    (None, ExportFormat::Markdown) => "\\\n",
+                    (None, ExportFormat::PlainText) => "\n",
+                });
+            } else if self
+                .last_char
+                .is_some_and(|last| !binds_without_space(last, first_char))
+            {
+                self.line.push(' ');
+            }
+        }
+        self.pending_break = false;
+        if let Some(link) = self.link.as_mut().filter(|link| !link.started) {
+            link.started = true;
+            self.line.push('[');
+        }
+        if emphasis != self.emphasis {
+            self.line.push_str(emphasis.delimiter());
+            self.emphasis = emphasis;
+        }
+        for (i, word) in words.enumerate() {
+            if i > 0 {
+                self.line.push(' ');
+            }
+            self.line.push_str(word);
+            self.last_char = word.chars().next_back();
+        }
+    }
+
+    fn close_emphasis(&mut self) {
+        self.line.push_str(self.emphasis.delimiter());
+        self.emphasis = Emphasis::None;
+    }
+
+    fn close_link(&mut self) {
+        let Some(link) = self.link.take() else {
+            return;
+        };
+        if link.started {
+            self.close_emphasis();
+            self.line.push_str("](");
+            self.line.push_str(&link_destination(&link.href));
+            self.line.push(')');
+        }
+    }
+
+    /// Write the buffered block, if any, with its quote/list/heading prefix.
+    fn end_block(&mut self) {
+        if let Some(link) = self.link.as_mut().filter(|link| link.started) {
+            // Links spanning blocks reopen in the next block.
+            let href = link.href.clone();
+            let single_run = link.single_run;
+            self.close_link();
+            self.link = Some(OpenLink {
+                href,
+                started: false,
+                single_run,
+            });
+        }
+        self.close_emphasis();
+        self.last_char = None;
+        self.pending_break = false;
+        if self.line.is_empty() {
+            return;
+        }
+        let block = core::mem::take(&mut self.line);
+
+        let quote = if self.markdown() { "> " } else { "  " };
+        let mut first_prefix = quote.repeat(usize::from(self.quote_depth));
+        let mut rest_prefix = first_prefix.clone();
+        if let Some((innermost, outer)) = self.lists.split_last() {
+            let outer_width: usize = outer.iter().map(|level| level.width).sum();
+            first_prefix.push_str(&" ".repeat(outer_width));
+            rest_prefix.push_str(&" ".repeat(outer_width + innermost.width));
+            match self.item_marker.take() {
+                Some(marker) if !marker.is_empty() => {
+                    first_prefix.push_str(&marker);
+                    first_prefix.push(' ');
+                }
+                _ => first_prefix.push_str(&" ".repeat(innermost.width)),
+            }
+        }
+        if let (true, Some(level)) = (self.markdown(), self.heading) {
+            first_prefix.push_str(&"#".repeat(usize::from(level)));
+            first_prefix.push(' ');
+        }
+
+        if self.blank_before {
+            // A blank line inside a quote keeps it open; one at a shallower
+            // depth ends the deeper quote.
+            if self.lists.is_empty() {
+                let depth = self.quote_depth.min(self.written_quote_depth);
+                let blank = quote.repeat(usize::from(depth));
+                self.write(blank.trim_end());
+            }
+            self.write("\n");
+        }
+        for (i, line) in block.split('\n').enumerate() {
+            self.write(if i == 0 { &first_prefix } else { &rest_prefix });
+            self.write(line);
+            self.write("\n");
+        }
+        self.blank_before = self.lists.is_empty();
+        self.written_quote_depth = self.quote_depth;
+        self.emit_pending_pages();
+    }
+
+    fn emit_pending_pages(&mut self) {
+        for label in core::mem::take(&mut self.pending_pages) {
+            if self.blank_before {
+                self.write("\n");
+            }
+            if self.markdown() {
+                self.write(&format!("<!-- page {} -->\n", label));
+            } else {
+                self.write(&format!("\u{c}[page {}]\n", label));
+            }
+            self.blank_before = true;
+            self.written_quote_depth = 0;
+        }
+    }
+
+    fn write(&mut self, text: &str) {
+        if self.error.is_none() {
+            if let Err(err) = self.out.write_all(text.as_bytes()) {
+                self.error = Some(err);
+            }
+            self.wrote_any = true;
+        }
+    }
+}
+
+/// Whether adjacent runs ending in `prev` and starting with `next` join
+/// without a space, as in `word.` or `(word`.
+fn binds_without_space(prev: char, next: char) -> bool {
+    matches!(
+        next,
+        '.' | ','
+            | ';'
+            | ':'
+            | '!'
+            | '?'
+            | ')'
+            | ']'
+            | '}'
+            | '\u{201D}'
+            | '\u{2019}'
+            | '\u{BB}'
+            | '\u{2026}'
+    ) || matches!(
+        prev,
+        '(' | '[' | '{' | '\u{201C}' | '\u{2018}' | '\u{AB}' | '-' | '\u{2014}'
+    )
+}
+
+/// Backslash-escape characters CommonMark would read as inline markup.
+fn escape_markdown(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for ch in text.chars() {
+        if matches!(ch, '\\' | '`' | '*' | '_' | '[' | ']' | '<' | '>' | '#') {
+            out.push('\\');
+        }
+        out.push(ch);
+    }
+    out
+}
+
+/// Link destination, wrapped in `<…>` when it would otherwise end early.
+fn link_destination(href: &str) -> String {
+    if href.contains(|ch: char| ch.is_whitespace() || ch == '(' || ch == ')') {
+        format!("<{}>", href.replace('<', "%3C").replace('>', "%3E"))
+    } else {
+        href.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render_prep::{StyleConfig, Styler};
+
+    fn export(format: ExportFormat, html: &str, page_anchors: &[(&str, &str)]) -> String {
+        let styler = Styler::new(StyleConfig::default()).with_link_events(true);
+        let mut out = Vec::with_capacity(0);
+        let mut writer = ExportWriter::new(format, &mut out);
+        writer.begin_chapter(
+            page_anchors
+                .iter()
+                .map(|(id, label)| (id.to_string(), label.to_string()))
+                .collect(),
+        );
+        styler
+            .style_chapter_with(html, |item| writer.push(item))
+            .unwrap();
+        writer.end_chapter().unwrap();
+        writer.finish().unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    const CHAPTER: &str = r#"<html><body>
+        <h1>Chapter <em>One</em></h1>
+        <p>Some <em>emphasis</em> and <strong>bold</strong> text with a
+           <a href="https://example.com/a b">link</a>.</p>
+        <ul><li>first</li><li>second<ol start="3"><li>nested</li></ol></li></ul>
+        <blockquote><p>Quoted<br/>line</p></blockquote>
+        <p id="p5">Page five 2*3.</p>
+        </body></html>"#;
+
+    #[test]
+    fn markdown_keeps_structure_emphasis_and_links() {
+        let md = export(ExportFormat::Markdown, CHAPTER, &[("p5", "5")]);
+        assert_eq!(
+            md,
+            "# Chapter *One*\n\
+             \n\
+             Some *emphasis* and **bold** text with a [link](<https://example.com/a b>).\n\
+             \n\
+             - first\n\
+             - second\n\
+             \x20 3. nested\n\
+             \n\
+             > Quoted\\\n\
+             > line\n\
+             \n\
+             <!-- page 5 -->\n\
+             \n\
+             Page five 2\\*3.\n"
+        );
+    }
+
+    #[test]
+    fn plain_text_uses_list_markers_and_form_feeds() {
+        let text = export(ExportFormat::PlainText, CHAPTER, &[("p5", "5")]);
+        assert_eq!(
+            text,
+            "Chapter One\n\
+             \n\
+             Some emphasis and bold text with a link.\n\
+             \n\
+             \u{2022} first\n\
+             \u{2022} second\n\
+             \x20 3. nested\n\
+             \n\
+             \x20 Quoted\n\
+             \x20 line\n\
+             \n\
+             \u{c}[page 5]\n\
+             \n\
+             Page five 2*3.\n"
+        );
+    }
+}
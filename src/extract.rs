@@ -0,0 +1,263 @@
+//! Selective extraction of EPUB resources to the filesystem.
+//!
+//! [`EpubBook::extract_resources`](crate::EpubBook::extract_resources) walks
+//! the manifest, keeps the items a [`ResourceFilter`] selects, and streams
+//! each one to a file under the destination directory, mirroring its path
+//! inside the container. Paths are sanitized so no entry can land outside the
+//! destination, and per-entry and total size caps bound what gets written.
+
+use core::fmt;
+use std::path::{Path, PathBuf};
+
+use crate::metadata::ManifestItem;
+
+/// Which manifest items to extract.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ResourceFilter {
+    /// Every manifest item.
+    #[default]
+    All,
+    /// Raster and vector images (`image/*`).
+    Images,
+    /// Embedded fonts, by media type or file extension.
+    Fonts,
+    /// CSS stylesheets.
+    Stylesheets,
+    /// Items whose media type starts with the given prefix, e.g. `"audio/"`.
+    MediaTypePrefix(String),
+}
+
+impl ResourceFilter {
+    /// Whether `item` is selected by this filter.
+    pub fn matches(&self, item: &ManifestItem) -> bool {
+        let media_type = item.media_type.trim().to_ascii_lowercase();
+        match self {
+            Self::All => true,
+            Self::Images => media_type.starts_with("image/"),
+            Self::Fonts => is_font(&media_type, &item.href),
+            Self::Stylesheets => media_type == "text/css",
+            Self::MediaTypePrefix(prefix) => {
+                media_type.starts_with(prefix.trim().to_ascii_lowercase().as_str())
+            }
+        }
+    }
+}
+
+fn is_font(media_type: &str, href: &str) -> bool {
+    if media_type.starts_with("font/")
+        || media_type.starts_with("application/font-")
+        || media_type.starts_with("application/x-font-")
+        || media_type == "application/vnd.ms-opentype"
+    {
+        return true;
+    }
+    let path = href.split('#').next().unwrap_or(href);
+    let extension = path.rsplit_once('.').map_or("", |(_, ext)| ext);
+    ["ttf", "otf", "woff", "woff2"]
+        .iter()
+        .any(|known| extension.eq_ignore_ascii_case(known))
+}
+
+/// Progress for one resource written by
+/// [`EpubBook::extract_resources`](crate::EpubBook::extract_resources).
+#[derive(Clone, Copy, Debug)]
+pub struct ExtractProgress<'a> {
+    /// 0-based position among the selected resources.
+    pub index: usize,
+    /// Number of resources selected by the filter.
+    pub total: usize,
+    /// OPF-relative manifest href.
+    pub href: &'a str,
+    /// File that was written.
+    pub path: &'a Path,
+    /// Bytes written for this resource.
+    pub bytes: usize,
+}
+
+/// Options for [`EpubBook::extract_resources`](crate::EpubBook::extract_resources).
+pub struct ExtractOptions<'a> {
+    /// Largest single resource to write; larger ones are skipped.
+    pub max_entry_bytes: usize,
+    /// Total bytes to write; resources past the budget are skipped.
+    pub max_total_bytes: usize,
+    /// Replace files that already exist in the destination.
+    pub overwrite: bool,
+    /// Reverse font obfuscation declared in `META-INF/encryption.xml`.
+    pub deobfuscate_fonts: bool,
+    progress: Option<&'a mut dyn FnMut(ExtractProgress<'_>)>,
+}
+
+impl Default for ExtractOptions<'_> {
+    fn default() -> Self {
+        Self {
+            max_entry_bytes: 64 * 1024 * 1024,
+            max_total_bytes: 512 * 1024 * 1024,
+            overwrite: false,
+            deobfuscate_fonts: true,
+            progress: None,
+        }
+    }
+}
+
+impl fmt::Debug for ExtractOptions<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ExtractOptions")
+            .field("max_entry_bytes", &self.max_entry_bytes)
+            .field("max_total_bytes", &self.max_total_bytes)
+            .field("overwrite", &self.overwrite)
+            .field("deobfuscate_fonts", &self.deobfuscate_fonts)
+            .field("progress", &self.progress.is_some())
+            .finish()
+    }
+}
+
+impl<'a> ExtractOptions<'a> {
+    /// Set the per-resource size cap.
+    pub fn with_max_entry_bytes(mut self, max_entry_bytes: usize) -> Self {
+        self.max_entry_bytes = max_entry_bytes;
+        self
+    }
+
+    /// Set the total size budget.
+    pub fn with_max_total_bytes(mut self, max_total_bytes: usize) -> Self {
+        self.max_total_bytes = max_total_bytes;
+        self
+    }
+
+    /// Replace existing files instead of skipping them.
+    pub fn with_overwrite(mut self, overwrite: bool) -> Self {
+        self.overwrite = overwrite;
+        self
+    }
+
+    /// Enable or disable font de-obfuscation.
+    pub fn with_deobfuscate_fonts(mut self, deobfuscate_fonts: bool) -> Self {
+        self.deobfuscate_fonts = deobfuscate_fonts;
+        self
+    }
+
+    /// Call `progress` after each resource is written.
+    pub fn with_progress(mut self, progress: &'a mut dyn FnMut(ExtractProgress<'_>)) -> Self {
+        self.progress = Some(progress);
+        self
+    }
+
+    pub(crate) fn report_progress(&mut self, progress: ExtractProgress<'_>) {
+        if let Some(callback) = self.progress.as_mut() {
+            callback(progress);
+        }
+    }
+}
+
+/// Why a selected resource was not written.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum SkipReason {
+    /// The manifest href does not map to a safe relative path.
+    UnsafePath,
+    /// The manifest item has no entry in the archive.
+    Missing,
+    /// The resource exceeds [`ExtractOptions::max_entry_bytes`].
+    TooLarge,
+    /// Writing the resource would exceed [`ExtractOptions::max_total_bytes`].
+    BudgetExhausted,
+    /// The destination file exists and overwriting is disabled.
+    Exists,
+    /// Reading the entry or writing the file failed; holds the error message.
+    Failed(String),
+}
+
+/// A resource written to disk.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ExtractedResource {
+    /// OPF-relative manifest href.
+    pub href: String,
+    /// File that was written.
+    pub path: PathBuf,
+    /// Bytes written.
+    pub bytes: usize,
+}
+
+/// A selected resource that was not written.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SkippedResource {
+    /// OPF-relative manifest href.
+    pub href: String,
+    /// Why it was skipped.
+    pub reason: SkipReason,
+}
+
+/// Outcome of [`EpubBook::extract_resources`](crate::EpubBook::extract_resources).
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ExtractReport {
+    /// Resources written, in manifest order.
+    pub extracted: Vec<ExtractedResource>,
+    /// Selected resources that were skipped, in manifest order.
+    pub skipped: Vec<SkippedResource>,
+}
+
+impl ExtractReport {
+    /// Total bytes written.
+    pub fn total_bytes(&self) -> usize {
+        self.extracted.iter().map(|resource| resource.bytes).sum()
+    }
+}
+
+/// Relative filesystem path for a container path, or `None` when any
+/// component could escape the destination or is not portable.
+pub(crate) fn sanitized_relative_path(container_path: &str) -> Option<PathBuf> {
+    let mut out = PathBuf::new();
+    for component in container_path.split('/') {
+        if component.is_empty()
+            || component == "."
+            || component == ".."
+            || component.contains(['\\', ':', '\0'])
+        {
+            return None;
+        }
+        out.push(component);
+    }
+    (!out.as_os_str().is_empty()).then_some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(href: &str, media_type: &str) -> ManifestItem {
+        ManifestItem {
+            id: "id".to_string(),
+            href: href.to_string(),
+            media_type: media_type.to_string(),
+            properties: None,
+            fallback: None,
+        }
+    }
+
+    #[test]
+    fn filters_match_media_types_and_font_extensions() {
+        assert!(ResourceFilter::Images.matches(&item("a.png", "image/png")));
+        assert!(!ResourceFilter::Images.matches(&item("a.css", "text/css")));
+        assert!(ResourceFilter::Fonts.matches(&item("a.otf", "application/vnd.ms-opentype")));
+        assert!(ResourceFilter::Fonts.matches(&item("a.TTF", "application/octet-stream")));
+        assert!(ResourceFilter::Stylesheets.matches(&item("a.css", "text/css")));
+        assert!(ResourceFilter::MediaTypePrefix("Audio/".to_string())
+            .matches(&item("a.mp3", "audio/mpeg")));
+        assert!(ResourceFilter::All.matches(&item("a.xhtml", "application/xhtml+xml")));
+    }
+
+    #[test]
+    fn rejects_paths_that_escape_destination() {
+        assert_eq!(
+            sanitized_relative_path("OEBPS/images/a.png"),
+            Some(["OEBPS", "images", "a.png"].iter().collect())
+        );
+        assert_eq!(sanitized_relative_path("../a.png"), None);
+        assert_eq!(sanitized_relative_path("/etc/passwd"), None);
+        assert_eq!(sanitized_relative_path("a/./b"), None);
+        assert_eq!(sanitized_relative_path("C:/a"), None);
+        assert_eq!(sanitized_relative_path("a\\..\\b"), None);
+        assert_eq!(sanitized_relative_path(""), None);
+    }
+}
@@ -0,0 +1,376 @@
+//! Lightweight language identification from chapter text.
+//!
+//! Many books omit `dc:language` or leave a template's `en` in place, which
+//! picks the wrong hyphenation patterns and line-breaking rules.
+//! [`detect_language`] scores text against small per-language profiles of
+//! frequent character trigrams (rank-weighted, Cavnar–Trenkle style) and
+//! reports the best match with a confidence margin.
+//!
+//! The profiles are compiled in only with the `lang-detect` feature; without
+//! it [`detect_language`] always returns `None`.
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+
+/// Languages the detector can identify.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub enum Language {
+    /// English (`en`).
+    English,
+    /// German (`de`).
+    German,
+    /// French (`fr`).
+    French,
+    /// Spanish (`es`).
+    Spanish,
+    /// Italian (`it`).
+    Italian,
+    /// Portuguese (`pt`).
+    Portuguese,
+    /// Dutch (`nl`).
+    Dutch,
+    /// Swedish (`sv`).
+    Swedish,
+    /// Polish (`pl`).
+    Polish,
+    /// Finnish (`fi`).
+    Finnish,
+    /// Russian (`ru`).
+    Russian,
+    /// Turkish (`tr`).
+    Turkish,
+}
+
+impl Language {
+    /// Every detectable language.
+    pub const ALL: [Language; 12] = [
+        Language::English,
+        Language::German,
+        Language::French,
+        Language::Spanish,
+        Language::Italian,
+        Language::Portuguese,
+        Language::Dutch,
+        Language::Swedish,
+        Language::Polish,
+        Language::Finnish,
+        Language::Russian,
+        Language::Turkish,
+    ];
+
+    /// ISO 639-1 code, usable as a BCP 47 primary language subtag.
+    pub const fn code(&self) -> &'static str {
+        match self {
+            Language::English => "en",
+            Language::German => "de",
+            Language::French => "fr",
+            Language::Spanish => "es",
+            Language::Italian => "it",
+            Language::Portuguese => "pt",
+            Language::Dutch => "nl",
+            Language::Swedish => "sv",
+            Language::Polish => "pl",
+            Language::Finnish => "fi",
+            Language::Russian => "ru",
+            Language::Turkish => "tr",
+        }
+    }
+
+    /// Match the primary subtag of a language tag such as `en-GB` or `pt_BR`.
+    pub fn from_tag(tag: &str) -> Option<Self> {
+        let primary = tag.trim().split(['-', '_']).next()?;
+        Self::ALL
+            .into_iter()
+            .find(|lang| lang.code().eq_ignore_ascii_case(primary))
+    }
+}
+
+/// Best-matching language for a text sample.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LanguageGuess {
+    /// Detected language.
+    pub language: Language,
+    /// Margin over the runner-up in `0.0..=1.0`; `0.0` is a tie.
+    pub confidence: f32,
+}
+
+impl LanguageGuess {
+    /// Confidence at which a guess should override a declared language.
+    pub const CONFIDENT: f32 = 0.25;
+
+    /// Whether the guess is strong enough to override a declared language.
+    pub fn is_confident(&self) -> bool {
+        self.confidence >= Self::CONFIDENT
+    }
+}
+
+/// Fewest trigrams a sample needs before a guess is attempted.
+pub const MIN_SAMPLE_TRIGRAMS: usize = 40;
+
+/// Guess the language of `text`.
+///
+/// Returns `None` when the sample is shorter than [`MIN_SAMPLE_TRIGRAMS`],
+/// matches no profile, or the `lang-detect` feature is disabled.
+pub fn detect_language(text: &str) -> Option<LanguageGuess> {
+    let trigrams = trigrams(text);
+    if trigrams.len() < MIN_SAMPLE_TRIGRAMS {
+        return None;
+    }
+    let mut scores: Vec<(Language, u32)> = profiles()
+        .iter()
+        .map(|(lang, profile)| (*lang, score(&trigrams, profile)))
+        .collect();
+    scores.sort_by_key(|(_, score)| core::cmp::Reverse(*score));
+    let (language, best) = *scores.first()?;
+    if best == 0 {
+        return None;
+    }
+    let runner_up = scores.get(1).map_or(0, |(_, score)| *score);
+    Some(LanguageGuess {
+        language,
+        confidence: (best - runner_up) as f32 / best as f32,
+    })
+}
+
+/// Lowercased character trigrams of `text`, with each run of non-letters
+/// collapsed to a single space so word boundaries contribute.
+fn trigrams(text: &str) -> Vec<[char; 3]> {
+    let mut out = Vec::with_capacity(text.len());
+    let mut window = [' '; 3];
+    let mut filled = 1usize;
+    let mut last_space = true;
+    let mut push = |ch: char, out: &mut Vec<[char; 3]>| {
+        window = [window[1], window[2], ch];
+        filled = (filled + 1).min(3);
+        if filled == 3 {
+            out.push(window);
+        }
+    };
+    for ch in text.chars().flat_map(char::to_lowercase) {
+        if ch.is_alphabetic() {
+            push(ch, &mut out);
+            last_space = false;
+        } else if !last_space {
+            push(' ', &mut out);
+            last_space = true;
+        }
+    }
+    if !last_space {
+        push(' ', &mut out);
+    }
+    out
+}
+
+/// Rank-weighted hits of `trigrams` in a profile of frequent trigrams.
+fn score(trigrams: &[[char; 3]], profile: &[&str]) -> u32 {
+    let mut total = 0u32;
+    for trigram in trigrams {
+        let rank = profile.iter().position(|entry| {
+            let mut chars = entry.chars();
+            trigram.iter().all(|&ch| chars.next() == Some(ch))
+        });
+        if let Some(rank) = rank {
+            total = total.saturating_add((profile.len() - rank) as u32);
+        }
+    }
+    total
+}
+
+#[cfg(not(feature = "lang-detect"))]
+fn profiles() -> &'static [(Language, &'static [&'static str])] {
+    &[]
+}
+
+#[cfg(feature = "lang-detect")]
+fn profiles() -> &'static [(Language, &'static [&'static str])] {
+    PROFILES
+}
+
+/// Frequent trigrams per language, most frequent first. Spaces mark word
+/// boundaries.
+#[cfg(feature = "lang-detect")]
+const PROFILES: &[(Language, &[&str])] = &[
+    (
+        Language::English,
+        &[
+            " th", "the", "he ", "ed ", " an", "and", "nd ", " of", "of ", " to", "ing", "ng ",
+            "to ", " in", "er ", "in ", "is ", " a ", "at ", "re ", "on ", "ion", "es ", " he",
+            " wa", "was", "as ", "hat", "tha", "his", " hi", "ent", "it ", "ere", "for", " fo",
+            "or ", " wh", "her", "ly ",
+        ],
+    ),
+    (
+        Language::German,
+        &[
+            "en ", "er ", " de", "der", "ich", "ie ", "die", " di", "sch", "ch ", "ein", " ei",
+            "und", " un", "nd ", "den", "che", "cht", "ine", " ge", "gen", "in ", "te ", "ten",
+            " da", "ung", "ng ", "nde", " zu", "auf", " ve", "ver", "ist", "nic", "ht ", "ber",
+            "sie", " si", "das", "mit",
+        ],
+    ),
+    (
+        Language::French,
+        &[
+            "es ", " de", "de ", "le ", " le", "ent", "nt ", "la ", " la", "re ", " et", "et ",
+            "que", " qu", "ue ", "ion", "les", " pa", "ne ", "on ", "ur ", " co", "men", "des",
+            " un", "une", "ait", "eur", "our", "ais", " da", "dan", "ns ", "qui", " ne", "est",
+            " so", " il", "il ", "pas",
+        ],
+    ),
+    (
+        Language::Spanish,
+        &[
+            "de ", " de", "os ", "la ", " la", "el ", " qu", "que", "ue ", " el", "en ", "es ",
+            " co", "as ", " en", "ent", "er ", "ado", " se", "los", " lo", "con", "ien", "nte",
+            " po", "cio", "ar ", "por", "ra ", " es", "del", "aba", " ha", "una", " un", "est",
+            " su", "ero", " y ", "ía ",
+        ],
+    ),
+    (
+        Language::Italian,
+        &[
+            "di ", " di", "la ", "to ", "che", " ch", "he ", "re ", "ne ", " co", " la", "ell",
+            "el ", " il", "il ", "ent", "del", "lla", "no ", " de", "one", "per", " pe", "are",
+            "ato", "zio", " un", "non", " no", "ta ", "ere", "con", "sse", "ess", "gli", " gl",
+            "ll ", " è ", "ava", "eva",
+        ],
+    ),
+    (
+        Language::Portuguese,
+        &[
+            "de ", " de", "os ", " qu", "que", "ue ", "do ", " co", "da ", "ão ", "com", "ent",
+            " se", "ção", "to ", "as ", " do", " da", "em ", " em", "ra ", "nte", "es ", "uma",
+            " um", "um ", "não", " nã", "ado", " pa", "ara", "par", "est", "men", "ndo", "ele",
+            "ava", " o ", "ões", "lha",
+        ],
+    ),
+    (
+        Language::Dutch,
+        &[
+            "en ", " de", "de ", "an ", "et ", "het", " he", "van", " va", "een", " ee", "er ",
+            "aar", "ij ", "in ", "te ", "ijk", " en", "ver", " ve", "ede", "ten", "nde", "oor",
+            "cht", " ge", "gen", "den", " da", "dat", "at ", "zij", " zi", "ie ", "we ", "aan",
+            "ijn", "sch", "ik ", " ik",
+        ],
+    ),
+    (
+        Language::Swedish,
+        &[
+            "en ", "att", " at", "och", " oc", "ch ", "et ", "för", " fö", "an ", " de", "de ",
+            "er ", "ar ", "det", "ing", "som", " so", "om ", " in", "ter", "den", "and", "är ",
+            " är", " ti", "ill", "med", " me", "ade", "lig", "nde", "ra ", "var", " va", "på ",
+            " på", "sta", "han", "jag",
+        ],
+    ),
+    (
+        Language::Polish,
+        &[
+            "ie ", "nie", " ni", "owa", " po", "ch ", "ego", "go ", " pr", "prz", "rze", "sta",
+            "ani", "się", " si", "ię ", "ej ", " na", "ny ", "wie", "ych", " w ", "ia ", "na ",
+            " za", "cze", "ski", "kie", "ki ", "dzi", "rzy", "ni ", " do", "em ", "pra", "ści",
+            "ało", "był", " by", "jak",
+        ],
+    ),
+    (
+        Language::Finnish,
+        &[
+            "en ", "an ", "in ", "ssa", "sa ", "ist", " ja", "ja ", "ta ", "tä ", "sta", "lla",
+            " ka", "ais", "kan", "ine", "een", " on", "on ", "ell", "ise", "tti", "oli", " ol",
+            "aan", "stä", "lle", "ksi", "ään", "ili", "ssä", "vat", "ett", " ku", "kse", "ämä",
+            "kä ", "nen", "ini", "tta",
+        ],
+    ),
+    (
+        Language::Russian,
+        &[
+            " на", "ого", "то ", "ени", "ост", " по", "ия ", " пр", "ть ", "ова", "ста", "ет ",
+            "на ", "не ", " не", "что", " чт", "ал ", "ли ", "ать", "его", "про", "го ", "ной",
+            "ыл ", "как", " ка", "ом ", "он ", " он", "ла ", "ние", "ся ", "тел", "ные", "по ",
+            "ств", " в ", " и ", "ак ",
+        ],
+    ),
+    (
+        Language::Turkish,
+        &[
+            "lar", "ler", " bi", "bir", "ir ", " ve", "ve ", "ın ", "ini", "in ", "an ", "eri",
+            "ara", "ind", "ası", "ını", "arı", "en ", "de ", "da ", " ka", "yor", "ak ", "iyo",
+            "rın", "le ", "la ", "nda", " bu", "bu ", "dı ", "di ", "ken", "rak", "sın", "mak",
+            " ya", "ile", "ği ", "dan",
+        ],
+    ),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn language_tags_match_primary_subtag() {
+        assert_eq!(Language::from_tag("en-GB"), Some(Language::English));
+        assert_eq!(Language::from_tag(" PT_br "), Some(Language::Portuguese));
+        assert_eq!(Language::from_tag("x-unknown"), None);
+        assert_eq!(Language::from_tag(""), None);
+        for lang in Language::ALL {
+            assert_eq!(Language::from_tag(lang.code()), Some(lang));
+        }
+    }
+
+    #[test]
+    fn trigrams_collapse_non_letters_into_word_boundaries() {
+        let grams = trigrams("Hi, yo!");
+        let text: Vec<alloc::string::String> = grams.iter().map(|g| g.iter().collect()).collect();
+        assert_eq!(text, [" hi", "hi ", "i y", " yo", "yo "]);
+    }
+
+    #[test]
+    fn short_samples_are_not_guessed() {
+        assert_eq!(detect_language("The end."), None);
+        assert_eq!(detect_language(""), None);
+    }
+
+    #[cfg(not(feature = "lang-detect"))]
+    #[test]
+    fn detection_is_disabled_without_tables() {
+        let text = "It was the best of times, it was the worst of times, it was the age of wisdom.";
+        assert_eq!(detect_language(text), None);
+    }
+
+    #[cfg(feature = "lang-detect")]
+    #[test]
+    fn every_profile_entry_is_a_trigram() {
+        for (lang, profile) in PROFILES {
+            for entry in *profile {
+                assert_eq!(entry.chars().count(), 3, "{:?} entry {:?}", lang, entry);
+            }
+        }
+    }
+
+    #[cfg(feature = "lang-detect")]
+    #[test]
+    fn detects_each_language_from_a_paragraph() {
+        let samples = [
+            (Language::English, "It was the best of times, it was the worst of times. He walked into the house and found that the door to his study was open, and the letter he had written was gone."),
+            (Language::German, "Es war einmal ein kleines Mädchen, die hatte jeder lieb, der sie nur ansah. Am allerliebsten aber ihre Großmutter, die wusste gar nicht, was sie alles dem Kinde geben sollte."),
+            (Language::French, "Il était une fois une petite fille de village, la plus jolie qu'on eût su voir. Sa mère en était folle, et sa mère-grand plus folle encore. Elle ne savait pas que les loups sont dangereux."),
+            (Language::Spanish, "En un lugar de la Mancha, de cuyo nombre no quiero acordarme, no ha mucho tiempo que vivía un hidalgo de los de lanza en astillero, adarga antigua, rocín flaco y galgo corredor."),
+            (Language::Italian, "Nel mezzo del cammin di nostra vita mi ritrovai per una selva oscura, che la diritta via era smarrita. Ahi quanto a dir qual era è cosa dura questa selva selvaggia e aspra e forte."),
+            (Language::Portuguese, "Era uma vez uma menina que vivia com a sua mãe numa casa perto da floresta. Todos os dias ela levava pão e leite para a avó, que não podia sair de casa porque estava doente."),
+            (Language::Dutch, "Er was eens een klein meisje dat door iedereen werd liefgehad. Zij woonde met haar moeder in een huis aan de rand van het bos, en zij ging elke dag naar haar grootmoeder."),
+            (Language::Swedish, "Det var en gång en liten flicka som alla tyckte om. Hon bodde med sin mor i ett hus vid skogen, och varje dag gick hon till sin mormor för att ge henne bröd och mjölk."),
+            (Language::Polish, "Pewnego razu była sobie mała dziewczynka, którą wszyscy bardzo kochali. Mieszkała z matką w domu na skraju lasu i codziennie chodziła do babci, która była chora i nie mogła wychodzić."),
+            (Language::Finnish, "Olipa kerran pieni tyttö, josta kaikki pitivät kovasti. Hän asui äitinsä kanssa talossa metsän laidalla, ja joka päivä hän kävi isoäitinsä luona viemässä leipää ja maitoa."),
+            (Language::Russian, "Жила-была маленькая девочка, которую все очень любили. Она жила с матерью в доме на краю леса и каждый день ходила к бабушке, которая была больна и не могла выходить из дома."),
+            (Language::Turkish, "Bir varmış bir yokmuş, küçük bir kız varmış. Herkes onu çok severmiş. Annesiyle birlikte ormanın kenarındaki bir evde yaşarmış ve her gün hasta olan büyükannesine ekmek götürürmüş."),
+        ];
+        for (expected, text) in samples {
+            let guess = detect_language(text).expect("sample should be long enough");
+            assert_eq!(guess.language, expected, "sample: {}", text);
+            assert!(guess.confidence > 0.0);
+        }
+    }
+}
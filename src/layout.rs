@@ -10,6 +10,7 @@ use alloc::string::String;
 use alloc::vec;
 use alloc::vec::Vec;
 
+use crate::css::{Dimension, ListStyleType};
 use crate::tokenizer::Token;
 
 /// Text style for layout (bold, italic, etc.)
@@ -80,6 +81,8 @@ impl TextSpan {
 pub struct Line {
     /// Styled text spans that make up this line
     pub spans: Vec<TextSpan>,
+    /// X offset from the content left edge (first-line indent)
+    pub x: i32,
     /// Y position on the page
     pub y: i32,
 }
@@ -89,6 +92,7 @@ impl Line {
     pub fn new(text: String, y: i32, style: TextStyle) -> Self {
         Self {
             spans: vec![TextSpan::new(text, style)],
+            x: 0,
             y,
         }
     }
@@ -155,6 +159,70 @@ impl Page {
     }
 }
 
+/// How consecutive paragraphs are separated
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[non_exhaustive]
+pub enum ParagraphSpacing {
+    /// Vertical gap between paragraphs, as a fraction of the line height
+    BlankLine(f32),
+    /// No vertical gap; the first line of each following paragraph is indented
+    FirstLineIndent,
+}
+
+impl Default for ParagraphSpacing {
+    fn default() -> Self {
+        // Half line space keeps separation while saving vertical space
+        ParagraphSpacing::BlankLine(0.5)
+    }
+}
+
+/// Widow/orphan policy, matching the render crate's `WidowOrphanControl`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WidowOrphanControl {
+    /// Keep at least this many lines at paragraph start/end when possible.
+    pub min_lines: u8,
+    /// Enable widow/orphan controls.
+    pub enabled: bool,
+}
+
+impl Default for WidowOrphanControl {
+    fn default() -> Self {
+        Self {
+            min_lines: 1,
+            enabled: false,
+        }
+    }
+}
+
+/// Keep-with-next policy for headings, matching the render crate's `KeepWithNext`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct KeepWithNext {
+    /// Body lines that must follow a heading on the same page.
+    pub min_lines: u8,
+    /// Enable keep-with-next for headings (off by default).
+    pub enabled: bool,
+}
+
+impl Default for KeepWithNext {
+    fn default() -> Self {
+        Self {
+            min_lines: 1,
+            enabled: false,
+        }
+    }
+}
+
+/// Heading block that must stay on the same page as the lines following it
+#[derive(Clone, Copy, Debug)]
+struct KeepAnchor {
+    /// Index of the heading's first line in the current page
+    start: usize,
+    /// Whether heading lines are still being laid out
+    heading_open: bool,
+    /// Body lines placed after the heading so far
+    body_lines: usize,
+}
+
 /// Font metrics for text measurement
 #[derive(Clone, Debug)]
 pub struct FontMetrics {
@@ -212,6 +280,30 @@ pub struct LayoutEngine {
     left_margin: f32,
     /// Top margin in pixels
     top_margin: f32,
+    /// Right margin in pixels, reserved from the page width
+    right_margin: f32,
+    /// Paragraph separation mode
+    paragraph_spacing: ParagraphSpacing,
+    /// First-line indent width in pixels
+    indent_width: f32,
+    /// Space before h1/h2 headings in line heights (half for deeper levels)
+    heading_spacing: f32,
+    /// Whether the next line opens an indented paragraph
+    indent_next: bool,
+    /// Indent applied to the line being built
+    current_line_indent: f32,
+    /// Widow/orphan policy
+    widow_orphan_control: WidowOrphanControl,
+    /// Lines of the current paragraph on the current page
+    paragraph_lines_on_page: usize,
+    /// Lines of the current paragraph on the previous page
+    paragraph_lines_on_prev_page: usize,
+    /// Keep-with-next policy for headings
+    keep_with_next: KeepWithNext,
+    /// Heading currently waiting for its following lines
+    keep_anchor: Option<KeepAnchor>,
+    /// `<hr>` width as a fraction of the line width
+    hr_width_ratio: f32,
     /// Finalized spans for the current line
     current_spans: Vec<TextSpan>,
     /// Text being accumulated for the current span
@@ -234,10 +326,22 @@ pub struct LayoutEngine {
     current_line_count: usize,
     /// Current list nesting depth
     list_depth: usize,
-    /// Stack tracking ordered vs unordered at each nesting level
-    list_ordered_stack: Vec<bool>,
-    /// Item counter at each list nesting level
-    list_item_counters: Vec<usize>,
+    /// Marker style at each nesting level
+    list_style_stack: Vec<ListStyleType>,
+    /// Ordinal of the last item at each list nesting level
+    list_item_counters: Vec<i32>,
+    /// Whether text is inside a preformatted block (laid out without re-wrapping)
+    preformatted: bool,
+    /// Whether the current preformatted line was cut at the page width
+    preformatted_clipped: bool,
+    /// Inside `<strong>`/`<b>`
+    bold_active: bool,
+    /// Inside `<em>`/`<i>`
+    italic_active: bool,
+    /// Inside a heading, which renders bold
+    heading_bold: bool,
+    /// Inside a figure caption, which renders italic
+    in_caption: bool,
 }
 
 impl LayoutEngine {
@@ -253,6 +357,18 @@ impl LayoutEngine {
     pub const DEFAULT_HEADER_HEIGHT: f32 = 45.0;
     /// Footer area for progress (must match renderer FOOTER_HEIGHT)
     pub const DEFAULT_FOOTER_HEIGHT: f32 = 40.0;
+    /// Marker appended to preformatted lines cut at the page width
+    pub const TRUNCATION_MARKER: char = '\u{2026}';
+    /// Default first-line indent in pixels (two 10px characters)
+    pub const DEFAULT_INDENT_WIDTH: f32 = 20.0;
+    /// Default space before h1/h2 headings, in line heights
+    pub const DEFAULT_HEADING_SPACING: f32 = 1.0;
+    /// Default `<hr>` width as a fraction of the line width
+    pub const DEFAULT_HR_WIDTH_RATIO: f32 = 0.5;
+    /// Character repeated to draw an `<hr>` rule
+    pub const RULE_CHAR: char = '\u{2500}';
+    /// Columns a tab advances inside preformatted text
+    const TAB_WIDTH: usize = 4;
 
     /// Create a new layout engine
     ///
@@ -273,6 +389,18 @@ impl LayoutEngine {
             font_metrics,
             left_margin: Self::DEFAULT_MARGIN,
             top_margin: Self::DEFAULT_TOP_MARGIN,
+            right_margin: 0.0,
+            paragraph_spacing: ParagraphSpacing::default(),
+            indent_width: Self::DEFAULT_INDENT_WIDTH,
+            heading_spacing: Self::DEFAULT_HEADING_SPACING,
+            indent_next: false,
+            current_line_indent: 0.0,
+            widow_orphan_control: WidowOrphanControl::default(),
+            paragraph_lines_on_page: 0,
+            paragraph_lines_on_prev_page: 0,
+            keep_with_next: KeepWithNext::default(),
+            keep_anchor: None,
+            hr_width_ratio: Self::DEFAULT_HR_WIDTH_RATIO,
             current_spans: Vec::with_capacity(0),
             current_span_text: String::with_capacity(0),
             current_span_style: TextStyle::Normal,
@@ -284,8 +412,14 @@ impl LayoutEngine {
             max_lines_per_page: max_lines.max(1),
             current_line_count: 0,
             list_depth: 0,
-            list_ordered_stack: Vec::with_capacity(0),
+            list_style_stack: Vec::with_capacity(0),
             list_item_counters: Vec::with_capacity(0),
+            preformatted: false,
+            preformatted_clipped: false,
+            bold_active: false,
+            italic_active: false,
+            heading_bold: false,
+            in_caption: false,
         }
     }
 
@@ -311,128 +445,304 @@ impl LayoutEngine {
         self
     }
 
+    /// Set the right margin, narrowing every line by `right` pixels
+    pub fn with_right_margin(mut self, right: f32) -> Self {
+        self.right_margin = right;
+        self
+    }
+
+    /// Set how consecutive paragraphs are separated
+    pub fn with_paragraph_spacing(mut self, spacing: ParagraphSpacing) -> Self {
+        self.paragraph_spacing = spacing;
+        self
+    }
+
+    /// Set the first-line indent used by [`ParagraphSpacing::FirstLineIndent`]
+    pub fn with_indent_width(mut self, width: f32) -> Self {
+        self.indent_width = width;
+        self
+    }
+
+    /// Set the space before h1/h2 headings in line heights (deeper levels get half)
+    pub fn with_heading_spacing(mut self, lines: f32) -> Self {
+        self.heading_spacing = lines;
+        self
+    }
+
+    /// Set the widow/orphan policy for paragraphs split across pages
+    pub fn with_widow_orphan_control(mut self, control: WidowOrphanControl) -> Self {
+        self.widow_orphan_control = control;
+        self
+    }
+
+    /// Set the keep-with-next policy for headings
+    pub fn with_keep_with_next(mut self, keep: KeepWithNext) -> Self {
+        self.keep_with_next = keep;
+        self
+    }
+
+    /// Set the `<hr>` rule width as a fraction of the line width
+    pub fn with_hr_width_ratio(mut self, ratio: f32) -> Self {
+        self.hr_width_ratio = ratio;
+        self
+    }
+
     /// Convert tokens into laid-out pages
     pub fn layout_tokens(&mut self, tokens: &[Token]) -> Vec<Page> {
         self.reset();
+        for token in tokens {
+            self.push_token(token);
+        }
+        self.end_block();
+        self.finalize_page();
+        core::mem::take(&mut self.pages)
+    }
 
-        let mut bold_active = false;
-        let mut italic_active = false;
-        let mut heading_bold = false;
+    /// Start laying out a new chapter incrementally, discarding any state.
+    ///
+    /// Follow with [`push_token`](Self::push_token) for each token, drain
+    /// finished pages with [`take_settled_pages`](Self::take_settled_pages),
+    /// and end with [`finish_chapter`](Self::finish_chapter).
+    pub fn start_chapter(&mut self) {
+        self.reset();
+    }
 
-        for token in tokens {
-            match token {
-                Token::Text(ref text) => {
-                    let style =
-                        self.current_style_from_flags(bold_active || heading_bold, italic_active);
+    /// Lay out one token of the chapter started with
+    /// [`start_chapter`](Self::start_chapter).
+    pub fn push_token(&mut self, token: &Token) {
+        match token {
+            Token::Text(ref text) => {
+                let style = self.current_style_from_flags(
+                    self.bold_active || self.heading_bold,
+                    self.italic_active || self.in_caption,
+                );
+                if self.preformatted {
+                    self.add_preformatted_text(text, style);
+                } else {
                     self.add_text(text, style);
                 }
-                Token::ParagraphBreak => {
-                    self.flush_line();
-                    self.add_paragraph_space();
-                    heading_bold = false;
-                }
-                Token::Heading(level) => {
-                    self.flush_line();
-                    // Headings get extra space before (more space for higher level headings)
-                    if self.current_line_count > 0 {
-                        // Add 1-2 lines of space before heading based on level
-                        let space_lines = if *level <= 2 { 2 } else { 1 };
-                        for _ in 0..space_lines {
-                            self.add_paragraph_space();
-                        }
-                    }
-                    // Headings are always bold (via heading_bold, not bold_active)
-                    heading_bold = true;
-                    // Note: Currently we use same font size for all headings
-                    // Future: could use larger fonts for h1-h2
-                }
-                Token::Emphasis(start) => {
-                    self.flush_partial_word();
-                    italic_active = *start;
-                    self.current_span_style =
-                        self.current_style_from_flags(bold_active || heading_bold, italic_active);
-                }
-                Token::Strong(start) => {
-                    self.flush_partial_word();
-                    bold_active = *start;
-                    self.current_span_style =
-                        self.current_style_from_flags(bold_active || heading_bold, italic_active);
-                }
-                Token::LineBreak => {
-                    self.flush_line();
-                }
-                // List tokens — track nesting and emit bullet/number prefixes
-                Token::ListStart(ordered) => {
-                    self.flush_line();
-                    self.list_depth += 1;
-                    self.list_ordered_stack.push(*ordered);
-                    self.list_item_counters.push(0);
-                }
-                Token::ListEnd => {
-                    self.flush_line();
-                    self.list_depth = self.list_depth.saturating_sub(1);
-                    self.list_ordered_stack.pop();
-                    self.list_item_counters.pop();
-                    if self.list_depth == 0 {
-                        self.add_paragraph_space();
-                    }
+            }
+            Token::ParagraphBreak => {
+                self.end_block();
+                if self.heading_bold {
+                    self.close_keep_heading();
                 }
-                Token::ListItemStart => {
-                    self.flush_line();
-                    // Increment item counter for the current list level
-                    if let Some(counter) = self.list_item_counters.last_mut() {
-                        *counter += 1;
-                    }
-                    // Build indentation prefix based on nesting depth
-                    let indent = "  ".repeat(self.list_depth.saturating_sub(1));
-                    let is_ordered = self.list_ordered_stack.last().copied().unwrap_or(false);
-                    let marker = if is_ordered {
-                        let count = self.list_item_counters.last().copied().unwrap_or(1);
-                        format!("{}{}.", indent, count)
+                self.add_paragraph_space();
+                // Only body paragraphs indent their successor; text after a heading starts flush
+                self.indent_next = !self.heading_bold;
+                self.heading_bold = false;
+            }
+            Token::Heading(level) => {
+                self.end_block();
+                self.indent_next = false;
+                // Headings get extra space before (more space for higher level headings)
+                if self.current_line_count > 0 {
+                    let space_lines = if *level <= 2 {
+                        self.heading_spacing
                     } else {
-                        format!("{}\u{2022}", indent) // bullet: •
+                        self.heading_spacing * 0.5
                     };
-                    let marker_width = self.font_metrics.text_width(&marker, TextStyle::Normal);
-                    self.current_span_text.push_str(&marker);
-                    self.current_span_style = TextStyle::Normal;
-                    self.current_line_width = marker_width;
+                    self.add_vertical_space(space_lines);
                 }
-                Token::ListItemEnd => {
-                    // Nothing needed — next ListItemStart or ListEnd handles spacing
+                self.open_keep_heading();
+                // Headings are always bold (via self.heading_bold, not self.bold_active)
+                self.heading_bold = true;
+                // Note: Currently we use same font size for all headings
+                // Future: could use larger fonts for h1-h2
+            }
+            Token::Emphasis(start) => {
+                self.flush_partial_word();
+                self.italic_active = *start;
+                self.current_span_style = self.current_style_from_flags(
+                    self.bold_active || self.heading_bold,
+                    self.italic_active || self.in_caption,
+                );
+            }
+            Token::Strong(start) => {
+                self.flush_partial_word();
+                self.bold_active = *start;
+                self.current_span_style = self.current_style_from_flags(
+                    self.bold_active || self.heading_bold,
+                    self.italic_active || self.in_caption,
+                );
+            }
+            Token::LineBreak => {
+                self.flush_line();
+            }
+            // List tokens — track nesting and emit bullet/number prefixes
+            Token::ListStart(ordered) => {
+                self.end_block();
+                self.indent_next = false;
+                self.list_depth += 1;
+                self.list_style_stack.push(if *ordered {
+                    ListStyleType::Decimal
+                } else {
+                    ListStyleType::Disc
+                });
+                self.list_item_counters.push(0);
+            }
+            Token::ListStyle { style, start } => {
+                if let Some(current) = self.list_style_stack.last_mut() {
+                    *current = style.clone();
                 }
-                // Link tokens — text content flows through as Token::Text normally
-                Token::LinkStart(_href) => {
-                    // Text inside the link renders normally via Token::Text
-                    // Future: could track link state for underline rendering
+                if let Some(counter) = self.list_item_counters.last_mut() {
+                    *counter = start.saturating_sub(1);
                 }
-                Token::LinkEnd => {
-                    // End of link — no special rendering needed
+            }
+            Token::ListItemValue(value) => {
+                if let Some(counter) = self.list_item_counters.last_mut() {
+                    *counter = value.saturating_sub(1);
                 }
-                // Image tokens — render a placeholder line
-                Token::Image { src: _, ref alt } => {
-                    self.flush_line();
-                    let placeholder = if alt.is_empty() {
-                        String::from("[Image]")
-                    } else {
-                        format!("[Image: {}]", alt)
-                    };
-                    let width = self
-                        .font_metrics
-                        .text_width(&placeholder, TextStyle::Normal);
-                    self.current_span_text = placeholder;
-                    self.current_span_style = TextStyle::Normal;
-                    self.current_line_width = width;
-                    self.flush_line();
+            }
+            Token::ListEnd => {
+                self.end_block();
+                self.list_depth = self.list_depth.saturating_sub(1);
+                self.list_style_stack.pop();
+                self.list_item_counters.pop();
+                if self.list_depth == 0 {
                     self.add_paragraph_space();
                 }
+                self.indent_next = false;
+            }
+            Token::ListItemStart => {
+                self.end_block();
+                // Increment item counter for the current list level
+                if let Some(counter) = self.list_item_counters.last_mut() {
+                    *counter = counter.saturating_add(1);
+                }
+                // Build indentation prefix based on nesting depth
+                let indent = "  ".repeat(self.list_depth.saturating_sub(1));
+                let count = self.list_item_counters.last().copied().unwrap_or(1);
+                let marker = match self.list_style_stack.last() {
+                    Some(style) => format!("{}{}", indent, style.marker(count)),
+                    None => format!("{}\u{2022}", indent), // bullet: •
+                };
+                let marker_width = self.font_metrics.text_width(&marker, TextStyle::Normal);
+                self.current_span_text.push_str(&marker);
+                self.current_span_style = TextStyle::Normal;
+                self.current_line_width = marker_width;
+            }
+            Token::ListItemEnd => {
+                // Nothing needed — next ListItemStart or ListEnd handles spacing
+            }
+            // Link tokens — text content flows through as Token::Text normally
+            Token::LinkStart(_href) => {
+                // Text inside the link renders normally via Token::Text
+                // Future: could track link state for underline rendering
+            }
+            Token::LinkEnd => {
+                // End of link — no special rendering needed
+            }
+            // Image tokens — render a placeholder line, then reserve the
+            // rest of the declared height
+            Token::Image {
+                ref alt,
+                width,
+                height,
+                ..
+            } => {
+                self.end_block();
+                let rows = self.image_rows(*width, *height);
+                if self.current_line_count > 0
+                    && self.current_line_count + rows > self.max_lines_per_page
+                {
+                    self.break_page();
+                }
+                let placeholder = if alt.is_empty() {
+                    String::from("[Image]")
+                } else {
+                    format!("[Image: {}]", alt)
+                };
+                let width = self
+                    .font_metrics
+                    .text_width(&placeholder, TextStyle::Normal);
+                self.current_span_text = placeholder;
+                self.current_span_style = TextStyle::Normal;
+                self.current_line_width = width;
+                self.end_block();
+                let reserved = rows - 1;
+                self.current_line_count += reserved;
+                self.current_y += self.line_height * reserved as f32;
+                self.add_paragraph_space();
+                self.indent_next = false;
+            }
+            // Preformatted blocks — one line per source line, no re-wrapping
+            Token::PreformattedStart => {
+                self.end_block();
+                self.indent_next = false;
+                self.preformatted = true;
+                self.preformatted_clipped = false;
+            }
+            Token::PreformattedEnd => {
+                self.end_block();
+                self.preformatted = false;
+                self.preformatted_clipped = false;
+            }
+            Token::Anchor(_) => {
+                // Fragment targets take no space on the page
+            }
+            Token::Hr => {
+                self.end_block();
+                self.indent_next = false;
+                self.add_rule();
+            }
+            // A figure's image and caption move to the next page together
+            Token::FigureStart => {
+                self.end_block();
+                self.indent_next = false;
+                self.open_keep_block();
+            }
+            Token::FigureEnd => {
+                self.end_block();
+                // The figure block is complete; nothing further is kept with it
+                self.keep_anchor = None;
+            }
+            // Captions render italic; spacing comes from the following paragraph break
+            Token::CaptionStart => {
+                self.end_block();
+                self.indent_next = false;
+                self.in_caption = true;
+                self.current_span_style = self.current_style_from_flags(
+                    self.bold_active,
+                    self.italic_active || self.in_caption,
+                );
+            }
+            Token::CaptionEnd => {
+                self.end_block();
+                self.in_caption = false;
+                self.current_span_style =
+                    self.current_style_from_flags(self.bold_active, self.italic_active);
             }
         }
+    }
 
-        // Flush any remaining content
-        self.flush_line();
-        self.finalize_page();
+    /// Hand completed pages that later tokens can no longer change to `on_page`.
+    ///
+    /// The most recently completed page is held back while widow control may
+    /// still pull lines from it. Returns the number of pages emitted.
+    pub fn take_settled_pages<F: FnMut(Page)>(&mut self, mut on_page: F) -> usize {
+        let settled = if self.widow_orphan_control.enabled {
+            self.pages.len().saturating_sub(1)
+        } else {
+            self.pages.len()
+        };
+        for page in self.pages.drain(..settled) {
+            on_page(page);
+        }
+        settled
+    }
 
-        core::mem::take(&mut self.pages)
+    /// Flush the chapter and hand all remaining pages to `on_page`.
+    ///
+    /// Returns the number of pages emitted.
+    pub fn finish_chapter<F: FnMut(Page)>(&mut self, mut on_page: F) -> usize {
+        self.end_block();
+        self.finalize_page();
+        let count = self.pages.len();
+        for page in self.pages.drain(..) {
+            on_page(page);
+        }
+        count
     }
 
     /// Reset the layout engine state
@@ -447,8 +757,68 @@ impl LayoutEngine {
         self.page_number = 1;
         self.current_line_count = 0;
         self.list_depth = 0;
-        self.list_ordered_stack.clear();
+        self.list_style_stack.clear();
         self.list_item_counters.clear();
+        self.preformatted = false;
+        self.preformatted_clipped = false;
+        self.indent_next = false;
+        self.current_line_indent = 0.0;
+        self.paragraph_lines_on_page = 0;
+        self.paragraph_lines_on_prev_page = 0;
+        self.keep_anchor = None;
+        self.bold_active = false;
+        self.italic_active = false;
+        self.heading_bold = false;
+        self.in_caption = false;
+    }
+
+    /// Lines an image placeholder occupies: its declared height, scaled down
+    /// with the width to fit the line, capped at one page.
+    fn image_rows(&self, width: Option<Dimension>, height: Option<Dimension>) -> usize {
+        let Some(height) = height else {
+            return 1;
+        };
+        let line_width = (self.page_width - self.right_margin).max(1.0) as u32;
+        let page_height = (self.max_lines_per_page as f32 * self.line_height) as u32;
+        let mut height_px = height.resolve(page_height) as f32;
+        if let Some(width_px) = width.map(|w| w.resolve(line_width)) {
+            if width_px > line_width {
+                height_px *= line_width as f32 / width_px as f32;
+            }
+        }
+        ((height_px / self.line_height).ceil() as usize).clamp(1, self.max_lines_per_page)
+    }
+
+    /// Width available to the line being built, after right margin and indent
+    fn available_width(&self) -> f32 {
+        (self.page_width - self.right_margin - self.current_line_indent).max(0.0)
+    }
+
+    /// Apply a pending first-line indent to a line that is about to receive text
+    fn begin_line(&mut self) {
+        if self.indent_next {
+            self.indent_next = false;
+            if self.paragraph_spacing == ParagraphSpacing::FirstLineIndent {
+                self.current_line_indent = self.indent_width;
+            }
+        }
+    }
+
+    /// Emit a centered rule line for `<hr>`
+    fn add_rule(&mut self) {
+        let available = self.available_width();
+        let char_width = self.font_metrics.char_width_for_style(TextStyle::Normal);
+        if char_width <= 0.0 {
+            return;
+        }
+        let target = available * self.hr_width_ratio.clamp(0.0, 1.0);
+        let count = ((target / char_width) as usize).max(1);
+        let width = count as f32 * char_width;
+        self.current_line_indent = ((available - width) / 2.0).max(0.0);
+        self.current_span_text = core::iter::repeat_n(Self::RULE_CHAR, count).collect();
+        self.current_span_style = TextStyle::Normal;
+        self.current_line_width = width;
+        self.end_block();
     }
 
     /// Get current style based on bold/italic flags
@@ -469,6 +839,92 @@ impl LayoutEngine {
         }
     }
 
+    /// Add preformatted text verbatim, breaking lines only at newlines.
+    ///
+    /// Lines wider than the page are cut and end with
+    /// [`Self::TRUNCATION_MARKER`]; the rest of the source line is dropped.
+    fn add_preformatted_text(&mut self, text: &str, style: TextStyle) {
+        if style != self.current_span_style {
+            self.flush_partial_word();
+            self.current_span_style = style;
+        }
+        let char_width = self.font_metrics.char_width_for_style(style);
+        for ch in text.chars() {
+            match ch {
+                '\n' => self.end_preformatted_line(),
+                '\r' => {}
+                _ if self.preformatted_clipped => {}
+                '\t' => {
+                    let column = self.current_line_chars();
+                    let spaces = Self::TAB_WIDTH - column % Self::TAB_WIDTH;
+                    for _ in 0..spaces {
+                        self.push_preformatted_char(' ', char_width);
+                    }
+                }
+                _ => self.push_preformatted_char(ch, char_width),
+            }
+        }
+    }
+
+    /// Append one preformatted character, clipping the line if it overflows.
+    fn push_preformatted_char(&mut self, ch: char, char_width: f32) {
+        if self.preformatted_clipped {
+            return;
+        }
+        if self.current_line_width + char_width <= self.available_width() {
+            self.current_span_text.push(ch);
+            self.current_line_width += char_width;
+            return;
+        }
+        // Make room for the marker by dropping trailing characters.
+        let marker_width = self
+            .font_metrics
+            .char_width_for_style(self.current_span_style);
+        while self.current_line_width + marker_width > self.available_width() {
+            let removed = match self.current_span_text.pop() {
+                Some(_) => self.current_span_style,
+                None => match self.current_spans.last_mut() {
+                    Some(span) => {
+                        let style = span.style;
+                        span.text.pop();
+                        if span.text.is_empty() {
+                            self.current_spans.pop();
+                        }
+                        style
+                    }
+                    None => break,
+                },
+            };
+            self.current_line_width -= self.font_metrics.char_width_for_style(removed);
+        }
+        self.current_span_text.push(Self::TRUNCATION_MARKER);
+        self.current_line_width += marker_width;
+        self.preformatted_clipped = true;
+    }
+
+    /// Finish a preformatted source line; blank lines still take vertical space.
+    fn end_preformatted_line(&mut self) {
+        if self.current_line_is_empty() {
+            if self.current_line_count >= self.max_lines_per_page {
+                self.break_page();
+            }
+            self.current_line_count += 1;
+            self.current_y += self.line_height;
+        } else {
+            self.flush_line();
+        }
+        self.preformatted_clipped = false;
+    }
+
+    /// Number of characters on the line being built.
+    fn current_line_chars(&self) -> usize {
+        self.current_spans
+            .iter()
+            .map(|span| span.text.chars().count())
+            .sum::<usize>()
+            + self.current_span_text.chars().count()
+    }
+
     /// Check if the current line being built is empty (no spans and no pending text)
     fn current_line_is_empty(&self) -> bool {
         self.current_spans.is_empty() && self.current_span_text.is_empty()
@@ -476,6 +932,9 @@ impl LayoutEngine {
 
     /// Add a single word with greedy line breaking
     fn add_word(&mut self, word: &str, style: TextStyle) {
+        if self.current_line_is_empty() {
+            self.begin_line();
+        }
         let word_width = self.font_metrics.text_width(word, style);
         let space_width = if self.current_line_is_empty() {
             0.0
@@ -485,7 +944,7 @@ impl LayoutEngine {
 
         let total_width = self.current_line_width + space_width + word_width;
 
-        if total_width <= self.page_width || self.current_line_is_empty() {
+        if total_width <= self.available_width() || self.current_line_is_empty() {
             // If style changed from current span, finalize previous span and start new
             if style != self.current_span_style {
                 if !self.current_span_text.is_empty() {
@@ -538,14 +997,13 @@ impl LayoutEngine {
 
         // Check if we need a new page
         if self.current_line_count >= self.max_lines_per_page {
-            self.finalize_page();
-            self.current_y = self.top_margin;
-            self.current_line_count = 0;
+            self.break_page();
         }
 
         // Create the line from accumulated spans
         let line = Line {
             spans: core::mem::take(&mut self.current_spans),
+            x: self.current_line_indent as i32,
             y: self.current_y as i32,
         };
 
@@ -553,21 +1011,153 @@ impl LayoutEngine {
         self.current_line_count += 1;
         self.current_y += self.line_height;
         self.current_line_width = 0.0;
+        self.current_line_indent = 0.0;
+        if !self.preformatted {
+            self.paragraph_lines_on_page += 1;
+        }
+        if let Some(anchor) = self.keep_anchor.as_mut() {
+            if !anchor.heading_open {
+                anchor.body_lines += 1;
+                if anchor.body_lines >= self.keep_with_next.min_lines as usize {
+                    self.keep_anchor = None;
+                }
+            }
+        }
+    }
+
+    /// Mark the start of a heading that must stay with the lines after it
+    fn open_keep_heading(&mut self) {
+        if !self.keep_with_next.enabled || self.keep_with_next.min_lines == 0 {
+            return;
+        }
+        self.open_keep_block();
+    }
+
+    /// Mark the start of a block whose lines move to the next page together
+    fn open_keep_block(&mut self) {
+        // Consecutive headings keep together as one block.
+        if let Some(anchor) = self.keep_anchor.as_mut() {
+            if anchor.body_lines == 0 {
+                anchor.heading_open = true;
+                return;
+            }
+        }
+        self.keep_anchor = Some(KeepAnchor {
+            start: self.current_page_lines.len(),
+            heading_open: true,
+            body_lines: 0,
+        });
+    }
+
+    /// Heading lines are done; start counting the lines that follow
+    fn close_keep_heading(&mut self) {
+        if let Some(anchor) = self.keep_anchor.as_mut() {
+            anchor.heading_open = false;
+        }
+    }
+
+    /// Flush the current line and close the paragraph it belongs to
+    fn end_block(&mut self) {
+        self.flush_line();
+        self.fix_widow();
+        self.paragraph_lines_on_page = 0;
+        self.paragraph_lines_on_prev_page = 0;
+    }
+
+    /// Start a new page, carrying lines that must not stay at the page bottom.
+    ///
+    /// Orphaned paragraph starts (fewer than `min_lines`) and headings still
+    /// waiting for their following lines move to the new page.
+    fn break_page(&mut self) {
+        let control = self.widow_orphan_control;
+        let on_page = self.paragraph_lines_on_page;
+        let page_len = self.current_page_lines.len();
+        let mut carry = if control.enabled
+            && on_page > 0
+            && on_page < control.min_lines as usize
+            && on_page < page_len
+        {
+            on_page
+        } else {
+            0
+        };
+        if let Some(anchor) = self.keep_anchor.take() {
+            if anchor.start > 0 && anchor.start < page_len {
+                carry = carry.max(page_len - anchor.start);
+            }
+        }
+        let carried = self.current_page_lines.split_off(page_len - carry);
+        let carried_top = carried.first().map_or(self.current_y, |line| line.y as f32);
+        let shift = carried_top - self.top_margin;
+        self.finalize_page();
+        self.current_line_count = carried.len();
+        self.current_page_lines = carried;
+        for line in &mut self.current_page_lines {
+            line.y -= shift as i32;
+        }
+        self.current_y -= shift;
+        let carried_paragraph = carry.min(on_page);
+        self.paragraph_lines_on_prev_page = on_page - carried_paragraph;
+        self.paragraph_lines_on_page = carried_paragraph;
+    }
+
+    /// Pull lines from the previous page so a paragraph does not end with a widow.
+    ///
+    /// Only applies when the previous page keeps at least `min_lines` of the
+    /// paragraph and the current page has room for the pulled lines.
+    fn fix_widow(&mut self) {
+        let control = self.widow_orphan_control;
+        let min_lines = control.min_lines as usize;
+        let on_page = self.paragraph_lines_on_page;
+        let on_prev = self.paragraph_lines_on_prev_page;
+        if !control.enabled || on_prev == 0 || on_page == 0 || on_page >= min_lines {
+            return;
+        }
+        let need = min_lines - on_page;
+        if on_prev < need + min_lines || self.current_line_count + need > self.max_lines_per_page {
+            return;
+        }
+        let Some(prev) = self.pages.last_mut() else {
+            return;
+        };
+        let mut pulled = prev.lines.split_off(prev.lines.len() - need);
+        let shift = self.line_height * need as f32;
+        for (i, line) in pulled.iter_mut().enumerate() {
+            line.y = (self.top_margin + self.line_height * i as f32) as i32;
+        }
+        for line in &mut self.current_page_lines {
+            line.y += shift as i32;
+        }
+        pulled.append(&mut self.current_page_lines);
+        self.current_page_lines = pulled;
+        if let Some(anchor) = self.keep_anchor.as_mut() {
+            anchor.start += need;
+        }
+        self.current_line_count += need;
+        self.current_y += shift;
+        self.paragraph_lines_on_page += need;
+        self.paragraph_lines_on_prev_page -= need;
     }
 
-    /// Add paragraph spacing (half line for compact layout)
+    /// Add paragraph spacing according to the configured [`ParagraphSpacing`]
     fn add_paragraph_space(&mut self) {
+        let lines = match self.paragraph_spacing {
+            ParagraphSpacing::BlankLine(lines) => lines,
+            ParagraphSpacing::FirstLineIndent => 0.0,
+        };
+        self.add_vertical_space(lines);
+    }
+
+    /// Add vertical space measured in line heights
+    fn add_vertical_space(&mut self, lines: f32) {
         // Check if we need a new page for the space
         if self.current_line_count >= self.max_lines_per_page {
-            self.finalize_page();
-            self.current_y = self.top_margin;
-            self.current_line_count = 0;
+            self.break_page();
         }
 
-        // Add half line space between paragraphs (12px for 24px line height)
-        // This saves space while maintaining visual separation
+        // No space at the top of a page
         if self.current_line_count > 0 {
-            self.current_y += self.line_height * 0.5;
+            self.current_y += self.line_height * lines;
         }
     }
 
@@ -616,6 +1206,20 @@ pub struct LayoutConfig {
     pub left_margin: f32,
     /// Top margin in pixels
     pub top_margin: f32,
+    /// Right margin in pixels, subtracted from `page_width` for every line
+    pub right_margin: f32,
+    /// Paragraph separation mode
+    pub paragraph_spacing: ParagraphSpacing,
+    /// First-line indent in pixels for [`ParagraphSpacing::FirstLineIndent`]
+    pub indent_width: f32,
+    /// Space before h1/h2 headings in line heights (deeper levels get half)
+    pub heading_spacing: f32,
+    /// Widow/orphan policy for paragraphs split across pages
+    pub widow_orphan_control: WidowOrphanControl,
+    /// Keep-with-next policy for headings
+    pub keep_with_next: KeepWithNext,
+    /// `<hr>` width as a fraction of the line width
+    pub hr_width_ratio: f32,
     /// Font metrics
     pub font_metrics: FontMetrics,
 }
@@ -637,7 +1241,14 @@ impl Default for LayoutConfig {
             page_height: content_height,
             line_height: 26.0, // ~1.3x font height for comfortable reading
             left_margin: LayoutEngine::DEFAULT_MARGIN,
-            top_margin: 0.0, // No top margin - header area handled separately
+            top_margin: 0.0,   // No top margin - header area handled separately
+            right_margin: 0.0, // `page_width` already excludes both side margins
+            paragraph_spacing: ParagraphSpacing::default(),
+            indent_width: LayoutEngine::DEFAULT_INDENT_WIDTH,
+            heading_spacing: LayoutEngine::DEFAULT_HEADING_SPACING,
+            widow_orphan_control: WidowOrphanControl::default(),
+            keep_with_next: KeepWithNext::default(),
+            hr_width_ratio: LayoutEngine::DEFAULT_HR_WIDTH_RATIO,
             font_metrics: FontMetrics::default(),
         }
     }
@@ -649,6 +1260,13 @@ impl LayoutConfig {
         LayoutEngine::new(self.page_width, self.page_height, self.line_height)
             .with_font_metrics(self.font_metrics.clone())
             .with_margins(self.left_margin, self.top_margin)
+            .with_right_margin(self.right_margin)
+            .with_paragraph_spacing(self.paragraph_spacing)
+            .with_indent_width(self.indent_width)
+            .with_heading_spacing(self.heading_spacing)
+            .with_widow_orphan_control(self.widow_orphan_control)
+            .with_keep_with_next(self.keep_with_next)
+            .with_hr_width_ratio(self.hr_width_ratio)
     }
 }
 
@@ -885,6 +1503,46 @@ mod tests {
         assert_eq!(texts[2], "3. Gamma");
     }
 
+    #[test]
+    fn test_list_style_and_value_overrides() {
+        let tokens = vec![
+            Token::ListStart(true),
+            Token::ListStyle {
+                style: ListStyleType::UpperRoman,
+                start: 3,
+            },
+            Token::ListItemStart,
+            Token::Text("Three".to_string()),
+            Token::ListItemEnd,
+            Token::ListItemStart,
+            Token::Text("Four".to_string()),
+            Token::ListItemEnd,
+            Token::ListItemValue(9),
+            Token::ListItemStart,
+            Token::Text("Nine".to_string()),
+            Token::ListItemEnd,
+            Token::ListEnd,
+            Token::ListStart(false),
+            Token::ListStyle {
+                style: ListStyleType::Circle,
+                start: 1,
+            },
+            Token::ListItemStart,
+            Token::Text("Hollow".to_string()),
+            Token::ListItemEnd,
+            Token::ListEnd,
+        ];
+
+        let mut engine = LayoutEngine::new(460.0, 650.0, 20.0);
+        let pages = engine.layout_tokens(&tokens);
+        let texts = collect_line_texts(&pages);
+
+        assert_eq!(
+            texts,
+            vec!["III. Three", "IV. Four", "IX. Nine", "\u{25E6} Hollow"]
+        );
+    }
+
     #[test]
     fn test_nested_list_layout() {
         let tokens = vec![
@@ -926,6 +1584,8 @@ mod tests {
         let tokens = vec![Token::Image {
             src: "img/cover.jpg".to_string(),
             alt: "Book cover".to_string(),
+            width: None,
+            height: None,
         }];
 
         let mut engine = LayoutEngine::new(460.0, 650.0, 20.0);
@@ -941,6 +1601,8 @@ mod tests {
         let tokens = vec![Token::Image {
             src: "img/photo.png".to_string(),
             alt: String::with_capacity(0),
+            width: None,
+            height: None,
         }];
 
         let mut engine = LayoutEngine::new(460.0, 650.0, 20.0);
@@ -951,6 +1613,35 @@ mod tests {
         assert_eq!(texts[0], "[Image]");
     }
 
+    #[test]
+    fn test_image_placeholder_reserves_declared_height() {
+        let image = |width, height| Token::Image {
+            src: "img/map.png".to_string(),
+            alt: "Map".to_string(),
+            width,
+            height: Some(height),
+        };
+        let tokens = vec![
+            image(Some(Dimension::Px(920)), Dimension::Px(200)),
+            Token::Text("After".to_string()),
+            Token::ParagraphBreak,
+            image(None, Dimension::Percent(100)),
+        ];
+
+        let mut engine = LayoutEngine::new(460.0, 300.0, 20.0);
+        let pages = engine.layout_tokens(&tokens);
+
+        // 200px scaled to the 460px line is 100px: five rows, then the
+        // half-line paragraph space, before "After".
+        let first = &pages[0].lines;
+        assert_eq!(first[0].text(), "[Image: Map]");
+        assert_eq!(first[1].text(), "After");
+        assert_eq!(first[1].y - first[0].y, 100 + 10);
+        // A full-page image cannot share the page and moves to the next one.
+        assert_eq!(pages.len(), 2);
+        assert_eq!(pages[1].lines[0].text(), "[Image: Map]");
+    }
+
     #[test]
     fn test_link_text_renders_normally() {
         let tokens = vec![
@@ -993,6 +1684,8 @@ mod tests {
             Token::Image {
                 src: "fig1.png".to_string(),
                 alt: "Figure 1".to_string(),
+                width: None,
+                height: None,
             },
             // Link in paragraph
             Token::Text("Visit ".to_string()),
@@ -1086,6 +1779,8 @@ mod tests {
             Token::Image {
                 src: "img.png".to_string(),
                 alt: "Alt text".to_string(),
+                width: None,
+                height: None,
             },
             Token::LineBreak,
             Token::Text("Final line.".to_string()),
@@ -1289,6 +1984,7 @@ mod tests {
                 bold_char_width: 9.0,
                 italic_char_width: 8.0,
             },
+            ..LayoutConfig::default()
         };
 
         let mut engine = config.create_engine();
@@ -1537,4 +2233,318 @@ mod tests {
         let total_lines: usize = pages.iter().map(|p| p.line_count()).sum();
         assert!(total_lines >= 50);
     }
+
+    #[test]
+    fn test_preformatted_keeps_source_lines() {
+        let tokens = vec![
+            Token::Text("Intro".to_string()),
+            Token::ParagraphBreak,
+            Token::PreformattedStart,
+            Token::Text("fn main() {\n    run();\n}\n".to_string()),
+            Token::PreformattedEnd,
+            Token::ParagraphBreak,
+            Token::Text("After".to_string()),
+        ];
+        let mut engine = LayoutEngine::new(460.0, 650.0, 20.0);
+        let pages = engine.layout_tokens(&tokens);
+        assert_eq!(
+            collect_line_texts(&pages),
+            vec!["Intro", "fn main() {", "    run();", "}", "After"]
+        );
+    }
+
+    #[test]
+    fn test_preformatted_blank_line_takes_vertical_space() {
+        let tokens = vec![
+            Token::PreformattedStart,
+            Token::Text("a\n\nb".to_string()),
+            Token::PreformattedEnd,
+        ];
+        let mut engine = LayoutEngine::new(460.0, 650.0, 20.0);
+        let pages = engine.layout_tokens(&tokens);
+        let lines = &pages[0].lines;
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[1].y - lines[0].y, 40);
+    }
+
+    #[test]
+    fn test_preformatted_long_line_is_truncated_not_wrapped() {
+        // 10px glyphs on a 100px page: 10 columns per line.
+        let tokens = vec![
+            Token::PreformattedStart,
+            Token::Text("0123456789abcdef\nok".to_string()),
+            Token::PreformattedEnd,
+        ];
+        let mut engine = LayoutEngine::new(100.0, 650.0, 20.0);
+        let pages = engine.layout_tokens(&tokens);
+        assert_eq!(collect_line_texts(&pages), vec!["012345678\u{2026}", "ok"]);
+    }
+
+    #[test]
+    fn test_preformatted_truncation_spans_style_changes() {
+        let tokens = vec![
+            Token::PreformattedStart,
+            Token::Text("let x".to_string()),
+            Token::Strong(true),
+            Token::Text(" = 1234567;".to_string()),
+            Token::Strong(false),
+            Token::PreformattedEnd,
+        ];
+        let mut engine = LayoutEngine::new(100.0, 650.0, 20.0);
+        let pages = engine.layout_tokens(&tokens);
+        let line = &pages[0].lines[0];
+        assert_eq!(line.text(), "let x = 1\u{2026}");
+        assert_eq!(line.spans[0].style, TextStyle::Normal);
+        assert_eq!(line.spans[1].style, TextStyle::Bold);
+    }
+
+    #[test]
+    fn test_preformatted_expands_tabs() {
+        let tokens = vec![
+            Token::PreformattedStart,
+            Token::Text("a\tb".to_string()),
+            Token::PreformattedEnd,
+        ];
+        let mut engine = LayoutEngine::new(460.0, 650.0, 20.0);
+        let pages = engine.layout_tokens(&tokens);
+        assert_eq!(collect_line_texts(&pages), vec!["a   b"]);
+    }
+
+    fn two_paragraphs_after_heading() -> Vec<Token> {
+        vec![
+            Token::Heading(1),
+            Token::Text("Title".to_string()),
+            Token::ParagraphBreak,
+            Token::Text("First paragraph.".to_string()),
+            Token::ParagraphBreak,
+            Token::Text("Second paragraph.".to_string()),
+            Token::ParagraphBreak,
+        ]
+    }
+
+    #[test]
+    fn test_first_line_indent_mode() {
+        let mut engine = LayoutEngine::new(460.0, 650.0, 20.0)
+            .with_paragraph_spacing(ParagraphSpacing::FirstLineIndent)
+            .with_indent_width(30.0);
+        let pages = engine.layout_tokens(&two_paragraphs_after_heading());
+        let lines = &pages[0].lines;
+        assert_eq!(lines.len(), 3);
+        // Text right after a heading starts flush; following paragraphs indent.
+        assert_eq!(lines[1].x, 0);
+        assert_eq!(lines[2].x, 30);
+        // No vertical gap between paragraphs in indent mode.
+        assert_eq!(lines[2].y - lines[1].y, 20);
+    }
+
+    #[test]
+    fn test_blank_line_spacing_fraction() {
+        let mut engine = LayoutEngine::new(460.0, 650.0, 20.0)
+            .with_paragraph_spacing(ParagraphSpacing::BlankLine(1.0));
+        let pages = engine.layout_tokens(&two_paragraphs_after_heading());
+        let lines = &pages[0].lines;
+        assert_eq!(lines[2].y - lines[1].y, 40);
+        assert!(lines.iter().all(|line| line.x == 0));
+    }
+
+    #[test]
+    fn test_heading_spacing_configurable() {
+        let tokens = vec![
+            Token::Text("Intro.".to_string()),
+            Token::ParagraphBreak,
+            Token::Heading(2),
+            Token::Text("Section".to_string()),
+            Token::ParagraphBreak,
+        ];
+        let default_gap = {
+            let pages = LayoutEngine::new(460.0, 650.0, 20.0).layout_tokens(&tokens);
+            pages[0].lines[1].y - pages[0].lines[0].y
+        };
+        // Paragraph gap (0.5) + default heading spacing (1.0) on a 20px line.
+        assert_eq!(default_gap, 50);
+
+        let pages = LayoutEngine::new(460.0, 650.0, 20.0)
+            .with_heading_spacing(2.0)
+            .layout_tokens(&tokens);
+        assert_eq!(pages[0].lines[1].y - pages[0].lines[0].y, 70);
+    }
+
+    #[test]
+    fn test_right_margin_narrows_lines() {
+        let tokens = vec![Token::Text("aaaa bbbb cccc".to_string())];
+        let mut engine = LayoutEngine::new(140.0, 650.0, 20.0);
+        assert_eq!(collect_line_texts(&engine.layout_tokens(&tokens)).len(), 1);
+
+        let mut engine = LayoutEngine::new(140.0, 650.0, 20.0).with_right_margin(60.0);
+        assert_eq!(
+            collect_line_texts(&engine.layout_tokens(&tokens)),
+            vec!["aaaa", "bbbb", "cccc"]
+        );
+    }
+
+    #[test]
+    fn test_layout_config_spacing_defaults_preserve_legacy_layout() {
+        let config = LayoutConfig::default();
+        assert_eq!(config.paragraph_spacing, ParagraphSpacing::BlankLine(0.5));
+        assert_eq!(config.right_margin, 0.0);
+        assert_eq!(config.heading_spacing, 1.0);
+    }
+
+    fn paragraph(words: usize, tag: &str) -> Vec<Token> {
+        let text = (0..words)
+            .map(|i| format!("{}{:03}", tag, i))
+            .collect::<Vec<_>>()
+            .join(" ");
+        vec![Token::Text(text), Token::ParagraphBreak]
+    }
+
+    fn page_texts(pages: &[Page]) -> Vec<Vec<String>> {
+        pages
+            .iter()
+            .map(|p| p.lines.iter().map(|l| l.text()).collect())
+            .collect()
+    }
+
+    /// One 4-char word per line, four lines per page.
+    fn narrow_engine(min_lines: u8) -> LayoutEngine {
+        LayoutEngine::new(50.0, 120.0, 20.0).with_widow_orphan_control(WidowOrphanControl {
+            min_lines,
+            enabled: true,
+        })
+    }
+
+    #[test]
+    fn test_orphan_line_moves_to_next_page() {
+        let mut tokens = paragraph(3, "a");
+        tokens.extend(paragraph(4, "b"));
+
+        let pages = LayoutEngine::new(50.0, 120.0, 20.0).layout_tokens(&tokens);
+        assert_eq!(pages[0].line_count(), 4);
+
+        let pages = narrow_engine(2).layout_tokens(&tokens);
+        let texts = page_texts(&pages);
+        assert_eq!(texts[0], vec!["a000", "a001", "a002"]);
+        assert_eq!(texts[1], vec!["b000", "b001", "b002", "b003"]);
+        assert_eq!(pages[1].lines[0].y, 0);
+    }
+
+    #[test]
+    fn test_widow_pulls_line_from_previous_page() {
+        let mut tokens = paragraph(1, "a");
+        tokens.extend(paragraph(4, "b"));
+
+        let pages = narrow_engine(2).layout_tokens(&tokens);
+        let texts = page_texts(&pages);
+        assert_eq!(texts[0], vec!["a000", "b000", "b001"]);
+        assert_eq!(texts[1], vec!["b002", "b003"]);
+        let ys: Vec<i32> = pages[1].lines.iter().map(|l| l.y).collect();
+        assert_eq!(ys, vec![0, 20]);
+    }
+
+    #[test]
+    fn test_widow_control_keeps_short_paragraph_start() {
+        // Pulling would leave a single line behind, so the split stays as is.
+        let mut tokens = paragraph(2, "a");
+        tokens.extend(paragraph(3, "b"));
+
+        let pages = narrow_engine(2).layout_tokens(&tokens);
+        let texts = page_texts(&pages);
+        assert_eq!(texts[0], vec!["a000", "a001", "b000", "b001"]);
+        assert_eq!(texts[1], vec!["b002"]);
+    }
+
+    #[test]
+    fn test_heading_moves_with_following_line() {
+        let mut tokens = paragraph(3, "a");
+        tokens.push(Token::Heading(3));
+        tokens.push(Token::Text("Head".to_string()));
+        tokens.push(Token::ParagraphBreak);
+        tokens.extend(paragraph(2, "b"));
+
+        let pages = LayoutEngine::new(50.0, 120.0, 20.0).layout_tokens(&tokens);
+        assert_eq!(page_texts(&pages)[0].last().unwrap(), "Head");
+
+        let pages = LayoutEngine::new(50.0, 120.0, 20.0)
+            .with_keep_with_next(KeepWithNext {
+                min_lines: 1,
+                enabled: true,
+            })
+            .layout_tokens(&tokens);
+        let texts = page_texts(&pages);
+        assert_eq!(texts[0], vec!["a000", "a001", "a002"]);
+        assert_eq!(texts[1], vec!["Head", "b000", "b001"]);
+        assert_eq!(pages[1].lines[0].y, 0);
+    }
+
+    #[test]
+    fn test_keep_with_next_min_lines() {
+        let mut tokens = paragraph(2, "a");
+        tokens.push(Token::Heading(3));
+        tokens.push(Token::Text("Head".to_string()));
+        tokens.push(Token::ParagraphBreak);
+        tokens.extend(paragraph(3, "b"));
+
+        let keep = |min_lines| {
+            let pages = LayoutEngine::new(50.0, 120.0, 20.0)
+                .with_keep_with_next(KeepWithNext {
+                    min_lines,
+                    enabled: true,
+                })
+                .layout_tokens(&tokens);
+            page_texts(&pages)
+        };
+        // One body line fits under the heading, satisfying min_lines = 1.
+        assert_eq!(keep(1)[0], vec!["a000", "a001", "Head", "b000"]);
+        // Two lines are required, so the heading starts the next page.
+        let texts = keep(2);
+        assert_eq!(texts[0], vec!["a000", "a001"]);
+        assert_eq!(texts[1], vec!["Head", "b000", "b001", "b002"]);
+    }
+
+    #[test]
+    fn test_hr_renders_centered_rule() {
+        let tokens = vec![
+            Token::Text("a".to_string()),
+            Token::ParagraphBreak,
+            Token::Hr,
+        ];
+        let pages = LayoutEngine::new(400.0, 200.0, 20.0).layout_tokens(&tokens);
+        let rule = &pages[0].lines[1];
+        assert_eq!(rule.text(), "\u{2500}".repeat(20));
+        assert_eq!(rule.x, 100);
+
+        let pages = LayoutEngine::new(400.0, 200.0, 20.0)
+            .with_hr_width_ratio(1.0)
+            .layout_tokens(&tokens);
+        assert_eq!(pages[0].lines[1].x, 0);
+        assert_eq!(pages[0].lines[1].text().chars().count(), 40);
+    }
+
+    #[test]
+    fn test_caption_is_italic_and_kept_with_figure() {
+        let mut tokens = paragraph(2, "a");
+        tokens.extend([
+            Token::FigureStart,
+            Token::Image {
+                src: "f.png".to_string(),
+                alt: String::with_capacity(0),
+                width: None,
+                height: None,
+            },
+            Token::CaptionStart,
+            Token::Text("cap0 cap1".to_string()),
+            Token::CaptionEnd,
+            Token::FigureEnd,
+        ]);
+        let pages = LayoutEngine::new(50.0, 120.0, 20.0)
+            .with_keep_with_next(KeepWithNext {
+                min_lines: 1,
+                enabled: false,
+            })
+            .layout_tokens(&tokens);
+        let texts = page_texts(&pages);
+        assert_eq!(texts[0], vec!["a000", "a001"]);
+        assert_eq!(texts[1], vec!["[Image]", "cap0", "cap1"]);
+        assert_eq!(pages[1].lines[1].spans[0].style, TextStyle::Italic);
+    }
 }
@@ -7,6 +7,8 @@
 //!
 //! - `std` (default) -- enables streaming ZIP reader and file I/O
 //! - `layout` -- text layout engine for pagination
+//! - `serde` -- `Serialize`/`Deserialize` for persisted state (reading
+//!   positions, navigation, validation reports) with snake_case field names
 //!
 //! # Allocation Behavior
 //!
@@ -46,24 +48,54 @@ extern crate alloc;
 
 pub mod css;
 pub mod error;
+pub mod lang;
 pub mod metadata;
 pub mod navigation;
+pub mod persist;
 pub mod spine;
+pub mod storage;
 pub mod streaming;
 pub mod tokenizer;
 
 #[cfg(feature = "layout")]
 pub mod layout;
 
+#[cfg(feature = "std")]
+mod digest;
+
+#[cfg(feature = "std")]
+mod cmap;
+
 #[cfg(feature = "std")]
 pub mod book;
 
+#[cfg(feature = "std")]
+pub mod export;
+
+#[cfg(feature = "std")]
+pub mod extract;
+
+#[cfg(feature = "std")]
+pub mod search;
+
+#[cfg(feature = "std")]
+pub mod split;
+
+#[cfg(feature = "std")]
+pub mod sync_book;
+
 #[cfg(feature = "std")]
 pub mod validate;
 
 #[cfg(feature = "std")]
 pub mod render_prep;
 
+#[cfg(feature = "std")]
+pub mod rewrite;
+
+#[cfg(feature = "std")]
+pub mod styled_stream;
+
 #[cfg(feature = "async")]
 pub mod async_api;
 
@@ -72,43 +104,84 @@ pub mod zip;
 
 // Re-export key types for convenience
 #[cfg(feature = "async")]
-pub use async_api::{open_epub_file_async, open_epub_file_async_with_options};
+pub use async_api::{
+    open_epub_file_async, open_epub_file_async_with_options, AsyncEpubBook, PrefetchOptions,
+};
 #[cfg(feature = "std")]
 pub use book::{
     parse_epub_file, parse_epub_file_with_options, parse_epub_reader,
-    parse_epub_reader_with_options, ChapterRef, ChapterStreamResult, EpubBook, EpubBookBuilder,
-    EpubBookOptions, EpubSummary, Locator, PaginationSession, ReadingPosition, ReadingSession,
-    ResolvedLocation, ValidationMode,
+    parse_epub_reader_with_options, parse_epub_storage, parse_epub_storage_with_options, BookStats,
+    ChapterComplexity, ChapterRef, ChapterStreamResult, EpubBook, EpubBookBuilder, EpubBookOptions,
+    EpubSummary, FindOptions, Locator, OpenPhaseStats, OpenProfile, PaginationSession,
+    ReadingSession, ResolvedLocation, ScriptedContentSummary, SessionWithBook, TextExtractOptions,
+    TextHit, ValidationMode,
+};
+pub use css::{
+    BreakBefore, CssStyle, CustomProperties, Dimension, Display, Float, ListStyleType,
+    StyleDeclarations, Stylesheet,
 };
-pub use css::{CssStyle, Stylesheet};
 pub use error::{
     EpubError, ErrorLimitContext, ErrorPhase, LimitKind, PhaseError, PhaseErrorContext, ZipError,
     ZipErrorKind,
 };
-pub use metadata::EpubMetadata;
+#[cfg(feature = "std")]
+pub use export::{ExportFormat, ExportOptions};
+#[cfg(feature = "std")]
+pub use extract::{
+    ExtractOptions, ExtractProgress, ExtractReport, ExtractedResource, ResourceFilter, SkipReason,
+    SkippedResource,
+};
+pub use lang::{detect_language, Language, LanguageGuess};
+pub use metadata::{find_opf_conflicts, EpubMetadata, OpfConflict};
 pub use navigation::Navigation;
+pub use persist::ReadingPosition;
 #[cfg(feature = "std")]
 pub use render_prep::{
-    BlockRole, ChapterStylesheets, ComputedTextStyle, EmbeddedFontFace, EmbeddedFontStyle,
-    FontFallbackPolicy, FontLimits, FontPolicy, FontResolutionTrace, FontResolver, LayoutHints,
-    MemoryBudget, PreparedChapter, RenderPrep, RenderPrepError, RenderPrepOptions, RenderPrepTrace,
-    ResolvedFontFace, StyleConfig, StyleLimits, StyledChapter, StyledEvent, StyledEventOrRun,
-    StyledRun, Styler, StylesheetSource,
+    BlockRole, ChapterEventIter, ChapterStylesheets, ChapterTitleResolver, ComputedTextStyle,
+    EmbeddedFontFace, EmbeddedFontStyle, FontFallbackPolicy, FontLimits, FontObfuscation,
+    FontObfuscationMap, FontPolicy, FontRegistration, FontRegistrationStatus, FontResolutionTrace,
+    FontResolver, FontSkipReason, FontSynthesis, FootnoteNumbering, HeadingLevelStyle, LayoutHints,
+    MemoryBudget, MissingGlyph, MissingGlyphReport, NoterefFormat, PreparedChapter, QuoteStyle,
+    RenderPrep, RenderPrepError, RenderPrepOptions, RenderPrepTrace, ResolvedFontFace,
+    SmartPunctuation, StyleConfig, StyleLimits, StyledChapter, StyledEvent, StyledEventOrRun,
+    StyledRun, Styler, StylesheetSource, TypeRamp,
 };
+#[cfg(feature = "std")]
+pub use search::{SearchBudget, SearchCursor, SearchSession, SearchStep};
 pub use spine::Spine;
+#[cfg(feature = "std")]
+pub use split::{plan_segments, ChapterSegment, ChapterSplitOptions};
+pub use storage::RandomAccess;
+#[cfg(feature = "std")]
+pub use storage::ReadSeekAdapter;
 pub use streaming::{
-    ChunkAllocator, ChunkLimits, PaginationContext, ScratchBuffers, StreamingChapterProcessor,
-    StreamingStats,
+    AdaptiveChunkSizer, ChunkAllocator, ChunkLimits, MicrosClock, PaginationContext, PhaseMicros,
+    RequiredSizes, ScratchBuffer, ScratchBuffers, ScratchBuffersBuilder, ScratchSizeError,
+    StreamingChapterProcessor, StreamingStats,
 };
+#[cfg(feature = "std")]
+pub use styled_stream::{
+    FilterEvents, MergeRuns, SplitOn, StyledItem, StyledStreamExt, TakeTextBudget,
+};
+#[cfg(feature = "std")]
+pub use sync_book::SyncEpubBook;
 pub use tokenizer::{
-    tokenize_html_into, tokenize_html_limited, tokenize_html_with_scratch, Token, TokenizeError,
-    TokenizeLimits, TokenizeScratch,
+    tokenize_html_into, tokenize_html_limited, tokenize_html_with_scratch,
+    tokenize_html_with_spans, tokenize_html_with_stylesheets, NestingOverflow, PrefixCache,
+    PrefixCacheStats, Span, SpannedToken, Token, TokenizeError, TokenizeLimits, TokenizeScratch,
+    Tokenizer,
 };
 #[cfg(feature = "std")]
 pub use validate::{
-    validate_epub_file, validate_epub_file_with_options, validate_epub_reader,
-    validate_epub_reader_with_options, ValidationDiagnostic, ValidationOptions, ValidationReport,
+    validate_dir, validate_epub_file, validate_epub_file_with_options, validate_epub_reader,
+    validate_epub_reader_with_options, BookValidation, CssAnalysisOptions, DeviceProfile,
+    DigestAlgorithm, DigestStatus, DirValidationOptions, DirValidationSummary, OversizedImage,
+    SignedReference, StylesheetStats, ValidationDiagnostic, ValidationOptions, ValidationReport,
     ValidationSeverity,
 };
+#[cfg(feature = "async")]
+pub use zip::AsyncStreamingZip;
+#[cfg(all(feature = "std", target_has_atomic = "64"))]
+pub use zip::SharedDecompressedTotal;
 #[cfg(feature = "std")]
 pub use zip::ZipLimits;
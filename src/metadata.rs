@@ -5,6 +5,7 @@
 
 extern crate alloc;
 
+use alloc::collections::BTreeMap;
 use alloc::format;
 use alloc::string::{String, ToString};
 use alloc::vec::Vec;
@@ -12,6 +13,7 @@ use quick_xml::events::Event;
 use quick_xml::reader::Reader;
 
 use crate::error::EpubError;
+use crate::spine::MAX_SPINE_ITEMS;
 
 /// Maximum number of manifest items (fixed-size constraint)
 const MAX_MANIFEST_ITEMS: usize = 1024;
@@ -23,7 +25,8 @@ const MAX_SUBJECTS: usize = 64;
 const MAX_GUIDE_REFS: usize = 64;
 
 /// A single item in the EPUB manifest (id -> href mapping)
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ManifestItem {
     /// Resource identifier
     pub id: String,
@@ -33,10 +36,31 @@ pub struct ManifestItem {
     pub media_type: String,
     /// Optional properties (e.g. "cover-image", "nav")
     pub properties: Option<String>,
+    /// Id of the manifest item to use when this media type is unsupported
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub fallback: Option<String>,
+}
+
+impl ManifestItem {
+    /// Whether the space-separated `properties` attribute contains `name`.
+    pub fn has_property(&self, name: &str) -> bool {
+        self.properties
+            .as_deref()
+            .is_some_and(|props| props.split_whitespace().any(|prop| prop == name))
+    }
+
+    /// Whether the reader can display this item as a spine entry
+    /// (an XHTML/HTML content document or a raster image).
+    pub fn is_displayable(&self) -> bool {
+        let media_type = self.media_type.as_str();
+        matches!(media_type, "application/xhtml+xml" | "text/html")
+            || (media_type.starts_with("image/") && media_type != "image/svg+xml")
+    }
 }
 
 /// A reference from the EPUB 2.0 `<guide>` element
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct GuideRef {
     /// Reference type (e.g. "cover", "toc", "text")
     pub guide_type: String,
@@ -47,7 +71,7 @@ pub struct GuideRef {
 }
 
 /// EPUB metadata extracted from content.opf
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct EpubMetadata {
     /// Book title
     pub title: String,
@@ -72,6 +96,9 @@ pub struct EpubMetadata {
     /// Subject tags (dc:subject) — can have multiple
     pub subjects: Vec<String>,
     /// Unique identifier (dc:identifier) — ISBN, UUID, etc.
+    ///
+    /// The identifier named by `<package unique-identifier>` wins over other
+    /// `dc:identifier` entries; it keys font de-obfuscation.
     pub identifier: Option<String>,
 
     // -- EPUB-specific metadata --
@@ -122,6 +149,22 @@ impl EpubMetadata {
         self.manifest.iter().find(|item| item.id == id)
     }
 
+    /// Follow the `fallback` chain from item `id` to the first displayable item
+    ///
+    /// Returns `id`'s own item when it is displayable. Returns `None` when the
+    /// chain ends, references a missing item, or loops without reaching one.
+    pub fn displayable_item(&self, id: &str) -> Option<&ManifestItem> {
+        let mut item = self.get_item(id)?;
+        // Each hop visits a distinct item, so a longer walk must be a cycle.
+        for _ in 0..self.manifest.len() {
+            if item.is_displayable() {
+                return Some(item);
+            }
+            item = self.get_item(item.fallback.as_deref()?)?;
+        }
+        None
+    }
+
     /// Get cover image manifest item
     pub fn get_cover_item(&self) -> Option<&ManifestItem> {
         self.cover_id.as_ref().and_then(|id| self.get_item(id))
@@ -203,6 +246,9 @@ pub fn parse_opf(content: &[u8]) -> Result<EpubMetadata, EpubError> {
     let mut in_spine = false;
     let mut in_guide = false;
     let mut current_meta_property: Option<String> = None;
+    let mut unique_identifier_ref: Option<String> = None;
+    let mut current_identifier_id: Option<String> = None;
+    let mut found_unique_identifier = false;
 
     loop {
         match reader.read_event_into(&mut buf) {
@@ -213,6 +259,13 @@ pub fn parse_opf(content: &[u8]) -> Result<EpubMetadata, EpubError> {
                     .map_err(|e| EpubError::Parse(format!("Decode error: {:?}", e)))?
                     .to_string();
 
+                if name == "package" || name.ends_with(":package") {
+                    unique_identifier_ref = attribute_value(&e, &reader, "unique-identifier")?;
+                }
+                if in_metadata && matches!(name.as_str(), "identifier" | "dc:identifier") {
+                    current_identifier_id = attribute_value(&e, &reader, "id")?;
+                }
+
                 // Track which section we're in
                 match name.as_str() {
                     "metadata" => in_metadata = true,
@@ -225,15 +278,7 @@ pub fn parse_opf(content: &[u8]) -> Result<EpubMetadata, EpubError> {
                 // Parse manifest item
                 if in_manifest && name == "item" && metadata.manifest.len() < MAX_MANIFEST_ITEMS {
                     if let Some(item) = parse_manifest_item(&e, &reader)? {
-                        // Check if this is a cover image (EPUB3)
-                        if item
-                            .properties
-                            .as_ref()
-                            .is_some_and(|p| p.contains("cover-image"))
-                        {
-                            metadata.cover_id = Some(item.id.clone());
-                        }
-                        metadata.manifest.push(item);
+                        push_manifest_item(&mut metadata, item);
                     }
                 }
 
@@ -338,13 +383,16 @@ pub fn parse_opf(content: &[u8]) -> Result<EpubMetadata, EpubError> {
                         "description" | "dc:description" => {
                             metadata.description = Some(text);
                         }
-                        "subject" | "dc:subject" => {
-                            if metadata.subjects.len() < MAX_SUBJECTS {
-                                metadata.subjects.push(text);
-                            }
+                        "subject" | "dc:subject" if metadata.subjects.len() < MAX_SUBJECTS => {
+                            metadata.subjects.push(text);
                         }
                         "identifier" | "dc:identifier" => {
-                            metadata.identifier = Some(text);
+                            let is_unique = unique_identifier_ref.is_some()
+                                && current_identifier_id == unique_identifier_ref;
+                            if is_unique || !found_unique_identifier {
+                                metadata.identifier = Some(text);
+                            }
+                            found_unique_identifier |= is_unique;
                         }
                         _ => {}
                     }
@@ -378,14 +426,7 @@ pub fn parse_opf(content: &[u8]) -> Result<EpubMetadata, EpubError> {
                 // Handle empty manifest items
                 if in_manifest && name == "item" && metadata.manifest.len() < MAX_MANIFEST_ITEMS {
                     if let Some(item) = parse_manifest_item(&e, &reader)? {
-                        if item
-                            .properties
-                            .as_ref()
-                            .is_some_and(|p| p.contains("cover-image"))
-                        {
-                            metadata.cover_id = Some(item.id.clone());
-                        }
-                        metadata.manifest.push(item);
+                        push_manifest_item(&mut metadata, item);
                     }
                 }
 
@@ -457,6 +498,180 @@ pub fn parse_opf(content: &[u8]) -> Result<EpubMetadata, EpubError> {
     Ok(metadata)
 }
 
+/// Add `item` to the manifest; a later item with an existing id replaces the
+/// earlier one in place (last-wins, see [`OpfConflict::DuplicateManifestId`]).
+fn push_manifest_item(metadata: &mut EpubMetadata, item: ManifestItem) {
+    // Check if this is a cover image (EPUB3)
+    if item
+        .properties
+        .as_ref()
+        .is_some_and(|p| p.contains("cover-image"))
+    {
+        metadata.cover_id = Some(item.id.clone());
+    }
+    match metadata.manifest.iter_mut().find(|m| m.id == item.id) {
+        Some(existing) => *existing = item,
+        None => metadata.manifest.push(item),
+    }
+}
+
+/// A duplicate or contradictory declaration in the package document.
+///
+/// Opening a book resolves every conflict last-wins: the later declaration
+/// takes effect and the earlier one is discarded. Strict opens reject packages with
+/// conflicts instead (see [`EpubError::OpfConflicts`]).
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum OpfConflict {
+    /// Two manifest items share an `id`; the later item replaces the earlier.
+    DuplicateManifestId {
+        /// The shared id.
+        id: String,
+        /// `href` of the replaced item.
+        overridden_href: String,
+        /// `href` of the item that took effect.
+        href: String,
+    },
+    /// Two spine `itemref`s name the same item; only the later is kept.
+    DuplicateSpineIdref {
+        /// The shared idref.
+        idref: String,
+        /// Document-order index of the dropped `itemref`.
+        dropped_index: usize,
+        /// Document-order index of the kept `itemref`.
+        index: usize,
+    },
+    /// Several `<spine toc>` attributes disagree; the last one is used.
+    ConflictingToc {
+        /// The overridden toc id.
+        overridden: String,
+        /// The toc id that took effect.
+        toc: String,
+    },
+}
+
+impl core::fmt::Display for OpfConflict {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::DuplicateManifestId {
+                id,
+                overridden_href,
+                href,
+            } => write!(
+                f,
+                "manifest id '{}' declared twice ('{}' overridden by '{}')",
+                id, overridden_href, href
+            ),
+            Self::DuplicateSpineIdref {
+                idref,
+                dropped_index,
+                index,
+            } => write!(
+                f,
+                "spine idref '{}' repeated (itemref {} dropped for itemref {})",
+                idref, dropped_index, index
+            ),
+            Self::ConflictingToc { overridden, toc } => {
+                write!(f, "spine toc '{}' overridden by '{}'", overridden, toc)
+            }
+        }
+    }
+}
+
+/// Scan an OPF for declarations that opening a book resolves last-wins.
+///
+/// Conflicts are listed in document order. Like the parsers, the scan stops
+/// looking at manifest items and itemrefs past their fixed caps.
+pub fn find_opf_conflicts(content: &[u8]) -> Result<Vec<OpfConflict>, EpubError> {
+    let mut reader = Reader::from_reader(content);
+    reader.config_mut().trim_text(true);
+
+    let mut buf = Vec::with_capacity(0);
+    let mut conflicts = Vec::with_capacity(0);
+    let mut manifest: BTreeMap<String, String> = BTreeMap::new();
+    let mut manifest_count = 0usize;
+    let mut itemrefs: BTreeMap<String, usize> = BTreeMap::new();
+    let mut itemref_count = 0usize;
+    let mut toc_count = 0usize;
+    let mut toc: Option<String> = None;
+    let mut in_manifest = false;
+    let mut in_spine = false;
+
+    loop {
+        match reader.read_event_into(&mut buf) {
+            Ok(Event::Start(e)) | Ok(Event::Empty(e)) => {
+                let name = reader
+                    .decoder()
+                    .decode(e.name().as_ref())
+                    .map_err(|e| EpubError::Parse(format!("Decode error: {:?}", e)))?
+                    .to_string();
+                match name.as_str() {
+                    "manifest" => in_manifest = true,
+                    "spine" => {
+                        in_spine = true;
+                        toc_count += 1;
+                        let value = if toc_count <= MAX_SPINE_ITEMS {
+                            attribute_value(&e, &reader, "toc")?
+                        } else {
+                            None
+                        };
+                        if let Some(value) = value.filter(|value| !value.is_empty()) {
+                            match toc.replace(value.clone()) {
+                                Some(previous) if previous != value => {
+                                    conflicts.push(OpfConflict::ConflictingToc {
+                                        overridden: previous,
+                                        toc: value,
+                                    });
+                                }
+                                _ => {}
+                            }
+                        }
+                    }
+                    "item" if in_manifest && manifest_count < MAX_MANIFEST_ITEMS => {
+                        if let Some(item) = parse_manifest_item(&e, &reader)? {
+                            manifest_count += 1;
+                            if let Some(overridden_href) =
+                                manifest.insert(item.id.clone(), item.href.clone())
+                            {
+                                conflicts.push(OpfConflict::DuplicateManifestId {
+                                    id: item.id,
+                                    overridden_href,
+                                    href: item.href,
+                                });
+                            }
+                        }
+                    }
+                    "itemref" if in_spine && itemref_count < MAX_SPINE_ITEMS => {
+                        if let Some(idref) = attribute_value(&e, &reader, "idref")? {
+                            let index = itemref_count;
+                            itemref_count += 1;
+                            if let Some(dropped_index) = itemrefs.insert(idref.clone(), index) {
+                                conflicts.push(OpfConflict::DuplicateSpineIdref {
+                                    idref,
+                                    dropped_index,
+                                    index,
+                                });
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            Ok(Event::End(e)) => match e.name().as_ref() {
+                b"manifest" => in_manifest = false,
+                b"spine" => in_spine = false,
+                _ => {}
+            },
+            Ok(Event::Eof) => break,
+            Err(e) => return Err(EpubError::Parse(format!("XML parse error: {:?}", e))),
+            _ => {}
+        }
+        buf.clear();
+    }
+
+    Ok(conflicts)
+}
+
 /// Parse a manifest item from XML element attributes
 fn parse_manifest_item<'a>(
     e: &quick_xml::events::BytesStart<'a>,
@@ -466,6 +681,7 @@ fn parse_manifest_item<'a>(
     let mut href = None;
     let mut media_type = None;
     let mut properties = None;
+    let mut fallback = None;
 
     for attr in e.attributes() {
         let attr = attr.map_err(|e| EpubError::Parse(format!("Attr error: {:?}", e)))?;
@@ -484,6 +700,7 @@ fn parse_manifest_item<'a>(
             "href" => href = Some(value),
             "media-type" => media_type = Some(value),
             "properties" => properties = Some(value),
+            "fallback" => fallback = Some(value),
             _ => {}
         }
     }
@@ -494,6 +711,7 @@ fn parse_manifest_item<'a>(
             href,
             media_type,
             properties,
+            fallback,
         }))
     } else {
         Ok(None) // Skip incomplete items
@@ -540,6 +758,29 @@ fn parse_guide_reference<'a>(
     }
 }
 
+/// Value of the attribute named `name`, if present
+fn attribute_value<'a>(
+    e: &quick_xml::events::BytesStart<'a>,
+    reader: &Reader<&[u8]>,
+    name: &str,
+) -> Result<Option<String>, EpubError> {
+    for attr in e.attributes() {
+        let attr = attr.map_err(|e| EpubError::Parse(format!("Attr error: {:?}", e)))?;
+        let key = reader
+            .decoder()
+            .decode(attr.key.as_ref())
+            .map_err(|e| EpubError::Parse(format!("Decode error: {:?}", e)))?;
+        if key == name {
+            let value = reader
+                .decoder()
+                .decode(&attr.value)
+                .map_err(|e| EpubError::Parse(format!("Decode error: {:?}", e)))?;
+            return Ok(Some(value.to_string()));
+        }
+    }
+    Ok(None)
+}
+
 /// Full EPUB metadata extraction from both container.xml and content.opf
 ///
 /// This is a convenience function that takes both file contents and returns
@@ -603,6 +844,24 @@ mod tests {
         assert_eq!(metadata.manifest[1].href, "chapter1.xhtml");
     }
 
+    #[test]
+    fn test_parse_opf_prefers_unique_identifier() {
+        let opf = br#"<?xml version="1.0"?>
+<package xmlns="http://www.idpf.org/2007/opf" version="3.0" unique-identifier="uid">
+  <metadata xmlns:dc="http://purl.org/dc/elements/1.1/">
+    <dc:identifier id="isbn">urn:isbn:9780000000000</dc:identifier>
+    <dc:identifier id="uid">urn:uuid:0f1b2c3d-4e5f-6071-8293-a4b5c6d7e8f9</dc:identifier>
+    <dc:identifier>other</dc:identifier>
+  </metadata>
+</package>"#;
+
+        let metadata = parse_opf(opf).unwrap();
+        assert_eq!(
+            metadata.identifier.as_deref(),
+            Some("urn:uuid:0f1b2c3d-4e5f-6071-8293-a4b5c6d7e8f9")
+        );
+    }
+
     #[test]
     fn test_parse_opf_with_cover() {
         let opf = br#"<?xml version="1.0"?>
@@ -629,6 +888,7 @@ mod tests {
             href: "chapter1.xhtml".to_string(),
             media_type: "application/xhtml+xml".to_string(),
             properties: None,
+            fallback: None,
         });
 
         let item = metadata.get_item("item1");
@@ -638,6 +898,33 @@ mod tests {
         assert!(metadata.get_item("nonexistent").is_none());
     }
 
+    #[test]
+    fn test_displayable_item_follows_fallback_chain() {
+        let opf = br#"<?xml version="1.0"?>
+<package xmlns="http://www.idpf.org/2007/opf" version="3.0">
+  <metadata xmlns:dc="http://purl.org/dc/elements/1.1/">
+    <dc:title>Fallbacks</dc:title>
+  </metadata>
+  <manifest>
+    <item id="svg" href="map.svg" media-type="image/svg+xml" fallback="foreign"/>
+    <item id="foreign" href="map.dtb" media-type="application/x-dtbook+xml" fallback="png"/>
+    <item id="png" href="map.png" media-type="image/png"/>
+    <item id="loop-a" href="a.svg" media-type="image/svg+xml" fallback="loop-b"/>
+    <item id="loop-b" href="b.svg" media-type="image/svg+xml" fallback="loop-a"/>
+    <item id="dangling" href="c.svg" media-type="image/svg+xml" fallback="missing"/>
+    <item id="text" href="text.xhtml" media-type="application/xhtml+xml"/>
+  </manifest>
+</package>"#;
+
+        let metadata = parse_opf(opf).unwrap();
+        assert_eq!(metadata.manifest[0].fallback.as_deref(), Some("foreign"));
+        assert_eq!(metadata.displayable_item("svg").unwrap().id, "png");
+        assert_eq!(metadata.displayable_item("text").unwrap().id, "text");
+        assert!(metadata.displayable_item("loop-a").is_none());
+        assert!(metadata.displayable_item("dangling").is_none());
+        assert!(metadata.displayable_item("missing").is_none());
+    }
+
     #[test]
     fn test_parse_opf_dublin_core_date() {
         let opf = br#"<?xml version="1.0"?>
@@ -1019,4 +1306,72 @@ mod tests {
         assert_eq!(metadata.title, "Another Book");
         assert_eq!(metadata.opf_path, Some("OEBPS/content.opf".to_string()));
     }
+
+    const CONFLICTED_OPF: &[u8] = br#"<?xml version="1.0"?>
+<package xmlns="http://www.idpf.org/2007/opf" version="2.0">
+  <manifest>
+    <item id="ch1" href="old.xhtml" media-type="application/xhtml+xml"/>
+    <item id="ncx" href="toc.ncx" media-type="application/x-dtbncx+xml"/>
+    <item id="ch1" href="new.xhtml" media-type="application/xhtml+xml"/>
+  </manifest>
+  <spine toc="ncx">
+    <itemref idref="ch1"/>
+    <itemref idref="ch1"/>
+  </spine>
+  <spine toc="other"/>
+</package>"#;
+
+    #[test]
+    fn test_parse_opf_duplicate_ids_resolve_last_wins() {
+        let metadata = parse_opf(CONFLICTED_OPF).unwrap();
+        assert_eq!(metadata.manifest.len(), 2);
+        assert_eq!(metadata.manifest[0].id, "ch1");
+        assert_eq!(metadata.manifest[0].href, "new.xhtml");
+        assert_eq!(metadata.get_item("ch1").unwrap().href, "new.xhtml");
+    }
+
+    #[test]
+    fn test_find_opf_conflicts_lists_each_conflict() {
+        let conflicts = find_opf_conflicts(CONFLICTED_OPF).unwrap();
+        assert_eq!(
+            conflicts,
+            vec![
+                OpfConflict::DuplicateManifestId {
+                    id: "ch1".to_string(),
+                    overridden_href: "old.xhtml".to_string(),
+                    href: "new.xhtml".to_string(),
+                },
+                OpfConflict::DuplicateSpineIdref {
+                    idref: "ch1".to_string(),
+                    dropped_index: 0,
+                    index: 1,
+                },
+                OpfConflict::ConflictingToc {
+                    overridden: "ncx".to_string(),
+                    toc: "other".to_string(),
+                },
+            ]
+        );
+        let mut spine = crate::spine::parse_spine(CONFLICTED_OPF).unwrap();
+        spine.resolve_duplicate_idrefs();
+        assert_eq!(spine.len(), 1);
+        assert_eq!(spine.toc_id(), Some("other"));
+    }
+
+    #[test]
+    fn test_opf_parsers_survive_truncated_and_corrupted_input() {
+        for len in 0..CONFLICTED_OPF.len() {
+            let truncated = &CONFLICTED_OPF[..len];
+            let _ = parse_opf(truncated);
+            let _ = crate::spine::parse_spine(truncated);
+            let _ = find_opf_conflicts(truncated);
+        }
+        let mut corrupted = CONFLICTED_OPF.to_vec();
+        for i in (0..corrupted.len()).step_by(7) {
+            corrupted[i] = corrupted[i].wrapping_mul(31).wrapping_add(i as u8);
+            let _ = parse_opf(&corrupted);
+            let _ = crate::spine::parse_spine(&corrupted);
+            let _ = find_opf_conflicts(&corrupted);
+        }
+    }
 }
@@ -31,13 +31,16 @@ use crate::error::EpubError;
 ///
 /// Navigation points can be nested to represent hierarchical structures
 /// (e.g., chapters containing sections).
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
 pub struct NavPoint {
     /// Display label for this navigation point
     pub label: String,
     /// Content href (relative path, possibly with fragment)
     pub href: String,
     /// Child navigation points (for hierarchical TOC)
+    #[cfg_attr(feature = "serde", serde(default))]
     pub children: Vec<NavPoint>,
 }
 
@@ -45,7 +48,9 @@ pub struct NavPoint {
 ///
 /// Contains table of contents, page list, and landmarks extracted
 /// from either the EPUB 3.x nav document or EPUB 2.0 NCX.
-#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default, rename_all = "snake_case"))]
 pub struct Navigation {
     /// Table of contents entries
     pub toc: Vec<NavPoint>,
@@ -87,6 +92,37 @@ impl Navigation {
         flatten_nav_points(&self.toc, 0, &mut result);
         result
     }
+
+    /// Page-list entries inside a chapter as `(fragment id, label)` pairs, in
+    /// page-list order.
+    ///
+    /// Entries without a fragment or label are skipped.
+    pub fn page_list_for_chapter(&self, chapter_href: &str) -> Vec<(String, String)> {
+        self.page_list
+            .iter()
+            .filter(|point| href_targets_chapter(&point.href, chapter_href))
+            .filter_map(|point| {
+                let (_, fragment) = point.href.split_once('#')?;
+                let label = point.label.trim();
+                (!fragment.is_empty() && !label.is_empty())
+                    .then(|| (fragment.to_string(), label.to_string()))
+            })
+            .collect()
+    }
+}
+
+/// Whether a nav `href` points into `chapter_href`.
+///
+/// Nav hrefs are relative to the nav document, so a target also matches when
+/// it is a path suffix of the OPF-relative chapter href.
+pub(crate) fn href_targets_chapter(href: &str, chapter_href: &str) -> bool {
+    let target = href.split('#').next().unwrap_or_default();
+    let target = target.trim_start_matches("./");
+    !target.is_empty()
+        && (target == chapter_href
+            || chapter_href
+                .strip_suffix(target)
+                .is_some_and(|prefix| prefix.ends_with('/')))
 }
 
 /// Count all navigation points recursively
@@ -214,24 +250,22 @@ pub fn parse_nav_xhtml(content: &[u8]) -> Result<Navigation, EpubError> {
                     _ => {}
                 }
             }
-            Ok(Event::Text(e)) => {
-                if in_anchor && current_nav_type.is_some() {
-                    let text = reader.decoder().decode(&e).unwrap_or_default().to_string();
-                    if let Some(item) = item_stack.last_mut() {
-                        match &mut item.label {
-                            Some(existing) => {
-                                // Add space separator when concatenating text segments
-                                // from formatted anchors (e.g. "Part <em>One</em>")
-                                if !existing.is_empty()
-                                    && !existing.ends_with(' ')
-                                    && !text.starts_with(' ')
-                                {
-                                    existing.push(' ');
-                                }
-                                existing.push_str(&text);
+            Ok(Event::Text(e)) if in_anchor && current_nav_type.is_some() => {
+                let text = reader.decoder().decode(&e).unwrap_or_default().to_string();
+                if let Some(item) = item_stack.last_mut() {
+                    match &mut item.label {
+                        Some(existing) => {
+                            // Add space separator when concatenating text segments
+                            // from formatted anchors (e.g. "Part <em>One</em>")
+                            if !existing.is_empty()
+                                && !existing.ends_with(' ')
+                                && !text.starts_with(' ')
+                            {
+                                existing.push(' ');
                             }
-                            None => item.label = Some(text),
+                            existing.push_str(&text);
                         }
+                        None => item.label = Some(text),
                     }
                 }
             }
@@ -396,20 +430,18 @@ pub fn parse_ncx(content: &[u8]) -> Result<Navigation, EpubError> {
                     _ => {}
                 }
             }
-            Ok(Event::Text(e)) => {
-                if in_text {
-                    let text = reader.decoder().decode(&e).unwrap_or_default().to_string();
-                    if in_page_target {
-                        match &mut current_label {
-                            Some(existing) => existing.push_str(&text),
-                            None => current_label = Some(text),
-                        }
-                    } else if let Some(point) = nav_point_stack.last_mut() {
-                        if point.label.is_empty() {
-                            point.label = text;
-                        } else {
-                            point.label.push_str(&text);
-                        }
+            Ok(Event::Text(e)) if in_text => {
+                let text = reader.decoder().decode(&e).unwrap_or_default().to_string();
+                if in_page_target {
+                    match &mut current_label {
+                        Some(existing) => existing.push_str(&text),
+                        None => current_label = Some(text),
+                    }
+                } else if let Some(point) = nav_point_stack.last_mut() {
+                    if point.label.is_empty() {
+                        point.label = text;
+                    } else {
+                        point.label.push_str(&text);
                     }
                 }
             }
@@ -1104,4 +1136,33 @@ mod tests {
         assert!(nav.has_page_list());
         assert!(nav.has_landmarks());
     }
+
+    #[test]
+    fn test_page_list_for_chapter_filters_by_target() {
+        let point = |label: &str, href: &str| NavPoint {
+            label: label.into(),
+            href: href.into(),
+            children: Vec::with_capacity(0),
+        };
+        let nav = Navigation {
+            toc: Vec::with_capacity(0),
+            page_list: vec![
+                point("i", "front.xhtml#pi"),
+                point("1", "ch1.xhtml#p1"),
+                point("2", "./ch1.xhtml#p2"),
+                point("", "ch1.xhtml#p3"),
+                point("4", "ch1.xhtml"),
+                point("5", "ch2.xhtml#p5"),
+            ],
+            landmarks: Vec::with_capacity(0),
+        };
+        assert_eq!(
+            nav.page_list_for_chapter("text/ch1.xhtml"),
+            vec![
+                ("p1".to_string(), "1".to_string()),
+                ("p2".to_string(), "2".to_string())
+            ]
+        );
+        assert!(nav.page_list_for_chapter("ch3.xhtml").is_empty());
+    }
 }
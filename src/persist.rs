@@ -0,0 +1,988 @@
+//! Compact binary persistence for reader state.
+//!
+//! Records use a fixed little-endian layout designed to be written directly
+//! to EEPROM or flash pages:
+//!
+//! ```text
+//! magic "MUEP" | version u8 | kind u8 | payload_len u16 | payload | crc32 u32
+//! ```
+//!
+//! The CRC-32 (IEEE) covers the header and payload. Decoding ignores bytes
+//! after the checksum, so erased flash padding (`0xFF`) behind a record is
+//! harmless.
+//!
+//! The crate keeps no full-text search index, so the search record stores
+//! the resumable `SearchCursor` of a book-wide search instead (with `std`).
+//!
+//! [`PageMapSet`] keeps page maps for several pagination profiles in memory
+//! and translates page numbers between them; [`remap_position`] places a
+//! saved [`ReadingPosition`] in a rebuilt map.
+//!
+//! # Allocation behavior
+//! - Encoding writes into caller-provided buffers and never allocates
+//! - Decoding allocates only the decoded strings and output vectors
+//!
+//! Only `alloc` is required, so the module is usable in `no_std` builds.
+
+extern crate alloc;
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+
+#[cfg(feature = "std")]
+use crate::search::SearchCursor;
+
+/// Current on-disk format version.
+pub const FORMAT_VERSION: u8 = 1;
+
+const MAGIC: [u8; 4] = *b"MUEP";
+const HEADER_LEN: usize = 8;
+const CHECKSUM_LEN: usize = 4;
+
+/// Fixed bytes for a position payload (indices + flags), excluding strings.
+const POSITION_FIXED_LEN: usize = 9;
+const FLAG_HREF: u8 = 0b001;
+const FLAG_ANCHOR: u8 = 0b010;
+/// A `u32` segment index follows the strings.
+const FLAG_SEGMENT: u8 = 0b100;
+
+/// Bytes per page-map entry.
+const PAGE_START_LEN: usize = 8;
+
+/// Bytes of a search cursor payload.
+#[cfg(feature = "std")]
+const SEARCH_CURSOR_LEN: usize = 13;
+
+/// Record type tag stored in the header.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[non_exhaustive]
+pub enum RecordKind {
+    /// A single [`ReadingPosition`].
+    Position,
+    /// A list of bookmarked [`ReadingPosition`]s.
+    Bookmarks,
+    /// A page map of [`PageStart`] entries.
+    PageMap,
+    /// A suspended book-wide search cursor.
+    SearchCursor,
+}
+
+impl RecordKind {
+    /// Stable tag byte for this record kind.
+    pub const fn code(&self) -> u8 {
+        match self {
+            Self::Position => 1,
+            Self::Bookmarks => 2,
+            Self::PageMap => 3,
+            Self::SearchCursor => 4,
+        }
+    }
+
+    fn from_code(code: u8) -> Option<Self> {
+        match code {
+            1 => Some(Self::Position),
+            2 => Some(Self::Bookmarks),
+            3 => Some(Self::PageMap),
+            4 => Some(Self::SearchCursor),
+            _ => None,
+        }
+    }
+}
+
+/// Stable reading position with anchor + fallback offset information.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default, rename_all = "snake_case"))]
+pub struct ReadingPosition {
+    /// 0-based chapter index in spine order.
+    pub chapter_index: usize,
+    /// Optional chapter href hint for robust restore across index shifts.
+    pub chapter_href: Option<String>,
+    /// Segment of a split chapter (see `ChapterSegment`), restored
+    /// together with `chapter_href`.
+    pub segment: Option<usize>,
+    /// Optional anchor payload (fragment id or CFI-like token).
+    pub anchor: Option<String>,
+    /// Fallback character offset in the chapter when anchor cannot be resolved.
+    pub fallback_offset: usize,
+}
+
+/// Start of a rendered page, as stored in a page map.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct PageStart {
+    /// 0-based chapter index in spine order.
+    pub chapter_index: u32,
+    /// Character offset of the page start within the chapter.
+    pub offset: u32,
+}
+
+/// Page maps for several pagination profiles of the same book.
+///
+/// Every map indexes the same `(chapter_index, offset)` anchor stream, so a
+/// page in one profile can be translated to the page showing the same text
+/// in another (e.g. after rotating between portrait and landscape) without
+/// re-paginating. Profiles are keyed by their 32-byte pagination profile id.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PageMapSet {
+    maps: Vec<([u8; 32], Vec<PageStart>)>,
+}
+
+impl PageMapSet {
+    /// Empty set.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Store the page map for `profile`, returning any map it replaces.
+    ///
+    /// Entries are sorted so lookups can binary-search.
+    pub fn insert(
+        &mut self,
+        profile: [u8; 32],
+        mut pages: Vec<PageStart>,
+    ) -> Option<Vec<PageStart>> {
+        pages.sort_unstable();
+        match self.maps.iter_mut().find(|(id, _)| *id == profile) {
+            Some((_, existing)) => Some(core::mem::replace(existing, pages)),
+            None => {
+                self.maps.push((profile, pages));
+                None
+            }
+        }
+    }
+
+    /// Drop the page map for `profile`.
+    pub fn remove(&mut self, profile: [u8; 32]) -> Option<Vec<PageStart>> {
+        let idx = self.maps.iter().position(|(id, _)| *id == profile)?;
+        Some(self.maps.remove(idx).1)
+    }
+
+    /// Page map for `profile`.
+    pub fn get(&self, profile: [u8; 32]) -> Option<&[PageStart]> {
+        self.maps
+            .iter()
+            .find(|(id, _)| *id == profile)
+            .map(|(_, pages)| pages.as_slice())
+    }
+
+    /// Ids of the stored profiles, in insertion order.
+    pub fn profiles(&self) -> impl Iterator<Item = [u8; 32]> + '_ {
+        self.maps.iter().map(|(id, _)| *id)
+    }
+
+    /// Page of `profile` containing `anchor`.
+    pub fn page_for_anchor(&self, profile: [u8; 32], anchor: PageStart) -> Option<usize> {
+        page_containing(self.get(profile)?, anchor)
+    }
+
+    /// Translate `page` of profile `from` into the page of profile `to` that
+    /// contains the same page-start anchor.
+    pub fn translate_page(&self, from: [u8; 32], page: usize, to: [u8; 32]) -> Option<usize> {
+        translate_page(self.get(from)?, page, self.get(to)?)
+    }
+
+    /// Place `position`, saved under profile `from`, in the page map of
+    /// profile `to`; see [`remap_position`].
+    ///
+    /// A missing `from` map only loses the interpolation fallback. Returns
+    /// `None` when `to` has no map or its map is empty.
+    pub fn remap_position(
+        &self,
+        position: &ReadingPosition,
+        from: [u8; 32],
+        to: [u8; 32],
+    ) -> Option<RemappedPosition> {
+        remap_position(position, self.get(from).unwrap_or(&[]), self.get(to)?)
+    }
+}
+
+/// Where a saved position lands in a rebuilt page map.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RemappedPosition {
+    /// 0-based page in the new map.
+    pub page: usize,
+    /// Start anchor of that page.
+    pub page_start: PageStart,
+    /// Characters from the page start to the position; `0` when the
+    /// position was moved to the page start.
+    pub offset_in_page: u32,
+    /// Position to persist for the new layout.
+    pub position: ReadingPosition,
+    /// Why the match is approximate, if it is.
+    pub conflict: Option<RemapConflict>,
+}
+
+/// Reason a [`RemappedPosition`] could not be matched exactly.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[non_exhaustive]
+pub enum RemapConflict {
+    /// No page of the new map starts in the position's chapter. The page was
+    /// interpolated from the position's place in the old map, or from the
+    /// neighbouring chapters when the old map lacks it too.
+    ChapterNotInMap {
+        /// Chapter the position refers to.
+        chapter_index: u32,
+    },
+    /// The position precedes the first page of its chapter in the new map
+    /// and was moved to that page's start.
+    BeforeFirstPage,
+}
+
+impl fmt::Display for RemapConflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RemapConflict::ChapterNotInMap { chapter_index } => {
+                write!(f, "chapter {} has no page in the new map", chapter_index)
+            }
+            RemapConflict::BeforeFirstPage => {
+                write!(f, "position precedes the first page of its chapter")
+            }
+        }
+    }
+}
+
+/// Place `position` in the sorted page map `new`, e.g. after re-paginating
+/// with a different font size.
+///
+/// Page starts are word anchors shared by every layout of the book, so a
+/// position whose chapter appears in `new` maps exactly to the page
+/// containing its `fallback_offset`. Otherwise the position is reported as
+/// a [`RemapConflict`] and lands on the page at the same relative place in
+/// the book as its page in the sorted `old` map, at that page's start.
+/// Returns `None` only when `new` is empty.
+pub fn remap_position(
+    position: &ReadingPosition,
+    old: &[PageStart],
+    new: &[PageStart],
+) -> Option<RemappedPosition> {
+    let chapter_index = u32::try_from(position.chapter_index).unwrap_or(u32::MAX);
+    let anchor = PageStart {
+        chapter_index,
+        offset: u32::try_from(position.fallback_offset).unwrap_or(u32::MAX),
+    };
+    let chapter_start = new.partition_point(|start| start.chapter_index < chapter_index);
+    let in_chapter = new
+        .get(chapter_start)
+        .is_some_and(|start| start.chapter_index == chapter_index);
+
+    let (page, conflict) = if in_chapter {
+        match page_containing(new, anchor) {
+            Some(page) if page >= chapter_start => (page, None),
+            _ => (chapter_start, Some(RemapConflict::BeforeFirstPage)),
+        }
+    } else {
+        let page = match (page_containing(old, anchor), old.len()) {
+            (Some(old_page), old_len) if old[old_page].chapter_index == chapter_index => {
+                interpolate_page(old_page, old_len, new.len())
+            }
+            // The chapter's text would follow the last page before it.
+            _ => chapter_start.saturating_sub(1),
+        };
+        (page, Some(RemapConflict::ChapterNotInMap { chapter_index }))
+    };
+
+    let page_start = *new.get(page)?;
+    if conflict.is_none() {
+        return Some(RemappedPosition {
+            page,
+            page_start,
+            offset_in_page: anchor.offset - page_start.offset,
+            position: position.clone(),
+            conflict,
+        });
+    }
+    let moved = page_start.chapter_index != chapter_index;
+    Some(RemappedPosition {
+        page,
+        page_start,
+        offset_in_page: 0,
+        position: ReadingPosition {
+            chapter_index: page_start.chapter_index as usize,
+            chapter_href: position.chapter_href.clone().filter(|_| !moved),
+            segment: position.segment.filter(|_| !moved),
+            anchor: None,
+            fallback_offset: page_start.offset as usize,
+        },
+        conflict,
+    })
+}
+
+/// Page at the same fraction of a `new_len`-page map as `page` is of an
+/// `old_len`-page map, rounded to the nearest page.
+fn interpolate_page(page: usize, old_len: usize, new_len: usize) -> usize {
+    let scaled = (2 * page as u64 * new_len as u64 + old_len as u64) / (2 * old_len as u64);
+    (scaled as usize).min(new_len.saturating_sub(1))
+}
+
+/// Index of the page in a sorted page map whose range contains `anchor`.
+///
+/// Returns `None` when `anchor` precedes the first page.
+pub fn page_containing(pages: &[PageStart], anchor: PageStart) -> Option<usize> {
+    pages
+        .partition_point(|start| *start <= anchor)
+        .checked_sub(1)
+}
+
+/// Map `page` of the sorted page map `from` to the page of `to` showing its
+/// first character.
+pub fn translate_page(from: &[PageStart], page: usize, to: &[PageStart]) -> Option<usize> {
+    page_containing(to, *from.get(page)?)
+}
+
+/// Errors from binary encode/decode.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[non_exhaustive]
+pub enum PersistError {
+    /// Output buffer cannot hold the encoded record.
+    BufferTooSmall {
+        /// Bytes needed for the record.
+        required: usize,
+        /// Bytes available in the buffer.
+        provided: usize,
+    },
+    /// Payload does not fit the 16-bit length field or a field exceeds its width.
+    ValueTooLarge,
+    /// Record does not start with the expected magic bytes.
+    BadMagic,
+    /// Record was written by an unsupported format version.
+    UnsupportedVersion(u8),
+    /// Record holds a different kind than requested.
+    UnexpectedKind(u8),
+    /// Record ends before the declared payload and checksum.
+    Truncated,
+    /// Stored checksum does not match the record contents.
+    ChecksumMismatch,
+    /// Payload is structurally invalid (bad flags, trailing bytes, non-UTF-8 text).
+    Corrupt,
+}
+
+impl fmt::Display for PersistError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PersistError::BufferTooSmall { required, provided } => write!(
+                f,
+                "Buffer too small for record: required {} bytes, provided {}",
+                required, provided
+            ),
+            PersistError::ValueTooLarge => write!(f, "Value too large for record layout"),
+            PersistError::BadMagic => write!(f, "Missing record magic"),
+            PersistError::UnsupportedVersion(v) => write!(f, "Unsupported record version {}", v),
+            PersistError::UnexpectedKind(k) => write!(f, "Unexpected record kind {}", k),
+            PersistError::Truncated => write!(f, "Record truncated"),
+            PersistError::ChecksumMismatch => write!(f, "Record checksum mismatch"),
+            PersistError::Corrupt => write!(f, "Record payload corrupt"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for PersistError {}
+
+/// Encoded size of a position record.
+pub fn position_encoded_len(position: &ReadingPosition) -> usize {
+    HEADER_LEN + position_payload_len(position) + CHECKSUM_LEN
+}
+
+/// Encode a reading position into `out`, returning the record length.
+pub fn encode_position(position: &ReadingPosition, out: &mut [u8]) -> Result<usize, PersistError> {
+    let payload_len = position_payload_len(position);
+    let mut writer = RecordWriter::begin(out, RecordKind::Position, payload_len)?;
+    writer.position(position)?;
+    writer.finish()
+}
+
+/// Decode a position record from the start of `bytes`.
+pub fn decode_position(bytes: &[u8]) -> Result<ReadingPosition, PersistError> {
+    let mut reader = RecordReader::open(bytes, RecordKind::Position)?;
+    let position = reader.position()?;
+    reader.finish()?;
+    Ok(position)
+}
+
+/// Encoded size of a bookmark list record.
+pub fn bookmarks_encoded_len(bookmarks: &[ReadingPosition]) -> usize {
+    HEADER_LEN + bookmarks_payload_len(bookmarks) + CHECKSUM_LEN
+}
+
+/// Encode a bookmark list into `out`, returning the record length.
+pub fn encode_bookmarks(
+    bookmarks: &[ReadingPosition],
+    out: &mut [u8],
+) -> Result<usize, PersistError> {
+    let count = u16::try_from(bookmarks.len()).map_err(|_| PersistError::ValueTooLarge)?;
+    let payload_len = bookmarks_payload_len(bookmarks);
+    let mut writer = RecordWriter::begin(out, RecordKind::Bookmarks, payload_len)?;
+    writer.put(&count.to_le_bytes());
+    for position in bookmarks {
+        writer.position(position)?;
+    }
+    writer.finish()
+}
+
+/// Decode a bookmark list record from the start of `bytes`.
+pub fn decode_bookmarks(bytes: &[u8]) -> Result<Vec<ReadingPosition>, PersistError> {
+    let mut reader = RecordReader::open(bytes, RecordKind::Bookmarks)?;
+    let count = reader.u16()? as usize;
+    // Each entry needs at least its fixed part; reject counts the payload cannot hold.
+    if count.saturating_mul(POSITION_FIXED_LEN) > reader.remaining() {
+        return Err(PersistError::Corrupt);
+    }
+    let mut bookmarks = Vec::with_capacity(count);
+    for _ in 0..count {
+        bookmarks.push(reader.position()?);
+    }
+    reader.finish()?;
+    Ok(bookmarks)
+}
+
+/// Encoded size of a page map record with `pages` entries.
+pub fn page_map_encoded_len(pages: usize) -> usize {
+    HEADER_LEN + 2 + pages * PAGE_START_LEN + CHECKSUM_LEN
+}
+
+/// Encode a page map into `out`, returning the record length.
+pub fn encode_page_map(pages: &[PageStart], out: &mut [u8]) -> Result<usize, PersistError> {
+    let count = u16::try_from(pages.len()).map_err(|_| PersistError::ValueTooLarge)?;
+    let mut writer =
+        RecordWriter::begin(out, RecordKind::PageMap, 2 + pages.len() * PAGE_START_LEN)?;
+    writer.put(&count.to_le_bytes());
+    for page in pages {
+        writer.put(&page.chapter_index.to_le_bytes());
+        writer.put(&page.offset.to_le_bytes());
+    }
+    writer.finish()
+}
+
+/// Decode a page map record from the start of `bytes`.
+pub fn decode_page_map(bytes: &[u8]) -> Result<Vec<PageStart>, PersistError> {
+    let mut reader = RecordReader::open(bytes, RecordKind::PageMap)?;
+    let count = reader.u16()? as usize;
+    if count * PAGE_START_LEN != reader.remaining() {
+        return Err(PersistError::Corrupt);
+    }
+    let mut pages = Vec::with_capacity(count);
+    for _ in 0..count {
+        pages.push(PageStart {
+            chapter_index: reader.u32()?,
+            offset: reader.u32()?,
+        });
+    }
+    reader.finish()?;
+    Ok(pages)
+}
+
+/// Encoded size of a search cursor record.
+#[cfg(feature = "std")]
+pub const SEARCH_CURSOR_ENCODED_LEN: usize = HEADER_LEN + SEARCH_CURSOR_LEN + CHECKSUM_LEN;
+
+/// Encode a search cursor into `out`, returning the record length.
+#[cfg(feature = "std")]
+pub fn encode_search_cursor(cursor: &SearchCursor, out: &mut [u8]) -> Result<usize, PersistError> {
+    let narrow = |value: usize| u32::try_from(value).map_err(|_| PersistError::ValueTooLarge);
+    let chapter = narrow(cursor.chapter_index)?;
+    let offset = narrow(cursor.offset)?;
+    let hits = narrow(cursor.hits)?;
+    let mut writer = RecordWriter::begin(out, RecordKind::SearchCursor, SEARCH_CURSOR_LEN)?;
+    writer.put(&chapter.to_le_bytes());
+    writer.put(&offset.to_le_bytes());
+    writer.put(&hits.to_le_bytes());
+    writer.put(&[u8::from(cursor.finished)]);
+    writer.finish()
+}
+
+/// Decode a search cursor record from the start of `bytes`.
+#[cfg(feature = "std")]
+pub fn decode_search_cursor(bytes: &[u8]) -> Result<SearchCursor, PersistError> {
+    let mut reader = RecordReader::open(bytes, RecordKind::SearchCursor)?;
+    let chapter_index = reader.u32()? as usize;
+    let offset = reader.u32()? as usize;
+    let hits = reader.u32()? as usize;
+    let finished = match reader.take(1)?[0] {
+        0 => false,
+        1 => true,
+        _ => return Err(PersistError::Corrupt),
+    };
+    reader.finish()?;
+    Ok(SearchCursor {
+        chapter_index,
+        offset,
+        hits,
+        finished,
+    })
+}
+
+/// Read the kind of the record at the start of `bytes` without validating the payload.
+pub fn peek_kind(bytes: &[u8]) -> Result<RecordKind, PersistError> {
+    let header = parse_header(bytes)?;
+    RecordKind::from_code(header.kind).ok_or(PersistError::UnexpectedKind(header.kind))
+}
+
+fn position_payload_len(position: &ReadingPosition) -> usize {
+    POSITION_FIXED_LEN
+        + position.chapter_href.as_ref().map_or(0, |s| 2 + s.len())
+        + position.anchor.as_ref().map_or(0, |s| 2 + s.len())
+        + position.segment.map_or(0, |_| 4)
+}
+
+fn bookmarks_payload_len(bookmarks: &[ReadingPosition]) -> usize {
+    2 + bookmarks.iter().map(position_payload_len).sum::<usize>()
+}
+
+struct RecordWriter<'a> {
+    out: &'a mut [u8],
+    pos: usize,
+}
+
+impl<'a> RecordWriter<'a> {
+    fn begin(
+        out: &'a mut [u8],
+        kind: RecordKind,
+        payload_len: usize,
+    ) -> Result<Self, PersistError> {
+        let declared = u16::try_from(payload_len).map_err(|_| PersistError::ValueTooLarge)?;
+        let required = HEADER_LEN + payload_len + CHECKSUM_LEN;
+        if out.len() < required {
+            return Err(PersistError::BufferTooSmall {
+                required,
+                provided: out.len(),
+            });
+        }
+        let mut writer = Self {
+            out: &mut out[..required],
+            pos: 0,
+        };
+        writer.put(&MAGIC);
+        writer.put(&[FORMAT_VERSION, kind.code()]);
+        writer.put(&declared.to_le_bytes());
+        Ok(writer)
+    }
+
+    fn put(&mut self, bytes: &[u8]) {
+        self.out[self.pos..self.pos + bytes.len()].copy_from_slice(bytes);
+        self.pos += bytes.len();
+    }
+
+    fn position(&mut self, position: &ReadingPosition) -> Result<(), PersistError> {
+        let chapter =
+            u32::try_from(position.chapter_index).map_err(|_| PersistError::ValueTooLarge)?;
+        let offset =
+            u32::try_from(position.fallback_offset).map_err(|_| PersistError::ValueTooLarge)?;
+        let segment = position
+            .segment
+            .map(u32::try_from)
+            .transpose()
+            .map_err(|_| PersistError::ValueTooLarge)?;
+        let mut flags = 0u8;
+        if position.chapter_href.is_some() {
+            flags |= FLAG_HREF;
+        }
+        if position.anchor.is_some() {
+            flags |= FLAG_ANCHOR;
+        }
+        if segment.is_some() {
+            flags |= FLAG_SEGMENT;
+        }
+        self.put(&chapter.to_le_bytes());
+        self.put(&offset.to_le_bytes());
+        self.put(&[flags]);
+        for text in [&position.chapter_href, &position.anchor]
+            .into_iter()
+            .flatten()
+        {
+            let len = u16::try_from(text.len()).map_err(|_| PersistError::ValueTooLarge)?;
+            self.put(&len.to_le_bytes());
+            self.put(text.as_bytes());
+        }
+        if let Some(segment) = segment {
+            self.put(&segment.to_le_bytes());
+        }
+        Ok(())
+    }
+
+    fn finish(mut self) -> Result<usize, PersistError> {
+        if self.pos + CHECKSUM_LEN != self.out.len() {
+            return Err(PersistError::Corrupt);
+        }
+        let crc = crc32(&self.out[..self.pos]);
+        self.put(&crc.to_le_bytes());
+        Ok(self.pos)
+    }
+}
+
+struct Header {
+    kind: u8,
+    payload_len: usize,
+}
+
+fn parse_header(bytes: &[u8]) -> Result<Header, PersistError> {
+    if bytes.len() < HEADER_LEN {
+        return Err(PersistError::Truncated);
+    }
+    if bytes[..4] != MAGIC {
+        return Err(PersistError::BadMagic);
+    }
+    if bytes[4] != FORMAT_VERSION {
+        return Err(PersistError::UnsupportedVersion(bytes[4]));
+    }
+    Ok(Header {
+        kind: bytes[5],
+        payload_len: u16::from_le_bytes([bytes[6], bytes[7]]) as usize,
+    })
+}
+
+struct RecordReader<'a> {
+    payload: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> RecordReader<'a> {
+    fn open(bytes: &'a [u8], kind: RecordKind) -> Result<Self, PersistError> {
+        let header = parse_header(bytes)?;
+        if header.kind != kind.code() {
+            return Err(PersistError::UnexpectedKind(header.kind));
+        }
+        let body_end = HEADER_LEN + header.payload_len;
+        let record = bytes
+            .get(..body_end + CHECKSUM_LEN)
+            .ok_or(PersistError::Truncated)?;
+        let stored = u32::from_le_bytes([
+            record[body_end],
+            record[body_end + 1],
+            record[body_end + 2],
+            record[body_end + 3],
+        ]);
+        if crc32(&record[..body_end]) != stored {
+            return Err(PersistError::ChecksumMismatch);
+        }
+        Ok(Self {
+            payload: &record[HEADER_LEN..body_end],
+            pos: 0,
+        })
+    }
+
+    fn remaining(&self) -> usize {
+        self.payload.len() - self.pos
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], PersistError> {
+        let bytes = self
+            .payload
+            .get(self.pos..self.pos + len)
+            .ok_or(PersistError::Corrupt)?;
+        self.pos += len;
+        Ok(bytes)
+    }
+
+    fn u16(&mut self) -> Result<u16, PersistError> {
+        let b = self.take(2)?;
+        Ok(u16::from_le_bytes([b[0], b[1]]))
+    }
+
+    fn u32(&mut self) -> Result<u32, PersistError> {
+        let b = self.take(4)?;
+        Ok(u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+    }
+
+    fn text(&mut self) -> Result<String, PersistError> {
+        let len = self.u16()? as usize;
+        let bytes = self.take(len)?;
+        core::str::from_utf8(bytes)
+            .map(String::from)
+            .map_err(|_| PersistError::Corrupt)
+    }
+
+    fn position(&mut self) -> Result<ReadingPosition, PersistError> {
+        let chapter_index = self.u32()? as usize;
+        let fallback_offset = self.u32()? as usize;
+        let flags = self.take(1)?[0];
+        if flags & !(FLAG_HREF | FLAG_ANCHOR | FLAG_SEGMENT) != 0 {
+            return Err(PersistError::Corrupt);
+        }
+        let chapter_href = if flags & FLAG_HREF != 0 {
+            Some(self.text()?)
+        } else {
+            None
+        };
+        let anchor = if flags & FLAG_ANCHOR != 0 {
+            Some(self.text()?)
+        } else {
+            None
+        };
+        let segment = if flags & FLAG_SEGMENT != 0 {
+            Some(self.u32()? as usize)
+        } else {
+            None
+        };
+        Ok(ReadingPosition {
+            chapter_index,
+            chapter_href,
+            segment,
+            anchor,
+            fallback_offset,
+        })
+    }
+
+    fn finish(self) -> Result<(), PersistError> {
+        if self.pos == self.payload.len() {
+            Ok(())
+        } else {
+            Err(PersistError::Corrupt)
+        }
+    }
+}
+
+/// Bitwise CRC-32 (IEEE 802.3), bit-identical to `crc32fast::hash`.
+///
+/// `crc32fast` is only linked with `std`, and its slice-by-16 tables take
+/// 16 KiB of flash; records here are a few hundred bytes, so the table-free
+/// loop is fast enough and keeps `no_std` builds small.
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = 0xFFFF_FFFFu32;
+    for &byte in bytes {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xEDB8_8320 & mask);
+        }
+    }
+    !crc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_position() -> ReadingPosition {
+        ReadingPosition {
+            chapter_index: 3,
+            chapter_href: Some("text/ch3.xhtml".into()),
+            segment: Some(2),
+            anchor: Some("sec-2".into()),
+            fallback_offset: 1234,
+        }
+    }
+
+    #[test]
+    fn crc32_matches_reference() {
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+        assert_eq!(crc32(b"abc"), 0x3524_41C2);
+    }
+
+    #[test]
+    fn position_round_trips_with_flash_padding() {
+        let position = sample_position();
+        let mut page = [0xFFu8; 64];
+        let len = encode_position(&position, &mut page).unwrap();
+        assert_eq!(len, position_encoded_len(&position));
+        assert_eq!(peek_kind(&page).unwrap(), RecordKind::Position);
+        assert_eq!(decode_position(&page).unwrap(), position);
+
+        let bare = ReadingPosition::default();
+        let len = encode_position(&bare, &mut page).unwrap();
+        assert_eq!(len, HEADER_LEN + POSITION_FIXED_LEN + CHECKSUM_LEN);
+        assert_eq!(decode_position(&page[..len]).unwrap(), bare);
+    }
+
+    #[test]
+    fn encode_reports_required_buffer_size() {
+        let position = sample_position();
+        let mut small = [0u8; 8];
+        assert_eq!(
+            encode_position(&position, &mut small),
+            Err(PersistError::BufferTooSmall {
+                required: position_encoded_len(&position),
+                provided: 8,
+            })
+        );
+    }
+
+    #[test]
+    fn decode_rejects_corruption() {
+        let mut buf = [0u8; 64];
+        let len = encode_position(&sample_position(), &mut buf).unwrap();
+
+        let mut flipped = buf;
+        flipped[HEADER_LEN + 1] ^= 0x01;
+        assert_eq!(
+            decode_position(&flipped),
+            Err(PersistError::ChecksumMismatch)
+        );
+        assert_eq!(
+            decode_position(&buf[..len - 1]),
+            Err(PersistError::Truncated)
+        );
+
+        let mut versioned = buf;
+        versioned[4] = FORMAT_VERSION + 1;
+        assert_eq!(
+            decode_position(&versioned),
+            Err(PersistError::UnsupportedVersion(FORMAT_VERSION + 1))
+        );
+        assert_eq!(
+            decode_bookmarks(&buf),
+            Err(PersistError::UnexpectedKind(RecordKind::Position.code()))
+        );
+        assert_eq!(decode_position(&[0u8; 16]), Err(PersistError::BadMagic));
+    }
+
+    #[test]
+    fn bookmarks_and_page_map_round_trip() {
+        let bookmarks = alloc::vec![sample_position(), ReadingPosition::default()];
+        let mut buf = [0u8; 128];
+        let len = encode_bookmarks(&bookmarks, &mut buf).unwrap();
+        assert_eq!(len, bookmarks_encoded_len(&bookmarks));
+        assert_eq!(decode_bookmarks(&buf).unwrap(), bookmarks);
+
+        let pages = [
+            PageStart {
+                chapter_index: 0,
+                offset: 0,
+            },
+            PageStart {
+                chapter_index: 0,
+                offset: 812,
+            },
+            PageStart {
+                chapter_index: 1,
+                offset: 0,
+            },
+        ];
+        let len = encode_page_map(&pages, &mut buf).unwrap();
+        assert_eq!(len, page_map_encoded_len(pages.len()));
+        assert_eq!(decode_page_map(&buf).unwrap(), pages);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn search_cursor_round_trips() {
+        let mut buf = [0u8; 64];
+        let cursor = SearchCursor {
+            chapter_index: 4,
+            offset: 77,
+            hits: 9,
+            finished: false,
+        };
+        let len = encode_search_cursor(&cursor, &mut buf).unwrap();
+        assert_eq!(len, SEARCH_CURSOR_ENCODED_LEN);
+        assert_eq!(peek_kind(&buf).unwrap(), RecordKind::SearchCursor);
+        assert_eq!(decode_search_cursor(&buf).unwrap(), cursor);
+    }
+
+    #[test]
+    fn page_map_set_translates_between_profiles() {
+        let start = |chapter_index, offset| PageStart {
+            chapter_index,
+            offset,
+        };
+        let portrait = [1u8; 32];
+        let landscape = [2u8; 32];
+        let mut maps = PageMapSet::new();
+        maps.insert(
+            portrait,
+            alloc::vec![
+                start(0, 0),
+                start(0, 600),
+                start(0, 1200),
+                start(1, 0),
+                start(1, 600)
+            ],
+        );
+        // Unsorted input is normalized.
+        maps.insert(
+            landscape,
+            alloc::vec![start(1, 0), start(0, 0), start(0, 900)],
+        );
+
+        assert_eq!(maps.translate_page(portrait, 1, landscape), Some(0));
+        assert_eq!(maps.translate_page(portrait, 2, landscape), Some(1));
+        assert_eq!(maps.translate_page(portrait, 4, landscape), Some(2));
+        assert_eq!(maps.translate_page(landscape, 1, portrait), Some(1));
+        assert_eq!(maps.translate_page(landscape, 3, portrait), None);
+        assert_eq!(maps.translate_page(portrait, 0, [3u8; 32]), None);
+        assert_eq!(maps.page_for_anchor(landscape, start(0, 899)), Some(0));
+
+        assert_eq!(maps.profiles().count(), 2);
+        assert!(maps.remove(landscape).is_some());
+        assert_eq!(maps.get(landscape), None);
+        assert_eq!(page_containing(&[start(1, 0)], start(0, 5)), None);
+    }
+
+    #[test]
+    fn remap_position_matches_anchors_and_reports_conflicts() {
+        let start = |chapter_index, offset| PageStart {
+            chapter_index,
+            offset,
+        };
+        let at = |chapter_index, fallback_offset| ReadingPosition {
+            chapter_index,
+            chapter_href: Some(alloc::format!("c{}.xhtml", chapter_index)),
+            fallback_offset,
+            ..ReadingPosition::default()
+        };
+        let small = [1u8; 32];
+        let large = [2u8; 32];
+        let mut maps = PageMapSet::new();
+        maps.insert(
+            small,
+            alloc::vec![
+                start(0, 0),
+                start(0, 500),
+                start(1, 0),
+                start(1, 500),
+                start(1, 1000),
+                start(2, 0),
+                start(2, 500),
+                start(3, 0),
+            ],
+        );
+        // The larger font's map was only built for chapters 0, 1 and 3.
+        maps.insert(
+            large,
+            alloc::vec![
+                start(0, 0),
+                start(0, 300),
+                start(0, 600),
+                start(1, 0),
+                start(1, 300),
+                start(1, 600),
+                start(1, 900),
+                start(1, 1200),
+                start(3, 100),
+            ],
+        );
+
+        let exact = maps.remap_position(&at(1, 700), small, large).unwrap();
+        assert_eq!(exact.page, 5);
+        assert_eq!(exact.page_start, start(1, 600));
+        assert_eq!(exact.offset_in_page, 100);
+        assert_eq!(exact.position, at(1, 700));
+        assert_eq!(exact.conflict, None);
+
+        // Chapter 2 is missing: page 6 of 8 maps to page 7 of 9.
+        let missing = maps.remap_position(&at(2, 600), small, large).unwrap();
+        assert_eq!(
+            missing.conflict,
+            Some(RemapConflict::ChapterNotInMap { chapter_index: 2 })
+        );
+        assert_eq!(missing.page, 7);
+        assert_eq!(missing.position.chapter_index, 1);
+        assert_eq!(missing.position.fallback_offset, 1200);
+        assert_eq!(missing.position.chapter_href, None);
+
+        // Without the old map, it falls after the preceding chapter.
+        let unknown = maps.remap_position(&at(2, 600), [9u8; 32], large).unwrap();
+        assert_eq!(unknown.page, 7);
+        assert_eq!(unknown.page_start, start(1, 1200));
+
+        let early = maps.remap_position(&at(3, 50), small, large).unwrap();
+        assert_eq!(early.conflict, Some(RemapConflict::BeforeFirstPage));
+        assert_eq!(early.page, 8);
+        assert_eq!(early.position, at(3, 100));
+
+        assert_eq!(maps.remap_position(&at(0, 0), small, [9u8; 32]), None);
+    }
+}
@@ -2,7 +2,9 @@
 
 extern crate alloc;
 
+use alloc::borrow::Cow;
 use alloc::boxed::Box;
+use alloc::collections::VecDeque;
 use alloc::format;
 use alloc::string::{String, ToString};
 use alloc::vec::Vec;
@@ -10,13 +12,18 @@ use core::cmp::min;
 use core::fmt;
 use quick_xml::events::Event;
 use quick_xml::reader::Reader;
+use std::io::BufRead;
 
-use crate::book::EpubBook;
+use crate::book::{ChapterRef, EpubBook};
+use crate::cmap::GlyphCoverage;
 use crate::css::{
-    parse_inline_style, parse_stylesheet, CssStyle, FontSize, FontStyle, FontWeight, LineHeight,
-    Stylesheet,
+    cascade, parse_inline_declarations, parse_stylesheet_with_properties, BreakBefore, CssStyle,
+    CustomProperties, Dimension, Display, ElementPosition, Float, FontSize, FontStyle, FontWeight,
+    LineHeight, ListStyleType, StyleDeclarations, Stylesheet,
 };
 use crate::error::{EpubError, ErrorLimitContext, ErrorPhase, PhaseError, PhaseErrorContext};
+use crate::tokenizer::{NestingOverflow, Span};
+use crate::zip::percent_decode_path;
 
 /// Limits for stylesheet parsing and application.
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -25,8 +32,16 @@ pub struct StyleLimits {
     pub max_selectors: usize,
     /// Maximum bytes read for any individual stylesheet.
     pub max_css_bytes: usize,
-    /// Maximum supported list nesting depth (reserved for downstream layout usage).
+    /// Maximum element nesting depth kept on the styling stack.
     pub max_nesting: usize,
+    /// Handling of elements beyond `max_nesting`.
+    pub nesting_overflow: NestingOverflow,
+    /// Maximum attributes on a single element.
+    pub max_attributes: usize,
+    /// Maximum entity and character references expanded per chapter.
+    pub max_entity_expansions: usize,
+    /// Maximum markup events (tags, text nodes, references) per chapter.
+    pub max_events: usize,
 }
 
 impl Default for StyleLimits {
@@ -35,6 +50,10 @@ impl Default for StyleLimits {
             max_selectors: 4096,
             max_css_bytes: 512 * 1024,
             max_nesting: 32,
+            nesting_overflow: NestingOverflow::Flatten,
+            max_attributes: 256,
+            max_entity_expansions: 100_000,
+            max_events: 1_000_000,
         }
     }
 }
@@ -73,6 +92,13 @@ pub struct LayoutHints {
     pub min_line_height: f32,
     /// Upper clamp for effective line-height multiplier.
     pub max_line_height: f32,
+    /// Device pixels per CSS pixel (`dpi / 96`), applied to absolute
+    /// stylesheet sizes (`px`, `pt`).
+    pub css_px_scale: f32,
+    /// Viewport width in pixels for `vw` sizes; `0` ignores them.
+    pub viewport_width_px: f32,
+    /// Viewport height in pixels for `vh` sizes; `0` ignores them.
+    pub viewport_height_px: f32,
 }
 
 impl Default for LayoutHints {
@@ -83,10 +109,53 @@ impl Default for LayoutHints {
             max_font_size_px: 42.0,
             min_line_height: 1.1,
             max_line_height: 2.2,
+            css_px_scale: 1.0,
+            viewport_width_px: 0.0,
+            viewport_height_px: 0.0,
         }
     }
 }
 
+/// Fallback look of one heading level.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct HeadingLevelStyle {
+    /// Font size as a multiple of [`LayoutHints::base_font_size_px`].
+    pub size_scale: f32,
+    /// Font weight (`400` normal, `700` bold).
+    pub weight: u16,
+}
+
+/// Heading type ramp, applied to sizes and weights publisher CSS leaves
+/// unset so books without stylesheets keep a visual hierarchy.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TypeRamp {
+    /// Styles for `h1` through `h6`.
+    pub levels: [HeadingLevelStyle; 6],
+}
+
+impl Default for TypeRamp {
+    fn default() -> Self {
+        let level = |size_scale, weight| HeadingLevelStyle { size_scale, weight };
+        Self {
+            levels: [
+                level(1.6, 700),
+                level(1.4, 700),
+                level(1.25, 700),
+                level(1.1, 700),
+                level(1.0, 700),
+                level(1.0, 400),
+            ],
+        }
+    }
+}
+
+impl TypeRamp {
+    /// Style for heading `level`, clamped to `1..=6`.
+    pub fn level(&self, level: u8) -> HeadingLevelStyle {
+        self.levels[usize::from(level.clamp(1, 6) - 1)]
+    }
+}
+
 /// Style engine options.
 #[derive(Clone, Copy, Debug, Default, PartialEq)]
 pub struct StyleConfig {
@@ -94,6 +163,11 @@ pub struct StyleConfig {
     pub limits: StyleLimits,
     /// Normalization and clamp hints.
     pub hints: LayoutHints,
+    /// Fallback heading sizes and weights.
+    pub type_ramp: TypeRamp,
+    /// Keep subtrees hidden by `display: none` or the `hidden` attribute,
+    /// e.g. when styling for a search index.
+    pub include_hidden: bool,
 }
 
 /// Render-prep orchestration options.
@@ -107,6 +181,123 @@ pub struct RenderPrepOptions {
     pub layout_hints: LayoutHints,
     /// Hard memory/resource budgets.
     pub memory: MemoryBudget,
+    /// Typographic punctuation rewriting for text runs.
+    pub smart_punctuation: SmartPunctuation,
+    /// Sequential renumbering of footnote references.
+    pub footnotes: FootnoteNumbering,
+}
+
+/// Opt-in rewriting of ASCII punctuation into typographic forms.
+///
+/// Straight quotes become curly quotes in the configured [`QuoteStyle`],
+/// `--` becomes an em dash, and `...` becomes an ellipsis. Text inside
+/// `pre`/`code`-like elements is left untouched.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SmartPunctuation {
+    /// Enable the rewrite.
+    pub enabled: bool,
+    /// Quote marks substituted for straight quotes.
+    pub quotes: QuoteStyle,
+}
+
+/// Opt-in renumbering of footnote references.
+///
+/// Noterefs are links marked `epub:type="noteref"` or `role="doc-noteref"`,
+/// plus fragment links whose whole text is a marker such as `*`, `†`,
+/// `[3]`, or `¹`. Each gets the next number of the chapter in
+/// [`Self::format`], preceded by a [`StyledEvent::NoteRef`] that keeps the
+/// original link target.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct FootnoteNumbering {
+    /// Enable the rewrite.
+    pub enabled: bool,
+    /// Marker format for the new numbers.
+    pub format: NoterefFormat,
+}
+
+/// Marker format for renumbered footnote references.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum NoterefFormat {
+    /// Superscript digits, e.g. `¹²`.
+    #[default]
+    Superscript,
+    /// Bracketed digits, e.g. `[12]`.
+    Bracketed,
+}
+
+impl NoterefFormat {
+    /// Marker text for footnote `number`.
+    pub fn marker(self, number: u32) -> String {
+        match self {
+            Self::Superscript => number
+                .to_string()
+                .chars()
+                .map(|digit| match digit {
+                    '1' => '\u{b9}',
+                    '2' => '\u{b2}',
+                    '3' => '\u{b3}',
+                    d => char::from_u32(0x2070 + d.to_digit(10).unwrap_or(0)).unwrap_or(d),
+                })
+                .collect(),
+            Self::Bracketed => format!("[{number}]"),
+        }
+    }
+}
+
+/// Locale convention for curly quotes.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum QuoteStyle {
+    /// “double” and ‘single’.
+    #[default]
+    English,
+    /// „double“ and ‚single‘.
+    German,
+    /// «double» and ‹single›.
+    French,
+    /// „double” and ‚single’.
+    Polish,
+    /// ”double” and ’single’.
+    Swedish,
+}
+
+impl QuoteStyle {
+    /// Pick the conventional style for a BCP 47 language tag such as `de-AT`.
+    pub fn for_language(tag: &str) -> Self {
+        let primary = tag.split(['-', '_']).next().unwrap_or("");
+        match primary.to_ascii_lowercase().as_str() {
+            "de" | "cs" | "sk" | "sl" | "is" | "lt" => Self::German,
+            "fr" | "ru" | "uk" | "be" | "es" | "it" | "pt" | "el" | "no" | "nb" | "nn" => {
+                Self::French
+            }
+            "pl" | "hu" | "ro" | "hr" | "nl" | "bg" => Self::Polish,
+            "sv" | "fi" => Self::Swedish,
+            _ => Self::English,
+        }
+    }
+
+    /// Opening and closing double quotes.
+    fn double(self) -> (char, char) {
+        match self {
+            Self::English => ('\u{201C}', '\u{201D}'),
+            Self::German => ('\u{201E}', '\u{201C}'),
+            Self::French => ('\u{00AB}', '\u{00BB}'),
+            Self::Polish => ('\u{201E}', '\u{201D}'),
+            Self::Swedish => ('\u{201D}', '\u{201D}'),
+        }
+    }
+
+    /// Opening and closing single quotes.
+    fn single(self) -> (char, char) {
+        match self {
+            Self::English => ('\u{2018}', '\u{2019}'),
+            Self::German => ('\u{201A}', '\u{2018}'),
+            Self::French => ('\u{2039}', '\u{203A}'),
+            Self::Polish => ('\u{201A}', '\u{2019}'),
+            Self::Swedish => ('\u{2019}', '\u{2019}'),
+        }
+    }
 }
 
 /// Hard memory/resource budgets for open/parse/style/layout/render paths.
@@ -363,7 +554,9 @@ impl ChapterStylesheets {
 }
 
 /// Font style descriptor for `@font-face` metadata.
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
 pub enum EmbeddedFontStyle {
     /// Upright style.
     Normal,
@@ -374,7 +567,8 @@ pub enum EmbeddedFontStyle {
 }
 
 /// Embedded font face metadata extracted from EPUB CSS.
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct EmbeddedFontFace {
     /// Requested font family from `@font-face`.
     pub family: String,
@@ -390,8 +584,141 @@ pub struct EmbeddedFontFace {
     pub format: Option<String>,
 }
 
+/// Font obfuscation algorithm declared in `META-INF/encryption.xml`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum FontObfuscation {
+    /// IDPF (`http://www.idpf.org/2008/embedding`): the first 1040 bytes are
+    /// XORed with the SHA-1 of the package unique identifier.
+    Idpf,
+    /// Adobe (`http://ns.adobe.com/pdf/enc#RC`): the first 1024 bytes are
+    /// XORed with the 16 bytes of the identifier's UUID.
+    Adobe,
+}
+
+impl FontObfuscation {
+    /// Algorithm for an `EncryptionMethod` URI, if it is a font obfuscation.
+    pub fn from_algorithm(uri: &str) -> Option<Self> {
+        match uri.trim() {
+            "http://www.idpf.org/2008/embedding" => Some(Self::Idpf),
+            "http://ns.adobe.com/pdf/enc#RC" => Some(Self::Adobe),
+            _ => None,
+        }
+    }
+
+    /// Reverse the obfuscation of `bytes` in place.
+    ///
+    /// Obfuscation is a XOR, so this also obfuscates plain bytes. Returns
+    /// `false` and leaves `bytes` untouched when no key can be derived from
+    /// `unique_identifier` (Adobe needs a UUID).
+    pub fn deobfuscate(self, bytes: &mut [u8], unique_identifier: &str) -> bool {
+        match self {
+            Self::Idpf => {
+                let id: String = unique_identifier
+                    .chars()
+                    .filter(|ch| !matches!(ch, ' ' | '\t' | '\r' | '\n'))
+                    .collect();
+                xor_prefix(bytes, &crate::digest::sha1(id.as_bytes()), 1040);
+                true
+            }
+            Self::Adobe => match adobe_font_key(unique_identifier) {
+                Some(key) => {
+                    xor_prefix(bytes, &key, 1024);
+                    true
+                }
+                None => false,
+            },
+        }
+    }
+}
+
+/// XOR the first `len` bytes with a repeating `key`.
+fn xor_prefix(bytes: &mut [u8], key: &[u8], len: usize) {
+    for (byte, k) in bytes.iter_mut().take(len).zip(key.iter().cycle()) {
+        *byte ^= k;
+    }
+}
+
+/// 16-byte Adobe key from a `urn:uuid:` identifier.
+fn adobe_font_key(identifier: &str) -> Option<[u8; 16]> {
+    let identifier = identifier.trim();
+    let uuid = identifier
+        .get(..9)
+        .filter(|prefix| prefix.eq_ignore_ascii_case("urn:uuid:"))
+        .map_or(identifier, |_| &identifier[9..]);
+    let digits: Vec<u8> = uuid
+        .bytes()
+        .filter(|b| *b != b'-')
+        .map(|b| (b as char).to_digit(16).map(|d| d as u8))
+        .collect::<Option<_>>()?;
+    if digits.len() != 32 {
+        return None;
+    }
+    let mut key = [0u8; 16];
+    for (byte, pair) in key.iter_mut().zip(digits.chunks_exact(2)) {
+        *byte = (pair[0] << 4) | pair[1];
+    }
+    Some(key)
+}
+
+/// Obfuscated font resources of one book and the key to reverse them.
+///
+/// Built by [`EpubBook::font_obfuscation`](crate::EpubBook::font_obfuscation)
+/// from `META-INF/encryption.xml`; hrefs are OPF-relative, matching
+/// [`EmbeddedFontFace::href`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct FontObfuscationMap {
+    unique_identifier: String,
+    entries: Vec<(String, FontObfuscation)>,
+}
+
+impl FontObfuscationMap {
+    /// Empty map keyed by the package unique identifier.
+    pub fn new(unique_identifier: impl Into<String>) -> Self {
+        Self {
+            unique_identifier: unique_identifier.into(),
+            entries: Vec::with_capacity(0),
+        }
+    }
+
+    /// Record that the resource at `href` is obfuscated with `algorithm`.
+    pub fn insert(&mut self, href: &str, algorithm: FontObfuscation) {
+        let href = normalize_obfuscated_href(href).to_string();
+        match self.entries.iter_mut().find(|(known, _)| *known == href) {
+            Some(entry) => entry.1 = algorithm,
+            None => self.entries.push((href, algorithm)),
+        }
+    }
+
+    /// Algorithm obfuscating the resource at `href`, if any.
+    pub fn get(&self, href: &str) -> Option<FontObfuscation> {
+        let href = normalize_obfuscated_href(href);
+        self.entries
+            .iter()
+            .find(|(known, _)| known == href)
+            .map(|(_, algorithm)| *algorithm)
+    }
+
+    /// Whether no obfuscated resources are recorded.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// De-obfuscate `bytes` loaded from `href` in place, if it is listed.
+    pub fn deobfuscate(&self, href: &str, bytes: &mut [u8]) -> bool {
+        self.get(href)
+            .is_some_and(|algorithm| algorithm.deobfuscate(bytes, &self.unique_identifier))
+    }
+}
+
+fn normalize_obfuscated_href(href: &str) -> &str {
+    let href = href.split('#').next().unwrap_or(href);
+    href.trim_start_matches("./")
+}
+
 /// Semantic block role for computed styles.
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
 pub enum BlockRole {
     /// Body text.
     Body,
@@ -401,6 +728,33 @@ pub enum BlockRole {
     Heading(u8),
     /// List item block.
     ListItem,
+    /// Figure caption block.
+    Caption,
+}
+
+impl BlockRole {
+    /// Stable `(kind, heading level)` code for binary formats.
+    pub fn code(self) -> (u8, u8) {
+        match self {
+            Self::Body => (0, 0),
+            Self::Paragraph => (1, 0),
+            Self::Heading(level) => (2, level),
+            Self::ListItem => (3, 0),
+            Self::Caption => (4, 0),
+        }
+    }
+
+    /// Decode a role written with [`code`](Self::code).
+    pub fn from_code(kind: u8, level: u8) -> Option<Self> {
+        match kind {
+            0 => Some(Self::Body),
+            1 => Some(Self::Paragraph),
+            2 => Some(Self::Heading(level)),
+            3 => Some(Self::ListItem),
+            4 => Some(Self::Caption),
+            _ => None,
+        }
+    }
 }
 
 /// Cascaded and normalized text style for rendering.
@@ -420,6 +774,8 @@ pub struct ComputedTextStyle {
     pub letter_spacing: f32,
     /// Semantic block role.
     pub block_role: BlockRole,
+    /// BCP 47 language from the nearest `lang`/`xml:lang`, if known.
+    pub language: Option<String>,
 }
 
 /// Styled text run.
@@ -433,10 +789,14 @@ pub struct StyledRun {
     pub font_id: u32,
     /// Resolved family selected by the font resolver.
     pub resolved_family: String,
+    /// Styling the renderer must synthesize for the resolved face.
+    pub synthesis: FontSynthesis,
+    /// Source byte range of this run in the chapter XHTML, when known.
+    pub span: Option<Span>,
 }
 
 /// Structured block/layout events.
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub enum StyledEvent {
     /// Paragraph starts.
     ParagraphStart,
@@ -446,12 +806,85 @@ pub enum StyledEvent {
     HeadingStart(u8),
     /// Heading ends.
     HeadingEnd(u8),
+    /// List (`ol`/`ul`) starts with its resolved marker style and first
+    /// ordinal.
+    ListStart {
+        /// Marker style for the list's items.
+        style: ListStyleType,
+        /// Ordinal of the first item.
+        start: i32,
+    },
+    /// List ends.
+    ListEnd,
+    /// Ordinal override from `<li value="n">`, emitted before the item's
+    /// start event.
+    ListItemValue(i32),
     /// List item starts.
     ListItemStart,
     /// List item ends.
     ListItemEnd,
+    /// Blockquote starts at the given nesting depth (1 = outermost).
+    BlockquoteStart(u8),
+    /// Blockquote at the given nesting depth ends.
+    BlockquoteEnd(u8),
+    /// Image (`<img>`) with its declared size; author CSS wins over the
+    /// `width`/`height` attributes.
+    Image {
+        /// Image source path as written in the chapter.
+        src: String,
+        /// Alternative text.
+        alt: String,
+        /// Declared width, if any.
+        width: Option<Dimension>,
+        /// Declared height, if any.
+        height: Option<Dimension>,
+        /// Float placement from CSS `float` or the `align` attribute.
+        float: Float,
+    },
+    /// Margins the author set on the next paragraph or heading, in pixels,
+    /// emitted before its start event. Unset sides keep the layout default.
+    BlockMargins {
+        /// Space above the block.
+        top: Option<u32>,
+        /// Space below the block.
+        bottom: Option<u32>,
+    },
+    /// Forced page, column, or region break from `break-before` or its
+    /// legacy spellings, emitted before the block's anchor and start events.
+    /// Never [`BreakBefore::Auto`].
+    BlockBreak(BreakBefore),
     /// Explicit line break.
     LineBreak,
+    /// Element `id` attribute (fragment target), emitted before the
+    /// element's own start event.
+    Anchor(String),
+    /// Footnote reference renumbered by [`FootnoteNumbering`]; the next run
+    /// is its new marker.
+    NoteRef {
+        /// 1-based number within the chapter.
+        number: u32,
+        /// Original link target, e.g. `notes.xhtml#n4`.
+        target: String,
+    },
+    /// The chapter contains a `<script>` element. Emitted once, where the
+    /// first script appears, so UIs can show an "interactive content not
+    /// supported" placeholder.
+    ScriptedContent,
+    /// Thematic break (`<hr>`).
+    Hr,
+    /// Link (`<a href>`) content starts; emitted only when enabled with
+    /// [`Styler::with_link_events`].
+    LinkStart(String),
+    /// Link content ends.
+    LinkEnd,
+    /// Figure starts.
+    FigureStart,
+    /// Figure ends.
+    FigureEnd,
+    /// Figure caption starts.
+    CaptionStart,
+    /// Figure caption ends.
+    CaptionEnd,
 }
 
 /// Stream item for styled output.
@@ -489,11 +922,47 @@ impl StyledChapter {
     }
 }
 
+impl IntoIterator for StyledChapter {
+    type Item = StyledEventOrRun;
+    type IntoIter = std::vec::IntoIter<StyledEventOrRun>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.items.into_iter()
+    }
+}
+
+impl FromIterator<StyledEventOrRun> for StyledChapter {
+    fn from_iter<T: IntoIterator<Item = StyledEventOrRun>>(iter: T) -> Self {
+        Self::from_items(iter.into_iter().collect())
+    }
+}
+
+/// Parse state of one chapter being styled, advanced one markup event at a
+/// time by [`Styler::style_step`].
+struct StyleState<R> {
+    reader: Reader<R>,
+    buf: Vec<u8>,
+    stack: Vec<ElementCtx>,
+    skip_depth: usize,
+    hidden_depth: usize,
+    flattened_depth: usize,
+    event_count: usize,
+    entity_count: usize,
+    saw_script: bool,
+    punctuation: SmartPunctuator,
+    noterefs: NoterefState,
+    siblings: SiblingTracker,
+    finished: bool,
+}
+
 /// Lightweight style system with CSS cascade resolution.
 #[derive(Clone, Debug)]
 pub struct Styler {
     config: StyleConfig,
     memory: MemoryBudget,
+    smart_punctuation: SmartPunctuation,
+    footnotes: FootnoteNumbering,
+    link_events: bool,
     parsed: Vec<Stylesheet>,
 }
 
@@ -503,6 +972,9 @@ impl Styler {
         Self {
             config,
             memory: MemoryBudget::default(),
+            smart_punctuation: SmartPunctuation::default(),
+            footnotes: FootnoteNumbering::default(),
+            link_events: false,
             parsed: Vec::with_capacity(0),
         }
     }
@@ -513,6 +985,25 @@ impl Styler {
         self
     }
 
+    /// Rewrite straight quotes, dashes, and ellipses in emitted runs.
+    pub fn with_smart_punctuation(mut self, smart_punctuation: SmartPunctuation) -> Self {
+        self.smart_punctuation = smart_punctuation;
+        self
+    }
+
+    /// Renumber footnote references sequentially per chapter.
+    pub fn with_footnote_numbering(mut self, footnotes: FootnoteNumbering) -> Self {
+        self.footnotes = footnotes;
+        self
+    }
+
+    /// Emit [`StyledEvent::LinkStart`]/[`StyledEvent::LinkEnd`] around link
+    /// content, for consumers that keep hyperlinks (e.g. Markdown export).
+    pub fn with_link_events(mut self, enabled: bool) -> Self {
+        self.link_events = enabled;
+        self
+    }
+
     /// Parse and load stylesheets in cascade order.
     pub fn load_stylesheets(
         &mut self,
@@ -546,7 +1037,12 @@ impl Styler {
             .with_source(href.to_string());
             return Err(err);
         }
-        let parsed = parse_stylesheet(css).map_err(|e| {
+        let inherited = self
+            .parsed
+            .last()
+            .map(|sheet| sheet.custom_properties.clone())
+            .unwrap_or_default();
+        let parsed = parse_stylesheet_with_properties(css, &inherited).map_err(|e| {
             RenderPrepError::new_with_phase(
                 ErrorPhase::Style,
                 "STYLE_PARSE_ERROR",
@@ -613,218 +1109,519 @@ impl Styler {
     where
         F: FnMut(StyledEventOrRun),
     {
-        let mut reader = Reader::from_reader(html_bytes);
+        let mut state = self.style_state(html_bytes, self.last_child_flags(html_bytes));
+        while self.style_step(&mut state, &mut on_item)? {}
+        Ok(())
+    }
+
+    /// [`last_child_flags`] of the chapter, computed only when a loaded
+    /// selector needs them.
+    fn last_child_flags(&self, html_bytes: &[u8]) -> Vec<bool> {
+        let uses_last_child = self
+            .parsed
+            .iter()
+            .flat_map(|sheet| sheet.rules.iter())
+            .any(|rule| rule.selector.uses_last_child());
+        if uses_last_child {
+            last_child_flags(html_bytes)
+        } else {
+            Vec::with_capacity(0)
+        }
+    }
+
+    /// Fresh parse state for styling `source`.
+    fn style_state<R: BufRead>(&self, source: R, last_child: Vec<bool>) -> StyleState<R> {
+        let mut reader = Reader::from_reader(source);
         reader.config_mut().trim_text(false);
-        let mut buf = Vec::with_capacity(0);
-        let mut stack: Vec<ElementCtx> = Vec::with_capacity(0);
-        let mut skip_depth = 0usize;
+        StyleState {
+            reader,
+            buf: Vec::with_capacity(0),
+            stack: Vec::with_capacity(0),
+            skip_depth: 0,
+            hidden_depth: 0,
+            flattened_depth: 0,
+            event_count: 0,
+            entity_count: 0,
+            saw_script: false,
+            punctuation: SmartPunctuator::new(self.smart_punctuation),
+            noterefs: NoterefState::new(self.footnotes),
+            siblings: SiblingTracker::new(last_child),
+            finished: false,
+        }
+    }
 
-        loop {
-            match reader.read_event_into(&mut buf) {
-                Ok(Event::Start(e)) => {
-                    let tag = decode_tag_name(&reader, e.name().as_ref())?;
-                    if should_skip_tag(&tag) {
-                        skip_depth += 1;
-                        buf.clear();
-                        continue;
-                    }
-                    if skip_depth > 0 {
-                        buf.clear();
-                        continue;
-                    }
-                    let ctx =
-                        element_ctx_from_start(&reader, &e, self.memory.max_inline_style_bytes)?;
-                    emit_start_event(&ctx.tag, &mut on_item);
-                    stack.push(ctx);
+    /// Consume one markup event from `state`, streaming the items it
+    /// completes to `on_item`. Returns `false` once the chapter has ended.
+    fn style_step<R: BufRead, F: FnMut(StyledEventOrRun)>(
+        &self,
+        state: &mut StyleState<R>,
+        on_item: &mut F,
+    ) -> Result<bool, RenderPrepError> {
+        if state.finished {
+            return Ok(false);
+        }
+        let limits = self.config.limits;
+        let no_properties = CustomProperties::new();
+        let properties = self
+            .parsed
+            .last()
+            .map_or(&no_properties, |sheet| &sheet.custom_properties);
+        let mut out = NoterefNumberer::new(&mut state.noterefs, on_item);
+        state.buf.clear();
+        let event_start = state.reader.buffer_position() as usize;
+        let event = state.reader.read_event_into(&mut state.buf);
+        if matches!(event, Ok(ref ev) if !matches!(ev, Event::Eof)) {
+            state.event_count += 1;
+            if state.event_count > limits.max_events {
+                return Err(stream_limit_error(
+                    "STYLE_EVENT_LIMIT",
+                    "max_events",
+                    state.event_count,
+                    limits.max_events,
+                    event_start,
+                ));
+            }
+        }
+        match event {
+            Ok(Event::Start(e)) => {
+                let tag = decode_tag_name(&state.reader, e.name().as_ref())?;
+                let position = state
+                    .siblings
+                    .enter(&tag, state.skip_depth == 0 && state.hidden_depth == 0);
+                emit_scripted_content(&tag, &mut state.saw_script, &mut |item| out.emit(item));
+                if state.hidden_depth > 0 {
+                    state.hidden_depth += 1;
+                    return Ok(true);
                 }
-                Ok(Event::Empty(e)) => {
-                    let tag = decode_tag_name(&reader, e.name().as_ref())?;
-                    if skip_depth > 0 || should_skip_tag(&tag) {
-                        buf.clear();
-                        continue;
-                    }
-                    let ctx =
-                        element_ctx_from_start(&reader, &e, self.memory.max_inline_style_bytes)?;
-                    emit_start_event(&ctx.tag, &mut on_item);
-                    if ctx.tag == "br" {
-                        on_item(StyledEventOrRun::Event(StyledEvent::LineBreak));
-                    }
-                    emit_end_event(&ctx.tag, &mut on_item);
+                if should_skip_tag(&tag) || is_unrendered_epub_element(e.name().as_ref()) {
+                    state.skip_depth += 1;
+                    return Ok(true);
                 }
-                Ok(Event::End(e)) => {
-                    let tag = decode_tag_name(&reader, e.name().as_ref())?;
-                    if should_skip_tag(&tag) {
-                        skip_depth = skip_depth.saturating_sub(1);
-                        buf.clear();
-                        continue;
-                    }
-                    if skip_depth > 0 {
-                        buf.clear();
-                        continue;
-                    }
-                    emit_end_event(&tag, &mut on_item);
-                    if !stack.is_empty() {
-                        stack.pop();
-                    }
+                if state.skip_depth > 0 {
+                    return Ok(true);
                 }
-                Ok(Event::Text(e)) => {
-                    if skip_depth > 0 {
-                        buf.clear();
-                        continue;
-                    }
-                    let text = e
-                        .decode()
-                        .map_err(|err| {
-                            RenderPrepError::new(
-                                "STYLE_TOKENIZE_ERROR",
-                                format!("Decode error: {:?}", err),
-                            )
-                            .with_phase(ErrorPhase::Style)
-                            .with_source("text node decode")
-                            .with_token_offset(reader_token_offset(&reader))
-                        })?
-                        .to_string();
-                    let preserve_ws = is_preformatted_context(&stack);
-                    let normalized = normalize_plain_text_whitespace(&text, preserve_ws);
-                    if normalized.is_empty() {
-                        buf.clear();
-                        continue;
+                check_attribute_limit(&e, limits.max_attributes, event_start)?;
+                if state.stack.len() >= limits.max_nesting {
+                    let depth = state.stack.len() + state.flattened_depth + 1;
+                    if limits.nesting_overflow == NestingOverflow::Error {
+                        return Err(RenderPrepError::new(
+                            "STYLE_NESTING_LIMIT",
+                            format!(
+                                "<{}> nesting exceeds max_nesting ({} > {})",
+                                tag, depth, limits.max_nesting
+                            ),
+                        )
+                        .with_limit("max_nesting", depth, limits.max_nesting)
+                        .with_token_offset(event_start));
                     }
-                    let (resolved, role, bold_tag, italic_tag) = self.resolve_context_style(&stack);
-                    let style = self.compute_style(resolved, role, bold_tag, italic_tag);
-                    on_item(StyledEventOrRun::Run(StyledRun {
-                        text: normalized,
-                        style,
-                        font_id: 0,
-                        resolved_family: String::with_capacity(0),
-                    }));
+                    // Keep the anchor so links into flattened markup resolve.
+                    let mut ctx = element_ctx_from_start(
+                        &state.reader,
+                        &e,
+                        self.memory.max_inline_style_bytes,
+                        properties,
+                    )?;
+                    emit_anchor_event(&mut ctx, &mut |item| out.emit(item));
+                    state.flattened_depth += 1;
+                    state.siblings.open_children();
+                    return Ok(true);
                 }
-                Ok(Event::CData(e)) => {
-                    if skip_depth > 0 {
-                        buf.clear();
-                        continue;
-                    }
-                    let text = reader
-                        .decoder()
-                        .decode(&e)
-                        .map_err(|err| {
-                            RenderPrepError::new(
-                                "STYLE_TOKENIZE_ERROR",
-                                format!("Decode error: {:?}", err),
-                            )
-                            .with_phase(ErrorPhase::Style)
-                            .with_source("cdata decode")
-                            .with_token_offset(reader_token_offset(&reader))
-                        })?
-                        .to_string();
-                    let preserve_ws = is_preformatted_context(&stack);
-                    let normalized = normalize_plain_text_whitespace(&text, preserve_ws);
-                    if normalized.is_empty() {
-                        buf.clear();
-                        continue;
+                let mut ctx = element_ctx_from_start(
+                    &state.reader,
+                    &e,
+                    self.memory.max_inline_style_bytes,
+                    properties,
+                )?;
+                ctx.position = position;
+                if self.is_hidden(&ctx) {
+                    state.hidden_depth = 1;
+                    return Ok(true);
+                }
+                self.emit_block_break(&ctx, &mut |item| out.emit(item));
+                emit_anchor_event(&mut ctx, &mut |item| out.emit(item));
+                state.punctuation.enter_tag(&ctx.tag);
+                self.emit_list_event(&ctx, &mut |item| out.emit(item));
+                self.emit_block_margins(&ctx, &mut |item| out.emit(item));
+                emit_start_event(&ctx.tag, &mut |item| out.emit(item));
+                self.emit_image_event(&ctx, &mut |item| out.emit(item));
+                if ctx.tag == "blockquote" {
+                    let depth = blockquote_depth(&state.stack).saturating_add(1);
+                    out.emit(StyledEventOrRun::Event(StyledEvent::BlockquoteStart(depth)));
+                }
+                out.open_link(&ctx, state.stack.len());
+                if self.link_events && ctx.tag == "a" {
+                    if let Some(href) = &ctx.href {
+                        out.emit(StyledEventOrRun::Event(StyledEvent::LinkStart(
+                            href.clone(),
+                        )));
                     }
-                    let (resolved, role, bold_tag, italic_tag) = self.resolve_context_style(&stack);
-                    let style = self.compute_style(resolved, role, bold_tag, italic_tag);
-                    on_item(StyledEventOrRun::Run(StyledRun {
-                        text: normalized,
-                        style,
-                        font_id: 0,
-                        resolved_family: String::with_capacity(0),
-                    }));
                 }
-                Ok(Event::GeneralRef(e)) => {
-                    if skip_depth > 0 {
-                        buf.clear();
-                        continue;
+                state.stack.push(ctx);
+                state.siblings.open_children();
+            }
+            Ok(Event::Empty(e)) => {
+                let tag = decode_tag_name(&state.reader, e.name().as_ref())?;
+                let position = state
+                    .siblings
+                    .enter(&tag, state.skip_depth == 0 && state.hidden_depth == 0);
+                emit_scripted_content(&tag, &mut state.saw_script, &mut |item| out.emit(item));
+                if state.skip_depth > 0
+                    || state.hidden_depth > 0
+                    || should_skip_tag(&tag)
+                    || is_unrendered_epub_element(e.name().as_ref())
+                {
+                    return Ok(true);
+                }
+                check_attribute_limit(&e, limits.max_attributes, event_start)?;
+                let mut ctx = element_ctx_from_start(
+                    &state.reader,
+                    &e,
+                    self.memory.max_inline_style_bytes,
+                    properties,
+                )?;
+                ctx.position = position;
+                if self.is_hidden(&ctx) {
+                    return Ok(true);
+                }
+                self.emit_block_break(&ctx, &mut |item| out.emit(item));
+                emit_anchor_event(&mut ctx, &mut |item| out.emit(item));
+                state.punctuation.enter_tag(&ctx.tag);
+                self.emit_list_event(&ctx, &mut |item| out.emit(item));
+                emit_start_event(&ctx.tag, &mut |item| out.emit(item));
+                self.emit_image_event(&ctx, &mut |item| out.emit(item));
+                if ctx.tag == "br" {
+                    out.emit(StyledEventOrRun::Event(StyledEvent::LineBreak));
+                }
+                emit_end_event(&ctx.tag, &mut |item| out.emit(item));
+            }
+            Ok(Event::End(e)) => {
+                let tag = decode_tag_name(&state.reader, e.name().as_ref())?;
+                if state.hidden_depth > 0 {
+                    state.hidden_depth -= 1;
+                    return Ok(true);
+                }
+                if should_skip_tag(&tag) || is_unrendered_epub_element(e.name().as_ref()) {
+                    state.skip_depth = state.skip_depth.saturating_sub(1);
+                    return Ok(true);
+                }
+                if state.skip_depth > 0 {
+                    return Ok(true);
+                }
+                if state.flattened_depth > 0 {
+                    state.flattened_depth -= 1;
+                    state.siblings.close_children();
+                    return Ok(true);
+                }
+                state.punctuation.enter_tag(&tag);
+                emit_end_event(&tag, &mut |item| out.emit(item));
+                if tag == "blockquote" {
+                    let depth = blockquote_depth(&state.stack).max(1);
+                    out.emit(StyledEventOrRun::Event(StyledEvent::BlockquoteEnd(depth)));
+                }
+                state.siblings.close_children();
+                if let Some(ctx) = state.stack.pop() {
+                    if self.link_events && ctx.tag == "a" && ctx.href.is_some() {
+                        out.emit(StyledEventOrRun::Event(StyledEvent::LinkEnd));
                     }
-                    let entity_name = e.decode().map_err(|err| {
+                }
+                out.close_link(state.stack.len());
+            }
+            Ok(Event::Text(e)) => {
+                if state.skip_depth > 0 || state.hidden_depth > 0 {
+                    return Ok(true);
+                }
+                let text = e
+                    .decode()
+                    .map_err(|err| {
+                        RenderPrepError::new(
+                            "STYLE_TOKENIZE_ERROR",
+                            format!("Decode error: {:?}", err),
+                        )
+                        .with_phase(ErrorPhase::Style)
+                        .with_source("text node decode")
+                        .with_token_offset(reader_token_offset(&state.reader))
+                    })?
+                    .to_string();
+                let preserve_ws = is_preformatted_context(&state.stack);
+                let text = state.punctuation.apply(&text, preserve_ws);
+                let normalized = normalize_plain_text_whitespace(&text, preserve_ws);
+                if normalized.is_empty() {
+                    return Ok(true);
+                }
+                let style = self.style_for_stack(&state.stack);
+                out.emit(StyledEventOrRun::Run(StyledRun {
+                    text: normalized,
+                    style,
+                    font_id: 0,
+                    resolved_family: String::with_capacity(0),
+                    synthesis: FontSynthesis::default(),
+                    span: Some(Span::new(
+                        event_start,
+                        state.reader.buffer_position() as usize,
+                    )),
+                }));
+            }
+            Ok(Event::CData(e)) => {
+                if state.skip_depth > 0 || state.hidden_depth > 0 {
+                    return Ok(true);
+                }
+                let text = state
+                    .reader
+                    .decoder()
+                    .decode(&e)
+                    .map_err(|err| {
                         RenderPrepError::new(
                             "STYLE_TOKENIZE_ERROR",
                             format!("Decode error: {:?}", err),
                         )
+                        .with_phase(ErrorPhase::Style)
+                        .with_source("cdata decode")
+                        .with_token_offset(reader_token_offset(&state.reader))
+                    })?
+                    .to_string();
+                let preserve_ws = is_preformatted_context(&state.stack);
+                let text = state.punctuation.apply(&text, preserve_ws);
+                let normalized = normalize_plain_text_whitespace(&text, preserve_ws);
+                if normalized.is_empty() {
+                    return Ok(true);
+                }
+                let style = self.style_for_stack(&state.stack);
+                out.emit(StyledEventOrRun::Run(StyledRun {
+                    text: normalized,
+                    style,
+                    font_id: 0,
+                    resolved_family: String::with_capacity(0),
+                    synthesis: FontSynthesis::default(),
+                    span: Some(Span::new(
+                        event_start,
+                        state.reader.buffer_position() as usize,
+                    )),
+                }));
+            }
+            Ok(Event::GeneralRef(e)) => {
+                if state.skip_depth > 0 || state.hidden_depth > 0 {
+                    return Ok(true);
+                }
+                state.entity_count += 1;
+                if state.entity_count > limits.max_entity_expansions {
+                    return Err(stream_limit_error(
+                        "STYLE_ENTITY_LIMIT",
+                        "max_entity_expansions",
+                        state.entity_count,
+                        limits.max_entity_expansions,
+                        event_start,
+                    ));
+                }
+                let entity_name = e.decode().map_err(|err| {
+                    RenderPrepError::new("STYLE_TOKENIZE_ERROR", format!("Decode error: {:?}", err))
                         .with_phase(ErrorPhase::Style)
                         .with_source("entity decode")
-                        .with_token_offset(reader_token_offset(&reader))
-                    })?;
-                    let entity = format!("&{};", entity_name);
-                    let resolved_entity = quick_xml::escape::unescape(&entity)
-                        .map_err(|err| {
-                            RenderPrepError::new(
-                                "STYLE_TOKENIZE_ERROR",
-                                format!("Unescape error: {:?}", err),
-                            )
-                            .with_phase(ErrorPhase::Style)
-                            .with_source("entity unescape")
-                            .with_token_offset(reader_token_offset(&reader))
-                        })?
-                        .to_string();
-                    let preserve_ws = is_preformatted_context(&stack);
-                    let normalized = normalize_plain_text_whitespace(&resolved_entity, preserve_ws);
-                    if normalized.is_empty() {
-                        buf.clear();
-                        continue;
-                    }
-                    let (resolved, role, bold_tag, italic_tag) = self.resolve_context_style(&stack);
-                    let style = self.compute_style(resolved, role, bold_tag, italic_tag);
-                    on_item(StyledEventOrRun::Run(StyledRun {
-                        text: normalized,
-                        style,
-                        font_id: 0,
-                        resolved_family: String::with_capacity(0),
-                    }));
+                        .with_token_offset(reader_token_offset(&state.reader))
+                })?;
+                let entity = format!("&{};", entity_name);
+                let resolved_entity = quick_xml::escape::unescape(&entity)
+                    .map_err(|err| {
+                        RenderPrepError::new(
+                            "STYLE_TOKENIZE_ERROR",
+                            format!("Unescape error: {:?}", err),
+                        )
+                        .with_phase(ErrorPhase::Style)
+                        .with_source("entity unescape")
+                        .with_token_offset(reader_token_offset(&state.reader))
+                    })?
+                    .to_string();
+                let preserve_ws = is_preformatted_context(&state.stack);
+                let resolved_entity = state.punctuation.apply(&resolved_entity, preserve_ws);
+                let normalized = normalize_plain_text_whitespace(&resolved_entity, preserve_ws);
+                if normalized.is_empty() {
+                    return Ok(true);
                 }
-                Ok(Event::Eof) => break,
-                Ok(_) => {}
-                Err(err) => {
-                    return Err(RenderPrepError::new(
-                        "STYLE_TOKENIZE_ERROR",
-                        format!("XML error: {:?}", err),
-                    )
-                    .with_phase(ErrorPhase::Style)
-                    .with_source("xml tokenizer")
-                    .with_token_offset(reader_token_offset(&reader)));
+                let style = self.style_for_stack(&state.stack);
+                out.emit(StyledEventOrRun::Run(StyledRun {
+                    text: normalized,
+                    style,
+                    font_id: 0,
+                    resolved_family: String::with_capacity(0),
+                    synthesis: FontSynthesis::default(),
+                    span: Some(Span::new(
+                        event_start,
+                        state.reader.buffer_position() as usize,
+                    )),
+                }));
+            }
+            Ok(Event::Eof) => {
+                out.finish();
+                state.finished = true;
+                return Ok(false);
+            }
+            Ok(_) => {}
+            Err(err) => {
+                return Err(RenderPrepError::new(
+                    "STYLE_TOKENIZE_ERROR",
+                    format!("XML error: {:?}", err),
+                )
+                .with_phase(ErrorPhase::Style)
+                .with_source("xml tokenizer")
+                .with_token_offset(reader_token_offset(&state.reader)));
+            }
+        }
+        Ok(true)
+    }
+
+    /// Emit list start or item ordinal events, resolving the list's marker
+    /// style from CSS, then the `type` attribute, then the tag default.
+    fn emit_list_event<F: FnMut(StyledEventOrRun)>(&self, ctx: &ElementCtx, on_item: &mut F) {
+        match ctx.tag.as_str() {
+            "ol" | "ul" => {
+                let css = self.element_style(ctx);
+                let style = css
+                    .list_style_type
+                    .or_else(|| ctx.list_type.clone())
+                    .unwrap_or(if ctx.tag == "ol" {
+                        ListStyleType::Decimal
+                    } else {
+                        ListStyleType::Disc
+                    });
+                on_item(StyledEventOrRun::Event(StyledEvent::ListStart {
+                    style,
+                    start: ctx.list_ordinal.unwrap_or(1),
+                }));
+            }
+            "li" => {
+                if let Some(value) = ctx.list_ordinal {
+                    on_item(StyledEventOrRun::Event(StyledEvent::ListItemValue(value)));
                 }
             }
-            buf.clear();
+            _ => {}
         }
+    }
 
-        Ok(())
+    fn emit_image_event<F: FnMut(StyledEventOrRun)>(&self, ctx: &ElementCtx, on_item: &mut F) {
+        if ctx.tag != "img" {
+            return;
+        }
+        let Some(src) = ctx.src.clone() else {
+            return;
+        };
+        let css = self.element_style(ctx);
+        on_item(StyledEventOrRun::Event(StyledEvent::Image {
+            src,
+            alt: ctx.alt.clone().unwrap_or_default(),
+            width: css.width.or(ctx.attr_width),
+            height: css.height.or(ctx.attr_height),
+            float: css.float.or(ctx.attr_float).unwrap_or_default(),
+        }));
+    }
+
+    /// Emit the element's own CSS margins ahead of a paragraph or heading
+    /// start. Margins are box properties, so ancestors' margins never apply.
+    fn emit_block_margins<F: FnMut(StyledEventOrRun)>(&self, ctx: &ElementCtx, on_item: &mut F) {
+        if !matches!(
+            ctx.tag.as_str(),
+            "p" | "div" | "h1" | "h2" | "h3" | "h4" | "h5" | "h6"
+        ) {
+            return;
+        }
+        let css = self.element_style(ctx).box_properties();
+        if css.margin_top.is_none() && css.margin_bottom.is_none() {
+            return;
+        }
+        let scale = self.config.hints.css_px_scale;
+        let to_px = |margin: f32| (margin * scale).max(0.0).round() as u32;
+        on_item(StyledEventOrRun::Event(StyledEvent::BlockMargins {
+            top: css.margin_top.map(to_px),
+            bottom: css.margin_bottom.map(to_px),
+        }));
+    }
+
+    /// Emit a forced break ahead of a block-level element that asks for one.
+    /// Like margins, `break-before` is a box property and never inherited.
+    fn emit_block_break<F: FnMut(StyledEventOrRun)>(&self, ctx: &ElementCtx, on_item: &mut F) {
+        let css = self.element_style(ctx).box_properties();
+        let Some(kind) = css.break_before.filter(|kind| *kind != BreakBefore::Auto) else {
+            return;
+        };
+        let block = match css.display {
+            Some(display) => matches!(display, Display::Block | Display::ListItem),
+            None => is_block_tag(&ctx.tag),
+        };
+        if block {
+            on_item(StyledEventOrRun::Event(StyledEvent::BlockBreak(kind)));
+        }
+    }
+
+    /// Cascade the loaded stylesheets and inline style of one element.
+    fn element_style(&self, ctx: &ElementCtx) -> CssStyle {
+        let class_refs: Vec<&str> = ctx.classes.iter().map(String::as_str).collect();
+        cascade(
+            &self.parsed,
+            &ctx.tag,
+            &class_refs,
+            Some(&ctx.position),
+            ctx.inline_style.as_ref(),
+        )
+    }
+
+    /// Whether the element's subtree is suppressed by `hidden` or
+    /// `display: none`, unless [`StyleConfig::include_hidden`] keeps it.
+    fn is_hidden(&self, ctx: &ElementCtx) -> bool {
+        !self.config.include_hidden
+            && (ctx.hidden || self.element_style(ctx).display == Some(Display::None))
     }
 
-    fn resolve_tag_style(&self, tag: &str, classes: &[String]) -> CssStyle {
-        let class_refs: Vec<&str> = classes.iter().map(String::as_str).collect();
-        let mut style = CssStyle::new();
-        for ss in &self.parsed {
-            style.merge(&ss.resolve(tag, &class_refs));
+    /// Pixel size of `size` given the parent element's size, or `None` when
+    /// it needs a viewport dimension that is not configured.
+    fn resolve_font_size(&self, size: FontSize, parent_px: f32) -> Option<f32> {
+        let hints = &self.config.hints;
+        let viewport_percent =
+            |extent: f32, percent: f32| (extent > 0.0).then_some(extent * percent / 100.0);
+        match size {
+            FontSize::Px(px) => Some(px * hints.css_px_scale),
+            FontSize::Pt(pt) => Some(pt * (96.0 / 72.0) * hints.css_px_scale),
+            FontSize::Em(em) => Some(parent_px * em),
+            FontSize::Rem(rem) => Some(hints.base_font_size_px * rem),
+            FontSize::Percent(percent) => Some(parent_px * percent / 100.0),
+            FontSize::Vw(vw) => viewport_percent(hints.viewport_width_px, vw),
+            FontSize::Vh(vh) => viewport_percent(hints.viewport_height_px, vh),
         }
+    }
+
+    /// Computed text style for the innermost element of `stack`.
+    fn style_for_stack(&self, stack: &[ElementCtx]) -> ComputedTextStyle {
+        let (resolved, size_px, role, bold_tag, italic_tag) = self.resolve_context_style(stack);
+        let mut style = self.compute_style(resolved, size_px, role, bold_tag, italic_tag);
+        style.language = stack
+            .iter()
+            .rev()
+            .find_map(|ctx| ctx.lang.as_deref())
+            .filter(|lang| !lang.is_empty())
+            .map(str::to_string);
         style
     }
 
     fn compute_style(
         &self,
         resolved: CssStyle,
+        size_px: Option<f32>,
         role: BlockRole,
         bold_tag: bool,
         italic_tag: bool,
     ) -> ComputedTextStyle {
-        let mut size_px = match resolved.font_size {
-            Some(FontSize::Px(px)) => px,
-            Some(FontSize::Em(em)) => self.config.hints.base_font_size_px * em,
-            None => {
-                if matches!(role, BlockRole::Heading(1 | 2)) {
-                    self.config.hints.base_font_size_px * 1.25
-                } else {
-                    self.config.hints.base_font_size_px
-                }
-            }
+        let heading = match role {
+            BlockRole::Heading(level) => Some(self.config.type_ramp.level(level)),
+            _ => None,
         };
+        let css_px_scale = self.config.hints.css_px_scale;
+        let mut size_px = size_px.unwrap_or_else(|| {
+            self.config.hints.base_font_size_px * heading.map_or(1.0, |h| h.size_scale)
+        });
         size_px = size_px.clamp(
             self.config.hints.min_font_size_px,
             self.config.hints.max_font_size_px,
         );
 
         let mut line_height = match resolved.line_height {
-            Some(LineHeight::Px(px)) => (px / size_px).max(1.0),
+            Some(LineHeight::Px(px)) => (px * css_px_scale / size_px).max(1.0),
             Some(LineHeight::Multiplier(m)) => m,
             None => 1.4,
         };
@@ -833,9 +1630,10 @@ impl Styler {
             self.config.hints.max_line_height,
         );
 
-        let weight = match resolved.font_weight.unwrap_or(FontWeight::Normal) {
-            FontWeight::Bold => 700,
-            FontWeight::Normal => 400,
+        let weight = match resolved.font_weight {
+            Some(FontWeight::Bold) => 700,
+            Some(FontWeight::Normal) => 400,
+            None => heading.map_or(400, |h| h.weight),
         };
         let italic = matches!(
             resolved.font_style.unwrap_or(FontStyle::Normal),
@@ -858,19 +1656,36 @@ impl Styler {
             line_height,
             letter_spacing: 0.0,
             block_role: role,
+            language: None,
         }
     }
 
-    fn resolve_context_style(&self, stack: &[ElementCtx]) -> (CssStyle, BlockRole, bool, bool) {
+    /// Merge the styles of `stack` and resolve the innermost font size.
+    ///
+    /// Ancestors contribute only inherited properties; box properties come
+    /// from the innermost element alone. Relative sizes (`em`, `%`) compound
+    /// through the ancestors that set a size; the result is `None` when no
+    /// element sets one.
+    fn resolve_context_style(
+        &self,
+        stack: &[ElementCtx],
+    ) -> (CssStyle, Option<f32>, BlockRole, bool, bool) {
         let mut merged = CssStyle::new();
+        let mut size_px = None;
         let mut role = BlockRole::Body;
         let mut bold_tag = false;
         let mut italic_tag = false;
 
-        for ctx in stack {
-            merged.merge(&self.resolve_tag_style(&ctx.tag, &ctx.classes));
-            if let Some(inline) = &ctx.inline_style {
-                merged.merge(inline);
+        for (depth, ctx) in stack.iter().enumerate() {
+            let own = self.element_style(ctx);
+            if let Some(size) = own.font_size {
+                let parent_px = size_px.unwrap_or(self.config.hints.base_font_size_px);
+                size_px = self.resolve_font_size(size, parent_px).or(size_px);
+            }
+            if depth + 1 == stack.len() {
+                merged.merge(&own);
+            } else {
+                merged.merge(&own.inherited());
             }
             if matches!(ctx.tag.as_str(), "strong" | "b") {
                 bold_tag = true;
@@ -881,7 +1696,7 @@ impl Styler {
             role = role_from_tag(&ctx.tag).unwrap_or(role);
         }
 
-        (merged, role, bold_tag, italic_tag)
+        (merged, size_px, role, bold_tag, italic_tag)
     }
 }
 
@@ -898,6 +1713,16 @@ pub struct FontPolicy {
     pub synthetic_bold: bool,
     /// Whether synthetic italic is allowed.
     pub synthetic_italic: bool,
+    /// Per-language family preferences, keyed by BCP 47 language range.
+    ///
+    /// Consulted after the CSS family stack finds no embedded face; the
+    /// first family also replaces `default_family` for runs in that
+    /// language.
+    pub language_families: Vec<(String, Vec<String>)>,
+    /// Map a requested family with no embedded face to the registered
+    /// family whose name is most similar (e.g. `"Literata Book"` to
+    /// `"Literata"`), before falling back to the policy families.
+    pub match_similar_families: bool,
 }
 
 impl FontPolicy {
@@ -909,8 +1734,47 @@ impl FontPolicy {
             allow_embedded_fonts: true,
             synthetic_bold: false,
             synthetic_italic: false,
+            language_families: Vec::with_capacity(0),
+            match_similar_families: false,
         }
     }
+
+    /// Prefer `families` for runs whose language falls under `language`
+    /// (e.g. `"el"` also covers `"el-GR"`).
+    pub fn with_language_families<I, S>(mut self, language: &str, families: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let families = families.into_iter().map(Into::into).collect();
+        let language = language.trim();
+        self.language_families
+            .retain(|(existing, _)| !existing.eq_ignore_ascii_case(language));
+        self.language_families
+            .push((language.to_string(), families));
+        self
+    }
+
+    /// Preferred families for `language`, from the most specific matching
+    /// entry in [`Self::language_families`].
+    pub fn families_for_language(&self, language: &str) -> Option<&[String]> {
+        self.language_families
+            .iter()
+            .filter(|(range, _)| language_range_matches(range, language))
+            .max_by_key(|(range, _)| range.len())
+            .map(|(_, families)| families.as_slice())
+    }
+}
+
+/// Whether BCP 47 `range` covers `tag`, matching whole subtags without
+/// regard to case.
+fn language_range_matches(range: &str, tag: &str) -> bool {
+    let (range, tag) = (range.trim(), tag.trim());
+    if range.is_empty() || range.len() > tag.len() {
+        return false;
+    }
+    tag.as_bytes()[..range.len()].eq_ignore_ascii_case(range.as_bytes())
+        && matches!(tag.as_bytes().get(range.len()), None | Some(b'-'))
 }
 
 /// First-class public fallback policy alias.
@@ -931,15 +1795,84 @@ pub struct ResolvedFontFace {
     pub family: String,
     /// Selected face metadata when matched in EPUB.
     pub embedded: Option<EmbeddedFontFace>,
+    /// Styling the face lacks that the renderer should synthesize.
+    pub synthesis: FontSynthesis,
 }
 
-/// Trace output for fallback reasoning.
-#[derive(Clone, Debug, PartialEq, Eq)]
+/// Bold or italic styling a renderer must fake because the resolved face
+/// does not provide it.
+///
+/// Only set when [`FontPolicy::synthetic_bold`] or
+/// [`FontPolicy::synthetic_italic`] allows it.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct FontSynthesis {
+    /// Embolden glyphs (e.g. by double-striking).
+    pub bold: bool,
+    /// Slant glyphs (e.g. by shearing).
+    pub italic: bool,
+}
+
+impl FontSynthesis {
+    /// Whether nothing needs to be synthesized.
+    pub fn is_none(&self) -> bool {
+        !self.bold && !self.italic
+    }
+}
+
+/// Trace output for fallback reasoning.
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct FontResolutionTrace {
     /// Final selected face.
     pub face: ResolvedFontFace,
     /// Resolution reasoning chain.
     pub reason_chain: Vec<String>,
+    /// Characters of the run that no consulted embedded face has glyphs
+    /// for, in first-seen order.
+    pub missing_glyphs: Vec<char>,
+    /// Faces of the requested families that registration skipped, which
+    /// explain why an `@font-face` family fell back.
+    pub skipped_faces: Vec<FontRegistration>,
+}
+
+/// Outcome of registering one face with [`FontResolver::register_epub_fonts`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FontRegistration {
+    /// The face as declared.
+    pub face: EmbeddedFontFace,
+    /// Whether the face can be resolved.
+    pub status: FontRegistrationStatus,
+}
+
+/// Whether a face was registered.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum FontRegistrationStatus {
+    /// Registered under this resolver identity.
+    Accepted {
+        /// Value of [`ResolvedFontFace::font_id`] when the face is chosen.
+        font_id: u32,
+    },
+    /// Not registered; runs asking for it fall back.
+    Skipped(FontSkipReason),
+}
+
+/// Why [`FontResolver::register_epub_fonts`] skipped a face.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum FontSkipReason {
+    /// Same family, weight, style, and href as an earlier face.
+    Duplicate,
+    /// A source format renderers cannot use, such as SVG fonts or EOT
+    /// (`embedded-opentype`).
+    UnsupportedFormat(String),
+}
+
+impl fmt::Display for FontSkipReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Duplicate => write!(f, "duplicate face"),
+            Self::UnsupportedFormat(format) => write!(f, "unsupported format '{}'", format),
+        }
+    }
 }
 
 /// Font resolution engine.
@@ -947,7 +1880,12 @@ pub struct FontResolutionTrace {
 pub struct FontResolver {
     policy: FontPolicy,
     limits: FontLimits,
+    obfuscation: FontObfuscationMap,
     faces: Vec<EmbeddedFontFace>,
+    /// `cmap` coverage per entry of `faces`; `None` when unknown.
+    coverage: Vec<Option<GlyphCoverage>>,
+    /// Faces the last registration skipped for their format.
+    unsupported: Vec<FontRegistration>,
 }
 
 impl FontResolver {
@@ -956,7 +1894,10 @@ impl FontResolver {
         Self {
             policy,
             limits: FontLimits::default(),
+            obfuscation: FontObfuscationMap::default(),
             faces: Vec::with_capacity(0),
+            coverage: Vec::with_capacity(0),
+            unsupported: Vec::with_capacity(0),
         }
     }
 
@@ -966,17 +1907,32 @@ impl FontResolver {
         self
     }
 
+    /// De-obfuscate font bytes listed in `obfuscation` as they are loaded.
+    pub fn with_font_obfuscation(mut self, obfuscation: FontObfuscationMap) -> Self {
+        self.obfuscation = obfuscation;
+        self
+    }
+
     /// Register EPUB fonts and validate byte limits via callback.
+    ///
+    /// Loaded bytes of obfuscated fonts (see [`Self::with_font_obfuscation`])
+    /// are de-obfuscated before any checks. Each face's `cmap` is read so
+    /// runs can skip faces that lack their glyphs. SVG and EOT sources are
+    /// skipped rather than registered; the returned list has one entry per
+    /// input face, in order.
     pub fn register_epub_fonts<I, F>(
         &mut self,
         fonts: I,
         mut loader: F,
-    ) -> Result<(), RenderPrepError>
+    ) -> Result<Vec<FontRegistration>, RenderPrepError>
     where
         I: IntoIterator<Item = EmbeddedFontFace>,
         F: FnMut(&str) -> Result<Vec<u8>, EpubError>,
     {
         self.faces.clear();
+        self.coverage.clear();
+        self.unsupported.clear();
+        let mut registrations = Vec::with_capacity(0);
         let mut total = 0usize;
         let mut dedupe_keys: Vec<(String, u16, EmbeddedFontStyle, String)> = Vec::with_capacity(0);
 
@@ -989,6 +1945,21 @@ impl FontResolver {
                 face.href.to_ascii_lowercase(),
             );
             if dedupe_keys.contains(&dedupe_key) {
+                registrations.push(FontRegistration {
+                    face,
+                    status: FontRegistrationStatus::Skipped(FontSkipReason::Duplicate),
+                });
+                continue;
+            }
+            if let Some(format) = unsupported_font_format(&face) {
+                let registration = FontRegistration {
+                    face,
+                    status: FontRegistrationStatus::Skipped(FontSkipReason::UnsupportedFormat(
+                        format.to_string(),
+                    )),
+                };
+                self.unsupported.push(registration.clone());
+                registrations.push(registration);
                 continue;
             }
             if self.faces.len() >= self.limits.max_faces {
@@ -1003,10 +1974,11 @@ impl FontResolver {
                     self.limits.max_faces,
                 ));
             }
-            let bytes = loader(&face.href).map_err(|e| {
+            let mut bytes = loader(&face.href).map_err(|e| {
                 RenderPrepError::new_with_phase(ErrorPhase::Style, "FONT_LOAD_ERROR", e.to_string())
                     .with_path(face.href.clone())
             })?;
+            self.obfuscation.deobfuscate(&face.href, &mut bytes);
             if bytes.len() > self.limits.max_bytes_per_font {
                 let err = RenderPrepError::new_with_phase(
                     ErrorPhase::Style,
@@ -1042,10 +2014,17 @@ impl FontResolver {
                 ));
             }
             dedupe_keys.push(dedupe_key);
-            self.faces.push(face);
+            self.coverage.push(GlyphCoverage::parse(&bytes));
+            self.faces.push(face.clone());
+            registrations.push(FontRegistration {
+                face,
+                status: FontRegistrationStatus::Accepted {
+                    font_id: self.faces.len() as u32,
+                },
+            });
         }
 
-        Ok(())
+        Ok(registrations)
     }
 
     /// Resolve a style request to a concrete face.
@@ -1059,79 +2038,264 @@ impl FontResolver {
     }
 
     /// Resolve with full fallback reasoning and optional text context.
+    ///
+    /// With `text`, an embedded face whose `cmap` lacks any of its
+    /// characters is passed over for the next candidate, so subsetted fonts
+    /// fall back per run instead of drawing missing glyphs.
     pub fn resolve_with_trace_for_text(
         &self,
         style: &ComputedTextStyle,
         text: Option<&str>,
     ) -> FontResolutionTrace {
         let mut reasons = Vec::with_capacity(0);
+        let mut missing: Option<Vec<char>> = None;
+        let mut skipped_faces = Vec::with_capacity(0);
         for family in &style.family_stack {
             if !self.policy.allow_embedded_fonts {
                 reasons.push("embedded fonts disabled by policy".to_string());
                 break;
             }
+            if let Some(face) = self.match_embedded(family, style) {
+                let uncovered = self.uncovered_chars(&face, text);
+                if uncovered.is_empty() {
+                    reasons.push(format!(
+                        "matched embedded family '{}' via nearest weight/style",
+                        family
+                    ));
+                    Self::push_synthesis_reasons(&face, &mut reasons);
+                    return FontResolutionTrace {
+                        face,
+                        reason_chain: reasons,
+                        missing_glyphs: Vec::with_capacity(0),
+                        skipped_faces,
+                    };
+                }
+                reasons.push(format!(
+                    "family '{}' lacks glyphs for {}",
+                    family,
+                    format_codepoints(&uncovered)
+                ));
+                narrow_missing(&mut missing, uncovered);
+                continue;
+            }
             let requested = normalize_family(family);
-            let mut candidates: Vec<(usize, EmbeddedFontFace)> = self
-                .faces
+            let skipped: Vec<&FontRegistration> = self
+                .unsupported
                 .iter()
-                .enumerate()
-                .filter(|(_, face)| normalize_family(&face.family) == requested)
-                .map(|(idx, face)| (idx, face.clone()))
+                .filter(|registration| normalize_family(&registration.face.family) == requested)
                 .collect();
-            if !candidates.is_empty() {
-                candidates.sort_by_key(|(_, face)| {
-                    let weight_delta = (face.weight as i32 - style.weight as i32).unsigned_abs();
-                    let style_penalty = if style.italic {
-                        if matches!(
-                            face.style,
-                            EmbeddedFontStyle::Italic | EmbeddedFontStyle::Oblique
-                        ) {
-                            0
-                        } else {
-                            1000
-                        }
-                    } else if matches!(face.style, EmbeddedFontStyle::Normal) {
-                        0
-                    } else {
-                        1000
-                    };
-                    weight_delta + style_penalty
-                });
-                let (chosen_idx, chosen) = candidates[0].clone();
+            if let Some(first) = skipped.first() {
+                if let FontRegistrationStatus::Skipped(reason) = &first.status {
+                    reasons.push(format!(
+                        "family '{}' skipped at registration: {}",
+                        family, reason
+                    ));
+                }
+                skipped_faces.extend(skipped.into_iter().cloned());
+                continue;
+            }
+            reasons.push(format!("family '{}' unavailable in embedded set", family));
+        }
+
+        let language_families = style
+            .language
+            .as_deref()
+            .and_then(|lang| Some((lang, self.policy.families_for_language(lang)?)));
+        if let Some((lang, families)) = language_families {
+            for family in families {
+                if !self.policy.allow_embedded_fonts {
+                    break;
+                }
+                if let Some(face) = self.match_embedded(family, style) {
+                    let uncovered = self.uncovered_chars(&face, text);
+                    if uncovered.is_empty() {
+                        reasons.push(format!(
+                            "language '{}' prefers embedded family '{}'",
+                            lang, family
+                        ));
+                        Self::push_synthesis_reasons(&face, &mut reasons);
+                        return FontResolutionTrace {
+                            face,
+                            reason_chain: reasons,
+                            missing_glyphs: Vec::with_capacity(0),
+                            skipped_faces,
+                        };
+                    }
+                    reasons.push(format!(
+                        "language '{}' family '{}' lacks glyphs for {}",
+                        lang,
+                        family,
+                        format_codepoints(&uncovered)
+                    ));
+                    narrow_missing(&mut missing, uncovered);
+                    continue;
+                }
+                reasons.push(format!(
+                    "language '{}' family '{}' unavailable in embedded set",
+                    lang, family
+                ));
+            }
+        }
+
+        if self.policy.match_similar_families && self.policy.allow_embedded_fonts {
+            for family in &style.family_stack {
+                let Some(similar) = self.similar_family(family) else {
+                    continue;
+                };
+                let Some(face) = self.match_embedded(similar, style) else {
+                    continue;
+                };
+                if !self.uncovered_chars(&face, text).is_empty() {
+                    continue;
+                }
                 reasons.push(format!(
-                    "matched embedded family '{}' via nearest weight/style",
-                    family
+                    "family '{}' mapped to similar embedded family '{}'",
+                    family, similar
                 ));
+                Self::push_synthesis_reasons(&face, &mut reasons);
                 return FontResolutionTrace {
-                    face: ResolvedFontFace {
-                        font_id: chosen_idx as u32 + 1,
-                        family: chosen.family.clone(),
-                        embedded: Some(chosen),
-                    },
+                    face,
                     reason_chain: reasons,
+                    missing_glyphs: Vec::with_capacity(0),
+                    skipped_faces,
                 };
             }
-            reasons.push(format!("family '{}' unavailable in embedded set", family));
         }
 
         for family in &self.policy.preferred_families {
             reasons.push(format!("preferred fallback family candidate '{}'", family));
         }
-        reasons.push(format!(
-            "fallback to policy default '{}'",
-            self.policy.default_family
-        ));
-        if text.is_some_and(has_non_ascii) {
-            reasons
-                .push("missing glyph risk: non-ASCII text with no embedded face match".to_string());
+        let fallback_family = match language_families {
+            Some((lang, families)) if !families.is_empty() => {
+                reasons.push(format!(
+                    "fallback to language '{}' family '{}'",
+                    lang, families[0]
+                ));
+                families[0].clone()
+            }
+            _ => {
+                reasons.push(format!(
+                    "fallback to policy default '{}'",
+                    self.policy.default_family
+                ));
+                self.policy.default_family.clone()
+            }
+        };
+        match &missing {
+            Some(missing) if !missing.is_empty() => reasons.push(format!(
+                "missing glyphs: no embedded face covers {}",
+                format_codepoints(missing)
+            )),
+            Some(_) => {}
+            None if text.is_some_and(has_non_ascii) => reasons
+                .push("missing glyph risk: non-ASCII text with no embedded face match".to_string()),
+            None => {}
         }
         FontResolutionTrace {
             face: ResolvedFontFace {
                 font_id: 0,
-                family: self.policy.default_family.clone(),
+                family: fallback_family,
                 embedded: None,
+                synthesis: FontSynthesis::default(),
             },
             reason_chain: reasons,
+            missing_glyphs: missing.unwrap_or_default(),
+            skipped_faces,
+        }
+    }
+
+    /// Registered family whose name is closest to `family`, if close enough
+    /// to stand in for it. Generic families never match.
+    fn similar_family(&self, family: &str) -> Option<&str> {
+        let requested = normalize_family(family);
+        if is_generic_family(&requested) {
+            return None;
+        }
+        let mut best: Option<(u32, &str)> = None;
+        for face in &self.faces {
+            let score = family_similarity(&requested, &normalize_family(&face.family));
+            if score >= SIMILAR_FAMILY_MIN_SCORE && best.is_none_or(|(top, _)| score > top) {
+                best = Some((score, face.family.as_str()));
+            }
+        }
+        best.map(|(_, family)| family)
+    }
+
+    /// Distinct visible characters of `text` the embedded `face` has no
+    /// glyph for; empty when its coverage is unknown.
+    fn uncovered_chars(&self, face: &ResolvedFontFace, text: Option<&str>) -> Vec<char> {
+        let coverage = (face.font_id as usize)
+            .checked_sub(1)
+            .and_then(|idx| self.coverage.get(idx))
+            .and_then(Option::as_ref);
+        let (Some(coverage), Some(text)) = (coverage, text) else {
+            return Vec::with_capacity(0);
+        };
+        let mut uncovered = Vec::with_capacity(0);
+        for ch in text.chars() {
+            if !ch.is_whitespace()
+                && !ch.is_control()
+                && !is_format_char(ch)
+                && !coverage.contains(ch)
+                && !uncovered.contains(&ch)
+            {
+                uncovered.push(ch);
+            }
+        }
+        uncovered
+    }
+
+    /// Embedded face of `family` nearest to the requested weight and style.
+    fn match_embedded(&self, family: &str, style: &ComputedTextStyle) -> Option<ResolvedFontFace> {
+        let requested = normalize_family(family);
+        let (chosen_idx, chosen) = self
+            .faces
+            .iter()
+            .enumerate()
+            .filter(|(_, face)| normalize_family(&face.family) == requested)
+            .min_by_key(|(_, face)| {
+                let weight_delta = (face.weight as i32 - style.weight as i32).unsigned_abs();
+                let style_penalty = if style.italic {
+                    if matches!(
+                        face.style,
+                        EmbeddedFontStyle::Italic | EmbeddedFontStyle::Oblique
+                    ) {
+                        0
+                    } else {
+                        1000
+                    }
+                } else if matches!(face.style, EmbeddedFontStyle::Normal) {
+                    0
+                } else {
+                    1000
+                };
+                weight_delta + style_penalty
+            })?;
+        let synthesis = FontSynthesis {
+            bold: self.policy.synthetic_bold && style.weight >= 600 && chosen.weight < 600,
+            italic: self.policy.synthetic_italic
+                && style.italic
+                && matches!(chosen.style, EmbeddedFontStyle::Normal),
+        };
+        Some(ResolvedFontFace {
+            font_id: chosen_idx as u32 + 1,
+            family: chosen.family.clone(),
+            embedded: Some(chosen.clone()),
+            synthesis,
+        })
+    }
+
+    /// Trace entries for styling `face` leaves to the renderer.
+    fn push_synthesis_reasons(face: &ResolvedFontFace, reasons: &mut Vec<String>) {
+        if face.synthesis.bold {
+            let weight = face.embedded.as_ref().map_or(400, |face| face.weight);
+            reasons.push(format!(
+                "synthesizing bold: nearest embedded weight is {}",
+                weight
+            ));
+        }
+        if face.synthesis.italic {
+            reasons.push("synthesizing italic: no italic embedded face".to_string());
         }
     }
 }
@@ -1179,7 +2343,10 @@ impl RenderPrepTrace {
 impl RenderPrep {
     /// Create a render-prep engine.
     pub fn new(opts: RenderPrepOptions) -> Self {
-        let styler = Styler::new(opts.style).with_memory_budget(opts.memory);
+        let styler = Styler::new(opts.style)
+            .with_memory_budget(opts.memory)
+            .with_smart_punctuation(opts.smart_punctuation)
+            .with_footnote_numbering(opts.footnotes);
         let font_resolver = FontResolver::new(FontPolicy::default()).with_limits(opts.fonts);
         Self {
             opts,
@@ -1195,9 +2362,17 @@ impl RenderPrep {
         self
     }
 
+    /// Emit link start/end events in prepared chapters.
+    ///
+    /// See [`Styler::with_link_events`].
+    pub fn with_link_events(mut self, enabled: bool) -> Self {
+        self.styler = self.styler.with_link_events(enabled);
+        self
+    }
+
     /// Register all embedded fonts from a book.
-    pub fn with_embedded_fonts_from_book<R: std::io::Read + std::io::Seek>(
-        self,
+    pub fn with_embedded_fonts_from_book<R: crate::RandomAccess>(
+        mut self,
         book: &mut EpubBook<R>,
     ) -> Result<Self, RenderPrepError> {
         let fonts = book
@@ -1209,15 +2384,23 @@ impl RenderPrep {
                     e.to_string(),
                 )
             })?;
+        let obfuscation = book.font_obfuscation().map_err(|e| {
+            RenderPrepError::new_with_phase(
+                ErrorPhase::Parse,
+                "BOOK_FONT_OBFUSCATION",
+                e.to_string(),
+            )
+        })?;
+        self.font_resolver = self.font_resolver.with_font_obfuscation(obfuscation);
         self.with_registered_fonts(fonts, |href| book.read_resource(href))
     }
 
-    fn load_chapter_html_with_budget<R: std::io::Read + std::io::Seek>(
+    fn load_chapter_html_with_budget<S: ChapterSource>(
         &self,
-        book: &mut EpubBook<R>,
+        book: &mut S,
         index: usize,
     ) -> Result<(String, Vec<u8>), RenderPrepError> {
-        let chapter = book.chapter(index).map_err(|e| {
+        let chapter = book.chapter_ref(index).map_err(|e| {
             RenderPrepError::new_with_phase(ErrorPhase::Parse, "BOOK_CHAPTER_REF", e.to_string())
                 .with_chapter_index(index)
         })?;
@@ -1227,30 +2410,35 @@ impl RenderPrep {
                 .with_path(href.clone())
                 .with_chapter_index(index)
         })?;
-        if bytes.len() > self.opts.memory.max_entry_bytes {
+        self.check_entry_budget(index, &href, bytes.len())?;
+        Ok((href, bytes))
+    }
+
+    fn check_entry_budget(
+        &self,
+        index: usize,
+        href: &str,
+        len: usize,
+    ) -> Result<(), RenderPrepError> {
+        if len > self.opts.memory.max_entry_bytes {
             return Err(RenderPrepError::new_with_phase(
                 ErrorPhase::Parse,
                 "ENTRY_BYTES_LIMIT",
                 format!(
                     "Chapter entry exceeds max_entry_bytes ({} > {})",
-                    bytes.len(),
-                    self.opts.memory.max_entry_bytes
+                    len, self.opts.memory.max_entry_bytes
                 ),
             )
-            .with_path(href.clone())
+            .with_path(href.to_string())
             .with_chapter_index(index)
-            .with_limit(
-                "max_entry_bytes",
-                bytes.len(),
-                self.opts.memory.max_entry_bytes,
-            ));
+            .with_limit("max_entry_bytes", len, self.opts.memory.max_entry_bytes));
         }
-        Ok((href, bytes))
+        Ok(())
     }
 
-    fn apply_chapter_stylesheets_with_budget<R: std::io::Read + std::io::Seek>(
+    fn apply_chapter_stylesheets_with_budget<S: ChapterSource>(
         &mut self,
-        book: &mut EpubBook<R>,
+        book: &mut S,
         chapter_index: usize,
         chapter_href: &str,
         html: &[u8],
@@ -1317,20 +2505,21 @@ impl RenderPrep {
     }
 
     /// Prepare a chapter into styled runs/events.
-    pub fn prepare_chapter<R: std::io::Read + std::io::Seek>(
+    pub fn prepare_chapter<R: crate::RandomAccess>(
         &mut self,
         book: &mut EpubBook<R>,
         index: usize,
     ) -> Result<PreparedChapter, RenderPrepError> {
-        let mut items = Vec::with_capacity(0);
-        self.prepare_chapter_with(book, index, |item| items.push(item))?;
-        Ok(PreparedChapter {
-            styled: StyledChapter::from_items(items),
-        })
+        let (styled, missing_glyphs) = self.prepare_chapter_items(book, index)?;
+        let nav_label = book.chapter(index).ok().and_then(|chapter| chapter.title);
+        Ok(
+            PreparedChapter::from_styled(styled, nav_label, book.title())
+                .with_missing_glyphs(missing_glyphs),
+        )
     }
 
     /// Prepare a chapter and append results into an output buffer.
-    pub fn prepare_chapter_into<R: std::io::Read + std::io::Seek>(
+    pub fn prepare_chapter_into<R: crate::RandomAccess>(
         &mut self,
         book: &mut EpubBook<R>,
         index: usize,
@@ -1340,18 +2529,94 @@ impl RenderPrep {
     }
 
     /// Prepare a chapter and stream each styled item via callback.
-    pub fn prepare_chapter_with<R: std::io::Read + std::io::Seek, F: FnMut(StyledEventOrRun)>(
+    pub fn prepare_chapter_with<R: crate::RandomAccess, F: FnMut(StyledEventOrRun)>(
         &mut self,
         book: &mut EpubBook<R>,
         index: usize,
         mut on_item: F,
     ) -> Result<(), RenderPrepError> {
-        let (chapter_href, html) = self.load_chapter_html_with_budget(book, index)?;
-        self.apply_chapter_stylesheets_with_budget(book, index, &chapter_href, &html)?;
+        self.prepare_chapter_from_source(book, index, |item, _| on_item(item))
+    }
+
+    /// Prepare a chapter from [`EpubBook::split_chapters`] and stream each
+    /// styled item; a segment styles only its own content.
+    pub fn prepare_chapter_ref_with<R: crate::RandomAccess, F: FnMut(StyledEventOrRun)>(
+        &mut self,
+        book: &mut EpubBook<R>,
+        chapter: &ChapterRef,
+        on_item: F,
+    ) -> Result<(), RenderPrepError> {
+        if chapter.segment().is_none() {
+            return self.prepare_chapter_with(book, chapter.index, on_item);
+        }
+        let html = book.chapter_segment_html(chapter).map_err(|e| {
+            RenderPrepError::new_with_phase(ErrorPhase::Parse, "BOOK_CHAPTER_HTML", e.to_string())
+                .with_path(chapter.href.clone())
+                .with_chapter_index(chapter.index)
+        })?;
+        self.prepare_chapter_bytes_with(book, chapter.index, &html, on_item)
+    }
+
+    /// Prepare a chapter read through any [`ChapterSource`].
+    pub(crate) fn prepare_chapter_from_source<
+        S: ChapterSource,
+        F: FnMut(StyledEventOrRun, RenderPrepTrace),
+    >(
+        &mut self,
+        source: &mut S,
+        index: usize,
+        mut on_item: F,
+    ) -> Result<(), RenderPrepError> {
+        let (chapter_href, html) = self.load_chapter_html_with_budget(source, index)?;
+        self.apply_chapter_stylesheets_with_budget(source, index, &chapter_href, &html)?;
         let font_resolver = &self.font_resolver;
         self.styler.style_chapter_bytes_with(&html, |item| {
-            let (item, _) = resolve_item_with_font(font_resolver, item);
-            on_item(item);
+            let (item, trace) = resolve_item_with_font(font_resolver, item);
+            on_item(item, trace);
+        })
+    }
+
+    /// Collect a chapter through any [`ChapterSource`], with its
+    /// missing-glyph report.
+    pub(crate) fn prepare_chapter_items<S: ChapterSource>(
+        &mut self,
+        source: &mut S,
+        index: usize,
+    ) -> Result<(StyledChapter, MissingGlyphReport), RenderPrepError> {
+        let mut items = Vec::with_capacity(0);
+        let mut missing_glyphs = MissingGlyphReport::default();
+        self.prepare_chapter_from_source(source, index, |item, trace| {
+            if let Some(font) = trace.font_trace() {
+                missing_glyphs.record(font);
+            }
+            items.push(item);
+        })?;
+        Ok((StyledChapter::from_items(items), missing_glyphs))
+    }
+
+    /// Prepare a chapter as a pull-based [`ChapterEventIter`] instead of
+    /// streaming it to a callback.
+    ///
+    /// The iterator takes ownership of this prep's styler and fonts.
+    pub fn into_chapter_events<R: crate::RandomAccess>(
+        mut self,
+        book: &mut EpubBook<R>,
+        index: usize,
+    ) -> Result<ChapterEventIter, RenderPrepError> {
+        let (chapter_href, html) = self.load_chapter_html_with_budget(book, index)?;
+        self.apply_chapter_stylesheets_with_budget(book, index, &chapter_href, &html)?;
+        let last_child = self.styler.last_child_flags(&html);
+        let state = self
+            .styler
+            .style_state(std::io::Cursor::new(html), last_child);
+        Ok(ChapterEventIter {
+            prep: self,
+            state,
+            pending: VecDeque::with_capacity(4),
+            error: None,
+            max_items: usize::MAX,
+            emitted: 0,
+            done: false,
         })
     }
 
@@ -1359,10 +2624,7 @@ impl RenderPrep {
     ///
     /// This avoids re-reading chapter bytes from the ZIP archive and is intended for
     /// embedded call sites that already own a reusable chapter buffer.
-    pub fn prepare_chapter_bytes_with<
-        R: std::io::Read + std::io::Seek,
-        F: FnMut(StyledEventOrRun),
-    >(
+    pub fn prepare_chapter_bytes_with<R: crate::RandomAccess, F: FnMut(StyledEventOrRun)>(
         &mut self,
         book: &mut EpubBook<R>,
         index: usize,
@@ -1374,24 +2636,7 @@ impl RenderPrep {
                 .with_chapter_index(index)
         })?;
         let chapter_href = chapter.href;
-        if html.len() > self.opts.memory.max_entry_bytes {
-            return Err(RenderPrepError::new_with_phase(
-                ErrorPhase::Parse,
-                "ENTRY_BYTES_LIMIT",
-                format!(
-                    "Chapter entry exceeds max_entry_bytes ({} > {})",
-                    html.len(),
-                    self.opts.memory.max_entry_bytes
-                ),
-            )
-            .with_path(chapter_href.clone())
-            .with_chapter_index(index)
-            .with_limit(
-                "max_entry_bytes",
-                html.len(),
-                self.opts.memory.max_entry_bytes,
-            ));
-        }
+        self.check_entry_budget(index, &chapter_href, html.len())?;
         self.apply_chapter_stylesheets_with_budget(book, index, &chapter_href, html)?;
         let font_resolver = &self.font_resolver;
         self.styler.style_chapter_bytes_with(html, |item| {
@@ -1400,9 +2645,33 @@ impl RenderPrep {
         })
     }
 
+    /// Prepare a chapter from caller-loaded XHTML bytes and stylesheet sources.
+    ///
+    /// Performs no archive I/O, so callers that fetch chapter resources
+    /// themselves (for example through `AsyncEpubBook`) share the same
+    /// styling and budget checks as [`Self::prepare_chapter_with`].
+    pub fn prepare_chapter_source_with<F: FnMut(StyledEventOrRun)>(
+        &mut self,
+        index: usize,
+        chapter_href: &str,
+        html: &[u8],
+        stylesheets: &ChapterStylesheets,
+        mut on_item: F,
+    ) -> Result<(), RenderPrepError> {
+        self.check_entry_budget(index, chapter_href, html.len())?;
+        self.styler
+            .load_stylesheets(stylesheets)
+            .map_err(|e| e.with_chapter_index(index))?;
+        let font_resolver = &self.font_resolver;
+        self.styler.style_chapter_bytes_with(html, |item| {
+            let (item, _) = resolve_item_with_font(font_resolver, item);
+            on_item(item);
+        })
+    }
+
     /// Prepare a chapter and stream each styled item with structured trace context.
     pub fn prepare_chapter_with_trace_context<
-        R: std::io::Read + std::io::Seek,
+        R: crate::RandomAccess,
         F: FnMut(StyledEventOrRun, RenderPrepTrace),
     >(
         &mut self,
@@ -1425,7 +2694,7 @@ impl RenderPrep {
         note = "Use prepare_chapter_with_trace_context for stable structured trace output."
     )]
     pub fn prepare_chapter_with_trace<
-        R: std::io::Read + std::io::Seek,
+        R: crate::RandomAccess,
         F: FnMut(StyledEventOrRun, Option<FontResolutionTrace>),
     >(
         &mut self,
@@ -1439,13 +2708,132 @@ impl RenderPrep {
     }
 }
 
+/// Pull-based styled items of one chapter, as an alternative to the
+/// callback APIs; see [`EpubBook::chapter_event_iter`].
+///
+/// Each call to `next` parses markup only until an item is ready. Besides
+/// the chapter bytes (bounded by [`MemoryBudget::max_entry_bytes`]), the
+/// iterator holds the items completed by one markup event; a link being
+/// renumbered as a footnote buffers its content until its end tag. After
+/// an error the iterator yields `None`.
+pub struct ChapterEventIter {
+    prep: RenderPrep,
+    state: StyleState<std::io::Cursor<Vec<u8>>>,
+    pending: VecDeque<StyledEventOrRun>,
+    error: Option<EpubError>,
+    max_items: usize,
+    emitted: usize,
+    done: bool,
+}
+
+impl ChapterEventIter {
+    /// Fail with [`EpubError::Parse`] instead of yielding more than
+    /// `max_items` items.
+    pub fn with_max_items(mut self, max_items: usize) -> Self {
+        self.max_items = max_items;
+        self
+    }
+
+    /// Items yielded so far.
+    pub fn items_emitted(&self) -> usize {
+        self.emitted
+    }
+}
+
+impl Iterator for ChapterEventIter {
+    type Item = Result<StyledEventOrRun, EpubError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(item) = self.pending.pop_front() {
+                if self.emitted >= self.max_items {
+                    self.pending.clear();
+                    self.done = true;
+                    return Some(Err(EpubError::Parse(format!(
+                        "Chapter event count exceeded max_items ({})",
+                        self.max_items
+                    ))));
+                }
+                self.emitted += 1;
+                return Some(Ok(item));
+            }
+            if self.done {
+                return self.error.take().map(Err);
+            }
+            let pending = &mut self.pending;
+            let font_resolver = &self.prep.font_resolver;
+            let step = self.prep.styler.style_step(&mut self.state, &mut |item| {
+                pending.push_back(resolve_item_with_font(font_resolver, item).0);
+            });
+            match step {
+                Ok(true) => {}
+                Ok(false) => self.done = true,
+                Err(err) => {
+                    self.error = Some(EpubError::from(err));
+                    self.done = true;
+                }
+            }
+        }
+    }
+}
+
+impl core::iter::FusedIterator for ChapterEventIter {}
+
+/// Chapter lookup and resource reads needed to prepare a chapter.
+///
+/// Implemented by [`EpubBook`] and by shared handles such as
+/// [`SyncEpubBook`](crate::sync_book::SyncEpubBook) so both use one prep path.
+pub(crate) trait ChapterSource {
+    /// Chapter descriptor by spine index.
+    fn chapter_ref(&self, index: usize) -> Result<ChapterRef, EpubError>;
+    /// Read a resource by OPF-relative href.
+    fn read_resource(&mut self, href: &str) -> Result<Vec<u8>, EpubError>;
+}
+
+impl<R: crate::RandomAccess> ChapterSource for EpubBook<R> {
+    fn chapter_ref(&self, index: usize) -> Result<ChapterRef, EpubError> {
+        self.resolve_displayable_chapter(index)
+    }
+
+    fn read_resource(&mut self, href: &str) -> Result<Vec<u8>, EpubError> {
+        EpubBook::read_resource(self, href)
+    }
+}
+
 /// Prepared chapter stream returned by render-prep.
 #[derive(Clone, Debug, PartialEq)]
 pub struct PreparedChapter {
     styled: StyledChapter,
+    title: Option<String>,
+    missing_glyphs: MissingGlyphReport,
 }
 
 impl PreparedChapter {
+    /// Wrap a styled stream, deriving its title with [`ChapterTitleResolver`].
+    pub(crate) fn from_styled(
+        styled: StyledChapter,
+        nav_label: Option<String>,
+        book_title: &str,
+    ) -> Self {
+        let mut resolver = ChapterTitleResolver::new(nav_label, book_title);
+        styled.iter().for_each(|item| resolver.observe(item));
+        Self {
+            title: resolver.title(),
+            styled,
+            missing_glyphs: MissingGlyphReport::default(),
+        }
+    }
+
+    pub(crate) fn with_missing_glyphs(mut self, missing_glyphs: MissingGlyphReport) -> Self {
+        self.missing_glyphs = missing_glyphs;
+        self
+    }
+
+    /// Display title: navigation label, else first `h1`/`h2`, else book title.
+    pub fn title(&self) -> Option<&str> {
+        self.title.as_deref()
+    }
+
     /// Iterate full styled stream.
     pub fn iter(&self) -> impl Iterator<Item = &StyledEventOrRun> {
         self.styled.iter()
@@ -1455,34 +2843,298 @@ impl PreparedChapter {
     pub fn runs(&self) -> impl Iterator<Item = &StyledRun> {
         self.styled.runs()
     }
-}
 
-#[derive(Clone, Debug, Default)]
-struct ElementCtx {
-    tag: String,
-    classes: Vec<String>,
-    inline_style: Option<CssStyle>,
+    /// Characters the book's embedded fonts could not draw in this chapter.
+    pub fn missing_glyphs(&self) -> &MissingGlyphReport {
+        &self.missing_glyphs
+    }
 }
 
-fn reader_token_offset(reader: &Reader<&[u8]>) -> usize {
-    usize::try_from(reader.buffer_position()).unwrap_or(usize::MAX)
+/// Characters of a chapter that no matching embedded font covers.
+///
+/// Built from [`FontResolutionTrace::missing_glyphs`]; runs containing them
+/// were resolved to a fallback face.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MissingGlyphReport {
+    glyphs: Vec<MissingGlyph>,
 }
 
-fn first_non_empty_declaration_index(style_attr: &str) -> Option<usize> {
-    style_attr
-        .split(';')
-        .enumerate()
-        .find(|(_, decl)| !decl.trim().is_empty())
-        .map(|(idx, _)| idx)
+/// One character missing from the embedded fonts.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MissingGlyph {
+    /// The uncovered character.
+    pub ch: char,
+    /// Number of runs that contained it.
+    pub runs: usize,
 }
 
-fn decode_tag_name(reader: &Reader<&[u8]>, raw: &[u8]) -> Result<String, RenderPrepError> {
-    reader
-        .decoder()
-        .decode(raw)
-        .map(|v| v.to_string())
-        .map_err(|err| {
-            RenderPrepError::new_with_phase(
+impl MissingGlyphReport {
+    /// Add the missing glyphs of one resolved run.
+    pub fn record(&mut self, trace: &FontResolutionTrace) {
+        for &ch in &trace.missing_glyphs {
+            match self.glyphs.iter_mut().find(|glyph| glyph.ch == ch) {
+                Some(glyph) => glyph.runs += 1,
+                None => self.glyphs.push(MissingGlyph { ch, runs: 1 }),
+            }
+        }
+    }
+
+    /// Missing characters in first-seen order.
+    pub fn glyphs(&self) -> &[MissingGlyph] {
+        &self.glyphs
+    }
+
+    /// Whether every run was covered.
+    pub fn is_empty(&self) -> bool {
+        self.glyphs.is_empty()
+    }
+}
+
+/// Derives a chapter's display title while its styled stream goes by.
+///
+/// The navigation label wins; otherwise the text of the first `h1`/`h2` is
+/// used, and the book title is the last resort.
+#[derive(Clone, Debug, Default)]
+pub struct ChapterTitleResolver {
+    nav_label: Option<String>,
+    book_title: Option<String>,
+    heading: Option<String>,
+    /// Text of the `h1`/`h2` being read, if inside one.
+    pending: Option<String>,
+}
+
+impl ChapterTitleResolver {
+    /// Start resolving with the chapter's navigation label and book title.
+    pub fn new(nav_label: Option<String>, book_title: &str) -> Self {
+        let non_empty = |s: &str| {
+            let s = s.trim();
+            (!s.is_empty()).then(|| s.to_string())
+        };
+        Self {
+            nav_label: nav_label.as_deref().and_then(non_empty),
+            book_title: non_empty(book_title),
+            heading: None,
+            pending: None,
+        }
+    }
+
+    /// Feed the next item of the chapter stream.
+    pub fn observe(&mut self, item: &StyledEventOrRun) {
+        if self.nav_label.is_some() || self.heading.is_some() {
+            return;
+        }
+        match item {
+            StyledEventOrRun::Event(StyledEvent::HeadingStart(1 | 2)) => {
+                self.pending = Some(String::with_capacity(32));
+            }
+            StyledEventOrRun::Event(StyledEvent::HeadingEnd(_)) => {
+                if let Some(text) = self.pending.take() {
+                    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+                    if !text.is_empty() {
+                        self.heading = Some(text);
+                    }
+                }
+            }
+            StyledEventOrRun::Run(run) => {
+                // Runs are trimmed; layout treats each boundary as a word break.
+                if let Some(text) = self.pending.as_mut() {
+                    text.push(' ');
+                    text.push_str(&run.text);
+                }
+            }
+            StyledEventOrRun::Event(_) => {}
+        }
+    }
+
+    /// Best title seen so far.
+    pub fn title(&self) -> Option<String> {
+        self.nav_label
+            .clone()
+            .or_else(|| self.heading.clone())
+            .or_else(|| self.book_title.clone())
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+struct ElementCtx {
+    tag: String,
+    /// `id` attribute; taken when the anchor event is emitted.
+    id: Option<String>,
+    classes: Vec<String>,
+    inline_style: Option<StyleDeclarations>,
+    /// Presentational `type` attribute on lists.
+    list_type: Option<ListStyleType>,
+    /// `start` on lists or `value` on list items.
+    list_ordinal: Option<i32>,
+    /// `src` and `alt` on images.
+    src: Option<String>,
+    alt: Option<String>,
+    /// Presentational `width`/`height`/`align` attributes.
+    attr_width: Option<Dimension>,
+    attr_height: Option<Dimension>,
+    attr_float: Option<Float>,
+    /// `href` on links.
+    href: Option<String>,
+    /// Marked as a footnote reference via `epub:type` or `role`.
+    noteref: bool,
+    /// Carries the `hidden` attribute.
+    hidden: bool,
+    /// `xml:lang` or `lang`; empty marks the language as unknown.
+    lang: Option<String>,
+    /// Position among element siblings, for structural pseudo-classes.
+    position: ElementPosition,
+}
+
+/// Element-sibling bookkeeping for structural pseudo-classes.
+///
+/// `:first-child` and `:first-of-type` are known when an element starts;
+/// `:last-child` needs lookahead, so it comes from [`last_child_flags`],
+/// indexed by the order elements start in the document.
+struct SiblingTracker {
+    /// Tags of the children seen so far, one entry per open element plus
+    /// the document level.
+    levels: Vec<Vec<String>>,
+    last_child: Vec<bool>,
+    next_element: usize,
+}
+
+impl SiblingTracker {
+    fn new(last_child: Vec<bool>) -> Self {
+        let mut levels = Vec::with_capacity(8);
+        levels.push(Vec::with_capacity(0));
+        Self {
+            levels,
+            last_child,
+            next_element: 0,
+        }
+    }
+
+    /// Count an element start and return its position. Elements inside
+    /// skipped subtrees (`counted == false`) only advance the document
+    /// order.
+    fn enter(&mut self, tag: &str, counted: bool) -> ElementPosition {
+        let index = self.next_element;
+        self.next_element += 1;
+        let Some(seen) = self.levels.last_mut().filter(|_| counted) else {
+            return ElementPosition::default();
+        };
+        let position = ElementPosition {
+            first_child: seen.is_empty(),
+            last_child: self.last_child.get(index).copied().unwrap_or(false),
+            first_of_type: !seen.iter().any(|known| known == tag),
+        };
+        seen.push(tag.to_string());
+        position
+    }
+
+    fn open_children(&mut self) {
+        self.levels.push(Vec::with_capacity(0));
+    }
+
+    fn close_children(&mut self) {
+        if self.levels.len() > 1 {
+            self.levels.pop();
+        }
+    }
+}
+
+/// For each element in document order, whether it is the last element
+/// child of its parent. Parse errors end the scan early.
+fn last_child_flags(html_bytes: &[u8]) -> Vec<bool> {
+    let mut reader = Reader::from_reader(html_bytes);
+    let mut buf = Vec::with_capacity(0);
+    let mut flags = Vec::with_capacity(0);
+    let mut open: Vec<Option<usize>> = Vec::with_capacity(8);
+    open.push(None);
+    loop {
+        match reader.read_event_into(&mut buf) {
+            Ok(Event::Start(_)) => {
+                if let Some(parent) = open.last_mut() {
+                    *parent = Some(flags.len());
+                }
+                flags.push(false);
+                open.push(None);
+            }
+            Ok(Event::Empty(_)) => {
+                if let Some(parent) = open.last_mut() {
+                    *parent = Some(flags.len());
+                }
+                flags.push(false);
+            }
+            Ok(Event::End(_)) if open.len() > 1 => {
+                if let Some(Some(last)) = open.pop() {
+                    flags[last] = true;
+                }
+            }
+            Ok(Event::Eof) | Err(_) => break,
+            _ => {}
+        }
+        buf.clear();
+    }
+    for last in open.into_iter().flatten() {
+        flags[last] = true;
+    }
+    flags
+}
+
+fn reader_token_offset<R>(reader: &Reader<R>) -> usize {
+    usize::try_from(reader.buffer_position()).unwrap_or(usize::MAX)
+}
+
+fn first_non_empty_declaration_index(style_attr: &str) -> Option<usize> {
+    style_attr
+        .split(';')
+        .enumerate()
+        .find(|(_, decl)| !decl.trim().is_empty())
+        .map(|(idx, _)| idx)
+}
+
+/// Streaming-limit error at source offset `offset`.
+fn stream_limit_error(
+    code: &'static str,
+    kind: &'static str,
+    actual: usize,
+    limit: usize,
+    offset: usize,
+) -> RenderPrepError {
+    RenderPrepError::new(
+        code,
+        format!("Chapter exceeds {} ({} > {})", kind, actual, limit),
+    )
+    .with_phase(ErrorPhase::Style)
+    .with_limit(kind, actual, limit)
+    .with_token_offset(offset)
+}
+
+fn check_attribute_limit(
+    e: &quick_xml::events::BytesStart<'_>,
+    limit: usize,
+    offset: usize,
+) -> Result<(), RenderPrepError> {
+    let count = e
+        .attributes()
+        .with_checks(false)
+        .take(limit.saturating_add(1))
+        .count();
+    if count > limit {
+        return Err(stream_limit_error(
+            "STYLE_ATTRIBUTE_LIMIT",
+            "max_attributes",
+            count,
+            limit,
+            offset,
+        ));
+    }
+    Ok(())
+}
+
+fn decode_tag_name<R>(reader: &Reader<R>, raw: &[u8]) -> Result<String, RenderPrepError> {
+    reader
+        .decoder()
+        .decode(raw)
+        .map(|v| v.to_string())
+        .map_err(|err| {
+            RenderPrepError::new_with_phase(
                 ErrorPhase::Style,
                 "STYLE_TOKENIZE_ERROR",
                 format!("Decode error: {:?}", err),
@@ -1498,14 +3150,27 @@ fn decode_tag_name(reader: &Reader<&[u8]>, raw: &[u8]) -> Result<String, RenderP
         })
 }
 
-fn element_ctx_from_start(
-    reader: &Reader<&[u8]>,
+fn element_ctx_from_start<R>(
+    reader: &Reader<R>,
     e: &quick_xml::events::BytesStart<'_>,
     max_inline_style_bytes: usize,
+    properties: &CustomProperties,
 ) -> Result<ElementCtx, RenderPrepError> {
     let tag = decode_tag_name(reader, e.name().as_ref())?;
+    let mut id = None;
     let mut classes = Vec::with_capacity(0);
     let mut inline_style = None;
+    let mut list_type = None;
+    let mut list_ordinal = None;
+    let mut src = None;
+    let mut alt = None;
+    let mut attr_width = None;
+    let mut attr_height = None;
+    let mut attr_float = None;
+    let mut href = None;
+    let mut noteref = false;
+    let mut hidden = false;
+    let mut lang = None;
     for attr in e.attributes().flatten() {
         let key = match reader.decoder().decode(attr.key.as_ref()) {
             Ok(v) => v.to_ascii_lowercase(),
@@ -1515,7 +3180,37 @@ fn element_ctx_from_start(
             Ok(v) => v.to_string(),
             Err(_) => continue,
         };
-        if key == "class" {
+        if key == "id" {
+            if !val.is_empty() {
+                id = Some(val);
+            }
+        } else if key == "type" {
+            list_type = ListStyleType::from_type_attr(&val);
+        } else if (key == "start" && matches!(tag.as_str(), "ol" | "ul"))
+            || (key == "value" && tag == "li")
+        {
+            list_ordinal = val.trim().parse::<i32>().ok();
+        } else if key == "src" {
+            src = Some(val);
+        } else if key == "alt" {
+            alt = Some(val);
+        } else if key == "width" {
+            attr_width = Dimension::parse(&val);
+        } else if key == "height" {
+            attr_height = Dimension::parse(&val);
+        } else if key == "align" {
+            attr_float = Float::parse(&val);
+        } else if key == "href" {
+            href = Some(val);
+        } else if key == "hidden" {
+            hidden = true;
+        } else if key == "xml:lang" || (key == "lang" && lang.is_none()) {
+            lang = Some(val.trim().to_string());
+        } else if key == "epub:type" || key == "role" {
+            noteref |= val
+                .split_whitespace()
+                .any(|v| v == "noteref" || v == "doc-noteref");
+        } else if key == "class" {
             classes = val
                 .split_whitespace()
                 .map(|v| v.trim().to_string())
@@ -1545,7 +3240,7 @@ fn element_ctx_from_start(
                 }
                 return Err(prep_err);
             }
-            let parsed = parse_inline_style(&val).map_err(|err| {
+            let parsed = parse_inline_declarations(&val, properties).map_err(|err| {
                 let mut prep_err = RenderPrepError::new_with_phase(
                     ErrorPhase::Style,
                     "STYLE_INLINE_PARSE_ERROR",
@@ -1564,15 +3259,209 @@ fn element_ctx_from_start(
     }
     Ok(ElementCtx {
         tag,
+        id,
         classes,
         inline_style,
+        list_type,
+        list_ordinal,
+        src,
+        alt,
+        attr_width,
+        attr_height,
+        attr_float,
+        href,
+        noteref,
+        hidden,
+        lang,
+        position: ElementPosition::default(),
     })
 }
 
+fn emit_anchor_event<F: FnMut(StyledEventOrRun)>(ctx: &mut ElementCtx, on_item: &mut F) {
+    if let Some(id) = ctx.id.take() {
+        on_item(StyledEventOrRun::Event(StyledEvent::Anchor(id)));
+    }
+}
+
+/// Routes styled items to the caller, holding back fragment links while
+/// [`FootnoteNumbering`] is on so noterefs can be renumbered once their
+/// text is known.
+struct NoterefNumberer<'a, F> {
+    state: &'a mut NoterefState,
+    on_item: &'a mut F,
+}
+
+/// Numbering carried across [`NoterefNumberer`] uses within one chapter.
+struct NoterefState {
+    numbering: FootnoteNumbering,
+    last_number: u32,
+    pending: Option<PendingNoteref>,
+}
+
+impl NoterefState {
+    fn new(numbering: FootnoteNumbering) -> Self {
+        Self {
+            numbering,
+            last_number: 0,
+            pending: None,
+        }
+    }
+}
+
+/// Link being buffered until its end tag.
+struct PendingNoteref {
+    target: String,
+    explicit: bool,
+    /// Element stack depth the link was opened at.
+    depth: usize,
+    items: Vec<StyledEventOrRun>,
+}
+
+impl<'a, F: FnMut(StyledEventOrRun)> NoterefNumberer<'a, F> {
+    fn new(state: &'a mut NoterefState, on_item: &'a mut F) -> Self {
+        Self { state, on_item }
+    }
+
+    fn emit(&mut self, item: StyledEventOrRun) {
+        match self.state.pending.as_mut() {
+            Some(pending) => pending.items.push(item),
+            None => (self.on_item)(item),
+        }
+    }
+
+    /// Start buffering `ctx` if it is a candidate noteref link opened at
+    /// stack `depth`.
+    fn open_link(&mut self, ctx: &ElementCtx, depth: usize) {
+        if !self.state.numbering.enabled || ctx.tag != "a" || self.state.pending.is_some() {
+            return;
+        }
+        let Some(href) = ctx.href.as_deref() else {
+            return;
+        };
+        if !ctx.noteref && !href.contains('#') {
+            return;
+        }
+        self.state.pending = Some(PendingNoteref {
+            target: href.to_string(),
+            explicit: ctx.noteref,
+            depth,
+            items: Vec::with_capacity(2),
+        });
+    }
+
+    /// Resolve the buffered link once the stack is back at its depth.
+    fn close_link(&mut self, depth: usize) {
+        if self.state.pending.as_ref().map(|p| p.depth) != Some(depth) {
+            return;
+        }
+        let Some(pending) = self.state.pending.take() else {
+            return;
+        };
+        let mut text = String::with_capacity(8);
+        let mut first_run = None;
+        for item in &pending.items {
+            if let StyledEventOrRun::Run(run) = item {
+                text.push_str(&run.text);
+                first_run.get_or_insert_with(|| run.clone());
+            }
+        }
+        let Some(mut marker) = first_run
+            .filter(|_| !text.trim().is_empty() && (pending.explicit || is_footnote_marker(&text)))
+        else {
+            pending.items.into_iter().for_each(&mut *self.on_item);
+            return;
+        };
+
+        self.state.last_number += 1;
+        for item in pending.items {
+            if let StyledEventOrRun::Event(_) = item {
+                (self.on_item)(item);
+            }
+        }
+        (self.on_item)(StyledEventOrRun::Event(StyledEvent::NoteRef {
+            number: self.state.last_number,
+            target: pending.target,
+        }));
+        marker.text = self.state.numbering.format.marker(self.state.last_number);
+        (self.on_item)(StyledEventOrRun::Run(marker));
+    }
+
+    /// Release a link left open at the end of the chapter unchanged.
+    fn finish(&mut self) {
+        if let Some(pending) = self.state.pending.take() {
+            pending.items.into_iter().for_each(&mut *self.on_item);
+        }
+    }
+}
+
+/// Whether link text reads as a footnote marker: `*`, `†`, `§`
+/// and similar symbols, digits, or superscript digits, optionally in
+/// brackets.
+fn is_footnote_marker(text: &str) -> bool {
+    let inner = text
+        .trim()
+        .trim_start_matches(['[', '('])
+        .trim_end_matches([']', ')'])
+        .trim();
+    if inner.is_empty() {
+        return false;
+    }
+    let symbols = inner.chars().all(|c| {
+        matches!(
+            c,
+            '*' | '\u{2020}' | '\u{2021}' | '\u{a7}' | '\u{b6}' | '\u{2016}'
+        )
+    });
+    let digits = inner.len() <= 3 && inner.chars().all(|c| c.is_ascii_digit());
+    let superscript = inner
+        .chars()
+        .all(|c| matches!(c, '\u{b9}' | '\u{b2}' | '\u{b3}' | '\u{2070}'..='\u{2079}'));
+    symbols || digits || superscript
+}
+
+/// Number of open `blockquote` elements, saturating at `u8::MAX`.
+fn blockquote_depth(stack: &[ElementCtx]) -> u8 {
+    let depth = stack.iter().filter(|ctx| ctx.tag == "blockquote").count();
+    u8::try_from(depth).unwrap_or(u8::MAX)
+}
+
+/// Elements laid out as blocks when no CSS `display` says otherwise.
+fn is_block_tag(tag: &str) -> bool {
+    matches!(
+        tag,
+        "p" | "div"
+            | "h1"
+            | "h2"
+            | "h3"
+            | "h4"
+            | "h5"
+            | "h6"
+            | "section"
+            | "article"
+            | "aside"
+            | "header"
+            | "footer"
+            | "nav"
+            | "blockquote"
+            | "figure"
+            | "figcaption"
+            | "ol"
+            | "ul"
+            | "li"
+            | "dl"
+            | "table"
+            | "pre"
+            | "hr"
+    )
+}
+
 fn emit_start_event<F: FnMut(StyledEventOrRun)>(tag: &str, on_item: &mut F) {
     match tag {
         "p" | "div" => on_item(StyledEventOrRun::Event(StyledEvent::ParagraphStart)),
         "li" => on_item(StyledEventOrRun::Event(StyledEvent::ListItemStart)),
+        "hr" => on_item(StyledEventOrRun::Event(StyledEvent::Hr)),
+        "figure" => on_item(StyledEventOrRun::Event(StyledEvent::FigureStart)),
+        "figcaption" => on_item(StyledEventOrRun::Event(StyledEvent::CaptionStart)),
         "h1" => on_item(StyledEventOrRun::Event(StyledEvent::HeadingStart(1))),
         "h2" => on_item(StyledEventOrRun::Event(StyledEvent::HeadingStart(2))),
         "h3" => on_item(StyledEventOrRun::Event(StyledEvent::HeadingStart(3))),
@@ -1587,6 +3476,9 @@ fn emit_end_event<F: FnMut(StyledEventOrRun)>(tag: &str, on_item: &mut F) {
     match tag {
         "p" | "div" => on_item(StyledEventOrRun::Event(StyledEvent::ParagraphEnd)),
         "li" => on_item(StyledEventOrRun::Event(StyledEvent::ListItemEnd)),
+        "ol" | "ul" => on_item(StyledEventOrRun::Event(StyledEvent::ListEnd)),
+        "figure" => on_item(StyledEventOrRun::Event(StyledEvent::FigureEnd)),
+        "figcaption" => on_item(StyledEventOrRun::Event(StyledEvent::CaptionEnd)),
         "h1" => on_item(StyledEventOrRun::Event(StyledEvent::HeadingEnd(1))),
         "h2" => on_item(StyledEventOrRun::Event(StyledEvent::HeadingEnd(2))),
         "h3" => on_item(StyledEventOrRun::Event(StyledEvent::HeadingEnd(3))),
@@ -1601,6 +3493,7 @@ fn role_from_tag(tag: &str) -> Option<BlockRole> {
     match tag {
         "p" | "div" => Some(BlockRole::Paragraph),
         "li" => Some(BlockRole::ListItem),
+        "figcaption" => Some(BlockRole::Caption),
         "h1" => Some(BlockRole::Heading(1)),
         "h2" => Some(BlockRole::Heading(2)),
         "h3" => Some(BlockRole::Heading(3)),
@@ -1611,6 +3504,25 @@ fn role_from_tag(tag: &str) -> Option<BlockRole> {
     }
 }
 
+/// Emit [`StyledEvent::ScriptedContent`] for the chapter's first `<script>`.
+fn emit_scripted_content<F: FnMut(StyledEventOrRun)>(
+    tag: &str,
+    saw_script: &mut bool,
+    on_item: &mut F,
+) {
+    if tag == "script" && !*saw_script {
+        *saw_script = true;
+        on_item(StyledEventOrRun::Event(StyledEvent::ScriptedContent));
+    }
+}
+
+/// `epub:case` branches and `epub:trigger` controls, matched on the
+/// qualified name since [`decode_tag_name`] drops prefixes. Only the
+/// `epub:default` branch of an `epub:switch` is rendered.
+fn is_unrendered_epub_element(qualified_name: &[u8]) -> bool {
+    matches!(qualified_name, b"epub:case" | b"epub:trigger")
+}
+
 fn should_skip_tag(tag: &str) -> bool {
     matches!(tag, "script" | "style" | "head" | "noscript")
 }
@@ -1624,6 +3536,109 @@ fn is_preformatted_context(stack: &[ElementCtx]) -> bool {
     })
 }
 
+/// Streaming rewriter for [`SmartPunctuation`].
+///
+/// Quote direction depends on the preceding character, which is carried
+/// across runs within a block and reset at block boundaries.
+struct SmartPunctuator {
+    config: SmartPunctuation,
+    prev: Option<char>,
+}
+
+impl SmartPunctuator {
+    fn new(config: SmartPunctuation) -> Self {
+        Self { config, prev: None }
+    }
+
+    /// Block-level tags start a new quoting context.
+    fn enter_tag(&mut self, tag: &str) {
+        if !is_inline_tag(tag) {
+            self.prev = None;
+        }
+    }
+
+    /// Rewrite `text`, leaving verbatim (`pre`/`code`) text unchanged.
+    fn apply<'a>(&mut self, text: &'a str, verbatim: bool) -> Cow<'a, str> {
+        if !self.config.enabled || verbatim || !text.contains(['"', '\'', '-', '.']) {
+            if let Some(last) = text.chars().next_back() {
+                self.prev = Some(last);
+            }
+            return Cow::Borrowed(text);
+        }
+        let (open_double, close_double) = self.config.quotes.double();
+        let (open_single, close_single) = self.config.quotes.single();
+        let mut out = String::with_capacity(text.len());
+        let mut chars = text.chars().peekable();
+        while let Some(ch) = chars.next() {
+            let opens = self.prev.is_none_or(|prev| {
+                prev.is_whitespace()
+                    || matches!(prev, '(' | '[' | '{' | '\u{2014}' | '\u{2013}')
+                    || prev == open_double
+                    || prev == open_single
+            });
+            let rewritten = match ch {
+                '"' if opens => open_double,
+                '"' => close_double,
+                // Apostrophes inside words are always the right single quote.
+                '\'' if self.prev.is_some_and(char::is_alphanumeric)
+                    && chars.peek().is_some_and(|next| next.is_alphanumeric()) =>
+                {
+                    '\u{2019}'
+                }
+                '\'' if opens => open_single,
+                '\'' => close_single,
+                '-' if chars.peek() == Some(&'-') => {
+                    while chars.next_if_eq(&'-').is_some() {}
+                    '\u{2014}'
+                }
+                '.' if chars.peek() == Some(&'.') => {
+                    let mut rest = chars.clone();
+                    rest.next();
+                    if rest.peek() == Some(&'.') {
+                        chars.next();
+                        chars.next();
+                        '\u{2026}'
+                    } else {
+                        '.'
+                    }
+                }
+                other => other,
+            };
+            out.push(rewritten);
+            self.prev = Some(rewritten);
+        }
+        Cow::Owned(out)
+    }
+}
+
+fn is_inline_tag(tag: &str) -> bool {
+    matches!(
+        tag,
+        "a" | "abbr"
+            | "b"
+            | "bdi"
+            | "bdo"
+            | "cite"
+            | "code"
+            | "dfn"
+            | "em"
+            | "i"
+            | "kbd"
+            | "mark"
+            | "q"
+            | "s"
+            | "samp"
+            | "small"
+            | "span"
+            | "strong"
+            | "sub"
+            | "sup"
+            | "time"
+            | "u"
+            | "var"
+    )
+}
+
 fn normalize_plain_text_whitespace(text: &str, preserve: bool) -> String {
     if preserve {
         return text.to_string();
@@ -1647,6 +3662,67 @@ fn normalize_plain_text_whitespace(text: &str, preserve: bool) -> String {
     result
 }
 
+/// Minimum [`family_similarity`] for [`FontPolicy::match_similar_families`].
+const SIMILAR_FAMILY_MIN_SCORE: u32 = 50;
+
+/// CSS generic families, which name a policy choice rather than a face.
+fn is_generic_family(family: &str) -> bool {
+    matches!(
+        family,
+        "serif" | "sans-serif" | "monospace" | "cursive" | "fantasy" | "system-ui" | "math"
+    ) || family.starts_with("ui-")
+}
+
+/// Dice coefficient of the character bigrams of two family names, ignoring
+/// everything but letters and digits, as a percentage.
+fn family_similarity(a: &str, b: &str) -> u32 {
+    let bigrams = |name: &str| {
+        let chars: Vec<char> = name.chars().filter(|ch| ch.is_alphanumeric()).collect();
+        chars
+            .windows(2)
+            .map(|pair| (pair[0], pair[1]))
+            .collect::<Vec<_>>()
+    };
+    let (a, mut b) = (bigrams(a), bigrams(b));
+    let total = a.len() + b.len();
+    if total == 0 {
+        return 0;
+    }
+    let mut shared = 0;
+    for pair in &a {
+        if let Some(pos) = b.iter().position(|other| other == pair) {
+            b.swap_remove(pos);
+            shared += 1;
+        }
+    }
+    (shared * 200 / total) as u32
+}
+
+/// Source format of `face` that cannot be registered, from its `format()`
+/// hint or file extension.
+fn unsupported_font_format(face: &EmbeddedFontFace) -> Option<&'static str> {
+    match face
+        .format
+        .as_deref()
+        .map(str::to_ascii_lowercase)
+        .as_deref()
+    {
+        Some("svg") => return Some("svg"),
+        Some("embedded-opentype") => return Some("embedded-opentype"),
+        Some(_) => return None,
+        None => {}
+    }
+    let href = face.href.to_ascii_lowercase();
+    let path = href.split(['#', '?']).next().unwrap_or_default();
+    if path.ends_with(".svg") || path.ends_with(".svgz") {
+        Some("svg")
+    } else if path.ends_with(".eot") {
+        Some("embedded-opentype")
+    } else {
+        None
+    }
+}
+
 fn normalize_family(family: &str) -> String {
     family
         .trim()
@@ -1659,6 +3735,40 @@ fn has_non_ascii(text: &str) -> bool {
     !text.is_ascii()
 }
 
+/// Invisible formatting characters fonts commonly leave unmapped.
+fn is_format_char(ch: char) -> bool {
+    matches!(
+        ch,
+        '\u{00AD}' | '\u{200B}'..='\u{200F}' | '\u{2060}' | '\u{FEFF}'
+    )
+}
+
+/// Keep only characters every rejected face lacked.
+fn narrow_missing(missing: &mut Option<Vec<char>>, uncovered: Vec<char>) {
+    *missing = Some(match missing.take() {
+        Some(previous) => previous
+            .into_iter()
+            .filter(|ch| uncovered.contains(ch))
+            .collect(),
+        None => uncovered,
+    });
+}
+
+/// `U+XXXX` list of `chars`, truncated after the first few.
+fn format_codepoints(chars: &[char]) -> String {
+    const SHOWN: usize = 8;
+    let mut out = chars
+        .iter()
+        .take(SHOWN)
+        .map(|ch| format!("U+{:04X}", *ch as u32))
+        .collect::<Vec<_>>()
+        .join(", ");
+    if chars.len() > SHOWN {
+        out.push_str(&format!(" and {} more", chars.len() - SHOWN));
+    }
+    out
+}
+
 fn resolve_item_with_font(
     font_resolver: &FontResolver,
     item: StyledEventOrRun,
@@ -1668,6 +3778,7 @@ fn resolve_item_with_font(
             let trace = font_resolver.resolve_with_trace_for_text(&run.style, Some(&run.text));
             run.font_id = trace.face.font_id;
             run.resolved_family = trace.face.family.clone();
+            run.synthesis = trace.face.synthesis;
             let style = run.style.clone();
             (
                 StyledEventOrRun::Run(run),
@@ -1694,6 +3805,9 @@ pub(crate) fn resolve_relative(base_path: &str, rel: &str) -> String {
     if rel.contains("://") {
         return rel.to_string();
     }
+    let rel = rel.split('#').next().unwrap_or(rel);
+    let rel = percent_decode_path(rel);
+    let rel = rel.as_str();
     if rel.starts_with('/') {
         return normalize_path(rel.trim_start_matches('/'));
     }
@@ -1780,23 +3894,35 @@ pub(crate) fn parse_stylesheet_links_bytes(chapter_href: &str, html_bytes: &[u8]
     out
 }
 
-fn font_src_rank(path: &str) -> u8 {
+fn font_src_rank(path: &str, format: Option<&str>) -> u8 {
     let lower = path.to_ascii_lowercase();
+    let format = format.map(str::to_ascii_lowercase);
+    match format.as_deref() {
+        Some("truetype" | "opentype") => return 4,
+        Some("woff2") => return 3,
+        Some("woff") => return 2,
+        Some("svg" | "embedded-opentype") => return 0,
+        _ => {}
+    }
     if lower.ends_with(".ttf") || lower.ends_with(".otf") {
-        3
+        4
     } else if lower.ends_with(".woff2") {
-        2
+        3
     } else if lower.ends_with(".woff") {
-        1
-    } else {
+        2
+    } else if lower.ends_with(".svg") || lower.ends_with(".svgz") || lower.ends_with(".eot") {
         0
+    } else {
+        1
     }
 }
 
-fn extract_font_face_src(css_href: &str, src_value: &str) -> Option<String> {
+/// Best `url()` source of an `@font-face` `src` list with its `format()`
+/// hint.
+fn extract_font_face_src(css_href: &str, src_value: &str) -> Option<(String, Option<String>)> {
     let lower = src_value.to_ascii_lowercase();
     let mut search_from = 0usize;
-    let mut best: Option<(u8, String)> = None;
+    let mut best: Option<(u8, String, Option<String>)> = None;
 
     while let Some(idx) = lower[search_from..].find("url(") {
         let start = search_from + idx + 4;
@@ -1805,18 +3931,29 @@ fn extract_font_face_src(css_href: &str, src_value: &str) -> Option<String> {
             break;
         };
         let raw = tail[..end].trim().trim_matches('"').trim_matches('\'');
+        search_from = start + end + 1;
+        // The hint belongs to this source if it comes before the next one.
+        let rest = &lower[search_from..];
+        let entry_end = rest.find("url(").unwrap_or(rest.len());
+        let format = rest[..entry_end].find("format(").and_then(|fmt_idx| {
+            let fmt_tail = &src_value[search_from + fmt_idx + 7..search_from + entry_end];
+            let raw = fmt_tail[..fmt_tail.find(')')?]
+                .trim()
+                .trim_matches('"')
+                .trim_matches('\'');
+            (!raw.is_empty()).then(|| raw.to_string())
+        });
         if !raw.is_empty() && !raw.starts_with("data:") {
             let resolved = resolve_relative(css_href, raw);
-            let rank = font_src_rank(&resolved);
+            let rank = font_src_rank(&resolved, format.as_deref());
             match &best {
-                Some((best_rank, _)) if *best_rank >= rank => {}
-                _ => best = Some((rank, resolved)),
+                Some((best_rank, _, _)) if *best_rank >= rank => {}
+                _ => best = Some((rank, resolved, format)),
             }
         }
-        search_from = start + end + 1;
     }
 
-    best.map(|(_, path)| path)
+    best.map(|(_, path, format)| (path, format))
 }
 
 pub(crate) fn parse_font_faces_from_css(css_href: &str, css: &str) -> Vec<EmbeddedFontFace> {
@@ -1880,24 +4017,13 @@ pub(crate) fn parse_font_faces_from_css(css_href: &str, css: &str) -> Vec<Embedd
                         EmbeddedFontStyle::Normal
                     };
                 }
-                "font-stretch" => {
-                    if !value.is_empty() {
-                        stretch = Some(value.to_string());
-                    }
+                "font-stretch" if !value.is_empty() => {
+                    stretch = Some(value.to_string());
                 }
                 "src" => {
-                    href = extract_font_face_src(css_href, value);
-                    if let Some(fmt_idx) = value.to_ascii_lowercase().find("format(") {
-                        let fmt_tail = &value[fmt_idx + 7..];
-                        if let Some(end_paren) = fmt_tail.find(')') {
-                            let raw = fmt_tail[..end_paren]
-                                .trim()
-                                .trim_matches('"')
-                                .trim_matches('\'');
-                            if !raw.is_empty() {
-                                format_hint = Some(raw.to_string());
-                            }
-                        }
+                    if let Some((src, format)) = extract_font_face_src(css_href, value) {
+                        href = Some(src);
+                        format_hint = format;
                     }
                 }
                 _ => {}
@@ -1925,6 +4051,81 @@ pub(crate) fn parse_font_faces_from_css(css_href: &str, css: &str) -> Vec<Embedd
 mod tests {
     use super::*;
 
+    #[test]
+    fn idpf_font_obfuscation_round_trips_prefix() {
+        let plain: Vec<u8> = (0..2000u32).map(|i| (i % 251) as u8).collect();
+        let id = "urn:uuid:0f1b2c3d-4e5f-6071-8293-a4b5c6d7e8f9";
+        let mut bytes = plain.clone();
+        assert!(FontObfuscation::Idpf.deobfuscate(&mut bytes, id));
+        assert_ne!(bytes[..1040], plain[..1040]);
+        assert_eq!(bytes[1040..], plain[1040..]);
+        // Whitespace in the identifier does not change the key.
+        assert!(FontObfuscation::Idpf.deobfuscate(
+            &mut bytes,
+            " urn:uuid:0f1b2c3d-4e5f-6071-8293-a4b5c6d7e8f9\n"
+        ));
+        assert_eq!(bytes, plain);
+    }
+
+    #[test]
+    fn font_obfuscation_known_answers() {
+        // IDPF key: SHA-1 of the identifier with whitespace removed.
+        const IDPF_KEY: [u8; 20] = [
+            0x98, 0xb0, 0x5f, 0x4b, 0x7d, 0x21, 0x80, 0xc8, 0x71, 0x4b, 0x8d, 0xd5, 0x2a, 0x50,
+            0x6a, 0x8c, 0xb3, 0x22, 0x5c, 0x0f,
+        ];
+        let mut bytes = [0u8; 1100];
+        assert!(FontObfuscation::Idpf.deobfuscate(
+            &mut bytes,
+            " urn:uuid:0f1b2c3d-4e5f-6071-8293-a4b5c6d7e8f9\n"
+        ));
+        assert_eq!(bytes[..20], IDPF_KEY);
+        assert_eq!(bytes[1020..1040], IDPF_KEY);
+        assert!(bytes[1040..].iter().all(|b| *b == 0));
+
+        // Adobe key: the 16 bytes of the UUID.
+        const ADOBE_KEY: [u8; 16] = [
+            0x0f, 0x1b, 0x2c, 0x3d, 0x4e, 0x5f, 0x60, 0x71, 0x82, 0x93, 0xa4, 0xb5, 0xc6, 0xd7,
+            0xe8, 0xf9,
+        ];
+        let mut bytes = [0u8; 1100];
+        assert!(FontObfuscation::Adobe
+            .deobfuscate(&mut bytes, "urn:uuid:0F1B2C3D-4E5F-6071-8293-A4B5C6D7E8F9"));
+        assert_eq!(bytes[..16], ADOBE_KEY);
+        assert_eq!(bytes[1008..1024], ADOBE_KEY);
+        assert!(bytes[1024..].iter().all(|b| *b == 0));
+    }
+
+    #[test]
+    fn adobe_font_obfuscation_needs_uuid() {
+        let key = adobe_font_key("urn:uuid:0F1B2C3D-4E5F-6071-8293-A4B5C6D7E8F9").unwrap();
+        assert_eq!(key[0], 0x0f);
+        assert_eq!(key[15], 0xf9);
+        let mut bytes = [0u8; 1100];
+        assert!(!FontObfuscation::Adobe.deobfuscate(&mut bytes, "isbn:123"));
+        assert!(bytes.iter().all(|b| *b == 0));
+        assert!(FontObfuscation::Adobe.deobfuscate(&mut bytes, "0f1b2c3d4e5f60718293a4b5c6d7e8f9"));
+        assert_eq!(bytes[16..32], key);
+        assert_eq!(bytes[1024], 0);
+    }
+
+    #[test]
+    fn font_obfuscation_map_matches_normalized_hrefs() {
+        assert_eq!(
+            FontObfuscation::from_algorithm("http://ns.adobe.com/pdf/enc#RC"),
+            Some(FontObfuscation::Adobe)
+        );
+        let mut map = FontObfuscationMap::new("id");
+        assert!(map.is_empty());
+        map.insert("./fonts/a.otf", FontObfuscation::Idpf);
+        assert_eq!(map.get("fonts/a.otf#x"), Some(FontObfuscation::Idpf));
+        assert_eq!(map.get("fonts/b.otf"), None);
+        let mut bytes = [1u8; 4];
+        assert!(!map.deobfuscate("fonts/b.otf", &mut bytes));
+        assert!(map.deobfuscate("fonts/a.otf", &mut bytes));
+        assert_ne!(bytes, [1u8; 4]);
+    }
+
     #[test]
     fn skip_tag_retains_semantic_elements() {
         assert!(!should_skip_tag("nav"));
@@ -1951,6 +4152,23 @@ mod tests {
         assert_eq!(links, vec!["styles/base.css", "text/theme.css"]);
     }
 
+    #[test]
+    fn parse_stylesheet_links_percent_decodes_hrefs() {
+        let html = r#"<html><head>
+<link rel="stylesheet" href="../My%20Styles/base%2Dlight.css"/>
+</head></html>"#;
+        let links = parse_stylesheet_links("text/ch1.xhtml", html);
+        assert_eq!(links, vec!["My Styles/base-light.css"]);
+    }
+
+    #[test]
+    fn resolve_relative_decodes_only_the_path_before_the_fragment() {
+        assert_eq!(
+            resolve_relative("text/ch1.xhtml", "../fonts/a%23b.ttf#iefix"),
+            "fonts/a#b.ttf"
+        );
+    }
+
     #[test]
     fn parse_font_faces_prefers_ttf_otf_sources() {
         let css = r#"
@@ -1997,39 +4215,455 @@ mod tests {
     }
 
     #[test]
-    fn styler_style_chapter_with_streams_items() {
+    fn styler_emits_anchor_events_for_ids() {
         let mut styler = Styler::new(StyleConfig::default());
         styler
             .load_stylesheets(&ChapterStylesheets::default())
             .expect("load should succeed");
-        let mut seen = 0usize;
-        styler
-            .style_chapter_with("<p>Hello</p>", |_item| {
-                seen += 1;
+        let chapter = styler
+            .style_chapter(r#"<h2 id="sec-3">Three</h2><p>See <a ID="n1"/>note</p>"#)
+            .expect("style should succeed");
+        let events: Vec<StyledEvent> = chapter
+            .iter()
+            .filter_map(|item| match item {
+                StyledEventOrRun::Event(event) => Some(event.clone()),
+                StyledEventOrRun::Run(_) => None,
             })
-            .expect("style_chapter_with should succeed");
-        assert!(seen > 0);
+            .collect();
+        assert_eq!(
+            events,
+            vec![
+                StyledEvent::Anchor("sec-3".to_string()),
+                StyledEvent::HeadingStart(2),
+                StyledEvent::HeadingEnd(2),
+                StyledEvent::ParagraphStart,
+                StyledEvent::Anchor("n1".to_string()),
+                StyledEvent::ParagraphEnd,
+            ]
+        );
     }
 
     #[test]
-    fn styler_applies_class_and_inline_style() {
+    fn styler_emits_hr_and_figure_events() {
         let mut styler = Styler::new(StyleConfig::default());
         styler
-            .load_stylesheets(&ChapterStylesheets {
-                sources: vec![StylesheetSource {
-                    href: "main.css".to_string(),
-                    css: ".intro { font-size: 20px; font-style: normal; }".to_string(),
-                }],
-            })
+            .load_stylesheets(&ChapterStylesheets::default())
             .expect("load should succeed");
         let chapter = styler
-            .style_chapter("<p class=\"intro\" style=\"font-style: italic\">Hello</p>")
+            .style_chapter("<hr/><figure><figcaption>Fig. 1</figcaption></figure>")
+            .expect("style should succeed");
+        let events: Vec<StyledEvent> = chapter
+            .iter()
+            .filter_map(|item| match item {
+                StyledEventOrRun::Event(event) => Some(event.clone()),
+                StyledEventOrRun::Run(_) => None,
+            })
+            .collect();
+        assert_eq!(
+            events,
+            vec![
+                StyledEvent::Hr,
+                StyledEvent::FigureStart,
+                StyledEvent::CaptionStart,
+                StyledEvent::CaptionEnd,
+                StyledEvent::FigureEnd,
+            ]
+        );
+        let run = chapter.runs().next().expect("caption run");
+        assert_eq!(run.style.block_role, BlockRole::Caption);
+    }
+
+    fn smart_run_texts(html: &str, quotes: QuoteStyle) -> Vec<String> {
+        let mut styler =
+            Styler::new(StyleConfig::default()).with_smart_punctuation(SmartPunctuation {
+                enabled: true,
+                quotes,
+            });
+        styler
+            .load_stylesheets(&ChapterStylesheets::default())
+            .expect("load should succeed");
+        let chapter = styler.style_chapter(html).expect("style should succeed");
+        chapter.runs().map(|run| run.text.clone()).collect()
+    }
+
+    #[test]
+    fn smart_punctuation_rewrites_quotes_dashes_and_ellipses() {
+        let texts = smart_run_texts(
+            r#"<p>"Wait -- it's 'fine'..." he said <em>"really"</em></p><pre>"raw" -- ...</pre>"#,
+            QuoteStyle::English,
+        );
+        assert_eq!(
+            texts,
+            vec![
+                "\u{201C}Wait \u{2014} it\u{2019}s \u{2018}fine\u{2019}\u{2026}\u{201D} he said",
+                "\u{201C}really\u{201D}",
+                "\"raw\" -- ...",
+            ]
+        );
+    }
+
+    #[test]
+    fn smart_punctuation_follows_quote_style() {
+        let texts = smart_run_texts(r#"<p>"Ja"</p><p>'so'</p>"#, QuoteStyle::German);
+        assert_eq!(texts, vec!["\u{201E}Ja\u{201C}", "\u{201A}so\u{2018}"]);
+        assert_eq!(QuoteStyle::for_language("de-AT"), QuoteStyle::German);
+        assert_eq!(QuoteStyle::for_language("fr"), QuoteStyle::French);
+        assert_eq!(QuoteStyle::for_language("en-US"), QuoteStyle::English);
+    }
+
+    #[test]
+    fn footnote_numbering_renumbers_noterefs_and_keeps_targets() {
+        let html = r##"<p>One<a href="#n1">*</a> two<sup><a href="notes.xhtml#n2">[7]</a></sup>
+            three<a epub:type="noteref" href="#n3">a</a> four<a href="#sec">chapter 2</a>
+            five<a href="#n4">&#x2020;</a></p>"##;
+        let style = |format| {
+            let mut styler =
+                Styler::new(StyleConfig::default()).with_footnote_numbering(FootnoteNumbering {
+                    enabled: true,
+                    format,
+                });
+            styler
+                .load_stylesheets(&ChapterStylesheets::default())
+                .expect("load should succeed");
+            styler.style_chapter(html).expect("style should succeed")
+        };
+
+        let chapter = style(NoterefFormat::Superscript);
+        let notes: Vec<(u32, String)> = chapter
+            .iter()
+            .filter_map(|item| match item {
+                StyledEventOrRun::Event(StyledEvent::NoteRef { number, target }) => {
+                    Some((*number, target.clone()))
+                }
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            notes,
+            vec![
+                (1, "#n1".to_string()),
+                (2, "notes.xhtml#n2".to_string()),
+                (3, "#n3".to_string()),
+                (4, "#n4".to_string()),
+            ]
+        );
+        let texts: Vec<&str> = chapter.runs().map(|run| run.text.as_str()).collect();
+        assert_eq!(
+            texts,
+            vec![
+                "One",
+                "\u{b9}",
+                "two",
+                "\u{b2}",
+                "three",
+                "\u{b3}",
+                "four",
+                "chapter 2",
+                "five",
+                "\u{2074}"
+            ]
+        );
+
+        let bracketed = style(NoterefFormat::Bracketed);
+        assert!(bracketed.runs().any(|run| run.text == "[4]"));
+        assert_eq!(
+            NoterefFormat::Superscript.marker(105),
+            "\u{b9}\u{2070}\u{2075}"
+        );
+    }
+
+    #[test]
+    fn footnote_numbering_is_off_by_default() {
+        let mut styler = Styler::new(StyleConfig::default());
+        styler
+            .load_stylesheets(&ChapterStylesheets::default())
+            .expect("load should succeed");
+        let chapter = styler
+            .style_chapter(r##"<p>One<a href="#n1">*</a></p>"##)
+            .expect("style should succeed");
+        assert!(chapter.runs().any(|run| run.text == "*"));
+        assert!(!chapter
+            .iter()
+            .any(|item| matches!(item, StyledEventOrRun::Event(StyledEvent::NoteRef { .. }))));
+    }
+
+    #[test]
+    fn scripted_content_event_is_emitted_once_at_first_script() {
+        let mut styler = Styler::new(StyleConfig::default());
+        styler
+            .load_stylesheets(&ChapterStylesheets::default())
+            .expect("load should succeed");
+        let scripted = |item: &StyledEventOrRun| {
+            matches!(item, StyledEventOrRun::Event(StyledEvent::ScriptedContent))
+        };
+
+        let chapter = styler
+            .style_chapter(
+                r#"<html><head><script src="quiz.js"/></head><body><p>Quiz</p><script>start()</script></body></html>"#,
+            )
+            .expect("style should succeed");
+        let items: Vec<&StyledEventOrRun> = chapter.iter().collect();
+        assert_eq!(items.iter().filter(|item| scripted(item)).count(), 1);
+        assert!(scripted(items[0]));
+        assert!(chapter.runs().all(|run| !run.text.contains("start")));
+
+        let plain = styler
+            .style_chapter("<p>No scripts here</p><noscript>fallback</noscript>")
+            .expect("style should succeed");
+        assert!(!plain.iter().any(scripted));
+    }
+
+    #[test]
+    fn epub_switch_renders_only_default_branch() {
+        let mut styler = Styler::new(StyleConfig::default());
+        styler
+            .load_stylesheets(&ChapterStylesheets::default())
+            .expect("load should succeed");
+        let chapter = styler
+            .style_chapter(r#"<p>Before</p><epub:switch id="eq"><epub:case required-namespace="http://www.w3.org/1998/Math/MathML"><math><mi>x</mi></math></epub:case><epub:default><p>x squared</p></epub:default></epub:switch><epub:trigger action="show" ref="eq"/><p>After</p>"#)
+            .expect("style should succeed");
+        let texts: Vec<&str> = chapter.runs().map(|run| run.text.as_str()).collect();
+        assert_eq!(texts, vec!["Before", "x squared", "After"]);
+    }
+
+    #[test]
+    fn smart_punctuation_is_off_by_default() {
+        let mut styler = Styler::new(StyleConfig::default());
+        styler
+            .load_stylesheets(&ChapterStylesheets::default())
+            .expect("load should succeed");
+        let chapter = styler
+            .style_chapter(r#"<p>"a" -- b...</p>"#)
+            .expect("style should succeed");
+        let texts: Vec<&str> = chapter.runs().map(|run| run.text.as_str()).collect();
+        assert_eq!(texts, vec!["\"a\" -- b..."]);
+    }
+
+    #[test]
+    fn styler_runs_carry_source_spans() {
+        let mut styler = Styler::new(StyleConfig::default());
+        styler
+            .load_stylesheets(&ChapterStylesheets::default())
+            .expect("load should succeed");
+        let html = "<h1>Title</h1><p>Hello <em>world</em></p>";
+        let chapter = styler.style_chapter(html).expect("style should succeed");
+        let sources: Vec<&str> = chapter
+            .runs()
+            .map(|run| {
+                let span = run.span.expect("styled runs should carry spans");
+                &html[span.start..span.end]
+            })
+            .collect();
+        assert_eq!(sources, vec!["Title", "Hello ", "world"]);
+    }
+
+    #[test]
+    fn styler_style_chapter_with_streams_items() {
+        let mut styler = Styler::new(StyleConfig::default());
+        styler
+            .load_stylesheets(&ChapterStylesheets::default())
+            .expect("load should succeed");
+        let mut seen = 0usize;
+        styler
+            .style_chapter_with("<p>Hello</p>", |_item| {
+                seen += 1;
+            })
+            .expect("style_chapter_with should succeed");
+        assert!(seen > 0);
+    }
+
+    #[test]
+    fn styler_applies_class_and_inline_style() {
+        let mut styler = Styler::new(StyleConfig::default());
+        styler
+            .load_stylesheets(&ChapterStylesheets {
+                sources: vec![StylesheetSource {
+                    href: "main.css".to_string(),
+                    css: ".intro { font-size: 20px; font-style: normal; }".to_string(),
+                }],
+            })
+            .expect("load should succeed");
+        let chapter = styler
+            .style_chapter("<p class=\"intro\" style=\"font-style: italic\">Hello</p>")
             .expect("style should succeed");
         let first = chapter.runs().next().expect("expected run");
         assert_eq!(first.style.size_px, 20.0);
         assert!(first.style.italic);
     }
 
+    #[test]
+    fn styler_applies_important_over_inline_style() {
+        let mut styler = Styler::new(StyleConfig::default());
+        styler
+            .load_stylesheets(&ChapterStylesheets {
+                sources: vec![StylesheetSource {
+                    href: "main.css".to_string(),
+                    css: "p { font-size: 20px !important; font-style: italic !important; }"
+                        .to_string(),
+                }],
+            })
+            .expect("load should succeed");
+        let chapter = styler
+            .style_chapter("<p style=\"font-size: 12px; font-style: normal !important\">Hello</p>")
+            .expect("style should succeed");
+        let first = chapter.runs().next().expect("expected run");
+        assert_eq!(first.style.size_px, 20.0);
+        assert!(!first.style.italic);
+    }
+
+    #[test]
+    fn styler_drops_hidden_subtrees_unless_included() {
+        let sources = ChapterStylesheets {
+            sources: vec![StylesheetSource {
+                href: "main.css".to_string(),
+                css: ".answer { display: none; } .shown { display: block; }".to_string(),
+            }],
+        };
+        let html = "<div><p>Question</p><div class=\"answer\"><p>Answer <b>key</b></p><img src=\"a.png\"/></div><p hidden=\"\">Alt</p><p class=\"shown\">Next</p></div>";
+        let texts = |config: StyleConfig| {
+            let mut styler = Styler::new(config);
+            styler
+                .load_stylesheets(&sources)
+                .expect("load should succeed");
+            let chapter = styler.style_chapter(html).expect("style should succeed");
+            chapter
+                .runs()
+                .map(|run| run.text.clone())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(texts(StyleConfig::default()), ["Question", "Next"]);
+        let included = StyleConfig {
+            include_hidden: true,
+            ..StyleConfig::default()
+        };
+        assert_eq!(
+            texts(included),
+            ["Question", "Answer", "key", "Alt", "Next"]
+        );
+    }
+
+    #[test]
+    fn styler_keeps_box_properties_on_their_element() {
+        let mut styler = Styler::new(StyleConfig::default());
+        styler
+            .load_stylesheets(&ChapterStylesheets {
+                sources: vec![StylesheetSource {
+                    href: "main.css".to_string(),
+                    css: "div { margin-top: 12px; font-weight: bold; } p { margin-bottom: 0; }"
+                        .to_string(),
+                }],
+            })
+            .expect("load should succeed");
+        let chapter = styler
+            .style_chapter("<div><p>Hello</p></div>")
+            .expect("style should succeed");
+        let margins: Vec<&StyledEvent> = chapter
+            .iter()
+            .filter_map(|item| match item {
+                StyledEventOrRun::Event(ev @ StyledEvent::BlockMargins { .. }) => Some(ev),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            margins,
+            vec![
+                &StyledEvent::BlockMargins {
+                    top: Some(12),
+                    bottom: None
+                },
+                &StyledEvent::BlockMargins {
+                    top: None,
+                    bottom: Some(0)
+                },
+            ]
+        );
+        let first = chapter.runs().next().expect("expected run");
+        assert_eq!(first.style.weight, 700);
+    }
+
+    #[test]
+    fn styler_emits_block_breaks_before_anchor_and_start() {
+        let mut styler = Styler::new(StyleConfig::default());
+        styler
+            .load_stylesheets(&ChapterStylesheets {
+                sources: vec![StylesheetSource {
+                    href: "main.css".to_string(),
+                    css: "h2 { page-break-before: always; } .col { -webkit-column-break-before: always; } span { break-before: page; }"
+                        .to_string(),
+                }],
+            })
+            .expect("load should succeed");
+        let chapter = styler
+            .style_chapter(
+                "<section><h2 id=\"c1\">One</h2><p class=\"col\">A <span>b</span></p></section>",
+            )
+            .expect("style should succeed");
+        let events: Vec<&StyledEvent> = chapter
+            .iter()
+            .filter_map(|item| match item {
+                StyledEventOrRun::Event(ev) => Some(ev),
+                StyledEventOrRun::Run(_) => None,
+            })
+            .take(4)
+            .collect();
+        assert_eq!(
+            events,
+            vec![
+                &StyledEvent::BlockBreak(BreakBefore::Page),
+                &StyledEvent::Anchor("c1".to_string()),
+                &StyledEvent::HeadingStart(2),
+                &StyledEvent::HeadingEnd(2),
+            ]
+        );
+        let breaks = chapter
+            .iter()
+            .filter(|item| matches!(item, StyledEventOrRun::Event(StyledEvent::BlockBreak(_))))
+            .count();
+        // The inline span's break does not apply.
+        assert_eq!(breaks, 2);
+    }
+
+    #[test]
+    fn styler_matches_structural_pseudo_classes() {
+        let mut styler = Styler::new(StyleConfig::default());
+        styler
+            .load_stylesheets(&ChapterStylesheets {
+                sources: vec![StylesheetSource {
+                    href: "main.css".to_string(),
+                    css:
+                        "p:first-child { font-weight: bold; } p:last-child { font-style: italic; } \
+                          h2:first-of-type { font-size: 30px; }"
+                            .to_string(),
+                }],
+            })
+            .expect("load should succeed");
+        let chapter = styler
+            .style_chapter(
+                "<section><p>a</p><h2>b</h2><p>c</p><h2>d</h2><p>e</p></section>\
+                 <div><script>x()</script><p>f</p></div>",
+            )
+            .expect("style should succeed");
+        let styles: Vec<(&str, u16, bool, f32)> = chapter
+            .runs()
+            .map(|run| {
+                (
+                    run.text.as_str(),
+                    run.style.weight,
+                    run.style.italic,
+                    run.style.size_px,
+                )
+            })
+            .collect();
+        assert_eq!(styles[0], ("a", 700, false, 16.0));
+        assert_eq!(styles[1].3, 30.0);
+        assert_eq!(styles[2], ("c", 400, false, 16.0));
+        assert_ne!(styles[3].3, 30.0);
+        assert_eq!(styles[4], ("e", 400, true, 16.0));
+        // A skipped script still counts as the preceding sibling.
+        assert_eq!(styles[5], ("f", 400, true, 16.0));
+    }
+
     #[test]
     fn styler_respects_stylesheet_precedence_order() {
         let mut styler = Styler::new(StyleConfig::default());
@@ -2054,6 +4688,217 @@ mod tests {
         assert_eq!(first.style.size_px, 18.0);
     }
 
+    #[test]
+    fn styler_resolves_custom_properties_across_stylesheets() {
+        let mut styler = Styler::new(StyleConfig::default());
+        styler
+            .load_stylesheets(&ChapterStylesheets {
+                sources: vec![
+                    StylesheetSource {
+                        href: "vars.css".to_string(),
+                        css: ":root { --text: 21px; }".to_string(),
+                    },
+                    StylesheetSource {
+                        href: "main.css".to_string(),
+                        css: "p { font-size: var(--text); }".to_string(),
+                    },
+                ],
+            })
+            .expect("load should succeed");
+        let chapter = styler
+            .style_chapter("<p>Hello</p><p style=\"font-size: var(--none, 15px)\">Bye</p>")
+            .expect("style should succeed");
+        let sizes: Vec<f32> = chapter.runs().map(|run| run.style.size_px).collect();
+        assert_eq!(sizes, vec![21.0, 15.0]);
+    }
+
+    #[test]
+    fn styler_resolves_relative_and_viewport_font_sizes() {
+        let mut config = StyleConfig::default();
+        config.hints.base_font_size_px = 20.0;
+        config.hints.viewport_width_px = 600.0;
+        let mut styler = Styler::new(config);
+        styler
+            .load_stylesheets(&ChapterStylesheets {
+                sources: vec![StylesheetSource {
+                    href: "main.css".to_string(),
+                    css: "div { font-size: 30px; } .half { font-size: 50%; } \
+                          .em { font-size: 0.8em; } .rem { font-size: 1.1rem; } \
+                          .vw { font-size: 4vw; } .vh { font-size: 10vh; }"
+                        .to_string(),
+                }],
+            })
+            .expect("load should succeed");
+        let chapter = styler
+            .style_chapter(
+                "<div><p class=\"half\"><span class=\"em\">a</span></p>\
+                 <p class=\"rem\">b</p><p class=\"vw\">c</p><p class=\"vh\">d</p></div>",
+            )
+            .expect("style should succeed");
+        let sizes: Vec<f32> = chapter.runs().map(|run| run.style.size_px).collect();
+        // 30px * 50% * 0.8em; 1.1rem of 20px; 4% of 600px; vh unset keeps 30px.
+        assert_eq!(sizes, vec![12.0, 22.0, 24.0, 30.0]);
+    }
+
+    #[test]
+    fn styler_applies_type_ramp_where_css_is_silent() {
+        let mut styler = Styler::new(StyleConfig::default());
+        styler
+            .load_stylesheets(&ChapterStylesheets {
+                sources: vec![StylesheetSource {
+                    href: "a.css".to_string(),
+                    css: "h2 { font-size: 20px; font-weight: normal; }".to_string(),
+                }],
+            })
+            .expect("load should succeed");
+        let chapter = styler
+            .style_chapter("<h1>One</h1><h2>Two</h2><h6>Six</h6><p>Body</p>")
+            .expect("style should succeed");
+        let styles: Vec<(f32, u16)> = chapter
+            .runs()
+            .map(|run| (run.style.size_px, run.style.weight))
+            .collect();
+        assert_eq!(
+            styles,
+            vec![(25.6, 700), (20.0, 400), (16.0, 400), (16.0, 400)]
+        );
+    }
+
+    #[test]
+    fn styler_emits_list_styles_and_ordinals() {
+        let mut styler = Styler::new(StyleConfig::default());
+        styler
+            .load_stylesheets(&ChapterStylesheets {
+                sources: vec![StylesheetSource {
+                    href: "a.css".to_string(),
+                    css: "ol.toc { list-style-type: upper-roman; }".to_string(),
+                }],
+            })
+            .expect("load should succeed");
+        let chapter = styler
+            .style_chapter(
+                r#"<ol class="toc" type="a" start="4"><li>x</li><li value="7">y</li></ol><ul><li>z</li></ul>"#,
+            )
+            .expect("style should succeed");
+        let events: Vec<&StyledEvent> = chapter
+            .iter()
+            .filter_map(|item| match item {
+                StyledEventOrRun::Event(ev) => Some(ev),
+                StyledEventOrRun::Run(_) => None,
+            })
+            .collect();
+        assert_eq!(
+            events,
+            vec![
+                &StyledEvent::ListStart {
+                    style: ListStyleType::UpperRoman,
+                    start: 4,
+                },
+                &StyledEvent::ListItemStart,
+                &StyledEvent::ListItemEnd,
+                &StyledEvent::ListItemValue(7),
+                &StyledEvent::ListItemStart,
+                &StyledEvent::ListItemEnd,
+                &StyledEvent::ListEnd,
+                &StyledEvent::ListStart {
+                    style: ListStyleType::Disc,
+                    start: 1,
+                },
+                &StyledEvent::ListItemStart,
+                &StyledEvent::ListItemEnd,
+                &StyledEvent::ListEnd,
+            ]
+        );
+    }
+
+    #[test]
+    fn chapter_title_prefers_nav_label_then_heading_then_book_title() {
+        let styler = Styler::new(StyleConfig::default());
+        let chapter = styler
+            .style_chapter("<h3>Aside</h3><h2>The <em>Long</em>\n  Road</h2><h1>Later</h1>")
+            .expect("style should succeed");
+        let resolve = |nav_label: Option<&str>, book_title: &str, items: &[StyledEventOrRun]| {
+            let mut resolver = ChapterTitleResolver::new(nav_label.map(String::from), book_title);
+            items.iter().for_each(|item| resolver.observe(item));
+            resolver.title()
+        };
+        let items: Vec<StyledEventOrRun> = chapter.iter().cloned().collect();
+        assert_eq!(
+            resolve(Some(" Chapter 2 "), "Book", &items).as_deref(),
+            Some("Chapter 2")
+        );
+        assert_eq!(
+            resolve(None, "Book", &items).as_deref(),
+            Some("The Long Road")
+        );
+        assert_eq!(resolve(Some(""), "Book", &[]).as_deref(), Some("Book"));
+        assert_eq!(resolve(None, "  ", &[]), None);
+    }
+
+    #[test]
+    fn styler_emits_blockquote_depth() {
+        let mut styler = Styler::new(StyleConfig::default());
+        styler
+            .load_stylesheets(&ChapterStylesheets::default())
+            .expect("load should succeed");
+        let chapter = styler
+            .style_chapter("<blockquote><p>a</p><blockquote><p>b</p></blockquote></blockquote>")
+            .expect("style should succeed");
+        let quotes: Vec<&StyledEvent> = chapter
+            .iter()
+            .filter_map(|item| match item {
+                StyledEventOrRun::Event(
+                    ev @ (StyledEvent::BlockquoteStart(_) | StyledEvent::BlockquoteEnd(_)),
+                ) => Some(ev),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            quotes,
+            vec![
+                &StyledEvent::BlockquoteStart(1),
+                &StyledEvent::BlockquoteStart(2),
+                &StyledEvent::BlockquoteEnd(2),
+                &StyledEvent::BlockquoteEnd(1),
+            ]
+        );
+    }
+
+    #[test]
+    fn styler_emits_sized_images() {
+        let mut styler = Styler::new(StyleConfig::default());
+        styler
+            .load_stylesheets(&ChapterStylesheets {
+                sources: vec![StylesheetSource {
+                    href: "a.css".to_string(),
+                    css: "img.wide { width: 100%; }".to_string(),
+                }],
+            })
+            .expect("load should succeed");
+        let chapter = styler
+            .style_chapter(
+                r#"<p><img class="wide" src="map.png" alt="Map" width="600" height="400" align="right"/></p>"#,
+            )
+            .expect("style should succeed");
+        let image = chapter
+            .iter()
+            .find_map(|item| match item {
+                StyledEventOrRun::Event(ev @ StyledEvent::Image { .. }) => Some(ev),
+                _ => None,
+            })
+            .expect("image event");
+        assert_eq!(
+            image,
+            &StyledEvent::Image {
+                src: "map.png".to_string(),
+                alt: "Map".to_string(),
+                width: Some(Dimension::Percent(100)),
+                height: Some(Dimension::Px(400)),
+                float: Float::Right,
+            }
+        );
+    }
+
     #[test]
     fn styler_enforces_css_byte_limit() {
         let mut styler = Styler::new(StyleConfig {
@@ -2062,6 +4907,8 @@ mod tests {
                 ..StyleLimits::default()
             },
             hints: LayoutHints::default(),
+            type_ramp: TypeRamp::default(),
+            include_hidden: false,
         });
         let styles = ChapterStylesheets {
             sources: vec![StylesheetSource {
@@ -2085,6 +4932,8 @@ mod tests {
                 ..StyleLimits::default()
             },
             hints: LayoutHints::default(),
+            type_ramp: TypeRamp::default(),
+            include_hidden: false,
         });
         let styles = ChapterStylesheets {
             sources: vec![StylesheetSource {
@@ -2125,6 +4974,106 @@ mod tests {
         assert!(ctx.token_offset.is_some());
     }
 
+    #[test]
+    fn styler_enforces_nesting_limit() {
+        let html = "<div><div><p id=\"n\">deep <b>bold</b></p></div></div>";
+        let styler = |nesting_overflow| {
+            let mut styler = Styler::new(StyleConfig {
+                limits: StyleLimits {
+                    max_nesting: 2,
+                    nesting_overflow,
+                    ..StyleLimits::default()
+                },
+                ..StyleConfig::default()
+            });
+            styler
+                .load_stylesheets(&ChapterStylesheets::default())
+                .expect("load should succeed");
+            styler
+        };
+
+        let err = styler(NestingOverflow::Error)
+            .style_chapter(html)
+            .expect_err("should reject deep nesting");
+        assert_eq!(err.code, "STYLE_NESTING_LIMIT");
+        let limit = err.limit.expect("expected limit context");
+        assert_eq!((limit.actual, limit.limit), (3, 2));
+        let ctx = err.context.expect("expected context");
+        assert_eq!(ctx.token_offset, Some(10));
+
+        let chapter = styler(NestingOverflow::Flatten)
+            .style_chapter(html)
+            .expect("flatten should succeed");
+        assert!(chapter.items.iter().any(|item| matches!(
+            item,
+            StyledEventOrRun::Event(StyledEvent::Anchor(id)) if id == "n"
+        )));
+        let runs: Vec<_> = chapter
+            .runs()
+            .map(|run| (run.text.as_str(), run.style.weight))
+            .collect();
+        assert_eq!(runs, vec![("deep", 400), ("bold", 400)]);
+    }
+
+    #[test]
+    fn styler_enforces_streaming_limits() {
+        let styler = |limits| {
+            let mut styler = Styler::new(StyleConfig {
+                limits,
+                ..StyleConfig::default()
+            });
+            styler
+                .load_stylesheets(&ChapterStylesheets::default())
+                .expect("load should succeed");
+            styler
+        };
+        let cases = [
+            (
+                StyleLimits {
+                    max_attributes: 2,
+                    ..StyleLimits::default()
+                },
+                "<p>x</p><p a=\"1\" b=\"2\" c=\"3\">y</p>",
+                "STYLE_ATTRIBUTE_LIMIT",
+                (3, 2),
+                8,
+            ),
+            (
+                StyleLimits {
+                    max_entity_expansions: 2,
+                    ..StyleLimits::default()
+                },
+                "<p>&amp;&lt;&gt;</p>",
+                "STYLE_ENTITY_LIMIT",
+                (3, 2),
+                12,
+            ),
+            (
+                StyleLimits {
+                    max_events: 4,
+                    ..StyleLimits::default()
+                },
+                "<p>a</p><p>b</p>",
+                "STYLE_EVENT_LIMIT",
+                (5, 4),
+                11,
+            ),
+        ];
+        for (limits, html, code, counts, offset) in cases {
+            let err = styler(limits)
+                .style_chapter(html)
+                .expect_err("limit should trip");
+            assert_eq!(err.code, code);
+            let limit = err.limit.expect("expected limit context");
+            assert_eq!((limit.actual, limit.limit), counts);
+            let ctx = err.context.expect("expected context");
+            assert_eq!(ctx.token_offset, Some(offset));
+        }
+        assert!(styler(StyleLimits::default())
+            .style_chapter("<p a=\"1\" b=\"2\">&amp;&lt;</p>")
+            .is_ok());
+    }
+
     #[test]
     fn style_tokenize_error_sets_token_offset_context() {
         let mut styler = Styler::new(StyleConfig::default());
@@ -2189,6 +5138,7 @@ mod tests {
             line_height: 1.4,
             letter_spacing: 0.0,
             block_role: BlockRole::Body,
+            language: None,
         };
         let trace = resolver.resolve_with_trace(&style);
         assert_eq!(trace.face.family, "serif");
@@ -2227,6 +5177,7 @@ mod tests {
             line_height: 1.4,
             letter_spacing: 0.0,
             block_role: BlockRole::Body,
+            language: None,
         };
         let trace = resolver.resolve_with_trace(&style);
         let chosen = trace.face.embedded.expect("should match embedded");
@@ -2244,6 +5195,7 @@ mod tests {
             line_height: 1.4,
             letter_spacing: 0.0,
             block_role: BlockRole::Body,
+            language: None,
         };
         let trace = resolver.resolve_with_trace_for_text(&style, Some("Привет"));
         assert!(trace
@@ -2252,6 +5204,204 @@ mod tests {
             .any(|v| v.contains("missing glyph risk")));
     }
 
+    #[test]
+    fn styler_propagates_element_language() {
+        let mut styler = Styler::new(StyleConfig::default());
+        styler
+            .load_stylesheets(&ChapterStylesheets::default())
+            .expect("load should succeed");
+        let chapter = styler
+            .style_chapter(
+                "<body lang=\"en\"><p>a <span xml:lang=\"el-GR\" lang=\"de\">b</span></p>\
+                 <p lang=\"\">c</p></body>",
+            )
+            .expect("style should succeed");
+        let languages: Vec<Option<&str>> = chapter
+            .runs()
+            .map(|run| run.style.language.as_deref())
+            .collect();
+        assert_eq!(languages, [Some("en"), Some("el-GR"), None]);
+    }
+
+    #[test]
+    fn font_resolver_prefers_language_families() {
+        let policy = FontPolicy::serif_default()
+            .with_language_families("el", ["GFS Didot", "Noto Serif"])
+            .with_language_families("el-polyton", ["GFS Porson"]);
+        assert_eq!(
+            policy.families_for_language("EL-GR").map(<[String]>::len),
+            Some(2)
+        );
+        assert_eq!(
+            policy.families_for_language("el-polyton"),
+            Some(&["GFS Porson".to_string()][..])
+        );
+        assert_eq!(policy.families_for_language("ell"), None);
+
+        let mut resolver = FontResolver::new(policy);
+        let face = EmbeddedFontFace {
+            family: "Noto Serif".to_string(),
+            weight: 400,
+            style: EmbeddedFontStyle::Normal,
+            stretch: None,
+            href: "noto.ttf".to_string(),
+            format: None,
+        };
+        resolver
+            .register_epub_fonts(vec![face], |_href| Ok(vec![1, 2, 3]))
+            .expect("register should succeed");
+        let mut style = ComputedTextStyle {
+            family_stack: vec!["Literata".to_string()],
+            weight: 400,
+            italic: false,
+            size_px: 16.0,
+            line_height: 1.4,
+            letter_spacing: 0.0,
+            block_role: BlockRole::Body,
+            language: Some("el-GR".to_string()),
+        };
+        let trace = resolver.resolve_with_trace(&style);
+        assert_eq!(trace.face.family, "Noto Serif");
+        assert!(trace
+            .reason_chain
+            .iter()
+            .any(|v| v.contains("language 'el-GR' prefers embedded family 'Noto Serif'")));
+
+        style.language = Some("el-polyton".to_string());
+        let trace = resolver.resolve_with_trace(&style);
+        assert_eq!(trace.face.font_id, 0);
+        assert_eq!(trace.face.family, "GFS Porson");
+        assert!(trace
+            .reason_chain
+            .iter()
+            .any(|v| v.contains("fallback to language 'el-polyton'")));
+
+        style.language = Some("en".to_string());
+        assert_eq!(resolver.resolve(&style).family, "serif");
+    }
+
+    #[test]
+    fn font_resolver_requests_synthesis_only_when_policy_allows() {
+        let face = EmbeddedFontFace {
+            family: "Literata".to_string(),
+            weight: 400,
+            style: EmbeddedFontStyle::Normal,
+            stretch: None,
+            href: "a.ttf".to_string(),
+            format: None,
+        };
+        let style = ComputedTextStyle {
+            family_stack: vec!["Literata".to_string()],
+            weight: 700,
+            italic: true,
+            size_px: 16.0,
+            line_height: 1.4,
+            letter_spacing: 0.0,
+            block_role: BlockRole::Body,
+            language: None,
+        };
+        let resolve = |policy: FontPolicy| {
+            let mut resolver = FontResolver::new(policy);
+            resolver
+                .register_epub_fonts(vec![face.clone()], |_href| Ok(vec![1, 2, 3]))
+                .expect("register should succeed");
+            resolver.resolve_with_trace(&style)
+        };
+
+        let trace = resolve(FontPolicy::serif_default());
+        assert!(trace.face.synthesis.is_none());
+
+        let trace = resolve(FontPolicy {
+            synthetic_bold: true,
+            synthetic_italic: true,
+            ..FontPolicy::serif_default()
+        });
+        assert_eq!(
+            trace.face.synthesis,
+            FontSynthesis {
+                bold: true,
+                italic: true
+            }
+        );
+        assert!(trace
+            .reason_chain
+            .iter()
+            .any(|v| v.contains("synthesizing bold")));
+        assert!(trace
+            .reason_chain
+            .iter()
+            .any(|v| v.contains("synthesizing italic")));
+    }
+
+    #[test]
+    fn font_resolver_skips_faces_missing_run_glyphs() {
+        use crate::cmap::tests::{font_with_cmap, format4};
+
+        let face = |family: &str, href: &str| EmbeddedFontFace {
+            family: family.to_string(),
+            weight: 400,
+            style: EmbeddedFontStyle::Normal,
+            stretch: None,
+            href: href.to_string(),
+            format: None,
+        };
+        let mut resolver = FontResolver::new(FontPolicy::serif_default());
+        resolver
+            .register_epub_fonts(
+                vec![face("Subset", "subset.ttf"), face("Greek", "greek.ttf")],
+                |href| {
+                    Ok(match href {
+                        "subset.ttf" => font_with_cmap(&format4(&[(0x20, 0x7E)])),
+                        _ => font_with_cmap(&format4(&[(0x20, 0x7E), (0x391, 0x3A9)])),
+                    })
+                },
+            )
+            .expect("register should succeed");
+        let mut style = ComputedTextStyle {
+            family_stack: vec!["Subset".to_string(), "Greek".to_string()],
+            weight: 400,
+            italic: false,
+            size_px: 16.0,
+            line_height: 1.4,
+            letter_spacing: 0.0,
+            block_role: BlockRole::Body,
+            language: None,
+        };
+
+        let trace = resolver.resolve_with_trace_for_text(&style, Some("plain text"));
+        assert_eq!(trace.face.family, "Subset");
+        assert!(trace.missing_glyphs.is_empty());
+
+        let trace = resolver.resolve_with_trace_for_text(&style, Some("Ω mega\u{00AD}"));
+        assert_eq!(trace.face.family, "Greek");
+        assert!(trace
+            .reason_chain
+            .iter()
+            .any(|v| v == "family 'Subset' lacks glyphs for U+03A9"));
+        assert!(trace.missing_glyphs.is_empty());
+
+        style.family_stack.truncate(1);
+        let mut report = MissingGlyphReport::default();
+        for text in ["Ω mega", "ΩΨ", "abc"] {
+            let trace = resolver.resolve_with_trace_for_text(&style, Some(text));
+            report.record(&trace);
+        }
+        let trace = resolver.resolve_with_trace_for_text(&style, Some("Ω"));
+        assert_eq!(trace.face.font_id, 0);
+        assert_eq!(trace.missing_glyphs, ['Ω']);
+        assert!(!trace
+            .reason_chain
+            .iter()
+            .any(|v| v.contains("missing glyph risk")));
+        assert_eq!(
+            report.glyphs(),
+            [
+                MissingGlyph { ch: 'Ω', runs: 2 },
+                MissingGlyph { ch: 'Ψ', runs: 1 }
+            ]
+        );
+    }
+
     #[test]
     fn font_resolver_deduplicates_faces() {
         let mut resolver = FontResolver::new(FontPolicy::serif_default()).with_limits(FontLimits {
@@ -2266,9 +5416,19 @@ mod tests {
             href: "a.ttf".to_string(),
             format: None,
         };
-        resolver
+        let registrations = resolver
             .register_epub_fonts(vec![face.clone(), face], |_href| Ok(vec![1, 2, 3]))
             .expect("register should succeed");
+        assert_eq!(
+            registrations
+                .iter()
+                .map(|registration| registration.status.clone())
+                .collect::<Vec<_>>(),
+            vec![
+                FontRegistrationStatus::Accepted { font_id: 1 },
+                FontRegistrationStatus::Skipped(FontSkipReason::Duplicate),
+            ]
+        );
         let style = ComputedTextStyle {
             family_stack: vec!["Literata".to_string()],
             weight: 400,
@@ -2277,11 +5437,107 @@ mod tests {
             line_height: 1.4,
             letter_spacing: 0.0,
             block_role: BlockRole::Body,
+            language: None,
         };
         let trace = resolver.resolve_with_trace(&style);
         assert!(trace.face.embedded.is_some());
     }
 
+    #[test]
+    fn font_resolver_skips_svg_and_eot_faces_and_traces_them() {
+        let css = r#"
+@font-face { font-family: "Legacy"; src: url("../fonts/legacy.eot") format("embedded-opentype"), url("../fonts/legacy.svg#f") format("svg"); }
+@font-face { font-family: "Modern"; src: url("../fonts/modern.eot") format("embedded-opentype"), url("../fonts/modern.ttf"); }
+"#;
+        let faces = parse_font_faces_from_css("styles/main.css", css);
+        assert_eq!(faces[0].format.as_deref(), Some("embedded-opentype"));
+        assert_eq!(faces[1].href, "fonts/modern.ttf");
+        assert_eq!(faces[1].format, None);
+
+        let mut resolver = FontResolver::new(FontPolicy::serif_default());
+        let mut loaded = Vec::with_capacity(0);
+        let registrations = resolver
+            .register_epub_fonts(faces, |href| {
+                loaded.push(href.to_string());
+                Ok(vec![1, 2, 3])
+            })
+            .expect("register should succeed");
+        assert_eq!(loaded, ["fonts/modern.ttf"]);
+        assert_eq!(
+            registrations[0].status,
+            FontRegistrationStatus::Skipped(FontSkipReason::UnsupportedFormat(
+                "embedded-opentype".to_string()
+            ))
+        );
+        assert_eq!(
+            registrations[1].status,
+            FontRegistrationStatus::Accepted { font_id: 1 }
+        );
+
+        let style = ComputedTextStyle {
+            family_stack: vec!["Legacy".to_string()],
+            weight: 400,
+            italic: false,
+            size_px: 16.0,
+            line_height: 1.4,
+            letter_spacing: 0.0,
+            block_role: BlockRole::Body,
+            language: None,
+        };
+        let trace = resolver.resolve_with_trace(&style);
+        assert!(trace.face.embedded.is_none());
+        assert_eq!(trace.skipped_faces, registrations[..1]);
+        assert!(trace
+            .reason_chain
+            .iter()
+            .any(|v| v.contains("skipped at registration: unsupported format")));
+    }
+
+    #[test]
+    fn font_resolver_maps_similar_family_names_when_enabled() {
+        let face = EmbeddedFontFace {
+            family: "Literata".to_string(),
+            weight: 400,
+            style: EmbeddedFontStyle::Normal,
+            stretch: None,
+            href: "a.ttf".to_string(),
+            format: None,
+        };
+        let style = |family: &str| ComputedTextStyle {
+            family_stack: vec![family.to_string()],
+            weight: 400,
+            italic: false,
+            size_px: 16.0,
+            line_height: 1.4,
+            letter_spacing: 0.0,
+            block_role: BlockRole::Body,
+            language: None,
+        };
+        let resolver = |policy: FontPolicy| {
+            let mut resolver = FontResolver::new(policy);
+            resolver
+                .register_epub_fonts(vec![face.clone()], |_href| Ok(vec![1, 2, 3]))
+                .expect("register should succeed");
+            resolver
+        };
+
+        let plain = resolver(FontPolicy::serif_default());
+        assert!(plain.resolve(&style("Literata Book")).embedded.is_none());
+
+        let similar = resolver(FontPolicy {
+            match_similar_families: true,
+            ..FontPolicy::serif_default()
+        });
+        let trace = similar.resolve_with_trace(&style("Literata Book"));
+        assert_eq!(trace.face.family, "Literata");
+        assert!(trace
+            .reason_chain
+            .iter()
+            .any(|v| v.contains("similar embedded family 'Literata'")));
+        assert!(similar.resolve(&style("Georgia")).embedded.is_none());
+        assert!(similar.resolve(&style("serif")).embedded.is_none());
+    }
+
     #[test]
     fn font_resolver_register_rejects_too_many_faces() {
         let mut resolver = FontResolver::new(FontPolicy::serif_default()).with_limits(FontLimits {
@@ -0,0 +1,394 @@
+//! Byte-preserving insertion of markup into chapter XHTML.
+//!
+//! [`rewrite_html`] copies a chapter to a writer unchanged except for
+//! caller-provided fragments spliced in at byte offsets or element
+//! boundaries. Nothing is re-serialized: attribute quoting, whitespace,
+//! comments and entity spelling all survive, so offsets recorded against the
+//! original file (e.g. by annotation tools) stay meaningful.
+//!
+//! Every insertion point is validated against the XML token stream first; an
+//! offset inside a tag, comment, CDATA section or entity reference is
+//! rejected before any output is written.
+//!
+//! ```
+//! use mu_epub::rewrite::{rewrite_html, InsertAt, Insertion};
+//!
+//! let html = r#"<p id="p1">Hello &amp; welcome</p>"#;
+//! let mut out = Vec::new();
+//! rewrite_html(
+//!     html,
+//!     &[
+//!         Insertion::new(InsertAt::ContentStart("p1"), "<span class=\"hl\">"),
+//!         Insertion::new(InsertAt::Offset(22), "</span>"),
+//!     ],
+//!     &mut out,
+//! )
+//! .unwrap();
+//! assert_eq!(
+//!     String::from_utf8(out).unwrap(),
+//!     r#"<p id="p1"><span class="hl">Hello &amp;</span> welcome</p>"#
+//! );
+//! ```
+
+extern crate alloc;
+
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt;
+use std::io::Write;
+
+use quick_xml::events::Event;
+use quick_xml::reader::Reader;
+
+/// Where an [`Insertion`] goes in the source document.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum InsertAt<'a> {
+    /// Byte offset into the source. Must lie between tokens or inside text
+    /// on a character boundary.
+    Offset(usize),
+    /// Just before the start tag of the element with this `id`.
+    BeforeElement(&'a str),
+    /// Just after the start tag of the element with this `id`.
+    ContentStart(&'a str),
+    /// Just before the end tag of the element with this `id`.
+    ContentEnd(&'a str),
+    /// Just after the end tag of the element with this `id`.
+    AfterElement(&'a str),
+}
+
+/// A fragment to splice into the source at a given position.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Insertion<'a> {
+    /// Insertion point.
+    pub at: InsertAt<'a>,
+    /// Markup written verbatim at that point.
+    pub fragment: &'a str,
+}
+
+impl<'a> Insertion<'a> {
+    /// Insert `fragment` at `at`.
+    pub fn new(at: InsertAt<'a>, fragment: &'a str) -> Self {
+        Self { at, fragment }
+    }
+}
+
+/// Errors from [`rewrite_html`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum RewriteError {
+    /// Offset lies past the end of the document.
+    OffsetOutOfBounds {
+        /// Requested offset.
+        offset: usize,
+        /// Document length in bytes.
+        len: usize,
+    },
+    /// Offset falls inside a tag, comment, CDATA section or entity
+    /// reference, or splits a UTF-8 character.
+    OffsetInsideToken {
+        /// Requested offset.
+        offset: usize,
+    },
+    /// No element carries the requested `id`.
+    ElementNotFound(String),
+    /// A content boundary was requested for a self-closing element.
+    EmptyElement(String),
+    /// The document could not be tokenized.
+    Parse(String),
+    /// Writing the output failed.
+    Io(std::io::ErrorKind),
+}
+
+impl fmt::Display for RewriteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RewriteError::OffsetOutOfBounds { offset, len } => write!(
+                f,
+                "Insertion offset {} is past the end of the document ({} bytes)",
+                offset, len
+            ),
+            RewriteError::OffsetInsideToken { offset } => {
+                write!(f, "Insertion offset {} falls inside a markup token", offset)
+            }
+            RewriteError::ElementNotFound(id) => write!(f, "No element with id '{}'", id),
+            RewriteError::EmptyElement(id) => {
+                write!(f, "Element '{}' is self-closing and has no content", id)
+            }
+            RewriteError::Parse(msg) => write!(f, "Rewrite parse error: {}", msg),
+            RewriteError::Io(kind) => write!(f, "Rewrite I/O error: {}", kind),
+        }
+    }
+}
+
+impl std::error::Error for RewriteError {}
+
+/// Copy `html` to `out`, splicing in each insertion's fragment.
+///
+/// Fragments resolving to the same offset are written in slice order. All
+/// insertion points are validated before anything is written, so an error
+/// leaves `out` untouched. Returns the number of bytes written.
+pub fn rewrite_html<W: Write>(
+    html: &str,
+    insertions: &[Insertion<'_>],
+    out: &mut W,
+) -> Result<usize, RewriteError> {
+    let offsets = resolve_offsets(html, insertions)?;
+    let mut order: Vec<usize> = (0..insertions.len()).collect();
+    order.sort_by_key(|&i| offsets[i]);
+
+    let mut written = 0usize;
+    let mut copied = 0usize;
+    for i in order {
+        let offset = offsets[i];
+        for chunk in [&html[copied..offset], insertions[i].fragment] {
+            out.write_all(chunk.as_bytes())
+                .map_err(|err| RewriteError::Io(err.kind()))?;
+            written += chunk.len();
+        }
+        copied = offset;
+    }
+    out.write_all(&html.as_bytes()[copied..])
+        .map_err(|err| RewriteError::Io(err.kind()))?;
+    Ok(written + html.len() - copied)
+}
+
+/// Byte spans of an element's start and end tags.
+#[derive(Clone, Copy, Debug, Default)]
+struct ElementSpans {
+    start_tag: Option<(usize, usize)>,
+    end_tag: Option<(usize, usize)>,
+    empty: bool,
+}
+
+/// Resolve every insertion to a validated byte offset in `html`.
+fn resolve_offsets(html: &str, insertions: &[Insertion<'_>]) -> Result<Vec<usize>, RewriteError> {
+    let mut ids: Vec<&str> = insertions.iter().filter_map(|i| target_id(i.at)).collect();
+    ids.sort_unstable();
+    ids.dedup();
+    let mut elements = alloc::vec![ElementSpans::default(); ids.len()];
+
+    let mut raw_offsets: Vec<usize> = insertions
+        .iter()
+        .filter_map(|insertion| match insertion.at {
+            InsertAt::Offset(offset) => Some(offset),
+            _ => None,
+        })
+        .collect();
+    if let Some(&offset) = raw_offsets.iter().find(|&&offset| offset > html.len()) {
+        return Err(RewriteError::OffsetOutOfBounds {
+            offset,
+            len: html.len(),
+        });
+    }
+    raw_offsets.sort_unstable();
+    let mut pending = raw_offsets.iter().copied().peekable();
+
+    let mut reader = Reader::from_str(html);
+    reader.config_mut().trim_text(false);
+    reader.config_mut().check_end_names = false;
+    // Open elements, with the index of the id slot each one fills.
+    let mut stack: Vec<Option<usize>> = Vec::with_capacity(0);
+
+    loop {
+        let start = reader.buffer_position() as usize;
+        let event = reader
+            .read_event()
+            .map_err(|err| RewriteError::Parse(err.to_string()))?;
+        let end = reader.buffer_position() as usize;
+        let is_text = matches!(event, Event::Text(_));
+        while let Some(offset) = pending.next_if(|&offset| offset < end) {
+            let between_tokens = offset == start;
+            if !(between_tokens || (is_text && html.is_char_boundary(offset))) {
+                return Err(RewriteError::OffsetInsideToken { offset });
+            }
+        }
+
+        match event {
+            Event::Start(e) => {
+                let slot = element_slot(&e, &ids, &elements);
+                if let Some(slot) = slot {
+                    elements[slot].start_tag = Some((start, end));
+                }
+                stack.push(slot);
+            }
+            Event::Empty(e) => {
+                if let Some(slot) = element_slot(&e, &ids, &elements) {
+                    elements[slot].start_tag = Some((start, end));
+                    elements[slot].end_tag = Some((end, end));
+                    elements[slot].empty = true;
+                }
+            }
+            Event::End(_) => {
+                if let Some(Some(slot)) = stack.pop() {
+                    elements[slot].end_tag = Some((start, end));
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    insertions
+        .iter()
+        .map(|insertion| match (insertion.at, target_id(insertion.at)) {
+            (InsertAt::Offset(offset), _) => Ok(offset),
+            (at, id) => {
+                let id = id.unwrap_or_default();
+                let spans = ids
+                    .binary_search(&id)
+                    .map(|slot| elements[slot])
+                    .unwrap_or_default();
+                element_offset(at, id, &spans)
+            }
+        })
+        .collect()
+}
+
+/// Element `id` an insertion point refers to, if any.
+fn target_id(at: InsertAt<'_>) -> Option<&str> {
+    match at {
+        InsertAt::Offset(_) => None,
+        InsertAt::BeforeElement(id)
+        | InsertAt::ContentStart(id)
+        | InsertAt::ContentEnd(id)
+        | InsertAt::AfterElement(id) => Some(id),
+    }
+}
+
+/// Id slot for this element, unless it has none or an earlier element
+/// already claimed the id.
+fn element_slot(
+    e: &quick_xml::events::BytesStart<'_>,
+    ids: &[&str],
+    elements: &[ElementSpans],
+) -> Option<usize> {
+    let attr = e.try_get_attribute("id").ok().flatten()?;
+    let value = attr.unescape_value().ok()?;
+    ids.binary_search(&value.as_ref())
+        .ok()
+        .filter(|&slot| elements[slot].start_tag.is_none())
+}
+
+/// Offset for an element-relative insertion point.
+fn element_offset(at: InsertAt<'_>, id: &str, spans: &ElementSpans) -> Result<usize, RewriteError> {
+    let (Some(start_tag), Some(end_tag)) = (spans.start_tag, spans.end_tag) else {
+        return Err(RewriteError::ElementNotFound(id.to_string()));
+    };
+    if spans.empty && matches!(at, InsertAt::ContentStart(_) | InsertAt::ContentEnd(_)) {
+        return Err(RewriteError::EmptyElement(id.to_string()));
+    }
+    Ok(match at {
+        InsertAt::BeforeElement(_) => start_tag.0,
+        InsertAt::ContentStart(_) => start_tag.1,
+        InsertAt::ContentEnd(_) => end_tag.0,
+        _ => end_tag.1,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CHAPTER: &str = "<?xml version=\"1.0\"?>\n<!DOCTYPE html>\n<html xmlns=\"http://www.w3.org/1999/xhtml\">\n  <body>\n    <!-- note -->\n    <p id='a'  class=\"x\">Caf\u{e9} &amp;  co<br/><img id=\"pic\" src='p.png' /></p>\n    <p id=\"b\"><![CDATA[raw < text]]></p>\n  </body>\n</html>\n";
+
+    fn rewrite(insertions: &[Insertion<'_>]) -> Result<String, RewriteError> {
+        let mut out = Vec::with_capacity(0);
+        let written = rewrite_html(CHAPTER, insertions, &mut out)?;
+        assert_eq!(written, out.len());
+        Ok(String::from_utf8(out).unwrap())
+    }
+
+    fn offset_of(needle: &str) -> usize {
+        CHAPTER.find(needle).unwrap()
+    }
+
+    #[test]
+    fn no_insertions_copies_bytes_verbatim() {
+        assert_eq!(rewrite(&[]).unwrap(), CHAPTER);
+    }
+
+    #[test]
+    fn inserts_at_element_boundaries_and_text_offsets() {
+        let word = offset_of("&amp;") - 1;
+        let out = rewrite(&[
+            Insertion::new(InsertAt::AfterElement("a"), "[after]"),
+            Insertion::new(InsertAt::ContentEnd("a"), "[end]"),
+            Insertion::new(InsertAt::Offset(word), "</mark>"),
+            Insertion::new(InsertAt::ContentStart("a"), "<mark>"),
+            Insertion::new(InsertAt::BeforeElement("a"), "[before]"),
+            Insertion::new(InsertAt::BeforeElement("pic"), "[pic]"),
+            Insertion::new(InsertAt::AfterElement("pic"), "[/pic]"),
+        ])
+        .unwrap();
+
+        let expected = CHAPTER
+            .replace("<p id='a'", "[before]<p id='a'")
+            .replace("\"x\">Caf\u{e9}", "\"x\"><mark>Caf\u{e9}</mark>")
+            // `[end]` and `[/pic]` land on the same offset: slice order wins.
+            .replace(
+                "<img id=\"pic\" src='p.png' /></p>",
+                "[pic]<img id=\"pic\" src='p.png' />[end][/pic]</p>[after]",
+            );
+        assert_eq!(out, expected);
+    }
+
+    #[test]
+    fn same_offset_insertions_keep_slice_order() {
+        let out = rewrite(&[
+            Insertion::new(InsertAt::ContentEnd("b"), "1"),
+            Insertion::new(InsertAt::Offset(offset_of("</p>\n  </body>")), "2"),
+            Insertion::new(InsertAt::ContentEnd("b"), "3"),
+        ])
+        .unwrap();
+        assert!(out.contains("]]>123</p>\n  </body>"));
+    }
+
+    #[test]
+    fn rejects_offsets_inside_tokens() {
+        let inside = [
+            offset_of("id='a'"),
+            offset_of("amp;"),
+            offset_of("note"),
+            offset_of("raw <"),
+            offset_of("\u{e9}") + 1,
+        ];
+        for offset in inside {
+            assert_eq!(
+                rewrite(&[Insertion::new(InsertAt::Offset(offset), "x")]),
+                Err(RewriteError::OffsetInsideToken { offset }),
+                "offset {}",
+                offset
+            );
+        }
+        assert_eq!(
+            rewrite(&[Insertion::new(InsertAt::Offset(CHAPTER.len() + 1), "x")]),
+            Err(RewriteError::OffsetOutOfBounds {
+                offset: CHAPTER.len() + 1,
+                len: CHAPTER.len()
+            })
+        );
+        assert!(rewrite(&[Insertion::new(InsertAt::Offset(CHAPTER.len()), "x")]).is_ok());
+    }
+
+    #[test]
+    fn rejects_unknown_and_empty_element_targets_without_writing() {
+        let mut out = Vec::with_capacity(0);
+        let err = rewrite_html(
+            CHAPTER,
+            &[
+                Insertion::new(InsertAt::Offset(0), "x"),
+                Insertion::new(InsertAt::ContentStart("missing"), "y"),
+            ],
+            &mut out,
+        )
+        .unwrap_err();
+        assert_eq!(err, RewriteError::ElementNotFound("missing".to_string()));
+        assert!(out.is_empty());
+
+        assert_eq!(
+            rewrite(&[Insertion::new(InsertAt::ContentStart("pic"), "y")]),
+            Err(RewriteError::EmptyElement("pic".to_string()))
+        );
+    }
+}
@@ -0,0 +1,279 @@
+//! Book-wide sequential search that can be suspended between calls.
+//!
+//! [`SearchSession`] walks the spine from a starting position with
+//! [`EpubBook::find_in_chapter`](crate::EpubBook::find_in_chapter)'s
+//! matcher, one budgeted [`SearchSession::step`] at a time, so a UI can
+//! show hits as they arrive and keep its event loop responsive. The
+//! session's [`SearchCursor`] is plain data: persist it and hand it to
+//! [`SearchSession::resume`] to continue later.
+
+use std::time::{Duration, Instant};
+
+use crate::book::{is_markup_media_type, EpubBook, FindLimits, FindOptions, TextHit};
+use crate::error::EpubError;
+use crate::storage::RandomAccess;
+use crate::ReadingPosition;
+
+/// Where a [`SearchSession`] continues.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default, rename_all = "snake_case"))]
+pub struct SearchCursor {
+    /// Spine index of the chapter searched next.
+    pub chapter_index: usize,
+    /// Character offset in that chapter, counted like
+    /// [`ReadingPosition::fallback_offset`]; matches before it were
+    /// already reported.
+    pub offset: usize,
+    /// Hits reported so far.
+    pub hits: usize,
+    /// The end of the spine or [`FindOptions::max_hits`] was reached.
+    pub finished: bool,
+}
+
+/// Work allowed per [`SearchSession::step`].
+///
+/// A step always scans some text before checking the budget, so every call
+/// makes progress.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SearchBudget {
+    /// Wall-clock allowance; `None` disables it.
+    pub max_duration: Option<Duration>,
+    /// Decompressed chapter bytes to scan.
+    pub max_bytes: usize,
+    /// Hits to report before suspending.
+    pub max_hits: usize,
+}
+
+impl Default for SearchBudget {
+    fn default() -> Self {
+        Self {
+            max_duration: Some(Duration::from_millis(50)),
+            max_bytes: 256 * 1024,
+            max_hits: 20,
+        }
+    }
+}
+
+/// What one [`SearchSession::step`] did.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SearchStep {
+    /// Hits reported during the step.
+    pub hits: usize,
+    /// Decompressed chapter bytes scanned, not counting replayed text.
+    pub bytes: usize,
+    /// The search is complete; further steps report nothing.
+    pub finished: bool,
+}
+
+/// Resumable search for one needle across the spine.
+///
+/// Chapters are searched in spine order from the start position to the end
+/// of the book; non-XHTML spine items are skipped. Resuming inside a
+/// chapter re-tokenizes it up to the cursor; that replay is not charged to
+/// the [`SearchBudget`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SearchSession {
+    needle: String,
+    options: FindOptions,
+    cursor: SearchCursor,
+}
+
+impl SearchSession {
+    /// Search for `needle` starting at `from`.
+    ///
+    /// Matches in `from`'s chapter before its `fallback_offset` are not
+    /// reported. [`FindOptions::max_hits`] caps the whole session.
+    pub fn new(needle: &str, options: FindOptions, from: &ReadingPosition) -> Self {
+        Self::resume(
+            needle,
+            options,
+            SearchCursor {
+                chapter_index: from.chapter_index,
+                offset: from.fallback_offset,
+                ..SearchCursor::default()
+            },
+        )
+    }
+
+    /// Continue a search from a saved cursor.
+    pub fn resume(needle: &str, options: FindOptions, cursor: SearchCursor) -> Self {
+        Self {
+            needle: needle.to_string(),
+            options,
+            cursor,
+        }
+    }
+
+    /// Current cursor, for persistence.
+    pub fn cursor(&self) -> &SearchCursor {
+        &self.cursor
+    }
+
+    /// Whether the search is complete.
+    pub fn is_finished(&self) -> bool {
+        self.cursor.finished
+    }
+
+    /// Search until `budget` is spent or the search finishes, calling
+    /// `on_hit` for each match in reading order.
+    pub fn step<R, F>(
+        &mut self,
+        book: &mut EpubBook<R>,
+        budget: SearchBudget,
+        mut on_hit: F,
+    ) -> Result<SearchStep, EpubError>
+    where
+        R: RandomAccess,
+        F: FnMut(TextHit) -> Result<(), EpubError>,
+    {
+        let deadline = budget
+            .max_duration
+            .map(|duration| Instant::now() + duration);
+        let mut step = SearchStep::default();
+        if self.needle.is_empty() {
+            self.cursor.finished = true;
+        }
+        while !self.cursor.finished {
+            if self.cursor.chapter_index >= book.chapter_count()
+                || self.cursor.hits >= self.options.max_hits
+            {
+                self.cursor.finished = true;
+                break;
+            }
+            let expired = deadline.is_some_and(|deadline| Instant::now() >= deadline);
+            if step.hits >= budget.max_hits
+                || (step.bytes > 0 && (step.bytes >= budget.max_bytes || expired))
+            {
+                break;
+            }
+            let index = self.cursor.chapter_index;
+            let searchable = book
+                .resolve_displayable_chapter(index)
+                .is_ok_and(|chapter| is_markup_media_type(&chapter.media_type));
+            if !searchable {
+                self.next_chapter();
+                continue;
+            }
+
+            let options = FindOptions {
+                max_hits: self.options.max_hits - self.cursor.hits,
+                ..self.options
+            };
+            let limits = FindLimits {
+                deadline,
+                max_bytes: budget.max_bytes.saturating_sub(step.bytes).max(1),
+            };
+            let mut step_hits = step.hits;
+            let outcome = book.find_in_chapter_from(
+                index,
+                &self.needle,
+                options,
+                self.cursor.offset,
+                limits,
+                |hit| {
+                    on_hit(hit)?;
+                    step_hits += 1;
+                    Ok(step_hits < budget.max_hits)
+                },
+            )?;
+            step.hits += outcome.hits;
+            step.bytes += outcome.bytes;
+            self.cursor.hits += outcome.hits;
+            match outcome.resume_offset {
+                Some(offset) => self.cursor.offset = offset,
+                None => self.next_chapter(),
+            }
+        }
+        step.finished = self.cursor.finished;
+        Ok(step)
+    }
+
+    fn next_chapter(&mut self) {
+        self.cursor.chapter_index += 1;
+        self.cursor.offset = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn open_fixture() -> EpubBook<std::fs::File> {
+        EpubBook::open(
+            "tests/fixtures/Fundamental-Accessibility-Tests-Basic-Functionality-v2.0.0.epub",
+        )
+        .unwrap()
+    }
+
+    fn all_hits(book: &mut EpubBook<std::fs::File>, needle: &str) -> Vec<(usize, usize)> {
+        let mut hits = Vec::with_capacity(0);
+        for index in 0..book.chapter_count() {
+            book.find_in_chapter(index, needle, FindOptions::default(), |hit| {
+                hits.push((index, hit.offset));
+                Ok(())
+            })
+            .unwrap();
+        }
+        hits
+    }
+
+    #[test]
+    fn tiny_budgets_resume_to_the_same_hits() {
+        let mut book = open_fixture();
+        let expected = all_hits(&mut book, "test");
+        assert!(expected.len() > 2);
+
+        let mut session =
+            SearchSession::new("test", FindOptions::default(), &ReadingPosition::default());
+        let budget = SearchBudget {
+            max_duration: None,
+            max_bytes: 1,
+            max_hits: 1,
+        };
+        let mut hits = Vec::with_capacity(0);
+        let mut steps = 0;
+        while !session.is_finished() {
+            // Round-trip the cursor between steps, as a suspended UI would.
+            let cursor = session.cursor().clone();
+            session = SearchSession::resume("test", FindOptions::default(), cursor);
+            let step = session
+                .step(&mut book, budget, |hit| {
+                    hits.push((hit.position.chapter_index, hit.offset));
+                    Ok(())
+                })
+                .unwrap();
+            assert!(step.hits <= 1);
+            steps += 1;
+            assert!(steps < 10_000, "search should make progress");
+        }
+        assert_eq!(hits, expected);
+        assert_eq!(session.cursor().hits, expected.len());
+    }
+
+    #[test]
+    fn session_starts_at_position_and_honors_total_cap() {
+        let mut book = open_fixture();
+        let expected = all_hits(&mut book, "test");
+        let (chapter_index, offset) = expected[1];
+        let from = ReadingPosition {
+            chapter_index,
+            fallback_offset: offset,
+            ..ReadingPosition::default()
+        };
+        let options = FindOptions {
+            max_hits: 2,
+            ..FindOptions::default()
+        };
+        let mut session = SearchSession::new("test", options, &from);
+        let mut hits = Vec::with_capacity(0);
+        let step = session
+            .step(&mut book, SearchBudget::default(), |hit| {
+                hits.push((hit.position.chapter_index, hit.offset));
+                Ok(())
+            })
+            .unwrap();
+        assert!(step.finished);
+        assert_eq!(hits, expected[1..3]);
+    }
+}
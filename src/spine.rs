@@ -5,6 +5,7 @@
 
 extern crate alloc;
 
+use alloc::collections::BTreeMap;
 use alloc::format;
 use alloc::string::{String, ToString};
 use alloc::vec::Vec;
@@ -14,10 +15,11 @@ use quick_xml::reader::Reader;
 use crate::error::EpubError;
 
 /// Maximum number of spine items (fixed-size constraint)
-const MAX_SPINE_ITEMS: usize = 256;
+pub(crate) const MAX_SPINE_ITEMS: usize = 256;
 
 /// A single item in the EPUB spine (chapter reference)
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SpineItem {
     /// Manifest item this spine entry references
     pub idref: String,
@@ -32,7 +34,7 @@ pub struct SpineItem {
 /// Spine represents the reading order of an EPUB
 ///
 /// Tracks the ordered list of chapter IDs and provides navigation.
-#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
 pub struct Spine {
     /// Ordered spine entries
     items: Vec<SpineItem>,
@@ -72,6 +74,27 @@ impl Spine {
         &self.items
     }
 
+    /// Keep only the last position of each idref listed more than once.
+    ///
+    /// Opening a book resolves duplicate itemrefs this way; see
+    /// [`OpfConflict::DuplicateSpineIdref`](crate::metadata::OpfConflict::DuplicateSpineIdref).
+    pub fn resolve_duplicate_idrefs(&mut self) {
+        let mut last: BTreeMap<&str, usize> = BTreeMap::new();
+        for (index, item) in self.items.iter().enumerate() {
+            last.insert(item.idref.as_str(), index);
+        }
+        if last.len() == self.items.len() {
+            return;
+        }
+        let mut keep = alloc::vec![false; self.items.len()];
+        for index in last.into_values() {
+            keep[index] = true;
+        }
+        let mut keep = keep.into_iter();
+        self.items.retain(|_| keep.next().unwrap_or(false));
+        self.current = 0;
+    }
+
     /// Get optional TOC item id from `<spine toc=\"...\">` (EPUB 2.0).
     pub fn toc_id(&self) -> Option<&str> {
         self.toc_id.as_deref()
@@ -188,7 +211,10 @@ impl Spine {
 
 /// Parse spine from OPF content
 ///
-/// Extracts the ordered list of itemrefs from the spine element.
+/// Extracts the ordered list of itemrefs from the spine element, including
+/// repeated idrefs (see [`Spine::resolve_duplicate_idrefs`]). The last
+/// non-empty `toc` attribute wins; see
+/// [`find_opf_conflicts`](crate::metadata::find_opf_conflicts).
 pub fn parse_spine(content: &[u8]) -> Result<Spine, EpubError> {
     let mut reader = Reader::from_reader(content);
     reader.config_mut().trim_text(true);
@@ -521,6 +547,20 @@ mod tests {
         assert_eq!(spine.position(), 0);
     }
 
+    #[test]
+    fn test_resolve_duplicate_idrefs_keeps_last_position() {
+        let mut spine = Spine::from_idrefs(alloc::vec![
+            "chapter1".to_string(),
+            "chapter1".to_string(),
+            "chapter2".to_string(),
+            "chapter1".to_string(),
+        ]);
+        spine.resolve_duplicate_idrefs();
+        assert_eq!(spine.chapter_ids(), alloc::vec!["chapter2", "chapter1"]);
+        assert!(spine.go_to_id("chapter1"));
+        assert_eq!(spine.position(), 1);
+    }
+
     #[test]
     fn test_from_idrefs_constructor() {
         let idrefs = alloc::vec![
@@ -0,0 +1,565 @@
+//! Virtual chapters for books that keep their whole text in one spine item.
+//!
+//! Converted books sometimes ship a single multi-megabyte XHTML file, which
+//! defeats the per-chapter memory bounds of everything downstream.
+//! [`plan_segments`] finds split points at heading starts, or at the next
+//! block element once a segment grows past a byte threshold, and
+//! [`ChapterSegment::extract`] rebuilds each piece as a standalone document:
+//! the original `<head>`, the ancestors open at the split point reopened,
+//! the segment content, and matching end tags.
+//! [`EpubBook::split_chapters`](crate::EpubBook::split_chapters) exposes the
+//! pieces as ordinary [`ChapterRef`](crate::ChapterRef)s.
+
+use core::ops::Range;
+use std::io::{self, Write};
+
+use quick_xml::events::Event;
+use quick_xml::reader::Reader;
+
+use crate::error::EpubError;
+use crate::tokenizer::BoundaryScan;
+
+/// When and where [`plan_segments`] splits a chapter.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ChapterSplitOptions {
+    /// Spine items of at most this many bytes are left whole.
+    pub min_chapter_bytes: usize,
+    /// Segment size after which the next block element starts a new
+    /// segment; `usize::MAX` splits at headings only.
+    pub max_segment_bytes: usize,
+    /// Headings `h1` through `h{max_heading_level}` start a new segment;
+    /// `0` splits by size only.
+    pub max_heading_level: u8,
+}
+
+impl Default for ChapterSplitOptions {
+    fn default() -> Self {
+        Self {
+            min_chapter_bytes: 512 * 1024,
+            max_segment_bytes: 256 * 1024,
+            max_heading_level: 2,
+        }
+    }
+}
+
+/// One piece of a split spine item.
+///
+/// Byte offsets refer to the item's source document, so a segment stays
+/// valid only for the exact bytes it was planned from.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ChapterSegment {
+    /// 0-based position among the item's segments.
+    pub index: usize,
+    /// Number of segments the item was split into.
+    pub count: usize,
+    content: (usize, usize),
+    body: (usize, usize),
+    /// Start tags of the ancestors open where the segment begins.
+    reopen: Vec<(usize, usize)>,
+    /// Start tags of the ancestors still open where the segment ends.
+    close: Vec<(usize, usize)>,
+}
+
+impl ChapterSegment {
+    /// Byte range of the segment's own content in the source document.
+    pub fn byte_range(&self) -> Range<usize> {
+        self.content.0..self.content.1
+    }
+
+    /// Rebuild the segment as a standalone document from the item's full
+    /// source `html`.
+    pub fn extract(&self, html: &[u8]) -> Result<Vec<u8>, EpubError> {
+        self.assemble(|start, end| html.get(start..end.min(html.len())))
+    }
+
+    /// Like [`Self::extract`], from the bytes kept by a [`RangeCapture`].
+    pub(crate) fn extract_captured(&self, capture: &RangeCapture) -> Result<Vec<u8>, EpubError> {
+        self.assemble(|start, end| capture.get(start, end))
+    }
+
+    /// Source ranges [`Self::extract`] reads; the last one runs to the end
+    /// of the document.
+    pub(crate) fn source_ranges(&self) -> Vec<(usize, usize)> {
+        let mut ranges = Vec::with_capacity(self.reopen.len() + self.close.len() + 3);
+        ranges.push((0, self.body.0));
+        ranges.extend_from_slice(&self.reopen);
+        ranges.extend_from_slice(&self.close);
+        ranges.push(self.content);
+        ranges.push((self.body.1, usize::MAX));
+        ranges
+    }
+
+    fn assemble<'a>(
+        &self,
+        slice: impl Fn(usize, usize) -> Option<&'a [u8]>,
+    ) -> Result<Vec<u8>, EpubError> {
+        let missing = || EpubError::InvalidEpub("chapter segment exceeds its source".into());
+        let head = slice(0, self.body.0).ok_or_else(missing)?;
+        let content = slice(self.content.0, self.content.1).ok_or_else(missing)?;
+        let tail = slice(self.body.1, usize::MAX).ok_or_else(missing)?;
+        let mut out = Vec::with_capacity(head.len() + content.len() + tail.len());
+        out.extend_from_slice(head);
+        for &(start, end) in &self.reopen {
+            out.extend_from_slice(slice(start, end).ok_or_else(missing)?);
+        }
+        out.extend_from_slice(content);
+        for &(start, end) in self.close.iter().rev() {
+            let tag = slice(start, end).ok_or_else(missing)?;
+            out.extend_from_slice(b"</");
+            out.extend_from_slice(tag_name(tag));
+            out.push(b'>');
+        }
+        out.extend_from_slice(tail);
+        Ok(out)
+    }
+}
+
+/// Element name of a raw start tag such as `<div class="x">`.
+fn tag_name(tag: &[u8]) -> &[u8] {
+    let name = tag.strip_prefix(b"<").unwrap_or(tag);
+    let end = name
+        .iter()
+        .position(|b| b.is_ascii_whitespace() || matches!(b, b'/' | b'>'))
+        .unwrap_or(name.len());
+    &name[..end]
+}
+
+fn is_block(name: &str) -> bool {
+    matches!(
+        name,
+        "p" | "div"
+            | "section"
+            | "article"
+            | "blockquote"
+            | "pre"
+            | "ul"
+            | "ol"
+            | "table"
+            | "figure"
+            | "hr"
+            | "h1"
+            | "h2"
+            | "h3"
+            | "h4"
+            | "h5"
+            | "h6"
+    )
+}
+
+fn heading_level(name: &str) -> Option<u8> {
+    match name.as_bytes() {
+        [b'h', level @ b'1'..=b'6'] => Some(level - b'0'),
+        _ => None,
+    }
+}
+
+/// A planned split: where it falls, what is open there, and the heading
+/// text that starts it.
+struct Boundary {
+    offset: usize,
+    open: Vec<(usize, usize)>,
+    heading: Option<String>,
+}
+
+/// Plan the segments of an XHTML chapter.
+///
+/// Returns each segment with the text of the heading that starts it, or an
+/// empty list when the chapter has no `<body>` or needs no split. Splits
+/// only fall where the current segment already holds text, so a heading
+/// stays with the content that follows it.
+pub fn plan_segments(
+    html: &[u8],
+    options: &ChapterSplitOptions,
+) -> Result<Vec<(ChapterSegment, Option<String>)>, EpubError> {
+    let mut planner = SegmentPlanner::new(*options);
+    planner.feed(html)?;
+    planner.finish()
+}
+
+const UTF8_BOM: &[u8] = b"\xEF\xBB\xBF";
+
+/// Incremental [`plan_segments`]: the chapter is written in chunks, and
+/// only the unfinished markup construct at the end of a chunk is buffered.
+pub(crate) struct SegmentPlanner {
+    options: ChapterSplitOptions,
+    pending: Vec<u8>,
+    scan: BoundaryScan,
+    /// Source offset of `pending[0]`.
+    consumed: usize,
+    xml_buf: Vec<u8>,
+    body_start: Option<usize>,
+    body_end: Option<usize>,
+    stack: Vec<(usize, usize)>,
+    boundaries: Vec<Boundary>,
+    segment_start: usize,
+    has_text: bool,
+    /// Stack depth of the heading whose text is being captured.
+    capturing: Option<usize>,
+    /// First parse error, kept so a failed write can report it.
+    error: Option<EpubError>,
+}
+
+impl SegmentPlanner {
+    pub(crate) fn new(options: ChapterSplitOptions) -> Self {
+        Self {
+            options,
+            pending: Vec::with_capacity(0),
+            scan: BoundaryScan::default(),
+            consumed: 0,
+            xml_buf: Vec::with_capacity(0),
+            body_start: None,
+            body_end: None,
+            stack: Vec::with_capacity(0),
+            boundaries: Vec::with_capacity(0),
+            segment_start: 0,
+            has_text: false,
+            capturing: None,
+            error: None,
+        }
+    }
+
+    /// Parse the complete markup in `chunk` and anything buffered before it.
+    pub(crate) fn feed(&mut self, chunk: &[u8]) -> Result<(), EpubError> {
+        if self.body_end.is_some() {
+            return Ok(());
+        }
+        self.pending.extend_from_slice(chunk);
+        self.scan.advance(&self.pending);
+        let ready = self.scan.safe;
+        if ready > 0 {
+            self.parse_pending(ready)?;
+            self.pending.drain(..ready);
+            self.scan.consumed(ready);
+            self.consumed += ready;
+        }
+        Ok(())
+    }
+
+    /// The parse error that stopped a write, if any.
+    pub(crate) fn take_error(&mut self) -> Option<EpubError> {
+        self.error.take()
+    }
+
+    /// Parse what is left and lay out the segments.
+    pub(crate) fn finish(mut self) -> Result<Vec<(ChapterSegment, Option<String>)>, EpubError> {
+        if let Some(err) = self.error.take() {
+            return Err(err);
+        }
+        if self.body_end.is_none() && !self.pending.is_empty() {
+            self.parse_pending(self.pending.len())?;
+        }
+        let (Some(body_start), Some(body_end)) = (self.body_start, self.body_end) else {
+            return Ok(Vec::with_capacity(0));
+        };
+        if self.boundaries.is_empty() {
+            return Ok(Vec::with_capacity(0));
+        }
+
+        let count = self.boundaries.len() + 1;
+        let mut segments = Vec::with_capacity(count);
+        let mut start = body_start;
+        let mut reopen = Vec::with_capacity(0);
+        let mut heading = None;
+        for (index, boundary) in self
+            .boundaries
+            .into_iter()
+            .map(Some)
+            .chain([None])
+            .enumerate()
+        {
+            let (end, close, next_heading) = match boundary {
+                Some(boundary) => (boundary.offset, boundary.open, boundary.heading),
+                None => (body_end, Vec::with_capacity(0), None),
+            };
+            let segment = ChapterSegment {
+                index,
+                count,
+                content: (start, end),
+                body: (body_start, body_end),
+                reopen: core::mem::replace(&mut reopen, close.clone()),
+                close,
+            };
+            segments.push((segment, heading.take()));
+            start = end;
+            heading = next_heading;
+        }
+        Ok(segments)
+    }
+
+    fn parse_pending(&mut self, len: usize) -> Result<(), EpubError> {
+        let mut reader = Reader::from_reader(&self.pending[..len]);
+        reader.config_mut().trim_text(false);
+        // Each chunk gets a fresh reader; nesting is tracked in `stack`.
+        reader.config_mut().check_end_names = false;
+        reader.config_mut().allow_unmatched_ends = true;
+        // The reader skips a leading byte order mark without counting it.
+        let bom = if self.consumed == 0 && self.pending[..len].starts_with(UTF8_BOM) {
+            UTF8_BOM.len()
+        } else {
+            0
+        };
+        let base = self.consumed + bom;
+
+        loop {
+            self.xml_buf.clear();
+            let event_start = base + reader.buffer_position() as usize;
+            let event = reader
+                .read_event_into(&mut self.xml_buf)
+                .map_err(|err| EpubError::Parse(format!("XML parse error: {:?}", err)))?;
+            let event_end = base + reader.buffer_position() as usize;
+            match event {
+                Event::Start(ref e) | Event::Empty(ref e) if self.body_start.is_some() => {
+                    let name =
+                        String::from_utf8_lossy(e.local_name().as_ref()).to_ascii_lowercase();
+                    let level = heading_level(&name);
+                    let at_heading =
+                        level.is_some_and(|level| level <= self.options.max_heading_level);
+                    let oversized = is_block(&name)
+                        && event_start.saturating_sub(self.segment_start)
+                            >= self.options.max_segment_bytes;
+                    if self.has_text && (at_heading || oversized) {
+                        self.boundaries.push(Boundary {
+                            offset: event_start,
+                            open: self.stack.clone(),
+                            heading: None,
+                        });
+                        self.segment_start = event_start;
+                        self.has_text = false;
+                        if at_heading {
+                            self.capturing = Some(self.stack.len());
+                        }
+                    }
+                    if matches!(event, Event::Start(_)) {
+                        self.stack.push((event_start, event_end));
+                    }
+                }
+                Event::Start(e) if e.local_name().as_ref().eq_ignore_ascii_case(b"body") => {
+                    self.body_start = Some(event_end);
+                    self.segment_start = event_end;
+                }
+                Event::Text(e) if self.body_start.is_some() => {
+                    let text = e
+                        .decode()
+                        .map_err(|err| EpubError::Parse(format!("Decode error: {:?}", err)))?;
+                    self.has_text |= !text.trim().is_empty();
+                    if self.capturing.is_some() {
+                        push_heading_text(&mut self.boundaries, &text);
+                    }
+                }
+                Event::GeneralRef(e) if self.body_start.is_some() => {
+                    self.has_text = true;
+                    if self.capturing.is_some() {
+                        let name = e
+                            .decode()
+                            .map_err(|err| EpubError::Parse(format!("Decode error: {:?}", err)))?;
+                        let entity = format!("&{};", name);
+                        if let Ok(resolved) = quick_xml::escape::unescape(&entity) {
+                            push_heading_text(&mut self.boundaries, &resolved);
+                        }
+                    }
+                }
+                Event::End(_) if self.body_start.is_some() => {
+                    if self.stack.pop().is_none() {
+                        self.body_end = Some(event_start);
+                        return Ok(());
+                    }
+                    if self.capturing == Some(self.stack.len()) {
+                        self.capturing = None;
+                    }
+                }
+                Event::Eof => return Ok(()),
+                _ => {}
+            }
+        }
+    }
+}
+
+impl Write for SegmentPlanner {
+    fn write(&mut self, chunk: &[u8]) -> io::Result<usize> {
+        match self.feed(chunk) {
+            Ok(()) => Ok(chunk.len()),
+            Err(err) => {
+                self.error = Some(err);
+                Err(io::Error::other("chapter segment planning failed"))
+            }
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Append heading text to the latest boundary, collapsing whitespace.
+fn push_heading_text(boundaries: &mut [Boundary], text: &str) {
+    let Some(boundary) = boundaries.last_mut() else {
+        return;
+    };
+    let heading = boundary
+        .heading
+        .get_or_insert_with(|| String::with_capacity(text.len()));
+    for word in text.split_whitespace() {
+        if !heading.is_empty() {
+            heading.push(' ');
+        }
+        heading.push_str(word);
+    }
+}
+
+/// Writer that keeps only the bytes falling in a set of source ranges.
+///
+/// Lets a segment be read out of a large ZIP entry without holding the
+/// whole entry in memory.
+pub(crate) struct RangeCapture {
+    ranges: Vec<(usize, usize)>,
+    kept: Vec<Vec<u8>>,
+    position: usize,
+}
+
+impl RangeCapture {
+    pub(crate) fn new(ranges: Vec<(usize, usize)>) -> Self {
+        let kept = ranges.iter().map(|_| Vec::with_capacity(0)).collect();
+        Self {
+            ranges,
+            kept,
+            position: 0,
+        }
+    }
+
+    /// Bytes `start..end` of the stream, when one captured range holds them.
+    /// `end` is clamped to the bytes written.
+    fn get(&self, start: usize, end: usize) -> Option<&[u8]> {
+        let index = self
+            .ranges
+            .iter()
+            .position(|&(from, to)| from <= start && end.min(self.position) <= to)?;
+        let from = self.ranges[index].0;
+        self.kept[index].get(start - from..end.min(self.position).max(start) - from)
+    }
+}
+
+impl Write for RangeCapture {
+    fn write(&mut self, chunk: &[u8]) -> io::Result<usize> {
+        let chunk_start = self.position;
+        let chunk_end = chunk_start + chunk.len();
+        for (&(from, to), kept) in self.ranges.iter().zip(self.kept.iter_mut()) {
+            let start = from.max(chunk_start);
+            let end = to.min(chunk_end);
+            if start < end {
+                kept.extend_from_slice(&chunk[start - chunk_start..end - chunk_start]);
+            }
+        }
+        self.position = chunk_end;
+        Ok(chunk.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BOOK: &str = "<html><head><title>All</title></head><body>\
+        <section id=\"part\"><h1>One</h1><p>first</p>\
+        <h2>Two &amp; more</h2><p>second</p></section>\
+        <h1>Three</h1><p>third</p></body></html>";
+
+    fn options() -> ChapterSplitOptions {
+        ChapterSplitOptions {
+            min_chapter_bytes: 0,
+            max_segment_bytes: usize::MAX,
+            max_heading_level: 2,
+        }
+    }
+
+    #[test]
+    fn splits_at_headings_and_reopens_ancestors() {
+        let plan = plan_segments(BOOK.as_bytes(), &options()).unwrap();
+        let titles: Vec<_> = plan.iter().map(|(_, title)| title.as_deref()).collect();
+        assert_eq!(titles, vec![None, Some("Two & more"), Some("Three")]);
+
+        let docs: Vec<String> = plan
+            .iter()
+            .map(|(segment, _)| {
+                String::from_utf8(segment.extract(BOOK.as_bytes()).unwrap()).unwrap()
+            })
+            .collect();
+        assert_eq!(
+            docs[0],
+            "<html><head><title>All</title></head><body>\
+             <section id=\"part\"><h1>One</h1><p>first</p></section></body></html>"
+        );
+        assert_eq!(
+            docs[1],
+            "<html><head><title>All</title></head><body>\
+             <section id=\"part\"><h2>Two &amp; more</h2><p>second</p></section></body></html>"
+        );
+        assert_eq!(
+            docs[2],
+            "<html><head><title>All</title></head><body><h1>Three</h1><p>third</p></body></html>"
+        );
+        assert!(plan.iter().all(|(segment, _)| segment.count == 3));
+    }
+
+    #[test]
+    fn splits_oversized_runs_at_block_starts() {
+        let html = "<html><body><p>aaaa</p><p>bbbb</p><p>cccc</p></body></html>";
+        let plan = plan_segments(
+            html.as_bytes(),
+            &ChapterSplitOptions {
+                max_segment_bytes: 20,
+                max_heading_level: 0,
+                ..options()
+            },
+        )
+        .unwrap();
+        let ranges: Vec<_> = plan.iter().map(|(s, _)| &html[s.byte_range()]).collect();
+        assert_eq!(ranges, vec!["<p>aaaa</p><p>bbbb</p>", "<p>cccc</p>"]);
+        assert!(plan_segments(html.as_bytes(), &options())
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn offsets_count_a_leading_byte_order_mark() {
+        let html = format!("\u{feff}{}", BOOK);
+        let plan = plan_segments(html.as_bytes(), &options()).unwrap();
+        let doc = plan[2].0.extract(html.as_bytes()).unwrap();
+        assert_eq!(
+            doc,
+            "\u{feff}<html><head><title>All</title></head><body>\
+             <h1>Three</h1><p>third</p></body></html>"
+                .as_bytes()
+        );
+    }
+
+    #[test]
+    fn chunked_planning_matches_one_shot() {
+        let whole = plan_segments(BOOK.as_bytes(), &options()).unwrap();
+        for size in [1, 3, 7, 64] {
+            let mut planner = SegmentPlanner::new(options());
+            for chunk in BOOK.as_bytes().chunks(size) {
+                planner.write_all(chunk).unwrap();
+            }
+            assert_eq!(planner.finish().unwrap(), whole, "chunk size {size}");
+        }
+    }
+
+    #[test]
+    fn captured_ranges_rebuild_the_same_segment() {
+        let plan = plan_segments(BOOK.as_bytes(), &options()).unwrap();
+        let segment = &plan[1].0;
+        let mut capture = RangeCapture::new(segment.source_ranges());
+        for chunk in BOOK.as_bytes().chunks(7) {
+            capture.write_all(chunk).unwrap();
+        }
+        assert_eq!(
+            segment.extract_captured(&capture).unwrap(),
+            segment.extract(BOOK.as_bytes()).unwrap()
+        );
+    }
+}
@@ -0,0 +1,258 @@
+//! Positioned storage access for archive back-ends.
+//!
+//! [`RandomAccess`] is the minimal I/O contract the ZIP reader needs: the total
+//! size of the backing store and positioned reads. It has no `std` dependency,
+//! so flash translation layers, block devices, and memory-mapped regions can
+//! implement it directly without emulating a `Read + Seek` cursor.
+//!
+//! Implementations are provided for byte slices, `Vec<u8>`, and (with the
+//! `std` feature) `File`, `Cursor`, and `BufReader`. Any other `Read + Seek`
+//! type can be wrapped in [`ReadSeekAdapter`].
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+
+use crate::error::ZipError;
+
+/// Random-access byte storage backing an EPUB archive.
+///
+/// Reads are positioned and independent of each other; implementations may
+/// keep an internal cursor but callers never rely on it.
+pub trait RandomAccess {
+    /// Total size of the storage in bytes.
+    fn size(&mut self) -> Result<u64, ZipError>;
+
+    /// Read up to `buf.len()` bytes starting at `offset`.
+    ///
+    /// Returns the number of bytes read; `0` means `offset` is at or past the
+    /// end of the storage.
+    fn read_at(&mut self, offset: u64, buf: &mut [u8]) -> Result<usize, ZipError>;
+
+    /// Fill `buf` completely with bytes starting at `offset`.
+    ///
+    /// Returns [`ZipError::IoError`] if the storage ends before `buf` is full.
+    fn read_exact_at(&mut self, mut offset: u64, mut buf: &mut [u8]) -> Result<(), ZipError> {
+        while !buf.is_empty() {
+            let read = self.read_at(offset, buf)?;
+            if read == 0 {
+                return Err(ZipError::IoError);
+            }
+            offset = offset.checked_add(read as u64).ok_or(ZipError::IoError)?;
+            buf = &mut buf[read..];
+        }
+        Ok(())
+    }
+}
+
+impl<T: RandomAccess + ?Sized> RandomAccess for &mut T {
+    fn size(&mut self) -> Result<u64, ZipError> {
+        (**self).size()
+    }
+
+    fn read_at(&mut self, offset: u64, buf: &mut [u8]) -> Result<usize, ZipError> {
+        (**self).read_at(offset, buf)
+    }
+}
+
+impl RandomAccess for &[u8] {
+    fn size(&mut self) -> Result<u64, ZipError> {
+        Ok(self.len() as u64)
+    }
+
+    fn read_at(&mut self, offset: u64, buf: &mut [u8]) -> Result<usize, ZipError> {
+        Ok(copy_from_slice_at(self, offset, buf))
+    }
+}
+
+impl RandomAccess for Vec<u8> {
+    fn size(&mut self) -> Result<u64, ZipError> {
+        Ok(self.len() as u64)
+    }
+
+    fn read_at(&mut self, offset: u64, buf: &mut [u8]) -> Result<usize, ZipError> {
+        Ok(copy_from_slice_at(self, offset, buf))
+    }
+}
+
+fn copy_from_slice_at(data: &[u8], offset: u64, buf: &mut [u8]) -> usize {
+    let Ok(start) = usize::try_from(offset) else {
+        return 0;
+    };
+    let Some(available) = data.get(start..) else {
+        return 0;
+    };
+    let len = available.len().min(buf.len());
+    buf[..len].copy_from_slice(&available[..len]);
+    len
+}
+
+#[cfg(feature = "std")]
+mod std_impls {
+    use super::{copy_from_slice_at, RandomAccess};
+    use crate::error::ZipError;
+    use std::fs::File;
+    use std::io::{BufReader, Cursor, Read, Seek, SeekFrom};
+
+    /// Adapter exposing any `Read + Seek` source as [`RandomAccess`].
+    ///
+    /// Each positioned read seeks the inner reader before reading, so the
+    /// inner cursor position is unspecified between calls.
+    #[derive(Debug)]
+    pub struct ReadSeekAdapter<R> {
+        inner: R,
+    }
+
+    impl<R: Read + Seek> ReadSeekAdapter<R> {
+        /// Wrap a `Read + Seek` source.
+        pub fn new(inner: R) -> Self {
+            Self { inner }
+        }
+
+        /// Borrow the wrapped reader.
+        pub fn get_ref(&self) -> &R {
+            &self.inner
+        }
+
+        /// Mutably borrow the wrapped reader.
+        pub fn get_mut(&mut self) -> &mut R {
+            &mut self.inner
+        }
+
+        /// Unwrap the adapter, returning the inner reader.
+        pub fn into_inner(self) -> R {
+            self.inner
+        }
+    }
+
+    impl<R: Read + Seek> RandomAccess for ReadSeekAdapter<R> {
+        fn size(&mut self) -> Result<u64, ZipError> {
+            seek_size(&mut self.inner)
+        }
+
+        fn read_at(&mut self, offset: u64, buf: &mut [u8]) -> Result<usize, ZipError> {
+            seek_read_at(&mut self.inner, offset, buf)
+        }
+    }
+
+    impl RandomAccess for File {
+        fn size(&mut self) -> Result<u64, ZipError> {
+            self.metadata()
+                .map(|meta| meta.len())
+                .map_err(|_| ZipError::IoError)
+        }
+
+        fn read_at(&mut self, offset: u64, buf: &mut [u8]) -> Result<usize, ZipError> {
+            seek_read_at(self, offset, buf)
+        }
+    }
+
+    impl<R: Read + Seek> RandomAccess for BufReader<R> {
+        fn size(&mut self) -> Result<u64, ZipError> {
+            seek_size(self)
+        }
+
+        fn read_at(&mut self, offset: u64, buf: &mut [u8]) -> Result<usize, ZipError> {
+            seek_read_at(self, offset, buf)
+        }
+    }
+
+    impl<T: AsRef<[u8]>> RandomAccess for Cursor<T> {
+        fn size(&mut self) -> Result<u64, ZipError> {
+            Ok(self.get_ref().as_ref().len() as u64)
+        }
+
+        fn read_at(&mut self, offset: u64, buf: &mut [u8]) -> Result<usize, ZipError> {
+            Ok(copy_from_slice_at(self.get_ref().as_ref(), offset, buf))
+        }
+    }
+
+    fn seek_size<R: Seek>(reader: &mut R) -> Result<u64, ZipError> {
+        reader.seek(SeekFrom::End(0)).map_err(|_| ZipError::IoError)
+    }
+
+    fn seek_read_at<R: Read + Seek>(
+        reader: &mut R,
+        offset: u64,
+        buf: &mut [u8],
+    ) -> Result<usize, ZipError> {
+        reader
+            .seek(SeekFrom::Start(offset))
+            .map_err(|_| ZipError::IoError)?;
+        loop {
+            match reader.read(buf) {
+                Ok(read) => return Ok(read),
+                Err(err) if err.kind() == std::io::ErrorKind::Interrupted => continue,
+                Err(_) => return Err(ZipError::IoError),
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+pub use std_impls::ReadSeekAdapter;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Storage that serves at most `chunk` bytes per read, like a block device.
+    struct BlockStorage {
+        data: Vec<u8>,
+        chunk: usize,
+    }
+
+    impl RandomAccess for BlockStorage {
+        fn size(&mut self) -> Result<u64, ZipError> {
+            Ok(self.data.len() as u64)
+        }
+
+        fn read_at(&mut self, offset: u64, buf: &mut [u8]) -> Result<usize, ZipError> {
+            let len = buf.len().min(self.chunk);
+            Ok(copy_from_slice_at(&self.data, offset, &mut buf[..len]))
+        }
+    }
+
+    #[test]
+    fn slice_read_at_clamps_to_end() {
+        let mut data: &[u8] = b"abcdef";
+        let mut buf = [0u8; 4];
+        assert_eq!(data.size().unwrap(), 6);
+        assert_eq!(data.read_at(4, &mut buf).unwrap(), 2);
+        assert_eq!(&buf[..2], b"ef");
+        assert_eq!(data.read_at(6, &mut buf).unwrap(), 0);
+        assert_eq!(data.read_at(u64::MAX, &mut buf).unwrap(), 0);
+    }
+
+    #[test]
+    fn read_exact_at_stitches_short_reads() {
+        let mut storage = BlockStorage {
+            data: (0u8..32).collect(),
+            chunk: 3,
+        };
+        let mut buf = [0u8; 10];
+        storage.read_exact_at(5, &mut buf).unwrap();
+        assert_eq!(buf, [5, 6, 7, 8, 9, 10, 11, 12, 13, 14]);
+    }
+
+    #[test]
+    fn read_exact_at_fails_past_end() {
+        let mut data: &[u8] = b"abc";
+        let mut buf = [0u8; 4];
+        assert_eq!(data.read_exact_at(0, &mut buf), Err(ZipError::IoError));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn read_seek_adapter_matches_cursor() {
+        let bytes = b"0123456789".to_vec();
+        let mut adapter = ReadSeekAdapter::new(std::io::Cursor::new(bytes.clone()));
+        let mut cursor = std::io::Cursor::new(bytes);
+        let mut a = [0u8; 4];
+        let mut b = [0u8; 4];
+        adapter.read_exact_at(3, &mut a).unwrap();
+        cursor.read_exact_at(3, &mut b).unwrap();
+        assert_eq!(a, b);
+        assert_eq!(adapter.size().unwrap(), 10);
+    }
+}
@@ -2,6 +2,12 @@
 //!
 //! Provides truly streaming chapter processing that reads directly from ZIP
 //! without materializing the full chapter content.
+//!
+//! With the `layout` feature, [`ChapterPaginator`] paginates chapter bytes
+//! fed chunk-by-chunk without `std`, for hosts that do their own archive I/O.
+//!
+//! [`AdaptiveChunkSizer`] picks read sizes from measured throughput, between
+//! [`ChunkLimits::min_read_chunk`] and [`ChunkLimits::max_read_chunk`].
 
 extern crate alloc;
 
@@ -9,8 +15,14 @@ use alloc::string::{String, ToString};
 use alloc::vec::Vec;
 use core::cmp::min;
 
+#[cfg(feature = "layout")]
+use crate::layout::{LayoutEngine, Page};
 #[cfg(feature = "std")]
 use crate::render_prep::{RenderPrepError, RenderPrepOptions, StyledEventOrRun};
+#[cfg(feature = "layout")]
+use crate::tokenizer::{Token, TokenizeError, Tokenizer};
+#[cfg(feature = "std")]
+use std::time::Instant;
 
 /// Scratch buffer pool for streaming operations.
 ///
@@ -52,15 +64,201 @@ impl ScratchBuffers {
         self.xml_buf.clear();
         self.text_buf.clear();
     }
+
+    /// Capacities needed to process chapters under the given limits.
+    ///
+    /// `read_buf` holds one `max_read_chunk` read, `xml_buf` the largest
+    /// single markup event (a text node or an inline stylesheet), and
+    /// `text_buf` the accumulated text before a forced flush.
+    #[cfg(feature = "std")]
+    pub fn for_limits(
+        chunk: ChunkLimits,
+        tokenize: crate::tokenizer::TokenizeLimits,
+        style: crate::render_prep::StyleLimits,
+    ) -> RequiredSizes {
+        RequiredSizes {
+            read_buf: chunk.max_read_chunk,
+            xml_buf: tokenize.max_text_bytes.max(style.max_css_bytes),
+            text_buf: chunk.max_text_accumulation,
+        }
+    }
+
+    /// Start assembling buffers that must meet `required`.
+    pub fn builder(required: RequiredSizes) -> ScratchBuffersBuilder {
+        ScratchBuffersBuilder {
+            required,
+            read_buf: None,
+            xml_buf: None,
+            text_buf: None,
+        }
+    }
+
+    /// Check every buffer's capacity against `required`.
+    pub fn check(&self, required: &RequiredSizes) -> Result<(), ScratchSizeError> {
+        required.check(ScratchBuffer::Read, self.read_buf.capacity())?;
+        required.check(ScratchBuffer::Xml, self.xml_buf.capacity())?;
+        required.check(ScratchBuffer::Text, self.text_buf.capacity())
+    }
+}
+
+/// Minimum capacities for each [`ScratchBuffers`] buffer, in bytes.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct RequiredSizes {
+    /// Capacity of [`ScratchBuffers::read_buf`].
+    pub read_buf: usize,
+    /// Capacity of [`ScratchBuffers::xml_buf`].
+    pub xml_buf: usize,
+    /// Capacity of [`ScratchBuffers::text_buf`].
+    pub text_buf: usize,
+}
+
+impl RequiredSizes {
+    /// Sum of all buffer capacities.
+    pub fn total(&self) -> usize {
+        self.read_buf
+            .saturating_add(self.xml_buf)
+            .saturating_add(self.text_buf)
+    }
+
+    /// Required capacity of one buffer.
+    pub fn get(&self, buffer: ScratchBuffer) -> usize {
+        match buffer {
+            ScratchBuffer::Read => self.read_buf,
+            ScratchBuffer::Xml => self.xml_buf,
+            ScratchBuffer::Text => self.text_buf,
+        }
+    }
+
+    fn check(&self, buffer: ScratchBuffer, provided: usize) -> Result<(), ScratchSizeError> {
+        let required = self.get(buffer);
+        if provided < required {
+            return Err(ScratchSizeError {
+                buffer,
+                required,
+                provided,
+            });
+        }
+        Ok(())
+    }
+}
+
+/// One of the buffers in [`ScratchBuffers`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ScratchBuffer {
+    /// [`ScratchBuffers::read_buf`].
+    Read,
+    /// [`ScratchBuffers::xml_buf`].
+    Xml,
+    /// [`ScratchBuffers::text_buf`].
+    Text,
+}
+
+impl ScratchBuffer {
+    /// Field name of the buffer.
+    pub fn name(self) -> &'static str {
+        match self {
+            ScratchBuffer::Read => "read_buf",
+            ScratchBuffer::Xml => "xml_buf",
+            ScratchBuffer::Text => "text_buf",
+        }
+    }
+}
+
+/// A caller-provided scratch buffer is smaller than required.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ScratchSizeError {
+    /// The undersized buffer.
+    pub buffer: ScratchBuffer,
+    /// Required capacity in bytes.
+    pub required: usize,
+    /// Capacity provided.
+    pub provided: usize,
+}
+
+impl core::fmt::Display for ScratchSizeError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "scratch {} too small: required {} bytes, provided {}",
+            self.buffer.name(),
+            self.required,
+            self.provided
+        )
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ScratchSizeError {}
+
+/// Builder for [`ScratchBuffers`] from caller-provided storage.
+///
+/// Buffers not supplied are allocated at their required capacity by
+/// [`build`](Self::build); supplied buffers are cleared and kept as-is.
+#[derive(Debug)]
+pub struct ScratchBuffersBuilder {
+    required: RequiredSizes,
+    read_buf: Option<Vec<u8>>,
+    xml_buf: Option<Vec<u8>>,
+    text_buf: Option<String>,
+}
+
+impl ScratchBuffersBuilder {
+    /// Use `buf` as the read buffer.
+    pub fn with_read_buf(mut self, buf: Vec<u8>) -> Self {
+        self.read_buf = Some(buf);
+        self
+    }
+
+    /// Use `buf` as the XML event buffer.
+    pub fn with_xml_buf(mut self, buf: Vec<u8>) -> Self {
+        self.xml_buf = Some(buf);
+        self
+    }
+
+    /// Use `buf` as the text accumulation buffer.
+    pub fn with_text_buf(mut self, buf: String) -> Self {
+        self.text_buf = Some(buf);
+        self
+    }
+
+    /// Validate the supplied buffers and assemble them.
+    ///
+    /// # Errors
+    /// Returns [`ScratchSizeError`] naming the first buffer whose capacity
+    /// is below the requirement.
+    pub fn build(self) -> Result<ScratchBuffers, ScratchSizeError> {
+        let required = self.required;
+        let mut buffers = ScratchBuffers {
+            read_buf: self
+                .read_buf
+                .unwrap_or_else(|| Vec::with_capacity(required.read_buf)),
+            xml_buf: self
+                .xml_buf
+                .unwrap_or_else(|| Vec::with_capacity(required.xml_buf)),
+            text_buf: self
+                .text_buf
+                .unwrap_or_else(|| String::with_capacity(required.text_buf)),
+        };
+        buffers.check(&required)?;
+        buffers.clear();
+        Ok(buffers)
+    }
 }
 
 /// Chunking limits for incremental processing.
 ///
 /// Prevents single large allocations by breaking work into smaller chunks.
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct ChunkLimits {
     /// Maximum bytes to process in a single read operation.
     pub max_read_chunk: usize,
+    /// Smallest read adaptive chunk sizing may choose; set equal to
+    /// `max_read_chunk` to read fixed-size chunks.
+    pub min_read_chunk: usize,
     /// Maximum bytes for accumulated text before forcing a flush.
     pub max_text_accumulation: usize,
     /// Maximum number of events to process before yielding control.
@@ -73,6 +271,7 @@ impl Default for ChunkLimits {
     fn default() -> Self {
         Self {
             max_read_chunk: 16384,       // 16KB read chunks
+            min_read_chunk: 4096,        // 4KB on slow storage
             max_text_accumulation: 8192, // 8KB text buffer
             max_events_per_yield: 1000,  // Process 1000 events at a time
             max_stack_depth: 256,        // 256 levels of nesting
@@ -85,6 +284,7 @@ impl ChunkLimits {
     pub fn embedded() -> Self {
         Self {
             max_read_chunk: 4096,        // 4KB read chunks
+            min_read_chunk: 1024,        // 1KB on slow storage
             max_text_accumulation: 2048, // 2KB text buffer
             max_events_per_yield: 500,   // Process 500 events at a time
             max_stack_depth: 64,         // 64 levels of nesting
@@ -222,6 +422,7 @@ impl ChunkAllocator {
 
 /// Statistics for streaming operations.
 #[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct StreamingStats {
     /// Total bytes read from source.
     pub bytes_read: usize,
@@ -233,6 +434,345 @@ pub struct StreamingStats {
     pub chunks_processed: usize,
     /// Peak memory usage estimate.
     pub peak_memory_estimate: usize,
+    /// Size of the most recent read chunk.
+    pub last_chunk_size: usize,
+    /// Smallest read chunk chosen so far (`0` before the first read).
+    pub min_chunk_size: usize,
+    /// Largest read chunk chosen so far.
+    pub max_chunk_size: usize,
+    /// Smoothed read/decompression throughput in bytes per second.
+    pub throughput_bytes_per_sec: u64,
+    /// Decompressed chapter bytes produced.
+    pub bytes_decompressed: usize,
+    /// Tokens emitted by the tokenizer.
+    pub tokens_emitted: usize,
+    /// Styled text runs emitted.
+    pub runs_emitted: usize,
+    /// Pages handed to callers.
+    pub pages_emitted: usize,
+    /// Largest combined [`ScratchBuffers`] capacity seen.
+    pub peak_scratch_bytes: usize,
+    /// Time spent per phase; stays zero without a clock.
+    pub phase_micros: PhaseMicros,
+}
+
+/// Elapsed microseconds per streaming phase.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct PhaseMicros {
+    /// Reading and decompressing chapter bytes.
+    pub read: u64,
+    /// Tokenizing or styling markup.
+    pub tokenize: u64,
+    /// Laying out pages.
+    pub layout: u64,
+}
+
+impl PhaseMicros {
+    /// Sum of all phases.
+    pub fn total(&self) -> u64 {
+        self.read
+            .saturating_add(self.tokenize)
+            .saturating_add(self.layout)
+    }
+}
+
+/// Monotonic microsecond clock for phase timings on `no_std` hosts.
+pub type MicrosClock = fn() -> u64;
+
+impl StreamingStats {
+    /// Count `count` styled text runs, for hosts driving their own styler.
+    pub fn record_runs(&mut self, count: usize) {
+        self.runs_emitted += count;
+    }
+
+    /// Note the current capacity of `scratch` toward the peak.
+    pub fn record_scratch(&mut self, scratch: &ScratchBuffers) {
+        let bytes = scratch
+            .read_buf
+            .capacity()
+            .saturating_add(scratch.xml_buf.capacity())
+            .saturating_add(scratch.text_buf.capacity());
+        self.peak_scratch_bytes = self.peak_scratch_bytes.max(bytes);
+    }
+
+    /// Tokens per KiB of decompressed input, if any input was seen.
+    pub fn tokens_per_kib(&self) -> Option<f32> {
+        (self.bytes_decompressed > 0)
+            .then(|| self.tokens_emitted as f32 * 1024.0 / self.bytes_decompressed as f32)
+    }
+
+    /// Decompressed bytes per emitted page, if any page was emitted.
+    pub fn bytes_per_page(&self) -> Option<f32> {
+        (self.pages_emitted > 0).then(|| self.bytes_decompressed as f32 / self.pages_emitted as f32)
+    }
+
+    /// Decompressed bytes per second across all timed phases, if timed.
+    pub fn bytes_per_sec(&self) -> Option<u64> {
+        let micros = self.phase_micros.total();
+        (micros > 0).then(|| (self.bytes_decompressed as u64).saturating_mul(1_000_000) / micros)
+    }
+
+    /// Count one timed read of `bytes` into a `chunk_size` buffer.
+    #[cfg(feature = "std")]
+    fn record_read(&mut self, chunk_size: usize, bytes: usize, throughput_bytes_per_sec: u64) {
+        self.bytes_read += bytes;
+        self.bytes_decompressed += bytes;
+        self.chunks_processed += 1;
+        self.last_chunk_size = chunk_size;
+        self.min_chunk_size = match self.min_chunk_size {
+            0 => chunk_size,
+            min => min.min(chunk_size),
+        };
+        self.max_chunk_size = self.max_chunk_size.max(chunk_size);
+        self.peak_memory_estimate = self.peak_memory_estimate.max(chunk_size);
+        self.throughput_bytes_per_sec = throughput_bytes_per_sec;
+    }
+}
+
+/// Read-size controller driven by measured throughput.
+///
+/// Callers time each read (and the decompression behind it) and report it
+/// with [`record`](Self::record); the next chunk is sized so one read takes
+/// about [`target_micros`](Self::with_target_micros), clamped to the
+/// limits' `min_read_chunk..=max_read_chunk`. Fast storage therefore gets
+/// few large reads and slow storage small ones that keep the reader
+/// responsive. No clock is needed, so this works without `std`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AdaptiveChunkSizer {
+    min: usize,
+    max: usize,
+    current: usize,
+    target_micros: u64,
+    throughput: u64,
+}
+
+impl AdaptiveChunkSizer {
+    /// Default time budget for one read.
+    pub const DEFAULT_TARGET_MICROS: u64 = 10_000;
+
+    /// Sizer bounded by `limits`, starting at the smallest chunk.
+    pub fn new(limits: ChunkLimits) -> Self {
+        let max = limits.max_read_chunk.max(1);
+        let min = limits.min_read_chunk.clamp(1, max);
+        Self {
+            min,
+            max,
+            current: min,
+            target_micros: Self::DEFAULT_TARGET_MICROS,
+            throughput: 0,
+        }
+    }
+
+    /// Aim for reads taking about `micros` each.
+    pub fn with_target_micros(mut self, micros: u64) -> Self {
+        self.target_micros = micros.max(1);
+        self
+    }
+
+    /// Size for the next read.
+    pub fn chunk_size(&self) -> usize {
+        self.current
+    }
+
+    /// Smoothed throughput in bytes per second (`0` before any read).
+    pub fn throughput_bytes_per_sec(&self) -> u64 {
+        self.throughput
+    }
+
+    /// Report a read of `bytes` that took `elapsed_micros`, and resize.
+    pub fn record(&mut self, bytes: usize, elapsed_micros: u64) {
+        if bytes == 0 {
+            return;
+        }
+        let sample = (bytes as u64).saturating_mul(1_000_000) / elapsed_micros.max(1);
+        self.throughput = match self.throughput {
+            0 => sample,
+            prev => prev / 4 * 3 + sample / 4,
+        };
+        let ideal = self.throughput.saturating_mul(self.target_micros) / 1_000_000;
+        self.current = usize::try_from(ideal)
+            .unwrap_or(usize::MAX)
+            .clamp(self.min, self.max);
+    }
+}
+
+/// Incremental chapter pagination for `no_std` hosts.
+///
+/// Chapter bytes are fed chunk-by-chunk into the incremental [`Tokenizer`],
+/// whose tokens drive a [`LayoutEngine`]; pages are handed to a callback as
+/// soon as later input can no longer change them. No ZIP access, threads, or
+/// `std` are required: callers decompress with their own reader and either
+/// call [`feed`](Self::feed)/[`finish`](Self::finish) or let
+/// [`paginate`](Self::paginate) drive reads through [`ScratchBuffers`].
+///
+/// # Memory bounds
+/// With `limits = ChunkLimits::embedded()` and a 4 KB read buffer:
+/// - Stack: constant; neither tokenizing nor layout recurses
+/// - Read buffer: `scratch.read_buf`, capped at `max_read_chunk` (4 KB)
+/// - Tokenizer: one unterminated construct of at most
+///   `max_text_accumulation` (2 KB) plus the current chunk, and an element
+///   stack of at most `max_stack_depth` (64) entries
+/// - Layout: the page being built (at most the engine's lines per page), plus
+///   one completed page held back while widow control may still change it
+///
+/// Heap use is therefore about `read + 2 * text + 2 * page` and does not
+/// grow with chapter length.
+///
+/// # Example
+/// ```
+/// use mu_epub::layout::LayoutEngine;
+/// use mu_epub::streaming::{ChapterPaginator, ChunkLimits};
+///
+/// let mut paginator = ChapterPaginator::new(LayoutEngine::with_defaults(), ChunkLimits::embedded());
+/// let mut pages = 0;
+/// for chunk in [&b"<p>Hello, "[..], b"world</p><p>Second</p>"] {
+///     pages += paginator.feed(chunk, |_page| {}).unwrap();
+/// }
+/// pages += paginator.finish(|page| assert_eq!(page.page_number, 1)).unwrap();
+/// assert_eq!(pages, 1);
+/// ```
+#[cfg(feature = "layout")]
+pub struct ChapterPaginator {
+    tokenizer: Tokenizer,
+    layout: LayoutEngine,
+    limits: ChunkLimits,
+    stats: StreamingStats,
+    clock: Option<MicrosClock>,
+}
+
+#[cfg(feature = "layout")]
+impl ChapterPaginator {
+    /// Create a paginator laying out with `layout` under `limits`.
+    pub fn new(mut layout: LayoutEngine, limits: ChunkLimits) -> Self {
+        layout.start_chapter();
+        Self {
+            tokenizer: Tokenizer::with_chunk_limits(limits),
+            layout,
+            limits,
+            stats: StreamingStats::default(),
+            clock: None,
+        }
+    }
+
+    /// Time phases with `clock` and report them in [`StreamingStats::phase_micros`].
+    pub fn with_clock(mut self, clock: MicrosClock) -> Self {
+        self.clock = Some(clock);
+        self
+    }
+
+    fn now(&self) -> u64 {
+        self.clock.map_or(0, |clock| clock())
+    }
+
+    /// Feed the next chunk of chapter bytes.
+    ///
+    /// Returns the number of pages handed to `on_page`.
+    pub fn feed<F: FnMut(Page)>(
+        &mut self,
+        chunk: &[u8],
+        on_page: F,
+    ) -> Result<usize, TokenizeError> {
+        let clock = self.clock;
+        let started = self.now();
+        let tokens = self.tokenizer.feed(chunk)?;
+        push_tokens(&mut self.layout, clock, started, tokens, &mut self.stats);
+        let started = self.now();
+        let pages = self.layout.take_settled_pages(on_page);
+        self.stats.phase_micros.layout += self.now().saturating_sub(started);
+        self.stats.pages_emitted += pages;
+        self.stats.bytes_read += chunk.len();
+        self.stats.bytes_decompressed += chunk.len();
+        self.stats.bytes_processed += chunk.len();
+        self.stats.chunks_processed += 1;
+        Ok(pages)
+    }
+
+    /// Signal end of chapter and emit the remaining pages.
+    ///
+    /// The paginator is ready for the next chapter afterwards.
+    pub fn finish<F: FnMut(Page)>(&mut self, on_page: F) -> Result<usize, TokenizeError> {
+        let clock = self.clock;
+        let started = self.now();
+        let tokens = self.tokenizer.finish()?;
+        push_tokens(&mut self.layout, clock, started, tokens, &mut self.stats);
+        let started = self.now();
+        let pages = self.layout.finish_chapter(on_page);
+        self.stats.phase_micros.layout += self.now().saturating_sub(started);
+        self.stats.pages_emitted += pages;
+        self.tokenizer.reset();
+        self.layout.start_chapter();
+        Ok(pages)
+    }
+
+    /// Paginate a whole chapter, reading chunks into `scratch.read_buf`.
+    ///
+    /// `read` fills the buffer and returns the bytes written, or `0` at end
+    /// of chapter. Chunks are at most `max_read_chunk` bytes and at most the
+    /// buffer's capacity when one is pre-allocated. Returns the number of
+    /// pages handed to `on_page`.
+    pub fn paginate<E, R, F>(
+        &mut self,
+        scratch: &mut ScratchBuffers,
+        mut read: R,
+        mut on_page: F,
+    ) -> Result<usize, E>
+    where
+        E: From<TokenizeError>,
+        R: FnMut(&mut [u8]) -> Result<usize, E>,
+        F: FnMut(Page),
+    {
+        let capacity = match scratch.read_buf.capacity() {
+            0 => self.limits.max_read_chunk,
+            cap => cap.min(self.limits.max_read_chunk),
+        }
+        .max(1);
+        scratch.read_buf.clear();
+        scratch.read_buf.resize(capacity, 0);
+        self.stats.record_scratch(scratch);
+        let mut pages = 0usize;
+        loop {
+            let started = self.now();
+            let read_len = read(&mut scratch.read_buf)?.min(capacity);
+            self.stats.phase_micros.read += self.now().saturating_sub(started);
+            if read_len == 0 {
+                break;
+            }
+            pages += self.feed(&scratch.read_buf[..read_len], &mut on_page)?;
+        }
+        scratch.read_buf.clear();
+        Ok(pages + self.finish(&mut on_page)?)
+    }
+
+    /// Counters for the input processed so far.
+    pub fn stats(&self) -> StreamingStats {
+        self.stats
+    }
+}
+
+/// Lay out `tokens`, charging token production since `started` and layout
+/// to their phases.
+#[cfg(feature = "layout")]
+fn push_tokens(
+    layout: &mut LayoutEngine,
+    clock: Option<MicrosClock>,
+    started: u64,
+    tokens: impl Iterator<Item = Token>,
+    stats: &mut StreamingStats,
+) {
+    let now = || clock.map_or(0, |clock| clock());
+    let mut mark = started;
+    for token in tokens {
+        let tokenized = now();
+        layout.push_token(&token);
+        let laid_out = now();
+        stats.phase_micros.tokenize += tokenized.saturating_sub(mark);
+        stats.phase_micros.layout += laid_out.saturating_sub(tokenized);
+        stats.events_emitted += 1;
+        stats.tokens_emitted += 1;
+        mark = laid_out;
+    }
+    stats.phase_micros.tokenize += now().saturating_sub(mark);
 }
 
 /// Streaming chapter processor that reads incrementally from ZIP.
@@ -244,6 +784,11 @@ pub struct StreamingChapterProcessor {
     limits: ChunkLimits,
     #[allow(dead_code)]
     state: StreamingParseState,
+    // Only the std impl reads these.
+    #[cfg_attr(not(feature = "std"), allow(dead_code))]
+    sizer: AdaptiveChunkSizer,
+    #[cfg_attr(not(feature = "std"), allow(dead_code))]
+    stats: StreamingStats,
 }
 
 /// Current state of streaming parse.
@@ -274,9 +819,52 @@ impl StreamingChapterProcessor {
         Self {
             limits,
             state: StreamingParseState::Initial,
+            sizer: AdaptiveChunkSizer::new(limits),
+            stats: StreamingStats::default(),
         }
     }
 
+    /// Replace the chunk sizer, e.g. to change its time target.
+    pub fn with_chunk_sizer(mut self, sizer: AdaptiveChunkSizer) -> Self {
+        self.sizer = sizer;
+        self
+    }
+
+    /// Size the next [`read_chunk`](Self::read_chunk) will request.
+    pub fn next_chunk_size(&self) -> usize {
+        self.sizer.chunk_size()
+    }
+
+    /// Read the next chunk of chapter bytes into `buf` with `read`.
+    ///
+    /// `buf` is resized to [`next_chunk_size`](Self::next_chunk_size) and
+    /// truncated to the bytes read; the read is timed to size the following
+    /// chunk. Returns the bytes read, `0` at end of chapter.
+    pub fn read_chunk<E, R>(&mut self, buf: &mut Vec<u8>, read: R) -> Result<usize, E>
+    where
+        R: FnOnce(&mut [u8]) -> Result<usize, E>,
+    {
+        let size = self.sizer.chunk_size();
+        buf.clear();
+        buf.resize(size, 0);
+        let started = Instant::now();
+        let read_len = read(buf)?.min(size);
+        let elapsed = u64::try_from(started.elapsed().as_micros()).unwrap_or(u64::MAX);
+        buf.truncate(read_len);
+        self.stats.phase_micros.read += elapsed;
+        if read_len > 0 {
+            self.sizer.record(read_len, elapsed);
+            self.stats
+                .record_read(size, read_len, self.sizer.throughput_bytes_per_sec());
+        }
+        Ok(read_len)
+    }
+
+    /// Read counters, chunk sizes, and throughput so far.
+    pub fn stats(&self) -> StreamingStats {
+        self.stats
+    }
+
     /// Process a chunk of HTML bytes and emit styled items.
     ///
     /// Returns the number of items emitted. When the chunk is exhausted
@@ -328,6 +916,48 @@ mod tests {
         assert_eq!(buffers.read_buf.capacity(), read_cap);
     }
 
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_scratch_builder_names_undersized_buffer() {
+        let required = ScratchBuffers::for_limits(
+            ChunkLimits::embedded(),
+            crate::tokenizer::TokenizeLimits::embedded(),
+            crate::render_prep::StyleLimits {
+                max_css_bytes: 16 * 1024,
+                ..Default::default()
+            },
+        );
+        assert_eq!(
+            required,
+            RequiredSizes {
+                read_buf: 4096,
+                xml_buf: 16 * 1024,
+                text_buf: 2048,
+            }
+        );
+
+        let buffers = ScratchBuffers::builder(required)
+            .with_read_buf(Vec::with_capacity(8192))
+            .build()
+            .unwrap();
+        assert!(buffers.check(&required).is_ok());
+        assert!(buffers.xml_buf.capacity() >= required.xml_buf);
+
+        let err = ScratchBuffers::builder(required)
+            .with_xml_buf(Vec::with_capacity(4096))
+            .build()
+            .unwrap_err();
+        assert_eq!(err.buffer, ScratchBuffer::Xml);
+        assert_eq!(err.required, 16 * 1024);
+        assert!(err.provided < err.required);
+        assert!(err.to_string().contains("xml_buf"));
+        let err = crate::error::EpubError::from(err);
+        assert!(matches!(
+            err,
+            crate::error::EpubError::BufferTooSmall { .. }
+        ));
+    }
+
     #[test]
     fn test_pagination_context_basic() {
         let mut ctx = PaginationContext::new();
@@ -366,6 +996,153 @@ mod tests {
         assert_eq!(allocator.available(), 1);
     }
 
+    #[cfg(feature = "layout")]
+    #[test]
+    fn test_chapter_paginator_matches_one_shot_layout() {
+        use crate::layout::LayoutEngine;
+        use crate::tokenizer::tokenize_html;
+
+        let mut html = String::with_capacity(8192);
+        for i in 0..60 {
+            html.push_str(&alloc::format!(
+                "<p>Paragraph {} with <em>some emphasis</em> and enough words to wrap.</p>",
+                i
+            ));
+        }
+        let engine = || LayoutEngine::new(300.0, 200.0, 20.0);
+        let expected = engine().layout_tokens(&tokenize_html(&html).unwrap());
+        assert!(expected.len() > 3);
+
+        let mut paginator = ChapterPaginator::new(engine(), ChunkLimits::embedded());
+        let mut scratch = ScratchBuffers::new(37, 0);
+        let mut source = html.as_bytes();
+        let mut pages = Vec::with_capacity(0);
+        let count = paginator
+            .paginate::<TokenizeError, _, _>(
+                &mut scratch,
+                |buf| {
+                    let n = buf.len().min(source.len());
+                    buf[..n].copy_from_slice(&source[..n]);
+                    source = &source[n..];
+                    Ok(n)
+                },
+                |page| {
+                    pages.push(page);
+                },
+            )
+            .unwrap();
+        assert_eq!(count, expected.len());
+        assert_eq!(pages, expected);
+        let stats = paginator.stats();
+        assert_eq!(stats.bytes_read, html.len());
+        assert_eq!(stats.bytes_decompressed, html.len());
+        assert!(stats.chunks_processed >= html.len() / 37);
+        assert_eq!(stats.pages_emitted, expected.len());
+        assert_eq!(stats.tokens_emitted, tokenize_html(&html).unwrap().len());
+        assert_eq!(
+            stats.peak_scratch_bytes,
+            scratch.read_buf.capacity() + scratch.text_buf.capacity()
+        );
+        assert!(stats.tokens_per_kib().unwrap() > 0.0);
+        let per_page = stats.bytes_per_page().unwrap();
+        assert!((per_page - html.len() as f32 / expected.len() as f32).abs() < 0.01);
+        assert_eq!(stats.phase_micros, PhaseMicros::default());
+        assert_eq!(stats.bytes_per_sec(), None);
+
+        // Pages are emitted while input is still arriving.
+        let mut paginator = ChapterPaginator::new(engine(), ChunkLimits::embedded());
+        let half = html.len() / 2;
+        let emitted = paginator.feed(&html.as_bytes()[..half], |_| {}).unwrap();
+        assert!(emitted > 0 && emitted < expected.len());
+    }
+
+    #[cfg(feature = "layout")]
+    #[test]
+    fn test_chapter_paginator_times_phases_with_clock() {
+        use core::sync::atomic::{AtomicU64, Ordering};
+
+        static TICKS: AtomicU64 = AtomicU64::new(0);
+        fn tick() -> u64 {
+            TICKS.fetch_add(10, Ordering::Relaxed)
+        }
+
+        let engine = crate::layout::LayoutEngine::new(300.0, 200.0, 20.0);
+        let mut paginator = ChapterPaginator::new(engine, ChunkLimits::embedded()).with_clock(tick);
+        paginator.feed(b"<p>Hello</p>", |_| {}).unwrap();
+        paginator.finish(|_| {}).unwrap();
+        let stats = paginator.stats();
+        assert!(stats.tokens_emitted > 0);
+        assert!(stats.phase_micros.tokenize > 0);
+        assert!(stats.phase_micros.layout > 0);
+        let total = stats.phase_micros.total();
+        assert!(total < TICKS.load(Ordering::Relaxed));
+        assert_eq!(stats.bytes_per_sec(), Some(12 * 1_000_000 / total));
+    }
+
+    #[test]
+    fn test_adaptive_chunk_sizer_tracks_throughput_within_bounds() {
+        let limits = ChunkLimits::embedded();
+        let mut sizer = AdaptiveChunkSizer::new(limits).with_target_micros(1_000);
+        assert_eq!(sizer.chunk_size(), 1024);
+
+        // 1 KB in 100us = ~10 MB/s: 1ms of that exceeds the max.
+        sizer.record(1024, 100);
+        assert_eq!(sizer.chunk_size(), 4096);
+        assert_eq!(sizer.throughput_bytes_per_sec(), 10_240_000);
+
+        // Slow storage pulls the size back down, but never below the min.
+        for _ in 0..20 {
+            sizer.record(4096, 1_000_000);
+        }
+        assert_eq!(sizer.chunk_size(), 1024);
+
+        // 2 MB/s: about 2 KB per 1ms read.
+        let mut sizer = AdaptiveChunkSizer::new(limits).with_target_micros(1_000);
+        sizer.record(2048, 1_000);
+        assert_eq!(sizer.chunk_size(), 2048);
+
+        let fixed = ChunkLimits {
+            min_read_chunk: 4096,
+            ..limits
+        };
+        let mut sizer = AdaptiveChunkSizer::new(fixed);
+        sizer.record(4096, 1);
+        assert_eq!(sizer.chunk_size(), 4096);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_processor_read_chunk_reports_sizes_and_throughput() {
+        let source = alloc::vec![b'x'; 64 * 1024];
+        let mut processor =
+            StreamingChapterProcessor::new(RenderPrepOptions::default(), ChunkLimits::default());
+        assert_eq!(processor.next_chunk_size(), 4096);
+
+        let mut offset = 0;
+        let mut buf = Vec::with_capacity(0);
+        loop {
+            let n = processor
+                .read_chunk::<(), _>(&mut buf, |out| {
+                    let n = out.len().min(source.len() - offset);
+                    out[..n].copy_from_slice(&source[offset..offset + n]);
+                    offset += n;
+                    Ok(n)
+                })
+                .unwrap();
+            assert_eq!(buf.len(), n);
+            if n == 0 {
+                break;
+            }
+        }
+
+        let stats = processor.stats();
+        assert_eq!(stats.bytes_read, source.len());
+        assert_eq!(stats.min_chunk_size, 4096);
+        assert!(stats.max_chunk_size <= 16384);
+        assert!(stats.throughput_bytes_per_sec > 0);
+        assert!(stats.chunks_processed >= source.len() / 16384);
+    }
+
     #[test]
     fn test_chunk_allocator_exhaustion() {
         let mut allocator = ChunkAllocator::new(1024, 2);
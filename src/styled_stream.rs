@@ -0,0 +1,536 @@
+//! Combinators over styled event/run streams.
+//!
+//! [`StyledStreamExt`] adds the filtering and reshaping passes hosts
+//! otherwise write by hand around [`StyledEventOrRun`] streams. The same
+//! adapters work on the Vec-backed form (`StyledChapter` / `Vec` iterators
+//! yielding plain items) and on the streaming form
+//! ([`ChapterEventIter`](crate::render_prep::ChapterEventIter), whose items
+//! are `Result`s): errors pass through untouched, in order.
+//!
+//! ```no_run
+//! use mu_epub::{EpubBook, StyledStreamExt};
+//!
+//! let mut book = EpubBook::open("book.epub")?;
+//! let chapter = book.chapter_styled_runs(0)?;
+//! let preview: Vec<_> = chapter
+//!     .into_iter()
+//!     .merge_runs()
+//!     .take_text_budget(280)
+//!     .collect();
+//! # let _ = preview;
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use alloc::collections::VecDeque;
+use alloc::string::String;
+use core::convert::Infallible;
+use core::iter::FusedIterator;
+
+use crate::render_prep::{StyledEvent, StyledEventOrRun, StyledRun};
+use crate::tokenizer::Span;
+
+/// Item of a styled stream: a bare [`StyledEventOrRun`] or a `Result`
+/// wrapping one.
+pub trait StyledItem: Sized {
+    /// Error carried by the stream; [`Infallible`] for bare items.
+    type Error;
+
+    /// Split into the item or the stream error.
+    fn into_result(self) -> Result<StyledEventOrRun, Self::Error>;
+
+    /// Wrap a successful item.
+    fn from_item(item: StyledEventOrRun) -> Self;
+
+    /// Wrap a stream error.
+    fn from_error(err: Self::Error) -> Self;
+}
+
+impl StyledItem for StyledEventOrRun {
+    type Error = Infallible;
+
+    fn into_result(self) -> Result<StyledEventOrRun, Infallible> {
+        Ok(self)
+    }
+
+    fn from_item(item: StyledEventOrRun) -> Self {
+        item
+    }
+
+    fn from_error(err: Infallible) -> Self {
+        match err {}
+    }
+}
+
+impl<E> StyledItem for Result<StyledEventOrRun, E> {
+    type Error = E;
+
+    fn into_result(self) -> Result<StyledEventOrRun, E> {
+        self
+    }
+
+    fn from_item(item: StyledEventOrRun) -> Self {
+        Ok(item)
+    }
+
+    fn from_error(err: E) -> Self {
+        Err(err)
+    }
+}
+
+/// Filtering and reshaping adapters for styled streams.
+pub trait StyledStreamExt: Iterator + Sized
+where
+    Self::Item: StyledItem,
+{
+    /// Merge adjacent runs that share style, font and synthesis.
+    ///
+    /// Styled runs carry trimmed words, so merged texts are joined with a
+    /// single space unless either side already has whitespace at the join;
+    /// any event or error between two runs keeps them apart. Merged spans cover the first run's start to the
+    /// last run's end, and become `None` if any part has no span.
+    fn merge_runs(self) -> MergeRuns<Self> {
+        MergeRuns {
+            inner: self,
+            pending: None,
+            queued: None,
+        }
+    }
+
+    /// Split run text after each stretch of characters matching
+    /// `is_delimiter`, e.g. `|c| matches!(c, '.' | '!' | '?')` for
+    /// sentences.
+    ///
+    /// Whitespace following a delimiter stays with the piece before it, so
+    /// every piece after the first starts at new content. Pieces keep the
+    /// style and source span of the run they came from.
+    fn split_on<P>(self, is_delimiter: P) -> SplitOn<Self, P>
+    where
+        P: FnMut(char) -> bool,
+    {
+        SplitOn {
+            inner: self,
+            is_delimiter,
+            pieces: VecDeque::with_capacity(0),
+        }
+    }
+
+    /// Keep only events accepted by `keep`; runs and errors always pass.
+    fn filter_events<P>(self, keep: P) -> FilterEvents<Self, P>
+    where
+        P: FnMut(&StyledEvent) -> bool,
+    {
+        FilterEvents { inner: self, keep }
+    }
+
+    /// End the stream once `max_chars` characters of run text were
+    /// yielded.
+    ///
+    /// The run crossing the budget is truncated at a character boundary.
+    /// Events after the last yielded run still pass so open blocks can
+    /// close; the stream ends at the next run.
+    fn take_text_budget(self, max_chars: usize) -> TakeTextBudget<Self> {
+        TakeTextBudget {
+            inner: self,
+            remaining: max_chars,
+            exhausted: false,
+            done: false,
+        }
+    }
+}
+
+impl<I> StyledStreamExt for I
+where
+    I: Iterator,
+    I::Item: StyledItem,
+{
+}
+
+/// Adapter returned by [`StyledStreamExt::merge_runs`].
+#[derive(Debug)]
+pub struct MergeRuns<I: Iterator> {
+    inner: I,
+    pending: Option<StyledRun>,
+    queued: Option<I::Item>,
+}
+
+fn same_run_style(a: &StyledRun, b: &StyledRun) -> bool {
+    a.font_id == b.font_id
+        && a.synthesis == b.synthesis
+        && a.resolved_family == b.resolved_family
+        && a.style == b.style
+}
+
+fn push_joined(text: &mut String, next: &str) {
+    let spaced = text.is_empty()
+        || next.is_empty()
+        || text.ends_with(char::is_whitespace)
+        || next.starts_with(char::is_whitespace);
+    if !spaced {
+        text.push(' ');
+    }
+    text.push_str(next);
+}
+
+fn merge_spans(a: Option<Span>, b: Option<Span>) -> Option<Span> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(Span {
+            start: a.start.min(b.start),
+            end: a.end.max(b.end),
+        }),
+        _ => None,
+    }
+}
+
+impl<I> Iterator for MergeRuns<I>
+where
+    I: Iterator,
+    I::Item: StyledItem,
+{
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<I::Item> {
+        loop {
+            let Some(item) = self.queued.take().or_else(|| self.inner.next()) else {
+                return self
+                    .pending
+                    .take()
+                    .map(|run| I::Item::from_item(StyledEventOrRun::Run(run)));
+            };
+            match item.into_result() {
+                Ok(StyledEventOrRun::Run(run)) => match self.pending.as_mut() {
+                    Some(pending) if same_run_style(pending, &run) => {
+                        push_joined(&mut pending.text, &run.text);
+                        pending.span = merge_spans(pending.span, run.span);
+                    }
+                    Some(pending) => {
+                        let done = core::mem::replace(pending, run);
+                        return Some(I::Item::from_item(StyledEventOrRun::Run(done)));
+                    }
+                    None => self.pending = Some(run),
+                },
+                other => {
+                    let other = match other {
+                        Ok(item) => I::Item::from_item(item),
+                        Err(err) => I::Item::from_error(err),
+                    };
+                    match self.pending.take() {
+                        Some(run) => {
+                            self.queued = Some(other);
+                            return Some(I::Item::from_item(StyledEventOrRun::Run(run)));
+                        }
+                        None => return Some(other),
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl<I> FusedIterator for MergeRuns<I>
+where
+    I: FusedIterator,
+    I::Item: StyledItem,
+{
+}
+
+/// Adapter returned by [`StyledStreamExt::split_on`].
+#[derive(Debug)]
+pub struct SplitOn<I, P> {
+    inner: I,
+    is_delimiter: P,
+    pieces: VecDeque<StyledRun>,
+}
+
+fn split_run<P>(run: StyledRun, is_delimiter: &mut P, out: &mut VecDeque<StyledRun>)
+where
+    P: FnMut(char) -> bool,
+{
+    let mut start = 0;
+    let mut after_delimiter = false;
+    for (index, ch) in run.text.char_indices() {
+        if is_delimiter(ch) {
+            after_delimiter = true;
+        } else if after_delimiter && !ch.is_whitespace() {
+            out.push_back(StyledRun {
+                text: run.text[start..index].to_string(),
+                style: run.style.clone(),
+                resolved_family: run.resolved_family.clone(),
+                ..run
+            });
+            start = index;
+            after_delimiter = false;
+        }
+    }
+    if start == 0 {
+        out.push_back(run);
+    } else {
+        out.push_back(StyledRun {
+            text: run.text[start..].to_string(),
+            ..run
+        });
+    }
+}
+
+impl<I, P> Iterator for SplitOn<I, P>
+where
+    I: Iterator,
+    I::Item: StyledItem,
+    P: FnMut(char) -> bool,
+{
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<I::Item> {
+        if let Some(piece) = self.pieces.pop_front() {
+            return Some(I::Item::from_item(StyledEventOrRun::Run(piece)));
+        }
+        let item = self.inner.next()?;
+        match item.into_result() {
+            Ok(StyledEventOrRun::Run(run)) => {
+                split_run(run, &mut self.is_delimiter, &mut self.pieces);
+                self.pieces
+                    .pop_front()
+                    .map(|piece| I::Item::from_item(StyledEventOrRun::Run(piece)))
+            }
+            Ok(item) => Some(I::Item::from_item(item)),
+            Err(err) => Some(I::Item::from_error(err)),
+        }
+    }
+}
+
+impl<I, P> FusedIterator for SplitOn<I, P>
+where
+    I: FusedIterator,
+    I::Item: StyledItem,
+    P: FnMut(char) -> bool,
+{
+}
+
+/// Adapter returned by [`StyledStreamExt::filter_events`].
+#[derive(Debug)]
+pub struct FilterEvents<I, P> {
+    inner: I,
+    keep: P,
+}
+
+impl<I, P> Iterator for FilterEvents<I, P>
+where
+    I: Iterator,
+    I::Item: StyledItem,
+    P: FnMut(&StyledEvent) -> bool,
+{
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<I::Item> {
+        loop {
+            match self.inner.next()?.into_result() {
+                Ok(StyledEventOrRun::Event(event)) if !(self.keep)(&event) => continue,
+                Ok(item) => return Some(I::Item::from_item(item)),
+                Err(err) => return Some(I::Item::from_error(err)),
+            }
+        }
+    }
+}
+
+impl<I, P> FusedIterator for FilterEvents<I, P>
+where
+    I: FusedIterator,
+    I::Item: StyledItem,
+    P: FnMut(&StyledEvent) -> bool,
+{
+}
+
+/// Adapter returned by [`StyledStreamExt::take_text_budget`].
+#[derive(Debug)]
+pub struct TakeTextBudget<I> {
+    inner: I,
+    remaining: usize,
+    exhausted: bool,
+    done: bool,
+}
+
+impl<I> TakeTextBudget<I> {
+    /// Characters of run text still allowed.
+    pub fn remaining(&self) -> usize {
+        self.remaining
+    }
+
+    /// Whether the budget was used up.
+    pub fn is_exhausted(&self) -> bool {
+        self.exhausted
+    }
+}
+
+impl<I> Iterator for TakeTextBudget<I>
+where
+    I: Iterator,
+    I::Item: StyledItem,
+{
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<I::Item> {
+        if self.done {
+            return None;
+        }
+        let item = match self.inner.next() {
+            Some(item) => item,
+            None => {
+                self.done = true;
+                return None;
+            }
+        };
+        match item.into_result() {
+            Ok(StyledEventOrRun::Run(mut run)) => {
+                if self.exhausted || self.remaining == 0 {
+                    self.done = true;
+                    return None;
+                }
+                let chars = run.text.chars().count();
+                if chars >= self.remaining {
+                    if let Some((cut, _)) = run.text.char_indices().nth(self.remaining) {
+                        run.text.truncate(cut);
+                    }
+                    self.remaining = 0;
+                    self.exhausted = true;
+                } else {
+                    self.remaining -= chars;
+                }
+                Some(I::Item::from_item(StyledEventOrRun::Run(run)))
+            }
+            Ok(item) => Some(I::Item::from_item(item)),
+            Err(err) => Some(I::Item::from_error(err)),
+        }
+    }
+}
+
+impl<I> FusedIterator for TakeTextBudget<I>
+where
+    I: Iterator,
+    I::Item: StyledItem,
+{
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render_prep::{
+        BlockRole, ChapterStylesheets, ComputedTextStyle, FontSynthesis, StyleConfig,
+        StyledChapter, Styler,
+    };
+
+    fn run(text: &str, weight: u16, start: usize) -> StyledEventOrRun {
+        StyledEventOrRun::Run(StyledRun {
+            text: text.to_string(),
+            style: ComputedTextStyle {
+                family_stack: vec!["serif".to_string()],
+                weight,
+                italic: false,
+                size_px: 16.0,
+                line_height: 1.4,
+                letter_spacing: 0.0,
+                block_role: BlockRole::Body,
+                language: None,
+            },
+            font_id: 0,
+            resolved_family: "serif".to_string(),
+            synthesis: FontSynthesis::default(),
+            span: Some(Span {
+                start,
+                end: start + text.len(),
+            }),
+        })
+    }
+
+    fn texts<T: StyledItem>(items: impl IntoIterator<Item = T>) -> Vec<String> {
+        items
+            .into_iter()
+            .map(|item| match item.into_result() {
+                Ok(StyledEventOrRun::Run(run)) => run.text,
+                Ok(StyledEventOrRun::Event(event)) => format!("{:?}", event),
+                Err(_) => "error".to_string(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn combinators_reshape_plain_and_fallible_streams() {
+        let html = "<html><body><p>One. <span>Two? Three</span><b>!</b><br/>Four</p></body></html>";
+        let mut styler = Styler::new(StyleConfig::default());
+        styler
+            .load_stylesheets(&ChapterStylesheets::default())
+            .unwrap();
+        let chapter = styler.style_chapter(html).unwrap();
+        assert_eq!(
+            texts(chapter.iter().cloned()),
+            [
+                "ParagraphStart",
+                "One.",
+                "Two? Three",
+                "!",
+                "LineBreak",
+                "Four",
+                "ParagraphEnd"
+            ]
+        );
+
+        let merged: StyledChapter = chapter.clone().into_iter().merge_runs().collect();
+        let first = merged.runs().next().unwrap();
+        assert_eq!(first.text, "One. Two? Three");
+        let span = first.span.unwrap();
+        assert_eq!(span.start, html.find("One.").unwrap());
+        assert_eq!(span.end, html.find("Three").unwrap() + "Three".len());
+        assert_eq!(
+            texts(
+                merged
+                    .into_iter()
+                    .filter_events(|event| *event != StyledEvent::LineBreak)
+                    .split_on(|c| matches!(c, '.' | '?' | '!'))
+            ),
+            [
+                "ParagraphStart",
+                "One. ",
+                "Two? ",
+                "Three",
+                "!",
+                "Four",
+                "ParagraphEnd"
+            ]
+        );
+
+        let budgeted = chapter.iter().cloned().take_text_budget(8);
+        assert_eq!(texts(budgeted), ["ParagraphStart", "One.", "Two?"]);
+
+        // Errors from a streaming source pass through in order.
+        let fallible = chapter
+            .iter()
+            .take(3)
+            .cloned()
+            .map(Ok::<_, ()>)
+            .chain([Err(())])
+            .chain(chapter.iter().skip(3).cloned().map(Ok))
+            .merge_runs()
+            .take_text_budget(16);
+        assert_eq!(
+            texts(fallible),
+            [
+                "ParagraphStart",
+                "One. Two? Three",
+                "error",
+                "!",
+                "LineBreak"
+            ]
+        );
+    }
+
+    #[test]
+    fn merge_runs_keeps_existing_whitespace_at_joins() {
+        let merged: Vec<_> = [
+            run("Hello ", 400, 0),
+            run("world", 400, 6),
+            run(" again", 400, 11),
+        ]
+        .into_iter()
+        .merge_runs()
+        .collect();
+        assert_eq!(texts(merged), ["Hello world again"]);
+    }
+}
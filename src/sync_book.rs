@@ -0,0 +1,411 @@
+//! Thread-safe shared EPUB handle.
+//!
+//! [`EpubBook`] needs `&mut self` for every read because it owns a single ZIP
+//! cursor. [`SyncEpubBook`] parses package metadata once, keeps it immutable,
+//! and hands each concurrent read its own pooled [`StreamingZip`], so chapter
+//! fetches from different threads proceed in parallel.
+
+extern crate alloc;
+
+use alloc::boxed::Box;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use std::fs::File;
+use std::io::{Cursor, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use crate::book::{
+    chapter_ref, chapter_refs, displayable_chapter_ref, read_entry_into_with_limit,
+    resolve_opf_relative_path, ChapterRef, EpubBook, EpubBookOptions,
+};
+use crate::error::EpubError;
+use crate::metadata::EpubMetadata;
+use crate::navigation::Navigation;
+use crate::render_prep::{
+    ChapterSource, PreparedChapter, RenderPrep, RenderPrepError, StyledEventOrRun,
+};
+use crate::spine::Spine;
+use crate::storage::RandomAccess;
+#[cfg(target_has_atomic = "64")]
+use crate::zip::SharedDecompressedTotal;
+use crate::zip::StreamingZip;
+
+/// Default number of idle ZIP readers kept for reuse.
+const DEFAULT_MAX_IDLE_READERS: usize = 4;
+
+type ReaderFactory<R> = Box<dyn Fn() -> Result<R, EpubError> + Send + Sync>;
+
+/// Shared, `Sync` EPUB handle for concurrent chapter access.
+///
+/// Each read checks out a ZIP reader from an internal pool, opening a new one
+/// from the source factory when all readers are busy. Readers return to the
+/// pool afterwards, up to [`Self::with_max_idle_readers`].
+///
+/// # Allocation behavior
+/// - Package metadata, spine, and navigation are parsed once
+/// - Each pooled reader holds its own central directory cache (~4KB)
+///
+/// All readers charge one shared total against
+/// [`ZipLimits::max_total_decompressed`](crate::ZipLimits::max_total_decompressed),
+/// so the budget covers the whole book rather than each reader. On targets
+/// without 64-bit atomics each reader keeps its own total instead.
+pub struct SyncEpubBook<R: RandomAccess> {
+    opf_path: String,
+    metadata: EpubMetadata,
+    spine: Spine,
+    navigation: Option<Navigation>,
+    options: EpubBookOptions,
+    open_reader: ReaderFactory<R>,
+    pool: Mutex<Vec<StreamingZip<R>>>,
+    max_idle_readers: usize,
+    #[cfg(target_has_atomic = "64")]
+    decompressed_total: SharedDecompressedTotal,
+}
+
+impl SyncEpubBook<File> {
+    /// Open an EPUB from disk; each pooled reader opens its own file handle.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, EpubError> {
+        Self::open_with_options(path, EpubBookOptions::default())
+    }
+
+    /// Open an EPUB from disk with explicit options.
+    pub fn open_with_options<P: AsRef<Path>>(
+        path: P,
+        options: EpubBookOptions,
+    ) -> Result<Self, EpubError> {
+        let path: PathBuf = path.as_ref().to_path_buf();
+        Self::from_factory_with_options(
+            move || File::open(&path).map_err(|e| EpubError::Io(e.to_string())),
+            options,
+        )
+    }
+}
+
+impl SyncEpubBook<Cursor<Arc<[u8]>>> {
+    /// Open an EPUB held in shared memory; readers share the same bytes.
+    pub fn from_shared_bytes(bytes: Arc<[u8]>) -> Result<Self, EpubError> {
+        Self::from_shared_bytes_with_options(bytes, EpubBookOptions::default())
+    }
+
+    /// Open an EPUB held in shared memory with explicit options.
+    pub fn from_shared_bytes_with_options(
+        bytes: Arc<[u8]>,
+        options: EpubBookOptions,
+    ) -> Result<Self, EpubError> {
+        Self::from_factory_with_options(move || Ok(Cursor::new(Arc::clone(&bytes))), options)
+    }
+}
+
+impl<R: RandomAccess + Send> SyncEpubBook<R> {
+    /// Open an EPUB from a factory producing independent readers of the same archive.
+    pub fn from_factory<F>(factory: F) -> Result<Self, EpubError>
+    where
+        F: Fn() -> Result<R, EpubError> + Send + Sync + 'static,
+    {
+        Self::from_factory_with_options(factory, EpubBookOptions::default())
+    }
+
+    /// Open an EPUB from a reader factory with explicit options.
+    ///
+    /// The first reader parses the package and seeds the pool.
+    pub fn from_factory_with_options<F>(
+        factory: F,
+        options: EpubBookOptions,
+    ) -> Result<Self, EpubError>
+    where
+        F: Fn() -> Result<R, EpubError> + Send + Sync + 'static,
+    {
+        let mut parts = EpubBook::from_storage_with_options(factory()?, options)?.into_parts();
+        #[cfg(target_has_atomic = "64")]
+        let decompressed_total = SharedDecompressedTotal::new(parts.zip.decompressed_total());
+        #[cfg(target_has_atomic = "64")]
+        parts
+            .zip
+            .share_decompressed_total(decompressed_total.clone());
+        let mut pool = Vec::with_capacity(DEFAULT_MAX_IDLE_READERS);
+        pool.push(parts.zip);
+        Ok(Self {
+            opf_path: parts.opf_path,
+            metadata: parts.metadata,
+            spine: parts.spine,
+            navigation: parts.navigation,
+            options,
+            open_reader: Box::new(factory),
+            pool: Mutex::new(pool),
+            max_idle_readers: DEFAULT_MAX_IDLE_READERS,
+            #[cfg(target_has_atomic = "64")]
+            decompressed_total,
+        })
+    }
+
+    /// Set how many idle readers are kept for reuse (at least one).
+    pub fn with_max_idle_readers(mut self, max_idle_readers: usize) -> Self {
+        self.max_idle_readers = max_idle_readers.max(1);
+        self
+    }
+
+    /// EPUB package metadata.
+    pub fn metadata(&self) -> &EpubMetadata {
+        &self.metadata
+    }
+
+    /// Convenience: metadata title.
+    pub fn title(&self) -> &str {
+        self.metadata.title.as_str()
+    }
+
+    /// Reading order from `<spine>`.
+    pub fn spine(&self) -> &Spine {
+        &self.spine
+    }
+
+    /// Parsed navigation document, when one is available.
+    pub fn navigation(&self) -> Option<&Navigation> {
+        self.navigation.as_ref()
+    }
+
+    /// Bytes decompressed by all pooled readers so far, including opening.
+    #[cfg(target_has_atomic = "64")]
+    pub fn decompressed_total(&self) -> u64 {
+        self.decompressed_total.get()
+    }
+
+    /// Number of entries in the spine reading order.
+    pub fn chapter_count(&self) -> usize {
+        self.spine.len()
+    }
+
+    /// Enumerate chapters in spine order.
+    pub fn chapters(&self) -> impl Iterator<Item = ChapterRef> + '_ {
+        chapter_refs(&self.metadata, &self.spine, self.navigation.as_ref())
+    }
+
+    /// Get a chapter descriptor by spine index.
+    pub fn chapter(&self, index: usize) -> Result<ChapterRef, EpubError> {
+        chapter_ref(&self.metadata, &self.spine, self.navigation.as_ref(), index)
+    }
+
+    /// Get the descriptor of the content displayed for a spine index,
+    /// following manifest `fallback` chains past undisplayable items.
+    ///
+    /// See [`EpubBook::resolve_displayable_chapter`].
+    pub fn resolve_displayable_chapter(&self, index: usize) -> Result<ChapterRef, EpubError> {
+        displayable_chapter_ref(&self.metadata, &self.spine, self.navigation.as_ref(), index)
+    }
+
+    /// Read a resource by OPF-relative href into a new `Vec<u8>`.
+    ///
+    /// Fragment suffixes (e.g. `chapter.xhtml#p3`) are ignored.
+    pub fn read_resource(&self, href: &str) -> Result<Vec<u8>, EpubError> {
+        let mut out = Vec::with_capacity(0);
+        self.read_resource_into(href, &mut out)?;
+        Ok(out)
+    }
+
+    /// Stream a resource by OPF-relative href into a writer.
+    ///
+    /// Fragment suffixes (e.g. `chapter.xhtml#p3`) are ignored.
+    pub fn read_resource_into<W: Write>(
+        &self,
+        href: &str,
+        writer: &mut W,
+    ) -> Result<usize, EpubError> {
+        self.read_resource_into_with_hard_cap(href, writer, usize::MAX)
+    }
+
+    /// Stream a resource by OPF-relative href with a hard cap.
+    ///
+    /// Fragment suffixes (e.g. `chapter.xhtml#p3`) are ignored.
+    pub fn read_resource_into_with_hard_cap<W: Write>(
+        &self,
+        href: &str,
+        writer: &mut W,
+        hard_cap_bytes: usize,
+    ) -> Result<usize, EpubError> {
+        let zip_path = resolve_opf_relative_path(&self.opf_path, href);
+        self.with_reader(|zip| read_entry_into_with_limit(zip, &zip_path, writer, hard_cap_bytes))
+    }
+
+    /// Read a spine chapter as UTF-8 HTML/XHTML text by index.
+    pub fn chapter_html(&self, index: usize) -> Result<String, EpubError> {
+        let chapter = self.resolve_displayable_chapter(index)?;
+        let bytes = self.read_resource(&chapter.href)?;
+        String::from_utf8(bytes).map_err(|_| EpubError::ChapterNotUtf8 { href: chapter.href })
+    }
+
+    /// Prepare a chapter into styled runs/events with a caller-owned [`RenderPrep`].
+    ///
+    /// `RenderPrep` carries per-chapter stylesheet state, so each thread
+    /// should use its own instance.
+    pub fn prepare_chapter(
+        &self,
+        prep: &mut RenderPrep,
+        index: usize,
+    ) -> Result<PreparedChapter, RenderPrepError> {
+        let mut source = self;
+        let (styled, missing_glyphs) = prep.prepare_chapter_items(&mut source, index)?;
+        let nav_label = self.chapter(index).ok().and_then(|chapter| chapter.title);
+        Ok(
+            PreparedChapter::from_styled(styled, nav_label, self.title())
+                .with_missing_glyphs(missing_glyphs),
+        )
+    }
+
+    /// Prepare a chapter and stream each styled item via callback.
+    pub fn prepare_chapter_with<F: FnMut(StyledEventOrRun)>(
+        &self,
+        prep: &mut RenderPrep,
+        index: usize,
+        mut on_item: F,
+    ) -> Result<(), RenderPrepError> {
+        let mut source = self;
+        prep.prepare_chapter_from_source(&mut source, index, |item, _| on_item(item))
+    }
+
+    /// Run `f` with a pooled reader, opening a new one if none is idle.
+    fn with_reader<T>(
+        &self,
+        f: impl FnOnce(&mut StreamingZip<R>) -> Result<T, EpubError>,
+    ) -> Result<T, EpubError> {
+        let idle = self
+            .pool
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .pop();
+        let mut zip = match idle {
+            Some(zip) => zip,
+            None => self.open_zip()?,
+        };
+        let result = f(&mut zip);
+        let mut pool = self
+            .pool
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        if pool.len() < self.max_idle_readers {
+            pool.push(zip);
+        }
+        result
+    }
+
+    fn open_zip(&self) -> Result<StreamingZip<R>, EpubError> {
+        let reader = (self.open_reader)()?;
+        let mut zip = StreamingZip::new_with_limits(reader, self.options.zip_limits)
+            .map_err(EpubError::Zip)?;
+        zip.set_tolerant_names(self.options.tolerant_entry_names);
+        #[cfg(target_has_atomic = "64")]
+        zip.share_decompressed_total(self.decompressed_total.clone());
+        Ok(zip)
+    }
+
+    /// Number of idle readers currently pooled.
+    #[cfg(test)]
+    fn idle_readers(&self) -> usize {
+        self.pool
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .len()
+    }
+}
+
+impl<R: RandomAccess + Send> ChapterSource for &SyncEpubBook<R> {
+    fn chapter_ref(&self, index: usize) -> Result<ChapterRef, EpubError> {
+        self.resolve_displayable_chapter(index)
+    }
+
+    fn read_resource(&mut self, href: &str) -> Result<Vec<u8>, EpubError> {
+        SyncEpubBook::read_resource(self, href)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render_prep::RenderPrepOptions;
+    use crate::zip::{ZipError, ZipLimits};
+
+    const FIXTURE: &str =
+        "tests/fixtures/Fundamental-Accessibility-Tests-Basic-Functionality-v2.0.0.epub";
+
+    fn assert_send_sync<T: Send + Sync>() {}
+
+    #[test]
+    fn sync_book_is_send_and_sync() {
+        assert_send_sync::<SyncEpubBook<File>>();
+        assert_send_sync::<SyncEpubBook<Cursor<Arc<[u8]>>>>();
+    }
+
+    #[test]
+    fn concurrent_reads_match_single_threaded_book() {
+        let mut book = EpubBook::open(FIXTURE).unwrap();
+        let expected: Vec<String> = (0..book.chapter_count())
+            .map(|index| book.chapter_html(index).unwrap())
+            .collect();
+
+        let shared = SyncEpubBook::open(FIXTURE).unwrap();
+        assert_eq!(shared.metadata(), book.metadata());
+        std::thread::scope(|scope| {
+            for _ in 0..4 {
+                scope.spawn(|| {
+                    for (index, html) in expected.iter().enumerate() {
+                        assert_eq!(&shared.chapter_html(index).unwrap(), html);
+                    }
+                });
+            }
+        });
+        assert!(shared.idle_readers() >= 1);
+        assert!(shared.idle_readers() <= DEFAULT_MAX_IDLE_READERS);
+    }
+
+    #[test]
+    fn concurrent_prepare_chapter_matches_book() {
+        let mut book = EpubBook::open(FIXTURE).unwrap();
+        let mut prep = RenderPrep::new(RenderPrepOptions::default()).with_serif_default();
+        let expected = prep.prepare_chapter(&mut book, 1).unwrap();
+
+        let bytes: Arc<[u8]> = std::fs::read(FIXTURE).unwrap().into();
+        let shared = SyncEpubBook::from_shared_bytes(bytes)
+            .unwrap()
+            .with_max_idle_readers(2);
+        std::thread::scope(|scope| {
+            for _ in 0..3 {
+                scope.spawn(|| {
+                    let mut prep =
+                        RenderPrep::new(RenderPrepOptions::default()).with_serif_default();
+                    let prepared = shared.prepare_chapter(&mut prep, 1).unwrap();
+                    assert_eq!(prepared, expected);
+                });
+            }
+        });
+        assert!(shared.idle_readers() <= 2);
+    }
+
+    #[test]
+    #[cfg(target_has_atomic = "64")]
+    fn pooled_readers_share_decompression_budget() {
+        let opened = SyncEpubBook::open(FIXTURE).unwrap();
+        let at_open = opened.decompressed_total();
+        let chapter = opened.chapter_html(1).unwrap().len() as u64;
+        assert_eq!(opened.decompressed_total(), at_open + chapter);
+
+        let limits = ZipLimits::new(usize::MAX, 1024)
+            .with_max_total_decompressed(at_open + chapter + chapter / 2);
+        let shared = SyncEpubBook::open_with_options(
+            FIXTURE,
+            EpubBookOptions {
+                zip_limits: Some(limits),
+                ..EpubBookOptions::default()
+            },
+        )
+        .unwrap();
+        shared.chapter_html(1).unwrap();
+        // The nested read opens a second reader, which starts from the
+        // book's total rather than a fresh budget.
+        let nested = shared.with_reader(|_| Ok(shared.chapter_html(1)));
+        assert!(matches!(
+            nested,
+            Ok(Err(EpubError::Zip(ZipError::DecompressionBudgetExceeded)))
+        ));
+        assert_eq!(shared.decompressed_total(), at_open + chapter);
+    }
+}
@@ -8,11 +8,16 @@ extern crate alloc;
 
 use alloc::format;
 use alloc::string::{String, ToString};
-use alloc::vec::Vec;
+use alloc::vec::{Drain, Vec};
 use quick_xml::escape::unescape;
 use quick_xml::events::{BytesStart, Event};
 use quick_xml::reader::Reader;
 
+use crate::css::{
+    cascade, parse_inline_declarations, parse_inline_style, CustomProperties, Dimension,
+    ListStyleType, Stylesheet,
+};
+
 /// Token types for simplified XHTML representation
 #[derive(Clone, Debug, PartialEq, Eq)]
 #[non_exhaustive]
@@ -31,8 +36,20 @@ pub enum Token {
     LineBreak,
     /// Start of a list (true = ordered, false = unordered)
     ListStart(bool),
+    /// Marker style and first ordinal of the list just started, emitted
+    /// after [`Token::ListStart`] when the list sets `type`, `start`, or
+    /// an inline `list-style-type`
+    ListStyle {
+        /// Marker style for the list's items
+        style: ListStyleType,
+        /// Ordinal of the first item
+        start: i32,
+    },
     /// End of a list
     ListEnd,
+    /// Ordinal override from `<li value="n">`, emitted before the item's
+    /// [`Token::ListItemStart`]; following items count on from it
+    ListItemValue(i32),
     /// Start of a list item
     ListItemStart,
     /// End of a list item
@@ -41,13 +58,79 @@ pub enum Token {
     LinkStart(String),
     /// End of a link
     LinkEnd,
-    /// Image reference with src and alt text
+    /// Image reference with src, alt text, and declared size
     Image {
         /// Image source path (relative to EPUB content)
         src: String,
         /// Alternative text for the image
         alt: String,
+        /// Declared width (inline `style` wins over the `width` attribute)
+        width: Option<Dimension>,
+        /// Declared height (inline `style` wins over the `height` attribute)
+        height: Option<Dimension>,
     },
+    /// Start of a preformatted block (`<pre>`); enclosed text keeps its
+    /// whitespace and line breaks verbatim
+    PreformattedStart,
+    /// End of a preformatted block
+    PreformattedEnd,
+    /// Element `id` attribute (fragment target), emitted before the
+    /// element's own tokens
+    Anchor(String),
+    /// Thematic break (`<hr>`)
+    Hr,
+    /// Start of a figure (`<figure>`)
+    FigureStart,
+    /// End of a figure
+    FigureEnd,
+    /// Start of a figure caption (`<figcaption>`)
+    CaptionStart,
+    /// End of a figure caption
+    CaptionEnd,
+}
+
+/// Byte range in the source chapter that produced a token.
+///
+/// Offsets are relative to the start of the tokenized input. Tokens closed
+/// implicitly at end of input carry an empty span at the input length.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Span {
+    /// Inclusive start byte offset.
+    pub start: usize,
+    /// Exclusive end byte offset.
+    pub end: usize,
+}
+
+impl Span {
+    /// Create a span from start/end byte offsets.
+    pub fn new(start: usize, end: usize) -> Self {
+        Self { start, end }
+    }
+
+    /// Number of source bytes covered.
+    pub fn len(&self) -> usize {
+        self.end.saturating_sub(self.start)
+    }
+
+    /// Whether the span covers no source bytes.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Shift both offsets by `base` bytes.
+    pub fn offset(self, base: usize) -> Self {
+        Self::new(self.start + base, self.end + base)
+    }
+}
+
+/// Token paired with the source byte range that produced it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SpannedToken {
+    /// Token payload.
+    pub token: Token,
+    /// Source byte range (see [`Span`]).
+    pub span: Span,
 }
 
 /// Error type for tokenization failures
@@ -58,6 +141,17 @@ pub enum TokenizeError {
     ParseError(String),
     /// Invalid HTML structure
     InvalidStructure(String),
+    /// A [`TokenizeLimits`] cap was exceeded.
+    LimitExceeded {
+        /// Limit field name (e.g. `max_attributes`).
+        kind: &'static str,
+        /// Observed value.
+        actual: usize,
+        /// Configured cap.
+        limit: usize,
+        /// Source byte offset of the offending markup.
+        offset: usize,
+    },
 }
 
 impl core::fmt::Display for TokenizeError {
@@ -65,6 +159,16 @@ impl core::fmt::Display for TokenizeError {
         match self {
             TokenizeError::ParseError(msg) => write!(f, "Parse error: {}", msg),
             TokenizeError::InvalidStructure(msg) => write!(f, "Invalid structure: {}", msg),
+            TokenizeError::LimitExceeded {
+                kind,
+                actual,
+                limit,
+                offset,
+            } => write!(
+                f,
+                "Limit exceeded: {} ({} > {}) at byte {}",
+                kind, actual, limit, offset
+            ),
         }
     }
 }
@@ -72,9 +176,74 @@ impl core::fmt::Display for TokenizeError {
 #[cfg(feature = "std")]
 impl std::error::Error for TokenizeError {}
 
+#[cfg(feature = "defmt")]
+impl defmt::Format for TokenizeError {
+    fn format(&self, f: defmt::Formatter<'_>) {
+        match self {
+            TokenizeError::ParseError(msg) => defmt::write!(f, "Parse error: {=str}", msg.as_str()),
+            TokenizeError::InvalidStructure(msg) => {
+                defmt::write!(f, "Invalid structure: {=str}", msg.as_str())
+            }
+            TokenizeError::LimitExceeded {
+                kind,
+                actual,
+                limit,
+                offset,
+            } => defmt::write!(
+                f,
+                "Limit exceeded: {=str} ({=usize} > {=usize}) at byte {=usize}",
+                kind,
+                actual,
+                limit,
+                offset
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "defmt")]
+impl defmt::Format for Token {
+    fn format(&self, f: defmt::Formatter<'_>) {
+        match self {
+            Token::Text(text) => defmt::write!(f, "Text({=str})", text.as_str()),
+            Token::ParagraphBreak => defmt::write!(f, "ParagraphBreak"),
+            Token::Heading(level) => defmt::write!(f, "Heading({=u8})", level),
+            Token::Emphasis(start) => defmt::write!(f, "Emphasis({=bool})", start),
+            Token::Strong(start) => defmt::write!(f, "Strong({=bool})", start),
+            Token::LineBreak => defmt::write!(f, "LineBreak"),
+            Token::ListStart(ordered) => defmt::write!(f, "ListStart({=bool})", ordered),
+            Token::ListStyle { style, start } => {
+                defmt::write!(
+                    f,
+                    "ListStyle({=str}, {=i32})",
+                    style.marker(*start).as_str(),
+                    start
+                )
+            }
+            Token::ListEnd => defmt::write!(f, "ListEnd"),
+            Token::ListItemValue(value) => defmt::write!(f, "ListItemValue({=i32})", value),
+            Token::ListItemStart => defmt::write!(f, "ListItemStart"),
+            Token::ListItemEnd => defmt::write!(f, "ListItemEnd"),
+            Token::LinkStart(href) => defmt::write!(f, "LinkStart({=str})", href.as_str()),
+            Token::LinkEnd => defmt::write!(f, "LinkEnd"),
+            Token::PreformattedStart => defmt::write!(f, "PreformattedStart"),
+            Token::PreformattedEnd => defmt::write!(f, "PreformattedEnd"),
+            Token::Anchor(id) => defmt::write!(f, "Anchor({=str})", id.as_str()),
+            Token::Hr => defmt::write!(f, "Hr"),
+            Token::FigureStart => defmt::write!(f, "FigureStart"),
+            Token::FigureEnd => defmt::write!(f, "FigureEnd"),
+            Token::CaptionStart => defmt::write!(f, "CaptionStart"),
+            Token::CaptionEnd => defmt::write!(f, "CaptionEnd"),
+            Token::Image { src, alt, .. } => {
+                defmt::write!(f, "Image({=str}, {=str})", src.as_str(), alt.as_str())
+            }
+        }
+    }
+}
+
 /// Convert XHTML string into a token stream
 ///
-/// Parses HTML tags: p, h1-h6, em, strong, br, span, div
+/// Parses HTML tags: p, h1-h6, em, strong, br, span, div, pre
 /// Strips out: script, style, head, attributes (except class for styling)
 /// Extracts text content and converts HTML entities
 ///
@@ -93,15 +262,65 @@ pub fn tokenize_html(html: &str) -> Result<Vec<Token>, TokenizeError> {
     Ok(tokens)
 }
 
+/// Convert XHTML into a token stream, cascading list marker styles
+///
+/// Like [`tokenize_html`], but `list-style-type` declared by rules of
+/// `sheets` that match a list's tag and classes applies as well as inline
+/// declarations.
+pub fn tokenize_html_with_stylesheets(
+    html: &str,
+    sheets: &[Stylesheet],
+) -> Result<Vec<Token>, TokenizeError> {
+    let mut tokens = Vec::with_capacity((html.len() / 10).min(10000));
+    let mut core = TokenizerCore::new(TokenizeLimits::unbounded());
+    core.list_rules = list_rules(sheets);
+    tokenize_with_core(html, &mut tokens, &mut TokenizeScratch::embedded(), core)?;
+    Ok(tokens)
+}
+
+/// Rules of `sheets` that declare `list-style-type`.
+fn list_rules(sheets: &[Stylesheet]) -> Stylesheet {
+    Stylesheet {
+        rules: sheets
+            .iter()
+            .flat_map(|sheet| sheet.rules.iter())
+            .filter(|rule| {
+                rule.style.list_style_type.is_some() || rule.important.list_style_type.is_some()
+            })
+            .cloned()
+            .collect(),
+        custom_properties: CustomProperties::new(),
+    }
+}
+
+/// What to do with elements nested deeper than a `max_nesting` limit.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum NestingOverflow {
+    /// Fail with an error naming the depth and byte offset.
+    #[default]
+    Error,
+    /// Treat the excess elements as transparent: their content is kept and
+    /// styled like the deepest allowed ancestor.
+    Flatten,
+}
+
 /// Limits for bounded tokenization to prevent unbounded Vec growth.
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct TokenizeLimits {
     /// Maximum number of tokens to emit before returning an error.
     pub max_tokens: usize,
     /// Maximum nesting depth for element stack.
     pub max_nesting: usize,
+    /// Handling of elements beyond `max_nesting`.
+    pub nesting_overflow: NestingOverflow,
     /// Maximum text node size in bytes before truncation.
     pub max_text_bytes: usize,
+    /// Maximum attributes on a single element.
+    pub max_attributes: usize,
+    /// Maximum entity and character references expanded per chapter.
+    pub max_entity_expansions: usize,
 }
 
 impl Default for TokenizeLimits {
@@ -109,7 +328,10 @@ impl Default for TokenizeLimits {
         Self {
             max_tokens: 100_000,
             max_nesting: 256,
+            nesting_overflow: NestingOverflow::Error,
             max_text_bytes: 64 * 1024,
+            max_attributes: 256,
+            max_entity_expansions: 100_000,
         }
     }
 }
@@ -120,7 +342,22 @@ impl TokenizeLimits {
         Self {
             max_tokens: 10_000,
             max_nesting: 64,
+            nesting_overflow: NestingOverflow::Error,
             max_text_bytes: 8 * 1024,
+            max_attributes: 64,
+            max_entity_expansions: 10_000,
+        }
+    }
+
+    /// No caps; used by the unbounded one-shot APIs.
+    fn unbounded() -> Self {
+        Self {
+            max_tokens: usize::MAX,
+            max_nesting: usize::MAX,
+            nesting_overflow: NestingOverflow::Error,
+            max_text_bytes: usize::MAX,
+            max_attributes: usize::MAX,
+            max_entity_expansions: usize::MAX,
         }
     }
 }
@@ -161,64 +398,801 @@ pub fn tokenize_html_limited(
 
     // Stack to track nested elements for proper closing
     let mut element_stack: Vec<ElementType> = Vec::with_capacity(limits.max_nesting.min(64));
-    // Track if we're inside a tag that should be skipped (script, style, head)
-    let mut skip_depth: usize = 0;
-    // Track if we need a paragraph break after current block element
-    let mut pending_paragraph_break: bool = false;
-    // Track if we need a heading close after text content
-    let mut pending_heading_close: Option<u8> = None;
+    let mut core = TokenizerCore::new(limits);
 
-    let mut token_count: usize = 0;
+    drive_reader(
+        &mut reader,
+        &mut buf,
+        &mut core,
+        &mut element_stack,
+        &mut tokens,
+    )?;
+    core.finish(&mut element_stack, &mut tokens, html.len())?;
 
+    Ok(tokens)
+}
+
+/// Convert XHTML into tokens paired with their source byte ranges.
+///
+/// Produces the same token sequence as [`tokenize_html_limited`]; each token
+/// carries the [`Span`] of the markup that produced it so callers can map
+/// tokens back to exact chapter byte ranges (locators, search hits,
+/// diagnostics).
+///
+/// Span conventions:
+/// - `Text`: the text node (extended over merged entity references)
+/// - start/end tokens: the corresponding start/end tag
+/// - `Heading`: the heading start tag
+/// - `ParagraphBreak`: the tag that opened the following block
+///
+/// # Example
+/// ```
+/// use mu_epub::tokenizer::{tokenize_html_with_spans, Token, TokenizeLimits};
+///
+/// let html = "<p>Hello</p>";
+/// let tokens = tokenize_html_with_spans(html, TokenizeLimits::default()).unwrap();
+/// assert_eq!(tokens[0].token, Token::Text("Hello".to_string()));
+/// assert_eq!(&html[tokens[0].span.start..tokens[0].span.end], "Hello");
+/// ```
+pub fn tokenize_html_with_spans(
+    html: &str,
+    limits: TokenizeLimits,
+) -> Result<Vec<SpannedToken>, TokenizeError> {
+    let mut reader = Reader::from_str(html);
+    reader.config_mut().trim_text(false);
+    reader.config_mut().expand_empty_elements = false;
+
+    let mut buf = Vec::with_capacity(0);
+    let mut tokens = Vec::with_capacity(limits.max_tokens.min(1024));
+    let mut element_stack: Vec<ElementType> = Vec::with_capacity(limits.max_nesting.min(64));
+    let mut core = TokenizerCore::new(limits);
+
+    drive_reader(
+        &mut reader,
+        &mut buf,
+        &mut core,
+        &mut element_stack,
+        &mut tokens,
+    )?;
+    core.finish(&mut element_stack, &mut tokens, html.len())?;
+
+    Ok(tokens)
+}
+
+/// Incremental XHTML tokenizer fed with arbitrary byte chunks.
+///
+/// Unlike [`tokenize_html_limited`], the full chapter never has to be in
+/// memory: bytes are buffered only until the next complete markup boundary,
+/// so chunks may split tags, comments, entities, or multi-byte UTF-8
+/// sequences anywhere. This makes it suitable for driving directly from
+/// `StreamingZip` chunk reads.
+///
+/// The emitted token sequence matches [`tokenize_html_limited`] for the same
+/// input, except that a text run longer than `max_text_bytes` is split at
+/// whitespace into several `Token::Text` tokens instead of being truncated.
+///
+/// # Allocation behavior
+/// - Buffers at most one unterminated construct (tag, comment, text run)
+/// - Returns an error instead of buffering a tag/comment beyond `max_text_bytes`
+/// - Token and nesting limits are enforced as in [`tokenize_html_limited`]
+///
+/// # Example
+/// ```
+/// use mu_epub::tokenizer::{Token, Tokenizer, TokenizeLimits};
+///
+/// let mut tokenizer = Tokenizer::new(TokenizeLimits::default());
+/// let mut tokens: Vec<Token> = Vec::with_capacity(0);
+/// for chunk in [&b"<p>Hel"[..], b"lo <e", b"m>world</em></p>"] {
+///     tokens.extend(tokenizer.feed(chunk).unwrap());
+/// }
+/// tokens.extend(tokenizer.finish().unwrap());
+/// assert_eq!(tokens[0], Token::Text("Hello".to_string()));
+/// ```
+#[derive(Clone)]
+pub struct Tokenizer {
+    core: TokenizerCore,
+    element_stack: Vec<ElementType>,
+    open_names: Vec<u64>,
+    /// Bytes received but not yet parsed (always starts at a markup boundary).
+    pending: Vec<u8>,
+    xml_buf: Vec<u8>,
+    out: Vec<SpannedToken>,
+    scan: BoundaryScan,
+    /// Total bytes parsed and removed from `pending` (span base offset).
+    consumed: usize,
+    finished: bool,
+}
+
+impl Tokenizer {
+    /// Create an incremental tokenizer with the given limits.
+    pub fn new(limits: TokenizeLimits) -> Self {
+        Self {
+            core: TokenizerCore::new(limits),
+            element_stack: Vec::with_capacity(limits.max_nesting.min(64)),
+            open_names: Vec::with_capacity(limits.max_nesting.min(64)),
+            pending: Vec::with_capacity(limits.max_text_bytes.min(8 * 1024)),
+            xml_buf: Vec::with_capacity(0),
+            out: Vec::with_capacity(64),
+            scan: BoundaryScan::default(),
+            consumed: 0,
+            finished: false,
+        }
+    }
+
+    /// Create an incremental tokenizer sized from streaming chunk limits.
+    ///
+    /// `max_stack_depth` bounds nesting and `max_text_accumulation` bounds
+    /// buffered text/markup; the token cap uses [`TokenizeLimits::default`].
+    pub fn with_chunk_limits(chunk: crate::streaming::ChunkLimits) -> Self {
+        Self::new(TokenizeLimits {
+            max_nesting: chunk.max_stack_depth,
+            max_text_bytes: chunk.max_text_accumulation,
+            ..TokenizeLimits::default()
+        })
+    }
+
+    /// Resolve list marker styles through the `list-style-type` rules of
+    /// `sheets`, in addition to inline declarations.
+    pub fn with_stylesheets(mut self, sheets: &[Stylesheet]) -> Self {
+        self.core.list_rules = list_rules(sheets);
+        self
+    }
+
+    /// Feed the next chunk of chapter bytes.
+    ///
+    /// Returns the tokens that became complete with this chunk. Tokens that
+    /// depend on bytes not yet seen (e.g. a text run that may continue) are
+    /// held back until a later `feed` or [`finish`](Self::finish).
+    pub fn feed(
+        &mut self,
+        chunk: &[u8],
+    ) -> Result<impl Iterator<Item = Token> + '_, TokenizeError> {
+        Ok(self.feed_spanned(chunk)?.map(|spanned| spanned.token))
+    }
+
+    /// Signal end of input and return all remaining tokens.
+    ///
+    /// Unclosed formatting elements are closed as in the one-shot APIs;
+    /// truncated markup is reported as a parse error.
+    pub fn finish(&mut self) -> Result<impl Iterator<Item = Token> + '_, TokenizeError> {
+        Ok(self.finish_spanned()?.map(|spanned| spanned.token))
+    }
+
+    /// Like [`feed`](Self::feed), but yields tokens with spans relative to
+    /// the first byte ever fed.
+    pub fn feed_spanned(&mut self, chunk: &[u8]) -> Result<Drain<'_, SpannedToken>, TokenizeError> {
+        if self.finished {
+            return Err(TokenizeError::InvalidStructure(
+                "Tokenizer fed after finish".to_string(),
+            ));
+        }
+        self.pending.extend_from_slice(chunk);
+        self.scan.advance(&self.pending);
+
+        let ready = self.scan.safe;
+        if ready > 0 {
+            self.parse_pending(ready)?;
+            self.consume_pending(ready);
+        }
+
+        let max_pending = self.core.limits.max_text_bytes;
+        while self.pending.len() > max_pending {
+            if !self.scan.in_text() {
+                return Err(TokenizeError::InvalidStructure(format!(
+                    "Unterminated markup exceeds max_text_bytes ({})",
+                    max_pending
+                )));
+            }
+            // Oversized text run: emit it in whitespace-delimited pieces so words stay intact.
+            let Some(ws) = self.pending[..=max_pending]
+                .iter()
+                .rposition(|b| b.is_ascii_whitespace())
+            else {
+                return Err(TokenizeError::InvalidStructure(format!(
+                    "Text run without whitespace exceeds max_text_bytes ({})",
+                    max_pending
+                )));
+            };
+            self.parse_pending(ws + 1)?;
+            self.consume_pending(ws + 1);
+        }
+
+        // A trailing text token may still absorb a following entity reference.
+        let hold = usize::from(matches!(
+            self.out.last(),
+            Some(SpannedToken {
+                token: Token::Text(_),
+                ..
+            })
+        ));
+        let ready_tokens = self.out.len() - hold;
+        Ok(self.out.drain(..ready_tokens))
+    }
+
+    /// Like [`finish`](Self::finish), but yields tokens with spans.
+    pub fn finish_spanned(&mut self) -> Result<Drain<'_, SpannedToken>, TokenizeError> {
+        if !self.finished {
+            self.finished = true;
+            let remaining = self.pending.len();
+            if remaining > 0 {
+                self.parse_pending(remaining)?;
+                self.consume_pending(remaining);
+            }
+            self.scan = BoundaryScan::default();
+            self.core
+                .finish(&mut self.element_stack, &mut self.out, self.consumed)?;
+        }
+        Ok(self.out.drain(..))
+    }
+
+    /// Reset to tokenize a new document, keeping allocated buffers.
+    pub fn reset(&mut self) {
+        let list_rules = core::mem::take(&mut self.core.list_rules);
+        self.core = TokenizerCore::new(self.core.limits);
+        self.core.list_rules = list_rules;
+        self.element_stack.clear();
+        self.open_names.clear();
+        self.pending.clear();
+        self.xml_buf.clear();
+        self.out.clear();
+        self.scan = BoundaryScan::default();
+        self.consumed = 0;
+        self.finished = false;
+    }
+
+    fn consume_pending(&mut self, len: usize) {
+        self.pending.drain(..len);
+        self.scan.consumed(len);
+        self.consumed += len;
+    }
+
+    fn parse_pending(&mut self, len: usize) -> Result<(), TokenizeError> {
+        let mut reader = Reader::from_reader(&self.pending[..len]);
+        reader.config_mut().trim_text(false);
+        reader.config_mut().expand_empty_elements = false;
+        // Each segment gets a fresh reader, so end-tag matching is tracked here.
+        reader.config_mut().check_end_names = false;
+        reader.config_mut().allow_unmatched_ends = true;
+
+        loop {
+            self.xml_buf.clear();
+            let start = reader.buffer_position() as usize;
+            match reader.read_event_into(&mut self.xml_buf) {
+                Ok(Event::Eof) => break,
+                Ok(event) => {
+                    let span =
+                        Span::new(start, reader.buffer_position() as usize).offset(self.consumed);
+                    match &event {
+                        Event::Start(e) => self.open_names.push(name_hash(e.name().as_ref())),
+                        Event::End(e) => {
+                            let hash = name_hash(e.name().as_ref());
+                            if self.open_names.pop() != Some(hash) {
+                                let name = decode_name(e.name().as_ref(), &reader)?;
+                                return Err(TokenizeError::ParseError(format!(
+                                    "XML error: mismatched end tag </{}>",
+                                    name
+                                )));
+                            }
+                        }
+                        _ => {}
+                    }
+                    self.core.handle_event(
+                        event,
+                        span,
+                        &reader,
+                        &mut self.element_stack,
+                        &mut self.out,
+                    )?;
+                }
+                Err(e) => {
+                    return Err(TokenizeError::ParseError(format!("XML error: {:?}", e)));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// FNV-1a hash of an element name for cheap end-tag matching.
+fn name_hash(name: &[u8]) -> u64 {
+    name.iter().fold(0xcbf2_9ce4_8422_2325u64, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
+
+/// Lexical state used to find the last complete markup boundary.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+enum ScanState {
+    /// Character data between markup constructs.
+    #[default]
+    Text,
+    /// Saw `<`; waiting for enough bytes to classify the construct.
+    MarkupStart,
+    /// Inside a tag or declaration, tracking quotes and `[...]` subsets.
+    Tag {
+        quote: Option<u8>,
+        bracket_depth: u8,
+    },
+    /// Inside a comment, CDATA section, or PI until the terminator appears.
+    Until(&'static [u8]),
+}
+
+/// Incremental scanner over the pending byte buffer.
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct BoundaryScan {
+    state: ScanState,
+    /// Next byte to examine.
+    pos: usize,
+    /// Start of the construct currently being scanned.
+    construct_start: usize,
+    /// Bytes before this offset form complete markup and text.
+    pub(crate) safe: usize,
+}
+
+impl BoundaryScan {
+    fn in_text(&self) -> bool {
+        self.state == ScanState::Text
+    }
+
+    pub(crate) fn advance(&mut self, buf: &[u8]) {
+        while self.pos < buf.len() {
+            match self.state {
+                ScanState::Text => match buf[self.pos..].iter().position(|b| *b == b'<') {
+                    Some(offset) => {
+                        self.pos += offset;
+                        self.safe = self.pos;
+                        self.construct_start = self.pos;
+                        self.state = ScanState::MarkupStart;
+                    }
+                    None => self.pos = buf.len(),
+                },
+                ScanState::MarkupStart => {
+                    let rest = &buf[self.construct_start..];
+                    let (state, skip) = if rest.starts_with(b"<!--") {
+                        (ScanState::Until(b"-->"), 4)
+                    } else if rest.starts_with(b"<![CDATA[") {
+                        (ScanState::Until(b"]]>"), 9)
+                    } else if rest.starts_with(b"<?") {
+                        (ScanState::Until(b"?>"), 2)
+                    } else if b"<!--".starts_with(rest) || b"<![CDATA[".starts_with(rest) {
+                        // Not enough bytes to tell a comment/CDATA from a tag yet.
+                        self.pos = buf.len();
+                        return;
+                    } else {
+                        (
+                            ScanState::Tag {
+                                quote: None,
+                                bracket_depth: 0,
+                            },
+                            1,
+                        )
+                    };
+                    self.state = state;
+                    self.pos = self.construct_start + skip;
+                }
+                ScanState::Tag {
+                    mut quote,
+                    mut bracket_depth,
+                } => {
+                    let mut end = None;
+                    for (offset, byte) in buf[self.pos..].iter().enumerate() {
+                        match (quote, *byte) {
+                            (Some(q), b) if b == q => quote = None,
+                            (Some(_), _) => {}
+                            (None, b'"' | b'\'') => quote = Some(*byte),
+                            (None, b'[') => bracket_depth = bracket_depth.saturating_add(1),
+                            (None, b']') => bracket_depth = bracket_depth.saturating_sub(1),
+                            (None, b'>') if bracket_depth == 0 => {
+                                end = Some(self.pos + offset + 1);
+                                break;
+                            }
+                            _ => {}
+                        }
+                    }
+                    match end {
+                        Some(end) => self.close_construct(end),
+                        None => {
+                            self.state = ScanState::Tag {
+                                quote,
+                                bracket_depth,
+                            };
+                            self.pos = buf.len();
+                        }
+                    }
+                }
+                ScanState::Until(terminator) => {
+                    match buf[self.pos..]
+                        .windows(terminator.len())
+                        .position(|window| window == terminator)
+                    {
+                        Some(offset) => self.close_construct(self.pos + offset + terminator.len()),
+                        None => {
+                            // Re-examine a possibly split terminator on the next feed.
+                            self.pos = buf.len().saturating_sub(terminator.len() - 1).max(self.pos);
+                            return;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    fn close_construct(&mut self, end: usize) {
+        self.state = ScanState::Text;
+        self.pos = end;
+        self.safe = end;
+    }
+
+    /// Rebase offsets after `len` bytes were removed from the buffer front.
+    pub(crate) fn consumed(&mut self, len: usize) {
+        self.pos = self.pos.saturating_sub(len);
+        self.safe = self.safe.saturating_sub(len);
+        self.construct_start = self.construct_start.saturating_sub(len);
+    }
+}
+
+/// Run a reader to EOF, routing every event through the shared tokenizer core.
+fn drive_reader<S: TokenSink>(
+    reader: &mut Reader<&[u8]>,
+    buf: &mut Vec<u8>,
+    core: &mut TokenizerCore,
+    element_stack: &mut Vec<ElementType>,
+    tokens: &mut S,
+) -> Result<(), TokenizeError> {
     loop {
-        match reader.read_event_into(&mut buf) {
-            Ok(Event::Start(e)) => {
-                let name = decode_name(e.name().as_ref(), &reader)?;
+        let start = reader.buffer_position() as usize;
+        match reader.read_event_into(buf) {
+            Ok(Event::Eof) => break,
+            Ok(event) => {
+                let span = Span::new(start, reader.buffer_position() as usize);
+                core.handle_event(event, span, reader, element_stack, tokens)?;
+            }
+            Err(e) => {
+                return Err(TokenizeError::ParseError(format!("XML error: {:?}", e)));
+            }
+        }
+        buf.clear();
+    }
+    Ok(())
+}
+
+/// Destination for tokens produced by the shared tokenizer core.
+trait TokenSink {
+    fn push_token(&mut self, token: Token, span: Span);
+
+    /// Last token's text if it is `Token::Text`, extending its span to `end`.
+    fn last_text_mut(&mut self, end: usize) -> Option<&mut String>;
+}
+
+impl TokenSink for Vec<Token> {
+    fn push_token(&mut self, token: Token, _span: Span) {
+        self.push(token);
+    }
+
+    fn last_text_mut(&mut self, _end: usize) -> Option<&mut String> {
+        match self.last_mut() {
+            Some(Token::Text(text)) => Some(text),
+            _ => None,
+        }
+    }
+}
+
+impl TokenSink for Vec<SpannedToken> {
+    fn push_token(&mut self, token: Token, span: Span) {
+        self.push(SpannedToken { token, span });
+    }
+
+    fn last_text_mut(&mut self, end: usize) -> Option<&mut String> {
+        match self.last_mut() {
+            Some(SpannedToken {
+                token: Token::Text(text),
+                span,
+            }) => {
+                span.end = span.end.max(end);
+                Some(text)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Tokenizer warm start for chapters that open with the same bytes.
+///
+/// Chapters of one book usually repeat an identical XML declaration,
+/// doctype, namespaces, and `<head>`. The cache keeps the first
+/// `max_prefix` bytes of the last chapter it saw; when the next chapter
+/// shares a prefix with them, that prefix (cut back to a markup boundary)
+/// is tokenized once and the [`Tokenizer`] state after it is kept. Later
+/// chapters starting with the same bytes resume from that state instead of
+/// re-parsing the boilerplate. Output matches a fresh [`Tokenizer`].
+///
+/// Inflating the entry is unaffected: ZIP entries are compressed
+/// independently, so only the parse of the shared bytes is skipped.
+#[derive(Clone)]
+pub struct PrefixCache {
+    limits: TokenizeLimits,
+    max_prefix: usize,
+    /// Leading bytes of the most recent chapter.
+    recent: Vec<u8>,
+    /// Shared prefix, tokenizer state after it, and the tokens it produced.
+    warm: Option<(Vec<u8>, Tokenizer, Vec<SpannedToken>)>,
+    stats: PrefixCacheStats,
+}
+
+/// Counters for [`PrefixCache`] reuse.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PrefixCacheStats {
+    /// Chapters tokenized through the cache.
+    pub chapters: usize,
+    /// Chapters that resumed from the cached prefix state.
+    pub hits: usize,
+    /// Prefix bytes not re-tokenized thanks to hits.
+    pub bytes_skipped: usize,
+}
+
+impl PrefixCache {
+    /// Cache comparing up to `max_prefix` leading bytes per chapter.
+    pub fn new(limits: TokenizeLimits, max_prefix: usize) -> Self {
+        Self {
+            limits,
+            max_prefix,
+            recent: Vec::with_capacity(max_prefix.min(8 * 1024)),
+            warm: None,
+            stats: PrefixCacheStats::default(),
+        }
+    }
+
+    /// Tokenize a chapter, streaming tokens to `on_token`.
+    pub fn tokenize_with<F>(&mut self, html: &[u8], mut on_token: F) -> Result<(), TokenizeError>
+    where
+        F: FnMut(Token),
+    {
+        self.stats.chapters += 1;
+        let (mut tokenizer, rest) = match &self.warm {
+            Some((prefix, state, tokens)) if html.starts_with(prefix) => {
+                self.stats.hits += 1;
+                self.stats.bytes_skipped += prefix.len();
+                tokens.iter().for_each(|t| on_token(t.token.clone()));
+                (state.clone(), &html[prefix.len()..])
+            }
+            _ => self.learn_prefix(html, &mut on_token)?,
+        };
+        tokenizer.feed(rest)?.for_each(&mut on_token);
+        tokenizer.finish()?.for_each(&mut on_token);
+
+        self.recent.clear();
+        self.recent
+            .extend_from_slice(&html[..html.len().min(self.max_prefix)]);
+        Ok(())
+    }
+
+    /// Tokenize a chapter into a vector.
+    pub fn tokenize(&mut self, html: &[u8]) -> Result<Vec<Token>, TokenizeError> {
+        let mut tokens = Vec::with_capacity((html.len() / 10).min(10000));
+        self.tokenize_with(html, |token| tokens.push(token))?;
+        Ok(tokens)
+    }
+
+    /// Reuse counters so far.
+    pub fn stats(&self) -> PrefixCacheStats {
+        self.stats
+    }
+
+    /// Length of the cached shared prefix, `0` when none is learned yet.
+    pub fn prefix_len(&self) -> usize {
+        self.warm.as_ref().map_or(0, |(prefix, _, _)| prefix.len())
+    }
+
+    /// Tokenize the prefix `html` shares with the previous chapter,
+    /// remembering the state after it. Returns the tokenizer positioned
+    /// there and the bytes still to feed.
+    fn learn_prefix<'h, F>(
+        &mut self,
+        html: &'h [u8],
+        on_token: &mut F,
+    ) -> Result<(Tokenizer, &'h [u8]), TokenizeError>
+    where
+        F: FnMut(Token),
+    {
+        let mut tokenizer = Tokenizer::new(self.limits);
+        let common = self
+            .recent
+            .iter()
+            .zip(html)
+            .take_while(|(a, b)| a == b)
+            .count();
+        let mut scan = BoundaryScan::default();
+        scan.advance(&html[..common]);
+        let boundary = scan.safe;
+        if boundary == 0 {
+            return Ok((tokenizer, html));
+        }
+
+        let tokens: Vec<SpannedToken> = tokenizer.feed_spanned(&html[..boundary])?.collect();
+        tokens.iter().for_each(|t| on_token(t.token.clone()));
+        self.warm = Some((html[..boundary].to_vec(), tokenizer.clone(), tokens));
+        Ok((tokenizer, &html[boundary..]))
+    }
+}
+
+/// Event-driven tokenizer state shared by the one-shot and incremental APIs.
+#[derive(Clone)]
+struct TokenizerCore {
+    limits: TokenizeLimits,
+    /// Depth inside elements whose content is skipped (script, style, head).
+    skip_depth: usize,
+    /// Whether a paragraph break is owed before the next block.
+    pending_paragraph_break: bool,
+    /// Heading token owed before the heading's first content, with the
+    /// span of its start tag.
+    pending_heading_close: Option<(u8, Span)>,
+    token_count: usize,
+    /// Entity and character references expanded so far.
+    entity_count: usize,
+    /// Depth inside `<pre>` and inline code elements, where text whitespace
+    /// is preserved.
+    pre_depth: usize,
+    /// Whether the previous event opened an outermost `<pre>`; a single
+    /// newline directly after it is dropped, as in HTML.
+    pre_leading_newline: bool,
+    /// Whether the last text token is verbatim text that following text or
+    /// references in the same element continue.
+    verbatim_continues: bool,
+    /// Depth inside elements flattened by [`NestingOverflow::Flatten`].
+    flattened_depth: usize,
+    /// Stylesheet rules that set `list-style-type`.
+    list_rules: Stylesheet,
+    /// Span of the event currently being handled.
+    span: Span,
+}
+
+impl TokenizerCore {
+    fn new(limits: TokenizeLimits) -> Self {
+        Self {
+            limits,
+            skip_depth: 0,
+            pending_paragraph_break: false,
+            pending_heading_close: None,
+            token_count: 0,
+            entity_count: 0,
+            pre_depth: 0,
+            pre_leading_newline: false,
+            verbatim_continues: false,
+            flattened_depth: 0,
+            list_rules: Stylesheet::new(),
+            span: Span::default(),
+        }
+    }
+
+    fn push<S: TokenSink>(&mut self, tokens: &mut S, token: Token) -> Result<(), TokenizeError> {
+        let span = self.span;
+        self.push_spanned(tokens, token, span)
+    }
+
+    fn push_spanned<S: TokenSink>(
+        &mut self,
+        tokens: &mut S,
+        token: Token,
+        span: Span,
+    ) -> Result<(), TokenizeError> {
+        if self.token_count >= self.limits.max_tokens {
+            return Err(TokenizeError::LimitExceeded {
+                kind: "max_tokens",
+                actual: self.token_count + 1,
+                limit: self.limits.max_tokens,
+                offset: span.start,
+            });
+        }
+        tokens.push_token(token, span);
+        self.token_count += 1;
+        Ok(())
+    }
+
+    /// Emit a heading token owed from an earlier start tag, if any.
+    fn flush_pending_heading<S: TokenSink>(
+        &mut self,
+        tokens: &mut S,
+    ) -> Result<bool, TokenizeError> {
+        match self.pending_heading_close.take() {
+            Some((level, span)) => {
+                self.push_spanned(tokens, Token::Heading(level), span)?;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    /// Reject elements carrying more than `max_attributes` attributes.
+    fn check_attributes(&self, e: &BytesStart<'_>) -> Result<(), TokenizeError> {
+        let limit = self.limits.max_attributes;
+        let count = e
+            .attributes()
+            .with_checks(false)
+            .take(limit.saturating_add(1))
+            .count();
+        if count > limit {
+            return Err(TokenizeError::LimitExceeded {
+                kind: "max_attributes",
+                actual: count,
+                limit,
+                offset: self.span.start,
+            });
+        }
+        Ok(())
+    }
+
+    /// Emit an anchor for the element's `id` attribute, if present.
+    fn push_anchor<S: TokenSink>(
+        &mut self,
+        e: &BytesStart<'_>,
+        reader: &Reader<&[u8]>,
+        tokens: &mut S,
+    ) -> Result<(), TokenizeError> {
+        match get_attribute(e, reader, "id") {
+            Some(id) if !id.is_empty() => self.push(tokens, Token::Anchor(id)),
+            _ => Ok(()),
+        }
+    }
+
+    /// Flush pending paragraph break and heading before a new element.
+    fn flush_pending_block<S: TokenSink>(&mut self, tokens: &mut S) -> Result<(), TokenizeError> {
+        if self.pending_paragraph_break && self.token_count > 0 {
+            self.push(tokens, Token::ParagraphBreak)?;
+            self.pending_paragraph_break = false;
+        }
+        if self.flush_pending_heading(tokens)? {
+            self.pending_paragraph_break = true;
+        }
+        Ok(())
+    }
+
+    fn handle_event<S: TokenSink>(
+        &mut self,
+        event: Event<'_>,
+        span: Span,
+        reader: &Reader<&[u8]>,
+        element_stack: &mut Vec<ElementType>,
+        tokens: &mut S,
+    ) -> Result<(), TokenizeError> {
+        self.span = span;
+        let after_pre_start = core::mem::take(&mut self.pre_leading_newline);
+        if matches!(event, Event::Start(_) | Event::End(_) | Event::Empty(_)) {
+            self.verbatim_continues = false;
+        }
+        match event {
+            Event::Start(e) => {
+                let name = decode_name(e.name().as_ref(), reader)?;
 
                 // Check if we should skip this element and its children
                 if should_skip_element(&name) {
-                    skip_depth += 1;
-                    continue;
+                    self.skip_depth += 1;
+                    return Ok(());
                 }
 
                 // If skipping, don't process anything
-                if skip_depth > 0 {
-                    continue;
+                if self.skip_depth > 0 {
+                    return Ok(());
                 }
 
-                // Check nesting limit
-                if element_stack.len() >= limits.max_nesting {
-                    return Err(TokenizeError::InvalidStructure(format!(
-                        "Nesting depth exceeds max_nesting ({})",
-                        limits.max_nesting
-                    )));
-                }
+                self.check_attributes(&e)?;
 
-                // Flush any pending paragraph break from previous block
-                if pending_paragraph_break && !tokens.is_empty() {
-                    if token_count >= limits.max_tokens {
-                        return Err(TokenizeError::InvalidStructure(format!(
-                            "Token count exceeds max_tokens ({}",
-                            limits.max_tokens
-                        )));
+                if element_stack.len() >= self.limits.max_nesting {
+                    if self.limits.nesting_overflow == NestingOverflow::Error {
+                        return Err(TokenizeError::LimitExceeded {
+                            kind: "max_nesting",
+                            actual: element_stack.len() + self.flattened_depth + 1,
+                            limit: self.limits.max_nesting,
+                            offset: span.start,
+                        });
                     }
-                    tokens.push(Token::ParagraphBreak);
-                    token_count += 1;
-                    pending_paragraph_break = false;
+                    self.flush_pending_block(tokens)?;
+                    self.push_anchor(&e, reader, tokens)?;
+                    self.flattened_depth += 1;
+                    return Ok(());
                 }
 
-                // Flush any pending heading close
-                if let Some(level) = pending_heading_close.take() {
-                    if token_count >= limits.max_tokens {
-                        return Err(TokenizeError::InvalidStructure(format!(
-                            "Token count exceeds max_tokens ({}",
-                            limits.max_tokens
-                        )));
-                    }
-                    tokens.push(Token::Heading(level));
-                    token_count += 1;
-                    pending_paragraph_break = true;
-                }
+                self.flush_pending_block(tokens)?;
+                self.push_anchor(&e, reader, tokens)?;
 
                 match name.as_str() {
                     "p" | "div" => {
@@ -227,97 +1201,82 @@ pub fn tokenize_html_limited(
                     "span" => {
                         element_stack.push(ElementType::Span);
                     }
+                    "pre" => {
+                        element_stack.push(ElementType::Preformatted);
+                        self.push(tokens, Token::PreformattedStart)?;
+                        self.pre_leading_newline = self.pre_depth == 0;
+                        self.pre_depth += 1;
+                    }
+                    "code" | "kbd" | "samp" => {
+                        element_stack.push(ElementType::Code);
+                        self.pre_depth += 1;
+                    }
+                    "hr" => {
+                        // <hr> as a start tag (non-self-closing)
+                        self.push(tokens, Token::Hr)?;
+                        self.pending_paragraph_break = true;
+                        element_stack.push(ElementType::Generic);
+                    }
                     h if h.starts_with('h') && h.len() == 2 => {
                         if let Some(level) = h.chars().nth(1).and_then(|c| c.to_digit(10)) {
                             if (1..=6).contains(&level) {
                                 element_stack.push(ElementType::Heading(level as u8));
-                                pending_heading_close = Some(level as u8);
+                                self.pending_heading_close = Some((level as u8, span));
                             }
                         }
                     }
                     "em" | "i" => {
                         element_stack.push(ElementType::Emphasis);
-                        if token_count >= limits.max_tokens {
-                            return Err(TokenizeError::InvalidStructure(format!(
-                                "Token count exceeds max_tokens ({}",
-                                limits.max_tokens
-                            )));
-                        }
-                        tokens.push(Token::Emphasis(true));
-                        token_count += 1;
+                        self.push(tokens, Token::Emphasis(true))?;
                     }
                     "strong" | "b" => {
                         element_stack.push(ElementType::Strong);
-                        if token_count >= limits.max_tokens {
-                            return Err(TokenizeError::InvalidStructure(format!(
-                                "Token count exceeds max_tokens ({}",
-                                limits.max_tokens
-                            )));
-                        }
-                        tokens.push(Token::Strong(true));
-                        token_count += 1;
+                        self.push(tokens, Token::Strong(true))?;
                     }
                     "ul" => {
                         element_stack.push(ElementType::UnorderedList);
-                        if token_count >= limits.max_tokens {
-                            return Err(TokenizeError::InvalidStructure(format!(
-                                "Token count exceeds max_tokens ({}",
-                                limits.max_tokens
-                            )));
+                        self.push(tokens, Token::ListStart(false))?;
+                        if let Some(style) = list_style_token(&e, reader, &self.list_rules, false) {
+                            self.push(tokens, style)?;
                         }
-                        tokens.push(Token::ListStart(false));
-                        token_count += 1;
                     }
                     "ol" => {
                         element_stack.push(ElementType::OrderedList);
-                        if token_count >= limits.max_tokens {
-                            return Err(TokenizeError::InvalidStructure(format!(
-                                "Token count exceeds max_tokens ({}",
-                                limits.max_tokens
-                            )));
+                        self.push(tokens, Token::ListStart(true))?;
+                        if let Some(style) = list_style_token(&e, reader, &self.list_rules, true) {
+                            self.push(tokens, style)?;
                         }
-                        tokens.push(Token::ListStart(true));
-                        token_count += 1;
                     }
                     "li" => {
                         element_stack.push(ElementType::ListItem);
-                        if token_count >= limits.max_tokens {
-                            return Err(TokenizeError::InvalidStructure(format!(
-                                "Token count exceeds max_tokens ({}",
-                                limits.max_tokens
-                            )));
+                        if let Some(value) = get_attribute(&e, reader, "value")
+                            .and_then(|v| v.trim().parse::<i32>().ok())
+                        {
+                            self.push(tokens, Token::ListItemValue(value))?;
                         }
-                        tokens.push(Token::ListItemStart);
-                        token_count += 1;
+                        self.push(tokens, Token::ListItemStart)?;
                     }
                     "a" => {
-                        if let Some(href) = get_attribute(&e, &reader, "href") {
+                        if let Some(href) = get_attribute(&e, reader, "href") {
                             element_stack.push(ElementType::Link);
-                            if token_count >= limits.max_tokens {
-                                return Err(TokenizeError::InvalidStructure(format!(
-                                    "Token count exceeds max_tokens ({}",
-                                    limits.max_tokens
-                                )));
-                            }
-                            tokens.push(Token::LinkStart(href));
-                            token_count += 1;
+                            self.push(tokens, Token::LinkStart(href))?;
                         } else {
                             // No href — treat as generic container
                             element_stack.push(ElementType::Generic);
                         }
                     }
+                    "figure" => {
+                        element_stack.push(ElementType::Figure);
+                        self.push(tokens, Token::FigureStart)?;
+                    }
+                    "figcaption" => {
+                        element_stack.push(ElementType::Caption);
+                        self.push(tokens, Token::CaptionStart)?;
+                    }
                     "img" => {
                         // <img> as a start tag (non-self-closing)
-                        if let Some(src) = get_attribute(&e, &reader, "src") {
-                            let alt = get_attribute(&e, &reader, "alt").unwrap_or_default();
-                            if token_count >= limits.max_tokens {
-                                return Err(TokenizeError::InvalidStructure(format!(
-                                    "Token count exceeds max_tokens ({}",
-                                    limits.max_tokens
-                                )));
-                            }
-                            tokens.push(Token::Image { src, alt });
-                            token_count += 1;
+                        if let Some(token) = image_token(&e, reader) {
+                            self.push(tokens, token)?;
                         }
                         element_stack.push(ElementType::Generic);
                     }
@@ -327,117 +1286,70 @@ pub fn tokenize_html_limited(
                     }
                 }
             }
-            Ok(Event::Text(e)) => {
+            Event::Text(e) => {
                 // Skip text if we're inside a script/style/head block
-                if skip_depth > 0 {
-                    continue;
+                if self.skip_depth > 0 {
+                    return Ok(());
                 }
 
                 let text = e
                     .decode()
-                    .map_err(|e| TokenizeError::ParseError(format!("Decode error: {:?}", e)))?
-                    .to_string();
-
-                // Normalize whitespace: collapse multiple spaces/newlines
-                let normalized = normalize_whitespace_limited(&text, limits.max_text_bytes);
-
-                if !normalized.is_empty() {
-                    // Flush any pending heading close
-                    if let Some(level) = pending_heading_close.take() {
-                        if token_count >= limits.max_tokens {
-                            return Err(TokenizeError::InvalidStructure(format!(
-                                "Token count exceeds max_tokens ({}",
-                                limits.max_tokens
-                            )));
-                        }
-                        tokens.push(Token::Heading(level));
-                        token_count += 1;
-                    }
-                    if token_count >= limits.max_tokens {
-                        return Err(TokenizeError::InvalidStructure(format!(
-                            "Token count exceeds max_tokens ({}",
-                            limits.max_tokens
-                        )));
-                    }
-                    tokens.push(Token::Text(normalized));
-                    token_count += 1;
-                }
+                    .map_err(|e| TokenizeError::ParseError(format!("Decode error: {:?}", e)))?;
+                self.handle_text(&text, after_pre_start, tokens)?;
             }
-            Ok(Event::End(e)) => {
-                let name = decode_name(e.name().as_ref(), &reader)?;
+            Event::End(e) => {
+                let name = decode_name(e.name().as_ref(), reader)?;
 
                 // Check if we're ending a skip element
                 if should_skip_element(&name) {
-                    skip_depth = skip_depth.saturating_sub(1);
-                    continue;
+                    self.skip_depth = self.skip_depth.saturating_sub(1);
+                    return Ok(());
                 }
 
                 // If skipping, don't process end tags
-                if skip_depth > 0 {
-                    continue;
+                if self.skip_depth > 0 {
+                    return Ok(());
+                }
+
+                if self.flattened_depth > 0 {
+                    self.flattened_depth -= 1;
+                    return Ok(());
                 }
 
                 // Pop the element from stack and emit appropriate close token
                 if let Some(element) = element_stack.pop() {
                     match element {
                         ElementType::Paragraph => {
-                            pending_paragraph_break = true;
+                            self.pending_paragraph_break = true;
                         }
                         ElementType::Heading(_level) => {
                             // Heading already emitted on start, just mark for paragraph break
-                            pending_paragraph_break = true;
+                            self.pending_paragraph_break = true;
                             // Clear any pending close since we already handled it
-                            pending_heading_close = None;
+                            self.pending_heading_close = None;
                         }
-                        ElementType::Emphasis => {
-                            if token_count >= limits.max_tokens {
-                                return Err(TokenizeError::InvalidStructure(format!(
-                                    "Token count exceeds max_tokens ({}",
-                                    limits.max_tokens
-                                )));
-                            }
-                            tokens.push(Token::Emphasis(false));
-                            token_count += 1;
+                        ElementType::Emphasis => self.push(tokens, Token::Emphasis(false))?,
+                        ElementType::Strong => self.push(tokens, Token::Strong(false))?,
+                        ElementType::UnorderedList | ElementType::OrderedList => {
+                            self.push(tokens, Token::ListEnd)?;
                         }
-                        ElementType::Strong => {
-                            if token_count >= limits.max_tokens {
-                                return Err(TokenizeError::InvalidStructure(format!(
-                                    "Token count exceeds max_tokens ({}",
-                                    limits.max_tokens
-                                )));
-                            }
-                            tokens.push(Token::Strong(false));
-                            token_count += 1;
+                        ElementType::ListItem => self.push(tokens, Token::ListItemEnd)?,
+                        ElementType::Link => self.push(tokens, Token::LinkEnd)?,
+                        ElementType::Preformatted => {
+                            self.push(tokens, Token::PreformattedEnd)?;
+                            self.pre_depth = self.pre_depth.saturating_sub(1);
+                            self.pending_paragraph_break = true;
                         }
-                        ElementType::UnorderedList | ElementType::OrderedList => {
-                            if token_count >= limits.max_tokens {
-                                return Err(TokenizeError::InvalidStructure(format!(
-                                    "Token count exceeds max_tokens ({}",
-                                    limits.max_tokens
-                                )));
-                            }
-                            tokens.push(Token::ListEnd);
-                            token_count += 1;
+                        ElementType::Code => {
+                            self.pre_depth = self.pre_depth.saturating_sub(1);
                         }
-                        ElementType::ListItem => {
-                            if token_count >= limits.max_tokens {
-                                return Err(TokenizeError::InvalidStructure(format!(
-                                    "Token count exceeds max_tokens ({}",
-                                    limits.max_tokens
-                                )));
-                            }
-                            tokens.push(Token::ListItemEnd);
-                            token_count += 1;
+                        ElementType::Figure => {
+                            self.push(tokens, Token::FigureEnd)?;
+                            self.pending_paragraph_break = true;
                         }
-                        ElementType::Link => {
-                            if token_count >= limits.max_tokens {
-                                return Err(TokenizeError::InvalidStructure(format!(
-                                    "Token count exceeds max_tokens ({}",
-                                    limits.max_tokens
-                                )));
-                            }
-                            tokens.push(Token::LinkEnd);
-                            token_count += 1;
+                        ElementType::Caption => {
+                            self.push(tokens, Token::CaptionEnd)?;
+                            self.pending_paragraph_break = true;
                         }
                         ElementType::Span | ElementType::Generic => {
                             // No tokens needed for these
@@ -445,126 +1357,72 @@ pub fn tokenize_html_limited(
                     }
                 }
             }
-            Ok(Event::Empty(e)) => {
-                let name = decode_name(e.name().as_ref(), &reader)?;
+            Event::Empty(e) => {
+                let name = decode_name(e.name().as_ref(), reader)?;
 
                 // Skip empty elements inside script/style blocks
-                if skip_depth > 0 {
-                    continue;
+                if self.skip_depth > 0 {
+                    return Ok(());
                 }
 
-                // Flush any pending paragraph break
-                if pending_paragraph_break && !tokens.is_empty() {
-                    if token_count >= limits.max_tokens {
-                        return Err(TokenizeError::InvalidStructure(format!(
-                            "Token count exceeds max_tokens ({}",
-                            limits.max_tokens
-                        )));
-                    }
-                    tokens.push(Token::ParagraphBreak);
-                    token_count += 1;
-                    pending_paragraph_break = false;
-                }
-
-                // Flush any pending heading close
-                if let Some(level) = pending_heading_close.take() {
-                    if token_count >= limits.max_tokens {
-                        return Err(TokenizeError::InvalidStructure(format!(
-                            "Token count exceeds max_tokens ({}",
-                            limits.max_tokens
-                        )));
-                    }
-                    tokens.push(Token::Heading(level));
-                    token_count += 1;
-                    pending_paragraph_break = true;
-                }
+                self.check_attributes(&e)?;
+                self.flush_pending_block(tokens)?;
+                self.push_anchor(&e, reader, tokens)?;
 
                 match name.as_str() {
-                    "br" => {
-                        if token_count >= limits.max_tokens {
-                            return Err(TokenizeError::InvalidStructure(format!(
-                                "Token count exceeds max_tokens ({}",
-                                limits.max_tokens
-                            )));
-                        }
-                        tokens.push(Token::LineBreak);
-                        token_count += 1;
-                    }
-                    "p" | "div" => {
+                    "br" => self.push(tokens, Token::LineBreak)?,
+                    "p" | "div" | "pre" => {
                         // Empty paragraph still creates a paragraph break
-                        pending_paragraph_break = true;
+                        self.pending_paragraph_break = true;
+                    }
+                    "hr" => {
+                        self.push(tokens, Token::Hr)?;
+                        self.pending_paragraph_break = true;
                     }
                     h if h.starts_with('h') && h.len() == 2 => {
                         if let Some(level) = h.chars().nth(1).and_then(|c| c.to_digit(10)) {
                             if (1..=6).contains(&level) {
                                 // Empty heading - just emit the heading token
-                                if token_count >= limits.max_tokens {
-                                    return Err(TokenizeError::InvalidStructure(format!(
-                                        "Token count exceeds max_tokens ({}",
-                                        limits.max_tokens
-                                    )));
-                                }
-                                tokens.push(Token::Heading(level as u8));
-                                token_count += 1;
-                                pending_paragraph_break = true;
+                                self.push(tokens, Token::Heading(level as u8))?;
+                                self.pending_paragraph_break = true;
                             }
                         }
                     }
                     "img" => {
-                        if let Some(src) = get_attribute(&e, &reader, "src") {
-                            let alt = get_attribute(&e, &reader, "alt").unwrap_or_default();
-                            if token_count >= limits.max_tokens {
-                                return Err(TokenizeError::InvalidStructure(format!(
-                                    "Token count exceeds max_tokens ({}",
-                                    limits.max_tokens
-                                )));
-                            }
-                            tokens.push(Token::Image { src, alt });
-                            token_count += 1;
-                        }
                         // No src → skip
+                        if let Some(token) = image_token(&e, reader) {
+                            self.push(tokens, token)?;
+                        }
                     }
                     _ => {
                         // Other empty elements are ignored
                     }
                 }
             }
-            Ok(Event::CData(e)) => {
+            Event::CData(e) => {
                 // CDATA content is treated as raw text
-                if skip_depth == 0 {
+                if self.skip_depth == 0 {
                     let text = reader
                         .decoder()
                         .decode(&e)
-                        .map_err(|e| TokenizeError::ParseError(format!("Decode error: {:?}", e)))?
-                        .to_string();
-
-                    let normalized = normalize_whitespace_limited(&text, limits.max_text_bytes);
-                    if !normalized.is_empty() {
-                        if let Some(level) = pending_heading_close.take() {
-                            if token_count >= limits.max_tokens {
-                                return Err(TokenizeError::InvalidStructure(format!(
-                                    "Token count exceeds max_tokens ({}",
-                                    limits.max_tokens
-                                )));
-                            }
-                            tokens.push(Token::Heading(level));
-                            token_count += 1;
-                        }
-                        if token_count >= limits.max_tokens {
-                            return Err(TokenizeError::InvalidStructure(format!(
-                                "Token count exceeds max_tokens ({}",
-                                limits.max_tokens
-                            )));
-                        }
-                        tokens.push(Token::Text(normalized));
-                        token_count += 1;
-                    }
+                        .map_err(|e| TokenizeError::ParseError(format!("Decode error: {:?}", e)))?;
+                    self.handle_text(&text, after_pre_start, tokens)?;
                 }
             }
-            Ok(Event::GeneralRef(e)) => {
+            Event::GeneralRef(e) => {
                 // Entity references: &amp; &lt; &gt; &quot; &apos; &#8220; etc.
-                if skip_depth > 0 {
-                    continue;
+                if self.skip_depth > 0 {
+                    return Ok(());
+                }
+
+                self.entity_count += 1;
+                if self.entity_count > self.limits.max_entity_expansions {
+                    return Err(TokenizeError::LimitExceeded {
+                        kind: "max_entity_expansions",
+                        actual: self.entity_count,
+                        limit: self.limits.max_entity_expansions,
+                        offset: span.start,
+                    });
                 }
 
                 let entity_name = e
@@ -578,125 +1436,95 @@ pub fn tokenize_html_limited(
 
                 if !resolved.is_empty() {
                     // Flush any pending heading close
-                    if let Some(level) = pending_heading_close.take() {
-                        if token_count >= limits.max_tokens {
-                            return Err(TokenizeError::InvalidStructure(format!(
-                                "Token count exceeds max_tokens ({}",
-                                limits.max_tokens
-                            )));
-                        }
-                        tokens.push(Token::Heading(level));
-                        token_count += 1;
-                    }
+                    self.flush_pending_heading(tokens)?;
                     // Append to the last Text token if possible, otherwise create new one
-                    if let Some(Token::Text(ref mut last_text)) = tokens.last_mut() {
-                        if last_text.len() + resolved.len() <= limits.max_text_bytes {
+                    if let Some(last_text) = tokens.last_text_mut(span.end) {
+                        if last_text.len() + resolved.len() <= self.limits.max_text_bytes {
                             last_text.push_str(&resolved);
                         }
                     } else {
-                        if token_count >= limits.max_tokens {
-                            return Err(TokenizeError::InvalidStructure(format!(
-                                "Token count exceeds max_tokens ({}",
-                                limits.max_tokens
-                            )));
-                        }
-                        tokens.push(Token::Text(resolved));
-                        token_count += 1;
+                        self.push(tokens, Token::Text(resolved))?;
                     }
+                    self.verbatim_continues = self.pre_depth > 0;
                 }
             }
-            Ok(Event::Comment(_)) => {
-                // Comments are ignored
-            }
-            Ok(Event::Decl(_)) => {
-                // XML declaration is ignored
-            }
-            Ok(Event::PI(_)) => {
-                // Processing instructions are ignored
-            }
-            Ok(Event::DocType(_)) => {
-                // DOCTYPE is ignored
-            }
-            Ok(Event::Eof) => break,
-            Err(e) => {
-                return Err(TokenizeError::ParseError(format!("XML error: {:?}", e)));
+            Event::Comment(_) | Event::Decl(_) | Event::PI(_) | Event::DocType(_) => {
+                // Comments, declarations, processing instructions are ignored
             }
+            Event::Eof => {}
         }
-        buf.clear();
+        Ok(())
     }
 
-    // Close any unclosed formatting tags
-    while let Some(element) = element_stack.pop() {
-        match element {
-            ElementType::Emphasis => {
-                if token_count >= limits.max_tokens {
-                    return Err(TokenizeError::InvalidStructure(format!(
-                        "Token count exceeds max_tokens ({}",
-                        limits.max_tokens
-                    )));
-                }
-                tokens.push(Token::Emphasis(false));
-                token_count += 1;
-            }
-            ElementType::Strong => {
-                if token_count >= limits.max_tokens {
-                    return Err(TokenizeError::InvalidStructure(format!(
-                        "Token count exceeds max_tokens ({}",
-                        limits.max_tokens
-                    )));
+    fn handle_text<S: TokenSink>(
+        &mut self,
+        text: &str,
+        after_pre_start: bool,
+        tokens: &mut S,
+    ) -> Result<(), TokenizeError> {
+        let normalized = if self.pre_depth > 0 {
+            let text = if after_pre_start {
+                text.strip_prefix('\n').unwrap_or(text)
+            } else {
+                text
+            };
+            // Verbatim text joins the text it continues (e.g. after an entity).
+            if self.verbatim_continues {
+                if let Some(last_text) = tokens.last_text_mut(self.span.end) {
+                    let room = self.limits.max_text_bytes.saturating_sub(last_text.len());
+                    last_text.push_str(&preserve_whitespace_limited(text, room));
+                    return Ok(());
                 }
-                tokens.push(Token::Strong(false));
-                token_count += 1;
             }
-            ElementType::UnorderedList | ElementType::OrderedList => {
-                if token_count >= limits.max_tokens {
-                    return Err(TokenizeError::InvalidStructure(format!(
-                        "Token count exceeds max_tokens ({}",
-                        limits.max_tokens
-                    )));
+            preserve_whitespace_limited(text, self.limits.max_text_bytes)
+        } else {
+            // Normalize whitespace: collapse multiple spaces/newlines
+            normalize_whitespace_limited(text, self.limits.max_text_bytes)
+        };
+        if normalized.is_empty() {
+            return Ok(());
+        }
+        // Flush any pending heading close
+        self.flush_pending_heading(tokens)?;
+        self.verbatim_continues = self.pre_depth > 0;
+        self.push(tokens, Token::Text(normalized))
+    }
+
+    /// Close unclosed formatting elements and flush a trailing heading.
+    fn finish<S: TokenSink>(
+        &mut self,
+        element_stack: &mut Vec<ElementType>,
+        tokens: &mut S,
+        input_len: usize,
+    ) -> Result<(), TokenizeError> {
+        self.span = Span::new(input_len, input_len);
+        // A trailing paragraph break is never emitted; breaks only separate blocks.
+        while let Some(element) = element_stack.pop() {
+            match element {
+                ElementType::Emphasis => self.push(tokens, Token::Emphasis(false))?,
+                ElementType::Strong => self.push(tokens, Token::Strong(false))?,
+                ElementType::UnorderedList | ElementType::OrderedList => {
+                    self.push(tokens, Token::ListEnd)?;
                 }
-                tokens.push(Token::ListEnd);
-                token_count += 1;
-            }
-            ElementType::ListItem => {
-                if token_count >= limits.max_tokens {
-                    return Err(TokenizeError::InvalidStructure(format!(
-                        "Token count exceeds max_tokens ({}",
-                        limits.max_tokens
-                    )));
+                ElementType::ListItem => self.push(tokens, Token::ListItemEnd)?,
+                ElementType::Link => self.push(tokens, Token::LinkEnd)?,
+                ElementType::Preformatted => {
+                    self.push(tokens, Token::PreformattedEnd)?;
+                    self.pre_depth = self.pre_depth.saturating_sub(1);
                 }
-                tokens.push(Token::ListItemEnd);
-                token_count += 1;
-            }
-            ElementType::Link => {
-                if token_count >= limits.max_tokens {
-                    return Err(TokenizeError::InvalidStructure(format!(
-                        "Token count exceeds max_tokens ({}",
-                        limits.max_tokens
-                    )));
+                ElementType::Code => self.pre_depth = self.pre_depth.saturating_sub(1),
+                ElementType::Figure => self.push(tokens, Token::FigureEnd)?,
+                ElementType::Caption => self.push(tokens, Token::CaptionEnd)?,
+                ElementType::Paragraph | ElementType::Heading(_) => {
+                    // These already handled via pending_paragraph_break
                 }
-                tokens.push(Token::LinkEnd);
-                token_count += 1;
-            }
-            ElementType::Paragraph | ElementType::Heading(_) => {
-                // These already handled via pending_paragraph_break
+                ElementType::Span | ElementType::Generic => {}
             }
-            _ => {}
         }
-    }
 
-    // Flush any pending heading close
-    if let Some(level) = pending_heading_close {
-        if token_count >= limits.max_tokens {
-            return Err(TokenizeError::InvalidStructure(format!(
-                "Token count exceeds max_tokens ({}",
-                limits.max_tokens
-            )));
-        }
-        tokens.push(Token::Heading(level));
+        self.flush_pending_heading(tokens)?;
+        Ok(())
     }
-
-    Ok(tokens)
 }
 
 /// Normalize whitespace with a byte limit.
@@ -727,10 +1555,21 @@ fn normalize_whitespace_limited(text: &str, max_bytes: usize) -> String {
     result
 }
 
+/// Copy preformatted text verbatim, truncated to a byte limit.
+fn preserve_whitespace_limited(text: &str, max_bytes: usize) -> String {
+    let mut end = text.len().min(max_bytes);
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    text[..end].to_string()
+}
+
 /// Types of elements we track in the stack
 #[derive(Clone, Debug, PartialEq)]
 enum ElementType {
     Paragraph,
+    Preformatted,
+    Code,
     Heading(u8),
     Emphasis,
     Strong,
@@ -739,41 +1578,88 @@ enum ElementType {
     OrderedList,
     ListItem,
     Link,
+    Figure,
+    Caption,
     Generic,
 }
 
 /// Check if an element should be skipped entirely (with its children)
+///
+/// Within an `epub:switch` only the `epub:default` branch is kept: the
+/// `epub:case` alternatives require markup we do not render and would
+/// otherwise duplicate the fallback text. `epub:trigger` has no content.
 fn should_skip_element(name: &str) -> bool {
     matches!(
         name,
-        "script" | "style" | "head" | "nav" | "header" | "footer" | "aside" | "noscript"
+        "script"
+            | "style"
+            | "head"
+            | "nav"
+            | "header"
+            | "footer"
+            | "aside"
+            | "noscript"
+            | "epub:case"
+            | "epub:trigger"
     )
 }
 
-/// Normalize whitespace in text content
-/// Collapses multiple spaces/newlines and trims ends
-fn normalize_whitespace(text: &str) -> String {
-    let mut result = String::with_capacity(text.len());
-    let mut prev_was_space = true; // Start true to trim leading whitespace
-
-    for ch in text.chars() {
-        if ch.is_whitespace() {
-            if !prev_was_space {
-                result.push(' ');
-                prev_was_space = true;
-            }
-        } else {
-            result.push(ch);
-            prev_was_space = false;
-        }
-    }
-
-    // Trim trailing space if present
-    if result.ends_with(' ') {
-        result.pop();
+/// Build a [`Token::ListStyle`] from a list element's `type`, `start`, and
+/// cascaded `list-style-type`, or `None` when all are absent.
+fn list_style_token(
+    e: &BytesStart,
+    reader: &Reader<&[u8]>,
+    list_rules: &Stylesheet,
+    ordered: bool,
+) -> Option<Token> {
+    let inline = get_attribute(e, reader, "style")
+        .and_then(|css| parse_inline_declarations(&css, &CustomProperties::new()).ok());
+    let css = if list_rules.rules.is_empty() {
+        inline.map(|inline| inline.cascaded())
+    } else {
+        let tag = if ordered { "ol" } else { "ul" };
+        let class_attr = get_attribute(e, reader, "class").unwrap_or_default();
+        let classes: Vec<&str> = class_attr.split_whitespace().collect();
+        Some(cascade(
+            core::slice::from_ref(list_rules),
+            tag,
+            &classes,
+            None,
+            inline.as_ref(),
+        ))
+    };
+    let style = css.and_then(|css| css.list_style_type).or_else(|| {
+        get_attribute(e, reader, "type").and_then(|t| ListStyleType::from_type_attr(&t))
+    });
+    let start = get_attribute(e, reader, "start").and_then(|v| v.trim().parse::<i32>().ok());
+    if style.is_none() && start.is_none() {
+        return None;
     }
+    let default_style = if ordered {
+        ListStyleType::Decimal
+    } else {
+        ListStyleType::Disc
+    };
+    Some(Token::ListStyle {
+        style: style.unwrap_or(default_style),
+        start: start.unwrap_or(1),
+    })
+}
 
-    result
+/// Build the [`Token::Image`] for an `<img>`, or `None` when it has no `src`.
+fn image_token(e: &BytesStart, reader: &Reader<&[u8]>) -> Option<Token> {
+    let src = get_attribute(e, reader, "src")?;
+    let alt = get_attribute(e, reader, "alt").unwrap_or_default();
+    let inline = get_attribute(e, reader, "style")
+        .and_then(|css| parse_inline_style(&css).ok())
+        .unwrap_or_default();
+    let attr = |name| get_attribute(e, reader, name).and_then(|v| Dimension::parse(&v));
+    Some(Token::Image {
+        src,
+        alt,
+        width: inline.width.or_else(|| attr("width")),
+        height: inline.height.or_else(|| attr("height")),
+    })
 }
 
 /// Extract a named attribute value from a start/empty element
@@ -926,352 +1812,59 @@ pub fn tokenize_html_into(html: &str, tokens_out: &mut Vec<Token>) -> Result<(),
 /// # Arguments
 /// * `html` - The XHTML content to tokenize
 /// * `tokens_out` - Output buffer for tokens (will be cleared first)
-/// * `scratch` - Scratch buffers for internal parsing state
-///
-/// # Returns
-/// * `Ok(())` on success
-/// * `Err(TokenizeError)` on parse failure
-///
-/// # Example
-/// ```
-/// use mu_epub::tokenizer::{tokenize_html_with_scratch, TokenizeScratch, Token};
-///
-/// let html = "<p>Hello <em>world</em></p>";
-/// let mut tokens: Vec<Token> = Vec::with_capacity(0);
-/// let mut scratch = TokenizeScratch::embedded();
-/// tokenize_html_with_scratch(html, &mut tokens, &mut scratch).unwrap();
-///
-/// // Reuse scratch for subsequent calls
-/// let html2 = "<p>Second paragraph</p>";
-/// tokenize_html_with_scratch(html2, &mut tokens, &mut scratch).unwrap();
-/// ```
-pub fn tokenize_html_with_scratch(
-    html: &str,
-    tokens_out: &mut Vec<Token>,
-    scratch: &mut TokenizeScratch,
-) -> Result<(), TokenizeError> {
-    tokens_out.clear();
-    scratch.clear();
-
-    let mut reader = Reader::from_str(html);
-    reader.config_mut().trim_text(false);
-    reader.config_mut().expand_empty_elements = false;
-
-    // Track if we're inside a tag that should be skipped (script, style, head)
-    let mut skip_depth: usize = 0;
-    // Track if we need a paragraph break after current block element
-    let mut pending_paragraph_break: bool = false;
-    // Track if we need a heading close after text content
-    let mut pending_heading_close: Option<u8> = None;
-
-    loop {
-        match reader.read_event_into(&mut scratch.xml_buf) {
-            Ok(Event::Start(e)) => {
-                let name = decode_name(e.name().as_ref(), &reader)?;
-
-                // Check if we should skip this element and its children
-                if should_skip_element(&name) {
-                    skip_depth += 1;
-                    continue;
-                }
-
-                // If skipping, don't process anything
-                if skip_depth > 0 {
-                    continue;
-                }
-
-                // Flush any pending paragraph break from previous block
-                if pending_paragraph_break && !tokens_out.is_empty() {
-                    tokens_out.push(Token::ParagraphBreak);
-                    pending_paragraph_break = false;
-                }
-
-                // Flush any pending heading close
-                if let Some(level) = pending_heading_close.take() {
-                    tokens_out.push(Token::Heading(level));
-                    pending_paragraph_break = true;
-                }
-
-                match name.as_str() {
-                    "p" | "div" => {
-                        scratch.element_buf.push(ElementType::Paragraph);
-                    }
-                    "span" => {
-                        scratch.element_buf.push(ElementType::Span);
-                    }
-                    h if h.starts_with('h') && h.len() == 2 => {
-                        if let Some(level) = h.chars().nth(1).and_then(|c| c.to_digit(10)) {
-                            if (1..=6).contains(&level) {
-                                scratch.element_buf.push(ElementType::Heading(level as u8));
-                                pending_heading_close = Some(level as u8);
-                            }
-                        }
-                    }
-                    "em" | "i" => {
-                        scratch.element_buf.push(ElementType::Emphasis);
-                        tokens_out.push(Token::Emphasis(true));
-                    }
-                    "strong" | "b" => {
-                        scratch.element_buf.push(ElementType::Strong);
-                        tokens_out.push(Token::Strong(true));
-                    }
-                    "ul" => {
-                        scratch.element_buf.push(ElementType::UnorderedList);
-                        tokens_out.push(Token::ListStart(false));
-                    }
-                    "ol" => {
-                        scratch.element_buf.push(ElementType::OrderedList);
-                        tokens_out.push(Token::ListStart(true));
-                    }
-                    "li" => {
-                        scratch.element_buf.push(ElementType::ListItem);
-                        tokens_out.push(Token::ListItemStart);
-                    }
-                    "a" => {
-                        if let Some(href) = get_attribute(&e, &reader, "href") {
-                            scratch.element_buf.push(ElementType::Link);
-                            tokens_out.push(Token::LinkStart(href));
-                        } else {
-                            // No href — treat as generic container
-                            scratch.element_buf.push(ElementType::Generic);
-                        }
-                    }
-                    "img" => {
-                        // <img> as a start tag (non-self-closing)
-                        if let Some(src) = get_attribute(&e, &reader, "src") {
-                            let alt = get_attribute(&e, &reader, "alt").unwrap_or_default();
-                            tokens_out.push(Token::Image { src, alt });
-                        }
-                        scratch.element_buf.push(ElementType::Generic);
-                    }
-                    _ => {
-                        // Unknown element, treat as generic container
-                        scratch.element_buf.push(ElementType::Generic);
-                    }
-                }
-            }
-            Ok(Event::Text(e)) => {
-                // Skip text if we're inside a script/style/head block
-                if skip_depth > 0 {
-                    continue;
-                }
-
-                let text = e
-                    .decode()
-                    .map_err(|e| TokenizeError::ParseError(format!("Decode error: {:?}", e)))?
-                    .to_string();
-
-                // Normalize whitespace: collapse multiple spaces/newlines
-                let normalized = normalize_whitespace(&text);
-
-                if !normalized.is_empty() {
-                    // Flush any pending heading close
-                    if let Some(level) = pending_heading_close.take() {
-                        tokens_out.push(Token::Heading(level));
-                    }
-                    tokens_out.push(Token::Text(normalized));
-                }
-            }
-            Ok(Event::End(e)) => {
-                let name = decode_name(e.name().as_ref(), &reader)?;
-
-                // Check if we're ending a skip element
-                if should_skip_element(&name) {
-                    skip_depth = skip_depth.saturating_sub(1);
-                    continue;
-                }
-
-                // If skipping, don't process end tags
-                if skip_depth > 0 {
-                    continue;
-                }
-
-                // Pop the element from stack and emit appropriate close token
-                if let Some(element) = scratch.element_buf.pop() {
-                    match element {
-                        ElementType::Paragraph => {
-                            pending_paragraph_break = true;
-                        }
-                        ElementType::Heading(_level) => {
-                            // Heading already emitted on start, just mark for paragraph break
-                            pending_paragraph_break = true;
-                            // Clear any pending close since we already handled it
-                            pending_heading_close = None;
-                        }
-                        ElementType::Emphasis => {
-                            tokens_out.push(Token::Emphasis(false));
-                        }
-                        ElementType::Strong => {
-                            tokens_out.push(Token::Strong(false));
-                        }
-                        ElementType::UnorderedList | ElementType::OrderedList => {
-                            tokens_out.push(Token::ListEnd);
-                        }
-                        ElementType::ListItem => {
-                            tokens_out.push(Token::ListItemEnd);
-                        }
-                        ElementType::Link => {
-                            tokens_out.push(Token::LinkEnd);
-                        }
-                        ElementType::Span | ElementType::Generic => {
-                            // No tokens needed for these
-                        }
-                    }
-                }
-            }
-            Ok(Event::Empty(e)) => {
-                let name = decode_name(e.name().as_ref(), &reader)?;
-
-                // Skip empty elements inside script/style blocks
-                if skip_depth > 0 {
-                    continue;
-                }
-
-                // Flush any pending paragraph break
-                if pending_paragraph_break && !tokens_out.is_empty() {
-                    tokens_out.push(Token::ParagraphBreak);
-                    pending_paragraph_break = false;
-                }
-
-                // Flush any pending heading close
-                if let Some(level) = pending_heading_close.take() {
-                    tokens_out.push(Token::Heading(level));
-                    pending_paragraph_break = true;
-                }
-
-                match name.as_str() {
-                    "br" => {
-                        tokens_out.push(Token::LineBreak);
-                    }
-                    "p" | "div" => {
-                        // Empty paragraph still creates a paragraph break
-                        pending_paragraph_break = true;
-                    }
-                    h if h.starts_with('h') && h.len() == 2 => {
-                        if let Some(level) = h.chars().nth(1).and_then(|c| c.to_digit(10)) {
-                            if (1..=6).contains(&level) {
-                                // Empty heading - just emit the heading token
-                                tokens_out.push(Token::Heading(level as u8));
-                                pending_paragraph_break = true;
-                            }
-                        }
-                    }
-                    "img" => {
-                        if let Some(src) = get_attribute(&e, &reader, "src") {
-                            let alt = get_attribute(&e, &reader, "alt").unwrap_or_default();
-                            tokens_out.push(Token::Image { src, alt });
-                        }
-                        // No src → skip
-                    }
-                    _ => {
-                        // Other empty elements are ignored
-                    }
-                }
-            }
-            Ok(Event::CData(e)) => {
-                // CDATA content is treated as raw text
-                if skip_depth == 0 {
-                    let text = reader
-                        .decoder()
-                        .decode(&e)
-                        .map_err(|e| TokenizeError::ParseError(format!("Decode error: {:?}", e)))?
-                        .to_string();
-
-                    let normalized = normalize_whitespace(&text);
-                    if !normalized.is_empty() {
-                        // Flush any pending heading close
-                        if let Some(level) = pending_heading_close.take() {
-                            tokens_out.push(Token::Heading(level));
-                        }
-                        tokens_out.push(Token::Text(normalized));
-                    }
-                }
-            }
-            Ok(Event::GeneralRef(e)) => {
-                // Entity references: &amp; &lt; &gt; &quot; &apos; &#8220; etc.
-                if skip_depth > 0 {
-                    continue;
-                }
-
-                let entity_name = e
-                    .decode()
-                    .map_err(|e| TokenizeError::ParseError(format!("Decode error: {:?}", e)))?;
-                // Reconstruct the entity string and unescape it
-                let entity_str = format!("&{};", entity_name);
-                let resolved = unescape(&entity_str)
-                    .map_err(|e| TokenizeError::ParseError(format!("Unescape error: {:?}", e)))?
-                    .to_string();
-
-                if !resolved.is_empty() {
-                    // Flush any pending heading close
-                    if let Some(level) = pending_heading_close.take() {
-                        tokens_out.push(Token::Heading(level));
-                    }
-                    // Append to the last Text token if possible, otherwise create new one
-                    if let Some(Token::Text(ref mut last_text)) = tokens_out.last_mut() {
-                        last_text.push_str(&resolved);
-                    } else {
-                        tokens_out.push(Token::Text(resolved));
-                    }
-                }
-            }
-            Ok(Event::Comment(_)) => {
-                // Comments are ignored
-            }
-            Ok(Event::Decl(_)) => {
-                // XML declaration is ignored
-            }
-            Ok(Event::PI(_)) => {
-                // Processing instructions are ignored
-            }
-            Ok(Event::DocType(_)) => {
-                // DOCTYPE is ignored
-            }
-            Ok(Event::Eof) => break,
-            Err(e) => {
-                return Err(TokenizeError::ParseError(format!("XML error: {:?}", e)));
-            }
-        }
-        scratch.xml_buf.clear();
-    }
-
-    // Flush any remaining pending paragraph break
-    if pending_paragraph_break && !tokens_out.is_empty() {
-        // Don't add trailing paragraph break
-        // tokens_out.push(Token::ParagraphBreak);
-    }
+/// * `scratch` - Scratch buffers for internal parsing state
+///
+/// # Returns
+/// * `Ok(())` on success
+/// * `Err(TokenizeError)` on parse failure
+///
+/// # Example
+/// ```
+/// use mu_epub::tokenizer::{tokenize_html_with_scratch, TokenizeScratch, Token};
+///
+/// let html = "<p>Hello <em>world</em></p>";
+/// let mut tokens: Vec<Token> = Vec::with_capacity(0);
+/// let mut scratch = TokenizeScratch::embedded();
+/// tokenize_html_with_scratch(html, &mut tokens, &mut scratch).unwrap();
+///
+/// // Reuse scratch for subsequent calls
+/// let html2 = "<p>Second paragraph</p>";
+/// tokenize_html_with_scratch(html2, &mut tokens, &mut scratch).unwrap();
+/// ```
+pub fn tokenize_html_with_scratch(
+    html: &str,
+    tokens_out: &mut Vec<Token>,
+    scratch: &mut TokenizeScratch,
+) -> Result<(), TokenizeError> {
+    tokenize_with_core(
+        html,
+        tokens_out,
+        scratch,
+        TokenizerCore::new(TokenizeLimits::unbounded()),
+    )
+}
 
-    // Close any unclosed formatting tags
-    while let Some(element) = scratch.element_buf.pop() {
-        match element {
-            ElementType::Emphasis => {
-                tokens_out.push(Token::Emphasis(false));
-            }
-            ElementType::Strong => {
-                tokens_out.push(Token::Strong(false));
-            }
-            ElementType::UnorderedList | ElementType::OrderedList => {
-                tokens_out.push(Token::ListEnd);
-            }
-            ElementType::ListItem => {
-                tokens_out.push(Token::ListItemEnd);
-            }
-            ElementType::Link => {
-                tokens_out.push(Token::LinkEnd);
-            }
-            ElementType::Paragraph | ElementType::Heading(_) => {
-                // These already handled via pending_paragraph_break
-            }
-            _ => {}
-        }
-    }
+fn tokenize_with_core(
+    html: &str,
+    tokens_out: &mut Vec<Token>,
+    scratch: &mut TokenizeScratch,
+    mut core: TokenizerCore,
+) -> Result<(), TokenizeError> {
+    tokens_out.clear();
+    scratch.clear();
 
-    // Flush any pending heading close
-    if let Some(level) = pending_heading_close {
-        tokens_out.push(Token::Heading(level));
-    }
+    let mut reader = Reader::from_str(html);
+    reader.config_mut().trim_text(false);
+    reader.config_mut().expand_empty_elements = false;
 
-    Ok(())
+    drive_reader(
+        &mut reader,
+        &mut scratch.xml_buf,
+        &mut core,
+        &mut scratch.element_buf,
+        tokens_out,
+    )?;
+    core.finish(&mut scratch.element_buf, tokens_out, html.len())
 }
 
 #[cfg(test)]
@@ -1397,6 +1990,24 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_epub_switch_keeps_only_default_branch() {
+        let html = r#"<p>Before</p><epub:switch id="eq"><epub:case required-namespace="http://www.w3.org/1998/Math/MathML"><math><mi>x</mi></math></epub:case><epub:default><p>x squared</p></epub:default></epub:switch><epub:trigger action="show" ref="eq"/><p>After</p>"#;
+        let tokens = tokenize_html(html).unwrap();
+
+        assert_eq!(
+            tokens,
+            vec![
+                Token::Text("Before".to_string()),
+                Token::ParagraphBreak,
+                Token::Anchor("eq".to_string()),
+                Token::Text("x squared".to_string()),
+                Token::ParagraphBreak,
+                Token::Text("After".to_string()),
+            ]
+        );
+    }
+
     #[test]
     fn test_strip_head() {
         let html = "<head><title>Title</title></head><body><p>Content</p></body>";
@@ -1559,6 +2170,78 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_list_type_start_and_value_attributes() {
+        let html = r#"<ol type="a" start="5"><li>First</li><li value="9">Ninth</li></ol><ul style="list-style-type: square"><li>Dot</li></ul><ol type="X"><li>Plain</li></ol>"#;
+        let tokens = tokenize_html(html).unwrap();
+
+        assert_eq!(
+            tokens,
+            vec![
+                Token::ListStart(true),
+                Token::ListStyle {
+                    style: ListStyleType::LowerAlpha,
+                    start: 5,
+                },
+                Token::ListItemStart,
+                Token::Text("First".to_string()),
+                Token::ListItemEnd,
+                Token::ListItemValue(9),
+                Token::ListItemStart,
+                Token::Text("Ninth".to_string()),
+                Token::ListItemEnd,
+                Token::ListEnd,
+                Token::ListStart(false),
+                Token::ListStyle {
+                    style: ListStyleType::Square,
+                    start: 1,
+                },
+                Token::ListItemStart,
+                Token::Text("Dot".to_string()),
+                Token::ListItemEnd,
+                Token::ListEnd,
+                Token::ListStart(true),
+                Token::ListItemStart,
+                Token::Text("Plain".to_string()),
+                Token::ListItemEnd,
+                Token::ListEnd,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_list_style_type_cascades_from_stylesheets() {
+        let sheet = crate::css::parse_stylesheet(
+            "ol.toc { list-style-type: upper-roman } p { color: red }",
+        )
+        .unwrap();
+        let html = r#"<ol class="toc"><li>I</li></ol><ol class="toc" style="list-style-type: lower-alpha"><li>a</li></ol><ol><li>1</li></ol>"#;
+        let tokens = tokenize_html_with_stylesheets(html, core::slice::from_ref(&sheet)).unwrap();
+        let styles: Vec<&Token> = tokens
+            .iter()
+            .filter(|t| matches!(t, Token::ListStyle { .. }))
+            .collect();
+        assert_eq!(
+            styles,
+            vec![
+                &Token::ListStyle {
+                    style: ListStyleType::UpperRoman,
+                    start: 1,
+                },
+                &Token::ListStyle {
+                    style: ListStyleType::LowerAlpha,
+                    start: 1,
+                },
+            ]
+        );
+
+        let mut tokenizer = Tokenizer::new(TokenizeLimits::default()).with_stylesheets(&[sheet]);
+        tokenizer.reset();
+        let mut streamed: Vec<Token> = tokenizer.feed(html.as_bytes()).unwrap().collect();
+        streamed.extend(tokenizer.finish().unwrap());
+        assert_eq!(streamed, tokens);
+    }
+
     #[test]
     fn test_simple_ordered_list() {
         let html = "<ol><li>First</li><li>Second</li></ol>";
@@ -1684,6 +2367,8 @@ mod tests {
             vec![Token::Image {
                 src: "cover.jpg".to_string(),
                 alt: "Cover Image".to_string(),
+                width: None,
+                height: None,
             }]
         );
     }
@@ -1698,10 +2383,36 @@ mod tests {
             vec![Token::Image {
                 src: "photo.jpg".to_string(),
                 alt: String::with_capacity(0),
+                width: None,
+                height: None,
             }]
         );
     }
 
+    #[test]
+    fn test_image_dimensions_from_attributes_and_style() {
+        let html = r#"<img src="a.png" width="300" height="200"/><img src="b.png" width="300" style="width: 50%"/>"#;
+        let tokens = tokenize_html(html).unwrap();
+
+        assert_eq!(
+            tokens,
+            vec![
+                Token::Image {
+                    src: "a.png".to_string(),
+                    alt: String::with_capacity(0),
+                    width: Some(Dimension::Px(300)),
+                    height: Some(Dimension::Px(200)),
+                },
+                Token::Image {
+                    src: "b.png".to_string(),
+                    alt: String::with_capacity(0),
+                    width: Some(Dimension::Percent(50)),
+                    height: None,
+                },
+            ]
+        );
+    }
+
     #[test]
     fn test_image_without_src() {
         let html = r#"<img alt="Missing"/>"#;
@@ -1722,6 +2433,8 @@ mod tests {
             vec![Token::Image {
                 src: "pic.png".to_string(),
                 alt: "Pic".to_string(),
+                width: None,
+                height: None,
             }]
         );
     }
@@ -1748,6 +2461,8 @@ mod tests {
                 Token::Image {
                     src: "icon.png".to_string(),
                     alt: "icon".to_string(),
+                    width: None,
+                    height: None,
                 },
                 Token::ListItemEnd,
                 Token::ListEnd,
@@ -1907,6 +2622,8 @@ mod tests {
                 Token::Image {
                     src: "fig1.png".to_string(),
                     alt: "Figure 1".to_string(),
+                    width: None,
+                    height: None,
                 },
             ]
         );
@@ -1966,4 +2683,449 @@ mod tests {
         tokenize_html_with(html, |token| streamed.push(token)).unwrap();
         assert_eq!(baseline, streamed);
     }
+
+    fn tokenize_in_chunks(html: &str, chunk_size: usize) -> Result<Vec<Token>, TokenizeError> {
+        let mut tokenizer = Tokenizer::new(TokenizeLimits::default());
+        let mut tokens = Vec::with_capacity(0);
+        for chunk in html.as_bytes().chunks(chunk_size) {
+            tokens.extend(tokenizer.feed(chunk)?);
+        }
+        tokens.extend(tokenizer.finish()?);
+        Ok(tokens)
+    }
+
+    #[test]
+    fn test_incremental_tokenizer_matches_one_shot_for_any_chunk_size() {
+        let html = r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE html>
+<html><head><title>Ignored</title><style>p > em { color: red }</style></head>
+<body><!-- a < b --><h1 id="c1">Caf&#233; — Chapitre</h1>
+<p class="x" data-note="a > b">Smart “quotes” &amp; <em>émphasis</em> ok<br/>日本語テキスト</p>
+<ul><li><a href="n.xhtml#f">link</a></li></ul><p><![CDATA[raw <text>]]></p>
+<pre>
+  if a &lt; b {
+      go();  }</pre>
+<img src="i.png" alt="pic"/></body></html>"#;
+        let expected = tokenize_html_limited(html, TokenizeLimits::default()).unwrap();
+        for chunk_size in [1, 2, 3, 5, 7, 16, 64, html.len()] {
+            assert_eq!(
+                tokenize_in_chunks(html, chunk_size).unwrap(),
+                expected,
+                "chunk size {}",
+                chunk_size
+            );
+        }
+    }
+
+    #[test]
+    fn test_incremental_tokenizer_holds_text_until_boundary() {
+        let mut tokenizer = Tokenizer::new(TokenizeLimits::default());
+        assert_eq!(tokenizer.feed(b"<p>Hello wo").unwrap().count(), 0);
+        // Trailing text is held back: a following entity could still extend it.
+        assert_eq!(tokenizer.feed(b"rld</p>").unwrap().count(), 0);
+        let tokens: Vec<Token> = tokenizer.feed(b"<p>").unwrap().collect();
+        assert_eq!(
+            tokens,
+            vec![
+                Token::Text("Hello world".to_string()),
+                Token::ParagraphBreak
+            ]
+        );
+        assert_eq!(tokenizer.finish().unwrap().count(), 0);
+    }
+
+    #[test]
+    fn test_incremental_tokenizer_rejects_mismatched_end_tag() {
+        assert!(tokenize_in_chunks("<p>Text with <em>italic</p>", 4).is_err());
+    }
+
+    #[test]
+    fn test_incremental_tokenizer_rejects_truncated_markup_on_finish() {
+        let mut tokenizer = Tokenizer::new(TokenizeLimits::default());
+        let _ = tokenizer.feed(b"<p>ok</p><p class=\"unterminated").unwrap();
+        assert!(tokenizer.finish().is_err());
+    }
+
+    #[test]
+    fn test_incremental_tokenizer_splits_oversized_text_at_whitespace() {
+        let limits = TokenizeLimits {
+            max_text_bytes: 16,
+            ..TokenizeLimits::default()
+        };
+        let mut tokenizer = Tokenizer::new(limits);
+        let mut tokens = Vec::with_capacity(0);
+        tokens.extend(tokenizer.feed(b"<p>alpha beta gamma delta ").unwrap());
+        tokens.extend(tokenizer.feed(b"epsilon</p>").unwrap());
+        tokens.extend(tokenizer.finish().unwrap());
+        let words: Vec<&str> = tokens
+            .iter()
+            .filter_map(|t| match t {
+                Token::Text(text) => Some(text.as_str()),
+                _ => None,
+            })
+            .flat_map(|text| text.split(' '))
+            .collect();
+        assert_eq!(words, vec!["alpha", "beta", "gamma", "delta", "epsilon"]);
+    }
+
+    #[test]
+    fn test_incremental_tokenizer_bounds_unterminated_markup() {
+        let limits = TokenizeLimits {
+            max_text_bytes: 8,
+            ..TokenizeLimits::default()
+        };
+        let mut tokenizer = Tokenizer::new(limits);
+        assert!(tokenizer
+            .feed(b"<p><!-- a very long comment that never ends")
+            .is_err());
+    }
+
+    #[test]
+    fn test_incremental_tokenizer_reset_reuses_instance() {
+        let mut tokenizer = Tokenizer::new(TokenizeLimits::default());
+        tokenizer.feed(b"<p>first</p>").unwrap().for_each(drop);
+        tokenizer.finish().unwrap().for_each(drop);
+        assert!(tokenizer.feed(b"<p>").is_err());
+
+        tokenizer.reset();
+        let mut tokens: Vec<Token> = tokenizer.feed(b"<p>second</p>").unwrap().collect();
+        tokens.extend(tokenizer.finish().unwrap());
+        assert_eq!(tokens, vec![Token::Text("second".to_string())]);
+    }
+
+    #[test]
+    fn test_prefix_cache_resumes_shared_chapter_prefix() {
+        let chapter = |n: usize| {
+            alloc::format!(
+                "<?xml version=\"1.0\"?><html xmlns=\"http://www.w3.org/1999/xhtml\"><head>\
+                 <title>Book</title><style>p {{ margin: 0 }}</style></head><body>\
+                 <h1>Chapter {n}</h1><p>Text of chapter {n} with <em>emphasis</em>.</p></body></html>"
+            )
+        };
+        let mut cache = PrefixCache::new(TokenizeLimits::default(), 4096);
+        for n in 0..5 {
+            let html = chapter(n);
+            let expected = tokenize_html(&html).unwrap();
+            assert_eq!(cache.tokenize(html.as_bytes()).unwrap(), expected);
+        }
+        let stats = cache.stats();
+        assert_eq!(stats.chapters, 5);
+        assert_eq!(stats.hits, 3);
+        assert!(chapter(0).as_bytes()[..cache.prefix_len()].ends_with(b"<body><h1>"));
+        assert_eq!(stats.bytes_skipped, 3 * cache.prefix_len());
+
+        // Unrelated input falls back to a fresh tokenizer.
+        let other = "<p>Different <b>start</b></p>";
+        assert_eq!(
+            cache.tokenize(other.as_bytes()).unwrap(),
+            tokenize_html(other).unwrap()
+        );
+        assert_eq!(cache.stats().hits, 3);
+    }
+
+    #[test]
+    fn test_preformatted_preserves_whitespace() {
+        let html = "<p>Before</p><pre>\nfn main() {\n    run();\n}</pre><p>After  text</p>";
+        let tokens = tokenize_html(html).unwrap();
+        assert_eq!(
+            tokens,
+            vec![
+                Token::Text("Before".to_string()),
+                Token::ParagraphBreak,
+                Token::PreformattedStart,
+                Token::Text("fn main() {\n    run();\n}".to_string()),
+                Token::PreformattedEnd,
+                Token::ParagraphBreak,
+                Token::Text("After text".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_preformatted_keeps_nested_inline_whitespace() {
+        let html = "<pre><code>a  &lt;b&gt;\n  <b>c</b>  d</code></pre>";
+        let tokens = tokenize_html(html).unwrap();
+        assert_eq!(
+            tokens,
+            vec![
+                Token::PreformattedStart,
+                Token::Text("a  <b>\n  ".to_string()),
+                Token::Strong(true),
+                Token::Text("c".to_string()),
+                Token::Strong(false),
+                Token::Text("  d".to_string()),
+                Token::PreformattedEnd,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unclosed_preformatted_is_closed_at_end() {
+        let expected = vec![
+            Token::PreformattedStart,
+            Token::Text("x  y".to_string()),
+            Token::PreformattedEnd,
+        ];
+        let html = "<pre>x  y";
+        assert_eq!(
+            tokenize_html_limited(html, TokenizeLimits::default()).unwrap(),
+            expected
+        );
+        assert_eq!(tokenize_in_chunks(html, 3).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_preformatted_leading_newline_and_inline_code() {
+        let text = |s: &str| Token::Text(s.to_string());
+        let cases = [
+            // Only a newline directly after the outermost <pre> is dropped.
+            (
+                "<pre>a<pre>\nb</pre>\nc</pre>",
+                vec![
+                    Token::PreformattedStart,
+                    text("a"),
+                    Token::PreformattedStart,
+                    text("\nb"),
+                    Token::PreformattedEnd,
+                    text("\nc"),
+                    Token::PreformattedEnd,
+                ],
+            ),
+            (
+                "<pre><code>\nx  y</code></pre>",
+                vec![
+                    Token::PreformattedStart,
+                    text("\nx  y"),
+                    Token::PreformattedEnd,
+                ],
+            ),
+            (
+                "<pre>&amp;\nx</pre>",
+                vec![
+                    Token::PreformattedStart,
+                    text("&\nx"),
+                    Token::PreformattedEnd,
+                ],
+            ),
+            (
+                "<pre>\n\nx</pre>",
+                vec![
+                    Token::PreformattedStart,
+                    text("\nx"),
+                    Token::PreformattedEnd,
+                ],
+            ),
+            // Inline code keeps its whitespace without a block.
+            (
+                "<p>run <code>a  b\n c</code>  now</p>",
+                vec![text("run"), text("a  b\n c"), text("now")],
+            ),
+        ];
+        for (html, expected) in cases {
+            assert_eq!(tokenize_html(html).unwrap(), expected, "{}", html);
+            for chunk_size in [1, 4] {
+                assert_eq!(
+                    tokenize_in_chunks(html, chunk_size).unwrap(),
+                    expected,
+                    "{} in {}-byte chunks",
+                    html,
+                    chunk_size
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_element_ids_become_anchors() {
+        let html = r#"<h1 id="ch1">Title</h1><p id="p1">Text <a id="note"/>more <span id="">x</span></p><div id="aside"><style id="css">p {}</style></div>"#;
+        let tokens = tokenize_html(html).unwrap();
+        assert_eq!(
+            tokens,
+            vec![
+                Token::Anchor("ch1".to_string()),
+                Token::Heading(1),
+                Token::Text("Title".to_string()),
+                Token::ParagraphBreak,
+                Token::Anchor("p1".to_string()),
+                Token::Text("Text".to_string()),
+                Token::Anchor("note".to_string()),
+                Token::Text("more".to_string()),
+                Token::Text("x".to_string()),
+                Token::ParagraphBreak,
+                Token::Anchor("aside".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_hr_and_figure_tokens() {
+        let html = r#"<p>Before</p><hr/><figure><img src="a.png" alt="A"/><figcaption>Fig. 1</figcaption></figure><p>After</p><hr></hr>"#;
+        let tokens = tokenize_html(html).unwrap();
+        assert_eq!(
+            tokens,
+            vec![
+                Token::Text("Before".to_string()),
+                Token::ParagraphBreak,
+                Token::Hr,
+                Token::ParagraphBreak,
+                Token::FigureStart,
+                Token::Image {
+                    src: "a.png".to_string(),
+                    alt: "A".to_string(),
+                    width: None,
+                    height: None,
+                },
+                Token::CaptionStart,
+                Token::Text("Fig. 1".to_string()),
+                Token::CaptionEnd,
+                Token::FigureEnd,
+                Token::ParagraphBreak,
+                Token::Text("After".to_string()),
+                Token::ParagraphBreak,
+                Token::Hr,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unclosed_figure_is_closed_at_end() {
+        let tokens = tokenize_html("<figure><figcaption>Cap").unwrap();
+        assert_eq!(
+            tokens,
+            vec![
+                Token::FigureStart,
+                Token::CaptionStart,
+                Token::Text("Cap".to_string()),
+                Token::CaptionEnd,
+                Token::FigureEnd,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_spans_point_at_source_markup() {
+        let html = "<h2 id=\"s\">Title</h2><p>A &amp; B<em>c</em></p>";
+        let tokens = tokenize_html_with_spans(html, TokenizeLimits::default()).unwrap();
+        let slices: Vec<(&Token, &str)> = tokens
+            .iter()
+            .map(|t| (&t.token, &html[t.span.start..t.span.end]))
+            .collect();
+        assert_eq!(
+            slices,
+            vec![
+                (&Token::Anchor("s".to_string()), "<h2 id=\"s\">"),
+                (&Token::Heading(2), "<h2 id=\"s\">"),
+                (&Token::Text("Title".to_string()), "Title"),
+                (&Token::ParagraphBreak, "<p>"),
+                (&Token::Text("A&".to_string()), "A &amp;"),
+                (&Token::Text("B".to_string()), " B"),
+                (&Token::Emphasis(true), "<em>"),
+                (&Token::Text("c".to_string()), "c"),
+                (&Token::Emphasis(false), "</em>"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_spanned_tokens_match_plain_tokens() {
+        let html = "<h1>T</h1><p>Hello <em>world</em><br/>line 2</p><ul><li>x</li></ul>";
+        let plain = tokenize_html_limited(html, TokenizeLimits::default()).unwrap();
+        let spanned = tokenize_html_with_spans(html, TokenizeLimits::default()).unwrap();
+        let tokens: Vec<Token> = spanned.into_iter().map(|t| t.token).collect();
+        assert_eq!(tokens, plain);
+    }
+
+    #[test]
+    fn test_incremental_spans_are_absolute_offsets() {
+        let html = "<p>alpha</p><p>beta <b>gamma</b></p>";
+        let expected = tokenize_html_with_spans(html, TokenizeLimits::default()).unwrap();
+        let mut tokenizer = Tokenizer::new(TokenizeLimits::default());
+        let mut spanned = Vec::with_capacity(0);
+        for chunk in html.as_bytes().chunks(3) {
+            spanned.extend(tokenizer.feed_spanned(chunk).unwrap());
+        }
+        spanned.extend(tokenizer.finish_spanned().unwrap());
+        assert_eq!(spanned, expected);
+    }
+
+    #[test]
+    fn test_nesting_overflow_errors_or_flattens() {
+        let html = "<div><div><div id=\"x\"><em>deep</em></div></div><p>after</p></div>";
+        let limits = TokenizeLimits {
+            max_nesting: 2,
+            ..TokenizeLimits::default()
+        };
+        let err = tokenize_html_limited(html, limits).unwrap_err();
+        assert_eq!(
+            err,
+            TokenizeError::LimitExceeded {
+                kind: "max_nesting",
+                actual: 3,
+                limit: 2,
+                offset: 10,
+            }
+        );
+
+        let flattened = tokenize_html_limited(
+            html,
+            TokenizeLimits {
+                nesting_overflow: NestingOverflow::Flatten,
+                ..limits
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            flattened,
+            vec![
+                Token::Anchor("x".to_string()),
+                Token::Text("deep".to_string()),
+                Token::ParagraphBreak,
+                Token::Text("after".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_attribute_entity_and_token_limits_report_offsets() {
+        let limits = TokenizeLimits {
+            max_attributes: 2,
+            max_entity_expansions: 2,
+            max_tokens: 3,
+            ..TokenizeLimits::default()
+        };
+        let err =
+            tokenize_html_limited("<p>x</p><p a=\"1\" b=\"2\" c=\"3\">y</p>", limits).unwrap_err();
+        assert_eq!(
+            err,
+            TokenizeError::LimitExceeded {
+                kind: "max_attributes",
+                actual: 3,
+                limit: 2,
+                offset: 8,
+            }
+        );
+
+        let err = tokenize_html_limited("<p>&amp;&lt;&gt;</p>", limits).unwrap_err();
+        assert_eq!(
+            err,
+            TokenizeError::LimitExceeded {
+                kind: "max_entity_expansions",
+                actual: 3,
+                limit: 2,
+                offset: 12,
+            }
+        );
+
+        let err = tokenize_html_limited("<p>a</p><p>b</p><p>c</p>", limits).unwrap_err();
+        assert!(matches!(
+            err,
+            TokenizeError::LimitExceeded {
+                kind: "max_tokens",
+                actual: 4,
+                limit: 3,
+                ..
+            }
+        ));
+        assert!(tokenize_html_limited("<p a=\"1\" b=\"2\">&amp;&lt;</p>", limits).is_ok());
+    }
 }
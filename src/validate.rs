@@ -5,24 +5,36 @@
 
 extern crate alloc;
 
+use alloc::borrow::Cow;
 use alloc::format;
 use alloc::string::{String, ToString};
 use alloc::vec::Vec;
 use std::collections::{BTreeMap, BTreeSet};
 use std::fs::File;
-use std::io::{Read, Seek};
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc;
 
 use quick_xml::events::Event;
 use quick_xml::reader::Reader;
 
-use crate::metadata::{parse_container_xml, parse_opf, EpubMetadata};
-use crate::navigation::{parse_nav_xhtml, parse_ncx};
+use crate::book::{contains_script_element, is_markup_media_type};
+use crate::metadata::{
+    find_opf_conflicts, parse_container_xml, parse_opf, EpubMetadata, OpfConflict,
+};
+use crate::navigation::{parse_nav_xhtml, parse_ncx, Navigation};
+use crate::render_prep::resolve_relative;
 use crate::spine::Spine;
-use crate::zip::{StreamingZip, ZipLimits};
+use crate::storage::RandomAccess;
+use crate::zip::{percent_decode_path, CdEntry, StreamingZip, ZipLimits};
 
 /// Severity level for a validation diagnostic.
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+///
+/// Ordering follows declaration order, so sorting puts errors before warnings.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+#[non_exhaustive]
 pub enum ValidationSeverity {
     /// Violates a required structural expectation.
     Error,
@@ -30,11 +42,23 @@ pub enum ValidationSeverity {
     Warning,
 }
 
+impl ValidationSeverity {
+    /// Stable lowercase label (`"error"` / `"warning"`).
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Error => "error",
+            Self::Warning => "warning",
+        }
+    }
+}
+
 /// Structured validation diagnostic entry.
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
 pub struct ValidationDiagnostic {
     /// Stable machine-readable diagnostic code.
-    pub code: &'static str,
+    pub code: Cow<'static, str>,
     /// Severity classification.
     pub severity: ValidationSeverity,
     /// Human-readable description.
@@ -44,7 +68,8 @@ pub struct ValidationDiagnostic {
     /// Optional section/hint location (manifest/spine/nav/etc).
     pub location: Option<String>,
     /// Optional EPUB spec reference label.
-    pub spec_ref: Option<&'static str>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub spec_ref: Option<Cow<'static, str>>,
     /// Optional remediation hint.
     pub hint: Option<String>,
 }
@@ -52,7 +77,7 @@ pub struct ValidationDiagnostic {
 impl ValidationDiagnostic {
     fn error(code: &'static str, message: impl Into<String>) -> Self {
         Self {
-            code,
+            code: Cow::Borrowed(code),
             severity: ValidationSeverity::Error,
             message: message.into(),
             path: None,
@@ -64,7 +89,7 @@ impl ValidationDiagnostic {
 
     fn warning(code: &'static str, message: impl Into<String>) -> Self {
         Self {
-            code,
+            code: Cow::Borrowed(code),
             severity: ValidationSeverity::Warning,
             message: message.into(),
             path: None,
@@ -76,9 +101,116 @@ impl ValidationDiagnostic {
 }
 
 /// Validation report with all discovered diagnostics.
-#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
 pub struct ValidationReport {
     diagnostics: Vec<ValidationDiagnostic>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    signed_references: Vec<SignedReference>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    stylesheet_stats: Vec<StylesheetStats>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    oversized_images: Vec<OversizedImage>,
+}
+
+/// Digest algorithm named by a `signatures.xml` `<DigestMethod>`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+#[non_exhaustive]
+pub enum DigestAlgorithm {
+    /// SHA-1 (`xmldsig#sha1`).
+    Sha1,
+    /// SHA-256 (`xmlenc#sha256`).
+    Sha256,
+    /// Any other algorithm, by its URI.
+    Other(String),
+}
+
+impl DigestAlgorithm {
+    fn from_uri(uri: &str) -> Self {
+        match uri.rsplit_once('#').map(|(_, name)| name) {
+            Some("sha1") => Self::Sha1,
+            Some("sha256") => Self::Sha256,
+            _ => Self::Other(uri.to_string()),
+        }
+    }
+}
+
+/// Outcome of checking one signed reference.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+#[non_exhaustive]
+pub enum DigestStatus {
+    /// The archive entry hashes to the declared digest.
+    Verified,
+    /// The archive entry does not match the declared digest.
+    Mismatch,
+    /// The referenced archive entry does not exist.
+    Missing,
+    /// The digest could not be checked: unknown algorithm, a `<Transforms>`
+    /// step, or the `digest` feature is disabled.
+    Unverified,
+}
+
+/// Archive entry covered by `META-INF/signatures.xml`.
+///
+/// Only digests are checked; signature values and certificate chains are
+/// not validated.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub struct SignedReference {
+    /// Container path of the signed entry.
+    pub path: String,
+    /// Declared digest algorithm.
+    pub algorithm: DigestAlgorithm,
+    /// Declared digest bytes (empty when the value was not valid base64).
+    pub digest: Vec<u8>,
+    /// Check result.
+    pub status: DigestStatus,
+}
+
+/// Per-stylesheet results of [`ValidationOptions::css_analysis`].
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default, rename_all = "snake_case"))]
+pub struct StylesheetStats {
+    /// Container path of the stylesheet.
+    pub path: String,
+    /// Size in bytes.
+    pub bytes: usize,
+    /// Style rules, including those nested in `@media` and `@supports`.
+    pub rules: usize,
+    /// Selectors across all rules, counting each entry of a selector list.
+    pub selectors: usize,
+    /// Selectors repeating an earlier selector in the same media context.
+    pub duplicate_selectors: usize,
+    /// Rules whose every declaration is redeclared by later rules with the
+    /// same selector.
+    pub overridden_rules: usize,
+    /// Selectors matching no element of the sampled chapters; `None` when
+    /// no chapters were sampled.
+    pub unused_selectors: Option<usize>,
+}
+
+/// Image flagged by [`ValidationOptions::device_profile`].
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default, rename_all = "snake_case"))]
+pub struct OversizedImage {
+    /// Container path of the image.
+    pub path: String,
+    /// Width and height in pixels, when the header could be read.
+    pub dimensions: Option<(u32, u32)>,
+    /// Size in bytes.
+    pub bytes: usize,
+    /// Estimated bytes a right-sized copy would save: the share of pixels
+    /// beyond what the display shows, or the excess over
+    /// [`DeviceProfile::max_image_bytes`], whichever is larger.
+    pub wasted_bytes: usize,
 }
 
 impl ValidationReport {
@@ -108,6 +240,30 @@ impl ValidationReport {
             .count()
     }
 
+    /// Entries listed in `META-INF/signatures.xml`, with their digest checks.
+    pub fn signed_references(&self) -> &[SignedReference] {
+        &self.signed_references
+    }
+
+    /// Stylesheet statistics, when [`ValidationOptions::css_analysis`] was
+    /// enabled.
+    pub fn stylesheet_stats(&self) -> &[StylesheetStats] {
+        &self.stylesheet_stats
+    }
+
+    /// Images over the [`ValidationOptions::device_profile`] limits.
+    pub fn oversized_images(&self) -> &[OversizedImage] {
+        &self.oversized_images
+    }
+
+    /// Sum of [`OversizedImage::wasted_bytes`].
+    pub fn wasted_image_bytes(&self) -> usize {
+        self.oversized_images
+            .iter()
+            .map(|image| image.wasted_bytes)
+            .sum()
+    }
+
     /// Returns `true` when no error-level diagnostics were found.
     pub fn is_valid(&self) -> bool {
         self.error_count() == 0
@@ -123,6 +279,60 @@ impl ValidationReport {
 pub struct ValidationOptions {
     /// Optional ZIP safety limits used while reading archive entries.
     pub zip_limits: Option<ZipLimits>,
+    /// Opt-in analysis of redundant and unused CSS rules.
+    pub css_analysis: Option<CssAnalysisOptions>,
+    /// Opt-in audit of manifest images against a target display.
+    pub device_profile: Option<DeviceProfile>,
+}
+
+/// Target display for the image audit enabled by
+/// [`ValidationOptions::device_profile`].
+///
+/// Raster images wider or taller than the display are reported as
+/// `IMAGE_EXCEEDS_DISPLAY`, and larger files than `max_image_bytes` as
+/// `IMAGE_BYTES_EXCEED_LIMIT`; see [`ValidationReport::oversized_images`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DeviceProfile {
+    /// Display width in pixels.
+    pub width: u32,
+    /// Display height in pixels.
+    pub height: u32,
+    /// Largest image file worth shipping, in bytes.
+    pub max_image_bytes: usize,
+}
+
+impl DeviceProfile {
+    /// Profile for a `width` x `height` display with a 512 KiB image limit.
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            max_image_bytes: 512 * 1024,
+        }
+    }
+}
+
+/// Options for the stylesheet analysis enabled by
+/// [`ValidationOptions::css_analysis`].
+///
+/// Each manifest stylesheet gets a [`StylesheetStats`] entry and, where
+/// found, one `CSS_SELECTOR_DUPLICATE`, `CSS_RULE_OVERRIDDEN`, or
+/// `CSS_SELECTOR_UNUSED` warning naming a few examples.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CssAnalysisOptions {
+    /// Linear spine chapters, evenly spaced, checked for elements each
+    /// selector could match; `0` skips the unused-selector check.
+    ///
+    /// A selector is judged by its rightmost compound (tag, classes, and
+    /// id), so combinators and pseudo-classes never make it count as
+    /// unused.
+    pub sample_chapters: usize,
+}
+
+impl Default for CssAnalysisOptions {
+    fn default() -> Self {
+        Self { sample_chapters: 8 }
+    }
 }
 
 /// Validate an EPUB from a filesystem path.
@@ -139,13 +349,166 @@ pub fn validate_epub_file_with_options<P: AsRef<Path>>(
     Ok(validate_epub_reader_with_options(file, options))
 }
 
-/// Validate an EPUB from any `Read + Seek` reader.
-pub fn validate_epub_reader<R: Read + Seek>(reader: R) -> ValidationReport {
+/// Options for [`validate_dir`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DirValidationOptions {
+    /// Options applied to every book.
+    pub validation: ValidationOptions,
+    /// Books validated at once; `0` is treated as `1`.
+    pub max_parallel: usize,
+    /// Directory levels below the root to descend into; `None` walks the
+    /// whole tree.
+    pub max_depth: Option<usize>,
+}
+
+impl Default for DirValidationOptions {
+    fn default() -> Self {
+        Self {
+            validation: ValidationOptions::default(),
+            max_parallel: 4,
+            max_depth: None,
+        }
+    }
+}
+
+/// Validation outcome for one book found by [`validate_dir`].
+#[derive(Debug)]
+pub struct BookValidation {
+    /// Path of the `.epub` file.
+    pub path: PathBuf,
+    /// The report, or why the file could not be opened.
+    pub result: Result<ValidationReport, crate::EpubError>,
+}
+
+/// Aggregate statistics of a [`validate_dir`] run.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default, rename_all = "snake_case"))]
+pub struct DirValidationSummary {
+    /// `.epub` files found.
+    pub books_scanned: usize,
+    /// Books with no error-level diagnostics.
+    pub valid_books: usize,
+    /// Books that could not be opened.
+    pub unreadable: usize,
+    /// Subdirectories that could not be listed.
+    pub unreadable_dirs: usize,
+    /// Error diagnostics across all books, by code.
+    pub errors_by_code: BTreeMap<String, usize>,
+    /// Warning diagnostics across all books, by code.
+    pub warnings_by_code: BTreeMap<String, usize>,
+}
+
+impl DirValidationSummary {
+    fn record(&mut self, book: &BookValidation) {
+        self.books_scanned += 1;
+        let Ok(report) = &book.result else {
+            self.unreadable += 1;
+            return;
+        };
+        if report.is_valid() {
+            self.valid_books += 1;
+        }
+        for diagnostic in report.diagnostics() {
+            let histogram = match diagnostic.severity {
+                ValidationSeverity::Error => &mut self.errors_by_code,
+                ValidationSeverity::Warning => &mut self.warnings_by_code,
+            };
+            *histogram.entry(diagnostic.code.to_string()).or_insert(0) += 1;
+        }
+    }
+}
+
+/// Validate every `.epub` file under `root`, streaming each book's result
+/// to `on_report` on the calling thread.
+///
+/// Files are found in sorted order and validated on up to
+/// [`DirValidationOptions::max_parallel`] worker threads, so reports arrive
+/// in completion order. Symlinked directories are not followed. Only a
+/// `root` that cannot be listed is an error; other unlistable directories
+/// are counted in [`DirValidationSummary::unreadable_dirs`].
+pub fn validate_dir<P, F>(
+    root: P,
+    options: DirValidationOptions,
+    mut on_report: F,
+) -> Result<DirValidationSummary, crate::EpubError>
+where
+    P: AsRef<Path>,
+    F: FnMut(BookValidation),
+{
+    let mut summary = DirValidationSummary::default();
+    let mut books = Vec::with_capacity(0);
+    let mut pending = vec![(root.as_ref().to_path_buf(), 0usize)];
+    while let Some((dir, depth)) = pending.pop() {
+        let entries = match std::fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(err) if depth == 0 => return Err(crate::EpubError::Io(err.to_string())),
+            Err(_) => {
+                summary.unreadable_dirs += 1;
+                continue;
+            }
+        };
+        let mut subdirs = Vec::with_capacity(0);
+        for entry in entries.flatten() {
+            let Ok(file_type) = entry.file_type() else {
+                continue;
+            };
+            let path = entry.path();
+            if file_type.is_dir() {
+                if options.max_depth.is_none_or(|max| depth < max) {
+                    subdirs.push(path);
+                }
+            } else if is_epub_path(&path) && path.is_file() {
+                books.push(path);
+            }
+        }
+        // Reverse so the stack visits subdirectories in sorted order.
+        subdirs.sort_unstable_by(|a, b| b.cmp(a));
+        pending.extend(subdirs.into_iter().map(|dir| (dir, depth + 1)));
+    }
+    books.sort_unstable();
+
+    let next = AtomicUsize::new(0);
+    let workers = options.max_parallel.clamp(1, books.len().max(1));
+    std::thread::scope(|scope| {
+        let (sender, receiver) = mpsc::sync_channel(workers);
+        for _ in 0..workers {
+            let sender = sender.clone();
+            let (books, next) = (&books, &next);
+            scope.spawn(move || {
+                while let Some(path) = books.get(next.fetch_add(1, Ordering::Relaxed)) {
+                    let result = validate_epub_file_with_options(path, options.validation);
+                    let book = BookValidation {
+                        path: path.clone(),
+                        result,
+                    };
+                    if sender.send(book).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+        drop(sender);
+        for book in receiver {
+            summary.record(&book);
+            on_report(book);
+        }
+    });
+    Ok(summary)
+}
+
+fn is_epub_path(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("epub"))
+}
+
+/// Validate an EPUB from any [`RandomAccess`] reader.
+pub fn validate_epub_reader<R: RandomAccess>(reader: R) -> ValidationReport {
     validate_epub_reader_with_options(reader, ValidationOptions::default())
 }
 
-/// Validate an EPUB from any `Read + Seek` reader with explicit options.
-pub fn validate_epub_reader_with_options<R: Read + Seek>(
+/// Validate an EPUB from any [`RandomAccess`] reader with explicit options.
+pub fn validate_epub_reader_with_options<R: RandomAccess>(
     reader: R,
     options: ValidationOptions,
 ) -> ValidationReport {
@@ -157,7 +520,7 @@ pub fn validate_epub_reader_with_options<R: Read + Seek>(
                 "ZIP_INVALID_ARCHIVE",
                 format!("Failed to parse ZIP container: {}", err),
             );
-            d.spec_ref = Some("OCF ZIP container");
+            d.spec_ref = Some(Cow::Borrowed("OCF ZIP container"));
             report.push(d);
             return report;
         }
@@ -169,7 +532,7 @@ pub fn validate_epub_reader_with_options<R: Read + Seek>(
             format!("Invalid or missing mimetype entry: {}", err),
         );
         d.path = Some("mimetype".to_string());
-        d.spec_ref = Some("OCF mimetype");
+        d.spec_ref = Some(Cow::Borrowed("OCF mimetype"));
         d.hint = Some("Ensure `mimetype` exists and equals `application/epub+zip`.".to_string());
         report.push(d);
     }
@@ -182,7 +545,7 @@ pub fn validate_epub_reader_with_options<R: Read + Seek>(
                 "Missing required `META-INF/container.xml`.",
             );
             d.path = Some("META-INF/container.xml".to_string());
-            d.spec_ref = Some("OCF container.xml");
+            d.spec_ref = Some(Cow::Borrowed("OCF container.xml"));
             report.push(d);
             return report;
         }
@@ -209,7 +572,7 @@ pub fn validate_epub_reader_with_options<R: Read + Seek>(
                 format!("container.xml does not declare a usable rootfile: {}", err),
             );
             d.path = Some("META-INF/container.xml".to_string());
-            d.spec_ref = Some("EPUB package document discovery");
+            d.spec_ref = Some(Cow::Borrowed("EPUB package document discovery"));
             report.push(d);
             return report;
         }
@@ -226,7 +589,7 @@ pub fn validate_epub_reader_with_options<R: Read + Seek>(
                 ),
             );
             d.path = Some(opf_path.clone());
-            d.spec_ref = Some("Package document");
+            d.spec_ref = Some(Cow::Borrowed("Package document"));
             report.push(d);
             return report;
         }
@@ -253,7 +616,7 @@ pub fn validate_epub_reader_with_options<R: Read + Seek>(
                 format!("Failed to parse package document '{}': {}", opf_path, err),
             );
             d.path = Some(opf_path.clone());
-            d.spec_ref = Some("OPF package document");
+            d.spec_ref = Some(Cow::Borrowed("OPF package document"));
             report.push(d);
             return report;
         }
@@ -274,11 +637,26 @@ pub fn validate_epub_reader_with_options<R: Read + Seek>(
     };
 
     validate_manifest_integrity(&metadata, &mut report);
+    validate_opf_conflicts(&opf_bytes, &mut report);
     validate_manifest_fallbacks(&opf_bytes, &mut report);
     validate_manifest_resources_exist(&zip, &metadata, &opf_path, &mut report);
     validate_spine_integrity(&metadata, &spine, &mut report);
+    validate_scripted_content(&mut zip, &metadata, &spine, &opf_path, &mut report);
     validate_navigation_integrity(&mut zip, &metadata, &spine, &opf_path, &mut report);
     validate_container_sidecars(&mut zip, &mut report);
+    if let Some(css_options) = options.css_analysis {
+        analyze_stylesheets(
+            &mut zip,
+            &metadata,
+            &spine,
+            &opf_path,
+            css_options,
+            &mut report,
+        );
+    }
+    if let Some(profile) = options.device_profile {
+        audit_images(&mut zip, &metadata, &opf_path, profile, &mut report);
+    }
 
     report
 }
@@ -493,7 +871,7 @@ fn validate_manifest_fallbacks(opf_bytes: &[u8], report: &mut ValidationReport)
     }
 }
 
-fn validate_container_sidecars<F: Read + Seek>(
+fn validate_container_sidecars<F: RandomAccess>(
     zip: &mut StreamingZip<F>,
     report: &mut ValidationReport,
 ) {
@@ -511,10 +889,18 @@ fn validate_container_sidecars<F: Read + Seek>(
         "RIGHTS_XML_UNREADABLE",
         "RIGHTS_XML_PARSE_ERROR",
     );
+    validate_optional_xml_sidecar(
+        zip,
+        report,
+        "META-INF/signatures.xml",
+        "SIGNATURES_XML_UNREADABLE",
+        "SIGNATURES_XML_PARSE_ERROR",
+    );
     validate_encryption_references(zip, report);
+    validate_signature_digests(zip, report);
 }
 
-fn validate_optional_xml_sidecar<F: Read + Seek>(
+fn validate_optional_xml_sidecar<F: RandomAccess>(
     zip: &mut StreamingZip<F>,
     report: &mut ValidationReport,
     path: &str,
@@ -559,7 +945,7 @@ fn validate_optional_xml_sidecar<F: Read + Seek>(
     }
 }
 
-fn validate_encryption_references<F: Read + Seek>(
+fn validate_encryption_references<F: RandomAccess>(
     zip: &mut StreamingZip<F>,
     report: &mut ValidationReport,
 ) {
@@ -626,7 +1012,264 @@ fn validate_encryption_references<F: Read + Seek>(
     }
 }
 
-fn read_entry<F: Read + Seek>(
+/// `<Reference>` entry parsed from `signatures.xml`.
+#[derive(Debug, Default)]
+struct SignatureReferenceXml {
+    uri: String,
+    algorithm: String,
+    digest_value: String,
+    has_transforms: bool,
+}
+
+/// Collect `<Reference>` elements pointing at archive entries.
+///
+/// Same-document (`#id`) and remote references are skipped.
+fn parse_signature_references(bytes: &[u8]) -> Vec<SignatureReferenceXml> {
+    let mut refs = Vec::with_capacity(0);
+    let mut current: Option<SignatureReferenceXml> = None;
+    let mut in_digest_value = false;
+    let mut reader = Reader::from_reader(bytes);
+    reader.config_mut().trim_text(true);
+    let mut buf = Vec::with_capacity(0);
+    loop {
+        match reader.read_event_into(&mut buf) {
+            Ok(Event::Start(e)) | Ok(Event::Empty(e)) => match e.local_name().as_ref() {
+                b"Reference" => {
+                    current = Some(SignatureReferenceXml {
+                        uri: local_attribute(&reader, &e, b"URI"),
+                        ..SignatureReferenceXml::default()
+                    });
+                }
+                b"Transforms" => {
+                    if let Some(current) = current.as_mut() {
+                        current.has_transforms = true;
+                    }
+                }
+                b"DigestMethod" => {
+                    if let Some(current) = current.as_mut() {
+                        current.algorithm = local_attribute(&reader, &e, b"Algorithm");
+                    }
+                }
+                b"DigestValue" => in_digest_value = true,
+                _ => {}
+            },
+            Ok(Event::Text(text)) if in_digest_value => {
+                if let (Some(current), Ok(text)) = (current.as_mut(), text.decode()) {
+                    current.digest_value.push_str(&text);
+                }
+            }
+            Ok(Event::End(e)) => match e.local_name().as_ref() {
+                b"DigestValue" => in_digest_value = false,
+                b"Reference" => {
+                    if let Some(reference) = current.take() {
+                        let uri = reference.uri.as_str();
+                        if !uri.is_empty() && !uri.starts_with('#') && !uri.contains("://") {
+                            refs.push(reference);
+                        }
+                    }
+                }
+                _ => {}
+            },
+            Ok(Event::Eof) | Err(_) => break,
+            Ok(_) => {}
+        }
+        buf.clear();
+    }
+    refs
+}
+
+/// Trimmed value of the attribute with local name `name`, or empty.
+fn local_attribute(
+    reader: &Reader<&[u8]>,
+    element: &quick_xml::events::BytesStart<'_>,
+    name: &[u8],
+) -> String {
+    element
+        .attributes()
+        .flatten()
+        .find(|attr| attr.key.local_name().as_ref() == name)
+        .and_then(|attr| {
+            reader
+                .decoder()
+                .decode(&attr.value)
+                .ok()
+                .map(|value| value.trim().to_string())
+        })
+        .unwrap_or_default()
+}
+
+/// Check digests of the entries signed in `META-INF/signatures.xml`.
+///
+/// Reference URIs are container-root relative. Unparseable sidecars are
+/// reported by [`validate_optional_xml_sidecar`]; whatever references could
+/// be read are still checked.
+fn validate_signature_digests<F: RandomAccess>(
+    zip: &mut StreamingZip<F>,
+    report: &mut ValidationReport,
+) {
+    const PATH: &str = "META-INF/signatures.xml";
+    let Some(entry) = zip.get_entry(PATH).cloned() else {
+        return;
+    };
+    let Ok(bytes) = read_entry(zip, entry.local_header_offset) else {
+        return;
+    };
+
+    let mut unverified = 0usize;
+    for reference in parse_signature_references(&bytes) {
+        let href = reference.uri.split('#').next().unwrap_or_default();
+        let path = percent_decode_path(href.trim_start_matches("./").trim_start_matches('/'));
+        let algorithm = DigestAlgorithm::from_uri(&reference.algorithm);
+        let digest = decode_base64(&reference.digest_value);
+        let status = match zip.get_entry(&path).cloned() {
+            None => DigestStatus::Missing,
+            Some(_) if reference.has_transforms => DigestStatus::Unverified,
+            Some(entry) => match &digest {
+                None => DigestStatus::Mismatch,
+                Some(expected) => match compute_digest(zip, &entry, &algorithm) {
+                    Some(actual) if actual == *expected => DigestStatus::Verified,
+                    Some(_) => DigestStatus::Mismatch,
+                    None => DigestStatus::Unverified,
+                },
+            },
+        };
+        match status {
+            DigestStatus::Missing => {
+                let mut d = ValidationDiagnostic::error(
+                    "SIGNATURE_REFERENCE_MISSING",
+                    format!("`signatures.xml` signs missing resource '{}'.", path),
+                );
+                d.location = Some("ocf".to_string());
+                d.path = Some(PATH.to_string());
+                report.push(d);
+            }
+            DigestStatus::Mismatch => {
+                let mut d = ValidationDiagnostic::error(
+                    "SIGNATURE_DIGEST_MISMATCH",
+                    format!(
+                        "Digest of '{}' does not match the value in `signatures.xml`.",
+                        path
+                    ),
+                );
+                d.location = Some("ocf".to_string());
+                d.path = Some(path.clone());
+                d.hint = Some("The resource was modified after the book was signed.".to_string());
+                report.push(d);
+            }
+            DigestStatus::Unverified => unverified += 1,
+            DigestStatus::Verified => {}
+        }
+        report.signed_references.push(SignedReference {
+            path,
+            algorithm,
+            digest: digest.unwrap_or_default(),
+            status,
+        });
+    }
+    if unverified > 0 {
+        let mut d = ValidationDiagnostic::warning(
+            "SIGNATURE_DIGEST_UNVERIFIED",
+            format!(
+                "{} signed resource(s) in `signatures.xml` could not be digest-checked.",
+                unverified
+            ),
+        );
+        d.location = Some("ocf".to_string());
+        d.path = Some(PATH.to_string());
+        if cfg!(not(feature = "digest")) {
+            d.hint =
+                Some("Enable the `digest` feature to check SHA-1/SHA-256 digests.".to_string());
+        }
+        report.push(d);
+    }
+}
+
+/// Digest of `entry`, streamed under the archive's [`ZipLimits`], or `None`
+/// when the algorithm is unavailable or the entry cannot be read.
+#[cfg(feature = "digest")]
+fn compute_digest<F: RandomAccess>(
+    zip: &mut StreamingZip<F>,
+    entry: &CdEntry,
+    algorithm: &DigestAlgorithm,
+) -> Option<Vec<u8>> {
+    let mut hasher = match algorithm {
+        DigestAlgorithm::Sha1 => DigestWriter::Sha1(crate::digest::Sha1::new()),
+        DigestAlgorithm::Sha256 => DigestWriter::Sha256(crate::digest::Sha256::new()),
+        DigestAlgorithm::Other(_) => return None,
+    };
+    zip.read_file_to_writer(entry, &mut hasher).ok()?;
+    Some(match hasher {
+        DigestWriter::Sha1(hasher) => hasher.finalize().to_vec(),
+        DigestWriter::Sha256(hasher) => hasher.finalize().to_vec(),
+    })
+}
+
+/// Without the `digest` feature nothing is hashed, so the entry is not read.
+#[cfg(not(feature = "digest"))]
+fn compute_digest<F: RandomAccess>(
+    _zip: &mut StreamingZip<F>,
+    _entry: &CdEntry,
+    _algorithm: &DigestAlgorithm,
+) -> Option<Vec<u8>> {
+    None
+}
+
+/// Hasher that entry bytes are streamed into.
+#[cfg(feature = "digest")]
+enum DigestWriter {
+    Sha1(crate::digest::Sha1),
+    Sha256(crate::digest::Sha256),
+}
+
+#[cfg(feature = "digest")]
+impl std::io::Write for DigestWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match self {
+            DigestWriter::Sha1(hasher) => hasher.update(buf),
+            DigestWriter::Sha256(hasher) => hasher.update(buf),
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Decode standard base64, ignoring whitespace.
+fn decode_base64(text: &str) -> Option<Vec<u8>> {
+    let mut out = Vec::with_capacity(text.len() / 4 * 3);
+    let mut acc = 0u32;
+    let mut bits = 0u32;
+    let mut padding = 0usize;
+    for byte in text.bytes().filter(|b| !b.is_ascii_whitespace()) {
+        let value = match byte {
+            b'A'..=b'Z' => byte - b'A',
+            b'a'..=b'z' => byte - b'a' + 26,
+            b'0'..=b'9' => byte - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            b'=' => {
+                padding += 1;
+                continue;
+            }
+            _ => return None,
+        };
+        if padding > 0 {
+            return None;
+        }
+        acc = (acc << 6) | u32::from(value);
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((acc >> bits) as u8);
+            acc &= (1 << bits) - 1;
+        }
+    }
+    (padding <= 2 && !out.is_empty()).then_some(out)
+}
+
+fn read_entry<F: RandomAccess>(
     zip: &mut StreamingZip<F>,
     local_header_offset: u64,
 ) -> Result<Vec<u8>, crate::ZipError> {
@@ -644,7 +1287,6 @@ fn read_entry<F: Read + Seek>(
 }
 
 fn validate_manifest_integrity(metadata: &EpubMetadata, report: &mut ValidationReport) {
-    let mut ids = BTreeSet::new();
     let mut hrefs = BTreeSet::new();
     for item in &metadata.manifest {
         if item.id.trim().is_empty() {
@@ -674,15 +1316,6 @@ fn validate_manifest_integrity(metadata: &EpubMetadata, report: &mut ValidationR
             report.push(d);
         }
 
-        if !ids.insert(item.id.clone()) {
-            let mut d = ValidationDiagnostic::error(
-                "MANIFEST_ID_DUPLICATE",
-                format!("Duplicate manifest id '{}'.", item.id),
-            );
-            d.location = Some("manifest".to_string());
-            report.push(d);
-        }
-
         let href_key = item.href.to_ascii_lowercase();
         if !href_key.is_empty() && !hrefs.insert(href_key) {
             let mut d = ValidationDiagnostic::warning(
@@ -696,7 +1329,49 @@ fn validate_manifest_integrity(metadata: &EpubMetadata, report: &mut ValidationR
     }
 }
 
-fn validate_manifest_resources_exist<F: Read + Seek>(
+/// Report declarations that parsing resolved last-wins.
+fn validate_opf_conflicts(opf_bytes: &[u8], report: &mut ValidationReport) {
+    let Ok(conflicts) = find_opf_conflicts(opf_bytes) else {
+        return;
+    };
+    for conflict in conflicts {
+        let mut d = match &conflict {
+            OpfConflict::DuplicateManifestId { id, href, .. } => {
+                let mut d = ValidationDiagnostic::error(
+                    "MANIFEST_ID_DUPLICATE",
+                    format!("Duplicate manifest id '{}'.", id),
+                );
+                d.location = Some("manifest".to_string());
+                d.path = Some(href.clone());
+                d
+            }
+            OpfConflict::DuplicateSpineIdref { idref, index, .. } => {
+                let mut d = ValidationDiagnostic::error(
+                    "SPINE_IDREF_DUPLICATE",
+                    format!(
+                        "Spine item at index {} repeats manifest id '{}'.",
+                        index, idref
+                    ),
+                );
+                d.location = Some("spine".to_string());
+                d.spec_ref = Some(Cow::Borrowed("OPF spine/itemref"));
+                d
+            }
+            OpfConflict::ConflictingToc { .. } => {
+                let mut d = ValidationDiagnostic::warning(
+                    "SPINE_TOC_CONFLICT",
+                    "Spine declares conflicting `toc` attributes.",
+                );
+                d.location = Some("spine".to_string());
+                d
+            }
+        };
+        d.hint = Some(format!("Resolved last-wins: {}.", conflict));
+        report.push(d);
+    }
+}
+
+fn validate_manifest_resources_exist<F: RandomAccess>(
     zip: &StreamingZip<F>,
     metadata: &EpubMetadata,
     opf_path: &str,
@@ -753,7 +1428,7 @@ fn validate_spine_integrity(metadata: &EpubMetadata, spine: &Spine, report: &mut
                 ),
             );
             d.location = Some("spine".to_string());
-            d.spec_ref = Some("OPF spine/itemref");
+            d.spec_ref = Some(Cow::Borrowed("OPF spine/itemref"));
             d.hint = Some(
                 "Ensure each `<itemref idref=\"...\">` matches a manifest `<item id=\"...\">`."
                     .to_string(),
@@ -763,7 +1438,61 @@ fn validate_spine_integrity(metadata: &EpubMetadata, spine: &Spine, report: &mut
     }
 }
 
-fn validate_navigation_integrity<F: Read + Seek>(
+fn validate_scripted_content<F: RandomAccess>(
+    zip: &mut StreamingZip<F>,
+    metadata: &EpubMetadata,
+    spine: &Spine,
+    opf_path: &str,
+    report: &mut ValidationReport,
+) {
+    for item in spine.items() {
+        let Some(manifest_item) = metadata.get_item(&item.idref) else {
+            continue;
+        };
+        let declared = manifest_item.has_property("scripted");
+        let full_path = resolve_opf_relative(opf_path, &manifest_item.href);
+        let has_script = is_markup_media_type(&manifest_item.media_type)
+            && zip
+                .get_entry(&full_path)
+                .map(|entry| entry.local_header_offset)
+                .and_then(|offset| read_entry(zip, offset).ok())
+                .is_some_and(|bytes| contains_script_element(&bytes));
+        if !declared && !has_script {
+            continue;
+        }
+
+        let mut d = ValidationDiagnostic::warning(
+            "SPINE_SCRIPTED_CONTENT",
+            format!(
+                "Spine item '{}' contains scripted content; interactive features are not supported.",
+                item.idref
+            ),
+        );
+        d.location = Some("spine".to_string());
+        d.path = Some(full_path.clone());
+        d.hint = Some(
+            "Readers show a placeholder; provide a non-scripted fallback for essential content."
+                .to_string(),
+        );
+        report.push(d);
+
+        if has_script && !declared {
+            let mut d = ValidationDiagnostic::warning(
+                "MANIFEST_SCRIPTED_PROPERTY_MISSING",
+                format!(
+                    "Manifest item '{}' contains `<script>` but does not declare `properties=\"scripted\"`.",
+                    manifest_item.id
+                ),
+            );
+            d.location = Some("manifest".to_string());
+            d.path = Some(full_path);
+            d.spec_ref = Some(Cow::Borrowed("OPF manifest item properties"));
+            report.push(d);
+        }
+    }
+}
+
+fn validate_navigation_integrity<F: RandomAccess>(
     zip: &mut StreamingZip<F>,
     metadata: &EpubMetadata,
     spine: &Spine,
@@ -806,8 +1535,9 @@ fn validate_navigation_integrity<F: Read + Seek>(
         };
 
         match read_entry(zip, nav_entry.local_header_offset) {
-            Ok(bytes) => {
-                if let Err(err) = parse_nav_xhtml(&bytes) {
+            Ok(bytes) => match parse_nav_xhtml(&bytes) {
+                Ok(nav) => validate_nav_spine_order(&nav, &nav_item.href, metadata, spine, report),
+                Err(err) => {
                     let mut d = ValidationDiagnostic::error(
                         "NAV_DOCUMENT_PARSE_ERROR",
                         format!("Failed to parse nav document: {}", err),
@@ -816,7 +1546,7 @@ fn validate_navigation_integrity<F: Read + Seek>(
                     d.location = Some("navigation".to_string());
                     report.push(d);
                 }
-            }
+            },
             Err(err) => {
                 let mut d = ValidationDiagnostic::error(
                     "NAV_DOCUMENT_UNREADABLE",
@@ -837,8 +1567,11 @@ fn validate_navigation_integrity<F: Read + Seek>(
                 let full_path = resolve_opf_relative(opf_path, &item.href);
                 match zip.get_entry(&full_path).cloned() {
                     Some(entry) => match read_entry(zip, entry.local_header_offset) {
-                        Ok(bytes) => {
-                            if let Err(err) = parse_ncx(&bytes) {
+                        Ok(bytes) => match parse_ncx(&bytes) {
+                            Ok(nav) => {
+                                validate_nav_spine_order(&nav, &item.href, metadata, spine, report)
+                            }
+                            Err(err) => {
                                 let mut d = ValidationDiagnostic::error(
                                     "NCX_PARSE_ERROR",
                                     format!("Failed to parse NCX document: {}", err),
@@ -847,7 +1580,7 @@ fn validate_navigation_integrity<F: Read + Seek>(
                                 d.location = Some("navigation".to_string());
                                 report.push(d);
                             }
-                        }
+                        },
                         Err(err) => {
                             let mut d = ValidationDiagnostic::error(
                                 "NCX_UNREADABLE",
@@ -895,22 +1628,594 @@ fn validate_navigation_integrity<F: Read + Seek>(
     report.push(d);
 }
 
-fn resolve_opf_relative(opf_path: &str, href: &str) -> String {
-    if href.contains("://") || href.starts_with('/') {
-        return href.to_string();
-    }
-    match opf_path.rfind('/') {
-        Some(idx) => format!("{}/{}", &opf_path[..idx], href),
-        None => href.to_string(),
+/// Warn where the table of contents steps back in the spine, which usually
+/// means a conversion swapped chapters. Entries into non-linear items and
+/// further entries into the same item are ignored.
+fn validate_nav_spine_order(
+    nav: &Navigation,
+    nav_href: &str,
+    metadata: &EpubMetadata,
+    spine: &Spine,
+    report: &mut ValidationReport,
+) {
+    let spine_paths: Vec<Option<String>> = spine
+        .items()
+        .iter()
+        .map(|itemref| {
+            let item = metadata.get_item(&itemref.idref)?;
+            itemref.linear.then(|| resolve_relative("", &item.href))
+        })
+        .collect();
+    let mut previous: Option<(usize, &str)> = None;
+    for (_, point) in nav.toc_flat() {
+        let path = point.href.split('#').next().unwrap_or_default();
+        if path.is_empty() || path.contains("://") {
+            continue;
+        }
+        let path = resolve_relative(nav_href, path);
+        let Some(index) = spine_paths
+            .iter()
+            .position(|spine_path| spine_path.as_deref() == Some(path.as_str()))
+        else {
+            continue;
+        };
+        if let Some((prev_index, prev_href)) = previous {
+            if index == prev_index {
+                continue;
+            }
+            if index < prev_index {
+                let mut d = ValidationDiagnostic::warning(
+                    "NAV_SPINE_ORDER_MISMATCH",
+                    format!(
+                        "Navigation lists '{}' (spine position {}) after '{}' (spine position {}).",
+                        point.href, index, prev_href, prev_index
+                    ),
+                );
+                d.path = Some(point.href.clone());
+                d.location = Some("navigation".to_string());
+                d.hint = Some(
+                    "Check that the spine and table of contents list chapters in the same order."
+                        .to_string(),
+                );
+                report.push(d);
+            }
+        }
+        previous = Some((index, point.href.as_str()));
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+/// Bytes of an image read to find its dimensions; enough to step over the
+/// EXIF block that precedes the frame header in most JPEGs.
+const IMAGE_HEADER_PREFIX: usize = 64 * 1024;
 
-    const SIG_LOCAL_FILE_HEADER: u32 = 0x04034b50;
-    const SIG_CD_ENTRY: u32 = 0x02014b50;
+fn audit_images<F: RandomAccess>(
+    zip: &mut StreamingZip<F>,
+    metadata: &EpubMetadata,
+    opf_path: &str,
+    profile: DeviceProfile,
+    report: &mut ValidationReport,
+) {
+    for item in metadata.manifest.iter() {
+        if !item.media_type.starts_with("image/") || item.media_type == "image/svg+xml" {
+            continue;
+        }
+        let full_path = resolve_opf_relative(opf_path, &item.href);
+        let Some(entry) = zip.get_entry(&full_path).cloned() else {
+            continue;
+        };
+        let bytes = usize::try_from(entry.uncompressed_size).unwrap_or(usize::MAX);
+        let mut header = Vec::with_capacity(0);
+        let dimensions = zip
+            .read_file_range_to_writer(&entry, 0, IMAGE_HEADER_PREFIX, &mut header)
+            .ok()
+            .and_then(|_| image_dimensions(&header));
+
+        let mut wasted = 0usize;
+        if let Some((width, height)) = dimensions {
+            if width > profile.width || height > profile.height {
+                let scale = f64::min(
+                    f64::from(profile.width) / f64::from(width),
+                    f64::from(profile.height) / f64::from(height),
+                );
+                // Bytes scale roughly with pixel count.
+                wasted = (bytes as f64 * (1.0 - scale * scale)) as usize;
+                let mut d = ValidationDiagnostic::warning(
+                    "IMAGE_EXCEEDS_DISPLAY",
+                    format!(
+                        "Image is {}x{} px, larger than the {}x{} px display; about {} of its {} bytes are wasted.",
+                        width, height, profile.width, profile.height, wasted, bytes
+                    ),
+                );
+                d.path = Some(full_path.clone());
+                d.location = Some("manifest".to_string());
+                d.hint = Some("Downscale the image to fit the target display.".to_string());
+                report.push(d);
+            }
+        }
+        if bytes > profile.max_image_bytes {
+            wasted = wasted.max(bytes - profile.max_image_bytes);
+            let mut d = ValidationDiagnostic::warning(
+                "IMAGE_BYTES_EXCEED_LIMIT",
+                format!(
+                    "Image is {} bytes, over the {} byte limit.",
+                    bytes, profile.max_image_bytes
+                ),
+            );
+            d.path = Some(full_path.clone());
+            d.location = Some("manifest".to_string());
+            d.hint = Some("Recompress the image or lower its quality.".to_string());
+            report.push(d);
+        }
+        if wasted > 0 {
+            report.oversized_images.push(OversizedImage {
+                path: full_path,
+                dimensions,
+                bytes,
+                wasted_bytes: wasted,
+            });
+        }
+    }
+}
+
+/// Pixel size from a PNG, JPEG, GIF, or WebP header.
+fn image_dimensions(data: &[u8]) -> Option<(u32, u32)> {
+    let be16 = |at: usize| {
+        Some(u32::from(u16::from_be_bytes([
+            *data.get(at)?,
+            *data.get(at + 1)?,
+        ])))
+    };
+    let le16 = |at: usize| {
+        Some(u32::from(u16::from_le_bytes([
+            *data.get(at)?,
+            *data.get(at + 1)?,
+        ])))
+    };
+    let le24 = |at: usize| {
+        Some(u32::from_le_bytes([
+            *data.get(at)?,
+            *data.get(at + 1)?,
+            *data.get(at + 2)?,
+            0,
+        ]))
+    };
+    if data.starts_with(b"\x89PNG\r\n\x1a\n") && data.get(12..16) == Some(b"IHDR") {
+        let width = u32::from_be_bytes(data.get(16..20)?.try_into().ok()?);
+        let height = u32::from_be_bytes(data.get(20..24)?.try_into().ok()?);
+        return Some((width, height));
+    }
+    if data.starts_with(b"GIF8") {
+        return Some((le16(6)?, le16(8)?));
+    }
+    if data.starts_with(b"RIFF") && data.get(8..12) == Some(b"WEBP") {
+        return match data.get(12..16)? {
+            b"VP8 " => Some((le16(26)? & 0x3fff, le16(28)? & 0x3fff)),
+            b"VP8L" => {
+                let bits = u32::from_le_bytes(data.get(21..25)?.try_into().ok()?);
+                Some(((bits & 0x3fff) + 1, ((bits >> 14) & 0x3fff) + 1))
+            }
+            b"VP8X" => Some((le24(24)? + 1, le24(27)? + 1)),
+            _ => None,
+        };
+    }
+    if data.starts_with(&[0xff, 0xd8]) {
+        let mut pos = 2;
+        loop {
+            while *data.get(pos)? != 0xff {
+                pos += 1;
+            }
+            while *data.get(pos)? == 0xff {
+                pos += 1;
+            }
+            let marker = *data.get(pos)?;
+            pos += 1;
+            if matches!(marker, 0x01 | 0xd0..=0xd9) {
+                continue;
+            }
+            // Start-of-frame markers, excluding DHT, JPG, and DAC.
+            if matches!(marker, 0xc0..=0xcf) && !matches!(marker, 0xc4 | 0xc8 | 0xcc) {
+                return Some((be16(pos + 5)?, be16(pos + 3)?));
+            }
+            pos += usize::try_from(be16(pos)?).ok()?;
+        }
+    }
+    None
+}
+
+/// Selectors named in a CSS analysis diagnostic.
+const CSS_ANALYSIS_EXAMPLES: usize = 5;
+
+/// One style rule seen by the CSS analysis.
+struct AuditRule {
+    /// Preludes of the enclosing `@media`/`@supports` blocks.
+    context: String,
+    selectors: Vec<String>,
+    /// Declared property names with their `!important` flag.
+    properties: Vec<(String, bool)>,
+}
+
+/// Element signature from a sampled chapter: tag, classes, and id.
+type SampledElement = (Option<String>, Vec<String>, Option<String>);
+
+fn analyze_stylesheets<F: RandomAccess>(
+    zip: &mut StreamingZip<F>,
+    metadata: &EpubMetadata,
+    spine: &Spine,
+    opf_path: &str,
+    options: CssAnalysisOptions,
+    report: &mut ValidationReport,
+) {
+    let elements = sample_chapter_elements(zip, metadata, spine, opf_path, options);
+    for item in metadata.manifest.iter() {
+        if item.media_type != "text/css" {
+            continue;
+        }
+        let full_path = resolve_opf_relative(opf_path, &item.href);
+        let Some(bytes) = zip
+            .get_entry(&full_path)
+            .map(|entry| entry.local_header_offset)
+            .and_then(|offset| read_entry(zip, offset).ok())
+        else {
+            continue;
+        };
+        let css = strip_css_comments(&String::from_utf8_lossy(&bytes));
+        let mut rules = Vec::with_capacity(0);
+        collect_audit_rules(&css, "", &mut rules);
+
+        let mut stats = StylesheetStats {
+            path: full_path.clone(),
+            bytes: bytes.len(),
+            rules: rules.len(),
+            ..StylesheetStats::default()
+        };
+        let mut duplicates = Vec::with_capacity(0);
+        let mut overridden = Vec::with_capacity(0);
+        let mut unused = Vec::with_capacity(0);
+        let mut seen: BTreeSet<(&str, &str)> = BTreeSet::new();
+        for (idx, rule) in rules.iter().enumerate() {
+            for selector in &rule.selectors {
+                stats.selectors += 1;
+                if !seen.insert((rule.context.as_str(), selector.as_str())) {
+                    duplicates.push(selector.as_str());
+                }
+                if let Some(elements) = &elements {
+                    if !selector_may_match(selector, elements) {
+                        unused.push(selector.as_str());
+                    }
+                }
+            }
+            if is_rule_overridden(rule, &rules[idx + 1..]) {
+                overridden.push(rule.selectors.join(", "));
+            }
+        }
+        stats.duplicate_selectors = duplicates.len();
+        stats.overridden_rules = overridden.len();
+        stats.unused_selectors = elements.as_ref().map(|_| unused.len());
+
+        let mut push = |code: &'static str, message: String, examples: &[&str], hint: &str| {
+            let examples: Vec<String> = examples
+                .iter()
+                .take(CSS_ANALYSIS_EXAMPLES)
+                .map(|selector| format!("'{}'", selector))
+                .collect();
+            let mut d = ValidationDiagnostic::warning(
+                code,
+                format!("{} (e.g. {}).", message, examples.join(", ")),
+            );
+            d.path = Some(full_path.clone());
+            d.location = Some("stylesheet".to_string());
+            d.hint = Some(hint.to_string());
+            report.push(d);
+        };
+        if !duplicates.is_empty() {
+            push(
+                "CSS_SELECTOR_DUPLICATE",
+                format!("{} selectors repeat earlier ones", duplicates.len()),
+                &duplicates,
+                "Merge rules that share a selector.",
+            );
+        }
+        if !overridden.is_empty() {
+            let examples: Vec<&str> = overridden.iter().map(String::as_str).collect();
+            push(
+                "CSS_RULE_OVERRIDDEN",
+                format!(
+                    "{} rules are fully overridden by later rules",
+                    overridden.len()
+                ),
+                &examples,
+                "Remove rules whose declarations are all redeclared later.",
+            );
+        }
+        if !unused.is_empty() {
+            push(
+                "CSS_SELECTOR_UNUSED",
+                format!(
+                    "{} of {} selectors match no element in the sampled chapters",
+                    unused.len(),
+                    stats.selectors
+                ),
+                &unused,
+                "Remove selectors for markup the book does not use.",
+            );
+        }
+        report.stylesheet_stats.push(stats);
+    }
+}
+
+/// Element signatures of evenly spaced linear spine chapters, or `None`
+/// when sampling is disabled.
+fn sample_chapter_elements<F: RandomAccess>(
+    zip: &mut StreamingZip<F>,
+    metadata: &EpubMetadata,
+    spine: &Spine,
+    opf_path: &str,
+    options: CssAnalysisOptions,
+) -> Option<BTreeSet<SampledElement>> {
+    if options.sample_chapters == 0 {
+        return None;
+    }
+    let chapters: Vec<String> = spine
+        .items()
+        .iter()
+        .filter(|itemref| itemref.linear)
+        .filter_map(|itemref| metadata.get_item(&itemref.idref))
+        .filter(|item| is_markup_media_type(&item.media_type))
+        .map(|item| resolve_opf_relative(opf_path, &item.href))
+        .collect();
+    let samples = options.sample_chapters.min(chapters.len());
+    let mut elements = BTreeSet::new();
+    for i in 0..samples {
+        let path = &chapters[i * chapters.len() / samples];
+        let Some(bytes) = zip
+            .get_entry(path)
+            .map(|entry| entry.local_header_offset)
+            .and_then(|offset| read_entry(zip, offset).ok())
+        else {
+            continue;
+        };
+        collect_chapter_elements(&bytes, &mut elements);
+    }
+    Some(elements)
+}
+
+fn collect_chapter_elements(bytes: &[u8], elements: &mut BTreeSet<SampledElement>) {
+    let mut reader = Reader::from_reader(bytes);
+    let mut buf = Vec::with_capacity(0);
+    loop {
+        match reader.read_event_into(&mut buf) {
+            Ok(Event::Start(e)) | Ok(Event::Empty(e)) => {
+                let tag = String::from_utf8_lossy(e.local_name().as_ref()).to_ascii_lowercase();
+                let mut classes = Vec::with_capacity(0);
+                let mut id = None;
+                for attr in e.attributes().flatten() {
+                    let value = String::from_utf8_lossy(&attr.value);
+                    match attr.key.local_name().as_ref() {
+                        b"class" => {
+                            classes = value.split_whitespace().map(str::to_string).collect();
+                        }
+                        b"id" => id = Some(value.trim().to_string()),
+                        _ => {}
+                    }
+                }
+                elements.insert((Some(tag), classes, id));
+            }
+            Ok(Event::Eof) | Err(_) => break,
+            _ => {}
+        }
+        buf.clear();
+    }
+}
+
+/// Whether later rules in the same context redeclare every property of
+/// `rule` for each of its selectors, with at least the same importance.
+fn is_rule_overridden(rule: &AuditRule, later: &[AuditRule]) -> bool {
+    !rule.properties.is_empty()
+        && rule.selectors.iter().all(|selector| {
+            rule.properties.iter().all(|(property, important)| {
+                later.iter().any(|other| {
+                    other.context == rule.context
+                        && other.selectors.contains(selector)
+                        && other.properties.iter().any(|(name, other_important)| {
+                            name == property && (*other_important || !important)
+                        })
+                })
+            })
+        })
+}
+
+/// Whether any sampled element has the tag, classes, and id of the
+/// selector's rightmost compound.
+fn selector_may_match(selector: &str, elements: &BTreeSet<SampledElement>) -> bool {
+    let (tag, classes, id) = key_compound(selector);
+    elements.iter().any(|(el_tag, el_classes, el_id)| {
+        tag.as_ref().is_none_or(|tag| el_tag.as_ref() == Some(tag))
+            && classes.iter().all(|class| el_classes.contains(class))
+            && id.as_ref().is_none_or(|id| el_id.as_ref() == Some(id))
+    })
+}
+
+/// Tag, classes, and id of the last compound selector; attribute
+/// selectors and pseudo-classes are ignored since they only narrow it.
+fn key_compound(selector: &str) -> SampledElement {
+    let mut depth = 0usize;
+    let mut start = 0;
+    for (idx, ch) in selector.char_indices() {
+        match ch {
+            '(' | '[' => depth += 1,
+            ')' | ']' => depth = depth.saturating_sub(1),
+            ' ' | '>' | '+' | '~' if depth == 0 => start = idx + 1,
+            _ => {}
+        }
+    }
+    let mut chars = selector[start..].chars().peekable();
+    let (mut tag, mut classes, mut id) = (None, Vec::with_capacity(0), None);
+    while let Some(ch) = chars.next() {
+        match ch {
+            '.' => classes.push(css_ident(&mut chars)),
+            '#' => id = Some(css_ident(&mut chars)),
+            '[' | ':' => {
+                let mut depth = usize::from(ch == '[');
+                while let Some(&next) = chars.peek() {
+                    if depth == 0 && matches!(next, '.' | '#' | '[') {
+                        break;
+                    }
+                    chars.next();
+                    match next {
+                        '(' | '[' => depth += 1,
+                        ')' | ']' => depth = depth.saturating_sub(1),
+                        _ => {}
+                    }
+                }
+            }
+            _ if tag.is_none() && is_css_ident_char(ch) => {
+                let mut name = String::from(ch);
+                name.push_str(&css_ident(&mut chars));
+                tag = Some(name.to_ascii_lowercase());
+            }
+            _ => {}
+        }
+    }
+    (tag, classes, id)
+}
+
+fn is_css_ident_char(ch: char) -> bool {
+    ch.is_alphanumeric() || ch == '-' || ch == '_' || !ch.is_ascii()
+}
+
+fn css_ident(chars: &mut core::iter::Peekable<core::str::Chars<'_>>) -> String {
+    let mut out = String::with_capacity(0);
+    while let Some(&ch) = chars.peek() {
+        if ch == '\\' {
+            chars.next();
+            out.extend(chars.next());
+        } else if is_css_ident_char(ch) {
+            out.push(ch);
+            chars.next();
+        } else {
+            break;
+        }
+    }
+    out
+}
+
+/// Style rules of `css` (comments already removed), descending into
+/// conditional group rules and skipping other at-rules.
+fn collect_audit_rules(css: &str, context: &str, out: &mut Vec<AuditRule>) {
+    let mut rest = css;
+    while let Some(open) = rest.find('{') {
+        let prelude = rest[..open].rsplit(';').next().unwrap_or_default().trim();
+        let close = matching_brace(rest, open).unwrap_or(rest.len());
+        let body = &rest[open + 1..close];
+        if let Some(at_rule) = prelude.strip_prefix('@') {
+            let name = at_rule
+                .split(|ch: char| ch.is_whitespace() || ch == '(')
+                .next()
+                .unwrap_or_default()
+                .to_ascii_lowercase();
+            if matches!(name.as_str(), "media" | "supports" | "layer" | "document") {
+                let inner = format!("{}{} ", context, prelude);
+                collect_audit_rules(body, &inner, out);
+            }
+        } else if !prelude.is_empty() {
+            let properties = body
+                .split(';')
+                .filter_map(|decl| {
+                    let (name, value) = decl.split_once(':')?;
+                    let name = name.trim().to_ascii_lowercase();
+                    let important = value
+                        .trim_end()
+                        .to_ascii_lowercase()
+                        .ends_with("!important");
+                    (!name.is_empty()).then_some((name, important))
+                })
+                .collect();
+            out.push(AuditRule {
+                context: context.to_string(),
+                selectors: split_selector_list(prelude),
+                properties,
+            });
+        }
+        rest = rest.get(close + 1..).unwrap_or_default();
+    }
+}
+
+/// Byte index of the `}` closing the block opened at `open`.
+fn matching_brace(css: &str, open: usize) -> Option<usize> {
+    let mut depth = 0usize;
+    let mut quote = None;
+    for (idx, ch) in css[open..].char_indices() {
+        match (quote, ch) {
+            (Some(q), _) if ch == q => quote = None,
+            (Some(_), _) => {}
+            (None, '"' | '\'') => quote = Some(ch),
+            (None, '{') => depth += 1,
+            (None, '}') => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(open + idx);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Entries of a selector list, with whitespace collapsed.
+fn split_selector_list(prelude: &str) -> Vec<String> {
+    let mut selectors = Vec::with_capacity(1);
+    let mut depth = 0usize;
+    let mut start = 0;
+    for (idx, ch) in prelude.char_indices() {
+        match ch {
+            '(' | '[' => depth += 1,
+            ')' | ']' => depth = depth.saturating_sub(1),
+            ',' if depth == 0 => {
+                selectors.push(&prelude[start..idx]);
+                start = idx + 1;
+            }
+            _ => {}
+        }
+    }
+    selectors.push(&prelude[start..]);
+    selectors
+        .into_iter()
+        .map(|selector| selector.split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|selector| !selector.is_empty())
+        .collect()
+}
+
+fn strip_css_comments(css: &str) -> String {
+    let mut out = String::with_capacity(css.len());
+    let mut rest = css;
+    while let Some(start) = rest.find("/*") {
+        out.push_str(&rest[..start]);
+        rest = match rest[start + 2..].find("*/") {
+            Some(end) => &rest[start + 2 + end + 2..],
+            None => "",
+        };
+    }
+    out.push_str(rest);
+    out
+}
+
+fn resolve_opf_relative(opf_path: &str, href: &str) -> String {
+    if href.contains("://") || href.starts_with('/') {
+        return href.to_string();
+    }
+    match opf_path.rfind('/') {
+        Some(idx) => format!("{}/{}", &opf_path[..idx], href),
+        None => href.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SIG_LOCAL_FILE_HEADER: u32 = 0x04034b50;
+    const SIG_CD_ENTRY: u32 = 0x02014b50;
     const SIG_EOCD: u32 = 0x06054b50;
 
     fn build_zip(files: &[(&str, &[u8])]) -> Vec<u8> {
@@ -1036,6 +2341,69 @@ mod tests {
         ])
     }
 
+    #[test]
+    fn validate_dir_walks_tree_and_aggregates_codes() {
+        let root =
+            std::env::temp_dir().join(format!("mu-epub-validate-dir-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        let nested = root.join("shelf").join("deep");
+        std::fs::create_dir_all(&nested).unwrap();
+        std::fs::write(root.join("a.epub"), minimal_valid_epub_zip()).unwrap();
+        std::fs::write(nested.join("b.EPUB"), minimal_valid_epub_zip()).unwrap();
+        std::fs::write(root.join("shelf").join("broken.epub"), b"not a zip").unwrap();
+        std::fs::write(root.join("notes.txt"), b"ignored").unwrap();
+
+        let mut paths = Vec::with_capacity(0);
+        let summary = validate_dir(&root, DirValidationOptions::default(), |book| {
+            paths.push(book.path.strip_prefix(&root).unwrap().to_path_buf());
+        })
+        .unwrap();
+        paths.sort();
+        assert_eq!(
+            paths,
+            [
+                PathBuf::from("a.epub"),
+                Path::new("shelf").join("broken.epub"),
+                Path::new("shelf").join("deep").join("b.EPUB"),
+            ]
+        );
+        assert_eq!(summary.books_scanned, 3);
+        assert_eq!(summary.valid_books, 2);
+        assert_eq!(summary.unreadable, 0);
+        assert_eq!(summary.unreadable_dirs, 0);
+        assert_eq!(summary.errors_by_code.get("ZIP_INVALID_ARCHIVE"), Some(&1));
+
+        let shallow = validate_dir(
+            &root,
+            DirValidationOptions {
+                max_parallel: 1,
+                max_depth: Some(1),
+                ..DirValidationOptions::default()
+            },
+            |_| {},
+        )
+        .unwrap();
+        assert_eq!(shallow.books_scanned, 2);
+        assert!(validate_dir(
+            root.join("missing"),
+            DirValidationOptions::default(),
+            |_| {}
+        )
+        .is_err());
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn severity_orders_errors_first() {
+        let mut severities = alloc::vec![ValidationSeverity::Warning, ValidationSeverity::Error];
+        severities.sort();
+        assert_eq!(
+            severities,
+            alloc::vec![ValidationSeverity::Error, ValidationSeverity::Warning]
+        );
+        assert_eq!(ValidationSeverity::Warning.as_str(), "warning");
+    }
+
     #[test]
     fn validate_minimal_valid_epub() {
         let data = minimal_valid_epub_zip();
@@ -1044,6 +2412,67 @@ mod tests {
         assert_eq!(report.error_count(), 0);
     }
 
+    #[test]
+    fn validate_warns_about_scripted_spine_items() {
+        let container_xml = br#"<?xml version="1.0"?>
+<container version="1.0" xmlns="urn:oasis:names:tc:opendocument:xmlns:container">
+  <rootfiles>
+    <rootfile full-path="EPUB/package.opf" media-type="application/oebps-package+xml"/>
+  </rootfiles>
+</container>"#;
+
+        let opf = br#"<?xml version="1.0" encoding="UTF-8"?>
+<package version="3.0" xmlns="http://www.idpf.org/2007/opf">
+  <metadata xmlns:dc="http://purl.org/dc/elements/1.1/">
+    <dc:title>Test Book</dc:title>
+    <dc:language>en</dc:language>
+  </metadata>
+  <manifest>
+    <item id="c1" href="ch1.xhtml" media-type="application/xhtml+xml"/>
+    <item id="quiz" href="quiz.xhtml" media-type="application/xhtml+xml" properties="scripted"/>
+    <item id="sneaky" href="sneaky.xhtml" media-type="application/xhtml+xml"/>
+  </manifest>
+  <spine>
+    <itemref idref="c1"/>
+    <itemref idref="quiz"/>
+    <itemref idref="sneaky"/>
+  </spine>
+</package>"#;
+
+        let plain =
+            br#"<html xmlns="http://www.w3.org/1999/xhtml"><body><p>Hello</p></body></html>"#;
+        let scripted = br#"<html xmlns="http://www.w3.org/1999/xhtml"><body><script>go()</script></body></html>"#;
+        let data = build_zip(&[
+            ("mimetype", b"application/epub+zip"),
+            ("META-INF/container.xml", container_xml),
+            ("EPUB/package.opf", opf),
+            ("EPUB/ch1.xhtml", plain),
+            ("EPUB/quiz.xhtml", scripted),
+            ("EPUB/sneaky.xhtml", scripted),
+        ]);
+        let report = validate_epub_reader(std::io::Cursor::new(data));
+        let paths = |code: &str| -> Vec<String> {
+            report
+                .diagnostics()
+                .iter()
+                .filter(|d| d.code == code)
+                .filter_map(|d| d.path.clone())
+                .collect()
+        };
+
+        assert_eq!(
+            paths("SPINE_SCRIPTED_CONTENT"),
+            vec![
+                "EPUB/quiz.xhtml".to_string(),
+                "EPUB/sneaky.xhtml".to_string()
+            ]
+        );
+        assert_eq!(
+            paths("MANIFEST_SCRIPTED_PROPERTY_MISSING"),
+            vec!["EPUB/sneaky.xhtml".to_string()]
+        );
+    }
+
     #[test]
     fn validate_detects_missing_container() {
         let data = build_zip(&[("mimetype", b"application/epub+zip")]);
@@ -1091,6 +2520,53 @@ mod tests {
             .any(|d| d.code == "SPINE_IDREF_NOT_IN_MANIFEST"));
     }
 
+    #[test]
+    fn validate_reports_opf_conflicts() {
+        let container_xml = br#"<?xml version="1.0"?>
+<container version="1.0" xmlns="urn:oasis:names:tc:opendocument:xmlns:container">
+  <rootfiles>
+    <rootfile full-path="EPUB/package.opf" media-type="application/oebps-package+xml"/>
+  </rootfiles>
+</container>"#;
+
+        let opf = br#"<?xml version="1.0" encoding="UTF-8"?>
+<package version="3.0" xmlns="http://www.idpf.org/2007/opf">
+  <metadata xmlns:dc="http://purl.org/dc/elements/1.1/">
+    <dc:title>Test</dc:title><dc:creator>A</dc:creator><dc:language>en</dc:language>
+  </metadata>
+  <manifest>
+    <item id="c" href="one.xhtml" media-type="application/xhtml+xml"/>
+    <item id="c" href="two.xhtml" media-type="application/xhtml+xml"/>
+  </manifest>
+  <spine>
+    <itemref idref="c"/>
+    <itemref idref="c"/>
+  </spine>
+</package>"#;
+
+        let data = build_zip(&[
+            ("mimetype", b"application/epub+zip"),
+            ("META-INF/container.xml", container_xml),
+            ("EPUB/package.opf", opf),
+            ("EPUB/one.xhtml", b"<html/>"),
+            ("EPUB/two.xhtml", b"<html/>"),
+        ]);
+        let report = validate_epub_reader(std::io::Cursor::new(data));
+        let codes: Vec<_> = report
+            .diagnostics()
+            .iter()
+            .map(|d| d.code.as_ref())
+            .collect();
+        assert!(codes.contains(&"MANIFEST_ID_DUPLICATE"));
+        assert!(codes.contains(&"SPINE_IDREF_DUPLICATE"));
+        let duplicate = report
+            .diagnostics()
+            .iter()
+            .find(|d| d.code == "MANIFEST_ID_DUPLICATE")
+            .unwrap();
+        assert_eq!(duplicate.path.as_deref(), Some("two.xhtml"));
+    }
+
     #[test]
     fn validate_detects_missing_manifest_resource() {
         let container_xml = br#"<?xml version="1.0"?>
@@ -1307,6 +2783,372 @@ mod tests {
             .any(|d| d.code == "ENCRYPTION_REFERENCE_MISSING"));
     }
 
+    #[test]
+    fn validate_checks_signature_digests() {
+        let signatures_xml = br##"<?xml version="1.0" encoding="UTF-8"?>
+<signatures xmlns="urn:oasis:names:tc:opendocument:xmlns:container">
+  <Signature xmlns="http://www.w3.org/2000/09/xmldsig#">
+    <SignedInfo>
+      <Reference URI="#manifest"><DigestValue>ignored</DigestValue></Reference>
+    </SignedInfo>
+    <Object>
+      <Manifest Id="manifest">
+        <Reference URI="EPUB/ch1.xhtml">
+          <DigestMethod Algorithm="http://www.w3.org/2001/04/xmlenc#sha256"/>
+          <DigestValue>n2QGPI0rtoBFAGZ/7mE3ueaGzm31WsRjg5vbHFAbAa8=</DigestValue>
+        </Reference>
+        <Reference URI="EPUB/nav.xhtml">
+          <DigestMethod Algorithm="http://www.w3.org/2000/09/xmldsig#sha1"/>
+          <DigestValue>EfatjsUqKYSrqv18O1FlA3hcIHI=</DigestValue>
+        </Reference>
+        <Reference URI="EPUB/missing.css">
+          <DigestMethod Algorithm="http://www.w3.org/2000/09/xmldsig#sha1"/>
+          <DigestValue>EfatjsUqKYSrqv18O1FlA3hcIHI=</DigestValue>
+        </Reference>
+      </Manifest>
+    </Object>
+  </Signature>
+</signatures>"##;
+        let data = build_zip(&[
+            ("mimetype", b"application/epub+zip"),
+            (
+                "META-INF/container.xml",
+                br#"<?xml version="1.0"?>
+<container version="1.0" xmlns="urn:oasis:names:tc:opendocument:xmlns:container">
+  <rootfiles>
+    <rootfile full-path="EPUB/package.opf" media-type="application/oebps-package+xml"/>
+  </rootfiles>
+</container>"#,
+            ),
+            (
+                "EPUB/package.opf",
+                br#"<?xml version="1.0" encoding="UTF-8"?>
+<package version="3.0" xmlns="http://www.idpf.org/2007/opf">
+  <metadata xmlns:dc="http://purl.org/dc/elements/1.1/">
+    <dc:title>Test Book</dc:title>
+    <dc:creator>Tester</dc:creator>
+    <dc:language>en</dc:language>
+  </metadata>
+  <manifest>
+    <item id="nav" href="nav.xhtml" media-type="application/xhtml+xml" properties="nav"/>
+    <item id="c1" href="ch1.xhtml" media-type="application/xhtml+xml"/>
+  </manifest>
+  <spine><itemref idref="c1"/></spine>
+</package>"#,
+            ),
+            (
+                "EPUB/nav.xhtml",
+                br#"<?xml version="1.0" encoding="utf-8"?>
+<html xmlns="http://www.w3.org/1999/xhtml" xmlns:epub="http://www.idpf.org/2007/ops">
+  <body><nav epub:type="toc"><ol><li><a href="ch1.xhtml">Chapter 1</a></li></ol></nav></body>
+</html>"#,
+            ),
+            (
+                "EPUB/ch1.xhtml",
+                br#"<html xmlns="http://www.w3.org/1999/xhtml"><body><p>Hello</p></body></html>"#,
+            ),
+            ("META-INF/signatures.xml", signatures_xml),
+        ]);
+        let report = validate_epub_reader(std::io::Cursor::new(data));
+        let refs = report.signed_references();
+        let paths: Vec<&str> = refs.iter().map(|r| r.path.as_str()).collect();
+        assert_eq!(
+            paths,
+            ["EPUB/ch1.xhtml", "EPUB/nav.xhtml", "EPUB/missing.css"]
+        );
+        assert_eq!(refs[0].algorithm, DigestAlgorithm::Sha256);
+        assert_eq!(refs[0].digest.len(), 32);
+        assert_eq!(refs[2].status, DigestStatus::Missing);
+        let has = |code: &str| report.diagnostics().iter().any(|d| d.code == code);
+        assert!(has("SIGNATURE_REFERENCE_MISSING"));
+        if cfg!(feature = "digest") {
+            assert_eq!(refs[0].status, DigestStatus::Verified);
+            assert_eq!(refs[1].status, DigestStatus::Mismatch);
+            assert!(has("SIGNATURE_DIGEST_MISMATCH"));
+            assert!(!has("SIGNATURE_DIGEST_UNVERIFIED"));
+        } else {
+            assert_eq!(refs[0].status, DigestStatus::Unverified);
+            assert!(has("SIGNATURE_DIGEST_UNVERIFIED"));
+        }
+    }
+
+    #[test]
+    fn validate_warns_when_nav_order_disagrees_with_spine() {
+        let chapter =
+            br#"<html xmlns="http://www.w3.org/1999/xhtml"><body><p>Hello</p></body></html>"#;
+        let data = build_zip(&[
+            ("mimetype", b"application/epub+zip"),
+            (
+                "META-INF/container.xml",
+                br#"<?xml version="1.0"?>
+<container version="1.0" xmlns="urn:oasis:names:tc:opendocument:xmlns:container">
+  <rootfiles>
+    <rootfile full-path="EPUB/package.opf" media-type="application/oebps-package+xml"/>
+  </rootfiles>
+</container>"#,
+            ),
+            (
+                "EPUB/package.opf",
+                br#"<?xml version="1.0" encoding="UTF-8"?>
+<package version="3.0" xmlns="http://www.idpf.org/2007/opf">
+  <metadata xmlns:dc="http://purl.org/dc/elements/1.1/">
+    <dc:title>Test Book</dc:title>
+    <dc:creator>Tester</dc:creator>
+    <dc:language>en</dc:language>
+  </metadata>
+  <manifest>
+    <item id="nav" href="nav.xhtml" media-type="application/xhtml+xml" properties="nav"/>
+    <item id="c1" href="text/ch1.xhtml" media-type="application/xhtml+xml"/>
+    <item id="c2" href="text/ch2.xhtml" media-type="application/xhtml+xml"/>
+    <item id="c3" href="text/ch3.xhtml" media-type="application/xhtml+xml"/>
+    <item id="notes" href="text/notes.xhtml" media-type="application/xhtml+xml"/>
+  </manifest>
+  <spine>
+    <itemref idref="c1"/><itemref idref="c2"/><itemref idref="c3"/>
+    <itemref idref="notes" linear="no"/>
+  </spine>
+</package>"#,
+            ),
+            (
+                "EPUB/nav.xhtml",
+                br#"<?xml version="1.0" encoding="utf-8"?>
+<html xmlns="http://www.w3.org/1999/xhtml" xmlns:epub="http://www.idpf.org/2007/ops">
+  <body><nav epub:type="toc"><ol>
+    <li><a href="text/ch1.xhtml">One</a></li>
+    <li><a href="text/notes.xhtml">Notes</a></li>
+    <li><a href="text/ch3.xhtml">Three</a><ol><li><a href="text/ch3.xhtml#s1">3.1</a></li></ol></li>
+    <li><a href="./text/ch2.xhtml#start">Two</a></li>
+  </ol></nav></body>
+</html>"#,
+            ),
+            ("EPUB/text/ch1.xhtml", chapter),
+            ("EPUB/text/ch2.xhtml", chapter),
+            ("EPUB/text/ch3.xhtml", chapter),
+            ("EPUB/text/notes.xhtml", chapter),
+        ]);
+        let report = validate_epub_reader(std::io::Cursor::new(data));
+        let mismatches: Vec<&ValidationDiagnostic> = report
+            .diagnostics()
+            .iter()
+            .filter(|d| d.code == "NAV_SPINE_ORDER_MISMATCH")
+            .collect();
+        assert_eq!(mismatches.len(), 1);
+        assert_eq!(mismatches[0].severity, ValidationSeverity::Warning);
+        assert_eq!(
+            mismatches[0].path.as_deref(),
+            Some("./text/ch2.xhtml#start")
+        );
+        assert!(mismatches[0]
+            .message
+            .contains("after 'text/ch3.xhtml' (spine position 2)"));
+
+        let ordered = validate_epub_reader(std::io::Cursor::new(minimal_valid_epub_zip()));
+        assert!(ordered
+            .diagnostics()
+            .iter()
+            .all(|d| d.code != "NAV_SPINE_ORDER_MISMATCH"));
+    }
+
+    #[test]
+    fn css_analysis_reports_redundant_and_unused_selectors() {
+        let css = br#"/* base */
+p { margin: 0; }
+.intro, h1 { font-weight: bold; }
+p { margin: 1em; }
+.unused-class { color: red; }
+div > span.note:first-child { color: blue; }
+@media print { p { margin: 0 } }
+@font-face { font-family: X; src: url(x.ttf); }
+"#;
+        let data = build_zip(&[
+            ("mimetype", b"application/epub+zip"),
+            (
+                "META-INF/container.xml",
+                br#"<?xml version="1.0"?>
+<container version="1.0" xmlns="urn:oasis:names:tc:opendocument:xmlns:container">
+  <rootfiles>
+    <rootfile full-path="EPUB/package.opf" media-type="application/oebps-package+xml"/>
+  </rootfiles>
+</container>"#,
+            ),
+            (
+                "EPUB/package.opf",
+                br#"<?xml version="1.0" encoding="UTF-8"?>
+<package version="3.0" xmlns="http://www.idpf.org/2007/opf">
+  <metadata xmlns:dc="http://purl.org/dc/elements/1.1/">
+    <dc:title>Test Book</dc:title>
+    <dc:creator>Tester</dc:creator>
+    <dc:language>en</dc:language>
+  </metadata>
+  <manifest>
+    <item id="nav" href="nav.xhtml" media-type="application/xhtml+xml" properties="nav"/>
+    <item id="c1" href="ch1.xhtml" media-type="application/xhtml+xml"/>
+    <item id="css" href="style.css" media-type="text/css"/>
+  </manifest>
+  <spine><itemref idref="c1"/></spine>
+</package>"#,
+            ),
+            (
+                "EPUB/nav.xhtml",
+                br#"<?xml version="1.0" encoding="utf-8"?>
+<html xmlns="http://www.w3.org/1999/xhtml" xmlns:epub="http://www.idpf.org/2007/ops">
+  <body><nav epub:type="toc"><ol><li><a href="ch1.xhtml">Chapter 1</a></li></ol></nav></body>
+</html>"#,
+            ),
+            (
+                "EPUB/ch1.xhtml",
+                br#"<html xmlns="http://www.w3.org/1999/xhtml"><body><h1>T</h1><p class="intro">Hello</p></body></html>"#,
+            ),
+            ("EPUB/style.css", css),
+        ]);
+
+        let plain = validate_epub_reader(std::io::Cursor::new(data.clone()));
+        assert!(plain.stylesheet_stats().is_empty());
+
+        let options = ValidationOptions {
+            css_analysis: Some(CssAnalysisOptions::default()),
+            ..ValidationOptions::default()
+        };
+        let report = validate_epub_reader_with_options(std::io::Cursor::new(data), options);
+        assert_eq!(
+            report.stylesheet_stats(),
+            [StylesheetStats {
+                path: "EPUB/style.css".to_string(),
+                bytes: css.len(),
+                rules: 6,
+                selectors: 7,
+                duplicate_selectors: 1,
+                overridden_rules: 1,
+                unused_selectors: Some(2),
+            }]
+        );
+        let message = |code: &str| {
+            report
+                .diagnostics()
+                .iter()
+                .find(|d| d.code == code)
+                .map(|d| d.message.clone())
+                .unwrap_or_default()
+        };
+        assert!(message("CSS_SELECTOR_DUPLICATE").contains("'p'"));
+        assert!(message("CSS_RULE_OVERRIDDEN").contains("'p'"));
+        assert!(message("CSS_SELECTOR_UNUSED")
+            .contains("'.unused-class', 'div > span.note:first-child'"));
+    }
+
+    #[test]
+    fn device_profile_flags_oversized_images() {
+        let mut png = b"\x89PNG\r\n\x1a\n\0\0\0\x0dIHDR".to_vec();
+        png.extend_from_slice(&2000u32.to_be_bytes());
+        png.extend_from_slice(&1000u32.to_be_bytes());
+        png.resize(1000, 0);
+        let mut jpeg = vec![0xff, 0xd8, 0xff, 0xe0, 0, 4, 0, 0, 0xff, 0xc0, 0, 17, 8];
+        jpeg.extend_from_slice(&50u16.to_be_bytes());
+        jpeg.extend_from_slice(&100u16.to_be_bytes());
+        jpeg.resize(40, 0);
+        let gif = b"GIF89a\x0a\x00\x0a\x00";
+        assert_eq!(image_dimensions(&jpeg), Some((100, 50)));
+        assert_eq!(image_dimensions(gif), Some((10, 10)));
+        assert_eq!(
+            image_dimensions(b"RIFF\0\0\0\0WEBPVP8L\0\0\0\0\x2f\x3f\x80\x1f\x00"),
+            Some((64, 127))
+        );
+
+        let data = build_zip(&[
+            ("mimetype", b"application/epub+zip"),
+            (
+                "META-INF/container.xml",
+                br#"<?xml version="1.0"?>
+<container version="1.0" xmlns="urn:oasis:names:tc:opendocument:xmlns:container">
+  <rootfiles>
+    <rootfile full-path="EPUB/package.opf" media-type="application/oebps-package+xml"/>
+  </rootfiles>
+</container>"#,
+            ),
+            (
+                "EPUB/package.opf",
+                br#"<?xml version="1.0" encoding="UTF-8"?>
+<package version="3.0" xmlns="http://www.idpf.org/2007/opf">
+  <metadata xmlns:dc="http://purl.org/dc/elements/1.1/">
+    <dc:title>Test Book</dc:title>
+    <dc:creator>Tester</dc:creator>
+    <dc:language>en</dc:language>
+  </metadata>
+  <manifest>
+    <item id="nav" href="nav.xhtml" media-type="application/xhtml+xml" properties="nav"/>
+    <item id="c1" href="ch1.xhtml" media-type="application/xhtml+xml"/>
+    <item id="big" href="img/big.png" media-type="image/png"/>
+    <item id="heavy" href="img/heavy.jpg" media-type="image/jpeg"/>
+    <item id="small" href="img/small.gif" media-type="image/gif"/>
+  </manifest>
+  <spine><itemref idref="c1"/></spine>
+</package>"#,
+            ),
+            (
+                "EPUB/nav.xhtml",
+                br#"<?xml version="1.0" encoding="utf-8"?>
+<html xmlns="http://www.w3.org/1999/xhtml" xmlns:epub="http://www.idpf.org/2007/ops">
+  <body><nav epub:type="toc"><ol><li><a href="ch1.xhtml">Chapter 1</a></li></ol></nav></body>
+</html>"#,
+            ),
+            (
+                "EPUB/ch1.xhtml",
+                br#"<html xmlns="http://www.w3.org/1999/xhtml"><body><p>Hello</p></body></html>"#,
+            ),
+            ("EPUB/img/big.png", &png),
+            ("EPUB/img/heavy.jpg", &jpeg),
+            ("EPUB/img/small.gif", gif),
+        ]);
+        let options = ValidationOptions {
+            device_profile: Some(DeviceProfile {
+                max_image_bytes: 30,
+                ..DeviceProfile::new(600, 800)
+            }),
+            ..ValidationOptions::default()
+        };
+        let report = validate_epub_reader_with_options(std::io::Cursor::new(data), options);
+        assert_eq!(
+            report.oversized_images(),
+            [
+                OversizedImage {
+                    path: "EPUB/img/big.png".to_string(),
+                    dimensions: Some((2000, 1000)),
+                    bytes: 1000,
+                    wasted_bytes: 970,
+                },
+                OversizedImage {
+                    path: "EPUB/img/heavy.jpg".to_string(),
+                    dimensions: Some((100, 50)),
+                    bytes: 40,
+                    wasted_bytes: 10,
+                },
+            ]
+        );
+        assert_eq!(report.wasted_image_bytes(), 980);
+        let codes: Vec<&str> = report
+            .diagnostics()
+            .iter()
+            .filter(|d| d.code.starts_with("IMAGE_"))
+            .map(|d| d.code.as_ref())
+            .collect();
+        assert_eq!(
+            codes,
+            [
+                "IMAGE_EXCEEDS_DISPLAY",
+                "IMAGE_BYTES_EXCEED_LIMIT",
+                "IMAGE_BYTES_EXCEED_LIMIT"
+            ]
+        );
+    }
+
+    #[test]
+    fn decode_base64_handles_padding_and_whitespace() {
+        assert_eq!(decode_base64("aGVs\nbG8="), Some(b"hello".to_vec()));
+        assert_eq!(decode_base64("aGk="), Some(b"hi".to_vec()));
+        assert_eq!(decode_base64("a=b="), None);
+        assert_eq!(decode_base64("not base64!"), None);
+    }
+
     #[test]
     fn validate_detects_invalid_rights_xml() {
         let data = build_zip(&[
@@ -1355,4 +3197,26 @@ mod tests {
             .iter()
             .any(|d| d.code == "RIGHTS_XML_PARSE_ERROR"));
     }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_report_serde_round_trip_keeps_unknown_codes() {
+        let mut report = ValidationReport::new();
+        let mut d = ValidationDiagnostic::error("SPINE_EMPTY", "spine has no itemrefs");
+        d.spec_ref = Some(Cow::Borrowed("OPF spine/itemref"));
+        d.path = Some("OEBPS/content.opf".to_string());
+        report.push(d);
+        report.push(ValidationDiagnostic::warning("NAV_MISSING", "no nav"));
+
+        let json = serde_json::to_string(&report).unwrap();
+        assert!(json.contains("\"spec_ref\":\"OPF spine/itemref\""));
+        let back: ValidationReport = serde_json::from_str(&json).unwrap();
+        assert_eq!(back, report);
+
+        // Codes from a newer release deserialize as owned labels.
+        let json = json.replace("NAV_MISSING", "NAV_FROM_THE_FUTURE");
+        let back: ValidationReport = serde_json::from_str(&json).unwrap();
+        assert_eq!(back.diagnostics()[1].code, "NAV_FROM_THE_FUTURE");
+        assert_eq!(back.warning_count(), 1);
+    }
 }
@@ -7,10 +7,21 @@
 extern crate alloc;
 
 use alloc::string::{String, ToString};
+#[cfg(target_has_atomic = "64")]
+use alloc::sync::Arc;
+#[cfg(target_has_atomic = "64")]
+use core::sync::atomic::{AtomicU64, Ordering};
 use heapless::Vec as HeaplessVec;
 use log;
 use miniz_oxide::{DataFormat, MZFlush, MZStatus};
-use std::io::{Read, Seek, SeekFrom, Write};
+use std::io::Write;
+
+use crate::storage::RandomAccess;
+
+#[cfg(feature = "async")]
+mod async_reader;
+#[cfg(feature = "async")]
+pub use async_reader::AsyncStreamingZip;
 
 /// Maximum number of central directory entries to cache
 const MAX_CD_ENTRIES: usize = 256;
@@ -18,6 +29,16 @@ const MAX_CD_ENTRIES: usize = 256;
 /// Maximum filename length in ZIP entries
 const MAX_FILENAME_LEN: usize = 256;
 
+/// Magic prefix of an exported central directory snapshot.
+const SNAPSHOT_MAGIC: [u8; 4] = *b"MUZD";
+/// Snapshot layout version; bumped on incompatible changes.
+const SNAPSHOT_VERSION: u8 = 1;
+/// Fixed snapshot header: magic, version + 3 reserved bytes, archive size,
+/// mtime, EOCD offset, EOCD CRC, total entries, directory size, stored entries.
+const SNAPSHOT_HEADER_LEN: usize = 56;
+/// Fixed bytes per snapshot entry, excluding the filename.
+const SNAPSHOT_ENTRY_LEN: usize = 32;
+
 /// Runtime-configurable ZIP safety limits.
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct ZipLimits {
@@ -29,16 +50,36 @@ pub struct ZipLimits {
     pub strict: bool,
     /// Maximum bytes scanned from file tail while searching for EOCD.
     pub max_eocd_scan: usize,
+    /// Maximum uncompressed-to-compressed size ratio per entry; `0` (the
+    /// default) disables the check. Entries inflating to at most 64 KiB are
+    /// always allowed.
+    pub max_compression_ratio: u32,
+    /// Maximum bytes decompressed over the reader's lifetime, across all
+    /// entries.
+    pub max_total_decompressed: u64,
+    /// Maximum central directory size the async reader buffers while opening.
+    pub max_central_directory_size: usize,
+    /// Maximum inflate checkpoints kept by ranged reads; each holds a ~43 KB
+    /// inflate state. `0` disables checkpoints.
+    pub max_sync_points: usize,
 }
 
 impl ZipLimits {
     /// Create explicit ZIP limits.
+    ///
+    /// Zip-bomb guards are off; enable them with
+    /// [`with_max_compression_ratio`](Self::with_max_compression_ratio) and
+    /// [`with_max_total_decompressed`](Self::with_max_total_decompressed).
     pub fn new(max_file_read_size: usize, max_mimetype_size: usize) -> Self {
         Self {
             max_file_read_size,
             max_mimetype_size,
             strict: false,
             max_eocd_scan: MAX_EOCD_SCAN,
+            max_compression_ratio: 0,
+            max_total_decompressed: u64::MAX,
+            max_central_directory_size: MAX_CD_BUFFER,
+            max_sync_points: MAX_SYNC_POINTS,
         }
     }
 
@@ -53,8 +94,38 @@ impl ZipLimits {
         self.max_eocd_scan = max_eocd_scan.max(EOCD_MIN_SIZE);
         self
     }
+
+    /// Set the per-entry compression ratio cap; `0` disables it.
+    ///
+    /// Real EPUB content rarely exceeds 20:1 and DEFLATE tops out near
+    /// 1032:1, so a cap around 100 rejects bombs without false positives.
+    pub fn with_max_compression_ratio(mut self, max_compression_ratio: u32) -> Self {
+        self.max_compression_ratio = max_compression_ratio;
+        self
+    }
+
+    /// Set the total decompression budget for the reader's lifetime.
+    pub fn with_max_total_decompressed(mut self, max_total_decompressed: u64) -> Self {
+        self.max_total_decompressed = max_total_decompressed;
+        self
+    }
+
+    /// Set the cap on the central directory buffered by the async reader.
+    pub fn with_max_central_directory_size(mut self, max_central_directory_size: usize) -> Self {
+        self.max_central_directory_size = max_central_directory_size;
+        self
+    }
+
+    /// Set how many ranged-read checkpoints the reader keeps; `0` disables them.
+    pub fn with_max_sync_points(mut self, max_sync_points: usize) -> Self {
+        self.max_sync_points = max_sync_points;
+        self
+    }
 }
 
+/// Output size below which the compression ratio is not checked.
+const RATIO_GRACE_BYTES: u64 = 64 * 1024;
+
 /// Local file header signature (little-endian)
 const SIG_LOCAL_FILE_HEADER: u32 = 0x04034b50;
 
@@ -71,6 +142,14 @@ const SIG_ZIP64_EOCD_LOCATOR: u32 = 0x07064b50;
 const EOCD_MIN_SIZE: usize = 22;
 /// Maximum EOCD search window (EOCD + max comment length)
 const MAX_EOCD_SCAN: usize = EOCD_MIN_SIZE + u16::MAX as usize;
+/// Default cap on a central directory buffered in memory
+const MAX_CD_BUFFER: usize = 1024 * 1024;
+
+/// Decompressed distance between inflate checkpoints kept by ranged reads.
+const SYNC_POINT_INTERVAL: u64 = 256 * 1024;
+/// Default checkpoints kept across all entries; each holds a ~43 KB inflate
+/// state.
+const MAX_SYNC_POINTS: usize = 8;
 
 /// Compression methods
 const METHOD_STORED: u16 = 0;
@@ -81,6 +160,7 @@ pub use crate::error::ZipError;
 
 #[derive(Clone, Copy, Debug)]
 struct EocdInfo {
+    eocd_pos: u64,
     cd_offset: u64,
     cd_size: u64,
     num_entries: u64,
@@ -96,7 +176,7 @@ struct Zip64EocdInfo {
 }
 
 /// Central directory entry metadata
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct CdEntry {
     /// Compression method (0=stored, 8=deflated)
     pub method: u16,
@@ -126,19 +206,88 @@ impl CdEntry {
     }
 }
 
+/// Sequential reader over a [`RandomAccess`] source.
+///
+/// Tracks its own position so variable-length records can be parsed with
+/// plain positioned reads.
+struct StorageCursor<'a, F: RandomAccess> {
+    storage: &'a mut F,
+    pos: u64,
+}
+
+impl<'a, F: RandomAccess> StorageCursor<'a, F> {
+    fn new(storage: &'a mut F, pos: u64) -> Self {
+        Self { storage, pos }
+    }
+
+    /// Fill `buf` and advance past it.
+    fn read_exact(&mut self, buf: &mut [u8]) -> Result<(), ZipError> {
+        self.storage.read_exact_at(self.pos, buf)?;
+        self.pos += buf.len() as u64;
+        Ok(())
+    }
+
+    /// Advance without reading.
+    fn skip(&mut self, len: usize) {
+        self.pos = self.pos.saturating_add(len as u64);
+    }
+}
+
 /// Streaming ZIP file reader
-pub struct StreamingZip<F: Read + Seek> {
+pub struct StreamingZip<F: RandomAccess> {
     /// File handle
     file: F,
     /// Central directory entries (fixed size)
     entries: HeaplessVec<CdEntry, MAX_CD_ENTRIES>,
     /// Number of entries in central directory
     num_entries: usize,
+    /// Central directory size in bytes, as recorded in the EOCD.
+    cd_size: u64,
+    /// Offset of the end of central directory record.
+    eocd_pos: u64,
     /// Optional configurable resource/safety limits.
     limits: Option<ZipLimits>,
+    /// Whether lookups fall back to Unicode-tolerant name matching.
+    tolerant_names: bool,
+    /// Whether the directory was restored from a snapshot instead of scanned.
+    directory_imported: bool,
+    /// Bytes decompressed so far, charged against
+    /// [`ZipLimits::max_total_decompressed`].
+    decompressed_total: u64,
+    /// Budget shared with other readers of the same archive, which replaces
+    /// `decompressed_total` when set.
+    #[cfg(target_has_atomic = "64")]
+    shared_total: Option<SharedDecompressedTotal>,
+    /// Inflate checkpoints recorded by ranged reads, oldest first.
+    sync_points: alloc::vec::Vec<SyncPoint>,
 }
 
-impl<F: Read + Seek> StreamingZip<F> {
+/// Decompression total shared by several [`StreamingZip`] readers of one
+/// archive; clones refer to the same count.
+///
+/// Only available on targets with 64-bit atomics.
+#[cfg(target_has_atomic = "64")]
+#[derive(Clone, Debug, Default)]
+pub struct SharedDecompressedTotal(Arc<AtomicU64>);
+
+#[cfg(target_has_atomic = "64")]
+impl SharedDecompressedTotal {
+    /// Start a shared total at `bytes`.
+    pub fn new(bytes: u64) -> Self {
+        Self(Arc::new(AtomicU64::new(bytes)))
+    }
+
+    /// Bytes charged so far.
+    pub fn get(&self) -> u64 {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    fn charge(&self, bytes: u64) {
+        self.0.fetch_add(bytes, Ordering::Relaxed);
+    }
+}
+
+impl<F: RandomAccess> StreamingZip<F> {
     /// Open a ZIP file and parse the central directory
     pub fn new(file: F) -> Result<Self, ZipError> {
         Self::new_with_limits(file, None)
@@ -156,342 +305,164 @@ impl<F: Read + Seek> StreamingZip<F> {
             return Err(ZipError::CentralDirFull);
         }
 
-        let mut entries: HeaplessVec<CdEntry, MAX_CD_ENTRIES> = HeaplessVec::new();
-
         // Parse central directory entries
-        file.seek(SeekFrom::Start(eocd.cd_offset))
-            .map_err(|_| ZipError::IoError)?;
-        let cd_end = eocd
-            .cd_offset
-            .checked_add(eocd.cd_size)
-            .ok_or(ZipError::InvalidFormat)?;
-
-        let entries_to_scan = core::cmp::min(eocd.num_entries, MAX_CD_ENTRIES as u64);
-        for _ in 0..entries_to_scan {
-            let pos = file.stream_position().map_err(|_| ZipError::IoError)?;
-            if pos >= cd_end {
-                if strict {
-                    return Err(ZipError::InvalidFormat);
-                }
-                break;
-            }
-            if let Some(entry) = Self::read_cd_entry(&mut file)? {
-                entries.push(entry).map_err(|_| ZipError::CentralDirFull)?;
-            } else if strict {
-                return Err(ZipError::InvalidFormat);
-            } else {
-                break;
-            }
-        }
-
-        if eocd.num_entries > MAX_CD_ENTRIES as u64 {
-            log::warn!(
-                "[ZIP] Archive has {} entries but only {} were loaded (max: {})",
-                eocd.num_entries,
-                entries.len(),
-                MAX_CD_ENTRIES
-            );
-        }
-
-        log::debug!(
-            "[ZIP] Parsed {} central directory entries (offset {})",
-            entries.len(),
-            eocd.cd_offset
-        );
+        let entries = parse_central_directory(&mut file, eocd.cd_offset, &eocd, strict)?;
 
         Ok(Self {
             file,
             entries,
             num_entries: core::cmp::min(eocd.num_entries, usize::MAX as u64) as usize,
+            cd_size: eocd.cd_size,
+            eocd_pos: eocd.eocd_pos,
             limits,
+            tolerant_names: false,
+            directory_imported: false,
+            decompressed_total: 0,
+            #[cfg(target_has_atomic = "64")]
+            shared_total: None,
+            sync_points: alloc::vec::Vec::with_capacity(0),
         })
     }
 
-    /// Find EOCD and extract central directory info
-    fn find_eocd(file: &mut F, max_eocd_scan: usize) -> Result<EocdInfo, ZipError> {
-        // Get file size
-        let file_size = file.seek(SeekFrom::End(0)).map_err(|_| ZipError::IoError)?;
-
-        if file_size < EOCD_MIN_SIZE as u64 {
-            return Err(ZipError::InvalidFormat);
-        }
-
-        // Scan last (EOCD + max comment) bytes for EOCD signature.
-        let scan_range = file_size.min(max_eocd_scan as u64) as usize;
-        let mut buffer = alloc::vec![0u8; scan_range];
-
-        file.seek(SeekFrom::Start(file_size - scan_range as u64))
-            .map_err(|_| ZipError::IoError)?;
-        let bytes_read = file.read(&mut buffer).map_err(|_| ZipError::IoError)?;
-        let scan_base = file_size - bytes_read as u64;
-
-        // Scan backwards for EOCD signature
-        for i in (0..=bytes_read.saturating_sub(EOCD_MIN_SIZE)).rev() {
-            if Self::read_u32_le(&buffer, i) == SIG_EOCD {
-                // Found EOCD, extract info
-                let num_entries = Self::read_u16_le(&buffer, i + 8);
-                let cd_size_32 = Self::read_u32_le(&buffer, i + 12);
-                let cd_offset_32 = Self::read_u32_le(&buffer, i + 16) as u64;
-                let comment_len = Self::read_u16_le(&buffer, i + 20) as u64;
-                let eocd_pos = scan_base + i as u64;
-                let eocd_end = eocd_pos + EOCD_MIN_SIZE as u64 + comment_len;
-                if eocd_end != file_size {
-                    continue;
-                }
-
-                let uses_zip64_sentinel = num_entries == u16::MAX
-                    || cd_size_32 == u32::MAX
-                    || cd_offset_32 == u32::MAX as u64;
-
-                let mut zip64_locator: Option<(u32, u64, u32)> = None;
-                if eocd_pos >= 20 {
-                    file.seek(SeekFrom::Start(eocd_pos - 20))
-                        .map_err(|_| ZipError::IoError)?;
-                    let mut locator = [0u8; 20];
-                    file.read_exact(&mut locator)
-                        .map_err(|_| ZipError::IoError)?;
-                    if u32::from_le_bytes([locator[0], locator[1], locator[2], locator[3]])
-                        == SIG_ZIP64_EOCD_LOCATOR
-                    {
-                        let zip64_disk =
-                            u32::from_le_bytes([locator[4], locator[5], locator[6], locator[7]]);
-                        let zip64_eocd_offset = u64::from_le_bytes([
-                            locator[8],
-                            locator[9],
-                            locator[10],
-                            locator[11],
-                            locator[12],
-                            locator[13],
-                            locator[14],
-                            locator[15],
-                        ]);
-                        let total_disks = u32::from_le_bytes([
-                            locator[16],
-                            locator[17],
-                            locator[18],
-                            locator[19],
-                        ]);
-                        zip64_locator = Some((zip64_disk, zip64_eocd_offset, total_disks));
-                    }
-                }
-
-                if uses_zip64_sentinel || zip64_locator.is_some() {
-                    let (zip64_disk, zip64_eocd_offset, total_disks) =
-                        zip64_locator.ok_or(ZipError::InvalidFormat)?;
-                    if zip64_disk != 0 || total_disks != 1 {
-                        return Err(ZipError::UnsupportedZip64);
-                    }
-                    let zip64 = Self::read_zip64_eocd(file, zip64_eocd_offset)?;
-                    if zip64.disk_number != 0 || zip64.disk_with_cd_start != 0 {
-                        return Err(ZipError::UnsupportedZip64);
-                    }
-                    let cd_end = zip64
-                        .cd_offset
-                        .checked_add(zip64.cd_size)
-                        .ok_or(ZipError::InvalidFormat)?;
-                    if cd_end > eocd_pos || cd_end > file_size {
-                        return Err(ZipError::InvalidFormat);
-                    }
-                    return Ok(EocdInfo {
-                        cd_offset: zip64.cd_offset,
-                        cd_size: zip64.cd_size,
-                        num_entries: zip64.num_entries,
-                    });
-                }
-
-                let cd_end = cd_offset_32
-                    .checked_add(cd_size_32 as u64)
-                    .ok_or(ZipError::InvalidFormat)?;
-                if cd_end > eocd_pos || cd_end > file_size {
-                    return Err(ZipError::InvalidFormat);
-                }
-
-                return Ok(EocdInfo {
-                    cd_offset: cd_offset_32,
-                    cd_size: cd_size_32 as u64,
-                    num_entries: num_entries as u64,
-                });
-            }
-        }
-
-        Err(ZipError::InvalidFormat)
-    }
-
-    fn read_zip64_eocd(file: &mut F, offset: u64) -> Result<Zip64EocdInfo, ZipError> {
-        file.seek(SeekFrom::Start(offset))
-            .map_err(|_| ZipError::IoError)?;
-        let mut fixed = [0u8; 56];
-        file.read_exact(&mut fixed).map_err(|_| ZipError::IoError)?;
-
-        let sig = u32::from_le_bytes([fixed[0], fixed[1], fixed[2], fixed[3]]);
-        if sig != SIG_ZIP64_EOCD {
-            return Err(ZipError::InvalidFormat);
+    /// Open a ZIP file using a central directory snapshot from
+    /// [`export_directory`](Self::export_directory).
+    ///
+    /// The snapshot is only trusted if the archive still has the same size,
+    /// the caller-supplied `mtime` matches the one it was exported with, and
+    /// the EOCD record (including its comment) has the same CRC-32. A stale,
+    /// corrupt, or incompatible snapshot falls back to scanning the archive
+    /// like [`new_with_limits`](Self::new_with_limits); check
+    /// [`directory_imported`](Self::directory_imported) to learn whether the
+    /// cache should be refreshed.
+    pub fn import_directory(
+        mut file: F,
+        snapshot: &[u8],
+        mtime: u64,
+        limits: Option<ZipLimits>,
+    ) -> Result<Self, ZipError> {
+        let Some(restored) = decode_directory_snapshot(snapshot) else {
+            log::debug!("[ZIP] Ignoring malformed central directory snapshot");
+            return Self::new_with_limits(file, limits);
+        };
+        let archive_size = file.size()?;
+        let max_eocd_scan = limits
+            .map(|l| l.max_eocd_scan.min(MAX_EOCD_SCAN))
+            .unwrap_or(MAX_EOCD_SCAN);
+        let fresh = restored.archive_size == archive_size
+            && restored.mtime == mtime
+            && archive_size - restored.eocd_pos <= max_eocd_scan as u64
+            && eocd_crc(&mut file, restored.eocd_pos, archive_size)? == restored.eocd_crc;
+        if !fresh {
+            log::debug!("[ZIP] Central directory snapshot is stale; rescanning");
+            return Self::new_with_limits(file, limits);
         }
-
-        let record_size = u64::from_le_bytes([
-            fixed[4], fixed[5], fixed[6], fixed[7], fixed[8], fixed[9], fixed[10], fixed[11],
-        ]);
-        if record_size < 44 {
-            return Err(ZipError::InvalidFormat);
+        if limits.is_some_and(|l| l.strict) && restored.num_entries > MAX_CD_ENTRIES as u64 {
+            return Err(ZipError::CentralDirFull);
         }
 
-        let disk_number = u32::from_le_bytes([fixed[16], fixed[17], fixed[18], fixed[19]]);
-        let disk_with_cd_start = u32::from_le_bytes([fixed[20], fixed[21], fixed[22], fixed[23]]);
-        let num_entries = u64::from_le_bytes([
-            fixed[32], fixed[33], fixed[34], fixed[35], fixed[36], fixed[37], fixed[38], fixed[39],
-        ]);
-        let cd_size = u64::from_le_bytes([
-            fixed[40], fixed[41], fixed[42], fixed[43], fixed[44], fixed[45], fixed[46], fixed[47],
-        ]);
-        let cd_offset = u64::from_le_bytes([
-            fixed[48], fixed[49], fixed[50], fixed[51], fixed[52], fixed[53], fixed[54], fixed[55],
-        ]);
-
-        Ok(Zip64EocdInfo {
-            disk_number,
-            disk_with_cd_start,
-            num_entries,
-            cd_size,
-            cd_offset,
+        Ok(Self {
+            file,
+            entries: restored.entries,
+            num_entries: core::cmp::min(restored.num_entries, usize::MAX as u64) as usize,
+            cd_size: restored.cd_size,
+            eocd_pos: restored.eocd_pos,
+            limits,
+            tolerant_names: false,
+            directory_imported: true,
+            decompressed_total: 0,
+            #[cfg(target_has_atomic = "64")]
+            shared_total: None,
+            sync_points: alloc::vec::Vec::with_capacity(0),
         })
     }
 
-    /// Read a central directory entry from file
-    fn read_cd_entry(file: &mut F) -> Result<Option<CdEntry>, ZipError> {
-        let mut sig_buf = [0u8; 4];
-        if file.read_exact(&mut sig_buf).is_err() {
-            return Ok(None);
-        }
-        let sig = u32::from_le_bytes(sig_buf);
-
-        if sig != SIG_CD_ENTRY {
-            return Ok(None); // End of central directory
+    /// Serialize the parsed central directory into a compact snapshot.
+    ///
+    /// `mtime` is an opaque modification stamp of the archive (e.g. seconds
+    /// since the epoch or a FAT timestamp) that the caller passes again to
+    /// [`import_directory`](Self::import_directory). The snapshot is
+    /// checksummed and also records the archive size and a CRC-32 of the
+    /// EOCD record, so it can be cached next to the book and safely ignored
+    /// once the file changes.
+    pub fn export_directory(&mut self, mtime: u64) -> Result<alloc::vec::Vec<u8>, ZipError> {
+        let archive_size = self.file.size()?;
+        let eocd_crc = eocd_crc(&mut self.file, self.eocd_pos, archive_size)?;
+        let names: usize = self.entries.iter().map(|e| e.filename.len()).sum();
+        let mut out = alloc::vec::Vec::with_capacity(
+            SNAPSHOT_HEADER_LEN + self.entries.len() * SNAPSHOT_ENTRY_LEN + names + 4,
+        );
+        out.extend_from_slice(&SNAPSHOT_MAGIC);
+        out.extend_from_slice(&[SNAPSHOT_VERSION, 0, 0, 0]);
+        out.extend_from_slice(&archive_size.to_le_bytes());
+        out.extend_from_slice(&mtime.to_le_bytes());
+        out.extend_from_slice(&self.eocd_pos.to_le_bytes());
+        out.extend_from_slice(&eocd_crc.to_le_bytes());
+        out.extend_from_slice(&(self.num_entries as u64).to_le_bytes());
+        out.extend_from_slice(&self.cd_size.to_le_bytes());
+        out.extend_from_slice(&(self.entries.len() as u32).to_le_bytes());
+        for entry in &self.entries {
+            out.extend_from_slice(&entry.method.to_le_bytes());
+            out.extend_from_slice(&entry.crc32.to_le_bytes());
+            out.extend_from_slice(&entry.compressed_size.to_le_bytes());
+            out.extend_from_slice(&entry.uncompressed_size.to_le_bytes());
+            out.extend_from_slice(&entry.local_header_offset.to_le_bytes());
+            out.extend_from_slice(&(entry.filename.len() as u16).to_le_bytes());
+            out.extend_from_slice(entry.filename.as_bytes());
         }
+        let crc = crc32fast::hash(&out);
+        out.extend_from_slice(&crc.to_le_bytes());
+        Ok(out)
+    }
 
-        // Read fixed portion of central directory entry (42 bytes = offsets 4-45)
-        // This includes everything up to and including the local header offset
-        let mut buf = [0u8; 42];
-        file.read_exact(&mut buf).map_err(|_| ZipError::IoError)?;
-
-        let mut entry = CdEntry::new();
+    /// Whether the central directory was restored from a snapshot by
+    /// [`import_directory`](Self::import_directory) rather than scanned.
+    pub fn directory_imported(&self) -> bool {
+        self.directory_imported
+    }
 
-        // Parse central directory entry fields
-        // buf contains bytes 4-49 of the CD entry (after the 4-byte signature)
-        // buf[N] corresponds to CD entry offset (N + 4)
-        entry.method = u16::from_le_bytes([buf[6], buf[7]]); // CD offset 10
-        entry.crc32 = u32::from_le_bytes([buf[12], buf[13], buf[14], buf[15]]); // CD offset 16
-        let compressed_size_32 = u32::from_le_bytes([buf[16], buf[17], buf[18], buf[19]]); // CD offset 20
-        let uncompressed_size_32 = u32::from_le_bytes([buf[20], buf[21], buf[22], buf[23]]); // CD offset 24
-        let name_len = u16::from_le_bytes([buf[24], buf[25]]) as usize; // CD offset 28
-        let extra_len = u16::from_le_bytes([buf[26], buf[27]]) as usize; // CD offset 30
-        let comment_len = u16::from_le_bytes([buf[28], buf[29]]) as usize; // CD offset 32
-        let local_header_offset_32 = u32::from_le_bytes([buf[38], buf[39], buf[40], buf[41]]); // CD offset 42
-        entry.compressed_size = compressed_size_32 as u64;
-        entry.uncompressed_size = uncompressed_size_32 as u64;
-        entry.local_header_offset = local_header_offset_32 as u64;
-
-        // Read filename
-        if name_len > 0 && name_len <= MAX_FILENAME_LEN {
-            let mut name_buf = alloc::vec![0u8; name_len];
-            file.read_exact(&mut name_buf)
-                .map_err(|_| ZipError::IoError)?;
-            entry.filename = String::from_utf8_lossy(&name_buf).to_string();
-        } else if name_len > MAX_FILENAME_LEN {
-            // Skip over filename bytes we can't store
-            file.seek(SeekFrom::Current(name_len as i64))
-                .map_err(|_| ZipError::IoError)?;
+    /// Find EOCD and extract central directory info
+    fn find_eocd(file: &mut F, max_eocd_scan: usize) -> Result<EocdInfo, ZipError> {
+        let file_size = file.size()?;
+        let mut buffer = eocd_scan_buffer(file_size, max_eocd_scan)?;
+        let scan_base = file_size - buffer.len() as u64;
+        file.read_exact_at(scan_base, &mut buffer)?;
+        let record = EocdRecord::locate(&buffer, scan_base, file_size)?;
+
+        let mut locator = None;
+        if let Some(offset) = record.zip64_locator_offset() {
+            let mut bytes = [0u8; 20];
+            file.read_exact_at(offset, &mut bytes)?;
+            locator = parse_zip64_locator(&bytes);
         }
-
-        let needs_zip64_uncompressed = uncompressed_size_32 == u32::MAX;
-        let needs_zip64_compressed = compressed_size_32 == u32::MAX;
-        let needs_zip64_offset = local_header_offset_32 == u32::MAX;
-        let mut got_zip64_uncompressed = false;
-        let mut got_zip64_compressed = false;
-        let mut got_zip64_offset = false;
-
-        // Parse ZIP extra fields, specifically ZIP64 extended information (0x0001).
-        let mut extra_remaining = extra_len;
-        while extra_remaining >= 4 {
-            let mut hdr = [0u8; 4];
-            file.read_exact(&mut hdr).map_err(|_| ZipError::IoError)?;
-            let header_id = u16::from_le_bytes([hdr[0], hdr[1]]);
-            let field_size = u16::from_le_bytes([hdr[2], hdr[3]]) as usize;
-            extra_remaining -= 4;
-
-            if field_size > extra_remaining {
-                return Err(ZipError::InvalidFormat);
-            }
-
-            if header_id == 0x0001 {
-                let mut field_remaining = field_size;
-                if needs_zip64_uncompressed {
-                    if field_remaining < 8 {
-                        return Err(ZipError::InvalidFormat);
-                    }
-                    let mut val = [0u8; 8];
-                    file.read_exact(&mut val).map_err(|_| ZipError::IoError)?;
-                    entry.uncompressed_size = u64::from_le_bytes(val);
-                    got_zip64_uncompressed = true;
-                    field_remaining -= 8;
-                }
-                if needs_zip64_compressed {
-                    if field_remaining < 8 {
-                        return Err(ZipError::InvalidFormat);
-                    }
-                    let mut val = [0u8; 8];
-                    file.read_exact(&mut val).map_err(|_| ZipError::IoError)?;
-                    entry.compressed_size = u64::from_le_bytes(val);
-                    got_zip64_compressed = true;
-                    field_remaining -= 8;
-                }
-                if needs_zip64_offset {
-                    if field_remaining < 8 {
-                        return Err(ZipError::InvalidFormat);
-                    }
-                    let mut val = [0u8; 8];
-                    file.read_exact(&mut val).map_err(|_| ZipError::IoError)?;
-                    entry.local_header_offset = u64::from_le_bytes(val);
-                    got_zip64_offset = true;
-                    field_remaining -= 8;
-                }
-                if field_remaining > 0 {
-                    file.seek(SeekFrom::Current(field_remaining as i64))
-                        .map_err(|_| ZipError::IoError)?;
-                }
-            } else if field_size > 0 {
-                file.seek(SeekFrom::Current(field_size as i64))
-                    .map_err(|_| ZipError::IoError)?;
+        let zip64 = match record.zip64_eocd_offset(locator)? {
+            Some(offset) => {
+                let mut fixed = [0u8; 56];
+                file.read_exact_at(offset, &mut fixed)?;
+                Some(parse_zip64_eocd(&fixed)?)
             }
-            extra_remaining -= field_size;
-        }
-        if extra_remaining > 0 {
-            file.seek(SeekFrom::Current(extra_remaining as i64))
-                .map_err(|_| ZipError::IoError)?;
-        }
-
-        if (needs_zip64_uncompressed && !got_zip64_uncompressed)
-            || (needs_zip64_compressed && !got_zip64_compressed)
-            || (needs_zip64_offset && !got_zip64_offset)
-        {
-            return Err(ZipError::InvalidFormat);
-        }
-
-        if comment_len > 0 {
-            file.seek(SeekFrom::Current(comment_len as i64))
-                .map_err(|_| ZipError::IoError)?;
-        }
-
-        Ok(Some(entry))
+            None => None,
+        };
+        record.resolve(zip64, file_size)
     }
 
     /// Get entry by filename (case-insensitive)
     pub fn get_entry(&self, name: &str) -> Option<&CdEntry> {
-        self.entries.iter().find(|e| {
-            e.filename == name
-                || e.filename.eq_ignore_ascii_case(name)
-                || (name.starts_with('/') && e.filename.eq_ignore_ascii_case(&name[1..]))
-                || (e.filename.starts_with('/') && e.filename[1..].eq_ignore_ascii_case(name))
-        })
+        find_entry(&self.entries, name, self.tolerant_names)
+    }
+
+    /// Enable or disable tolerant entry-name matching.
+    ///
+    /// When enabled, a lookup that finds no exact or ASCII case-insensitive
+    /// match retries comparing names with Unicode case folding and
+    /// composed/decomposed (NFC/NFD) Latin letters treated as equal, as
+    /// produced by archives authored on macOS.
+    pub fn set_tolerant_names(&mut self, tolerant: bool) {
+        self.tolerant_names = tolerant;
+    }
+
+    /// Whether tolerant entry-name matching is enabled.
+    pub fn tolerant_names(&self) -> bool {
+        self.tolerant_names
     }
 
     /// Debug: Log all entries in the ZIP (for troubleshooting)
@@ -533,14 +504,8 @@ impl<F: Read + Seek> StreamingZip<F> {
         if input_buf.is_empty() {
             return Err(ZipError::BufferTooSmall);
         }
-        if let Some(limits) = self.limits {
-            if entry.uncompressed_size > limits.max_file_read_size as u64 {
-                return Err(ZipError::FileTooLarge);
-            }
-            if entry.compressed_size > limits.max_file_read_size as u64 {
-                return Err(ZipError::FileTooLarge);
-            }
-        }
+        check_entry_limits(entry, self.limits)?;
+        let cap = OutputCap::for_entry(entry, self.limits, self.decompressed_total())?;
         let uncompressed_size =
             usize::try_from(entry.uncompressed_size).map_err(|_| ZipError::FileTooLarge)?;
         if uncompressed_size > buf.len() {
@@ -550,10 +515,7 @@ impl<F: Read + Seek> StreamingZip<F> {
         // Calculate data offset by reading local file header
         let data_offset = self.calc_data_offset(entry)?;
 
-        // Seek to data
-        self.file
-            .seek(SeekFrom::Start(data_offset))
-            .map_err(|_| ZipError::IoError)?;
+        let mut data = StorageCursor::new(&mut self.file, data_offset);
 
         match entry.method {
             METHOD_STORED => {
@@ -563,9 +525,9 @@ impl<F: Read + Seek> StreamingZip<F> {
                 if size > buf.len() {
                     return Err(ZipError::BufferTooSmall);
                 }
-                self.file
-                    .read_exact(&mut buf[..size])
-                    .map_err(|_| ZipError::IoError)?;
+                cap.check(size as u64)?;
+                data.read_exact(&mut buf[..size])?;
+                self.charge_decompressed(size as u64);
                 // Verify CRC32
                 if entry.crc32 != 0 {
                     let calc_crc = crc32fast::hash(&buf[..size]);
@@ -587,9 +549,7 @@ impl<F: Read + Seek> StreamingZip<F> {
                 loop {
                     if pending.is_empty() && compressed_remaining > 0 {
                         let take = core::cmp::min(compressed_remaining, input_buf.len());
-                        self.file
-                            .read_exact(&mut input_buf[..take])
-                            .map_err(|_| ZipError::IoError)?;
+                        data.read_exact(&mut input_buf[..take])?;
                         pending = &input_buf[..take];
                         compressed_remaining -= take;
                     }
@@ -608,6 +568,7 @@ impl<F: Read + Seek> StreamingZip<F> {
                     let produced = result.bytes_written;
                     pending = &pending[consumed..];
                     written += produced;
+                    cap.check(written as u64)?;
 
                     match result.status {
                         Ok(MZStatus::StreamEnd) => {
@@ -626,6 +587,7 @@ impl<F: Read + Seek> StreamingZip<F> {
                     }
                 }
 
+                self.charge_decompressed(written as u64);
                 // Verify CRC32 if available
                 if entry.crc32 != 0 {
                     let calc_crc = crc32fast::hash(&buf[..written]);
@@ -669,247 +631,1240 @@ impl<F: Read + Seek> StreamingZip<F> {
         if input_buf.is_empty() || output_buf.is_empty() {
             return Err(ZipError::BufferTooSmall);
         }
-        if let Some(limits) = self.limits {
-            if entry.uncompressed_size > limits.max_file_read_size as u64 {
-                return Err(ZipError::FileTooLarge);
-            }
-            if entry.compressed_size > limits.max_file_read_size as u64 {
-                return Err(ZipError::FileTooLarge);
+        check_entry_limits(entry, self.limits)?;
+        let cap = OutputCap::for_entry(entry, self.limits, self.decompressed_total())?;
+
+        let data_offset = self.calc_data_offset(entry)?;
+        let mut decoder = EntryDecoder::new(entry, cap)?;
+        let mut data = StorageCursor::new(&mut self.file, data_offset);
+        loop {
+            let take = decoder.next_read_len(input_buf.len());
+            if take == 0 {
+                break;
             }
+            data.read_exact(&mut input_buf[..take])?;
+            decoder.decode(&input_buf[..take], output_buf, writer)?;
         }
+        let written = decoder.finish(output_buf, writer)?;
+        self.charge_decompressed(written as u64);
+        Ok(written)
+    }
 
+    /// Stream up to `len` decompressed bytes of a file, starting at byte
+    /// `start`, into `writer`. Returns the bytes written, which is less than
+    /// `len` only when the file ends first.
+    ///
+    /// Stored entries are read directly at the range. DEFLATE streams cannot
+    /// be entered mid-way, so the inflater runs from the nearest cached
+    /// checkpoint at or before `start`, recording new checkpoints every
+    /// 256 KiB of output; later windows over the same entry skip the work
+    /// already done. Up to [`ZipLimits::max_sync_points`] checkpoints (8 by
+    /// default) stay cached on the reader at ~43 KB each. The CRC is not checked, since it covers the whole file.
+    pub fn read_file_range_to_writer<W: Write>(
+        &mut self,
+        entry: &CdEntry,
+        start: u64,
+        len: usize,
+        writer: &mut W,
+    ) -> Result<usize, ZipError> {
+        check_entry_limits(entry, self.limits)?;
+        let cap = OutputCap::for_entry(entry, self.limits, self.decompressed_total())?;
+        let end = start
+            .saturating_add(len as u64)
+            .min(entry.uncompressed_size);
+        if start >= end {
+            return Ok(0);
+        }
         let data_offset = self.calc_data_offset(entry)?;
-        self.file
-            .seek(SeekFrom::Start(data_offset))
-            .map_err(|_| ZipError::IoError)?;
-
+        let mut input_buf = alloc::vec![0u8; 8 * 1024];
         match entry.method {
             METHOD_STORED => {
-                let mut remaining =
-                    usize::try_from(entry.compressed_size).map_err(|_| ZipError::FileTooLarge)?;
-                let mut hasher = crc32fast::Hasher::new();
+                let end = end.min(entry.compressed_size);
+                cap.check(end - start)?;
+                let mut data = StorageCursor::new(&mut self.file, data_offset + start);
+                let mut remaining = usize::try_from(end.saturating_sub(start))
+                    .map_err(|_| ZipError::FileTooLarge)?;
                 let mut written = 0usize;
+                while remaining > 0 {
+                    let take = remaining.min(input_buf.len());
+                    data.read_exact(&mut input_buf[..take])?;
+                    writer
+                        .write_all(&input_buf[..take])
+                        .map_err(|_| ZipError::IoError)?;
+                    remaining -= take;
+                    written += take;
+                }
+                self.charge_decompressed(written as u64);
+                Ok(written)
+            }
+            METHOD_DEFLATED => {
+                self.inflate_range(entry, data_offset, start..end, cap, &mut input_buf, writer)
+            }
+            _ => Err(ZipError::UnsupportedCompression),
+        }
+    }
+
+    fn inflate_range<W: Write>(
+        &mut self,
+        entry: &CdEntry,
+        data_offset: u64,
+        range: core::ops::Range<u64>,
+        cap: OutputCap,
+        input_buf: &mut [u8],
+        writer: &mut W,
+    ) -> Result<usize, ZipError> {
+        let resume = self
+            .sync_points
+            .iter()
+            .filter(|p| p.entry_offset == entry.local_header_offset && p.output <= range.start)
+            .max_by_key(|p| p.output);
+        let (mut state, mut consumed, mut produced) = match resume {
+            Some(point) => (point.state.clone(), point.input, point.output),
+            None => (
+                alloc::boxed::Box::new(miniz_oxide::inflate::stream::InflateState::new(
+                    DataFormat::Raw,
+                )),
+                0,
+                0,
+            ),
+        };
+        let resumed_at = produced;
+        let mut next_sync = (produced / SYNC_POINT_INTERVAL + 1) * SYNC_POINT_INTERVAL;
+        let mut output_buf = alloc::vec![0u8; 8 * 1024];
+        let mut data = StorageCursor::new(&mut self.file, data_offset + consumed);
+        let mut fed = consumed;
+        let (mut pending_start, mut pending_end) = (0usize, 0usize);
+        let mut written = 0usize;
+        while produced < range.end {
+            if pending_start == pending_end && fed < entry.compressed_size {
+                let take = usize::try_from(entry.compressed_size - fed)
+                    .unwrap_or(usize::MAX)
+                    .min(input_buf.len());
+                data.read_exact(&mut input_buf[..take])?;
+                fed += take as u64;
+                (pending_start, pending_end) = (0, take);
+            }
+            let result = miniz_oxide::inflate::stream::inflate(
+                &mut state,
+                &input_buf[pending_start..pending_end],
+                &mut output_buf,
+                MZFlush::None,
+            );
+            pending_start += result.bytes_consumed;
+            consumed += result.bytes_consumed as u64;
+            let chunk_start = produced;
+            produced += result.bytes_written as u64;
+            cap.check(produced)?;
+
+            let from = range.start.max(chunk_start);
+            let to = range.end.min(produced);
+            if from < to {
+                let slice = &output_buf[(from - chunk_start) as usize..(to - chunk_start) as usize];
+                writer.write_all(slice).map_err(|_| ZipError::IoError)?;
+                written += slice.len();
+            }
+            if produced >= next_sync {
+                next_sync = (produced / SYNC_POINT_INTERVAL + 1) * SYNC_POINT_INTERVAL;
+                let known = self.sync_points.iter().any(|p| {
+                    p.entry_offset == entry.local_header_offset
+                        && p.output / SYNC_POINT_INTERVAL == produced / SYNC_POINT_INTERVAL
+                });
+                let max_sync_points = self
+                    .limits
+                    .map_or(MAX_SYNC_POINTS, |limits| limits.max_sync_points);
+                if !known && max_sync_points > 0 {
+                    if self.sync_points.len() >= max_sync_points {
+                        self.sync_points.remove(0);
+                    }
+                    self.sync_points.push(SyncPoint {
+                        entry_offset: entry.local_header_offset,
+                        input: consumed,
+                        output: produced,
+                        state: state.clone(),
+                    });
+                }
+            }
+
+            match result.status {
+                Ok(MZStatus::StreamEnd) => break,
+                Ok(MZStatus::Ok) => {
+                    if result.bytes_consumed == 0 && result.bytes_written == 0 {
+                        return Err(ZipError::DecompressError);
+                    }
+                }
+                Ok(MZStatus::NeedDict) | Err(_) => return Err(ZipError::DecompressError),
+            }
+        }
+        self.charge_decompressed(produced - resumed_at);
+        Ok(written)
+    }
+
+    /// Drop the inflate checkpoints recorded by
+    /// [`read_file_range_to_writer`](Self::read_file_range_to_writer).
+    pub fn clear_sync_points(&mut self) {
+        self.sync_points.clear();
+    }
+
+    /// Read a file by its local header offset (avoids borrow issues)
+    /// This is useful when you need to read a file after getting its metadata
+    pub fn read_file_at_offset(
+        &mut self,
+        local_header_offset: u64,
+        buf: &mut [u8],
+    ) -> Result<usize, ZipError> {
+        // Find entry by offset
+        let entry = self
+            .entries
+            .iter()
+            .find(|e| e.local_header_offset == local_header_offset)
+            .ok_or(ZipError::FileNotFound)?;
+
+        // Create a temporary entry clone to avoid borrow issues
+        let entry_clone = CdEntry {
+            method: entry.method,
+            compressed_size: entry.compressed_size,
+            uncompressed_size: entry.uncompressed_size,
+            local_header_offset: entry.local_header_offset,
+            crc32: entry.crc32,
+            filename: entry.filename.clone(),
+        };
+
+        self.read_file(&entry_clone, buf)
+    }
+
+    /// Calculate the offset to the actual file data (past local header)
+    fn calc_data_offset(&mut self, entry: &CdEntry) -> Result<u64, ZipError> {
+        let offset = entry.local_header_offset;
+
+        // Read local file header (30 bytes fixed + variable filename/extra)
+        let mut header = [0u8; 30];
+        self.file.read_exact_at(offset, &mut header)?;
+        local_data_offset(offset, &header)
+    }
+
+    /// Validate that the archive contains a valid EPUB mimetype file
+    ///
+    /// Checks that a file named "mimetype" exists and its content is exactly
+    /// `application/epub+zip`, as required by the EPUB specification.
+    pub fn validate_mimetype(&mut self) -> Result<(), ZipError> {
+        let entry = mimetype_entry(&self.entries, self.tolerant_names)?.clone();
+        let size = mimetype_size(&entry, self.limits)?;
+        let mut buf = alloc::vec![0u8; size];
+        let bytes_read = self.read_file(&entry, &mut buf)?;
+        check_mimetype_content(&buf[..bytes_read])
+    }
+
+    /// Size of the central directory in bytes.
+    pub fn central_directory_size(&self) -> u64 {
+        self.cd_size
+    }
+
+    /// Check if this archive is a valid EPUB file
+    ///
+    /// Convenience wrapper around `validate_mimetype()` that returns a boolean.
+    pub fn is_valid_epub(&mut self) -> bool {
+        self.validate_mimetype().is_ok()
+    }
+
+    /// Get number of entries in central directory
+    pub fn num_entries(&self) -> usize {
+        self.num_entries.min(self.entries.len())
+    }
+
+    /// Iterate over all entries
+    pub fn entries(&self) -> impl Iterator<Item = &CdEntry> {
+        self.entries.iter()
+    }
+
+    /// Get entry by index
+    pub fn get_entry_by_index(&self, index: usize) -> Option<&CdEntry> {
+        self.entries.get(index)
+    }
+
+    /// Bytes decompressed by successful reads so far, as charged against
+    /// [`ZipLimits::max_total_decompressed`].
+    ///
+    /// Includes reads by other readers sharing the total through
+    /// [`share_decompressed_total`](Self::share_decompressed_total).
+    pub fn decompressed_total(&self) -> u64 {
+        #[cfg(target_has_atomic = "64")]
+        if let Some(shared) = &self.shared_total {
+            return shared.get();
+        }
+        self.decompressed_total
+    }
+
+    /// Charge reads against `total` instead of this reader's own count, so
+    /// several readers of one archive draw from a single
+    /// [`ZipLimits::max_total_decompressed`] budget.
+    ///
+    /// `total` keeps its current value; bytes this reader decompressed
+    /// before the call are not added. Each read is capped at the budget left
+    /// when it starts, so concurrent reads can together overrun it by at
+    /// most one entry each.
+    #[cfg(target_has_atomic = "64")]
+    pub fn share_decompressed_total(&mut self, total: SharedDecompressedTotal) {
+        self.shared_total = Some(total);
+    }
+
+    fn charge_decompressed(&mut self, bytes: u64) {
+        #[cfg(target_has_atomic = "64")]
+        if let Some(shared) = &self.shared_total {
+            shared.charge(bytes);
+            return;
+        }
+        self.decompressed_total = self.decompressed_total.saturating_add(bytes);
+    }
+
+    /// Get the active limits used by this ZIP reader.
+    pub fn limits(&self) -> Option<ZipLimits> {
+        self.limits
+    }
+}
+
+/// Central directory restored from an exported snapshot, before the
+/// freshness checks against the archive.
+struct DirectorySnapshot {
+    archive_size: u64,
+    mtime: u64,
+    eocd_pos: u64,
+    eocd_crc: u32,
+    num_entries: u64,
+    cd_size: u64,
+    entries: HeaplessVec<CdEntry, MAX_CD_ENTRIES>,
+}
+
+/// Bounds-checked little-endian reader over snapshot bytes.
+struct SnapshotReader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> SnapshotReader<'a> {
+    fn take(&mut self, len: usize) -> Option<&'a [u8]> {
+        let end = self.pos.checked_add(len)?;
+        let slice = self.bytes.get(self.pos..end)?;
+        self.pos = end;
+        Some(slice)
+    }
+
+    fn u16(&mut self) -> Option<u16> {
+        self.take(2).map(|b| read_u16_le(b, 0))
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        self.take(4).map(|b| read_u32_le(b, 0))
+    }
+
+    fn u64(&mut self) -> Option<u64> {
+        self.take(8).map(|b| read_u64_le(b, 0))
+    }
+}
+
+/// Decode and structurally validate a central directory snapshot.
+///
+/// Returns `None` for anything that is not an intact snapshot of this
+/// version, so callers can fall back to scanning the archive.
+fn decode_directory_snapshot(bytes: &[u8]) -> Option<DirectorySnapshot> {
+    let body_len = bytes.len().checked_sub(4)?;
+    if body_len < SNAPSHOT_HEADER_LEN
+        || crc32fast::hash(&bytes[..body_len]) != read_u32_le(bytes, body_len)
+    {
+        return None;
+    }
+    let mut reader = SnapshotReader {
+        bytes: &bytes[..body_len],
+        pos: 0,
+    };
+    if reader.take(4)? != SNAPSHOT_MAGIC || reader.take(4)?[0] != SNAPSHOT_VERSION {
+        return None;
+    }
+    let archive_size = reader.u64()?;
+    let mtime = reader.u64()?;
+    let eocd_pos = reader.u64()?;
+    let eocd_crc = reader.u32()?;
+    let num_entries = reader.u64()?;
+    let cd_size = reader.u64()?;
+    let stored = reader.u32()? as usize;
+    if eocd_pos.checked_add(EOCD_MIN_SIZE as u64)? > archive_size
+        || cd_size > eocd_pos
+        || stored > MAX_CD_ENTRIES
+        || stored as u64 > num_entries
+    {
+        return None;
+    }
+
+    let mut entries: HeaplessVec<CdEntry, MAX_CD_ENTRIES> = HeaplessVec::new();
+    for _ in 0..stored {
+        let mut entry = CdEntry::new();
+        entry.method = reader.u16()?;
+        entry.crc32 = reader.u32()?;
+        entry.compressed_size = reader.u64()?;
+        entry.uncompressed_size = reader.u64()?;
+        entry.local_header_offset = reader.u64()?;
+        let name_len = reader.u16()? as usize;
+        if name_len > MAX_FILENAME_LEN || entry.local_header_offset >= eocd_pos {
+            return None;
+        }
+        entry.filename = core::str::from_utf8(reader.take(name_len)?)
+            .ok()?
+            .to_string();
+        entries.push(entry).ok()?;
+    }
+    if reader.pos != body_len {
+        return None;
+    }
+
+    Some(DirectorySnapshot {
+        archive_size,
+        mtime,
+        eocd_pos,
+        eocd_crc,
+        num_entries,
+        cd_size,
+        entries,
+    })
+}
+
+/// CRC-32 of the EOCD record and trailing comment at `eocd_pos`.
+fn eocd_crc<S: RandomAccess>(
+    storage: &mut S,
+    eocd_pos: u64,
+    archive_size: u64,
+) -> Result<u32, ZipError> {
+    let len = archive_size
+        .checked_sub(eocd_pos)
+        .filter(|len| (EOCD_MIN_SIZE as u64..=MAX_EOCD_SCAN as u64).contains(len))
+        .ok_or(ZipError::InvalidFormat)?;
+    let mut tail = alloc::vec![0u8; len as usize];
+    storage.read_exact_at(eocd_pos, &mut tail)?;
+    Ok(crc32fast::hash(&tail))
+}
+
+/// Parse central directory entries starting at `start` in `storage`.
+///
+/// Shared by the blocking and async readers: the blocking reader walks the
+/// archive in place, the async reader parses a buffered copy of the directory.
+fn parse_central_directory<S: RandomAccess>(
+    storage: &mut S,
+    start: u64,
+    eocd: &EocdInfo,
+    strict: bool,
+) -> Result<HeaplessVec<CdEntry, MAX_CD_ENTRIES>, ZipError> {
+    let mut entries: HeaplessVec<CdEntry, MAX_CD_ENTRIES> = HeaplessVec::new();
+    let mut cursor = StorageCursor::new(storage, start);
+    let cd_end = start
+        .checked_add(eocd.cd_size)
+        .ok_or(ZipError::InvalidFormat)?;
+
+    let entries_to_scan = core::cmp::min(eocd.num_entries, MAX_CD_ENTRIES as u64);
+    for _ in 0..entries_to_scan {
+        if cursor.pos >= cd_end {
+            if strict {
+                return Err(ZipError::InvalidFormat);
+            }
+            break;
+        }
+        if let Some(entry) = read_cd_entry(&mut cursor)? {
+            entries.push(entry).map_err(|_| ZipError::CentralDirFull)?;
+        } else if strict {
+            return Err(ZipError::InvalidFormat);
+        } else {
+            break;
+        }
+    }
+
+    if eocd.num_entries > MAX_CD_ENTRIES as u64 {
+        log::warn!(
+            "[ZIP] Archive has {} entries but only {} were loaded (max: {})",
+            eocd.num_entries,
+            entries.len(),
+            MAX_CD_ENTRIES
+        );
+    }
+
+    log::debug!(
+        "[ZIP] Parsed {} central directory entries (offset {})",
+        entries.len(),
+        eocd.cd_offset
+    );
+
+    Ok(entries)
+}
+
+/// Read a central directory entry at the cursor position
+fn read_cd_entry<S: RandomAccess>(
+    file: &mut StorageCursor<'_, S>,
+) -> Result<Option<CdEntry>, ZipError> {
+    let mut sig_buf = [0u8; 4];
+    if file.read_exact(&mut sig_buf).is_err() {
+        return Ok(None);
+    }
+    let sig = u32::from_le_bytes(sig_buf);
+
+    if sig != SIG_CD_ENTRY {
+        return Ok(None); // End of central directory
+    }
+
+    // Read fixed portion of central directory entry (42 bytes = offsets 4-45)
+    // This includes everything up to and including the local header offset
+    let mut buf = [0u8; 42];
+    file.read_exact(&mut buf)?;
+
+    let mut entry = CdEntry::new();
+
+    // Parse central directory entry fields
+    // buf contains bytes 4-49 of the CD entry (after the 4-byte signature)
+    // buf[N] corresponds to CD entry offset (N + 4)
+    entry.method = u16::from_le_bytes([buf[6], buf[7]]); // CD offset 10
+    entry.crc32 = u32::from_le_bytes([buf[12], buf[13], buf[14], buf[15]]); // CD offset 16
+    let compressed_size_32 = u32::from_le_bytes([buf[16], buf[17], buf[18], buf[19]]); // CD offset 20
+    let uncompressed_size_32 = u32::from_le_bytes([buf[20], buf[21], buf[22], buf[23]]); // CD offset 24
+    let name_len = u16::from_le_bytes([buf[24], buf[25]]) as usize; // CD offset 28
+    let extra_len = u16::from_le_bytes([buf[26], buf[27]]) as usize; // CD offset 30
+    let comment_len = u16::from_le_bytes([buf[28], buf[29]]) as usize; // CD offset 32
+    let local_header_offset_32 = u32::from_le_bytes([buf[38], buf[39], buf[40], buf[41]]); // CD offset 42
+    entry.compressed_size = compressed_size_32 as u64;
+    entry.uncompressed_size = uncompressed_size_32 as u64;
+    entry.local_header_offset = local_header_offset_32 as u64;
+
+    // Read filename
+    if name_len > 0 && name_len <= MAX_FILENAME_LEN {
+        let mut name_buf = alloc::vec![0u8; name_len];
+        file.read_exact(&mut name_buf)?;
+        entry.filename = String::from_utf8_lossy(&name_buf).to_string();
+    } else if name_len > MAX_FILENAME_LEN {
+        // Skip over filename bytes we can't store
+        file.skip(name_len);
+    }
+
+    let needs_zip64_uncompressed = uncompressed_size_32 == u32::MAX;
+    let needs_zip64_compressed = compressed_size_32 == u32::MAX;
+    let needs_zip64_offset = local_header_offset_32 == u32::MAX;
+    let mut got_zip64_uncompressed = false;
+    let mut got_zip64_compressed = false;
+    let mut got_zip64_offset = false;
+
+    // Parse ZIP extra fields, specifically ZIP64 extended information (0x0001).
+    let mut extra_remaining = extra_len;
+    while extra_remaining >= 4 {
+        let mut hdr = [0u8; 4];
+        file.read_exact(&mut hdr)?;
+        let header_id = u16::from_le_bytes([hdr[0], hdr[1]]);
+        let field_size = u16::from_le_bytes([hdr[2], hdr[3]]) as usize;
+        extra_remaining -= 4;
+
+        if field_size > extra_remaining {
+            return Err(ZipError::InvalidFormat);
+        }
+
+        if header_id == 0x0001 {
+            let mut field_remaining = field_size;
+            if needs_zip64_uncompressed {
+                if field_remaining < 8 {
+                    return Err(ZipError::InvalidFormat);
+                }
+                let mut val = [0u8; 8];
+                file.read_exact(&mut val)?;
+                entry.uncompressed_size = u64::from_le_bytes(val);
+                got_zip64_uncompressed = true;
+                field_remaining -= 8;
+            }
+            if needs_zip64_compressed {
+                if field_remaining < 8 {
+                    return Err(ZipError::InvalidFormat);
+                }
+                let mut val = [0u8; 8];
+                file.read_exact(&mut val)?;
+                entry.compressed_size = u64::from_le_bytes(val);
+                got_zip64_compressed = true;
+                field_remaining -= 8;
+            }
+            if needs_zip64_offset {
+                if field_remaining < 8 {
+                    return Err(ZipError::InvalidFormat);
+                }
+                let mut val = [0u8; 8];
+                file.read_exact(&mut val)?;
+                entry.local_header_offset = u64::from_le_bytes(val);
+                got_zip64_offset = true;
+                field_remaining -= 8;
+            }
+            if field_remaining > 0 {
+                file.skip(field_remaining);
+            }
+        } else if field_size > 0 {
+            file.skip(field_size);
+        }
+        extra_remaining -= field_size;
+    }
+    if extra_remaining > 0 {
+        file.skip(extra_remaining);
+    }
+
+    if (needs_zip64_uncompressed && !got_zip64_uncompressed)
+        || (needs_zip64_compressed && !got_zip64_compressed)
+        || (needs_zip64_offset && !got_zip64_offset)
+    {
+        return Err(ZipError::InvalidFormat);
+    }
+
+    if comment_len > 0 {
+        file.skip(comment_len);
+    }
+
+    Ok(Some(entry))
+}
+
+/// Look up an entry by filename (case-insensitive, optionally tolerant).
+fn find_entry<'a>(entries: &'a [CdEntry], name: &str, tolerant: bool) -> Option<&'a CdEntry> {
+    entries
+        .iter()
+        .find(|e| {
+            e.filename == name
+                || e.filename.eq_ignore_ascii_case(name)
+                || (name.starts_with('/') && e.filename.eq_ignore_ascii_case(&name[1..]))
+                || (e.filename.starts_with('/') && e.filename[1..].eq_ignore_ascii_case(name))
+        })
+        .or_else(|| {
+            if !tolerant {
+                return None;
+            }
+            let name = name.trim_start_matches('/');
+            entries
+                .iter()
+                .find(|e| entry_names_match_tolerant(e.filename.trim_start_matches('/'), name))
+        })
+}
+
+/// Allocate the tail window scanned for the EOCD record.
+fn eocd_scan_buffer(file_size: u64, max_eocd_scan: usize) -> Result<alloc::vec::Vec<u8>, ZipError> {
+    if file_size < EOCD_MIN_SIZE as u64 {
+        return Err(ZipError::InvalidFormat);
+    }
+
+    // Scan last (EOCD + max comment) bytes for EOCD signature.
+    let scan_range = file_size.min(max_eocd_scan as u64) as usize;
+    if scan_range < EOCD_MIN_SIZE {
+        return Err(ZipError::InvalidFormat);
+    }
+    Ok(alloc::vec![0u8; scan_range])
+}
+
+/// EOCD record found by the tail scan, before ZIP64 resolution.
+#[derive(Clone, Copy, Debug)]
+struct EocdRecord {
+    eocd_pos: u64,
+    num_entries: u16,
+    cd_size_32: u32,
+    cd_offset_32: u64,
+}
+
+impl EocdRecord {
+    /// Scan backwards through the archive tail for an EOCD ending at `file_size`.
+    fn locate(buffer: &[u8], scan_base: u64, file_size: u64) -> Result<Self, ZipError> {
+        for i in (0..=buffer.len().saturating_sub(EOCD_MIN_SIZE)).rev() {
+            if read_u32_le(buffer, i) != SIG_EOCD {
+                continue;
+            }
+            let comment_len = read_u16_le(buffer, i + 20) as u64;
+            let eocd_pos = scan_base + i as u64;
+            let eocd_end = eocd_pos + EOCD_MIN_SIZE as u64 + comment_len;
+            if eocd_end != file_size {
+                continue;
+            }
+            return Ok(Self {
+                eocd_pos,
+                num_entries: read_u16_le(buffer, i + 8),
+                cd_size_32: read_u32_le(buffer, i + 12),
+                cd_offset_32: read_u32_le(buffer, i + 16) as u64,
+            });
+        }
+        Err(ZipError::InvalidFormat)
+    }
+
+    /// Offset of the ZIP64 locator that would precede this record, if any.
+    fn zip64_locator_offset(&self) -> Option<u64> {
+        self.eocd_pos.checked_sub(20)
+    }
+
+    /// Offset of the ZIP64 EOCD record this archive requires, if any.
+    fn zip64_eocd_offset(&self, locator: Option<Zip64Locator>) -> Result<Option<u64>, ZipError> {
+        let uses_zip64_sentinel = self.num_entries == u16::MAX
+            || self.cd_size_32 == u32::MAX
+            || self.cd_offset_32 == u32::MAX as u64;
+        if !uses_zip64_sentinel && locator.is_none() {
+            return Ok(None);
+        }
+        let locator = locator.ok_or(ZipError::InvalidFormat)?;
+        if locator.disk != 0 || locator.total_disks != 1 {
+            return Err(ZipError::UnsupportedZip64);
+        }
+        Ok(Some(locator.eocd_offset))
+    }
+
+    /// Combine with the optional ZIP64 record into validated directory info.
+    fn resolve(&self, zip64: Option<Zip64EocdInfo>, file_size: u64) -> Result<EocdInfo, ZipError> {
+        if let Some(zip64) = zip64 {
+            if zip64.disk_number != 0 || zip64.disk_with_cd_start != 0 {
+                return Err(ZipError::UnsupportedZip64);
+            }
+            let cd_end = zip64
+                .cd_offset
+                .checked_add(zip64.cd_size)
+                .ok_or(ZipError::InvalidFormat)?;
+            if cd_end > self.eocd_pos || cd_end > file_size {
+                return Err(ZipError::InvalidFormat);
+            }
+            return Ok(EocdInfo {
+                eocd_pos: self.eocd_pos,
+                cd_offset: zip64.cd_offset,
+                cd_size: zip64.cd_size,
+                num_entries: zip64.num_entries,
+            });
+        }
+
+        let cd_end = self
+            .cd_offset_32
+            .checked_add(self.cd_size_32 as u64)
+            .ok_or(ZipError::InvalidFormat)?;
+        if cd_end > self.eocd_pos || cd_end > file_size {
+            return Err(ZipError::InvalidFormat);
+        }
+
+        Ok(EocdInfo {
+            eocd_pos: self.eocd_pos,
+            cd_offset: self.cd_offset_32,
+            cd_size: self.cd_size_32 as u64,
+            num_entries: self.num_entries as u64,
+        })
+    }
+}
+
+/// ZIP64 end of central directory locator fields.
+#[derive(Clone, Copy, Debug)]
+struct Zip64Locator {
+    disk: u32,
+    eocd_offset: u64,
+    total_disks: u32,
+}
+
+fn parse_zip64_locator(locator: &[u8; 20]) -> Option<Zip64Locator> {
+    if read_u32_le(locator, 0) != SIG_ZIP64_EOCD_LOCATOR {
+        return None;
+    }
+    Some(Zip64Locator {
+        disk: read_u32_le(locator, 4),
+        eocd_offset: read_u64_le(locator, 8),
+        total_disks: read_u32_le(locator, 16),
+    })
+}
+
+fn parse_zip64_eocd(fixed: &[u8; 56]) -> Result<Zip64EocdInfo, ZipError> {
+    if read_u32_le(fixed, 0) != SIG_ZIP64_EOCD {
+        return Err(ZipError::InvalidFormat);
+    }
 
-                while remaining > 0 {
-                    let take = core::cmp::min(remaining, input_buf.len());
-                    self.file
-                        .read_exact(&mut input_buf[..take])
-                        .map_err(|_| ZipError::IoError)?;
-                    writer
-                        .write_all(&input_buf[..take])
-                        .map_err(|_| ZipError::IoError)?;
-                    hasher.update(&input_buf[..take]);
-                    written += take;
-                    remaining -= take;
-                }
+    let record_size = read_u64_le(fixed, 4);
+    if record_size < 44 {
+        return Err(ZipError::InvalidFormat);
+    }
 
-                if entry.crc32 != 0 && hasher.finalize() != entry.crc32 {
-                    return Err(ZipError::CrcMismatch);
-                }
-                Ok(written)
-            }
-            METHOD_DEFLATED => {
-                let mut state = alloc::boxed::Box::new(
-                    miniz_oxide::inflate::stream::InflateState::new(DataFormat::Raw),
-                );
-                let mut compressed_remaining =
-                    usize::try_from(entry.compressed_size).map_err(|_| ZipError::FileTooLarge)?;
-                let mut pending = &[][..];
-                let mut written = 0usize;
-                let mut hasher = crc32fast::Hasher::new();
+    Ok(Zip64EocdInfo {
+        disk_number: read_u32_le(fixed, 16),
+        disk_with_cd_start: read_u32_le(fixed, 20),
+        num_entries: read_u64_le(fixed, 32),
+        cd_size: read_u64_le(fixed, 40),
+        cd_offset: read_u64_le(fixed, 48),
+    })
+}
 
-                loop {
-                    if pending.is_empty() && compressed_remaining > 0 {
-                        let take = core::cmp::min(compressed_remaining, input_buf.len());
-                        self.file
-                            .read_exact(&mut input_buf[..take])
-                            .map_err(|_| ZipError::IoError)?;
-                        pending = &input_buf[..take];
-                        compressed_remaining -= take;
-                    }
+/// Offset of an entry's data given its 30-byte local file header.
+fn local_data_offset(offset: u64, header: &[u8; 30]) -> Result<u64, ZipError> {
+    // Verify signature
+    if read_u32_le(header, 0) != SIG_LOCAL_FILE_HEADER {
+        return Err(ZipError::InvalidFormat);
+    }
 
-                    let result = miniz_oxide::inflate::stream::inflate(
-                        &mut state,
-                        pending,
-                        output_buf,
-                        MZFlush::None,
-                    );
-                    let consumed = result.bytes_consumed;
-                    let produced = result.bytes_written;
-                    pending = &pending[consumed..];
+    // Get filename and extra field lengths
+    let name_len = read_u16_le(header, 26) as u64;
+    let extra_len = read_u16_le(header, 28) as u64;
 
-                    if produced > 0 {
-                        writer
-                            .write_all(&output_buf[..produced])
-                            .map_err(|_| ZipError::IoError)?;
-                        hasher.update(&output_buf[..produced]);
-                        written += produced;
-                    }
+    // Data starts after local header + filename + extra field
+    Ok(offset + 30 + name_len + extra_len)
+}
 
-                    match result.status {
-                        Ok(MZStatus::StreamEnd) => {
-                            if compressed_remaining != 0 || !pending.is_empty() {
-                                return Err(ZipError::DecompressError);
-                            }
-                            break;
-                        }
-                        Ok(MZStatus::Ok) => {
-                            if consumed == 0 && produced == 0 {
-                                return Err(ZipError::DecompressError);
-                            }
-                        }
-                        Ok(MZStatus::NeedDict) => return Err(ZipError::DecompressError),
-                        Err(_) => return Err(ZipError::DecompressError),
-                    }
-                }
+/// Read u16 from buffer at offset (little-endian)
+fn read_u16_le(buf: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes([buf[offset], buf[offset + 1]])
+}
 
-                if entry.crc32 != 0 && hasher.finalize() != entry.crc32 {
-                    return Err(ZipError::CrcMismatch);
-                }
-                Ok(written)
-            }
-            _ => Err(ZipError::UnsupportedCompression),
+/// Read u32 from buffer at offset (little-endian)
+fn read_u32_le(buf: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes([
+        buf[offset],
+        buf[offset + 1],
+        buf[offset + 2],
+        buf[offset + 3],
+    ])
+}
+
+/// Read u64 from buffer at offset (little-endian)
+fn read_u64_le(buf: &[u8], offset: usize) -> u64 {
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(&buf[offset..offset + 8]);
+    u64::from_le_bytes(bytes)
+}
+
+/// Reject entries larger than the configured per-file read limit.
+fn check_entry_limits(entry: &CdEntry, limits: Option<ZipLimits>) -> Result<(), ZipError> {
+    if let Some(limits) = limits {
+        if entry.uncompressed_size > limits.max_file_read_size as u64 {
+            return Err(ZipError::FileTooLarge);
+        }
+        if entry.compressed_size > limits.max_file_read_size as u64 {
+            return Err(ZipError::FileTooLarge);
         }
     }
+    Ok(())
+}
 
-    /// Read a file by its local header offset (avoids borrow issues)
-    /// This is useful when you need to read a file after getting its metadata
-    pub fn read_file_at_offset(
-        &mut self,
-        local_header_offset: u64,
-        buf: &mut [u8],
-    ) -> Result<usize, ZipError> {
-        // Find entry by offset
-        let entry = self
-            .entries
-            .iter()
-            .find(|e| e.local_header_offset == local_header_offset)
-            .ok_or(ZipError::FileNotFound)?;
+/// Output allowance for one entry read under [`ZipLimits`].
+#[derive(Clone, Copy, Debug)]
+struct OutputCap {
+    /// Most bytes the entry may inflate to under the compression ratio cap.
+    ratio_limit: u64,
+    /// What is left of the reader's total decompression budget.
+    budget_left: u64,
+}
 
-        // Create a temporary entry clone to avoid borrow issues
-        let entry_clone = CdEntry {
-            method: entry.method,
-            compressed_size: entry.compressed_size,
-            uncompressed_size: entry.uncompressed_size,
-            local_header_offset: entry.local_header_offset,
-            crc32: entry.crc32,
-            filename: entry.filename.clone(),
-        };
+impl OutputCap {
+    const UNLIMITED: Self = Self {
+        ratio_limit: u64::MAX,
+        budget_left: u64::MAX,
+    };
 
-        self.read_file(&entry_clone, buf)
+    /// Allowance for `entry` after `decompressed` bytes were already read,
+    /// rejecting entries whose declared size breaks it up front.
+    fn for_entry(
+        entry: &CdEntry,
+        limits: Option<ZipLimits>,
+        decompressed: u64,
+    ) -> Result<Self, ZipError> {
+        let Some(limits) = limits else {
+            return Ok(Self::UNLIMITED);
+        };
+        let ratio_limit = match limits.max_compression_ratio {
+            0 => u64::MAX,
+            ratio => entry
+                .compressed_size
+                .saturating_mul(u64::from(ratio))
+                .max(RATIO_GRACE_BYTES),
+        };
+        let cap = Self {
+            ratio_limit,
+            budget_left: limits.max_total_decompressed.saturating_sub(decompressed),
+        };
+        cap.check(entry.uncompressed_size)?;
+        Ok(cap)
     }
 
-    /// Calculate the offset to the actual file data (past local header)
-    fn calc_data_offset(&mut self, entry: &CdEntry) -> Result<u64, ZipError> {
-        let offset = entry.local_header_offset;
-        self.file
-            .seek(SeekFrom::Start(offset))
-            .map_err(|_| ZipError::IoError)?;
+    /// Fail once an entry's output reaches `written` bytes past the cap.
+    fn check(&self, written: u64) -> Result<(), ZipError> {
+        if written > self.ratio_limit {
+            Err(ZipError::SuspiciousCompressionRatio)
+        } else if written > self.budget_left {
+            Err(ZipError::DecompressionBudgetExceeded)
+        } else {
+            Ok(())
+        }
+    }
+}
 
-        // Read local file header (30 bytes fixed + variable filename/extra)
-        let mut header = [0u8; 30];
-        self.file
-            .read_exact(&mut header)
-            .map_err(|_| ZipError::IoError)?;
+fn mimetype_entry(entries: &[CdEntry], tolerant: bool) -> Result<&CdEntry, ZipError> {
+    find_entry(entries, "mimetype", tolerant)
+        .ok_or_else(|| ZipError::InvalidMimetype("mimetype file not found in archive".to_string()))
+}
 
-        // Verify signature
-        let sig = u32::from_le_bytes([header[0], header[1], header[2], header[3]]);
-        if sig != SIG_LOCAL_FILE_HEADER {
-            return Err(ZipError::InvalidFormat);
+fn mimetype_size(entry: &CdEntry, limits: Option<ZipLimits>) -> Result<usize, ZipError> {
+    if let Some(limits) = limits {
+        if entry.uncompressed_size > limits.max_mimetype_size as u64 {
+            return Err(ZipError::InvalidMimetype(
+                "mimetype file too large".to_string(),
+            ));
         }
+    }
 
-        // Get filename and extra field lengths
-        let name_len = u16::from_le_bytes([header[26], header[27]]) as u64;
-        let extra_len = u16::from_le_bytes([header[28], header[29]]) as u64;
+    usize::try_from(entry.uncompressed_size)
+        .map_err(|_| ZipError::InvalidMimetype("mimetype file too large".to_string()))
+}
 
-        // Data starts after local header + filename + extra field
-        let data_offset = offset + 30 + name_len + extra_len;
+fn check_mimetype_content(bytes: &[u8]) -> Result<(), ZipError> {
+    let content = core::str::from_utf8(bytes)
+        .map_err(|_| ZipError::InvalidMimetype("mimetype file is not valid UTF-8".to_string()))?;
 
-        Ok(data_offset)
+    if content != "application/epub+zip" {
+        return Err(ZipError::InvalidMimetype(format!(
+            "expected 'application/epub+zip', got '{}'",
+            content
+        )));
     }
 
-    /// Read u16 from buffer at offset (little-endian)
-    fn read_u16_le(buf: &[u8], offset: usize) -> u16 {
-        u16::from_le_bytes([buf[offset], buf[offset + 1]])
+    Ok(())
+}
+
+/// Inflater state captured part-way through a DEFLATE entry so ranged
+/// reads can resume there instead of at the start of the stream.
+struct SyncPoint {
+    /// Local header offset identifying the entry.
+    entry_offset: u64,
+    /// Compressed bytes consumed at the checkpoint.
+    input: u64,
+    /// Decompressed bytes produced at the checkpoint.
+    output: u64,
+    state: alloc::boxed::Box<miniz_oxide::inflate::stream::InflateState>,
+}
+
+/// Incremental decoder for one entry's compressed bytes.
+///
+/// The caller feeds chunks of at most [`EntryDecoder::next_read_len`] bytes,
+/// read from wherever the archive lives, and the decoder streams decompressed
+/// output to a writer while tracking the CRC. This keeps the blocking and
+/// async readers on one decompression path.
+struct EntryDecoder {
+    inflate: Option<alloc::boxed::Box<miniz_oxide::inflate::stream::InflateState>>,
+    cap: OutputCap,
+    hasher: crc32fast::Hasher,
+    expected_crc: u32,
+    compressed_remaining: usize,
+    written: usize,
+    finished: bool,
+}
+
+impl EntryDecoder {
+    fn new(entry: &CdEntry, cap: OutputCap) -> Result<Self, ZipError> {
+        let inflate = match entry.method {
+            METHOD_STORED => None,
+            METHOD_DEFLATED => Some(alloc::boxed::Box::new(
+                miniz_oxide::inflate::stream::InflateState::new(DataFormat::Raw),
+            )),
+            _ => return Err(ZipError::UnsupportedCompression),
+        };
+        Ok(Self {
+            inflate,
+            cap,
+            hasher: crc32fast::Hasher::new(),
+            expected_crc: entry.crc32,
+            compressed_remaining: usize::try_from(entry.compressed_size)
+                .map_err(|_| ZipError::FileTooLarge)?,
+            written: 0,
+            finished: false,
+        })
     }
 
-    /// Read u32 from buffer at offset (little-endian)
-    fn read_u32_le(buf: &[u8], offset: usize) -> u32 {
-        u32::from_le_bytes([
-            buf[offset],
-            buf[offset + 1],
-            buf[offset + 2],
-            buf[offset + 3],
-        ])
+    /// Number of compressed bytes to read next; `0` once input is exhausted.
+    fn next_read_len(&self, capacity: usize) -> usize {
+        core::cmp::min(self.compressed_remaining, capacity)
     }
 
-    /// Validate that the archive contains a valid EPUB mimetype file
-    ///
-    /// Checks that a file named "mimetype" exists and its content is exactly
-    /// `application/epub+zip`, as required by the EPUB specification.
-    pub fn validate_mimetype(&mut self) -> Result<(), ZipError> {
-        let entry = self
-            .get_entry("mimetype")
-            .ok_or_else(|| {
-                ZipError::InvalidMimetype("mimetype file not found in archive".to_string())
-            })?
-            .clone();
-
-        if let Some(limits) = self.limits {
-            if entry.uncompressed_size > limits.max_mimetype_size as u64 {
-                return Err(ZipError::InvalidMimetype(
-                    "mimetype file too large".to_string(),
-                ));
-            }
-        }
+    /// Decode one chunk of compressed input into `writer`.
+    fn decode<W: Write>(
+        &mut self,
+        input: &[u8],
+        output_buf: &mut [u8],
+        writer: &mut W,
+    ) -> Result<(), ZipError> {
+        self.compressed_remaining = self
+            .compressed_remaining
+            .checked_sub(input.len())
+            .ok_or(ZipError::InvalidFormat)?;
 
-        let size = usize::try_from(entry.uncompressed_size)
-            .map_err(|_| ZipError::InvalidMimetype("mimetype file too large".to_string()))?;
-        let mut buf = alloc::vec![0u8; size];
-        let bytes_read = self.read_file(&entry, &mut buf)?;
+        let Some(state) = self.inflate.as_mut() else {
+            self.cap.check((self.written + input.len()) as u64)?;
+            writer.write_all(input).map_err(|_| ZipError::IoError)?;
+            self.hasher.update(input);
+            self.written += input.len();
+            return Ok(());
+        };
 
-        let content = core::str::from_utf8(&buf[..bytes_read]).map_err(|_| {
-            ZipError::InvalidMimetype("mimetype file is not valid UTF-8".to_string())
-        })?;
+        let mut pending = input;
+        // Keep inflating while input remains; once the archive has no more
+        // compressed bytes, drain until the stream reports its end.
+        while !self.finished && (!pending.is_empty() || self.compressed_remaining == 0) {
+            let result =
+                miniz_oxide::inflate::stream::inflate(state, pending, output_buf, MZFlush::None);
+            let consumed = result.bytes_consumed;
+            let produced = result.bytes_written;
+            pending = &pending[consumed..];
+
+            if produced > 0 {
+                self.cap.check((self.written + produced) as u64)?;
+                writer
+                    .write_all(&output_buf[..produced])
+                    .map_err(|_| ZipError::IoError)?;
+                self.hasher.update(&output_buf[..produced]);
+                self.written += produced;
+            }
 
-        if content != "application/epub+zip" {
-            return Err(ZipError::InvalidMimetype(format!(
-                "expected 'application/epub+zip', got '{}'",
-                content
-            )));
+            match result.status {
+                Ok(MZStatus::StreamEnd) => {
+                    if self.compressed_remaining != 0 || !pending.is_empty() {
+                        return Err(ZipError::DecompressError);
+                    }
+                    self.finished = true;
+                }
+                Ok(MZStatus::Ok) => {
+                    if consumed == 0 && produced == 0 {
+                        return Err(ZipError::DecompressError);
+                    }
+                }
+                Ok(MZStatus::NeedDict) => return Err(ZipError::DecompressError),
+                Err(_) => return Err(ZipError::DecompressError),
+            }
         }
-
         Ok(())
     }
 
-    /// Check if this archive is a valid EPUB file
-    ///
-    /// Convenience wrapper around `validate_mimetype()` that returns a boolean.
-    pub fn is_valid_epub(&mut self) -> bool {
-        self.validate_mimetype().is_ok()
+    /// Flush remaining output, verify the CRC, and return the bytes written.
+    fn finish<W: Write>(
+        mut self,
+        output_buf: &mut [u8],
+        writer: &mut W,
+    ) -> Result<usize, ZipError> {
+        if self.inflate.is_some() && !self.finished {
+            self.decode(&[], output_buf, writer)?;
+        }
+        if self.expected_crc != 0 && self.hasher.finalize() != self.expected_crc {
+            return Err(ZipError::CrcMismatch);
+        }
+        Ok(self.written)
     }
+}
 
-    /// Get number of entries in central directory
-    pub fn num_entries(&self) -> usize {
-        self.num_entries.min(self.entries.len())
+/// Decode RFC 3986 percent-escapes (`%20`, `%C3%A9`) in an href path.
+///
+/// Malformed escapes are kept verbatim; if the decoded bytes are not valid
+/// UTF-8 the input is returned unchanged.
+pub(crate) fn percent_decode_path(path: &str) -> String {
+    if !path.contains('%') {
+        return path.to_string();
     }
-
-    /// Iterate over all entries
-    pub fn entries(&self) -> impl Iterator<Item = &CdEntry> {
-        self.entries.iter()
+    let bytes = path.as_bytes();
+    let mut out = alloc::vec::Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            if let (Some(hi), Some(lo)) = (hex_value(bytes[i + 1]), hex_value(bytes[i + 2])) {
+                out.push(hi << 4 | lo);
+                i += 3;
+                continue;
+            }
+        }
+        out.push(bytes[i]);
+        i += 1;
     }
+    String::from_utf8(out).unwrap_or_else(|_| path.to_string())
+}
 
-    /// Get entry by index
-    pub fn get_entry_by_index(&self, index: usize) -> Option<&CdEntry> {
-        self.entries.get(index)
+fn hex_value(byte: u8) -> Option<u8> {
+    match byte {
+        b'0'..=b'9' => Some(byte - b'0'),
+        b'a'..=b'f' => Some(byte - b'a' + 10),
+        b'A'..=b'F' => Some(byte - b'A' + 10),
+        _ => None,
     }
+}
 
-    /// Get the active limits used by this ZIP reader.
-    pub fn limits(&self) -> Option<ZipLimits> {
-        self.limits
-    }
+/// Compare entry names ignoring case and NFC/NFD differences.
+fn entry_names_match_tolerant(a: &str, b: &str) -> bool {
+    fold_entry_name(a).eq(fold_entry_name(b))
+}
+
+/// Canonically decompose and lowercase a name, char by char.
+///
+/// Decomposition covers precomposed Latin letters (U+00C0..U+017F); other
+/// characters are only case folded. `+` folds to a space, since some
+/// authoring tools form-encode spaces in hrefs.
+fn fold_entry_name(name: &str) -> impl Iterator<Item = char> + '_ {
+    name.chars().flat_map(|ch| {
+        let ch = if ch == '+' { ' ' } else { ch };
+        let (base, mark) = match LATIN_DECOMPOSITIONS.binary_search_by(|entry| entry.0.cmp(&ch)) {
+            Ok(idx) => (
+                LATIN_DECOMPOSITIONS[idx].1,
+                Some(LATIN_DECOMPOSITIONS[idx].2),
+            ),
+            Err(_) => (ch, None),
+        };
+        base.to_lowercase().chain(mark)
+    })
 }
 
+/// Canonical decompositions `(composed, base, combining mark)`, sorted by
+/// composed code point.
+const LATIN_DECOMPOSITIONS: &[(char, char, char)] = &[
+    ('\u{00C0}', 'A', '\u{0300}'),
+    ('\u{00C1}', 'A', '\u{0301}'),
+    ('\u{00C2}', 'A', '\u{0302}'),
+    ('\u{00C3}', 'A', '\u{0303}'),
+    ('\u{00C4}', 'A', '\u{0308}'),
+    ('\u{00C5}', 'A', '\u{030A}'),
+    ('\u{00C7}', 'C', '\u{0327}'),
+    ('\u{00C8}', 'E', '\u{0300}'),
+    ('\u{00C9}', 'E', '\u{0301}'),
+    ('\u{00CA}', 'E', '\u{0302}'),
+    ('\u{00CB}', 'E', '\u{0308}'),
+    ('\u{00CC}', 'I', '\u{0300}'),
+    ('\u{00CD}', 'I', '\u{0301}'),
+    ('\u{00CE}', 'I', '\u{0302}'),
+    ('\u{00CF}', 'I', '\u{0308}'),
+    ('\u{00D1}', 'N', '\u{0303}'),
+    ('\u{00D2}', 'O', '\u{0300}'),
+    ('\u{00D3}', 'O', '\u{0301}'),
+    ('\u{00D4}', 'O', '\u{0302}'),
+    ('\u{00D5}', 'O', '\u{0303}'),
+    ('\u{00D6}', 'O', '\u{0308}'),
+    ('\u{00D9}', 'U', '\u{0300}'),
+    ('\u{00DA}', 'U', '\u{0301}'),
+    ('\u{00DB}', 'U', '\u{0302}'),
+    ('\u{00DC}', 'U', '\u{0308}'),
+    ('\u{00DD}', 'Y', '\u{0301}'),
+    ('\u{00E0}', 'a', '\u{0300}'),
+    ('\u{00E1}', 'a', '\u{0301}'),
+    ('\u{00E2}', 'a', '\u{0302}'),
+    ('\u{00E3}', 'a', '\u{0303}'),
+    ('\u{00E4}', 'a', '\u{0308}'),
+    ('\u{00E5}', 'a', '\u{030A}'),
+    ('\u{00E7}', 'c', '\u{0327}'),
+    ('\u{00E8}', 'e', '\u{0300}'),
+    ('\u{00E9}', 'e', '\u{0301}'),
+    ('\u{00EA}', 'e', '\u{0302}'),
+    ('\u{00EB}', 'e', '\u{0308}'),
+    ('\u{00EC}', 'i', '\u{0300}'),
+    ('\u{00ED}', 'i', '\u{0301}'),
+    ('\u{00EE}', 'i', '\u{0302}'),
+    ('\u{00EF}', 'i', '\u{0308}'),
+    ('\u{00F1}', 'n', '\u{0303}'),
+    ('\u{00F2}', 'o', '\u{0300}'),
+    ('\u{00F3}', 'o', '\u{0301}'),
+    ('\u{00F4}', 'o', '\u{0302}'),
+    ('\u{00F5}', 'o', '\u{0303}'),
+    ('\u{00F6}', 'o', '\u{0308}'),
+    ('\u{00F9}', 'u', '\u{0300}'),
+    ('\u{00FA}', 'u', '\u{0301}'),
+    ('\u{00FB}', 'u', '\u{0302}'),
+    ('\u{00FC}', 'u', '\u{0308}'),
+    ('\u{00FD}', 'y', '\u{0301}'),
+    ('\u{00FF}', 'y', '\u{0308}'),
+    ('\u{0100}', 'A', '\u{0304}'),
+    ('\u{0101}', 'a', '\u{0304}'),
+    ('\u{0102}', 'A', '\u{0306}'),
+    ('\u{0103}', 'a', '\u{0306}'),
+    ('\u{0104}', 'A', '\u{0328}'),
+    ('\u{0105}', 'a', '\u{0328}'),
+    ('\u{0106}', 'C', '\u{0301}'),
+    ('\u{0107}', 'c', '\u{0301}'),
+    ('\u{0108}', 'C', '\u{0302}'),
+    ('\u{0109}', 'c', '\u{0302}'),
+    ('\u{010A}', 'C', '\u{0307}'),
+    ('\u{010B}', 'c', '\u{0307}'),
+    ('\u{010C}', 'C', '\u{030C}'),
+    ('\u{010D}', 'c', '\u{030C}'),
+    ('\u{010E}', 'D', '\u{030C}'),
+    ('\u{010F}', 'd', '\u{030C}'),
+    ('\u{0112}', 'E', '\u{0304}'),
+    ('\u{0113}', 'e', '\u{0304}'),
+    ('\u{0114}', 'E', '\u{0306}'),
+    ('\u{0115}', 'e', '\u{0306}'),
+    ('\u{0116}', 'E', '\u{0307}'),
+    ('\u{0117}', 'e', '\u{0307}'),
+    ('\u{0118}', 'E', '\u{0328}'),
+    ('\u{0119}', 'e', '\u{0328}'),
+    ('\u{011A}', 'E', '\u{030C}'),
+    ('\u{011B}', 'e', '\u{030C}'),
+    ('\u{011C}', 'G', '\u{0302}'),
+    ('\u{011D}', 'g', '\u{0302}'),
+    ('\u{011E}', 'G', '\u{0306}'),
+    ('\u{011F}', 'g', '\u{0306}'),
+    ('\u{0120}', 'G', '\u{0307}'),
+    ('\u{0121}', 'g', '\u{0307}'),
+    ('\u{0122}', 'G', '\u{0327}'),
+    ('\u{0123}', 'g', '\u{0327}'),
+    ('\u{0124}', 'H', '\u{0302}'),
+    ('\u{0125}', 'h', '\u{0302}'),
+    ('\u{0128}', 'I', '\u{0303}'),
+    ('\u{0129}', 'i', '\u{0303}'),
+    ('\u{012A}', 'I', '\u{0304}'),
+    ('\u{012B}', 'i', '\u{0304}'),
+    ('\u{012C}', 'I', '\u{0306}'),
+    ('\u{012D}', 'i', '\u{0306}'),
+    ('\u{012E}', 'I', '\u{0328}'),
+    ('\u{012F}', 'i', '\u{0328}'),
+    ('\u{0130}', 'I', '\u{0307}'),
+    ('\u{0134}', 'J', '\u{0302}'),
+    ('\u{0135}', 'j', '\u{0302}'),
+    ('\u{0136}', 'K', '\u{0327}'),
+    ('\u{0137}', 'k', '\u{0327}'),
+    ('\u{0139}', 'L', '\u{0301}'),
+    ('\u{013A}', 'l', '\u{0301}'),
+    ('\u{013B}', 'L', '\u{0327}'),
+    ('\u{013C}', 'l', '\u{0327}'),
+    ('\u{013D}', 'L', '\u{030C}'),
+    ('\u{013E}', 'l', '\u{030C}'),
+    ('\u{0143}', 'N', '\u{0301}'),
+    ('\u{0144}', 'n', '\u{0301}'),
+    ('\u{0145}', 'N', '\u{0327}'),
+    ('\u{0146}', 'n', '\u{0327}'),
+    ('\u{0147}', 'N', '\u{030C}'),
+    ('\u{0148}', 'n', '\u{030C}'),
+    ('\u{014C}', 'O', '\u{0304}'),
+    ('\u{014D}', 'o', '\u{0304}'),
+    ('\u{014E}', 'O', '\u{0306}'),
+    ('\u{014F}', 'o', '\u{0306}'),
+    ('\u{0150}', 'O', '\u{030B}'),
+    ('\u{0151}', 'o', '\u{030B}'),
+    ('\u{0154}', 'R', '\u{0301}'),
+    ('\u{0155}', 'r', '\u{0301}'),
+    ('\u{0156}', 'R', '\u{0327}'),
+    ('\u{0157}', 'r', '\u{0327}'),
+    ('\u{0158}', 'R', '\u{030C}'),
+    ('\u{0159}', 'r', '\u{030C}'),
+    ('\u{015A}', 'S', '\u{0301}'),
+    ('\u{015B}', 's', '\u{0301}'),
+    ('\u{015C}', 'S', '\u{0302}'),
+    ('\u{015D}', 's', '\u{0302}'),
+    ('\u{015E}', 'S', '\u{0327}'),
+    ('\u{015F}', 's', '\u{0327}'),
+    ('\u{0160}', 'S', '\u{030C}'),
+    ('\u{0161}', 's', '\u{030C}'),
+    ('\u{0162}', 'T', '\u{0327}'),
+    ('\u{0163}', 't', '\u{0327}'),
+    ('\u{0164}', 'T', '\u{030C}'),
+    ('\u{0165}', 't', '\u{030C}'),
+    ('\u{0168}', 'U', '\u{0303}'),
+    ('\u{0169}', 'u', '\u{0303}'),
+    ('\u{016A}', 'U', '\u{0304}'),
+    ('\u{016B}', 'u', '\u{0304}'),
+    ('\u{016C}', 'U', '\u{0306}'),
+    ('\u{016D}', 'u', '\u{0306}'),
+    ('\u{016E}', 'U', '\u{030A}'),
+    ('\u{016F}', 'u', '\u{030A}'),
+    ('\u{0170}', 'U', '\u{030B}'),
+    ('\u{0171}', 'u', '\u{030B}'),
+    ('\u{0172}', 'U', '\u{0328}'),
+    ('\u{0173}', 'u', '\u{0328}'),
+    ('\u{0174}', 'W', '\u{0302}'),
+    ('\u{0175}', 'w', '\u{0302}'),
+    ('\u{0176}', 'Y', '\u{0302}'),
+    ('\u{0177}', 'y', '\u{0302}'),
+    ('\u{0178}', 'Y', '\u{0308}'),
+    ('\u{0179}', 'Z', '\u{0301}'),
+    ('\u{017A}', 'z', '\u{0301}'),
+    ('\u{017B}', 'Z', '\u{0307}'),
+    ('\u{017C}', 'z', '\u{0307}'),
+    ('\u{017D}', 'Z', '\u{030C}'),
+    ('\u{017E}', 'z', '\u{030C}'),
+];
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -986,10 +1941,17 @@ mod tests {
     ///
     /// The archive contains one file with the given name and content,
     /// stored without compression (method 0).
-    fn build_single_file_zip(filename: &str, content: &[u8]) -> Vec<u8> {
+    pub(super) fn build_single_file_zip(filename: &str, content: &[u8]) -> Vec<u8> {
+        build_single_entry_zip(filename, content, METHOD_STORED, content)
+    }
+
+    /// Like [`build_single_file_zip`], with `data` holding `content`
+    /// compressed by `method`.
+    fn build_single_entry_zip(filename: &str, content: &[u8], method: u16, data: &[u8]) -> Vec<u8> {
         let name_bytes = filename.as_bytes();
         let name_len = name_bytes.len() as u16;
         let content_len = content.len() as u32;
+        let data_len = data.len() as u32;
         let crc = crc32fast::hash(content);
 
         let mut zip = Vec::with_capacity(0);
@@ -999,16 +1961,16 @@ mod tests {
         zip.extend_from_slice(&SIG_LOCAL_FILE_HEADER.to_le_bytes()); // signature
         zip.extend_from_slice(&20u16.to_le_bytes()); // version needed
         zip.extend_from_slice(&0u16.to_le_bytes()); // flags
-        zip.extend_from_slice(&METHOD_STORED.to_le_bytes()); // compression
+        zip.extend_from_slice(&method.to_le_bytes()); // compression
         zip.extend_from_slice(&0u16.to_le_bytes()); // mod time
         zip.extend_from_slice(&0u16.to_le_bytes()); // mod date
         zip.extend_from_slice(&crc.to_le_bytes()); // CRC32
-        zip.extend_from_slice(&content_len.to_le_bytes()); // compressed size
+        zip.extend_from_slice(&data_len.to_le_bytes()); // compressed size
         zip.extend_from_slice(&content_len.to_le_bytes()); // uncompressed size
         zip.extend_from_slice(&name_len.to_le_bytes()); // filename length
         zip.extend_from_slice(&0u16.to_le_bytes()); // extra field length
         zip.extend_from_slice(name_bytes); // filename
-        zip.extend_from_slice(content); // file data
+        zip.extend_from_slice(data); // file data
 
         // -- Central directory entry --
         let cd_offset = zip.len() as u32;
@@ -1016,11 +1978,11 @@ mod tests {
         zip.extend_from_slice(&20u16.to_le_bytes()); // version made by
         zip.extend_from_slice(&20u16.to_le_bytes()); // version needed
         zip.extend_from_slice(&0u16.to_le_bytes()); // flags
-        zip.extend_from_slice(&METHOD_STORED.to_le_bytes()); // compression
+        zip.extend_from_slice(&method.to_le_bytes()); // compression
         zip.extend_from_slice(&0u16.to_le_bytes()); // mod time
         zip.extend_from_slice(&0u16.to_le_bytes()); // mod date
         zip.extend_from_slice(&crc.to_le_bytes()); // CRC32
-        zip.extend_from_slice(&content_len.to_le_bytes()); // compressed size
+        zip.extend_from_slice(&data_len.to_le_bytes()); // compressed size
         zip.extend_from_slice(&content_len.to_le_bytes()); // uncompressed size
         zip.extend_from_slice(&name_len.to_le_bytes()); // filename length
         zip.extend_from_slice(&0u16.to_le_bytes()); // extra field length
@@ -1046,7 +2008,7 @@ mod tests {
         zip
     }
 
-    fn build_single_file_zip64(filename: &str, content: &[u8]) -> Vec<u8> {
+    pub(super) fn build_single_file_zip64(filename: &str, content: &[u8]) -> Vec<u8> {
         let name_bytes = filename.as_bytes();
         let name_len = name_bytes.len() as u16;
         let content_len = content.len() as u64;
@@ -1210,6 +2172,67 @@ mod tests {
         assert!(matches!(result, Err(ZipError::CentralDirFull)));
     }
 
+    #[test]
+    fn test_directory_snapshot_round_trips_and_skips_scan() {
+        let content = b"application/epub+zip";
+        let zip_data = build_single_file_zip("mimetype", content);
+        let mut zip = StreamingZip::new(zip_data.as_slice()).unwrap();
+        assert!(!zip.directory_imported());
+        let snapshot = zip.export_directory(1_700_000_000).unwrap();
+
+        let mut restored =
+            StreamingZip::import_directory(zip_data.as_slice(), &snapshot, 1_700_000_000, None)
+                .unwrap();
+        assert!(restored.directory_imported());
+        assert_eq!(
+            restored.entries().collect::<Vec<_>>(),
+            zip.entries().collect::<Vec<_>>()
+        );
+        assert_eq!(restored.num_entries(), zip.num_entries());
+        assert_eq!(
+            restored.central_directory_size(),
+            zip.central_directory_size()
+        );
+        let entry = restored.get_entry("mimetype").unwrap().clone();
+        let mut buf = [0u8; 64];
+        let n = restored.read_file(&entry, &mut buf).unwrap();
+        assert_eq!(&buf[..n], content);
+        assert_eq!(restored.export_directory(1_700_000_000).unwrap(), snapshot);
+    }
+
+    #[test]
+    fn test_stale_or_corrupt_directory_snapshot_falls_back_to_scan() {
+        let zip_data = build_single_file_zip("mimetype", b"application/epub+zip");
+        let snapshot = StreamingZip::new(zip_data.as_slice())
+            .unwrap()
+            .export_directory(7)
+            .unwrap();
+
+        let other_mtime =
+            StreamingZip::import_directory(zip_data.as_slice(), &snapshot, 8, None).unwrap();
+        assert!(!other_mtime.directory_imported());
+        assert!(other_mtime.get_entry("mimetype").is_some());
+
+        let mut corrupt = snapshot.clone();
+        corrupt[SNAPSHOT_HEADER_LEN + 2] ^= 0xFF;
+        let zip = StreamingZip::import_directory(zip_data.as_slice(), &corrupt, 7, None).unwrap();
+        assert!(!zip.directory_imported());
+        let zip = StreamingZip::import_directory(zip_data.as_slice(), &[], 7, None).unwrap();
+        assert!(!zip.directory_imported());
+
+        // Same size, different EOCD bytes: caught by the EOCD CRC.
+        let mut edited = zip_data.clone();
+        let eocd_pos = edited.len() - EOCD_MIN_SIZE;
+        edited[eocd_pos + 4] ^= 0x01;
+        let zip = StreamingZip::import_directory(edited.as_slice(), &snapshot, 7, None).unwrap();
+        assert!(!zip.directory_imported());
+
+        let grown = add_zip_comment(zip_data, 16);
+        let zip = StreamingZip::import_directory(grown.as_slice(), &snapshot, 7, None).unwrap();
+        assert!(!zip.directory_imported());
+        assert!(zip.get_entry("mimetype").is_some());
+    }
+
     #[test]
     fn test_validate_mimetype_wrong_content() {
         let zip_data = build_single_file_zip("mimetype", b"text/plain");
@@ -1283,6 +2306,28 @@ mod tests {
         assert_eq!(&buf[..n], content);
     }
 
+    #[test]
+    fn test_streaming_zip_reads_from_byte_slice() {
+        let content = b"application/epub+zip";
+        let zip_data = build_single_file_zip("mimetype", content);
+        let mut zip = StreamingZip::new(zip_data.as_slice()).unwrap();
+
+        let entry = zip.get_entry("mimetype").unwrap().clone();
+        let mut buf = [0u8; 64];
+        let n = zip.read_file(&entry, &mut buf).unwrap();
+        assert_eq!(&buf[..n], content);
+    }
+
+    #[test]
+    fn test_streaming_zip_reads_through_read_seek_adapter() {
+        let content = b"application/epub+zip";
+        let zip_data = build_single_file_zip("mimetype", content);
+        let adapter = crate::storage::ReadSeekAdapter::new(std::io::Cursor::new(zip_data));
+        let mut zip = StreamingZip::new(adapter).unwrap();
+
+        assert!(zip.is_valid_epub());
+    }
+
     #[test]
     fn test_read_file_to_writer_with_scratch_streams_stored_entry() {
         let content = b"application/epub+zip";
@@ -1374,4 +2419,178 @@ mod tests {
         let n = zip.read_file(&entry, &mut buf).unwrap();
         assert_eq!(&buf[..n], content);
     }
+
+    #[test]
+    fn test_compression_ratio_guard_rejects_declared_bomb() {
+        let content = b"1234567890";
+        let mut zip_data = build_single_file_zip("data.txt", content);
+        // Claim the 10 stored bytes inflate to 10 MB.
+        let cd_uncompressed = 30 + "data.txt".len() + content.len() + 24;
+        zip_data[cd_uncompressed..cd_uncompressed + 4]
+            .copy_from_slice(&10_000_000u32.to_le_bytes());
+        let limits = ZipLimits::new(64 * 1024 * 1024, 1024);
+
+        let mut zip = StreamingZip::new_with_limits(
+            std::io::Cursor::new(&zip_data),
+            Some(limits.with_max_compression_ratio(100)),
+        )
+        .unwrap();
+        let entry = zip.get_entry("data.txt").unwrap().clone();
+        let mut out = Vec::with_capacity(0);
+        let err = zip.read_file_to_writer(&entry, &mut out).unwrap_err();
+        assert_eq!(err, ZipError::SuspiciousCompressionRatio);
+
+        // Off by default.
+        let mut zip =
+            StreamingZip::new_with_limits(std::io::Cursor::new(&zip_data), Some(limits)).unwrap();
+        assert_eq!(zip.read_file_to_writer(&entry, &mut out), Ok(10));
+    }
+
+    #[test]
+    fn test_total_decompressed_budget_spans_reads() {
+        let content = b"1234567890";
+        let zip_data = build_single_file_zip("data.txt", content);
+        let limits = ZipLimits::new(1024, 1024).with_max_total_decompressed(15);
+        let mut zip =
+            StreamingZip::new_with_limits(std::io::Cursor::new(zip_data), Some(limits)).unwrap();
+        let entry = zip.get_entry("data.txt").unwrap().clone();
+        let mut buf = [0u8; 32];
+        assert_eq!(zip.read_file(&entry, &mut buf), Ok(10));
+        assert_eq!(zip.decompressed_total(), 10);
+        let mut out = Vec::with_capacity(0);
+        let err = zip.read_file_to_writer(&entry, &mut out).unwrap_err();
+        assert_eq!(err, ZipError::DecompressionBudgetExceeded);
+        assert!(out.is_empty());
+    }
+
+    #[test]
+    fn test_percent_decode_path() {
+        assert_eq!(
+            percent_decode_path("My%20Chapter.xhtml"),
+            "My Chapter.xhtml"
+        );
+        assert_eq!(percent_decode_path("caf%C3%A9.xhtml"), "caf\u{e9}.xhtml");
+        assert_eq!(percent_decode_path("caf%c3%a9.xhtml"), "caf\u{e9}.xhtml");
+        assert_eq!(percent_decode_path("100%.xhtml"), "100%.xhtml");
+        assert_eq!(percent_decode_path("a%zz%2"), "a%zz%2");
+        // Invalid UTF-8 after decoding keeps the raw href.
+        assert_eq!(percent_decode_path("bad%FF.xhtml"), "bad%FF.xhtml");
+    }
+
+    #[test]
+    fn test_tolerant_entry_name_matching() {
+        assert!(entry_names_match_tolerant(
+            "OEBPS/Cafe\u{301}.xhtml",
+            "oebps/caf\u{e9}.xhtml"
+        ));
+        assert!(entry_names_match_tolerant(
+            "\u{c9}T\u{c9}.css",
+            "e\u{301}te\u{301}.css"
+        ));
+        assert!(entry_names_match_tolerant(
+            "My Chapter.xhtml",
+            "My+Chapter.xhtml"
+        ));
+        assert!(!entry_names_match_tolerant("cafe.xhtml", "caf\u{e9}.xhtml"));
+        assert!(LATIN_DECOMPOSITIONS.windows(2).all(|w| w[0].0 < w[1].0));
+    }
+
+    #[test]
+    fn test_get_entry_unicode_fallback_is_opt_in() {
+        let zip_data = build_single_file_zip("Cafe\u{301}.xhtml", b"x");
+        let cursor = std::io::Cursor::new(zip_data);
+        let mut zip = StreamingZip::new(cursor).unwrap();
+        assert!(zip.get_entry("caf\u{e9}.xhtml").is_none());
+        zip.set_tolerant_names(true);
+        assert!(zip.get_entry("/CAF\u{c9}.xhtml").is_some());
+    }
+
+    /// Raw DEFLATE stream holding `content` in stored blocks.
+    fn stored_deflate(content: &[u8]) -> Vec<u8> {
+        let mut out = Vec::with_capacity(content.len() + content.len() / 0xFFFF * 5 + 5);
+        let mut chunks = content.chunks(0xFFFF).peekable();
+        while let Some(chunk) = chunks.next() {
+            out.push(u8::from(chunks.peek().is_none())); // BFINAL, BTYPE=00
+            let len = chunk.len() as u16;
+            out.extend_from_slice(&len.to_le_bytes());
+            out.extend_from_slice(&(!len).to_le_bytes());
+            out.extend_from_slice(chunk);
+        }
+        out
+    }
+
+    #[test]
+    fn test_range_reads_resume_from_inflate_checkpoints() {
+        let content: Vec<u8> = (0..600_000u32).map(|i| (i % 251) as u8).collect();
+        let data = stored_deflate(&content);
+        let zip_data = build_single_entry_zip("big.xhtml", &content, METHOD_DEFLATED, &data);
+        let mut zip = StreamingZip::new(std::io::Cursor::new(zip_data)).unwrap();
+        let entry = zip.get_entry("big.xhtml").unwrap().clone();
+
+        let mut out = Vec::with_capacity(0);
+        let n = zip
+            .read_file_range_to_writer(&entry, 550_000, 100, &mut out)
+            .unwrap();
+        assert_eq!(n, 100);
+        assert_eq!(out, &content[550_000..550_100]);
+        assert_eq!(zip.sync_points.len(), 2);
+
+        let before = zip.decompressed_total();
+        out.clear();
+        zip.read_file_range_to_writer(&entry, 300_000, 1000, &mut out)
+            .unwrap();
+        assert_eq!(out, &content[300_000..301_000]);
+        assert!(zip.decompressed_total() - before < 64 * 1024);
+        assert_eq!(zip.sync_points.len(), 2);
+
+        out.clear();
+        let n = zip
+            .read_file_range_to_writer(&entry, 599_990, 100, &mut out)
+            .unwrap();
+        assert_eq!((n, out.as_slice()), (10, &content[599_990..]));
+    }
+
+    #[test]
+    fn test_range_reads_respect_sync_point_limit() {
+        let content: Vec<u8> = (0..600_000u32).map(|i| (i % 251) as u8).collect();
+        let data = stored_deflate(&content);
+        let zip_data = build_single_entry_zip("big.xhtml", &content, METHOD_DEFLATED, &data);
+        let limits = ZipLimits::new(usize::MAX, 1024).with_max_sync_points(1);
+        let mut zip =
+            StreamingZip::new_with_limits(std::io::Cursor::new(zip_data), Some(limits)).unwrap();
+        let entry = zip.get_entry("big.xhtml").unwrap().clone();
+
+        let mut out = Vec::with_capacity(0);
+        zip.read_file_range_to_writer(&entry, 550_000, 100, &mut out)
+            .unwrap();
+        assert_eq!(out, &content[550_000..550_100]);
+        assert_eq!(zip.sync_points.len(), 1);
+
+        zip.clear_sync_points();
+        zip.limits = Some(limits.with_max_sync_points(0));
+        out.clear();
+        zip.read_file_range_to_writer(&entry, 550_000, 100, &mut out)
+            .unwrap();
+        assert_eq!(out, &content[550_000..550_100]);
+        assert!(zip.sync_points.is_empty());
+    }
+
+    #[test]
+    fn test_range_read_of_stored_entry() {
+        let zip_data = build_single_file_zip("a.txt", b"0123456789");
+        let mut zip = StreamingZip::new(std::io::Cursor::new(zip_data)).unwrap();
+        let entry = zip.get_entry("a.txt").unwrap().clone();
+        let mut out = Vec::with_capacity(0);
+        assert_eq!(
+            zip.read_file_range_to_writer(&entry, 3, 4, &mut out)
+                .unwrap(),
+            4
+        );
+        assert_eq!(out, b"3456");
+        assert_eq!(
+            zip.read_file_range_to_writer(&entry, 20, 4, &mut out)
+                .unwrap(),
+            0
+        );
+    }
 }
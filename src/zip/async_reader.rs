@@ -0,0 +1,307 @@
+//! Async ZIP reader over `tokio::io::AsyncRead + AsyncSeek`.
+//!
+//! Mirrors [`StreamingZip`](super::StreamingZip) without blocking the
+//! executor: all archive reads are awaited on the underlying source, while
+//! record parsing and decompression share the blocking reader's code.
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+use core::future::poll_fn;
+use core::pin::Pin;
+use heapless::Vec as HeaplessVec;
+use std::io::{SeekFrom, Write};
+use tokio::io::{AsyncRead, AsyncSeek, ReadBuf};
+
+use super::{
+    check_entry_limits, check_mimetype_content, eocd_scan_buffer, find_entry, local_data_offset,
+    mimetype_entry, mimetype_size, parse_central_directory, parse_zip64_eocd, parse_zip64_locator,
+    CdEntry, EntryDecoder, EocdRecord, OutputCap, ZipError, ZipLimits, MAX_CD_BUFFER,
+    MAX_CD_ENTRIES, MAX_EOCD_SCAN,
+};
+
+/// Async streaming ZIP reader.
+///
+/// Opening reads the archive tail and the central directory; entries are then
+/// streamed on demand in bounded chunks.
+///
+/// # Allocation behavior
+/// - Allocates the EOCD scan window and one buffer holding the central
+///   directory while it is parsed, capped by
+///   [`ZipLimits::max_central_directory_size`]
+/// - Entry reads use caller-provided or fixed 8KB scratch buffers
+pub struct AsyncStreamingZip<R> {
+    reader: R,
+    entries: HeaplessVec<CdEntry, MAX_CD_ENTRIES>,
+    num_entries: usize,
+    limits: Option<ZipLimits>,
+    tolerant_names: bool,
+    /// Bytes decompressed so far, charged against
+    /// [`ZipLimits::max_total_decompressed`].
+    decompressed_total: u64,
+}
+
+impl<R: AsyncRead + AsyncSeek + Unpin> AsyncStreamingZip<R> {
+    /// Open a ZIP source and parse the central directory.
+    pub async fn new(reader: R) -> Result<Self, ZipError> {
+        Self::new_with_limits(reader, None).await
+    }
+
+    /// Open a ZIP source with explicit runtime limits.
+    pub async fn new_with_limits(
+        mut reader: R,
+        limits: Option<ZipLimits>,
+    ) -> Result<Self, ZipError> {
+        let max_eocd_scan = limits
+            .map(|l| l.max_eocd_scan.min(MAX_EOCD_SCAN))
+            .unwrap_or(MAX_EOCD_SCAN);
+        let file_size = seek(&mut reader, SeekFrom::End(0)).await?;
+        let mut buffer = eocd_scan_buffer(file_size, max_eocd_scan)?;
+        let scan_base = file_size - buffer.len() as u64;
+        read_exact_at(&mut reader, scan_base, &mut buffer).await?;
+        let record = EocdRecord::locate(&buffer, scan_base, file_size)?;
+
+        let mut locator = None;
+        if let Some(offset) = record.zip64_locator_offset() {
+            let mut bytes = [0u8; 20];
+            read_exact_at(&mut reader, offset, &mut bytes).await?;
+            locator = parse_zip64_locator(&bytes);
+        }
+        let zip64 = match record.zip64_eocd_offset(locator)? {
+            Some(offset) => {
+                let mut fixed = [0u8; 56];
+                read_exact_at(&mut reader, offset, &mut fixed).await?;
+                Some(parse_zip64_eocd(&fixed)?)
+            }
+            None => None,
+        };
+        let eocd = record.resolve(zip64, file_size)?;
+        let strict = limits.is_some_and(|l| l.strict);
+        if strict && eocd.num_entries > MAX_CD_ENTRIES as u64 {
+            return Err(ZipError::CentralDirFull);
+        }
+
+        // Buffer the directory once, then parse it with the blocking parser.
+        let max_cd_size = limits
+            .map(|l| l.max_central_directory_size)
+            .unwrap_or(MAX_CD_BUFFER);
+        let cd_size = usize::try_from(eocd.cd_size).map_err(|_| ZipError::InvalidFormat)?;
+        if cd_size > max_cd_size {
+            return Err(ZipError::CentralDirFull);
+        }
+        let mut directory: Vec<u8> = alloc::vec![0u8; cd_size];
+        read_exact_at(&mut reader, eocd.cd_offset, &mut directory).await?;
+        let entries = parse_central_directory(&mut directory.as_slice(), 0, &eocd, strict)?;
+
+        Ok(Self {
+            reader,
+            entries,
+            num_entries: core::cmp::min(eocd.num_entries, usize::MAX as u64) as usize,
+            limits,
+            tolerant_names: false,
+            decompressed_total: 0,
+        })
+    }
+
+    /// Get entry by filename (case-insensitive)
+    pub fn get_entry(&self, name: &str) -> Option<&CdEntry> {
+        find_entry(&self.entries, name, self.tolerant_names)
+    }
+
+    /// Enable or disable tolerant entry-name matching.
+    ///
+    /// See [`StreamingZip::set_tolerant_names`](super::StreamingZip::set_tolerant_names).
+    pub fn set_tolerant_names(&mut self, tolerant: bool) {
+        self.tolerant_names = tolerant;
+    }
+
+    /// Whether tolerant entry-name matching is enabled.
+    pub fn tolerant_names(&self) -> bool {
+        self.tolerant_names
+    }
+
+    /// Bytes decompressed by successful reads so far.
+    ///
+    /// See [`StreamingZip::decompressed_total`](super::StreamingZip::decompressed_total).
+    pub fn decompressed_total(&self) -> u64 {
+        self.decompressed_total
+    }
+
+    /// Stream a file's decompressed bytes into a writer.
+    pub async fn read_file_to_writer<W: Write>(
+        &mut self,
+        entry: &CdEntry,
+        writer: &mut W,
+    ) -> Result<usize, ZipError> {
+        let mut input_buf = alloc::vec![0u8; 8 * 1024];
+        let mut output_buf = alloc::vec![0u8; 8 * 1024];
+        self.read_file_to_writer_with_scratch(entry, writer, &mut input_buf, &mut output_buf)
+            .await
+    }
+
+    /// Stream a file's decompressed bytes into a writer using caller-provided scratch buffers.
+    ///
+    /// `input_buf` and `output_buf` must both be non-empty.
+    pub async fn read_file_to_writer_with_scratch<W: Write>(
+        &mut self,
+        entry: &CdEntry,
+        writer: &mut W,
+        input_buf: &mut [u8],
+        output_buf: &mut [u8],
+    ) -> Result<usize, ZipError> {
+        if input_buf.is_empty() || output_buf.is_empty() {
+            return Err(ZipError::BufferTooSmall);
+        }
+        check_entry_limits(entry, self.limits)?;
+        let cap = OutputCap::for_entry(entry, self.limits, self.decompressed_total)?;
+
+        let mut header = [0u8; 30];
+        read_exact_at(&mut self.reader, entry.local_header_offset, &mut header).await?;
+        let mut pos = local_data_offset(entry.local_header_offset, &header)?;
+        let mut decoder = EntryDecoder::new(entry, cap)?;
+        loop {
+            let take = decoder.next_read_len(input_buf.len());
+            if take == 0 {
+                break;
+            }
+            read_exact_at(&mut self.reader, pos, &mut input_buf[..take]).await?;
+            pos += take as u64;
+            decoder.decode(&input_buf[..take], output_buf, writer)?;
+        }
+        let written = decoder.finish(output_buf, writer)?;
+        self.decompressed_total += written as u64;
+        Ok(written)
+    }
+
+    /// Validate that the archive contains a valid EPUB mimetype file
+    pub async fn validate_mimetype(&mut self) -> Result<(), ZipError> {
+        let entry = mimetype_entry(&self.entries, self.tolerant_names)?.clone();
+        let size = mimetype_size(&entry, self.limits)?;
+        let mut buf = Vec::with_capacity(size);
+        self.read_file_to_writer(&entry, &mut buf).await?;
+        check_mimetype_content(&buf)
+    }
+
+    /// Get number of entries in central directory
+    pub fn num_entries(&self) -> usize {
+        self.num_entries.min(self.entries.len())
+    }
+
+    /// Iterate over all entries
+    pub fn entries(&self) -> impl Iterator<Item = &CdEntry> {
+        self.entries.iter()
+    }
+
+    /// Get the active limits used by this ZIP reader.
+    pub fn limits(&self) -> Option<ZipLimits> {
+        self.limits
+    }
+}
+
+async fn seek<R: AsyncSeek + Unpin>(reader: &mut R, pos: SeekFrom) -> Result<u64, ZipError> {
+    Pin::new(&mut *reader)
+        .start_seek(pos)
+        .map_err(|_| ZipError::IoError)?;
+    poll_fn(|cx| Pin::new(&mut *reader).poll_complete(cx))
+        .await
+        .map_err(|_| ZipError::IoError)
+}
+
+async fn read_exact_at<R: AsyncRead + AsyncSeek + Unpin>(
+    reader: &mut R,
+    offset: u64,
+    buf: &mut [u8],
+) -> Result<(), ZipError> {
+    seek(reader, SeekFrom::Start(offset)).await?;
+    let mut read_buf = ReadBuf::new(buf);
+    while read_buf.remaining() > 0 {
+        let before = read_buf.filled().len();
+        poll_fn(|cx| Pin::new(&mut *reader).poll_read(cx, &mut read_buf))
+            .await
+            .map_err(|_| ZipError::IoError)?;
+        if read_buf.filled().len() == before {
+            return Err(ZipError::IoError);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::tests::{build_single_file_zip, build_single_file_zip64};
+    use super::*;
+    use std::io::Cursor;
+
+    fn block_on<F: core::future::Future>(future: F) -> F::Output {
+        tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap()
+            .block_on(future)
+    }
+
+    #[test]
+    fn async_zip_reads_stored_entry() {
+        let content = b"application/epub+zip";
+        let data = build_single_file_zip("mimetype", content);
+        block_on(async {
+            let mut zip = AsyncStreamingZip::new(Cursor::new(data)).await.unwrap();
+            assert_eq!(zip.num_entries(), 1);
+            zip.validate_mimetype().await.unwrap();
+
+            let entry = zip.get_entry("mimetype").unwrap().clone();
+            let mut out = Vec::with_capacity(0);
+            let mut input = [0u8; 3];
+            let mut output = [0u8; 3];
+            let n = zip
+                .read_file_to_writer_with_scratch(&entry, &mut out, &mut input, &mut output)
+                .await
+                .unwrap();
+            assert_eq!(n, content.len());
+            assert_eq!(out, content);
+        });
+    }
+
+    #[test]
+    fn async_zip_reads_zip64_archive() {
+        let content = b"hello zip64";
+        let data = build_single_file_zip64("a.txt", content);
+        block_on(async {
+            let mut zip = AsyncStreamingZip::new(Cursor::new(data)).await.unwrap();
+            let entry = zip.get_entry("a.txt").unwrap().clone();
+            let mut out = Vec::with_capacity(0);
+            zip.read_file_to_writer(&entry, &mut out).await.unwrap();
+            assert_eq!(out, content);
+        });
+    }
+
+    #[test]
+    fn async_zip_rejects_central_directory_over_limit() {
+        let data = build_single_file_zip("mimetype", b"application/epub+zip");
+        let limits = ZipLimits::new(1024, 1024).with_max_central_directory_size(16);
+        block_on(async {
+            let result = AsyncStreamingZip::new_with_limits(Cursor::new(data), Some(limits)).await;
+            assert!(matches!(result, Err(ZipError::CentralDirFull)));
+        });
+    }
+
+    #[test]
+    fn async_zip_matches_blocking_reader_on_fixture() {
+        let bytes = std::fs::read(
+            "tests/fixtures/Fundamental-Accessibility-Tests-Basic-Functionality-v2.0.0.epub",
+        )
+        .unwrap();
+        let mut blocking = super::super::StreamingZip::new(Cursor::new(bytes.clone())).unwrap();
+        block_on(async {
+            let mut zip = AsyncStreamingZip::new(Cursor::new(bytes)).await.unwrap();
+            assert_eq!(zip.num_entries(), blocking.num_entries());
+            let entries: Vec<CdEntry> = zip.entries().cloned().collect();
+            for entry in entries {
+                let mut expected = Vec::with_capacity(0);
+                blocking.read_file_to_writer(&entry, &mut expected).unwrap();
+                let mut actual = Vec::with_capacity(0);
+                zip.read_file_to_writer(&entry, &mut actual).await.unwrap();
+                assert_eq!(actual, expected, "{}", entry.filename);
+            }
+        });
+    }
+}
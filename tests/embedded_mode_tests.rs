@@ -25,6 +25,7 @@ fn embedded_options() -> EpubBookOptions {
         zip_limits: Some(ZipLimits::new(256 * 1024, 128)), // 256KB max file, 128B mimetype
         validation_mode: ValidationMode::Lenient,
         max_nav_bytes: Some(64 * 1024), // 64KB nav limit
+        ..EpubBookOptions::default()
     }
 }
 
@@ -36,8 +37,11 @@ fn embedded_render_prep() -> RenderPrepOptions {
                 max_selectors: 128,
                 max_css_bytes: 16 * 1024,
                 max_nesting: 8,
+                ..StyleLimits::default()
             },
             hints: mu_epub::render_prep::LayoutHints::default(),
+            type_ramp: mu_epub::render_prep::TypeRamp::default(),
+            include_hidden: false,
         },
         fonts: FontLimits {
             max_faces: 4,
@@ -52,6 +56,7 @@ fn embedded_render_prep() -> RenderPrepOptions {
             max_inline_style_bytes: 1024,
             max_pages_in_memory: 4,
         },
+        ..RenderPrepOptions::default()
     }
 }
 
@@ -212,6 +217,7 @@ fn test_embedded_mode_stylesheet_limits() {
         max_selectors: 64,
         max_css_bytes: 8 * 1024,
         max_nesting: 4,
+        ..StyleLimits::default()
     };
 
     let result = book.chapter_stylesheets_with_options(0, limits);
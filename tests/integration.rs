@@ -6,12 +6,12 @@
 
 use std::fs::File;
 
-use mu_epub::book::{EpubBook, ValidationMode};
+use mu_epub::book::{EpubBook, Locator, ValidationMode};
 #[cfg(feature = "layout")]
 use mu_epub::layout::LayoutEngine;
 use mu_epub::metadata::parse_opf;
 use mu_epub::spine::parse_spine;
-use mu_epub::tokenizer::{tokenize_html, Token};
+use mu_epub::tokenizer::{tokenize_html, tokenize_html_limited, Token, TokenizeLimits, Tokenizer};
 use mu_epub::zip::StreamingZip;
 
 const SAMPLE_EPUB_PATH: &str =
@@ -288,6 +288,75 @@ fn test_tokenize_complex_formatting() {
     assert!(found_bold_end);
 }
 
+#[test]
+fn test_incremental_tokenizer_matches_one_shot_on_sample_chapters() {
+    let file = File::open(SAMPLE_EPUB_PATH).expect("Failed to open sample EPUB");
+    let mut book = EpubBook::from_reader(file).expect("Failed to parse EPUB");
+
+    for index in 0..book.chapter_count() {
+        let html = book.chapter_html(index).expect("Failed to decode chapter");
+        let expected =
+            tokenize_html_limited(&html, TokenizeLimits::default()).expect("one-shot tokenize");
+
+        let mut tokenizer = Tokenizer::new(TokenizeLimits::default());
+        let mut streamed = Vec::with_capacity(expected.len());
+        for chunk in html.as_bytes().chunks(13) {
+            streamed.extend(tokenizer.feed(chunk).expect("incremental feed"));
+        }
+        streamed.extend(tokenizer.finish().expect("incremental finish"));
+
+        assert_eq!(streamed, expected, "chapter {}", index);
+    }
+}
+
+#[test]
+fn test_fragment_offsets_match_tokenized_text_on_sample_chapters() {
+    let file = File::open(SAMPLE_EPUB_PATH).expect("Failed to open sample EPUB");
+    let mut book = EpubBook::from_reader(file).expect("Failed to parse EPUB");
+    let mut checked = 0usize;
+
+    for index in 0..book.chapter_count() {
+        let tokens = book.tokenize_spine_item(index).expect("tokenize chapter");
+        let href = book.chapter(index).expect("chapter ref").href;
+        let mut chars = 0usize;
+        let mut seen = std::collections::HashSet::new();
+        for token in tokens {
+            match token {
+                Token::Text(text) => chars += text.chars().count(),
+                Token::Anchor(id) if seen.insert(id.clone()) => {
+                    let mut session = book.reading_session();
+                    let resolved = session
+                        .resolve_locator_in_book(&mut book, Locator::Href(format!("{href}#{id}")))
+                        .expect("fragment locator should resolve");
+                    assert_eq!(resolved.fragment.as_deref(), Some(id.as_str()));
+                    assert_eq!(resolved.position.fallback_offset, chars, "#{id}");
+                    checked += 1;
+                }
+                _ => {}
+            }
+        }
+    }
+    assert!(checked > 0, "sample should contain element ids");
+}
+
+#[test]
+fn test_fragment_offset_missing_id_falls_back_to_chapter_start() {
+    let file = File::open(SAMPLE_EPUB_PATH).expect("Failed to open sample EPUB");
+    let mut book = EpubBook::from_reader(file).expect("Failed to parse EPUB");
+    let mut session = book.reading_session();
+    assert_eq!(
+        session
+            .resolve_fragment_offset(&mut book, "#no-such-anchor")
+            .expect("scan should succeed"),
+        None
+    );
+    let resolved = session
+        .resolve_locator_in_book(&mut book, Locator::Fragment("no-such-anchor".to_string()))
+        .expect("fragment locator should resolve");
+    assert_eq!(resolved.position.fallback_offset, 0);
+    assert_eq!(resolved.position.anchor.as_deref(), Some("no-such-anchor"));
+}
+
 // -- Layout tests -------------------------------------------------------------
 
 #[cfg(feature = "layout")]